@@ -8,7 +8,7 @@ use std::fmt::Debug;
 
 use openmls_traits::{
     signatures::{Signer, SignerError},
-    storage::{self, StorageProvider, CURRENT_VERSION},
+    storage::{self, StorageReader, StorageWriter, CURRENT_VERSION},
     types::{CryptoError, SignatureScheme},
 };
 
@@ -35,7 +35,7 @@ pub struct SignatureKeyPair {
 impl Debug for SignatureKeyPair {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SignatureKeyPair")
-            .field("private", &"***".to_string())
+            .field("private", &format_args!("{} bytes", self.private.len()))
             .field("public", &self.public)
             .field("signature_scheme", &self.signature_scheme)
             .finish()
@@ -118,14 +118,14 @@ impl SignatureKeyPair {
     /// Store this signature key pair in the key store.
     pub fn store<T>(&self, store: &T) -> Result<(), T::Error>
     where
-        T: StorageProvider<CURRENT_VERSION>,
+        T: StorageWriter<CURRENT_VERSION>,
     {
         store.write_signature_key_pair(&self.id(), self)
     }
 
     /// Read a signature key pair from the key store.
     pub fn read(
-        store: &impl StorageProvider<CURRENT_VERSION>,
+        store: &impl StorageReader<CURRENT_VERSION>,
         public_key: &[u8],
         signature_scheme: SignatureScheme,
     ) -> Option<Self> {
@@ -138,7 +138,7 @@ impl SignatureKeyPair {
     }
 
     /// Delete a signature key pair from the key store.
-    pub fn delete<T: StorageProvider<CURRENT_VERSION>>(
+    pub fn delete<T: StorageWriter<CURRENT_VERSION>>(
         store: &T,
         public_key: &[u8],
         signature_scheme: SignatureScheme,
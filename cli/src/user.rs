@@ -552,7 +552,8 @@ impl User {
         // tree, we need to include the ratchet_tree_extension.
         let group_config = MlsGroupCreateConfig::builder()
             .use_ratchet_tree_extension(true)
-            .build();
+            .build()
+            .expect("Invalid group configuration");
 
         let mls_group = MlsGroup::new_with_group_id(
             &self.provider,
@@ -12,6 +12,11 @@
 //! * Clients have to send a list of clients (group members) along with each
 //!   message for the DS to know where to send the message.
 //! * The DS stores and delivers key packages.
+//! * The DS tracks the epoch of each group it has seen handshake messages for
+//!   and only fans out the first commit for an epoch. Messages are delivered
+//!   to each client in the order the DS accepted them.
+//! * Messages can be fetched via HTTP (`/recv/{id}`) or pushed to the client
+//!   over a WebSocket (`/ws/{id}`).
 //!
 //! This is a very basic delivery service that allows to register clients and
 //! send messages to MLS groups.
@@ -33,10 +38,14 @@
 use actix_web::{get, post, web, web::Payload, App, HttpRequest, HttpServer, Responder};
 use base64::Engine;
 use clap::Command;
-use futures_util::StreamExt;
+use futures_util::{
+    future::{select, Either},
+    StreamExt,
+};
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tls_codec::{Deserialize, Serialize, TlsSliceU16, TlsVecU32};
+use tokio::sync::Notify;
 
 use ds_lib::{
     messages::{
@@ -59,6 +68,60 @@ pub struct DsData {
 
     // (group_id, epoch)
     groups: Mutex<HashMap<Vec<u8>, u64>>,
+
+    // (ClientIdentity, notifier for new messages)
+    notifiers: Mutex<HashMap<Vec<u8>, Arc<Notify>>>,
+}
+
+impl DsData {
+    /// Returns the notifier that is woken up when a message is queued for
+    /// the client `id`.
+    fn notifier(&self, id: &[u8]) -> Option<Arc<Notify>> {
+        let mut notifiers = self.notifiers.lock().ok()?;
+        Some(notifiers.entry(id.to_vec()).or_default().clone())
+    }
+
+    /// Wakes up the WebSocket connection of the client `id`, if any.
+    fn notify(&self, id: &[u8]) {
+        if let Some(notifier) = self.notifier(id) {
+            notifier.notify_one();
+        }
+    }
+
+    /// Returns the messages queued for the client `id` without removing them,
+    /// welcome messages first. Returns `None` if the client is unknown.
+    fn queued_messages(&self, id: &[u8]) -> Option<Vec<MlsMessageIn>> {
+        let clients = self.clients.lock().ok()?;
+        let client = clients.get(id)?;
+        Some(
+            client
+                .welcome_queue
+                .iter()
+                .chain(client.msgs.iter())
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Removes the `delivered` message from the queues of the client `id`.
+    /// Messages are delivered in order, so only the first message of a queue
+    /// is removed, and only if it is the `delivered` one. If the message was
+    /// removed in the meantime, e.g. because the client fetched it via
+    /// `/recv/{id}`, nothing is removed.
+    fn remove_delivered(&self, id: &[u8], delivered: &MlsMessageIn) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        let Some(client) = clients.get_mut(id) else {
+            return;
+        };
+        for queue in [&mut client.welcome_queue, &mut client.msgs] {
+            if queue.first() == Some(delivered) {
+                queue.remove(0);
+                return;
+            }
+        }
+    }
 }
 
 macro_rules! unwrap_item {
@@ -272,7 +335,8 @@ async fn consume_key_package(path: web::Path<String>, data: web::Data<DsData>) -
 
 /// Send a welcome message to a client.
 /// This takes a serialised `Welcome` message and stores the message for all
-/// clients in the welcome message.
+/// clients in the welcome message, i.e. all clients that reserved one of the
+/// key packages the welcome message is encrypted to.
 #[post("/send/welcome")]
 async fn send_welcome(mut body: Payload, data: web::Data<DsData>) -> impl Responder {
     let mut bytes = web::BytesMut::new();
@@ -284,30 +348,37 @@ async fn send_welcome(mut body: Payload, data: web::Data<DsData>) -> impl Respon
     log::debug!("Storing welcome message: {:?}", welcome_msg);
 
     let mut clients = unwrap_data!(data.clients.lock());
+    let mut delivered = false;
     for secret in welcome.secrets().iter() {
         let key_package_hash = &secret.new_member();
-        for (_client_name, client) in clients.iter_mut() {
-            match client
+        let client = clients.values_mut().find(|client| {
+            client
                 .reserved_key_pkg_hash
-                .take(key_package_hash.as_slice())
-            {
-                Some(_kp_hash) => {
-                    client.welcome_queue.push(welcome_msg);
-                    return actix_web::HttpResponse::Ok().finish();
-                }
-                None => continue,
-            };
+                .remove(key_package_hash.as_slice())
+        });
+        if let Some(client) = client {
+            client.welcome_queue.push(welcome_msg.clone());
+            data.notify(&client.id);
+            delivered = true;
         }
     }
-    actix_web::HttpResponse::NoContent().finish()
+
+    if delivered {
+        actix_web::HttpResponse::Ok().finish()
+    } else {
+        actix_web::HttpResponse::NoContent().finish()
+    }
 }
 
 /// Send an MLS message to a set of clients (group).
 /// This takes a serialised `GroupMessage` and stores the message for each
 /// client in the recipient list.
-/// If a handshake message is sent with an epoch smaller or equal to another
-/// handshake message this DS has seen, a 409 is returned and the message is not
-/// processed.
+/// If a handshake message is sent with an epoch smaller than the current epoch
+/// of the group, a 409 is returned and the message is not processed. Since a
+/// commit moves the group to the next epoch, only the first commit for each
+/// epoch is accepted.
+/// If any of the recipients is unknown, a 404 is returned and the message is
+/// not stored for any of them.
 #[post("/send/message")]
 async fn msg_send(mut body: Payload, data: web::Data<DsData>) -> impl Responder {
     let mut bytes = web::BytesMut::new();
@@ -320,40 +391,41 @@ async fn msg_send(mut body: Payload, data: web::Data<DsData>) -> impl Responder
     let mut clients = unwrap_data!(data.clients.lock());
     let mut groups = unwrap_data!(data.groups.lock());
 
-    let protocol_msg: ProtocolMessage = group_msg.msg.clone().try_into().unwrap();
+    let protocol_msg: ProtocolMessage = match group_msg.msg.clone().try_into() {
+        Ok(protocol_msg) => protocol_msg,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
 
-    // Reject any handshake message that has an earlier epoch than the one we know
-    // about.
-    // XXX: There's no test for this block in here right now because it's pretty
-    //      painful to test in the current setting. This should get tested through
-    //      the client and maybe later with the MlsGroup API.
+    if group_msg
+        .recipients
+        .iter()
+        .any(|recipient| !clients.contains_key(recipient.as_slice()))
+    {
+        return actix_web::HttpResponse::NotFound().finish();
+    }
+
+    // Reject any handshake message that has an earlier epoch than the one we
+    // know about. The DS may not see all commits of a group, e.g. if a commit
+    // only adds new members, so later epochs are accepted.
     if protocol_msg.is_handshake_message() {
         let epoch = protocol_msg.epoch().as_u64();
-        let group_id = protocol_msg.group_id().as_slice();
-        if let Some(&group_epoch) = groups.get(group_id) {
-            if group_epoch > epoch {
-                return actix_web::HttpResponse::Conflict().finish();
-            }
-            // Update server state to the latest epoch.
-            let old_value = groups.insert(group_id.to_vec(), epoch);
-            if old_value.is_none() {
-                return actix_web::HttpResponse::InternalServerError().finish();
-            }
-        } else {
-            // We haven't seen this group_id yet. Store it.
-            let old_value = groups.insert(group_id.to_vec(), epoch);
-            if old_value.is_some() {
-                return actix_web::HttpResponse::InternalServerError().finish();
-            }
+        let group_epoch = groups
+            .entry(protocol_msg.group_id().as_slice().to_vec())
+            .or_insert(epoch);
+        if epoch < *group_epoch {
+            return actix_web::HttpResponse::Conflict().finish();
         }
+        *group_epoch = match protocol_msg.content_type() {
+            ContentType::Commit => epoch + 1,
+            _ => epoch,
+        };
     }
 
     for recipient in group_msg.recipients.iter() {
-        let client = match clients.get_mut(recipient.as_slice()) {
-            Some(client) => client,
-            None => return actix_web::HttpResponse::NotFound().finish(),
-        };
-        client.msgs.push(group_msg.msg.clone());
+        if let Some(client) = clients.get_mut(recipient.as_slice()) {
+            client.msgs.push(group_msg.msg.clone());
+            data.notify(recipient.as_slice());
+        }
     }
     actix_web::HttpResponse::Ok().finish()
 }
@@ -417,6 +489,106 @@ async fn msg_recv(
     }
 }
 
+/// Push the messages stored for the client `{id}` over a WebSocket.
+/// The first message the client sends has to be a serialised
+/// `RecvMessageRequest` to authenticate the client. Afterwards, the DS sends
+/// each queued message as a binary frame containing a serialised
+/// `MlsMessageIn`, welcome messages first. A message is deleted on the DS once
+/// it was sent out, such that messages that couldn't be sent because the
+/// connection was closed are kept for the next connection.
+#[get("/ws/{id}")]
+async fn ws_recv(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Payload,
+    data: web::Data<DsData>,
+) -> Result<actix_web::HttpResponse, actix_web::Error> {
+    let id = match base64::engine::general_purpose::URL_SAFE.decode(path.into_inner()) {
+        Ok(v) => v,
+        Err(_) => return Ok(actix_web::HttpResponse::BadRequest().finish()),
+    };
+    match data.clients.lock() {
+        Ok(clients) if clients.contains_key(&id) => (),
+        Ok(_) => return Ok(actix_web::HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(actix_web::HttpResponse::InternalServerError().finish()),
+    }
+
+    let (response, session, msg_stream) = actix_ws::handle(&req, body)?;
+    actix_web::rt::spawn(push_messages(id, session, msg_stream, data));
+
+    Ok(response)
+}
+
+/// Drives the WebSocket connection of the client `id` until it is closed.
+async fn push_messages(
+    id: Vec<u8>,
+    mut session: actix_ws::Session,
+    mut msg_stream: actix_ws::MessageStream,
+    data: web::Data<DsData>,
+) {
+    let authenticated = match msg_stream.next().await {
+        Some(Ok(actix_ws::Message::Binary(bytes))) => {
+            match RecvMessageRequest::tls_deserialize(&mut bytes.as_ref()) {
+                Ok(req) => match data.clients.lock() {
+                    Ok(clients) => clients
+                        .get(&id)
+                        .is_some_and(|client| client.auth_token == req.auth_token),
+                    Err(_) => false,
+                },
+                Err(_) => false,
+            }
+        }
+        _ => false,
+    };
+    if !authenticated {
+        log::debug!("WebSocket authentication failed for client {:?}", id);
+        let _ = session.close(None).await;
+        return;
+    }
+
+    let Some(notifier) = data.notifier(&id) else {
+        let _ = session.close(None).await;
+        return;
+    };
+
+    log::debug!("Pushing messages to client {:?}", id);
+    loop {
+        // Send the queued messages and only delete them once they were sent.
+        let Some(messages) = data.queued_messages(&id) else {
+            break;
+        };
+        for message in messages {
+            let Ok(bytes) = message.tls_serialize_detached() else {
+                continue;
+            };
+            if session.binary(bytes).await.is_err() {
+                return;
+            }
+            data.remove_delivered(&id, &message);
+        }
+
+        // Wait until new messages are queued. A notification that is sent
+        // while the messages are sent out is kept, so no message is missed.
+        loop {
+            let notified = std::pin::pin!(notifier.notified());
+            match select(notified, msg_stream.next()).await {
+                Either::Left(_) => break,
+                Either::Right((Some(Ok(actix_ws::Message::Ping(bytes))), _)) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Either::Right((Some(Ok(actix_ws::Message::Close(_))) | Some(Err(_)) | None, _)) => {
+                    let _ = session.close(None).await;
+                    return;
+                }
+                Either::Right(_) => (),
+            }
+        }
+    }
+    let _ = session.close(None).await;
+}
+
 // === Main function driving the DS ===
 
 #[actix_web::main]
@@ -459,6 +631,7 @@ async fn main() -> std::io::Result<()> {
             .service(send_welcome)
             .service(msg_recv)
             .service(msg_send)
+            .service(ws_recv)
             .service(reset)
     })
     .bind(addr)?
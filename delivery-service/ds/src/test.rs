@@ -439,3 +439,208 @@ async fn test_group() {
         panic!("Expected application message");
     }
 }
+
+#[actix_rt::test]
+async fn test_commit_ordering() {
+    let data = web::Data::new(DsData::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(register_client)
+            .service(msg_send),
+    )
+    .await;
+
+    // Two clients create a group with the same group id, such that both of
+    // them can commit to the same epoch.
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let group_id = GroupId::from_slice(b"RacyGroup");
+    let mut client_ids = Vec::new();
+    let mut clients = Vec::new();
+    for client_name in ["Client1", "Client2"] {
+        let provider = OpenMlsRustCrypto::default();
+        let (credential_with_key, signer) =
+            generate_credential(client_name.into(), SignatureScheme::from(ciphersuite));
+        let key_package = generate_key_package(
+            ciphersuite,
+            credential_with_key.clone(),
+            Extensions::empty(),
+            &provider,
+            &signer,
+        );
+        let key_packages: Vec<(TlsByteVecU8, KeyPackageIn)> = vec![(
+            key_package
+                .key_package()
+                .hash_ref(provider.crypto())
+                .unwrap()
+                .as_slice()
+                .to_vec()
+                .into(),
+            key_package.key_package().clone().into(),
+        )];
+        let body = RegisterClientRequest {
+            key_packages: ClientKeyPackages(key_packages.into()),
+        };
+        let req = test::TestRequest::post()
+            .uri("/clients/register")
+            .set_payload(Bytes::copy_from_slice(
+                &body.tls_serialize_detached().unwrap(),
+            ))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        client_ids.push(credential_with_key.credential.serialized_content().to_vec());
+        let group = MlsGroup::new_with_group_id(
+            &provider,
+            &signer,
+            &MlsGroupCreateConfig::default(),
+            group_id.clone(),
+            credential_with_key,
+        )
+        .unwrap();
+        clients.push((provider, signer, group));
+    }
+
+    let send_message = |message: MlsMessageOut| {
+        let msg = GroupMessage::new(message.into(), &client_ids);
+        test::TestRequest::post()
+            .uri("/send/message")
+            .set_payload(Bytes::copy_from_slice(
+                &msg.tls_serialize_detached().unwrap(),
+            ))
+            .to_request()
+    };
+
+    let mut commits = Vec::new();
+    for (provider, signer, group) in clients.iter_mut() {
+        let commit = group
+            .self_update(&*provider, &*signer, LeafNodeParameters::default())
+            .unwrap()
+            .into_commit();
+        commits.push(commit);
+    }
+
+    // The first commit for epoch 0 is accepted, the second one is rejected.
+    let response = test::call_service(&app, send_message(commits.remove(0))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = test::call_service(&app, send_message(commits.remove(0))).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // The winner can commit again in the next epoch.
+    let (provider, signer, group) = &mut clients[0];
+    group.merge_pending_commit(&*provider).unwrap();
+    let commit = group
+        .self_update(&*provider, &*signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    let response = test::call_service(&app, send_message(commit)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Unknown recipients are rejected.
+    let (provider, signer, group) = &mut clients[1];
+    group.clear_pending_commit(provider.storage()).unwrap();
+    let message = group
+        .create_message(&*provider, &*signer, b"Hello")
+        .unwrap();
+    let msg = GroupMessage::new(message.into(), &[b"Unknown".to_vec()]);
+    let req = test::TestRequest::post()
+        .uri("/send/message")
+        .set_payload(Bytes::copy_from_slice(
+            &msg.tls_serialize_detached().unwrap(),
+        ))
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_push_queue() {
+    use futures_util::FutureExt;
+
+    let data = web::Data::new(DsData::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(register_client)
+            .service(msg_send),
+    )
+    .await;
+
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let provider = OpenMlsRustCrypto::default();
+    let (credential_with_key, signer) =
+        generate_credential("Client1".into(), SignatureScheme::from(ciphersuite));
+    let key_package = generate_key_package(
+        ciphersuite,
+        credential_with_key.clone(),
+        Extensions::empty(),
+        &provider,
+        &signer,
+    );
+    let key_packages: Vec<(TlsByteVecU8, KeyPackageIn)> = vec![(
+        key_package
+            .key_package()
+            .hash_ref(provider.crypto())
+            .unwrap()
+            .as_slice()
+            .to_vec()
+            .into(),
+        key_package.key_package().clone().into(),
+    )];
+    let body = RegisterClientRequest {
+        key_packages: ClientKeyPackages(key_packages.into()),
+    };
+    let req = test::TestRequest::post()
+        .uri("/clients/register")
+        .set_payload(Bytes::copy_from_slice(
+            &body.tls_serialize_detached().unwrap(),
+        ))
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let client_id = credential_with_key.credential.serialized_content().to_vec();
+    let mut group = MlsGroup::new(
+        &provider,
+        &signer,
+        &MlsGroupCreateConfig::default(),
+        credential_with_key,
+    )
+    .unwrap();
+    let notifier = data.notifier(&client_id).unwrap();
+
+    let mut messages = Vec::new();
+    for _ in 0..2 {
+        let message: MlsMessageIn = group
+            .create_message(&provider, &signer, b"Hello")
+            .unwrap()
+            .into();
+        let msg = GroupMessage::new(message.clone(), &[client_id.clone()]);
+        let req = test::TestRequest::post()
+            .uri("/send/message")
+            .set_payload(Bytes::copy_from_slice(
+                &msg.tls_serialize_detached().unwrap(),
+            ))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Queuing a message wakes up the WebSocket connection.
+        assert!(notifier.notified().now_or_never().is_some());
+        messages.push(message);
+    }
+
+    // Looking at the queue doesn't remove the messages.
+    assert_eq!(data.queued_messages(&client_id).unwrap(), messages);
+    assert_eq!(data.queued_messages(&client_id).unwrap(), messages);
+
+    // Only delivered messages are removed, and only once.
+    data.remove_delivered(&client_id, &messages[0]);
+    assert_eq!(data.queued_messages(&client_id).unwrap(), messages[1..]);
+    data.remove_delivered(&client_id, &messages[0]);
+    assert_eq!(data.queued_messages(&client_id).unwrap(), messages[1..]);
+    data.remove_delivered(&client_id, &messages[1]);
+    assert!(data.queued_messages(&client_id).unwrap().is_empty());
+    assert!(notifier.notified().now_or_never().is_none());
+}
@@ -227,7 +227,8 @@ impl MlsClient for MlsClientImpl {
             .sender_ratchet_configuration(SenderRatchetConfiguration::default())
             .use_ratchet_tree_extension(true)
             .wire_format_policy(wire_format_policy)
-            .build();
+            .build()
+            .map_err(into_status)?;
         let group = MlsGroup::new_with_group_id(
             &provider,
             &signature_keys,
@@ -372,7 +373,7 @@ impl MlsClient for MlsClientImpl {
                     request.identity
                 )))?;
 
-        use openmls_traits::storage::StorageProvider as _;
+        use openmls_traits::storage::StorageWriter as _;
 
         // Store the key package in the key store with the hash reference as id
         // for retrieval when parsing welcome messages.
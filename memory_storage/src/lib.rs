@@ -1,6 +1,9 @@
 use openmls_traits::storage::*;
 use serde::Serialize;
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+};
 
 #[cfg(feature = "test-utils")]
 use std::io::Write as _;
@@ -15,15 +18,25 @@ pub mod persistence;
 #[derive(Debug, Default)]
 pub struct MemoryStorage {
     pub values: RwLock<HashMap<Vec<u8>, Vec<u8>>>,
+    /// The original values of the keys that were written or deleted since the
+    /// last call to [`MemoryStorage::snapshot()`], or `None` if there is no
+    /// snapshot.
+    snapshot: RwLock<Option<Snapshot>>,
 }
 
+/// The original value of every key that was changed since the snapshot was
+/// taken. `None` means the key didn't exist.
+type Snapshot = HashMap<Vec<u8>, Option<Vec<u8>>>;
+
 // For testing we want to clone.
 #[cfg(feature = "test-utils")]
 impl Clone for MemoryStorage {
     fn clone(&self) -> Self {
         let values = self.values.read().unwrap();
+        let snapshot = self.snapshot.read().unwrap();
         Self {
             values: RwLock::new(values.clone()),
+            snapshot: RwLock::new(snapshot.clone()),
         }
     }
 }
@@ -80,11 +93,71 @@ impl MemoryStorage {
 
         Ok(Self {
             values: RwLock::new(map),
+            snapshot: RwLock::default(),
         })
     }
 }
 
 impl MemoryStorage {
+    /// Takes a copy-on-write snapshot of the storage.
+    ///
+    /// From now on, the original value of every key is recorded before it is
+    /// written or deleted for the first time, so that all changes can be
+    /// undone with [`MemoryStorage::rollback()`]. Call this before a
+    /// multi-step operation, e.g. merging a commit, and
+    /// [`MemoryStorage::release_snapshot()`] once it succeeded.
+    ///
+    /// If a snapshot was already taken, it is kept, so that a rollback
+    /// returns to the state before the outermost operation.
+    pub fn snapshot(&self) {
+        let mut snapshot = self.snapshot.write().unwrap();
+        if snapshot.is_none() {
+            *snapshot = Some(HashMap::new());
+        }
+    }
+
+    /// Discards the snapshot and keeps all changes made since it was taken.
+    pub fn release_snapshot(&self) {
+        *self.snapshot.write().unwrap() = None;
+    }
+
+    /// Undoes all changes made since the snapshot was taken and discards the
+    /// snapshot.
+    ///
+    /// Returns `false` if there was no snapshot to roll back to.
+    pub fn rollback(&self) -> bool {
+        let mut values = self.values.write().unwrap();
+        let Some(snapshot) = self.snapshot.write().unwrap().take() else {
+            return false;
+        };
+        for (key, original) in snapshot {
+            match original {
+                Some(value) => values.insert(key, value),
+                None => values.remove(&key),
+            };
+        }
+        true
+    }
+
+    /// Returns `true` if a snapshot was taken and neither released nor rolled
+    /// back.
+    pub fn has_snapshot(&self) -> bool {
+        self.snapshot.read().unwrap().is_some()
+    }
+
+    /// Records the original value of `storage_key` if there is a snapshot and
+    /// the key wasn't changed since the snapshot was taken.
+    ///
+    /// Must be called with the lock on `values` held, before the key is
+    /// changed.
+    fn record_original(&self, values: &HashMap<Vec<u8>, Vec<u8>>, storage_key: &[u8]) {
+        if let Some(snapshot) = self.snapshot.write().unwrap().as_mut() {
+            snapshot
+                .entry(storage_key.to_vec())
+                .or_insert_with(|| values.get(storage_key).cloned());
+        }
+    }
+
     /// Internal helper to abstract write operations.
     #[inline(always)]
     fn write<const VERSION: u16>(
@@ -92,7 +165,7 @@ impl MemoryStorage {
         label: &[u8],
         key: &[u8],
         value: Vec<u8>,
-    ) -> Result<(), <Self as StorageProvider<CURRENT_VERSION>>::Error> {
+    ) -> Result<(), <Self as StorageReader<CURRENT_VERSION>>::Error> {
         let mut values = self.values.write().unwrap();
         let storage_key = build_key_from_vec::<VERSION>(label, key.to_vec());
 
@@ -100,6 +173,7 @@ impl MemoryStorage {
         log::debug!("  write key: {}", hex::encode(&storage_key));
         log::trace!("{}", std::backtrace::Backtrace::capture());
 
+        self.record_original(&values, &storage_key);
         values.insert(storage_key, value.to_vec());
         Ok(())
     }
@@ -109,7 +183,7 @@ impl MemoryStorage {
         label: &[u8],
         key: &[u8],
         value: Vec<u8>,
-    ) -> Result<(), <Self as StorageProvider<CURRENT_VERSION>>::Error> {
+    ) -> Result<(), <Self as StorageReader<CURRENT_VERSION>>::Error> {
         let mut values = self.values.write().unwrap();
         let storage_key = build_key_from_vec::<VERSION>(label, key.to_vec());
 
@@ -117,6 +191,8 @@ impl MemoryStorage {
         log::debug!("  write key: {}", hex::encode(&storage_key));
         log::trace!("{}", std::backtrace::Backtrace::capture());
 
+        self.record_original(&values, &storage_key);
+
         // fetch value from db, falling back to an empty list if doens't exist
         let list_bytes = values.entry(storage_key).or_insert(b"[]".to_vec());
 
@@ -136,7 +212,7 @@ impl MemoryStorage {
         label: &[u8],
         key: &[u8],
         value: Vec<u8>,
-    ) -> Result<(), <Self as StorageProvider<CURRENT_VERSION>>::Error> {
+    ) -> Result<(), <Self as StorageReader<CURRENT_VERSION>>::Error> {
         let mut values = self.values.write().unwrap();
         let storage_key = build_key_from_vec::<VERSION>(label, key.to_vec());
 
@@ -144,6 +220,8 @@ impl MemoryStorage {
         log::debug!("  write key: {}", hex::encode(&storage_key));
         log::trace!("{}", std::backtrace::Backtrace::capture());
 
+        self.record_original(&values, &storage_key);
+
         // fetch value from db, falling back to an empty list if doens't exist
         let list_bytes = values.entry(storage_key).or_insert(b"[]".to_vec());
 
@@ -166,7 +244,7 @@ impl MemoryStorage {
         &self,
         label: &[u8],
         key: &[u8],
-    ) -> Result<Option<V>, <Self as StorageProvider<CURRENT_VERSION>>::Error> {
+    ) -> Result<Option<V>, <Self as StorageReader<CURRENT_VERSION>>::Error> {
         let values = self.values.read().unwrap();
         let storage_key = build_key_from_vec::<VERSION>(label, key.to_vec());
 
@@ -191,7 +269,7 @@ impl MemoryStorage {
         &self,
         label: &[u8],
         key: &[u8],
-    ) -> Result<Vec<V>, <Self as StorageProvider<CURRENT_VERSION>>::Error> {
+    ) -> Result<Vec<V>, <Self as StorageReader<CURRENT_VERSION>>::Error> {
         let values = self.values.read().unwrap();
 
         let mut storage_key = label.to_vec();
@@ -220,7 +298,7 @@ impl MemoryStorage {
         &self,
         label: &[u8],
         key: &[u8],
-    ) -> Result<(), <Self as StorageProvider<CURRENT_VERSION>>::Error> {
+    ) -> Result<(), <Self as StorageReader<CURRENT_VERSION>>::Error> {
         let mut values = self.values.write().unwrap();
 
         let mut storage_key = label.to_vec();
@@ -231,10 +309,40 @@ impl MemoryStorage {
         log::debug!("  delete key: {}", hex::encode(&storage_key));
         log::trace!("{}", std::backtrace::Backtrace::capture());
 
+        self.record_original(&values, &storage_key);
         values.remove(&storage_key);
 
         Ok(())
     }
+
+    /// Internal helper to enumerate the keys stored under any of the `labels`.
+    #[inline(always)]
+    fn read_keys<const VERSION: u16, K: Entity<VERSION>>(
+        &self,
+        labels: &[&[u8]],
+    ) -> Result<Vec<K>, <Self as StorageReader<CURRENT_VERSION>>::Error> {
+        let values = self.values.read().unwrap();
+        let version = u16::to_be_bytes(VERSION);
+
+        let mut keys = BTreeMap::new();
+        for storage_key in values.keys() {
+            for label in labels {
+                let Some(key) = storage_key
+                    .strip_prefix(*label)
+                    .and_then(|key| key.strip_suffix(&version))
+                else {
+                    continue;
+                };
+                // A label may be a prefix of another label. Keys that don't
+                // deserialize belong to the other label.
+                if let Ok(value) = serde_json::from_slice::<K>(key) {
+                    keys.entry(key).or_insert(value);
+                }
+            }
+        }
+
+        Ok(keys.into_values().collect())
+    }
 }
 
 /// Errors thrown by the key store.
@@ -251,6 +359,7 @@ pub enum MemoryStorageError {
 }
 
 const KEY_PACKAGE_LABEL: &[u8] = b"KeyPackage";
+const JOINED_GROUP_ID_LABEL: &[u8] = b"JoinedGroupId";
 const PSK_LABEL: &[u8] = b"Psk";
 const ENCRYPTION_KEY_PAIR_LABEL: &[u8] = b"EncryptionKeyPair";
 const SIGNATURE_KEY_PAIR_LABEL: &[u8] = b"SignatureKeyPair";
@@ -272,113 +381,39 @@ const OWN_LEAF_NODE_INDEX_LABEL: &[u8] = b"OwnLeafNodeIndex";
 const EPOCH_SECRETS_LABEL: &[u8] = b"EpochSecrets";
 const RESUMPTION_PSK_STORE_LABEL: &[u8] = b"ResumptionPsk";
 const MESSAGE_SECRETS_LABEL: &[u8] = b"MessageSecrets";
-
-impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
+const MEMBER_STATS_LABEL: &[u8] = b"MemberStats";
+const REPLICA_SEQUENCE_LABEL: &[u8] = b"ReplicaSequence";
+const USED_EXTERNAL_INITS_LABEL: &[u8] = b"UsedExternalInits";
+
+// related to LightGroup. A light group isn't an `MlsGroup`, so it isn't
+// enumerated by `group_ids()` and never collected as an orphan.
+const LIGHT_GROUP_LABEL: &[u8] = b"LightGroup";
+
+/// The labels of the values that are keyed by the group id alone. The queued
+/// proposals and the epoch key pairs are keyed by the group id together with
+/// other values, but a group with queued proposals always has a proposal
+/// queue.
+const GROUP_LABELS: &[&[u8]] = &[
+    JOIN_CONFIG_LABEL,
+    OWN_LEAF_NODES_LABEL,
+    GROUP_STATE_LABEL,
+    PROPOSAL_QUEUE_REFS_LABEL,
+    OWN_LEAF_NODE_INDEX_LABEL,
+    EPOCH_SECRETS_LABEL,
+    RESUMPTION_PSK_STORE_LABEL,
+    MESSAGE_SECRETS_LABEL,
+    MEMBER_STATS_LABEL,
+    REPLICA_SEQUENCE_LABEL,
+    USED_EXTERNAL_INITS_LABEL,
+    TREE_LABEL,
+    GROUP_CONTEXT_LABEL,
+    INTERIM_TRANSCRIPT_HASH_LABEL,
+    CONFIRMATION_TAG_LABEL,
+];
+
+impl StorageReader<CURRENT_VERSION> for MemoryStorage {
     type Error = MemoryStorageError;
 
-    fn queue_proposal<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
-        QueuedProposal: traits::QueuedProposal<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-        proposal_ref: &ProposalRef,
-        proposal: &QueuedProposal,
-    ) -> Result<(), Self::Error> {
-        // write proposal to key (group_id, proposal_ref)
-        let key = serde_json::to_vec(&(group_id, proposal_ref))?;
-        let value = serde_json::to_vec(proposal)?;
-        self.write::<CURRENT_VERSION>(QUEUED_PROPOSAL_LABEL, &key, value)?;
-
-        // update proposal list for group_id
-        let key = serde_json::to_vec(group_id)?;
-        let value = serde_json::to_vec(proposal_ref)?;
-        self.append::<CURRENT_VERSION>(PROPOSAL_QUEUE_REFS_LABEL, &key, value)?;
-
-        Ok(())
-    }
-
-    fn write_tree<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        TreeSync: traits::TreeSync<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-        tree: &TreeSync,
-    ) -> Result<(), Self::Error> {
-        self.write::<CURRENT_VERSION>(
-            TREE_LABEL,
-            &serde_json::to_vec(&group_id).unwrap(),
-            serde_json::to_vec(&tree).unwrap(),
-        )
-    }
-
-    fn write_interim_transcript_hash<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        InterimTranscriptHash: traits::InterimTranscriptHash<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-        interim_transcript_hash: &InterimTranscriptHash,
-    ) -> Result<(), Self::Error> {
-        let mut values = self.values.write().unwrap();
-        let key = build_key::<CURRENT_VERSION, &GroupId>(INTERIM_TRANSCRIPT_HASH_LABEL, group_id);
-        let value = serde_json::to_vec(&interim_transcript_hash).unwrap();
-
-        values.insert(key, value);
-        Ok(())
-    }
-
-    fn write_context<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        GroupContext: traits::GroupContext<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-        group_context: &GroupContext,
-    ) -> Result<(), Self::Error> {
-        let mut values = self.values.write().unwrap();
-        let key = build_key::<CURRENT_VERSION, &GroupId>(GROUP_CONTEXT_LABEL, group_id);
-        let value = serde_json::to_vec(&group_context).unwrap();
-
-        values.insert(key, value);
-        Ok(())
-    }
-
-    fn write_confirmation_tag<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        ConfirmationTag: traits::ConfirmationTag<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-        confirmation_tag: &ConfirmationTag,
-    ) -> Result<(), Self::Error> {
-        let mut values = self.values.write().unwrap();
-        let key = build_key::<CURRENT_VERSION, &GroupId>(CONFIRMATION_TAG_LABEL, group_id);
-        let value = serde_json::to_vec(&confirmation_tag).unwrap();
-
-        values.insert(key, value);
-        Ok(())
-    }
-
-    fn write_signature_key_pair<
-        SignaturePublicKey: traits::SignaturePublicKey<CURRENT_VERSION>,
-        SignatureKeyPair: traits::SignatureKeyPair<CURRENT_VERSION>,
-    >(
-        &self,
-        public_key: &SignaturePublicKey,
-        signature_key_pair: &SignatureKeyPair,
-    ) -> Result<(), Self::Error> {
-        let mut values = self.values.write().unwrap();
-        let key =
-            build_key::<CURRENT_VERSION, &SignaturePublicKey>(SIGNATURE_KEY_PAIR_LABEL, public_key);
-        let value = serde_json::to_vec(&signature_key_pair).unwrap();
-
-        values.insert(key, value);
-        Ok(())
-    }
-
     fn queued_proposal_refs<
         GroupId: traits::GroupId<CURRENT_VERSION>,
         ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
@@ -503,62 +538,25 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         Ok(value)
     }
 
-    fn write_key_package<
-        HashReference: traits::HashReference<CURRENT_VERSION>,
+    fn key_package<
+        KeyPackageRef: traits::HashReference<CURRENT_VERSION>,
         KeyPackage: traits::KeyPackage<CURRENT_VERSION>,
     >(
         &self,
-        hash_ref: &HashReference,
-        key_package: &KeyPackage,
-    ) -> Result<(), Self::Error> {
+        hash_ref: &KeyPackageRef,
+    ) -> Result<Option<KeyPackage>, Self::Error> {
         let key = serde_json::to_vec(&hash_ref).unwrap();
-        let value = serde_json::to_vec(&key_package).unwrap();
-
-        self.write::<CURRENT_VERSION>(KEY_PACKAGE_LABEL, &key, value)
-            .unwrap();
-
-        Ok(())
-    }
-
-    fn write_psk<
-        PskId: traits::PskId<CURRENT_VERSION>,
-        PskBundle: traits::PskBundle<CURRENT_VERSION>,
-    >(
-        &self,
-        psk_id: &PskId,
-        psk: &PskBundle,
-    ) -> Result<(), Self::Error> {
-        self.write::<CURRENT_VERSION>(
-            PSK_LABEL,
-            &serde_json::to_vec(&psk_id).unwrap(),
-            serde_json::to_vec(&psk).unwrap(),
-        )
-    }
-
-    fn write_encryption_key_pair<
-        EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>,
-        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
-    >(
-        &self,
-        public_key: &EncryptionKey,
-        key_pair: &HpkeKeyPair,
-    ) -> Result<(), Self::Error> {
-        self.write::<CURRENT_VERSION>(
-            ENCRYPTION_KEY_PAIR_LABEL,
-            &serde_json::to_vec(public_key).unwrap(),
-            serde_json::to_vec(key_pair).unwrap(),
-        )
+        self.read(KEY_PACKAGE_LABEL, &key)
     }
 
-    fn key_package<
+    fn joined_group_id<
         KeyPackageRef: traits::HashReference<CURRENT_VERSION>,
-        KeyPackage: traits::KeyPackage<CURRENT_VERSION>,
+        GroupId: traits::GroupId<CURRENT_VERSION> + Entity<CURRENT_VERSION>,
     >(
         &self,
         hash_ref: &KeyPackageRef,
-    ) -> Result<Option<KeyPackage>, Self::Error> {
-        let key = serde_json::to_vec(&hash_ref).unwrap();
-        self.read(KEY_PACKAGE_LABEL, &key)
+    ) -> Result<Option<GroupId>, Self::Error> {
+        self.read(JOINED_GROUP_ID_LABEL, &serde_json::to_vec(hash_ref)?)
     }
 
     fn psk<PskBundle: traits::PskBundle<CURRENT_VERSION>, PskId: traits::PskId<CURRENT_VERSION>>(
@@ -581,42 +579,6 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         )
     }
 
-    fn delete_signature_key_pair<
-        SignaturePublicKeuy: traits::SignaturePublicKey<CURRENT_VERSION>,
-    >(
-        &self,
-        public_key: &SignaturePublicKeuy,
-    ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(
-            SIGNATURE_KEY_PAIR_LABEL,
-            &serde_json::to_vec(public_key).unwrap(),
-        )
-    }
-
-    fn delete_encryption_key_pair<EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>>(
-        &self,
-        public_key: &EncryptionKey,
-    ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(
-            ENCRYPTION_KEY_PAIR_LABEL,
-            &serde_json::to_vec(&public_key).unwrap(),
-        )
-    }
-
-    fn delete_key_package<KeyPackageRef: traits::HashReference<CURRENT_VERSION>>(
-        &self,
-        hash_ref: &KeyPackageRef,
-    ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(KEY_PACKAGE_LABEL, &serde_json::to_vec(&hash_ref)?)
-    }
-
-    fn delete_psk<PskKey: traits::PskId<CURRENT_VERSION>>(
-        &self,
-        psk_id: &PskKey,
-    ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(PSK_LABEL, &serde_json::to_vec(&psk_id)?)
-    }
-
     fn group_state<
         GroupState: traits::GroupState<CURRENT_VERSION>,
         GroupId: traits::GroupId<CURRENT_VERSION>,
@@ -627,58 +589,54 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         self.read(GROUP_STATE_LABEL, &serde_json::to_vec(&group_id)?)
     }
 
-    fn write_group_state<
-        GroupState: traits::GroupState<CURRENT_VERSION>,
+    fn member_stats<
         GroupId: traits::GroupId<CURRENT_VERSION>,
+        MemberStats: traits::MemberStats<CURRENT_VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        group_state: &GroupState,
-    ) -> Result<(), Self::Error> {
-        self.write::<CURRENT_VERSION>(
-            GROUP_STATE_LABEL,
-            &serde_json::to_vec(group_id)?,
-            serde_json::to_vec(group_state)?,
-        )
+    ) -> Result<Option<MemberStats>, Self::Error> {
+        self.read(MEMBER_STATS_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn delete_group_state<GroupId: traits::GroupId<CURRENT_VERSION>>(
+    fn used_external_inits<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        UsedExternalInits: traits::UsedExternalInits<CURRENT_VERSION>,
+    >(
         &self,
         group_id: &GroupId,
-    ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(GROUP_STATE_LABEL, &serde_json::to_vec(group_id)?)
+    ) -> Result<Option<UsedExternalInits>, Self::Error> {
+        self.read(USED_EXTERNAL_INITS_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn message_secrets<
+    fn replica_sequence<
         GroupId: traits::GroupId<CURRENT_VERSION>,
-        MessageSecrets: traits::MessageSecrets<CURRENT_VERSION>,
+        ReplicaSequence: traits::ReplicaSequence<CURRENT_VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<MessageSecrets>, Self::Error> {
-        self.read(MESSAGE_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    ) -> Result<Option<ReplicaSequence>, Self::Error> {
+        self.read(REPLICA_SEQUENCE_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn write_message_secrets<
+    fn light_group<
         GroupId: traits::GroupId<CURRENT_VERSION>,
-        MessageSecrets: traits::MessageSecrets<CURRENT_VERSION>,
+        LightGroup: traits::LightGroup<CURRENT_VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        message_secrets: &MessageSecrets,
-    ) -> Result<(), Self::Error> {
-        self.write::<CURRENT_VERSION>(
-            MESSAGE_SECRETS_LABEL,
-            &serde_json::to_vec(group_id)?,
-            serde_json::to_vec(message_secrets)?,
-        )
+    ) -> Result<Option<LightGroup>, Self::Error> {
+        self.read(LIGHT_GROUP_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn delete_message_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+    fn message_secrets<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MessageSecrets: traits::MessageSecrets<CURRENT_VERSION>,
+    >(
         &self,
         group_id: &GroupId,
-    ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(MESSAGE_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    ) -> Result<Option<MessageSecrets>, Self::Error> {
+        self.read(MESSAGE_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
     }
 
     fn resumption_psk_store<
@@ -691,36 +649,463 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         self.read(RESUMPTION_PSK_STORE_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn write_resumption_psk_store<
+    fn own_leaf_index<
         GroupId: traits::GroupId<CURRENT_VERSION>,
-        ResumptionPskStore: traits::ResumptionPskStore<CURRENT_VERSION>,
+        LeafNodeIndex: traits::LeafNodeIndex<CURRENT_VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        resumption_psk_store: &ResumptionPskStore,
+    ) -> Result<Option<LeafNodeIndex>, Self::Error> {
+        self.read(OWN_LEAF_NODE_INDEX_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn group_epoch_secrets<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        GroupEpochSecrets: traits::GroupEpochSecrets<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<GroupEpochSecrets>, Self::Error> {
+        self.read(EPOCH_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn encryption_epoch_key_pairs<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        EpochKey: traits::EpochKey<CURRENT_VERSION>,
+        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        epoch: &EpochKey,
+        leaf_index: u32,
+    ) -> Result<Vec<HpkeKeyPair>, Self::Error> {
+        let key = epoch_key_pairs_id(group_id, epoch, leaf_index)?;
+        let storage_key = build_key_from_vec::<CURRENT_VERSION>(EPOCH_KEY_PAIRS_LABEL, key);
+        log::debug!("Reading encryption epoch key pairs");
+
+        let values = self.values.read().unwrap();
+        let value = values.get(&storage_key);
+
+        #[cfg(feature = "test-utils")]
+        log::debug!("  key: {}", hex::encode(&storage_key));
+
+        if let Some(value) = value {
+            #[cfg(feature = "test-utils")]
+            log::debug!("  value: {}", hex::encode(value));
+            return Ok(serde_json::from_slice(value).unwrap());
+        }
+
+        Err(MemoryStorageError::None)
+    }
+
+    fn mls_group_join_config<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MlsGroupJoinConfig: traits::MlsGroupJoinConfig<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error> {
+        self.read(JOIN_CONFIG_LABEL, &serde_json::to_vec(group_id).unwrap())
+    }
+
+    fn own_leaf_nodes<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        LeafNode: traits::LeafNode<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Vec<LeafNode>, Self::Error> {
+        self.read_list(OWN_LEAF_NODES_LABEL, &serde_json::to_vec(group_id).unwrap())
+    }
+
+    fn group_ids<GroupId: traits::GroupId<CURRENT_VERSION> + Entity<CURRENT_VERSION>>(
+        &self,
+    ) -> Result<Vec<GroupId>, Self::Error> {
+        self.read_keys(GROUP_LABELS)
+    }
+
+    fn key_package_refs<
+        KeyPackageRef: traits::HashReference<CURRENT_VERSION> + Entity<CURRENT_VERSION>,
+    >(
+        &self,
+    ) -> Result<Vec<KeyPackageRef>, Self::Error> {
+        self.read_keys(&[KEY_PACKAGE_LABEL])
+    }
+
+    fn encryption_keys<
+        EncryptionKey: traits::EncryptionKey<CURRENT_VERSION> + Entity<CURRENT_VERSION>,
+    >(
+        &self,
+    ) -> Result<Vec<EncryptionKey>, Self::Error> {
+        self.read_keys(&[ENCRYPTION_KEY_PAIR_LABEL])
+    }
+}
+
+impl StorageWriter<CURRENT_VERSION> for MemoryStorage {
+    fn queue_proposal<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
+        QueuedProposal: traits::QueuedProposal<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        proposal_ref: &ProposalRef,
+        proposal: &QueuedProposal,
+    ) -> Result<(), Self::Error> {
+        // write proposal to key (group_id, proposal_ref)
+        let key = serde_json::to_vec(&(group_id, proposal_ref))?;
+        let value = serde_json::to_vec(proposal)?;
+        self.write::<CURRENT_VERSION>(QUEUED_PROPOSAL_LABEL, &key, value)?;
+
+        // update proposal list for group_id
+        let key = serde_json::to_vec(group_id)?;
+        let value = serde_json::to_vec(proposal_ref)?;
+        self.append::<CURRENT_VERSION>(PROPOSAL_QUEUE_REFS_LABEL, &key, value)?;
+
+        Ok(())
+    }
+
+    fn write_tree<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        TreeSync: traits::TreeSync<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        tree: &TreeSync,
     ) -> Result<(), Self::Error> {
         self.write::<CURRENT_VERSION>(
-            RESUMPTION_PSK_STORE_LABEL,
+            TREE_LABEL,
+            &serde_json::to_vec(&group_id).unwrap(),
+            serde_json::to_vec(&tree).unwrap(),
+        )
+    }
+
+    fn write_interim_transcript_hash<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        InterimTranscriptHash: traits::InterimTranscriptHash<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        interim_transcript_hash: &InterimTranscriptHash,
+    ) -> Result<(), Self::Error> {
+        let mut values = self.values.write().unwrap();
+        let key = build_key::<CURRENT_VERSION, &GroupId>(INTERIM_TRANSCRIPT_HASH_LABEL, group_id);
+        let value = serde_json::to_vec(&interim_transcript_hash).unwrap();
+
+        self.record_original(&values, &key);
+        values.insert(key, value);
+        Ok(())
+    }
+
+    fn write_context<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        GroupContext: traits::GroupContext<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        group_context: &GroupContext,
+    ) -> Result<(), Self::Error> {
+        let mut values = self.values.write().unwrap();
+        let key = build_key::<CURRENT_VERSION, &GroupId>(GROUP_CONTEXT_LABEL, group_id);
+        let value = serde_json::to_vec(&group_context).unwrap();
+
+        self.record_original(&values, &key);
+        values.insert(key, value);
+        Ok(())
+    }
+
+    fn write_confirmation_tag<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ConfirmationTag: traits::ConfirmationTag<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        confirmation_tag: &ConfirmationTag,
+    ) -> Result<(), Self::Error> {
+        let mut values = self.values.write().unwrap();
+        let key = build_key::<CURRENT_VERSION, &GroupId>(CONFIRMATION_TAG_LABEL, group_id);
+        let value = serde_json::to_vec(&confirmation_tag).unwrap();
+
+        self.record_original(&values, &key);
+        values.insert(key, value);
+        Ok(())
+    }
+
+    fn write_signature_key_pair<
+        SignaturePublicKey: traits::SignaturePublicKey<CURRENT_VERSION>,
+        SignatureKeyPair: traits::SignatureKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature_key_pair: &SignatureKeyPair,
+    ) -> Result<(), Self::Error> {
+        let mut values = self.values.write().unwrap();
+        let key =
+            build_key::<CURRENT_VERSION, &SignaturePublicKey>(SIGNATURE_KEY_PAIR_LABEL, public_key);
+        let value = serde_json::to_vec(&signature_key_pair).unwrap();
+
+        self.record_original(&values, &key);
+        values.insert(key, value);
+        Ok(())
+    }
+
+    fn write_key_package<
+        HashReference: traits::HashReference<CURRENT_VERSION>,
+        KeyPackage: traits::KeyPackage<CURRENT_VERSION>,
+    >(
+        &self,
+        hash_ref: &HashReference,
+        key_package: &KeyPackage,
+    ) -> Result<(), Self::Error> {
+        let key = serde_json::to_vec(&hash_ref).unwrap();
+        let value = serde_json::to_vec(&key_package).unwrap();
+
+        self.write::<CURRENT_VERSION>(KEY_PACKAGE_LABEL, &key, value)
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn write_joined_group_id<
+        KeyPackageRef: traits::HashReference<CURRENT_VERSION>,
+        GroupId: traits::GroupId<CURRENT_VERSION> + Entity<CURRENT_VERSION>,
+    >(
+        &self,
+        hash_ref: &KeyPackageRef,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            JOINED_GROUP_ID_LABEL,
+            &serde_json::to_vec(hash_ref)?,
+            serde_json::to_vec(group_id)?,
+        )
+    }
+
+    fn write_psk<
+        PskId: traits::PskId<CURRENT_VERSION>,
+        PskBundle: traits::PskBundle<CURRENT_VERSION>,
+    >(
+        &self,
+        psk_id: &PskId,
+        psk: &PskBundle,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            PSK_LABEL,
+            &serde_json::to_vec(&psk_id).unwrap(),
+            serde_json::to_vec(&psk).unwrap(),
+        )
+    }
+
+    fn write_encryption_key_pair<
+        EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>,
+        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &EncryptionKey,
+        key_pair: &HpkeKeyPair,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            ENCRYPTION_KEY_PAIR_LABEL,
+            &serde_json::to_vec(public_key).unwrap(),
+            serde_json::to_vec(key_pair).unwrap(),
+        )
+    }
+
+    fn delete_signature_key_pair<
+        SignaturePublicKeuy: traits::SignaturePublicKey<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &SignaturePublicKeuy,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(
+            SIGNATURE_KEY_PAIR_LABEL,
+            &serde_json::to_vec(public_key).unwrap(),
+        )
+    }
+
+    fn delete_encryption_key_pair<EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>>(
+        &self,
+        public_key: &EncryptionKey,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(
+            ENCRYPTION_KEY_PAIR_LABEL,
+            &serde_json::to_vec(&public_key).unwrap(),
+        )
+    }
+
+    fn delete_key_package<KeyPackageRef: traits::HashReference<CURRENT_VERSION>>(
+        &self,
+        hash_ref: &KeyPackageRef,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(KEY_PACKAGE_LABEL, &serde_json::to_vec(&hash_ref)?)
+    }
+
+    fn delete_joined_group_id<KeyPackageRef: traits::HashReference<CURRENT_VERSION>>(
+        &self,
+        hash_ref: &KeyPackageRef,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(JOINED_GROUP_ID_LABEL, &serde_json::to_vec(hash_ref)?)
+    }
+
+    fn delete_psk<PskKey: traits::PskId<CURRENT_VERSION>>(
+        &self,
+        psk_id: &PskKey,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(PSK_LABEL, &serde_json::to_vec(&psk_id)?)
+    }
+
+    fn write_group_state<
+        GroupState: traits::GroupState<CURRENT_VERSION>,
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        group_state: &GroupState,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            GROUP_STATE_LABEL,
             &serde_json::to_vec(group_id)?,
-            serde_json::to_vec(resumption_psk_store)?,
+            serde_json::to_vec(group_state)?,
         )
     }
 
-    fn delete_all_resumption_psk_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+    fn delete_group_state<GroupId: traits::GroupId<CURRENT_VERSION>>(
         &self,
         group_id: &GroupId,
     ) -> Result<(), Self::Error> {
-        self.delete::<CURRENT_VERSION>(RESUMPTION_PSK_STORE_LABEL, &serde_json::to_vec(group_id)?)
+        self.delete::<CURRENT_VERSION>(GROUP_STATE_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn own_leaf_index<
+    fn write_message_secrets<
         GroupId: traits::GroupId<CURRENT_VERSION>,
-        LeafNodeIndex: traits::LeafNodeIndex<CURRENT_VERSION>,
+        MessageSecrets: traits::MessageSecrets<CURRENT_VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<LeafNodeIndex>, Self::Error> {
-        self.read(OWN_LEAF_NODE_INDEX_LABEL, &serde_json::to_vec(group_id)?)
+        message_secrets: &MessageSecrets,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            MESSAGE_SECRETS_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(message_secrets)?,
+        )
+    }
+
+    fn delete_message_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(MESSAGE_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_member_stats<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MemberStats: traits::MemberStats<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        member_stats: &MemberStats,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            MEMBER_STATS_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(member_stats)?,
+        )
+    }
+
+    fn delete_member_stats<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(MEMBER_STATS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_used_external_inits<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        UsedExternalInits: traits::UsedExternalInits<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        used_external_inits: &UsedExternalInits,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            USED_EXTERNAL_INITS_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(used_external_inits)?,
+        )
+    }
+
+    fn delete_used_external_inits<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(USED_EXTERNAL_INITS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_replica_sequence<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ReplicaSequence: traits::ReplicaSequence<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        replica_sequence: &ReplicaSequence,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            REPLICA_SEQUENCE_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(replica_sequence)?,
+        )
+    }
+
+    fn delete_replica_sequence<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(REPLICA_SEQUENCE_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_light_group<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        LightGroup: traits::LightGroup<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        light_group: &LightGroup,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            LIGHT_GROUP_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(light_group)?,
+        )
+    }
+
+    fn delete_light_group<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(LIGHT_GROUP_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_resumption_psk_store<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ResumptionPskStore: traits::ResumptionPskStore<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        resumption_psk_store: &ResumptionPskStore,
+    ) -> Result<(), Self::Error> {
+        self.write::<CURRENT_VERSION>(
+            RESUMPTION_PSK_STORE_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(resumption_psk_store)?,
+        )
+    }
+
+    fn delete_all_resumption_psk_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete::<CURRENT_VERSION>(RESUMPTION_PSK_STORE_LABEL, &serde_json::to_vec(group_id)?)
     }
 
     fn write_own_leaf_index<
@@ -745,16 +1130,6 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         self.delete::<CURRENT_VERSION>(OWN_LEAF_NODE_INDEX_LABEL, &serde_json::to_vec(group_id)?)
     }
 
-    fn group_epoch_secrets<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        GroupEpochSecrets: traits::GroupEpochSecrets<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-    ) -> Result<Option<GroupEpochSecrets>, Self::Error> {
-        self.read(EPOCH_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
-    }
-
     fn write_group_epoch_secrets<
         GroupId: traits::GroupId<CURRENT_VERSION>,
         GroupEpochSecrets: traits::GroupEpochSecrets<CURRENT_VERSION>,
@@ -800,35 +1175,6 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         self.write::<CURRENT_VERSION>(EPOCH_KEY_PAIRS_LABEL, &key, value)
     }
 
-    fn encryption_epoch_key_pairs<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        EpochKey: traits::EpochKey<CURRENT_VERSION>,
-        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-        epoch: &EpochKey,
-        leaf_index: u32,
-    ) -> Result<Vec<HpkeKeyPair>, Self::Error> {
-        let key = epoch_key_pairs_id(group_id, epoch, leaf_index)?;
-        let storage_key = build_key_from_vec::<CURRENT_VERSION>(EPOCH_KEY_PAIRS_LABEL, key);
-        log::debug!("Reading encryption epoch key pairs");
-
-        let values = self.values.read().unwrap();
-        let value = values.get(&storage_key);
-
-        #[cfg(feature = "test-utils")]
-        log::debug!("  key: {}", hex::encode(&storage_key));
-
-        if let Some(value) = value {
-            #[cfg(feature = "test-utils")]
-            log::debug!("  value: {}", hex::encode(value));
-            return Ok(serde_json::from_slice(value).unwrap());
-        }
-
-        Err(MemoryStorageError::None)
-    }
-
     fn delete_encryption_epoch_key_pairs<
         GroupId: traits::GroupId<CURRENT_VERSION>,
         EpochKey: traits::EpochKey<CURRENT_VERSION>,
@@ -856,26 +1202,18 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         for proposal_ref in proposal_refs {
             // Delete all proposals.
             let key = serde_json::to_vec(&(group_id, proposal_ref))?;
+            self.record_original(&values, &key);
             values.remove(&key);
         }
 
         // Delete the proposal refs from the store.
         let key = build_key::<CURRENT_VERSION, &GroupId>(PROPOSAL_QUEUE_REFS_LABEL, group_id);
+        self.record_original(&values, &key);
         values.remove(&key);
 
         Ok(())
     }
 
-    fn mls_group_join_config<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        MlsGroupJoinConfig: traits::MlsGroupJoinConfig<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error> {
-        self.read(JOIN_CONFIG_LABEL, &serde_json::to_vec(group_id).unwrap())
-    }
-
     fn write_mls_join_config<
         GroupId: traits::GroupId<CURRENT_VERSION>,
         MlsGroupJoinConfig: traits::MlsGroupJoinConfig<CURRENT_VERSION>,
@@ -890,16 +1228,6 @@ impl StorageProvider<CURRENT_VERSION> for MemoryStorage {
         self.write::<CURRENT_VERSION>(JOIN_CONFIG_LABEL, &key, value)
     }
 
-    fn own_leaf_nodes<
-        GroupId: traits::GroupId<CURRENT_VERSION>,
-        LeafNode: traits::LeafNode<CURRENT_VERSION>,
-    >(
-        &self,
-        group_id: &GroupId,
-    ) -> Result<Vec<LeafNode>, Self::Error> {
-        self.read_list(OWN_LEAF_NODES_LABEL, &serde_json::to_vec(group_id).unwrap())
-    }
-
     fn append_own_leaf_node<
         GroupId: traits::GroupId<CURRENT_VERSION>,
         LeafNode: traits::LeafNode<CURRENT_VERSION>,
@@ -996,7 +1324,7 @@ fn epoch_key_pairs_id(
     group_id: &impl traits::GroupId<CURRENT_VERSION>,
     epoch: &impl traits::EpochKey<CURRENT_VERSION>,
     leaf_index: u32,
-) -> Result<Vec<u8>, <MemoryStorage as StorageProvider<CURRENT_VERSION>>::Error> {
+) -> Result<Vec<u8>, <MemoryStorage as StorageReader<CURRENT_VERSION>>::Error> {
     let mut key = serde_json::to_vec(group_id)?;
     key.extend_from_slice(&serde_json::to_vec(epoch)?);
     key.extend_from_slice(&serde_json::to_vec(&leaf_index)?);
@@ -1,17 +1,88 @@
+//! Persistence of the [`MemoryStorage`](super::MemoryStorage) to files and
+//! other writers.
+//!
+//! The store is written as JSON that carries a format version. Optionally, the
+//! values are encrypted with ChaCha20Poly1305 under a 32 byte key, using the
+//! crypto and randomness providers from `openmls_traits`.
+
 use std::{
     collections::HashMap,
     env,
     fs::File,
-    io::{BufReader, BufWriter},
+    io::{BufReader, BufWriter, Read, Write},
     path::PathBuf,
 };
 
 use base64::Engine;
+use openmls_traits::{
+    crypto::OpenMlsCrypto,
+    random::OpenMlsRand,
+    types::{AeadType, CryptoError},
+};
 use serde::{Deserialize, Serialize};
 
+/// The version of the format written by [`MemoryStorage::save_to_writer`].
+/// Files written before the format was versioned have version 0.
+///
+/// [`MemoryStorage::save_to_writer`]: super::MemoryStorage::save_to_writer
+pub const PERSISTENCE_VERSION: u16 = 1;
+
+/// The AEAD used to encrypt persisted stores.
+const AEAD: AeadType = AeadType::ChaCha20Poly1305;
+
+/// An error that occurs when persisting or loading the store.
+#[derive(thiserror::Error, Debug)]
+pub enum PersistenceError {
+    /// The store could not be serialized or deserialized.
+    #[error("Error serializing or deserializing the store: {0}")]
+    Serialization(#[from] serde_json::Error),
+    /// A key or value in the persisted store is not valid base64.
+    #[error("Invalid encoding of the persisted store: {0}")]
+    Encoding(#[from] base64::DecodeError),
+    /// The persisted store was written with a newer format version.
+    #[error("Unsupported format version {0}")]
+    UnsupportedVersion(u16),
+    /// The persisted store is encrypted, but no key was given.
+    #[error("The persisted store is encrypted")]
+    Encrypted,
+    /// A key was given, but the persisted store is not encrypted.
+    #[error("The persisted store is not encrypted")]
+    NotEncrypted,
+    /// Encrypting or decrypting the store failed, e.g. because of a wrong
+    /// key.
+    #[error("Error encrypting or decrypting the store: {0}")]
+    Crypto(#[from] CryptoError),
+    /// The randomness provider failed to generate a nonce.
+    #[error("Error generating a nonce")]
+    Randomness,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct SerializableKeyStore {
+    /// The format version. Missing in files written before the format was
+    /// versioned.
+    #[serde(default)]
+    version: u16,
+    #[serde(default)]
     values: HashMap<String, String>,
+    /// The original values recorded by a snapshot that was neither released
+    /// nor rolled back when the store was saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot: Option<HashMap<String, Option<String>>>,
+    /// The encrypted `values` and `snapshot` of an encrypted store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted: Option<EncryptedKeyStore>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedKeyStore {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// The additional data that binds the ciphertext to the format version.
+fn aad(version: u16) -> Vec<u8> {
+    [b"openmls memory storage".as_slice(), &version.to_be_bytes()].concat()
 }
 
 pub fn get_file_path(file_name: &String) -> PathBuf {
@@ -24,21 +95,154 @@ impl super::MemoryStorage {
         get_file_path(&("openmls_cli_".to_owned() + user_name + "_ks.json"))
     }
 
-    pub fn save_to_file(&self, output_file: &File) -> Result<(), String> {
-        let writer = BufWriter::new(output_file);
-
-        let mut ser_ks = SerializableKeyStore::default();
+    fn to_serializable(&self) -> SerializableKeyStore {
+        let mut ser_ks = SerializableKeyStore {
+            version: PERSISTENCE_VERSION,
+            ..Default::default()
+        };
         for (key, value) in &*self.values.read().unwrap() {
             ser_ks.values.insert(
                 base64::prelude::BASE64_STANDARD.encode(key),
                 base64::prelude::BASE64_STANDARD.encode(value),
             );
         }
+        if let Some(snapshot) = &*self.snapshot.read().unwrap() {
+            ser_ks.snapshot = Some(
+                snapshot
+                    .iter()
+                    .map(|(key, original)| {
+                        (
+                            base64::prelude::BASE64_STANDARD.encode(key),
+                            original
+                                .as_ref()
+                                .map(|value| base64::prelude::BASE64_STANDARD.encode(value)),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        ser_ks
+    }
 
-        match serde_json::to_writer_pretty(writer, &ser_ks) {
-            Ok(()) => Ok(()),
-            Err(e) => Err(e.to_string()),
+    /// Inserts the values of `ser_ks` into this store. All values are
+    /// decoded before the first one is inserted, so nothing is inserted if
+    /// any of them is invalid.
+    fn insert_serializable(
+        &mut self,
+        ser_ks: SerializableKeyStore,
+    ) -> Result<(), PersistenceError> {
+        let mut values = HashMap::with_capacity(ser_ks.values.len());
+        let mut removed = Vec::new();
+        for (key, value) in ser_ks.values {
+            values.insert(
+                base64::prelude::BASE64_STANDARD.decode(key)?,
+                base64::prelude::BASE64_STANDARD.decode(value)?,
+            );
+        }
+        if let Some(snapshot) = ser_ks.snapshot {
+            for (key, original) in snapshot {
+                let key = base64::prelude::BASE64_STANDARD.decode(key)?;
+                match original {
+                    Some(value) => {
+                        values.insert(key, base64::prelude::BASE64_STANDARD.decode(value)?);
+                    }
+                    None => {
+                        values.remove(&key);
+                        removed.push(key);
+                    }
+                }
+            }
+        }
+
+        let mut ks_map = self.values.write().unwrap();
+        ks_map.extend(values);
+        for key in removed {
+            ks_map.remove(&key);
         }
+        Ok(())
+    }
+
+    /// Writes the store to `writer` in plaintext.
+    pub fn save_to_writer(&self, writer: impl Write) -> Result<(), PersistenceError> {
+        Ok(serde_json::to_writer_pretty(
+            writer,
+            &self.to_serializable(),
+        )?)
+    }
+
+    /// Writes the store to `writer`, encrypted with ChaCha20Poly1305 under
+    /// `key`. A fresh nonce is drawn from `rand` on every call.
+    pub fn save_to_writer_encrypted(
+        &self,
+        writer: impl Write,
+        crypto: &impl OpenMlsCrypto,
+        rand: &impl OpenMlsRand,
+        key: &[u8; 32],
+    ) -> Result<(), PersistenceError> {
+        let plaintext = serde_json::to_vec(&self.to_serializable())?;
+        let nonce = rand
+            .random_vec(AEAD.nonce_size())
+            .map_err(|_| PersistenceError::Randomness)?;
+        let ciphertext =
+            crypto.aead_encrypt(AEAD, key, &plaintext, &nonce, &aad(PERSISTENCE_VERSION))?;
+
+        let ser_ks = SerializableKeyStore {
+            version: PERSISTENCE_VERSION,
+            encrypted: Some(EncryptedKeyStore {
+                nonce: base64::prelude::BASE64_STANDARD.encode(nonce),
+                ciphertext: base64::prelude::BASE64_STANDARD.encode(ciphertext),
+            }),
+            ..Default::default()
+        };
+        Ok(serde_json::to_writer_pretty(writer, &ser_ks)?)
+    }
+
+    /// Loads a plaintext store from `reader` into this store.
+    ///
+    /// Loading fails as a whole if any value can't be decoded, in which case
+    /// this store is left unchanged.
+    ///
+    /// If the store was saved while a snapshot was held, e.g. because the
+    /// process crashed in the middle of an operation, the changes made since
+    /// the snapshot was taken are rolled back.
+    pub fn load_from_reader(&mut self, reader: impl Read) -> Result<(), PersistenceError> {
+        let ser_ks: SerializableKeyStore = serde_json::from_reader(reader)?;
+        if ser_ks.version > PERSISTENCE_VERSION {
+            return Err(PersistenceError::UnsupportedVersion(ser_ks.version));
+        }
+        if ser_ks.encrypted.is_some() {
+            return Err(PersistenceError::Encrypted);
+        }
+        self.insert_serializable(ser_ks)
+    }
+
+    /// Loads a store that was written with [`Self::save_to_writer_encrypted`]
+    /// from `reader` into this store. See [`Self::load_from_reader`] for how
+    /// snapshots are handled.
+    pub fn load_from_reader_encrypted(
+        &mut self,
+        reader: impl Read,
+        crypto: &impl OpenMlsCrypto,
+        key: &[u8; 32],
+    ) -> Result<(), PersistenceError> {
+        let ser_ks: SerializableKeyStore = serde_json::from_reader(reader)?;
+        if ser_ks.version > PERSISTENCE_VERSION {
+            return Err(PersistenceError::UnsupportedVersion(ser_ks.version));
+        }
+        let Some(encrypted) = ser_ks.encrypted else {
+            return Err(PersistenceError::NotEncrypted);
+        };
+        let nonce = base64::prelude::BASE64_STANDARD.decode(encrypted.nonce)?;
+        let ciphertext = base64::prelude::BASE64_STANDARD.decode(encrypted.ciphertext)?;
+        let plaintext =
+            crypto.aead_decrypt(AEAD, key, &ciphertext, &nonce, &aad(ser_ks.version))?;
+
+        self.insert_serializable(serde_json::from_slice(&plaintext)?)
+    }
+
+    pub fn save_to_file(&self, output_file: &File) -> Result<(), String> {
+        self.save_to_writer(BufWriter::new(output_file))
+            .map_err(|e| e.to_string())
     }
 
     pub fn save(&self, user_name: String) -> Result<(), String> {
@@ -50,24 +254,10 @@ impl super::MemoryStorage {
         }
     }
 
+    /// Loads the store from `input_file`. See [`Self::load_from_reader`].
     pub fn load_from_file(&mut self, input_file: &File) -> Result<(), String> {
-        // Prepare file reader.
-        let reader = BufReader::new(input_file);
-
-        // Read the JSON contents of the file as an instance of `SerializableKeyStore`.
-        match serde_json::from_reader::<BufReader<&File>, SerializableKeyStore>(reader) {
-            Ok(ser_ks) => {
-                let mut ks_map = self.values.write().unwrap();
-                for (key, value) in ser_ks.values {
-                    ks_map.insert(
-                        base64::prelude::BASE64_STANDARD.decode(key).unwrap(),
-                        base64::prelude::BASE64_STANDARD.decode(value).unwrap(),
-                    );
-                }
-                Ok(())
-            }
-            Err(e) => Err(e.to_string()),
-        }
+        self.load_from_reader(BufReader::new(input_file))
+            .map_err(|e| e.to_string())
     }
 
     pub fn load(&mut self, user_name: String) -> Result<(), String> {
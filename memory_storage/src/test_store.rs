@@ -1,24 +1,9 @@
 use super::*;
 use std::io::Write;
 
-impl StorageProvider<V_TEST> for MemoryStorage {
+impl StorageReader<V_TEST> for MemoryStorage {
     type Error = MemoryStorageError;
 
-    fn write_encryption_key_pair<
-        EncryptionKey: traits::EncryptionKey<V_TEST>,
-        HpkeKeyPair: traits::HpkeKeyPair<V_TEST>,
-    >(
-        &self,
-        public_key: &EncryptionKey,
-        key_pair: &HpkeKeyPair,
-    ) -> Result<(), Self::Error> {
-        self.write::<V_TEST>(
-            ENCRYPTION_KEY_PAIR_LABEL,
-            &serde_json::to_vec(&public_key).unwrap(),
-            serde_json::to_vec(&key_pair).unwrap(),
-        )
-    }
-
     fn encryption_epoch_key_pairs<
         GroupId: traits::GroupId<V_TEST>,
         EpochKey: traits::EpochKey<V_TEST>,
@@ -57,6 +42,224 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         self.read(KEY_PACKAGE_LABEL, &key)
     }
 
+    fn queued_proposal_refs<
+        GroupId: traits::GroupId<V_TEST>,
+        ProposalRef: traits::ProposalRef<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Vec<ProposalRef>, Self::Error> {
+        todo!()
+    }
+
+    fn tree<GroupId: traits::GroupId<V_TEST>, TreeSync: traits::TreeSync<V_TEST>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<TreeSync>, Self::Error> {
+        todo!()
+    }
+
+    fn group_context<
+        GroupId: traits::GroupId<V_TEST>,
+        GroupContext: traits::GroupContext<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<GroupContext>, Self::Error> {
+        todo!()
+    }
+
+    fn interim_transcript_hash<
+        GroupId: traits::GroupId<V_TEST>,
+        InterimTranscriptHash: traits::InterimTranscriptHash<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<InterimTranscriptHash>, Self::Error> {
+        todo!()
+    }
+
+    fn confirmation_tag<
+        GroupId: traits::GroupId<V_TEST>,
+        ConfirmationTag: traits::ConfirmationTag<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<ConfirmationTag>, Self::Error> {
+        todo!()
+    }
+
+    fn signature_key_pair<
+        SignaturePublicKey: traits::SignaturePublicKey<V_TEST>,
+        SignatureKeyPair: traits::SignatureKeyPair<V_TEST>,
+    >(
+        &self,
+        _public_key: &SignaturePublicKey,
+    ) -> Result<Option<SignatureKeyPair>, Self::Error> {
+        todo!()
+    }
+
+    fn encryption_key_pair<
+        HpkeKeyPair: traits::HpkeKeyPair<V_TEST>,
+        EncryptionKey: traits::EncryptionKey<V_TEST>,
+    >(
+        &self,
+        _public_key: &EncryptionKey,
+    ) -> Result<Option<HpkeKeyPair>, Self::Error> {
+        todo!()
+    }
+
+    fn psk<PskBundle: traits::PskBundle<V_TEST>, PskId: traits::PskId<V_TEST>>(
+        &self,
+        _psk_id: &PskId,
+    ) -> Result<Option<PskBundle>, Self::Error> {
+        todo!()
+    }
+
+    fn group_state<GroupState: traits::GroupState<V_TEST>, GroupId: traits::GroupId<V_TEST>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<GroupState>, Self::Error> {
+        todo!()
+    }
+
+    fn member_stats<GroupId: traits::GroupId<V_TEST>, MemberStats: traits::MemberStats<V_TEST>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<MemberStats>, Self::Error> {
+        todo!()
+    }
+
+    fn used_external_inits<
+        GroupId: traits::GroupId<V_TEST>,
+        UsedExternalInits: traits::UsedExternalInits<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<UsedExternalInits>, Self::Error> {
+        todo!()
+    }
+
+    fn replica_sequence<
+        GroupId: traits::GroupId<V_TEST>,
+        ReplicaSequence: traits::ReplicaSequence<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<ReplicaSequence>, Self::Error> {
+        todo!()
+    }
+
+    fn light_group<GroupId: traits::GroupId<V_TEST>, LightGroup: traits::LightGroup<V_TEST>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<LightGroup>, Self::Error> {
+        todo!()
+    }
+
+    fn message_secrets<
+        GroupId: traits::GroupId<V_TEST>,
+        MessageSecrets: traits::MessageSecrets<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<MessageSecrets>, Self::Error> {
+        todo!()
+    }
+
+    fn resumption_psk_store<
+        GroupId: traits::GroupId<V_TEST>,
+        ResumptionPskStore: traits::ResumptionPskStore<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<ResumptionPskStore>, Self::Error> {
+        todo!()
+    }
+
+    fn own_leaf_index<
+        GroupId: traits::GroupId<V_TEST>,
+        LeafNodeIndex: traits::LeafNodeIndex<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<LeafNodeIndex>, Self::Error> {
+        todo!()
+    }
+
+    fn group_epoch_secrets<
+        GroupId: traits::GroupId<V_TEST>,
+        GroupEpochSecrets: traits::GroupEpochSecrets<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<GroupEpochSecrets>, Self::Error> {
+        todo!()
+    }
+
+    fn mls_group_join_config<
+        GroupId: traits::GroupId<V_TEST>,
+        MlsGroupJoinConfig: traits::MlsGroupJoinConfig<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error> {
+        todo!()
+    }
+
+    fn own_leaf_nodes<GroupId: traits::GroupId<V_TEST>, LeafNode: traits::LeafNode<V_TEST>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Vec<LeafNode>, Self::Error> {
+        todo!()
+    }
+
+    fn queued_proposals<
+        GroupId: traits::GroupId<V_TEST>,
+        ProposalRef: traits::ProposalRef<V_TEST>,
+        QueuedProposal: traits::QueuedProposal<V_TEST>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Vec<(ProposalRef, QueuedProposal)>, Self::Error> {
+        todo!()
+    }
+
+    fn group_ids<GroupId: traits::GroupId<V_TEST> + Entity<V_TEST>>(
+        &self,
+    ) -> Result<Vec<GroupId>, Self::Error> {
+        self.read_keys(GROUP_LABELS)
+    }
+
+    fn key_package_refs<KeyPackageRef: traits::HashReference<V_TEST> + Entity<V_TEST>>(
+        &self,
+    ) -> Result<Vec<KeyPackageRef>, Self::Error> {
+        self.read_keys(&[KEY_PACKAGE_LABEL])
+    }
+
+    fn encryption_keys<EncryptionKey: traits::EncryptionKey<V_TEST> + Entity<V_TEST>>(
+        &self,
+    ) -> Result<Vec<EncryptionKey>, Self::Error> {
+        self.read_keys(&[ENCRYPTION_KEY_PAIR_LABEL])
+    }
+}
+
+impl StorageWriter<V_TEST> for MemoryStorage {
+    fn write_encryption_key_pair<
+        EncryptionKey: traits::EncryptionKey<V_TEST>,
+        HpkeKeyPair: traits::HpkeKeyPair<V_TEST>,
+    >(
+        &self,
+        public_key: &EncryptionKey,
+        key_pair: &HpkeKeyPair,
+    ) -> Result<(), Self::Error> {
+        self.write::<V_TEST>(
+            ENCRYPTION_KEY_PAIR_LABEL,
+            &serde_json::to_vec(&public_key).unwrap(),
+            serde_json::to_vec(&key_pair).unwrap(),
+        )
+    }
+
     fn write_key_package<
         HashReference: traits::HashReference<V_TEST>,
         KeyPackage: traits::KeyPackage<V_TEST>,
@@ -165,152 +368,133 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn queued_proposal_refs<
-        GroupId: traits::GroupId<V_TEST>,
-        ProposalRef: traits::ProposalRef<V_TEST>,
-    >(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Vec<ProposalRef>, Self::Error> {
-        todo!()
-    }
-
-    fn tree<GroupId: traits::GroupId<V_TEST>, TreeSync: traits::TreeSync<V_TEST>>(
+    fn delete_signature_key_pair<SignaturePublicKeuy: traits::SignaturePublicKey<V_TEST>>(
         &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<TreeSync>, Self::Error> {
+        _public_key: &SignaturePublicKeuy,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn group_context<
-        GroupId: traits::GroupId<V_TEST>,
-        GroupContext: traits::GroupContext<V_TEST>,
-    >(
+    fn delete_encryption_key_pair<EncryptionKey: traits::EncryptionKey<V_TEST>>(
         &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<GroupContext>, Self::Error> {
+        _public_key: &EncryptionKey,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn interim_transcript_hash<
+    fn delete_encryption_epoch_key_pairs<
         GroupId: traits::GroupId<V_TEST>,
-        InterimTranscriptHash: traits::InterimTranscriptHash<V_TEST>,
+        EpochKey: traits::EpochKey<V_TEST>,
     >(
         &self,
         _group_id: &GroupId,
-    ) -> Result<Option<InterimTranscriptHash>, Self::Error> {
+        _epoch: &EpochKey,
+        _leaf_index: u32,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn confirmation_tag<
-        GroupId: traits::GroupId<V_TEST>,
-        ConfirmationTag: traits::ConfirmationTag<V_TEST>,
-    >(
+    fn delete_key_package<KeyPackageRef: traits::HashReference<V_TEST>>(
         &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<ConfirmationTag>, Self::Error> {
+        _hash_ref: &KeyPackageRef,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn signature_key_pair<
-        SignaturePublicKey: traits::SignaturePublicKey<V_TEST>,
-        SignatureKeyPair: traits::SignatureKeyPair<V_TEST>,
-    >(
+    fn delete_psk<PskKey: traits::PskId<V_TEST>>(
         &self,
-        _public_key: &SignaturePublicKey,
-    ) -> Result<Option<SignatureKeyPair>, Self::Error> {
+        _psk_id: &PskKey,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn encryption_key_pair<
-        HpkeKeyPair: traits::HpkeKeyPair<V_TEST>,
-        EncryptionKey: traits::EncryptionKey<V_TEST>,
+    fn write_group_state<
+        GroupState: traits::GroupState<V_TEST>,
+        GroupId: traits::GroupId<V_TEST>,
     >(
         &self,
-        _public_key: &EncryptionKey,
-    ) -> Result<Option<HpkeKeyPair>, Self::Error> {
+        _group_id: &GroupId,
+        _group_state: &GroupState,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn psk<PskBundle: traits::PskBundle<V_TEST>, PskId: traits::PskId<V_TEST>>(
+    fn delete_group_state<GroupId: traits::GroupId<V_TEST>>(
         &self,
-        _psk_id: &PskId,
-    ) -> Result<Option<PskBundle>, Self::Error> {
+        _group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn delete_signature_key_pair<SignaturePublicKeuy: traits::SignaturePublicKey<V_TEST>>(
+    fn write_member_stats<
+        GroupId: traits::GroupId<V_TEST>,
+        MemberStats: traits::MemberStats<V_TEST>,
+    >(
         &self,
-        _public_key: &SignaturePublicKeuy,
+        _group_id: &GroupId,
+        _member_stats: &MemberStats,
     ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn delete_encryption_key_pair<EncryptionKey: traits::EncryptionKey<V_TEST>>(
+    fn delete_member_stats<GroupId: traits::GroupId<V_TEST>>(
         &self,
-        _public_key: &EncryptionKey,
+        _group_id: &GroupId,
     ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn delete_encryption_epoch_key_pairs<
+    fn write_used_external_inits<
         GroupId: traits::GroupId<V_TEST>,
-        EpochKey: traits::EpochKey<V_TEST>,
+        UsedExternalInits: traits::UsedExternalInits<V_TEST>,
     >(
         &self,
         _group_id: &GroupId,
-        _epoch: &EpochKey,
-        _leaf_index: u32,
-    ) -> Result<(), Self::Error> {
-        todo!()
-    }
-
-    fn delete_key_package<KeyPackageRef: traits::HashReference<V_TEST>>(
-        &self,
-        _hash_ref: &KeyPackageRef,
+        _used_external_inits: &UsedExternalInits,
     ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn delete_psk<PskKey: traits::PskId<V_TEST>>(
+    fn delete_used_external_inits<GroupId: traits::GroupId<V_TEST>>(
         &self,
-        _psk_id: &PskKey,
+        _group_id: &GroupId,
     ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn group_state<GroupState: traits::GroupState<V_TEST>, GroupId: traits::GroupId<V_TEST>>(
+    fn write_replica_sequence<
+        GroupId: traits::GroupId<V_TEST>,
+        ReplicaSequence: traits::ReplicaSequence<V_TEST>,
+    >(
         &self,
         _group_id: &GroupId,
-    ) -> Result<Option<GroupState>, Self::Error> {
+        _replica_sequence: &ReplicaSequence,
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn write_group_state<
-        GroupState: traits::GroupState<V_TEST>,
+    fn write_light_group<
         GroupId: traits::GroupId<V_TEST>,
+        LightGroup: traits::LightGroup<V_TEST>,
     >(
         &self,
         _group_id: &GroupId,
-        _group_state: &GroupState,
+        _light_group: &LightGroup,
     ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn delete_group_state<GroupId: traits::GroupId<V_TEST>>(
+    fn delete_replica_sequence<GroupId: traits::GroupId<V_TEST>>(
         &self,
         _group_id: &GroupId,
     ) -> Result<(), Self::Error> {
         todo!()
     }
 
-    fn message_secrets<
-        GroupId: traits::GroupId<V_TEST>,
-        MessageSecrets: traits::MessageSecrets<V_TEST>,
-    >(
+    fn delete_light_group<GroupId: traits::GroupId<V_TEST>>(
         &self,
         _group_id: &GroupId,
-    ) -> Result<Option<MessageSecrets>, Self::Error> {
+    ) -> Result<(), Self::Error> {
         todo!()
     }
 
@@ -332,16 +516,6 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn resumption_psk_store<
-        GroupId: traits::GroupId<V_TEST>,
-        ResumptionPskStore: traits::ResumptionPskStore<V_TEST>,
-    >(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<ResumptionPskStore>, Self::Error> {
-        todo!()
-    }
-
     fn write_resumption_psk_store<
         GroupId: traits::GroupId<V_TEST>,
         ResumptionPskStore: traits::ResumptionPskStore<V_TEST>,
@@ -360,16 +534,6 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn own_leaf_index<
-        GroupId: traits::GroupId<V_TEST>,
-        LeafNodeIndex: traits::LeafNodeIndex<V_TEST>,
-    >(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<LeafNodeIndex>, Self::Error> {
-        todo!()
-    }
-
     fn write_own_leaf_index<
         GroupId: traits::GroupId<V_TEST>,
         LeafNodeIndex: traits::LeafNodeIndex<V_TEST>,
@@ -388,16 +552,6 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn group_epoch_secrets<
-        GroupId: traits::GroupId<V_TEST>,
-        GroupEpochSecrets: traits::GroupEpochSecrets<V_TEST>,
-    >(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<GroupEpochSecrets>, Self::Error> {
-        todo!()
-    }
-
     fn write_group_epoch_secrets<
         GroupId: traits::GroupId<V_TEST>,
         GroupEpochSecrets: traits::GroupEpochSecrets<V_TEST>,
@@ -426,16 +580,6 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn mls_group_join_config<
-        GroupId: traits::GroupId<V_TEST>,
-        MlsGroupJoinConfig: traits::MlsGroupJoinConfig<V_TEST>,
-    >(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error> {
-        todo!()
-    }
-
     fn write_mls_join_config<
         GroupId: traits::GroupId<V_TEST>,
         MlsGroupJoinConfig: traits::MlsGroupJoinConfig<V_TEST>,
@@ -447,13 +591,6 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn own_leaf_nodes<GroupId: traits::GroupId<V_TEST>, LeafNode: traits::LeafNode<V_TEST>>(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Vec<LeafNode>, Self::Error> {
-        todo!()
-    }
-
     fn append_own_leaf_node<
         GroupId: traits::GroupId<V_TEST>,
         LeafNode: traits::LeafNode<V_TEST>,
@@ -465,17 +602,6 @@ impl StorageProvider<V_TEST> for MemoryStorage {
         todo!()
     }
 
-    fn queued_proposals<
-        GroupId: traits::GroupId<V_TEST>,
-        ProposalRef: traits::ProposalRef<V_TEST>,
-        QueuedProposal: traits::QueuedProposal<V_TEST>,
-    >(
-        &self,
-        _group_id: &GroupId,
-    ) -> Result<Vec<(ProposalRef, QueuedProposal)>, Self::Error> {
-        todo!()
-    }
-
     fn remove_proposal<
         GroupId: traits::GroupId<V_TEST>,
         ProposalRef: traits::ProposalRef<V_TEST>,
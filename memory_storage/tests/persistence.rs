@@ -0,0 +1,92 @@
+use openmls_memory_storage::{persistence::PersistenceError, MemoryStorage};
+use openmls_rust_crypto::RustCrypto;
+
+fn store() -> MemoryStorage {
+    let storage = MemoryStorage::default();
+    let mut values = storage.values.write().unwrap();
+    values.insert(b"key 1".to_vec(), b"value 1".to_vec());
+    values.insert(b"key 2".to_vec(), b"value 2".to_vec());
+    drop(values);
+    storage
+}
+
+#[test]
+fn writer_round_trip() {
+    let storage = store();
+    let mut persisted = Vec::new();
+    storage.save_to_writer(&mut persisted).unwrap();
+
+    let mut loaded = MemoryStorage::default();
+    loaded.load_from_reader(persisted.as_slice()).unwrap();
+    assert_eq!(
+        *loaded.values.read().unwrap(),
+        *storage.values.read().unwrap()
+    );
+
+    // Files written before the format was versioned can still be loaded.
+    let mut loaded = MemoryStorage::default();
+    loaded
+        .load_from_reader(br#"{"values": {"a2V5": "dmFsdWU="}}"#.as_slice())
+        .unwrap();
+    assert_eq!(
+        loaded.values.read().unwrap().get(b"key".as_slice()),
+        Some(&b"value".to_vec())
+    );
+
+    // Nothing is loaded if a single value can't be decoded.
+    let mut loaded = MemoryStorage::default();
+    assert!(matches!(
+        loaded.load_from_reader(
+            br#"{"values": {"a2V5": "dmFsdWU=", "a2V5IDI=": "not base64!"}}"#.as_slice()
+        ),
+        Err(PersistenceError::Encoding(_))
+    ));
+    assert!(loaded.values.read().unwrap().is_empty());
+
+    // Files written with a newer format are rejected.
+    assert!(matches!(
+        MemoryStorage::default().load_from_reader(br#"{"version": 65535}"#.as_slice()),
+        Err(PersistenceError::UnsupportedVersion(65535))
+    ));
+}
+
+#[test]
+fn encrypted_round_trip() {
+    let crypto = RustCrypto::default();
+    let key = [7u8; 32];
+    let storage = store();
+    let mut persisted = Vec::new();
+    storage
+        .save_to_writer_encrypted(&mut persisted, &crypto, &crypto, &key)
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&persisted).contains("dmFsdWUgMQ=="));
+
+    let mut loaded = MemoryStorage::default();
+    loaded
+        .load_from_reader_encrypted(persisted.as_slice(), &crypto, &key)
+        .unwrap();
+    assert_eq!(
+        *loaded.values.read().unwrap(),
+        *storage.values.read().unwrap()
+    );
+
+    // A wrong key or a missing key fails, and nothing is loaded.
+    let mut loaded = MemoryStorage::default();
+    assert!(matches!(
+        loaded.load_from_reader_encrypted(persisted.as_slice(), &crypto, &[8u8; 32]),
+        Err(PersistenceError::Crypto(_))
+    ));
+    assert!(matches!(
+        loaded.load_from_reader(persisted.as_slice()),
+        Err(PersistenceError::Encrypted)
+    ));
+    assert!(loaded.values.read().unwrap().is_empty());
+
+    // A plaintext store can't be loaded as an encrypted one.
+    let mut plaintext = Vec::new();
+    storage.save_to_writer(&mut plaintext).unwrap();
+    assert!(matches!(
+        loaded.load_from_reader_encrypted(plaintext.as_slice(), &crypto, &key),
+        Err(PersistenceError::NotEncrypted)
+    ));
+}
@@ -1,7 +1,7 @@
 use openmls_memory_storage::MemoryStorage;
 use openmls_traits::storage::{
     traits::{self},
-    Entity, Key, StorageProvider, CURRENT_VERSION,
+    Entity, Key, StorageReader, StorageWriter, CURRENT_VERSION,
 };
 use serde::{Deserialize, Serialize};
 
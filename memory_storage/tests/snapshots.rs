@@ -0,0 +1,75 @@
+use openmls_memory_storage::MemoryStorage;
+use openmls_traits::storage::{
+    traits::{self},
+    Entity, Key, StorageReader, StorageWriter, CURRENT_VERSION,
+};
+use serde::{Deserialize, Serialize};
+
+// Test types
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct TestGroupId(Vec<u8>);
+impl traits::GroupId<CURRENT_VERSION> for TestGroupId {}
+impl Key<CURRENT_VERSION> for TestGroupId {}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+struct ProposalRef(usize);
+impl traits::ProposalRef<CURRENT_VERSION> for ProposalRef {}
+impl Key<CURRENT_VERSION> for ProposalRef {}
+impl Entity<CURRENT_VERSION> for ProposalRef {}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct Proposal(Vec<u8>);
+impl traits::QueuedProposal<CURRENT_VERSION> for Proposal {}
+impl Entity<CURRENT_VERSION> for Proposal {}
+
+fn queued_refs(storage: &MemoryStorage, group_id: &TestGroupId) -> Vec<ProposalRef> {
+    storage.queued_proposal_refs(group_id).unwrap()
+}
+
+/// Roll back writes and deletes made after a snapshot
+#[test]
+fn snapshot_rollback() {
+    let group_id = TestGroupId(b"TestGroupId".to_vec());
+    let storage = MemoryStorage::default();
+    assert!(!storage.rollback());
+
+    storage
+        .queue_proposal(&group_id, &ProposalRef(0), &Proposal(b"0".to_vec()))
+        .unwrap();
+    let values_before = storage.values.read().unwrap().clone();
+
+    storage.snapshot();
+    assert!(storage.has_snapshot());
+    storage
+        .queue_proposal(&group_id, &ProposalRef(1), &Proposal(b"1".to_vec()))
+        .unwrap();
+    storage.remove_proposal(&group_id, &ProposalRef(0)).unwrap();
+    // A nested snapshot keeps the outer one.
+    storage.snapshot();
+    storage
+        .clear_proposal_queue::<TestGroupId, ProposalRef>(&group_id)
+        .unwrap();
+    assert!(queued_refs(&storage, &group_id).is_empty());
+
+    assert!(storage.rollback());
+    assert!(!storage.has_snapshot());
+    assert_eq!(vec![ProposalRef(0)], queued_refs(&storage, &group_id));
+    assert_eq!(values_before, *storage.values.read().unwrap());
+}
+
+/// Keep the changes made after a snapshot when releasing it
+#[test]
+fn snapshot_release() {
+    let group_id = TestGroupId(b"TestGroupId".to_vec());
+    let storage = MemoryStorage::default();
+
+    storage.snapshot();
+    storage
+        .queue_proposal(&group_id, &ProposalRef(0), &Proposal(b"0".to_vec()))
+        .unwrap();
+    storage.release_snapshot();
+
+    assert!(!storage.has_snapshot());
+    assert!(!storage.rollback());
+    assert_eq!(vec![ProposalRef(0)], queued_refs(&storage, &group_id));
+}
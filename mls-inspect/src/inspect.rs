@@ -0,0 +1,456 @@
+//! Decoding and pretty-printing of MLS artifacts.
+//!
+//! The [`Inspector`] only uses the public API of OpenMLS. It prints the
+//! (unverified) content of an artifact and collects the structural problems
+//! it finds along the way, e.g. invalid signatures, malformed ratchet trees or
+//! trailing bytes.
+
+use openmls::prelude::{
+    tls_codec::Deserialize, Capabilities, ContentType, Credential, Extension, Extensions, GroupId,
+    KeyPackageIn, LeafNodeIn, MlsMessageBodyIn, MlsMessageIn, NodeIn, PrivateMessageIn,
+    ProtocolVersion, PublicMessageIn, RatchetTreeIn, Sender, VerifiableGroupInfo, Welcome,
+};
+use openmls_rust_crypto::RustCrypto;
+use openmls_traits::types::Ciphersuite;
+
+/// The kind of artifact to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    /// An `MLSMessage`, which wraps any of the other artifacts except for
+    /// ratchet trees.
+    Message,
+    /// A bare `KeyPackage`.
+    KeyPackage,
+    /// A bare `Welcome`.
+    Welcome,
+    /// A bare `GroupInfo`.
+    GroupInfo,
+    /// A bare ratchet tree, as sent out of band.
+    RatchetTree,
+}
+
+/// The result of inspecting an artifact.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// The pretty-printed artifact.
+    pub text: String,
+    /// The structural problems that were found.
+    pub problems: Vec<String>,
+}
+
+/// Pretty-prints MLS artifacts and collects structural problems.
+pub struct Inspector {
+    crypto: RustCrypto,
+    redact: bool,
+    depth: usize,
+    report: Report,
+}
+
+impl Inspector {
+    /// Creates a new inspector. If `redact` is set, opaque payloads, such as
+    /// credentials, are not printed.
+    pub fn new(redact: bool) -> Self {
+        Self {
+            crypto: RustCrypto::default(),
+            redact,
+            depth: 0,
+            report: Report::default(),
+        }
+    }
+
+    /// Decodes the `bytes` as artifact of the given `kind` and returns the
+    /// report. Fails if the bytes can't be decoded.
+    pub fn inspect(mut self, kind: ArtifactKind, bytes: &[u8]) -> Result<Report, tls_codec::Error> {
+        let mut remaining = bytes;
+        match kind {
+            ArtifactKind::Message => {
+                let message = MlsMessageIn::tls_deserialize(&mut remaining)?;
+                self.message(message);
+            }
+            ArtifactKind::KeyPackage => {
+                let key_package = KeyPackageIn::tls_deserialize(&mut remaining)?;
+                self.key_package(key_package);
+            }
+            ArtifactKind::Welcome => {
+                let welcome = Welcome::tls_deserialize(&mut remaining)?;
+                self.welcome(&welcome);
+            }
+            ArtifactKind::GroupInfo => {
+                let group_info = VerifiableGroupInfo::tls_deserialize(&mut remaining)?;
+                self.group_info(&group_info);
+            }
+            ArtifactKind::RatchetTree => {
+                let ratchet_tree = RatchetTreeIn::tls_deserialize(&mut remaining)?;
+                self.ratchet_tree(&ratchet_tree, None);
+            }
+        }
+
+        if !remaining.is_empty() {
+            self.problem(format!(
+                "{} trailing bytes after the artifact",
+                remaining.len()
+            ));
+        }
+
+        Ok(self.report)
+    }
+
+    fn message(&mut self, message: MlsMessageIn) {
+        self.section("MLSMessage");
+        self.field("wire format", format!("{:?}", message.wire_format()));
+        match message.extract() {
+            MlsMessageBodyIn::PublicMessage(public_message) => self.public_message(&public_message),
+            MlsMessageBodyIn::PrivateMessage(private_message) => {
+                self.private_message(&private_message)
+            }
+            MlsMessageBodyIn::Welcome(welcome) => self.welcome(&welcome),
+            MlsMessageBodyIn::GroupInfo(group_info) => self.group_info(&group_info),
+            MlsMessageBodyIn::KeyPackage(key_package) => self.key_package(key_package),
+        }
+        self.end_section();
+    }
+
+    fn public_message(&mut self, message: &PublicMessageIn) {
+        self.section("PublicMessage");
+        self.group_id(message.group_id());
+        self.field("epoch", message.epoch().as_u64());
+        self.field("content type", format!("{:?}", message.content_type()));
+        self.sender(message.sender());
+        self.field(
+            "confirmation tag",
+            if message.confirmation_tag().is_some() {
+                "present"
+            } else {
+                "absent"
+            },
+        );
+
+        let content_type = message.content_type();
+        if content_type == ContentType::Application {
+            self.problem("application messages must be sent as PrivateMessage");
+        }
+        if content_type == ContentType::Commit && message.confirmation_tag().is_none() {
+            self.problem("commit without confirmation tag");
+        }
+        match message.sender() {
+            Sender::External(_) | Sender::NewMemberProposal
+                if content_type != ContentType::Proposal =>
+            {
+                self.problem("non-member sender may only send proposals");
+            }
+            Sender::NewMemberCommit if content_type != ContentType::Commit => {
+                self.problem("new member commit sender may only send commits");
+            }
+            _ => (),
+        }
+        self.end_section();
+    }
+
+    fn private_message(&mut self, message: &PrivateMessageIn) {
+        self.section("PrivateMessage");
+        self.group_id(message.group_id());
+        self.field("epoch", message.epoch().as_u64());
+        self.field("content type", format!("{:?}", message.content_type()));
+        self.end_section();
+    }
+
+    fn welcome(&mut self, welcome: &Welcome) {
+        self.section("Welcome");
+        self.ciphersuite(welcome.ciphersuite());
+        self.section(&format!(
+            "encrypted group secrets ({})",
+            welcome.secrets().len()
+        ));
+        let mut new_members = Vec::new();
+        for secrets in welcome.secrets() {
+            let new_member = secrets.new_member();
+            self.bytes("new member", new_member.as_slice());
+            if new_members.contains(&new_member) {
+                self.problem(format!(
+                    "duplicate group secrets for key package {}",
+                    hex(new_member.as_slice())
+                ));
+            }
+            new_members.push(new_member);
+        }
+        self.end_section();
+        if welcome.secrets().is_empty() {
+            self.problem("welcome without encrypted group secrets");
+        }
+        self.end_section();
+    }
+
+    fn group_info(&mut self, group_info: &VerifiableGroupInfo) {
+        self.section("GroupInfo (unverified)");
+        let ciphersuite = group_info.ciphersuite();
+        self.ciphersuite(ciphersuite);
+        self.group_id(group_info.group_id());
+        self.field("epoch", group_info.epoch().as_u64());
+        self.field("signer", group_info.signer().u32());
+        self.extensions("extensions", group_info.extensions());
+
+        match group_info.extensions().ratchet_tree() {
+            Some(extension) => {
+                let ratchet_tree = extension.ratchet_tree();
+                self.ratchet_tree(ratchet_tree, Some((ciphersuite, group_info.group_id())));
+
+                let signer_position = group_info.signer().u32() as usize * 2;
+                if !matches!(
+                    ratchet_tree.nodes().get(signer_position),
+                    Some(Some(NodeIn::LeafNode(_)))
+                ) {
+                    self.problem("the signer of the group info is not in the ratchet tree");
+                }
+            }
+            None => self.field("ratchet tree", "not included"),
+        }
+        if group_info.extensions().external_pub().is_none() {
+            self.field(
+                "external commits",
+                "not possible (no external_pub extension)",
+            );
+        }
+        self.end_section();
+    }
+
+    fn key_package(&mut self, key_package: KeyPackageIn) {
+        self.section("KeyPackage (unverified)");
+        self.field(
+            "protocol version",
+            format!("{:?}", key_package.protocol_version()),
+        );
+        self.ciphersuite(key_package.ciphersuite());
+        self.bytes("init key", key_package.init_key().key().as_slice());
+        self.leaf_node(key_package.leaf_node());
+        self.extensions("extensions", key_package.extensions());
+
+        if let Err(e) = key_package.validate(&self.crypto, ProtocolVersion::Mls10) {
+            self.problem(format!("invalid key package: {e}"));
+        }
+        self.end_section();
+    }
+
+    fn ratchet_tree(
+        &mut self,
+        ratchet_tree: &RatchetTreeIn,
+        context: Option<(Ciphersuite, &GroupId)>,
+    ) {
+        self.section("RatchetTree (unverified)");
+        let nodes = ratchet_tree.nodes();
+        self.field("nodes", nodes.len());
+        for (position, node) in nodes.iter().enumerate() {
+            let is_leaf_position = position % 2 == 0;
+            match node {
+                None => self.field(&format!("node {position}"), "blank"),
+                Some(NodeIn::LeafNode(leaf_node)) => {
+                    self.section(&format!("node {position}: leaf {}", position / 2));
+                    self.leaf_node(leaf_node);
+                    self.end_section();
+                    if !is_leaf_position {
+                        self.problem(format!("leaf node at parent position {position}"));
+                    }
+                }
+                Some(NodeIn::ParentNode(parent_node)) => {
+                    self.section(&format!("node {position}: parent"));
+                    self.bytes("encryption key", parent_node.encryption_key().as_slice());
+                    self.bytes("parent hash", parent_node.parent_hash());
+                    let unmerged_leaves: Vec<u32> = parent_node
+                        .unmerged_leaves()
+                        .iter()
+                        .map(|leaf_index| leaf_index.u32())
+                        .collect();
+                    self.field("unmerged leaves", format!("{unmerged_leaves:?}"));
+                    self.end_section();
+                    if is_leaf_position {
+                        self.problem(format!("parent node at leaf position {position}"));
+                    }
+                }
+            }
+        }
+
+        match nodes.last() {
+            None => self.problem("empty ratchet tree"),
+            Some(None) => self.problem("ratchet tree with trailing blank nodes"),
+            Some(Some(_)) => (),
+        }
+
+        if let Some((ciphersuite, group_id)) = context {
+            if let Err(e) = ratchet_tree
+                .clone()
+                .into_verified(ciphersuite, &self.crypto, group_id)
+            {
+                self.problem(format!("invalid ratchet tree: {e}"));
+            }
+        }
+        self.end_section();
+    }
+
+    fn leaf_node(&mut self, leaf_node: &LeafNodeIn) {
+        self.section("LeafNode");
+        self.bytes("encryption key", leaf_node.encryption_key().as_slice());
+        self.bytes("signature key", leaf_node.signature_key().as_slice());
+        self.credential(leaf_node.credential());
+        self.capabilities(leaf_node.capabilities());
+        self.extensions("extensions", leaf_node.extensions());
+        self.end_section();
+    }
+
+    fn credential(&mut self, credential: &Credential) {
+        self.field(
+            "credential type",
+            format!("{:?}", credential.credential_type()),
+        );
+        self.opaque("credential", credential.serialized_content());
+    }
+
+    fn capabilities(&mut self, capabilities: &Capabilities) {
+        self.section("Capabilities");
+        self.field("versions", format!("{:?}", capabilities.versions()));
+        self.field("ciphersuites", format!("{:?}", capabilities.ciphersuites()));
+        self.field("extensions", format!("{:?}", capabilities.extensions()));
+        self.field("proposals", format!("{:?}", capabilities.proposals()));
+        self.field("credentials", format!("{:?}", capabilities.credentials()));
+        self.end_section();
+    }
+
+    fn extensions(&mut self, name: &str, extensions: &Extensions) {
+        let extension_types: Vec<String> = extensions
+            .iter()
+            .map(Extension::extension_type)
+            .map(|extension_type| format!("{extension_type:?}"))
+            .collect();
+        self.field(name, format!("[{}]", extension_types.join(", ")));
+    }
+
+    fn sender(&mut self, sender: &Sender) {
+        let sender = match sender {
+            Sender::Member(leaf_index) => format!("member {}", leaf_index.u32()),
+            Sender::External(sender_index) => format!("external sender {}", sender_index.index()),
+            Sender::NewMemberProposal => "new member (proposal)".to_owned(),
+            Sender::NewMemberCommit => "new member (commit)".to_owned(),
+        };
+        self.field("sender", sender);
+    }
+
+    fn ciphersuite(&mut self, ciphersuite: Ciphersuite) {
+        self.field("ciphersuite", format!("{ciphersuite:?}"));
+    }
+
+    fn group_id(&mut self, group_id: &GroupId) {
+        self.bytes("group id", group_id.as_slice());
+    }
+
+    // === Output ===
+
+    fn section(&mut self, name: &str) {
+        self.line(format!("{name}:"));
+        self.depth += 1;
+    }
+
+    fn end_section(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn field(&mut self, name: &str, value: impl std::fmt::Display) {
+        self.line(format!("{name}: {value}"));
+    }
+
+    /// Prints public bytes, such as keys and hashes.
+    fn bytes(&mut self, name: &str, bytes: &[u8]) {
+        self.field(name, hex(bytes));
+    }
+
+    /// Prints bytes that may contain identifying information, unless
+    /// redaction is enabled.
+    fn opaque(&mut self, name: &str, bytes: &[u8]) {
+        if self.redact {
+            self.field(name, format!("<redacted, {} bytes>", bytes.len()));
+        } else {
+            self.bytes(name, bytes);
+        }
+    }
+
+    fn line(&mut self, line: String) {
+        for _ in 0..self.depth {
+            self.report.text.push_str("  ");
+        }
+        self.report.text.push_str(&line);
+        self.report.text.push('\n');
+    }
+
+    fn problem(&mut self, problem: impl Into<String>) {
+        self.report.problems.push(problem.into());
+    }
+}
+
+/// Encodes the `bytes` as lowercase hex string.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use openmls::prelude::{tls_codec::Serialize, *};
+    use openmls_basic_credential::SignatureKeyPair;
+    use openmls_rust_crypto::OpenMlsRustCrypto;
+
+    use super::*;
+
+    const CIPHERSUITE: Ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+
+    fn key_package_message() -> Vec<u8> {
+        let provider = OpenMlsRustCrypto::default();
+        let signer = SignatureKeyPair::new(CIPHERSUITE.signature_algorithm()).unwrap();
+        let credential_with_key = CredentialWithKey {
+            credential: BasicCredential::new(b"Alice".to_vec()).into(),
+            signature_key: signer.public().into(),
+        };
+        let key_package_bundle = KeyPackage::builder()
+            .build(CIPHERSUITE, &provider, &signer, credential_with_key)
+            .unwrap();
+
+        MlsMessageOut::from(key_package_bundle.key_package().clone())
+            .tls_serialize_detached()
+            .unwrap()
+    }
+
+    #[test]
+    fn key_package() {
+        let bytes = key_package_message();
+
+        let report = Inspector::new(true)
+            .inspect(ArtifactKind::Message, &bytes)
+            .unwrap();
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+        assert!(report.text.contains("KeyPackage (unverified):"));
+        assert!(report.text.contains("credential: <redacted, "));
+
+        // A modified signature is flagged.
+        let mut modified = bytes.clone();
+        *modified.last_mut().unwrap() ^= 0xff;
+        let report = Inspector::new(true)
+            .inspect(ArtifactKind::Message, &modified)
+            .unwrap();
+        assert_eq!(report.problems.len(), 1);
+
+        // Trailing bytes are flagged.
+        let mut extended = bytes;
+        extended.push(0);
+        let report = Inspector::new(false)
+            .inspect(ArtifactKind::Message, &extended)
+            .unwrap();
+        assert_eq!(
+            report.problems,
+            vec!["1 trailing bytes after the artifact".to_owned()]
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = key_package_message();
+
+        assert!(Inspector::new(true)
+            .inspect(ArtifactKind::Message, &bytes[..bytes.len() / 2])
+            .is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! # mls-inspect
+//!
+//! A developer tool that decodes MLS artifacts and pretty-prints them, e.g.
+//! to debug interop reports. See the README for usage.
+
+use std::{
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use clap::{Parser, ValueEnum};
+
+mod inspect;
+
+use inspect::{ArtifactKind, Inspector};
+
+#[derive(Parser)]
+#[clap(about = "Decode and pretty-print MLS artifacts")]
+struct Opts {
+    /// The file to read the artifact from. Reads from stdin if omitted or `-`.
+    #[clap(default_value = "-")]
+    input: String,
+
+    /// The kind of artifact.
+    #[clap(short, long, value_enum, default_value = "message")]
+    kind: Kind,
+
+    /// The encoding of the input.
+    #[clap(short, long, value_enum, default_value = "auto")]
+    format: Format,
+
+    /// Print opaque payloads, such as credentials, instead of redacting them.
+    #[clap(long)]
+    no_redact: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Kind {
+    /// An MLSMessage, wrapping a message, KeyPackage, Welcome or GroupInfo.
+    Message,
+    /// A bare KeyPackage.
+    KeyPackage,
+    /// A bare Welcome.
+    Welcome,
+    /// A bare GroupInfo.
+    GroupInfo,
+    /// A bare ratchet tree.
+    RatchetTree,
+}
+
+impl From<Kind> for ArtifactKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Message => ArtifactKind::Message,
+            Kind::KeyPackage => ArtifactKind::KeyPackage,
+            Kind::Welcome => ArtifactKind::Welcome,
+            Kind::GroupInfo => ArtifactKind::GroupInfo,
+            Kind::RatchetTree => ArtifactKind::RatchetTree,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Detect hex and base64, and fall back to raw bytes.
+    Auto,
+    /// Raw bytes.
+    Raw,
+    /// Hex encoded bytes.
+    Hex,
+    /// Base64 encoded bytes.
+    Base64,
+}
+
+fn main() -> ExitCode {
+    let opts = Opts::parse();
+
+    let input = match read_input(&opts.input) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("Error reading {}: {e}", opts.input);
+            return ExitCode::from(2);
+        }
+    };
+    let bytes = match decode_input(input, opts.format) {
+        Some(bytes) => bytes,
+        None => {
+            eprintln!("Error decoding the input");
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = match Inspector::new(!opts.no_redact).inspect(opts.kind.into(), &bytes) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error decoding the artifact: {e:?}");
+            return ExitCode::from(2);
+        }
+    };
+
+    print!("{}", report.text);
+    if report.problems.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+
+    println!("\nProblems:");
+    for problem in &report.problems {
+        println!("  - {problem}");
+    }
+    ExitCode::from(1)
+}
+
+fn read_input(input: &str) -> io::Result<Vec<u8>> {
+    if input == "-" {
+        let mut buffer = Vec::new();
+        io::stdin().read_to_end(&mut buffer)?;
+        Ok(buffer)
+    } else {
+        fs::read(input)
+    }
+}
+
+fn decode_input(input: Vec<u8>, format: Format) -> Option<Vec<u8>> {
+    match format {
+        Format::Raw => Some(input),
+        Format::Hex => decode_hex(&text(&input)?),
+        Format::Base64 => STANDARD.decode(text(&input)?).ok(),
+        Format::Auto => {
+            let Some(encoded) = text(&input) else {
+                return Some(input);
+            };
+            decode_hex(&encoded)
+                .or_else(|| STANDARD.decode(&encoded).ok())
+                .or(Some(input))
+        }
+    }
+}
+
+/// Returns the input as text without whitespace, if it is valid UTF-8.
+fn text(input: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(input).ok()?;
+    Some(text.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.is_empty() || text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -71,7 +71,8 @@ fn create_welcome(c: &mut Criterion, provider: &impl OpenMlsProvider) {
                         let mls_group_create_config = MlsGroupCreateConfig::builder()
                             .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
                             .ciphersuite(ciphersuite)
-                            .build();
+                            .build()
+                            .unwrap();
 
                         // === Alice creates a group ===
                         let alice_group = MlsGroup::new(
@@ -134,7 +135,8 @@ fn join_group(c: &mut Criterion, provider: &impl OpenMlsProvider) {
                         let mls_group_create_config = MlsGroupCreateConfig::builder()
                             .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
                             .ciphersuite(ciphersuite)
-                            .build();
+                            .build()
+                            .unwrap();
 
                         // === Alice creates a group ===
                         let mut alice_group = MlsGroup::new(
@@ -214,7 +216,8 @@ fn create_commit(c: &mut Criterion, provider: &impl OpenMlsProvider) {
                         let mls_group_create_config = MlsGroupCreateConfig::builder()
                             .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
                             .ciphersuite(ciphersuite)
-                            .build();
+                            .build()
+                            .unwrap();
 
                         // === Alice creates a group ===
                         let mut alice_group = MlsGroup::new(
@@ -269,6 +272,78 @@ fn create_commit(c: &mut Criterion, provider: &impl OpenMlsProvider) {
     }
 }
 
+/// The number of members added in one commit in [`add_many_members`].
+const BULK_ADD_MEMBERS: usize = 500;
+
+fn add_many_members(c: &mut Criterion, provider: &impl OpenMlsProvider) {
+    for &ciphersuite in provider.crypto().supported_ciphersuites().iter() {
+        let mut group = c.benchmark_group("Bulk add");
+        // Every iteration adds hundreds of members, so fewer samples suffice.
+        group.sample_size(10);
+        group.bench_function(
+            format!(
+                "Add {BULK_ADD_MEMBERS} members in one commit with ciphersuite: {ciphersuite:?}"
+            ),
+            move |b| {
+                b.iter_with_setup(
+                    || {
+                        let alice_credential = BasicCredential::new("Alice".into());
+                        let alice_signer =
+                            SignatureKeyPair::new(ciphersuite.signature_algorithm()).unwrap();
+                        let alice_credential_with_key = CredentialWithKey {
+                            credential: alice_credential.into(),
+                            signature_key: alice_signer.to_public_vec().into(),
+                        };
+
+                        let key_packages = (0..BULK_ADD_MEMBERS)
+                            .map(|i| {
+                                let credential = BasicCredential::new(format!("Member {i}").into());
+                                let signer =
+                                    SignatureKeyPair::new(ciphersuite.signature_algorithm())
+                                        .unwrap();
+                                let credential_with_key = CredentialWithKey {
+                                    credential: credential.into(),
+                                    signature_key: signer.to_public_vec().into(),
+                                };
+                                KeyPackage::builder()
+                                    .build(ciphersuite, provider, &signer, credential_with_key)
+                                    .expect("An unexpected error occurred.")
+                                    .key_package()
+                                    .clone()
+                            })
+                            .collect::<Vec<KeyPackage>>();
+
+                        let mls_group_create_config = MlsGroupCreateConfig::builder()
+                            .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+                            .ciphersuite(ciphersuite)
+                            .build()
+                            .unwrap();
+
+                        // === Alice creates a group ===
+                        let alice_group = MlsGroup::new(
+                            provider,
+                            &alice_signer,
+                            &mls_group_create_config,
+                            alice_credential_with_key.clone(),
+                        )
+                        .expect("An unexpected error occurred.");
+
+                        (alice_signer, alice_group, key_packages)
+                    },
+                    |(alice_signer, mut alice_group, key_packages)| {
+                        let _welcome =
+                            match alice_group.add_members(provider, &alice_signer, &key_packages) {
+                                Ok((_, welcome, _)) => welcome,
+                                Err(e) => panic!("Could not add members to group: {e:?}"),
+                            };
+                    },
+                );
+            },
+        );
+        group.finish();
+    }
+}
+
 fn kp_bundle_rust_crypto(c: &mut Criterion) {
     let provider = &OpenMlsRustCrypto::default();
     println!("provider: RustCrypto");
@@ -281,6 +356,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     create_welcome(c, &openmls_libcrux_crypto::Provider::default());
     join_group(c, &openmls_libcrux_crypto::Provider::default());
     create_commit(c, &openmls_libcrux_crypto::Provider::default());
+    add_many_members(c, &openmls_libcrux_crypto::Provider::default());
 }
 
 criterion_group!(benches, criterion_benchmark);
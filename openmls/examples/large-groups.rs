@@ -206,7 +206,8 @@ mod generate {
         let mls_group_create_config = MlsGroupCreateConfig::builder()
             .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
             .ciphersuite(CIPHERSUITE)
-            .build();
+            .build()
+            .unwrap();
 
         // If we have a previous group/member setup, let's use it.
         // The creator is always at 0.
@@ -71,6 +71,11 @@ impl<L: Clone + Debug + Default, P: Clone + Debug + Default> StagedAbDiff<L, P>
     pub(super) fn tree_size(&self) -> TreeSize {
         self.size
     }
+
+    /// Return an iterator over the leaves that are changed by the diff.
+    pub(crate) fn leaves(&self) -> impl Iterator<Item = (LeafNodeIndex, &L)> {
+        self.leaf_diff.iter().map(|(index, leaf)| (*index, leaf))
+    }
 }
 
 /// The [`AbDiff`] represents a set of differences (i.e. a "Diff") for an
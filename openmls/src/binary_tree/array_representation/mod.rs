@@ -11,7 +11,7 @@
 //! information.
 
 // Public
-pub use treemath::LeafNodeIndex;
+pub use treemath::{LeafNodeIndex, ParentNodeIndex, TreeNodeIndex};
 
 // Crate
 pub(crate) mod diff;
@@ -19,13 +19,10 @@ pub(crate) mod sorted_iter;
 pub(crate) mod tree;
 
 pub(crate) use treemath::{
-    direct_path, is_node_in_tree, left, right, root, ParentNodeIndex, TreeNodeIndex, TreeSize,
-    MIN_TREE_SIZE,
+    copath, direct_path, is_node_in_tree, left, level, lowest_common_ancestor, parent, right, root,
+    sibling, TreeSize, MAX_TREE_SIZE, MIN_TREE_SIZE,
 };
 
-#[cfg(any(feature = "test-utils", test))]
-pub(crate) use treemath::level;
-
 mod treemath;
 
 // Tests
@@ -65,16 +65,17 @@ pub struct ParentNodeIndex(u32);
 
 impl ParentNodeIndex {
     /// Create a new `ParentNodeIndex` from a `u32`.
-    pub(crate) fn new(index: u32) -> Self {
+    pub fn new(index: u32) -> Self {
         ParentNodeIndex(index)
     }
 
     /// Return the inner value as `u32`.
-    pub(crate) fn u32(&self) -> u32 {
+    pub fn u32(&self) -> u32 {
         self.0
     }
 
-    pub(crate) fn usize(&self) -> usize {
+    /// Return the inner value as `usize`.
+    pub fn usize(&self) -> usize {
         self.0 as usize
     }
 
@@ -122,13 +123,16 @@ impl From<ParentNodeIndex> for TreeNodeIndex {
 /// TreeNodeIndex references a node in a tree.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TreeNodeIndex {
+    /// A leaf node.
     Leaf(LeafNodeIndex),
+    /// A parent node.
     Parent(ParentNodeIndex),
 }
 
 impl TreeNodeIndex {
-    /// Create a new `TreeNodeIndex` from a `u32`.
-    fn new(index: u32) -> Self {
+    /// Create a new `TreeNodeIndex` from its index in the array representation
+    /// of the tree, i.e. leaves have even and parents have odd indices.
+    pub fn new(index: u32) -> Self {
         if index % 2 == 0 {
             TreeNodeIndex::Leaf(LeafNodeIndex::from_tree_index(index))
         } else {
@@ -142,8 +146,9 @@ impl TreeNodeIndex {
         Self::new(index)
     }
 
-    /// Return the inner value as `u32`.
-    fn u32(&self) -> u32 {
+    /// Return the index of the node in the array representation of the tree
+    /// as `u32`.
+    pub fn u32(&self) -> u32 {
         match self {
             TreeNodeIndex::Leaf(index) => index.to_tree_index(),
             TreeNodeIndex::Parent(index) => index.to_tree_index(),
@@ -194,7 +199,6 @@ impl TreeSize {
     }
 
     /// Creates a new `TreeSize` from a specific leaf count
-    #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn from_leaf_count(leaf_count: u32) -> Self {
         TreeSize::new(leaf_count * 2)
     }
@@ -315,7 +319,7 @@ pub(crate) fn right(index: ParentNodeIndex) -> TreeNodeIndex {
 
 /// Warning: There is no check about the tree size and whether the parent is
 /// beyond the root
-fn parent(x: TreeNodeIndex) -> ParentNodeIndex {
+pub(crate) fn parent(x: TreeNodeIndex) -> ParentNodeIndex {
     let x = x.u32();
     let k = level(x);
     let b = (x >> (k + 1)) & 0x01;
@@ -329,7 +333,7 @@ pub(crate) fn test_parent(index: TreeNodeIndex) -> ParentNodeIndex {
     parent(index)
 }
 
-fn sibling(index: TreeNodeIndex) -> TreeNodeIndex {
+pub(crate) fn sibling(index: TreeNodeIndex) -> TreeNodeIndex {
     let p = parent(index);
     match index.u32().cmp(&p.to_tree_index()) {
         Ordering::Less => right(p),
@@ -380,7 +384,7 @@ pub(crate) fn copath(leaf_index: LeafNodeIndex, size: TreeSize) -> Vec<TreeNodeI
 
 /// Common ancestor of two leaf nodes, aka the node where their direct paths
 /// intersect.
-pub(super) fn lowest_common_ancestor(x: LeafNodeIndex, y: LeafNodeIndex) -> ParentNodeIndex {
+pub(crate) fn lowest_common_ancestor(x: LeafNodeIndex, y: LeafNodeIndex) -> ParentNodeIndex {
     let x = x.to_tree_index();
     let y = y.to_tree_index();
     let (lx, ly) = (level(x) + 1, level(y) + 1);
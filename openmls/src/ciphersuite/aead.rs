@@ -19,7 +19,7 @@ impl core::fmt::Debug for AeadKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AeadKey")
             .field("aead_mode", &self.aead_mode)
-            .field("value", &"***")
+            .field("len", &self.value.as_slice().len())
             .finish()
     }
 }
@@ -32,7 +32,9 @@ pub(crate) struct AeadNonce([u8; NONCE_BYTES]);
 #[cfg(not(feature = "crypto-debug"))]
 impl core::fmt::Debug for AeadNonce {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("AeadNonce").field(&"***").finish()
+        f.debug_struct("AeadNonce")
+            .field("len", &NONCE_BYTES)
+            .finish()
     }
 }
 
@@ -113,8 +115,13 @@ impl AeadNonce {
         Self(rng.random_array().expect("Not enough entropy."))
     }
 
+    /// Create an `AeadNonce` from a slice. Returns `None` if the slice
+    /// doesn't have the length of a nonce.
+    pub(crate) fn from_slice(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Self)
+    }
+
     /// Get a slice to the nonce value.
-    #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn as_slice(&self) -> &[u8] {
         &self.0
     }
@@ -34,9 +34,10 @@ use tls_codec::{
 };
 
 use super::Ciphersuite;
+use crate::versions::VersionBehavior;
 
-const KEY_PACKAGE_REF_LABEL: &[u8; 28] = b"MLS 1.0 KeyPackage Reference";
-const PROPOSAL_REF_LABEL: &[u8; 26] = b"MLS 1.0 Proposal Reference";
+const KEY_PACKAGE_REF_LABEL: &str = "KeyPackage Reference";
+const PROPOSAL_REF_LABEL: &str = "Proposal Reference";
 
 /// A reference to an MLS object computed as a hash of the value.
 #[derive(
@@ -77,7 +78,8 @@ pub fn make_proposal_ref(
     ciphersuite: Ciphersuite,
     crypto: &impl OpenMlsCrypto,
 ) -> Result<ProposalRef, CryptoError> {
-    HashReference::new(value, ciphersuite, crypto, PROPOSAL_REF_LABEL)
+    let label = VersionBehavior::current().label(PROPOSAL_REF_LABEL);
+    HashReference::new(value, ciphersuite, crypto, label.as_bytes())
 }
 
 /// Compute a new [`KeyPackageRef`] value for a `value`.
@@ -86,7 +88,8 @@ pub fn make_key_package_ref(
     ciphersuite: Ciphersuite,
     crypto: &impl OpenMlsCrypto,
 ) -> Result<KeyPackageRef, CryptoError> {
-    HashReference::new(value, ciphersuite, crypto, KEY_PACKAGE_REF_LABEL)
+    let label = VersionBehavior::current().label(KEY_PACKAGE_REF_LABEL);
+    HashReference::new(value, ciphersuite, crypto, label.as_bytes())
 }
 
 impl HashReference {
@@ -41,7 +41,7 @@ use openmls_traits::{
 use thiserror::Error;
 use tls_codec::{Serialize, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize, VLBytes};
 
-use super::LABEL_PREFIX;
+use crate::versions::VersionBehavior;
 
 /// HPKE labeled encryption errors.
 #[derive(Error, Debug, PartialEq, Clone)]
@@ -78,8 +78,7 @@ pub struct EncryptContext {
 impl EncryptContext {
     /// Create a new [`EncryptContext`] from a string label and the content bytes.
     pub fn new(label: &str, context: VLBytes) -> Self {
-        let label_string = LABEL_PREFIX.to_owned() + label;
-        let label = label_string.as_bytes().into();
+        let label = VersionBehavior::current().label(label).into_bytes().into();
         Self { label, context }
     }
 }
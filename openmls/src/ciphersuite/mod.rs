@@ -38,8 +38,6 @@ pub(crate) use serde::{Deserialize, Serialize};
 #[cfg(test)]
 mod tests_and_kats;
 
-const LABEL_PREFIX: &str = "MLS 1.0 ";
-
 /// A simple type for HPKE public keys using [`VLBytes`] for (de)serializing.
 pub type HpkePublicKey = VLBytes;
 pub use openmls_traits::types::HpkePrivateKey;
@@ -3,6 +3,7 @@ use std::fmt::{Debug, Formatter};
 use tls_codec::SecretVLBytes;
 
 use super::{kdf_label::KdfLabel, *};
+use crate::versions::VersionBehavior;
 
 /// A struct to contain secrets. This is to provide better visibility into where
 /// and how secrets are used and to avoid passing secrets in their raw
@@ -22,7 +23,7 @@ impl Debug for Secret {
         #[cfg(feature = "crypto-debug")]
         return ds.field("value", &self.value).finish();
         #[cfg(not(feature = "crypto-debug"))]
-        ds.field("value", &"***").finish()
+        ds.field("len", &self.value.as_slice().len()).finish()
     }
 }
 
@@ -133,7 +134,7 @@ impl Secret {
         context: &[u8],
         length: usize,
     ) -> Result<Secret, CryptoError> {
-        let full_label = format!("MLS 1.0 {}", label);
+        let full_label = VersionBehavior::current().label(label);
         log::trace!(
             "KDF expand with label \"{}\" and {:?} with context {:x?}",
             &full_label,
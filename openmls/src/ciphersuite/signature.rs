@@ -4,7 +4,8 @@
 
 use tls_codec::Serialize;
 
-use super::{LABEL_PREFIX, *};
+use super::*;
+use crate::versions::VersionBehavior;
 
 /// Signature.
 #[derive(
@@ -48,8 +49,7 @@ pub struct SignContent {
 impl SignContent {
     /// Create a new [`SignContent`] from a string label and the content bytes.
     pub fn new(label: &str, content: VLBytes) -> Self {
-        let label_string = LABEL_PREFIX.to_owned() + label;
-        let label = label_string.as_bytes().into();
+        let label = VersionBehavior::current().label(label).into_bytes().into();
         Self { label, content }
     }
 }
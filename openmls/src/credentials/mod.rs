@@ -33,7 +33,11 @@ use tls_codec::{
 #[cfg(test)]
 mod tests;
 
-use crate::{ciphersuite::SignaturePublicKey, group::Member, treesync::LeafNode};
+use crate::{
+    ciphersuite::SignaturePublicKey,
+    group::Member,
+    treesync::{node::leaf_node::is_grease, LeafNode},
+};
 use errors::*;
 
 // Public
@@ -130,6 +134,14 @@ impl From<u16> for CredentialType {
     }
 }
 
+impl CredentialType {
+    /// Returns true if this is a GREASE credential type, which has no meaning
+    /// and must be ignored.
+    pub fn is_grease(self) -> bool {
+        is_grease(self.into())
+    }
+}
+
 impl From<CredentialType> for u16 {
     fn from(value: CredentialType) -> Self {
         match value {
@@ -0,0 +1,254 @@
+//! # Application-defined extensions
+//!
+//! Extensions that are not defined by MLS are carried as
+//! [`Extension::Unknown`] with an opaque payload. The [`AppExtension`] trait
+//! gives such a payload a type: it is encoded with `tls_codec`, so variable
+//! length fields like [`VLBytes`](tls_codec::VLBytes) or `Vec<T>` get the
+//! variable-length encoding of RFC 9420. The
+//! [`app_extension!`](crate::app_extension) macro defines a struct together
+//! with its encoding and the trait implementation.
+//!
+//! Applications that register their extensions with an
+//! [`AppExtensionRegistry`] and pass it as [`ProcessingPolicy`] reject groups
+//! and commits with malformed payloads, such that every member can rely on
+//! [`Extensions::app_extension()`] succeeding for the registered types. The
+//! registry can be combined with other policies by putting them in a tuple,
+//! e.g. `(registry, ProtectedMembers::new(authorize))`.
+
+use std::collections::BTreeMap;
+
+use tls_codec::{Deserialize, Serialize, Size};
+
+use super::{errors::AppExtensionError, Extension, ExtensionType, Extensions, UnknownExtension};
+use crate::group::{GroupContext, PolicyRejection, ProcessingPolicy, ReceivedCommitInfo};
+
+/// An application-defined extension with a typed payload.
+///
+/// Implementations are usually generated with the
+/// [`app_extension!`](crate::app_extension) macro.
+pub trait AppExtension: Sized + Serialize + Deserialize + Size {
+    /// The extension type. It must not be one of the extension types defined
+    /// by MLS.
+    const EXTENSION_TYPE: u16;
+
+    /// Returns the [`ExtensionType`] of the extension.
+    fn extension_type() -> ExtensionType {
+        ExtensionType::Unknown(Self::EXTENSION_TYPE)
+    }
+
+    /// Encodes the extension as an [`Extension`]. Returns
+    /// [`AppExtensionError::ReservedExtensionType`] if the extension type is
+    /// defined by MLS.
+    fn to_extension(&self) -> Result<Extension, AppExtensionError> {
+        if ExtensionType::from(Self::EXTENSION_TYPE) != Self::extension_type() {
+            return Err(AppExtensionError::ReservedExtensionType(
+                Self::EXTENSION_TYPE,
+            ));
+        }
+
+        Ok(Extension::Unknown(
+            Self::EXTENSION_TYPE,
+            UnknownExtension(self.tls_serialize_detached()?),
+        ))
+    }
+
+    /// Decodes the extension from an [`Extension`]. Returns `None` if the
+    /// extension is of a different type, and [`AppExtensionError::Malformed`]
+    /// if the payload can't be decoded.
+    fn from_extension(extension: &Extension) -> Option<Result<Self, AppExtensionError>> {
+        match extension {
+            Extension::Unknown(extension_type, UnknownExtension(payload))
+                if *extension_type == Self::EXTENSION_TYPE =>
+            {
+                Some(
+                    Self::tls_deserialize_exact(payload)
+                        .map_err(|_| AppExtensionError::Malformed(Self::extension_type())),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Defines a struct that is an [`AppExtension`] with the given extension type.
+///
+/// The fields are encoded in the order of declaration with `tls_codec`. Use
+/// [`VLBytes`](tls_codec::VLBytes) or `Vec<T>` for variable-length fields.
+/// Attributes, e.g. derives, are applied to the struct.
+///
+/// ```
+/// use openmls::{app_extension, extensions::AppExtension};
+/// use tls_codec::VLBytes;
+///
+/// app_extension! {
+///     /// The avatar of a group.
+///     #[derive(Debug, Clone, PartialEq, Eq)]
+///     pub struct GroupAvatar: 0xf042 {
+///         pub mime_type: VLBytes,
+///         pub image: VLBytes,
+///     }
+/// }
+///
+/// let avatar = GroupAvatar {
+///     mime_type: b"image/png".to_vec().into(),
+///     image: vec![0x89, 0x50, 0x4e, 0x47].into(),
+/// };
+/// let extension = avatar.to_extension().unwrap();
+/// let decoded = GroupAvatar::from_extension(&extension).unwrap().unwrap();
+/// assert_eq!(avatar, decoded);
+/// ```
+#[macro_export]
+macro_rules! app_extension {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident : $extension_type:literal {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::extensions::__tls_codec::Size for $name {
+            fn tls_serialized_len(&self) -> usize {
+                0 $(+ $crate::extensions::__tls_codec::Size::tls_serialized_len(&self.$field))*
+            }
+        }
+
+        impl $crate::extensions::__tls_codec::Serialize for $name {
+            #[allow(unused_mut)]
+            fn tls_serialize<W: ::std::io::Write>(
+                &self,
+                writer: &mut W,
+            ) -> ::std::result::Result<usize, $crate::extensions::__tls_codec::Error> {
+                let mut written = 0;
+                $(
+                    written += $crate::extensions::__tls_codec::Serialize::tls_serialize(
+                        &self.$field,
+                        writer,
+                    )?;
+                )*
+                Ok(written)
+            }
+        }
+
+        impl $crate::extensions::__tls_codec::Deserialize for $name {
+            #[allow(unused_variables)]
+            fn tls_deserialize<R: ::std::io::Read>(
+                bytes: &mut R,
+            ) -> ::std::result::Result<Self, $crate::extensions::__tls_codec::Error> {
+                Ok(Self {
+                    $(
+                        $field: <$ty as $crate::extensions::__tls_codec::Deserialize>::tls_deserialize(
+                            bytes,
+                        )?,
+                    )*
+                })
+            }
+        }
+
+        impl $crate::extensions::AppExtension for $name {
+            const EXTENSION_TYPE: u16 = $extension_type;
+        }
+    };
+}
+
+type PayloadParser = fn(&[u8]) -> Result<(), tls_codec::Error>;
+
+/// A set of [`AppExtension`]s whose payloads are checked when they are added
+/// to the group context, a key package or a leaf node.
+///
+/// The registry is a [`ProcessingPolicy`]. Passed to
+/// [`MlsGroup::process_message_with_policy()`](crate::group::MlsGroup::process_message_with_policy()),
+/// it rejects commits that introduce a registered extension with a malformed
+/// payload, in the group context extensions, in an added key package or its
+/// leaf node, in the leaf node of an update proposal or of an update path.
+/// Passed to
+/// [`StagedWelcome::new_from_welcome_with_policy()`](crate::group::StagedWelcome::new_from_welcome_with_policy())
+/// or
+/// [`MlsGroup::join_by_external_commit_with_policy()`](crate::group::MlsGroup::join_by_external_commit_with_policy()),
+/// it rejects groups whose group context or group info extensions are
+/// malformed. Errors are reported as [`PolicyRejection::AppExtension`].
+///
+/// Extension types that are not registered are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct AppExtensionRegistry {
+    parsers: BTreeMap<ExtensionType, PayloadParser>,
+}
+
+impl AppExtensionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the extension `T`. Returns
+    /// [`AppExtensionError::ReservedExtensionType`] if its extension type is
+    /// defined by MLS.
+    pub fn register<T: AppExtension>(mut self) -> Result<Self, AppExtensionError> {
+        if ExtensionType::from(T::EXTENSION_TYPE) != T::extension_type() {
+            return Err(AppExtensionError::ReservedExtensionType(T::EXTENSION_TYPE));
+        }
+
+        self.parsers.insert(T::extension_type(), |payload| {
+            T::tls_deserialize_exact(payload).map(|_| ())
+        });
+        Ok(self)
+    }
+
+    /// Returns `true` if an extension of the given type is registered.
+    pub fn is_registered(&self, extension_type: ExtensionType) -> bool {
+        self.parsers.contains_key(&extension_type)
+    }
+
+    /// Checks that the payloads of all registered extensions in `extensions`
+    /// can be decoded. Returns [`AppExtensionError::Malformed`] otherwise.
+    pub fn validate(&self, extensions: &Extensions) -> Result<(), AppExtensionError> {
+        for extension in extensions.iter() {
+            if let Extension::Unknown(_, UnknownExtension(payload)) = extension {
+                let extension_type = extension.extension_type();
+                if let Some(parse) = self.parsers.get(&extension_type) {
+                    parse(payload).map_err(|_| AppExtensionError::Malformed(extension_type))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProcessingPolicy for AppExtensionRegistry {
+    fn validate_group_info(
+        &self,
+        group_context: &GroupContext,
+        extensions: &Extensions,
+    ) -> Result<(), PolicyRejection> {
+        self.validate(group_context.extensions())?;
+        self.validate(extensions)?;
+        Ok(())
+    }
+
+    fn validate_staged_commit(&self, commit: &ReceivedCommitInfo) -> Result<(), PolicyRejection> {
+        let staged_commit = commit.staged_commit();
+
+        self.validate(staged_commit.group_context().extensions())?;
+        for add_proposal in staged_commit.add_proposals() {
+            let key_package = add_proposal.add_proposal().key_package();
+            self.validate(key_package.extensions())?;
+            self.validate(key_package.leaf_node().extensions())?;
+        }
+        for update_proposal in staged_commit.update_proposals() {
+            self.validate(update_proposal.update_proposal().leaf_node().extensions())?;
+        }
+        if let Some(leaf_node) = staged_commit.update_path_leaf_node() {
+            self.validate(leaf_node.extensions())?;
+        }
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@
 //! * `ParentHashError`
 //! * `RatchetTreeError`
 
+use super::ExtensionType;
 use crate::error::{ErrorString, LibraryError};
 
 use thiserror::Error;
@@ -103,3 +104,17 @@ pub enum InvalidExtensionError {
     )]
     IllegalInLeafNodes,
 }
+
+/// Application extension error
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum AppExtensionError {
+    /// The extension type is reserved for an extension that is defined by MLS.
+    #[error("The extension type {0} is reserved for an extension that is defined by MLS.")]
+    ReservedExtensionType(u16),
+    /// The payload of a registered extension is malformed.
+    #[error("The payload of the extension {0:?} is malformed.")]
+    Malformed(ExtensionType),
+    /// TLS codec error
+    #[error(transparent)]
+    TlsCodecError(#[from] tls_codec::Error),
+}
@@ -18,6 +18,9 @@
 //! - [`RatchetTreeExtension`] (GroupInfo extension)
 //! - [`RequiredCapabilitiesExtension`] (GroupContext extension)
 //! - [`ExternalPubExtension`] (GroupInfo extension)
+//!
+//! Applications can define their own extensions with typed payloads, see
+//! [`AppExtension`] and the [`app_extension!`](crate::app_extension) macro.
 
 use std::{
     fmt::Debug,
@@ -26,7 +29,10 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
+use crate::treesync::node::leaf_node::is_grease;
+
 // Private
+mod app_extension;
 mod application_id_extension;
 mod codec;
 mod external_pub_extension;
@@ -40,6 +46,7 @@ use errors::*;
 pub mod errors;
 
 // Public re-exports
+pub use app_extension::{AppExtension, AppExtensionRegistry};
 pub use application_id_extension::ApplicationIdExtension;
 pub use external_pub_extension::ExternalPubExtension;
 pub use external_sender_extension::{
@@ -48,6 +55,9 @@ pub use external_sender_extension::{
 pub use last_resort::LastResortExtension;
 pub use ratchet_tree_extension::RatchetTreeExtension;
 pub use required_capabilities::RequiredCapabilitiesExtension;
+// Used by the `app_extension!` macro.
+#[doc(hidden)]
+pub use tls_codec as __tls_codec;
 use tls_codec::{
     Deserialize as TlsDeserializeTrait, DeserializeBytes, Error, Serialize as TlsSerializeTrait,
     Size, TlsSize,
@@ -114,6 +124,12 @@ impl ExtensionType {
         }
     }
 
+    /// Returns true if this is a GREASE extension type, which has no meaning
+    /// and must be ignored.
+    pub fn is_grease(self) -> bool {
+        is_grease(self.into())
+    }
+
     /// Returns whether an extension type is valid when used in leaf nodes.
     /// Returns None if validity can not be determined.
     /// This is the case for unknown extensions.
@@ -433,6 +449,14 @@ impl Extensions {
             _ => None,
         }
     }
+
+    /// Decodes the application-defined extension `T` if there is any.
+    /// Returns [`AppExtensionError::Malformed`] if its payload can't be
+    /// decoded.
+    pub fn app_extension<T: AppExtension>(&self) -> Option<Result<T, AppExtensionError>> {
+        self.find_by_type(T::extension_type())
+            .and_then(T::from_extension)
+    }
 }
 
 impl Extension {
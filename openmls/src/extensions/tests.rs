@@ -2,7 +2,7 @@
 //! Some basic unit tests for extensions
 //! Proper testing is done through the public APIs.
 
-use tls_codec::{Deserialize, Serialize};
+use tls_codec::{Deserialize, Serialize, VLBytes};
 
 use super::*;
 use crate::{
@@ -187,8 +187,16 @@ fn with_group_context_extensions() {
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .with_group_context_extensions(extensions)
         .expect("failed to apply extensions at group config builder")
+        .capabilities(Capabilities::new(
+            None,
+            None,
+            Some(&[ExtensionType::Unknown(0xf023)]),
+            None,
+            None,
+        ))
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let alice_group = MlsGroup::new(
@@ -282,6 +290,73 @@ fn wrong_extension_with_group_context_extensions() {
     assert_eq!(err, InvalidExtensionError::IllegalInGroupContext);
 }
 
+#[test]
+fn create_config_validation() {
+    let unknown_extension = Extension::Unknown(0xf023, UnknownExtension(vec![0xca, 0xfe]));
+    let required_capabilities = Extension::RequiredCapabilities(
+        RequiredCapabilitiesExtension::new(&[ExtensionType::Unknown(0xf023)], &[], &[]),
+    );
+
+    // The creator doesn't support the group context extension.
+    let err = MlsGroupCreateConfig::builder()
+        .with_group_context_extensions(Extensions::single(unknown_extension.clone()))
+        .unwrap()
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        CreateConfigError::ExtensionNotInCapabilities(ExtensionType::Unknown(0xf023))
+    );
+
+    // The creator supports it. Other members aren't required to support it.
+    let capabilities = Capabilities::new(
+        None,
+        None,
+        Some(&[ExtensionType::Unknown(0xf023)]),
+        None,
+        None,
+    );
+    MlsGroupCreateConfig::builder()
+        .capabilities(capabilities.clone())
+        .with_group_context_extensions(Extensions::single(unknown_extension.clone()))
+        .unwrap()
+        .build()
+        .unwrap();
+
+    // The creator doesn't support the required capabilities.
+    let err = MlsGroupCreateConfig::builder()
+        .with_group_context_extensions(Extensions::single(required_capabilities.clone()))
+        .unwrap()
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        CreateConfigError::RequiredExtensionNotInCapabilities(ExtensionType::Unknown(0xf023))
+    );
+    let err = MlsGroupCreateConfig::builder()
+        .with_group_context_extensions(Extensions::single(Extension::RequiredCapabilities(
+            RequiredCapabilitiesExtension::new(&[], &[ProposalType::Custom(0xf024)], &[]),
+        )))
+        .unwrap()
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        CreateConfigError::RequiredProposalNotInCapabilities(ProposalType::Custom(0xf024))
+    );
+
+    // A consistent configuration is accepted.
+    let config = MlsGroupCreateConfig::builder()
+        .capabilities(capabilities)
+        .with_group_context_extensions(
+            Extensions::from_vec(vec![unknown_extension, required_capabilities]).unwrap(),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(config.validate().is_ok());
+}
+
 #[openmls_test::openmls_test]
 fn last_resort_extension() {
     let last_resort = Extension::LastResort(LastResortExtension::default());
@@ -332,7 +407,8 @@ fn last_resort_extension() {
 
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new(
@@ -378,3 +454,248 @@ fn last_resort_extension() {
         .expect("error retrieving key package")
         .expect("key package does not exist");
 }
+
+crate::app_extension! {
+    /// An application-defined group context extension.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct GroupAvatar: 0xf042 {
+        mime_type: VLBytes,
+        image: VLBytes,
+    }
+}
+
+crate::app_extension! {
+    /// An application-defined extension with a type that is defined by MLS.
+    #[derive(Debug)]
+    struct ReservedType: 0x0001 {
+        id: VLBytes,
+    }
+}
+
+#[openmls_test::openmls_test]
+fn app_extension() {
+    let avatar = GroupAvatar {
+        mime_type: b"image/png".to_vec().into(),
+        image: vec![0x89, 0x50, 0x4e, 0x47].into(),
+    };
+
+    // The fields are encoded with their variable-length prefix.
+    let extension = avatar.to_extension().unwrap();
+    let Extension::Unknown(0xf042, UnknownExtension(payload)) = &extension else {
+        panic!("expected an unknown extension, got {extension:?}");
+    };
+    assert_eq!(payload[0], 9);
+    assert_eq!(payload[10], 4);
+    assert_eq!(payload.len(), 15);
+    assert_eq!(
+        Extensions::single(extension.clone()).app_extension::<GroupAvatar>(),
+        Some(Ok(avatar.clone()))
+    );
+    assert_eq!(Extensions::empty().app_extension::<GroupAvatar>(), None);
+
+    // Extension types defined by MLS can't be used.
+    let reserved = ReservedType {
+        id: b"app".to_vec().into(),
+    };
+    assert_eq!(
+        reserved.to_extension().unwrap_err(),
+        AppExtensionError::ReservedExtensionType(1)
+    );
+    assert_eq!(
+        AppExtensionRegistry::new()
+            .register::<ReservedType>()
+            .unwrap_err(),
+        AppExtensionError::ReservedExtensionType(1)
+    );
+    let registry = AppExtensionRegistry::new()
+        .register::<GroupAvatar>()
+        .unwrap();
+    assert!(registry.is_registered(ExtensionType::Unknown(0xf042)));
+
+    // Alice creates a group with the avatar in the group context.
+    let avatar_type = ExtensionType::Unknown(0xf042);
+    let capabilities = Capabilities::new(None, None, Some(&[avatar_type]), None, None);
+    let required_capabilities = Extension::RequiredCapabilities(
+        RequiredCapabilitiesExtension::new(&[avatar_type], &[], &[]),
+    );
+    let group_context_extensions =
+        Extensions::from_vec(vec![extension, required_capabilities.clone()]).unwrap();
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .with_group_context_extensions(group_context_extensions)
+        .unwrap()
+        .capabilities(capabilities.clone())
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let alice_credential_with_key_and_signer =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+    let alice_signer = &alice_credential_with_key_and_signer.signer;
+    let mut alice_group = MlsGroup::new(
+        provider,
+        alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key_and_signer.credential_with_key,
+    )
+    .unwrap();
+
+    // Bob joins and reads the avatar.
+    let bob_credential_with_key_and_signer =
+        generate_credential_with_key("Bob".into(), ciphersuite.signature_algorithm(), provider);
+    let bob_key_package = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities)
+        .build(
+            ciphersuite,
+            provider,
+            &bob_credential_with_key_and_signer.signer,
+            bob_credential_with_key_and_signer.credential_with_key,
+        )
+        .unwrap();
+    let (_, welcome, _) = alice_group
+        .add_members(
+            provider,
+            alice_signer,
+            &[bob_key_package.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let welcome: MlsMessageIn = welcome.into();
+    let mut bob_group = StagedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap()
+    .into_group(provider)
+    .unwrap();
+    assert_eq!(
+        bob_group
+            .context()
+            .extensions()
+            .app_extension::<GroupAvatar>(),
+        Some(Ok(avatar))
+    );
+    assert_eq!(registry.validate(bob_group.context().extensions()), Ok(()));
+
+    // Alice adds Dave, whose key package carries a malformed avatar, which
+    // the registry rejects.
+    let dave_credential_with_key_and_signer =
+        generate_credential_with_key("Dave".into(), ciphersuite.signature_algorithm(), provider);
+    let dave_key_package = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities.clone())
+        .key_package_extensions(Extensions::single(Extension::Unknown(
+            0xf042,
+            UnknownExtension(vec![9, b'i']),
+        )))
+        .build(
+            ciphersuite,
+            provider,
+            &dave_credential_with_key_and_signer.signer,
+            dave_credential_with_key_and_signer.credential_with_key,
+        )
+        .unwrap();
+    let (commit, _, _) = alice_group
+        .add_members(
+            provider,
+            alice_signer,
+            &[dave_key_package.key_package().clone()],
+        )
+        .unwrap();
+    let commit = commit.into_protocol_message().unwrap();
+    let err = bob_group
+        .process_message_with_policy(provider, commit, &registry)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::RejectedByPolicy(
+            PolicyRejection::AppExtension(AppExtensionError::Malformed(_))
+        ))
+    ));
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+
+    // Alice commits a malformed avatar, which the registry rejects.
+    let malformed_extensions = Extensions::from_vec(vec![
+        Extension::Unknown(0xf042, UnknownExtension(vec![9, b'i'])),
+        required_capabilities,
+    ])
+    .unwrap();
+    let (commit, _, _) = alice_group
+        .update_group_context_extensions(provider, malformed_extensions, alice_signer)
+        .unwrap();
+    let commit = commit.into_protocol_message().unwrap();
+
+    let err = bob_group
+        .process_message_with_policy(provider, commit.clone(), &registry)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::RejectedByPolicy(
+            PolicyRejection::AppExtension(AppExtensionError::Malformed(_))
+        ))
+    ));
+
+    // Without the registry, the commit is accepted, but the avatar can't be
+    // decoded.
+    let processed_message = bob_group.process_message(provider, commit).unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    bob_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+    assert_eq!(
+        bob_group
+            .context()
+            .extensions()
+            .app_extension::<GroupAvatar>(),
+        Some(Err(AppExtensionError::Malformed(avatar_type)))
+    );
+
+    // Charlie refuses to join the group with the malformed avatar. The
+    // registry is combined with another policy in a tuple.
+    alice_group.merge_pending_commit(provider).unwrap();
+    let charlie_credential_with_key_and_signer = generate_credential_with_key(
+        "Charlie".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+    let charlie_key_package = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities)
+        .build(
+            ciphersuite,
+            provider,
+            &charlie_credential_with_key_and_signer.signer,
+            charlie_credential_with_key_and_signer.credential_with_key,
+        )
+        .unwrap();
+    let (_, welcome, _) = alice_group
+        .add_members(
+            provider,
+            alice_signer,
+            &[charlie_key_package.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let welcome: MlsMessageIn = welcome.into();
+    let err = StagedWelcome::new_from_welcome_with_policy(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+        &(registry, ExternalSenderPermissions::new()),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        WelcomeError::RejectedByPolicy(PolicyRejection::AppExtension(
+            AppExtensionError::Malformed(_)
+        ))
+    ));
+}
@@ -0,0 +1,184 @@
+//! # Payload classification
+//!
+//! Gateways that route mixed traffic need to know what kind of MLS structure
+//! a payload contains without attempting a full deserialization for every
+//! possible type. [`classify_payload()`] inspects the first bytes of a
+//! payload and reports whether it looks like an `MLSMessage` (with its wire
+//! format) or a ratchet tree, together with the protocol version and the
+//! ciphersuite where they can be determined from the encoding.
+//!
+//! The classification is a heuristic. A payload that is classified can still
+//! fail to deserialize, and it has to be validated as usual before it is
+//! used.
+
+use openmls_traits::types::Ciphersuite;
+
+use super::*;
+use crate::versions::ProtocolVersion;
+
+/// The kind of MLS structure a payload contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    /// An `MLSMessage` containing a [`PublicMessage`].
+    PublicMessage,
+    /// An `MLSMessage` containing a [`PrivateMessage`].
+    PrivateMessage,
+    /// An `MLSMessage` containing a `Welcome`.
+    Welcome,
+    /// An `MLSMessage` containing a `GroupInfo`.
+    GroupInfo,
+    /// An `MLSMessage` containing a `KeyPackage`.
+    KeyPackage,
+    /// A ratchet tree, e.g. as sent out of band alongside a `Welcome`.
+    RatchetTree,
+}
+
+impl PayloadKind {
+    /// Returns the [`WireFormat`] of the `MLSMessage`, or `None` for a ratchet
+    /// tree.
+    pub fn wire_format(&self) -> Option<WireFormat> {
+        match self {
+            PayloadKind::PublicMessage => Some(WireFormat::PublicMessage),
+            PayloadKind::PrivateMessage => Some(WireFormat::PrivateMessage),
+            PayloadKind::Welcome => Some(WireFormat::Welcome),
+            PayloadKind::GroupInfo => Some(WireFormat::GroupInfo),
+            PayloadKind::KeyPackage => Some(WireFormat::KeyPackage),
+            PayloadKind::RatchetTree => None,
+        }
+    }
+}
+
+/// The result of [`classify_payload()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadClassification {
+    kind: PayloadKind,
+    protocol_version: Option<ProtocolVersion>,
+    ciphersuite: Option<Ciphersuite>,
+}
+
+impl PayloadClassification {
+    /// Returns the kind of structure the payload contains.
+    pub fn kind(&self) -> PayloadKind {
+        self.kind
+    }
+
+    /// Returns the protocol version of the payload. It is `None` for ratchet
+    /// trees, which don't carry a version.
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.protocol_version
+    }
+
+    /// Returns the ciphersuite of the payload. It is only available for
+    /// `Welcome`, `GroupInfo` and `KeyPackage` messages of a known protocol
+    /// version, and only if the ciphersuite is known to OpenMLS.
+    pub fn ciphersuite(&self) -> Option<Ciphersuite> {
+        self.ciphersuite
+    }
+}
+
+/// Classifies the raw `bytes` of an MLS payload without deserializing it.
+///
+/// Returns `None` if the bytes look neither like an `MLSMessage` nor like a
+/// ratchet tree.
+pub fn classify_payload(bytes: &[u8]) -> Option<PayloadClassification> {
+    classify_mls_message(bytes).or_else(|| classify_ratchet_tree(bytes))
+}
+
+fn classify_mls_message(bytes: &[u8]) -> Option<PayloadClassification> {
+    let protocol_version = match read_u16(bytes, 0)? {
+        // The reserved version.
+        0 => return None,
+        version => ProtocolVersion::from(version),
+    };
+    let kind = match read_u16(bytes, 2)? {
+        1 => PayloadKind::PublicMessage,
+        2 => PayloadKind::PrivateMessage,
+        3 => PayloadKind::Welcome,
+        4 => PayloadKind::GroupInfo,
+        5 => PayloadKind::KeyPackage,
+        _ => return None,
+    };
+
+    // The layout of the body is only known for supported versions.
+    if !protocol_version.is_supported() {
+        return Some(PayloadClassification {
+            kind,
+            protocol_version: Some(protocol_version),
+            ciphersuite: None,
+        });
+    }
+
+    let body = &bytes[4..];
+    let ciphersuite = match kind {
+        // Both messages start with the group ID.
+        PayloadKind::PublicMessage | PayloadKind::PrivateMessage => {
+            let (length, prefix_length) = read_vl_length(body)?;
+            if prefix_length + length > body.len() {
+                return None;
+            }
+            None
+        }
+        // struct { CipherSuite cipher_suite; ... } Welcome;
+        PayloadKind::Welcome => Some(read_u16(body, 0)?),
+        // The GroupInfo starts with the GroupContext and the KeyPackage with
+        // the version, both followed by the ciphersuite.
+        PayloadKind::GroupInfo | PayloadKind::KeyPackage => {
+            if read_u16(body, 0)? != 1 {
+                return None;
+            }
+            Some(read_u16(body, 2)?)
+        }
+        PayloadKind::RatchetTree => None,
+    };
+
+    Some(PayloadClassification {
+        kind,
+        protocol_version: Some(protocol_version),
+        ciphersuite: ciphersuite.and_then(|ciphersuite| Ciphersuite::try_from(ciphersuite).ok()),
+    })
+}
+
+// optional<Node> ratchet_tree<V>;
+//
+// The first node of a non-empty tree is a present leaf.
+fn classify_ratchet_tree(bytes: &[u8]) -> Option<PayloadClassification> {
+    let (length, prefix_length) = read_vl_length(bytes)?;
+    if prefix_length + length != bytes.len() {
+        return None;
+    }
+    let nodes = &bytes[prefix_length..];
+    // present = 1, node_type = leaf(1)
+    if !nodes.starts_with(&[1, 1]) {
+        return None;
+    }
+
+    Some(PayloadClassification {
+        kind: PayloadKind::RatchetTree,
+        protocol_version: None,
+        ciphersuite: None,
+    })
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+// Reads the variable-length vector header from Section 2.1.2 of RFC 9420 and
+// returns the length of the vector and the length of the header.
+fn read_vl_length(bytes: &[u8]) -> Option<(usize, usize)> {
+    let first = *bytes.first()?;
+    let prefix_length = match first >> 6 {
+        0 => 1,
+        1 => 2,
+        2 => 4,
+        _ => return None,
+    };
+    let prefix = bytes.get(..prefix_length)?;
+    let length = prefix[1..]
+        .iter()
+        .fold((first & 0x3f) as usize, |length, byte| {
+            (length << 8) | *byte as usize
+        });
+    Some((length, prefix_length))
+}
@@ -39,9 +39,9 @@ impl Deserialize for MlsMessageIn {
         let body = MlsMessageBodyIn::tls_deserialize(bytes)?;
 
         // This is required by the RFC in the struct definition of MLSMessage
-        if version != ProtocolVersion::Mls10 {
+        if !version.is_supported() {
             return Err(tls_codec::Error::DecodingError(
-                "MlsMessage protocol version is not 1.0".into(),
+                "MlsMessage protocol version is not supported".into(),
             ));
         }
 
@@ -0,0 +1,85 @@
+//! # Message identifiers
+//!
+//! A [`MessageId`] identifies a private message without hashing it. It is
+//! derived from the header of the message and its sender data, which can only
+//! be decrypted by members of the group.
+//!
+//! Since every generation of a sender ratchet is only used once, the
+//! combination of group, epoch, sender, content type and generation is unique
+//! for every private message an honest member sends. A redelivered copy of a
+//! message has the same identifier as the original.
+
+use super::*;
+use crate::binary_tree::LeafNodeIndex;
+
+/// A deterministic identifier of a private message that can be used to
+/// deduplicate redelivered messages.
+///
+/// The sender gets the identifier from
+/// [`MlsGroup::create_message_with_id()`](crate::group::MlsGroup::create_message_with_id),
+/// receivers from [`ProcessedMessage::message_id()`]. The TLS serialization of
+/// the identifier can be used as a key in a database.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
+    TlsSerialize,
+    TlsSize,
+)]
+pub struct MessageId {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    sender: LeafNodeIndex,
+    content_type: ContentType,
+    generation: u32,
+}
+
+impl MessageId {
+    pub(crate) fn new(
+        group_id: GroupId,
+        epoch: GroupEpoch,
+        sender: LeafNodeIndex,
+        content_type: ContentType,
+        generation: u32,
+    ) -> Self {
+        Self {
+            group_id,
+            epoch,
+            sender,
+            content_type,
+            generation,
+        }
+    }
+
+    /// Returns the ID of the group the message was sent in.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the message was sent in.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the leaf index of the sender.
+    pub fn sender(&self) -> LeafNodeIndex {
+        self.sender
+    }
+
+    /// Returns the content type of the message.
+    pub fn content_type(&self) -> ContentType {
+        self.content_type
+    }
+
+    /// Returns the generation of the sender ratchet that was used to encrypt
+    /// the message.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
@@ -96,13 +96,19 @@ pub enum MlsMessageBodyIn {
 impl MlsMessageIn {
     /// Returns the wire format.
     pub fn wire_format(&self) -> WireFormat {
-        match self.body {
-            MlsMessageBodyIn::PrivateMessage(_) => WireFormat::PrivateMessage,
-            MlsMessageBodyIn::PublicMessage(_) => WireFormat::PublicMessage,
-            MlsMessageBodyIn::Welcome(_) => WireFormat::Welcome,
-            MlsMessageBodyIn::GroupInfo(_) => WireFormat::GroupInfo,
-            MlsMessageBodyIn::KeyPackage(_) => WireFormat::KeyPackage,
-        }
+        self.body.wire_format()
+    }
+
+    /// Returns the protocol version of the message.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Returns a reference to the contents of this [`MlsMessageIn`], e.g. to
+    /// inspect it without consuming it. See [`MlsMessageBodyIn`] for the
+    /// typed accessors.
+    pub fn body(&self) -> &MlsMessageBodyIn {
+        &self.body
     }
 
     /// Extract the content of an [`MlsMessageIn`] after deserialization for use
@@ -146,7 +152,6 @@ impl MlsMessageIn {
     /// Convert this message into a [`Welcome`].
     ///
     /// Returns `None` if this message is not a welcome message.
-    #[cfg(any(feature = "test-utils", test))]
     pub fn into_welcome(self) -> Option<Welcome> {
         match self.body {
             MlsMessageBodyIn::Welcome(w) => Some(w),
@@ -154,7 +159,11 @@ impl MlsMessageIn {
         }
     }
 
-    #[cfg(any(feature = "test-utils", test))]
+    /// Convert this message into a [`ProtocolMessage`].
+    ///
+    /// Returns `None` if this message is neither a public nor a private
+    /// message. See [`Self::try_into_protocol_message()`] for a version that
+    /// returns an error instead.
     pub fn into_protocol_message(self) -> Option<ProtocolMessage> {
         match self.body {
             MlsMessageBodyIn::PublicMessage(m) => Some(m.into()),
@@ -163,19 +172,111 @@ impl MlsMessageIn {
         }
     }
 
-    #[cfg(any(feature = "test-utils", test))]
+    /// Convert this message into a [`VerifiableGroupInfo`].
+    ///
+    /// Returns `None` if this message is not a group info.
     pub fn into_verifiable_group_info(self) -> Option<VerifiableGroupInfo> {
         match self.body {
             MlsMessageBodyIn::GroupInfo(group_info) => Some(group_info),
             _ => None,
         }
     }
+
+    /// Convert this message into a [`KeyPackageIn`], which still has to be
+    /// validated with [`KeyPackageIn::validate()`].
+    ///
+    /// Returns `None` if this message is not a key package.
+    pub fn into_key_package_in(self) -> Option<KeyPackageIn> {
+        match self.body {
+            MlsMessageBodyIn::KeyPackage(key_package) => Some(key_package),
+            _ => None,
+        }
+    }
+}
+
+impl MlsMessageBodyIn {
+    /// Returns the wire format of the message.
+    pub fn wire_format(&self) -> WireFormat {
+        match self {
+            MlsMessageBodyIn::PrivateMessage(_) => WireFormat::PrivateMessage,
+            MlsMessageBodyIn::PublicMessage(_) => WireFormat::PublicMessage,
+            MlsMessageBodyIn::Welcome(_) => WireFormat::Welcome,
+            MlsMessageBodyIn::GroupInfo(_) => WireFormat::GroupInfo,
+            MlsMessageBodyIn::KeyPackage(_) => WireFormat::KeyPackage,
+        }
+    }
+
+    /// Returns the [`PublicMessageIn`], or `None` if this is not a public
+    /// message.
+    pub fn as_public_message(&self) -> Option<&PublicMessageIn> {
+        match self {
+            MlsMessageBodyIn::PublicMessage(public_message) => Some(public_message),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`PrivateMessageIn`], or `None` if this is not a private
+    /// message.
+    pub fn as_private_message(&self) -> Option<&PrivateMessageIn> {
+        match self {
+            MlsMessageBodyIn::PrivateMessage(private_message) => Some(private_message),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`Welcome`], or `None` if this is not a welcome message.
+    pub fn as_welcome(&self) -> Option<&Welcome> {
+        match self {
+            MlsMessageBodyIn::Welcome(welcome) => Some(welcome),
+            _ => None,
+        }
+    }
+
+    /// Returns the (unverified) [`VerifiableGroupInfo`], or `None` if this is
+    /// not a group info.
+    pub fn as_group_info(&self) -> Option<&VerifiableGroupInfo> {
+        match self {
+            MlsMessageBodyIn::GroupInfo(group_info) => Some(group_info),
+            _ => None,
+        }
+    }
+
+    /// Returns the (unverified) [`KeyPackageIn`], or `None` if this is not a
+    /// key package.
+    pub fn as_key_package(&self) -> Option<&KeyPackageIn> {
+        match self {
+            MlsMessageBodyIn::KeyPackage(key_package) => Some(key_package),
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of the group the message belongs to, or `None` if this
+    /// is a welcome message or a key package, which don't reveal the group.
+    pub fn group_id(&self) -> Option<&GroupId> {
+        match self {
+            MlsMessageBodyIn::PublicMessage(public_message) => Some(public_message.group_id()),
+            MlsMessageBodyIn::PrivateMessage(private_message) => Some(private_message.group_id()),
+            MlsMessageBodyIn::GroupInfo(group_info) => Some(group_info.group_id()),
+            MlsMessageBodyIn::Welcome(_) | MlsMessageBodyIn::KeyPackage(_) => None,
+        }
+    }
+
+    /// Returns the epoch of the message, or `None` if this is a welcome
+    /// message or a key package.
+    pub fn epoch(&self) -> Option<GroupEpoch> {
+        match self {
+            MlsMessageBodyIn::PublicMessage(public_message) => Some(public_message.epoch()),
+            MlsMessageBodyIn::PrivateMessage(private_message) => Some(private_message.epoch()),
+            MlsMessageBodyIn::GroupInfo(group_info) => Some(group_info.epoch()),
+            MlsMessageBodyIn::Welcome(_) | MlsMessageBodyIn::KeyPackage(_) => None,
+        }
+    }
 }
 
 /// Enum containing a message for use with `process_message` and an
 /// [`MlsGroup`]. Both [`PublicMessage`] and [`PrivateMessage`] implement
 /// [`Into<ProtocolMessage>`].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProtocolMessage {
     /// A [`ProtocolMessage`] containing a [`PrivateMessage`].
     PrivateMessage(PrivateMessageIn),
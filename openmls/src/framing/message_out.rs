@@ -12,8 +12,8 @@ use tls_codec::Serialize;
 use super::*;
 
 use crate::{
-    key_packages::KeyPackage, messages::group_info::GroupInfo, prelude::KeyPackageBundle,
-    versions::ProtocolVersion,
+    ciphersuite::hash_ref::KeyPackageRef, key_packages::KeyPackage,
+    messages::group_info::GroupInfo, prelude::KeyPackageBundle, versions::ProtocolVersion,
 };
 
 #[cfg(any(feature = "test-utils", test))]
@@ -129,6 +129,18 @@ impl From<KeyPackageBundle> for MlsMessageOut {
 }
 
 impl MlsMessageOut {
+    /// Create an [`MlsMessageOut`] from a [`PublicMessage`], as well as the
+    /// currently used [`ProtocolVersion`].
+    pub(crate) fn from_public_message(
+        public_message: PublicMessage,
+        version: ProtocolVersion,
+    ) -> Self {
+        Self {
+            version,
+            body: MlsMessageBodyOut::PublicMessage(public_message),
+        }
+    }
+
     /// Create an [`MlsMessageOut`] from a [`PrivateMessage`], as well as the
     /// currently used [`ProtocolVersion`].
     pub(crate) fn from_private_message(
@@ -150,6 +162,28 @@ impl MlsMessageOut {
         }
     }
 
+    /// If this message is a [`Welcome`], splits it into one message per new
+    /// member, each of which only contains the encrypted group secrets of that
+    /// member. The messages are returned along with the [`KeyPackageRef`] of
+    /// the respective member. Returns `None` if this message is not a
+    /// [`Welcome`].
+    ///
+    /// See [`Welcome::split()`] for more details.
+    pub fn split_welcome(&self) -> Option<Vec<(KeyPackageRef, MlsMessageOut)>> {
+        match &self.body {
+            MlsMessageBodyOut::Welcome(welcome) => Some(
+                welcome
+                    .split()
+                    .into_iter()
+                    .map(|(key_package_ref, welcome)| {
+                        (key_package_ref, Self::from_welcome(welcome, self.version))
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
     /// Serializes the message to a byte vector. Returns [`MlsMessageError::UnableToEncode`] on failure.
     pub fn to_bytes(&self) -> Result<Vec<u8>, MlsMessageError> {
         self.tls_serialize_detached()
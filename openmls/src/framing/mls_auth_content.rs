@@ -185,6 +185,7 @@ impl AuthenticatedContent {
         epoch: GroupEpoch,
         signer: &impl Signer,
         sender_index: SenderExtensionIndex,
+        aad: &[u8],
     ) -> Result<Self, LibraryError> {
         let body = FramedContentBody::Proposal(proposal);
 
@@ -193,7 +194,7 @@ impl AuthenticatedContent {
             group_id,
             epoch,
             Sender::External(sender_index),
-            vec![].into(),
+            aad.into(),
             body,
         );
 
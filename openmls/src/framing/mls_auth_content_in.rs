@@ -10,6 +10,7 @@
 use std::io::Read;
 
 use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use serde::{Deserialize, Serialize};
 use tls_codec::Serialize as TlsSerializeTrait;
 
 use super::{mls_auth_content::*, mls_content_in::*, *};
@@ -17,7 +18,7 @@ use crate::{
     ciphersuite::signable::{SignedStruct, Verifiable, VerifiedStruct},
     credentials::CredentialWithKey,
     group::errors::ValidationError,
-    messages::proposals_in::ProposalIn,
+    messages::{proposals_in::ProposalIn, CommitIn},
     versions::ProtocolVersion,
 };
 
@@ -105,7 +106,7 @@ impl From<VerifiableAuthenticatedContentIn> for AuthenticatedContentIn {
 
 /// Wrapper struct around [`AuthenticatedContent`] to enforce signature verification
 /// before content can be accessed.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct VerifiableAuthenticatedContentIn {
     tbs: FramedContentTbsIn,
     auth: FramedContentAuthData,
@@ -185,6 +186,14 @@ impl VerifiableAuthenticatedContentIn {
     pub(crate) fn content_type(&self) -> ContentType {
         self.tbs.content.body.content_type()
     }
+
+    /// Get the commit, if the content is one.
+    pub(crate) fn commit(&self) -> Option<&CommitIn> {
+        match &self.tbs.content.body {
+            FramedContentBodyIn::Commit(commit) => Some(commit),
+            _ => None,
+        }
+    }
 }
 
 impl Verifiable for VerifiableAuthenticatedContentIn {
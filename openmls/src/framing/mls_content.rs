@@ -6,7 +6,7 @@ use crate::{
     error::LibraryError,
     group::{GroupEpoch, GroupId},
     messages::{proposals::Proposal, Commit},
-    versions::ProtocolVersion,
+    versions::{ProtocolVersion, VersionBehavior},
 };
 
 use std::io::Write;
@@ -176,7 +176,7 @@ impl FramedContentTbs {
             body,
         };
         FramedContentTbs {
-            version: ProtocolVersion::Mls10,
+            version: VersionBehavior::current().version,
             wire_format,
             content,
             serialized_context: None,
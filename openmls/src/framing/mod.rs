@@ -51,8 +51,10 @@ use crate::{
     schedule::{message_secrets::*, *},
 };
 
+pub(crate) mod classify;
 pub(crate) mod codec;
 
+pub(crate) mod message_id;
 pub(crate) mod message_in;
 pub(crate) mod message_out;
 pub(crate) mod mls_auth_content;
@@ -63,6 +65,7 @@ pub(crate) mod private_message;
 pub(crate) mod private_message_in;
 pub(crate) mod public_message;
 pub(crate) mod public_message_in;
+pub(crate) mod redacted;
 pub(crate) mod sender;
 pub(crate) mod validation;
 pub(crate) use errors::*;
@@ -83,12 +86,15 @@ pub(crate) use sender::*;
 // Public
 pub mod errors;
 
+pub use classify::{classify_payload, PayloadClassification, PayloadKind};
+pub use message_id::MessageId;
 pub use message_in::*;
 pub use message_out::*;
 pub use private_message::*;
 pub use private_message_in::*;
 pub use public_message::*;
 pub use public_message_in::*;
+pub use redacted::{DebugRedacted, Redacted};
 pub use sender::*;
 pub use validation::*;
 
@@ -175,6 +181,7 @@ impl<'a> FramingParameters<'a> {
     Clone,
     Copy,
     Debug,
+    Hash,
     Serialize,
     Deserialize,
     TlsDeserialize,
@@ -73,7 +73,7 @@ impl PrivateMessage {
         ciphersuite: Ciphersuite,
         message_secrets: &mut MessageSecrets,
         padding_size: usize,
-    ) -> Result<PrivateMessage, MessageEncryptionError<T>> {
+    ) -> Result<(PrivateMessage, MessageId), MessageEncryptionError<T>> {
         log::debug!("PrivateMessage::try_from_authenticated_content");
         log::trace!("  ciphersuite: {}", ciphersuite);
         // Check the message has the correct wire format
@@ -109,6 +109,7 @@ impl PrivateMessage {
             message_secrets,
             padding_size,
         )
+        .map(|(private_message, _)| private_message)
     }
 
     #[cfg(test)]
@@ -130,10 +131,12 @@ impl PrivateMessage {
             message_secrets,
             padding_size,
         )
+        .map(|(private_message, _)| private_message)
     }
 
     /// Internal function to encrypt content. The extra message header is only used
     /// for tests. Otherwise, the data from the given `AuthenticatedContent` is used.
+    /// Returns the [`MessageId`] of the encrypted message alongside it.
     fn encrypt_content<T>(
         crypto: &impl OpenMlsCrypto,
         rand: &impl OpenMlsRand,
@@ -142,7 +145,7 @@ impl PrivateMessage {
         ciphersuite: Ciphersuite,
         message_secrets: &mut MessageSecrets,
         padding_size: usize,
-    ) -> Result<PrivateMessage, MessageEncryptionError<T>> {
+    ) -> Result<(PrivateMessage, MessageId), MessageEncryptionError<T>> {
         // https://validation.openmls.tech/#valn1305
         let sender_index = if let Some(index) = public_message.sender().as_member() {
             index
@@ -242,14 +245,22 @@ impl PrivateMessage {
                 &sender_data_nonce,
             )
             .map_err(LibraryError::unexpected_crypto_error)?;
-        Ok(PrivateMessage {
-            group_id: header.group_id.clone(),
+        let message_id = MessageId::new(
+            header.group_id.clone(),
+            header.epoch,
+            header.sender,
+            public_message.content().content_type(),
+            generation,
+        );
+        let private_message = PrivateMessage {
+            group_id: header.group_id,
             epoch: header.epoch,
             content_type: public_message.content().content_type(),
             authenticated_data: public_message.authenticated_data().into(),
             encrypted_sender_data: encrypted_sender_data.into(),
             ciphertext: ciphertext.into(),
-        })
+        };
+        Ok((private_message, message_id))
     }
 
     /// Returns `true` if this is a handshake message and `false` otherwise.
@@ -298,7 +309,6 @@ impl PrivateMessage {
     }
 
     /// Get the cipher text bytes as slice.
-    #[cfg(test)]
     pub(crate) fn ciphertext(&self) -> &[u8] {
         self.ciphertext.as_slice()
     }
@@ -34,15 +34,24 @@ use super::*;
 /// } PrivateMessage;
 /// ```
 #[derive(
-    Debug, PartialEq, Eq, Clone, TlsSerialize, TlsSize, TlsDeserialize, TlsDeserializeBytes,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    TlsSerialize,
+    TlsSize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
 )]
 pub struct PrivateMessageIn {
-    group_id: GroupId,
-    epoch: GroupEpoch,
-    content_type: ContentType,
-    authenticated_data: VLBytes,
-    encrypted_sender_data: VLBytes,
-    ciphertext: VLBytes,
+    pub(super) group_id: GroupId,
+    pub(super) epoch: GroupEpoch,
+    pub(super) content_type: ContentType,
+    pub(super) authenticated_data: VLBytes,
+    pub(super) encrypted_sender_data: VLBytes,
+    pub(super) ciphertext: VLBytes,
 }
 
 impl PrivateMessageIn {
@@ -200,20 +209,25 @@ impl PrivateMessageIn {
     }
 
     /// Get the `group_id` in the `PrivateMessage`.
-    pub(crate) fn group_id(&self) -> &GroupId {
+    pub fn group_id(&self) -> &GroupId {
         &self.group_id
     }
 
     /// Get the `epoch` in the `PrivateMessage`.
-    pub(crate) fn epoch(&self) -> GroupEpoch {
+    pub fn epoch(&self) -> GroupEpoch {
         self.epoch
     }
 
     /// Get the `content_type` in the `PrivateMessage`.
-    pub(crate) fn content_type(&self) -> ContentType {
+    pub fn content_type(&self) -> ContentType {
         self.content_type
     }
 
+    /// Get the ciphertext.
+    pub(crate) fn ciphertext(&self) -> &[u8] {
+        self.ciphertext.as_slice()
+    }
+
     /// Set the ciphertext.
     #[cfg(test)]
     pub(crate) fn set_ciphertext(&mut self, ciphertext: Vec<u8>) {
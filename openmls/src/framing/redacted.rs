@@ -0,0 +1,270 @@
+//! # Redacted debug output
+//!
+//! The derived [`Debug`] implementations of messages print ciphertexts, tags
+//! and other key-derived values. This module provides the [`DebugRedacted`]
+//! trait, which only prints the structure of a message, together with sizes,
+//! epochs and hashes. Its output can be logged safely, e.g. at debug level in
+//! production.
+//!
+//! ```
+//! use openmls::prelude::*;
+//!
+//! fn log_message(message: &MlsMessageIn) {
+//!     log::debug!("Received message: {:?}", message.redacted());
+//! }
+//! ```
+
+use std::fmt::{self, Debug, Display, Formatter};
+
+use super::*;
+use crate::{
+    extensions::Extensions,
+    group::errors::ProcessMessageError,
+    key_packages::{KeyPackage, KeyPackageIn},
+    messages::group_info::{GroupInfo, VerifiableGroupInfo},
+};
+
+/// Types that can be printed without revealing ciphertexts or key-derived
+/// values.
+pub trait DebugRedacted {
+    /// Formats the redacted representation of `self`.
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result;
+
+    /// Returns a wrapper that implements [`Debug`] and [`Display`] using the
+    /// redacted representation of `self`.
+    fn redacted(&self) -> Redacted<'_, Self> {
+        Redacted(self)
+    }
+}
+
+/// A wrapper that formats the inner value using [`DebugRedacted`].
+pub struct Redacted<'a, T: ?Sized>(&'a T);
+
+impl<T: DebugRedacted + ?Sized> Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_redacted(f)
+    }
+}
+
+impl<T: DebugRedacted + ?Sized> Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_redacted(f)
+    }
+}
+
+/// Prints a byte string as hex.
+struct Hex<'a>(&'a [u8]);
+
+impl Debug for Hex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for b in self.0 {
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the length of a byte string instead of its content.
+struct Len(usize);
+
+impl Debug for Len {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", self.0)
+    }
+}
+
+/// Prints the types of the extensions.
+struct ExtensionTypes<'a>(&'a Extensions);
+
+impl Debug for ExtensionTypes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|extension| extension.extension_type()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for MlsMessageIn {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MlsMessageIn")
+            .field("version", &self.version)
+            .field("body", &self.body.redacted())
+            .finish()
+    }
+}
+
+impl DebugRedacted for MlsMessageBodyIn {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MlsMessageBodyIn::PublicMessage(m) => m.fmt_redacted(f),
+            MlsMessageBodyIn::PrivateMessage(m) => m.fmt_redacted(f),
+            MlsMessageBodyIn::Welcome(m) => m.fmt_redacted(f),
+            MlsMessageBodyIn::GroupInfo(m) => m.fmt_redacted(f),
+            MlsMessageBodyIn::KeyPackage(m) => m.fmt_redacted(f),
+        }
+    }
+}
+
+impl DebugRedacted for MlsMessageOut {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MlsMessageOut")
+            .field("version", &self.version)
+            .field("body", &self.body.redacted())
+            .finish()
+    }
+}
+
+impl DebugRedacted for MlsMessageBodyOut {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MlsMessageBodyOut::PublicMessage(m) => m.fmt_redacted(f),
+            MlsMessageBodyOut::PrivateMessage(m) => m.fmt_redacted(f),
+            MlsMessageBodyOut::Welcome(m) => m.fmt_redacted(f),
+            MlsMessageBodyOut::GroupInfo(m) => m.fmt_redacted(f),
+            MlsMessageBodyOut::KeyPackage(m) => m.fmt_redacted(f),
+        }
+    }
+}
+
+impl DebugRedacted for PublicMessage {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicMessage")
+            .field("group_id", &Hex(self.content.group_id.as_slice()))
+            .field("epoch", &self.content.epoch)
+            .field("sender", &self.content.sender)
+            .field("content_type", &self.content_type())
+            .field(
+                "authenticated_data",
+                &Len(self.content.authenticated_data.as_slice().len()),
+            )
+            .field("confirmation_tag", &self.auth.confirmation_tag.is_some())
+            .field("membership_tag", &self.membership_tag.is_some())
+            .finish()
+    }
+}
+
+impl DebugRedacted for PublicMessageIn {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicMessageIn")
+            .field("group_id", &Hex(self.content.group_id.as_slice()))
+            .field("epoch", &self.content.epoch)
+            .field("sender", &self.content.sender)
+            .field("content_type", &self.content_type())
+            .field(
+                "authenticated_data",
+                &Len(self.content.authenticated_data.as_slice().len()),
+            )
+            .field("confirmation_tag", &self.auth.confirmation_tag.is_some())
+            .field("membership_tag", &self.membership_tag.is_some())
+            .finish()
+    }
+}
+
+impl DebugRedacted for PrivateMessage {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateMessage")
+            .field("group_id", &Hex(self.group_id.as_slice()))
+            .field("epoch", &self.epoch)
+            .field("content_type", &self.content_type)
+            .field(
+                "authenticated_data",
+                &Len(self.authenticated_data.as_slice().len()),
+            )
+            .field(
+                "encrypted_sender_data",
+                &Len(self.encrypted_sender_data.as_slice().len()),
+            )
+            .field("ciphertext", &Len(self.ciphertext.as_slice().len()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for PrivateMessageIn {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateMessageIn")
+            .field("group_id", &Hex(self.group_id.as_slice()))
+            .field("epoch", &self.epoch)
+            .field("content_type", &self.content_type)
+            .field(
+                "authenticated_data",
+                &Len(self.authenticated_data.as_slice().len()),
+            )
+            .field(
+                "encrypted_sender_data",
+                &Len(self.encrypted_sender_data.as_slice().len()),
+            )
+            .field("ciphertext", &Len(self.ciphertext.as_slice().len()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for Welcome {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let new_members: Vec<_> = self
+            .secrets()
+            .iter()
+            .map(|secrets| secrets.new_member())
+            .collect();
+        f.debug_struct("Welcome")
+            .field("ciphersuite", &self.ciphersuite())
+            .field("new_members", &new_members)
+            .field(
+                "encrypted_group_info",
+                &Len(self.encrypted_group_info().len()),
+            )
+            .finish()
+    }
+}
+
+impl DebugRedacted for GroupInfo {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let group_context = self.group_context();
+        f.debug_struct("GroupInfo")
+            .field("group_id", &Hex(group_context.group_id().as_slice()))
+            .field("epoch", &group_context.epoch())
+            .field("ciphersuite", &group_context.ciphersuite())
+            .field("extensions", &ExtensionTypes(self.extensions()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for VerifiableGroupInfo {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerifiableGroupInfo")
+            .field("group_id", &Hex(self.group_id().as_slice()))
+            .field("epoch", &self.epoch())
+            .field("ciphersuite", &self.ciphersuite())
+            .field("signer", &self.signer())
+            .field("extensions", &ExtensionTypes(self.extensions()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for KeyPackage {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPackage")
+            .field("ciphersuite", &self.ciphersuite())
+            .field("extensions", &ExtensionTypes(self.extensions()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for KeyPackageIn {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPackageIn")
+            .field("protocol_version", &self.protocol_version())
+            .field("ciphersuite", &self.ciphersuite())
+            .field("extensions", &ExtensionTypes(self.extensions()))
+            .finish()
+    }
+}
+
+impl DebugRedacted for ProcessMessageError {
+    fn fmt_redacted(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // The error messages only describe what went wrong and never contain
+        // message content.
+        f.debug_tuple("ProcessMessageError")
+            .field(&format_args!("{self}"))
+            .finish()
+    }
+}
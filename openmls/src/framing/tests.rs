@@ -18,6 +18,7 @@ use crate::{
     storage::OpenMlsProvider,
     test_utils::frankenstein::*,
     tree::{secret_tree::SecretTree, sender_ratchet::SenderRatchetConfiguration},
+    versions::ProtocolVersion,
 };
 
 /// This tests serializing/deserializing PublicMessage
@@ -566,3 +567,98 @@ fn key_package_version() {
     // Expect a decoding  error
     matches!(err, tls_codec::Error::DecodingError(_));
 }
+
+/// Test that the redacted representation of messages doesn't contain the
+/// ciphertext.
+#[openmls_test::openmls_test]
+fn redacted_debug_output() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let message = alice_group
+        .create_message(provider, &alice_signer, b"Hello, Bob!")
+        .expect("Could not create message.");
+    let MlsMessageBodyOut::PrivateMessage(private_message) = &message.body else {
+        panic!("Expected a PrivateMessage.");
+    };
+    let ciphertext: String = private_message
+        .ciphertext
+        .as_slice()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    let redacted = format!("{:?}", message.redacted());
+    assert!(redacted.contains(&format!(
+        "ciphertext: {} bytes",
+        private_message.ciphertext.as_slice().len()
+    )));
+    assert!(!redacted.contains(&ciphertext));
+
+    // The same holds for the incoming message.
+    let message_in = MlsMessageIn::from(message.clone());
+    let redacted_in = format!("{}", message_in.redacted());
+    assert!(redacted_in.contains("PrivateMessageIn"));
+    assert!(!redacted_in.contains(&ciphertext));
+}
+
+#[openmls_test::openmls_test]
+fn classify_payloads() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let private_message = alice_group
+        .create_message(provider, &alice_signer, b"Hello")
+        .unwrap()
+        .tls_serialize_detached()
+        .unwrap();
+    let classification = classify_payload(&private_message).unwrap();
+    assert_eq!(classification.kind(), PayloadKind::PrivateMessage);
+    assert_eq!(
+        classification.kind().wire_format(),
+        Some(WireFormat::PrivateMessage)
+    );
+    assert_eq!(
+        classification.protocol_version(),
+        Some(ProtocolVersion::Mls10)
+    );
+    assert_eq!(classification.ciphersuite(), None);
+
+    let group_info = alice_group
+        .export_group_info(provider, &alice_signer, true)
+        .unwrap()
+        .tls_serialize_detached()
+        .unwrap();
+    let classification = classify_payload(&group_info).unwrap();
+    assert_eq!(classification.kind(), PayloadKind::GroupInfo);
+    assert_eq!(classification.ciphersuite(), Some(ciphersuite));
+
+    let (key_package, _, _) = key_package(ciphersuite, provider);
+    let key_package = MlsMessageOut::from(key_package)
+        .tls_serialize_detached()
+        .unwrap();
+    let classification = classify_payload(&key_package).unwrap();
+    assert_eq!(classification.kind(), PayloadKind::KeyPackage);
+    assert_eq!(classification.ciphersuite(), Some(ciphersuite));
+
+    let ratchet_tree = alice_group
+        .export_ratchet_tree()
+        .tls_serialize_detached()
+        .unwrap();
+    let classification = classify_payload(&ratchet_tree).unwrap();
+    assert_eq!(classification.kind(), PayloadKind::RatchetTree);
+    assert_eq!(classification.protocol_version(), None);
+
+    // Unknown versions are reported without looking at the body.
+    let mut future_message = private_message.clone();
+    future_message[..2].copy_from_slice(&2u16.to_be_bytes());
+    let classification = classify_payload(&future_message).unwrap();
+    assert_eq!(classification.kind(), PayloadKind::PrivateMessage);
+    assert_eq!(
+        classification.protocol_version(),
+        Some(ProtocolVersion::Other(2))
+    );
+
+    assert_eq!(classify_payload(&[]), None);
+    assert_eq!(classify_payload(b"not an MLS payload"), None);
+}
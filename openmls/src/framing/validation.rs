@@ -25,6 +25,7 @@
 
 use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
 use proposal_store::QueuedProposal;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     binary_tree::LeafNodeIndex,
@@ -32,7 +33,6 @@ use crate::{
     error::LibraryError,
     extensions::ExternalSendersExtension,
     group::{errors::ValidationError, mls_group::staged_commit::StagedCommit},
-    tree::sender_ratchet::SenderRatchetConfiguration,
     versions::ProtocolVersion,
 };
 
@@ -50,9 +50,10 @@ use super::{
 ///  - ValSem005
 ///  - ValSem007
 ///  - ValSem009
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct DecryptedMessage {
     verifiable_content: VerifiableAuthenticatedContentIn,
+    generation: Option<u32>,
 }
 
 impl DecryptedMessage {
@@ -86,16 +87,15 @@ impl DecryptedMessage {
 
         let verifiable_content = public_message.into_verifiable_content(serialized_context);
 
-        Self::from_verifiable_content(verifiable_content)
+        Self::from_verifiable_content(verifiable_content, None)
     }
 
     /// Constructs a [DecryptedMessage] from a [PrivateMessage] by attempting to decrypt it
     /// to a [VerifiableAuthenticatedContent] first.
     pub(crate) fn from_inbound_ciphertext(
-        ciphertext: PrivateMessageIn,
+        ciphertext: &PrivateMessageIn,
         crypto: &impl OpenMlsCrypto,
         group: &mut MlsGroup,
-        sender_ratchet_configuration: &SenderRatchetConfiguration,
     ) -> Result<Self, ValidationError> {
         // This will be refactored with #265.
         let ciphersuite = group.ciphersuite();
@@ -107,20 +107,32 @@ impl DecryptedMessage {
         let sender_data = ciphertext.sender_data(message_secrets, crypto, ciphersuite)?;
         // Check if we are the sender
         if sender_data.leaf_index == group.own_leaf_index() {
+            if ciphertext.content_type() == ContentType::Application {
+                return Err(ValidationError::OwnMessageEchoed {
+                    epoch: ciphertext.epoch(),
+                    generation: sender_data.generation,
+                });
+            }
             return Err(ValidationError::CannotDecryptOwnMessage);
         }
+        // The sender ratchet configuration may be overridden for the sender.
+        let sender_ratchet_configuration = group
+            .configuration()
+            .sender_ratchet_configuration_for(sender_data.leaf_index)
+            .clone();
         let message_secrets = group
             .message_secrets_mut(ciphertext.epoch())
             .map_err(|_| MessageDecryptionError::AeadError)?;
+        let generation = sender_data.generation;
         let verifiable_content = ciphertext.to_verifiable_content(
             ciphersuite,
             crypto,
             message_secrets,
             sender_data.leaf_index,
-            sender_ratchet_configuration,
+            &sender_ratchet_configuration,
             sender_data,
         )?;
-        Self::from_verifiable_content(verifiable_content)
+        Self::from_verifiable_content(verifiable_content, Some(generation))
     }
 
     // Internal constructor function. Does the following checks:
@@ -129,6 +141,7 @@ impl DecryptedMessage {
     // - Ensures application messages were originally PrivateMessage messages
     fn from_verifiable_content(
         verifiable_content: VerifiableAuthenticatedContentIn,
+        generation: Option<u32>,
     ) -> Result<Self, ValidationError> {
         // ValSem009
         if verifiable_content.content_type() == ContentType::Commit
@@ -145,7 +158,10 @@ impl DecryptedMessage {
                 return Err(LibraryError::custom("Expected sender to be member.").into());
             }
         }
-        Ok(DecryptedMessage { verifiable_content })
+        Ok(DecryptedMessage {
+            verifiable_content,
+            generation,
+        })
     }
 
     /// Gets the correct credential from the message depending on the sender type.
@@ -221,6 +237,7 @@ pub(crate) struct UnverifiedMessage {
     credential: Credential,
     sender_pk: OpenMlsSignaturePublicKey,
     sender_context: Option<SenderContext>,
+    generation: Option<u32>,
 }
 
 impl UnverifiedMessage {
@@ -236,6 +253,7 @@ impl UnverifiedMessage {
             credential,
             sender_pk,
             sender_context,
+            generation: decrypted_message.generation,
         }
     }
 
@@ -262,6 +280,11 @@ impl UnverifiedMessage {
     pub(crate) fn content_type(&self) -> ContentType {
         self.verifiable_content.content_type()
     }
+
+    /// Get the sender ratchet generation if the message was a private message.
+    pub(crate) fn generation(&self) -> Option<u32> {
+        self.generation
+    }
 }
 
 /// A message that has passed all syntax and semantics checks.
@@ -273,10 +296,13 @@ pub struct ProcessedMessage {
     authenticated_data: Vec<u8>,
     content: ProcessedMessageContent,
     credential: Credential,
+    wire_format: WireFormat,
+    generation: Option<u32>,
 }
 
 impl ProcessedMessage {
     /// Create a new `ProcessedMessage`.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         group_id: GroupId,
         epoch: GroupEpoch,
@@ -284,6 +310,8 @@ impl ProcessedMessage {
         authenticated_data: Vec<u8>,
         content: ProcessedMessageContent,
         credential: Credential,
+        wire_format: WireFormat,
+        generation: Option<u32>,
     ) -> Self {
         Self {
             group_id,
@@ -292,6 +320,8 @@ impl ProcessedMessage {
             authenticated_data,
             content,
             credential,
+            wire_format,
+            generation,
         }
     }
 
@@ -310,6 +340,52 @@ impl ProcessedMessage {
         &self.sender
     }
 
+    /// Returns the leaf index of the sender, or `None` if the sender is not a
+    /// member of the group.
+    pub fn sender_leaf_index(&self) -> Option<LeafNodeIndex> {
+        self.sender.as_member()
+    }
+
+    /// Returns the wire format the message was received in.
+    pub fn wire_format(&self) -> WireFormat {
+        self.wire_format
+    }
+
+    /// Returns the content type of the message.
+    pub fn content_type(&self) -> ContentType {
+        match self.content {
+            ProcessedMessageContent::ApplicationMessage(_) => ContentType::Application,
+            ProcessedMessageContent::ProposalMessage(_)
+            | ProcessedMessageContent::ExternalJoinProposalMessage(_) => ContentType::Proposal,
+            ProcessedMessageContent::StagedCommitMessage(_) => ContentType::Commit,
+        }
+    }
+
+    /// Returns the generation of the sender ratchet that was used to encrypt
+    /// the message, or `None` if the message was not a private message.
+    ///
+    /// Together with the [`epoch()`](Self::epoch), the
+    /// [`sender_leaf_index()`](Self::sender_leaf_index) and the
+    /// [`content_type()`](Self::content_type), the generation uniquely
+    /// identifies a private message.
+    pub fn generation(&self) -> Option<u32> {
+        self.generation
+    }
+
+    /// Returns the [`MessageId`] of the message, or `None` if the message was
+    /// not a private message.
+    pub fn message_id(&self) -> Option<MessageId> {
+        let sender = self.sender_leaf_index()?;
+        let generation = self.generation?;
+        Some(MessageId::new(
+            self.group_id.clone(),
+            self.epoch,
+            sender,
+            self.content_type(),
+            generation,
+        ))
+    }
+
     /// Returns the additional authenticated data (AAD) of the message.
     pub fn aad(&self) -> &[u8] {
         &self.authenticated_data
@@ -0,0 +1,151 @@
+//! # Pre-flight checks for adding members
+//!
+//! This module contains the [`AddCheckReport`], which is the result of running
+//! the validation an Add commit would perform on a candidate [`KeyPackage`]
+//! against the current state of a group. The checks don't modify the group, so
+//! that applications can vet invitees before creating a commit.
+
+use std::collections::HashMap;
+
+use openmls_traits::crypto::OpenMlsCrypto;
+
+use super::capability_report::{missing_capabilities, MissingCapability};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    credentials::Credential,
+    group::{Member, PublicGroup},
+    key_packages::{errors::KeyPackageVerifyError, KeyPackage, KeyPackageIn},
+};
+
+/// The party a candidate conflicts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddCheckConflict {
+    /// The member at the given leaf index.
+    Member(LeafNodeIndex),
+    /// The candidate at the given index in the list of checked key packages.
+    Candidate(usize),
+}
+
+/// A reason why a candidate [`KeyPackage`] can't be added to a group.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddCheckFailure {
+    /// The key package is invalid, e.g. because its signature is invalid or
+    /// its lifetime expired.
+    InvalidKeyPackage(KeyPackageVerifyError),
+    /// The candidate doesn't support a capability needed to join the group.
+    MissingCapability(MissingCapability),
+    /// The candidate uses the same credential as a member or another candidate.
+    DuplicateIdentity(AddCheckConflict),
+    /// The candidate uses the same signature key as a member or another
+    /// candidate.
+    DuplicateSignatureKey(AddCheckConflict),
+    /// The candidate uses the same encryption key as a member or another
+    /// candidate.
+    DuplicateEncryptionKey(AddCheckConflict),
+    /// The candidate uses the same init key as another candidate.
+    DuplicateInitKey(AddCheckConflict),
+}
+
+/// The result of checking whether a candidate [`KeyPackage`] can be added to a
+/// group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddCheckReport {
+    index: usize,
+    failures: Vec<AddCheckFailure>,
+}
+
+impl AddCheckReport {
+    /// Returns the index of the candidate in the list of checked key packages.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the reasons why the candidate can't be added to the group.
+    pub fn failures(&self) -> &[AddCheckFailure] {
+        &self.failures
+    }
+
+    /// Returns `true` if the candidate can be added to the group.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Runs the checks of an Add commit for each of the `key_packages` against
+/// the `group`. If two candidates conflict with each other, the conflict is
+/// reported for the later one.
+pub(crate) fn check_adds(
+    crypto: &impl OpenMlsCrypto,
+    group: &PublicGroup,
+    key_packages: &[KeyPackage],
+) -> Vec<AddCheckReport> {
+    let mut identities: Vec<(&Credential, AddCheckConflict)> = Vec::new();
+    let mut signature_keys: HashMap<Vec<u8>, AddCheckConflict> = HashMap::new();
+    let mut encryption_keys: HashMap<Vec<u8>, AddCheckConflict> = HashMap::new();
+    let mut init_keys: HashMap<Vec<u8>, AddCheckConflict> = HashMap::new();
+
+    let members: Vec<Member> = group.treesync().full_leave_members().collect();
+    for member in &members {
+        let conflict = AddCheckConflict::Member(member.index);
+        identities.push((&member.credential, conflict));
+        signature_keys.insert(member.signature_key.clone(), conflict);
+        encryption_keys.insert(member.encryption_key.clone(), conflict);
+    }
+
+    let mut reports = Vec::with_capacity(key_packages.len());
+    for (index, key_package) in key_packages.iter().enumerate() {
+        let mut failures = Vec::new();
+        let leaf_node = key_package.leaf_node();
+
+        // https://validation.openmls.tech/#valn0108
+        // https://validation.openmls.tech/#valn0201
+        // https://validation.openmls.tech/#valn0203
+        // https://validation.openmls.tech/#valn0204
+        if let Err(e) = KeyPackageIn::from(key_package.clone()).validate(crypto, group.version()) {
+            failures.push(AddCheckFailure::InvalidKeyPackage(e));
+        }
+
+        // https://validation.openmls.tech/#valn0103
+        // https://validation.openmls.tech/#valn0104
+        failures.extend(
+            missing_capabilities(group, key_package)
+                .into_iter()
+                .map(AddCheckFailure::MissingCapability),
+        );
+
+        let conflict = AddCheckConflict::Candidate(index);
+        if let Some((_, existing)) = identities
+            .iter()
+            .find(|(credential, _)| *credential == leaf_node.credential())
+        {
+            failures.push(AddCheckFailure::DuplicateIdentity(*existing));
+        } else {
+            identities.push((leaf_node.credential(), conflict));
+        }
+
+        // https://validation.openmls.tech/#valn0111
+        // https://validation.openmls.tech/#valn0112
+        let signature_key = leaf_node.signature_key().as_slice().to_vec();
+        if let Some(existing) = signature_keys.get(&signature_key) {
+            failures.push(AddCheckFailure::DuplicateSignatureKey(*existing));
+        } else {
+            signature_keys.insert(signature_key, conflict);
+        }
+        let encryption_key = leaf_node.encryption_key().key().as_slice().to_vec();
+        if let Some(existing) = encryption_keys.get(&encryption_key) {
+            failures.push(AddCheckFailure::DuplicateEncryptionKey(*existing));
+        } else {
+            encryption_keys.insert(encryption_key, conflict);
+        }
+        let init_key = key_package.hpke_init_key().as_slice().to_vec();
+        if let Some(existing) = init_keys.get(&init_key) {
+            failures.push(AddCheckFailure::DuplicateInitKey(*existing));
+        } else {
+            init_keys.insert(init_key, conflict);
+        }
+
+        reports.push(AddCheckReport { index, failures });
+    }
+
+    reports
+}
@@ -0,0 +1,181 @@
+//! # Application protocol version negotiation
+//!
+//! This module contains [`AppVersionNegotiation`], a small framework for
+//! agreeing on the version of the application protocol spoken in a group.
+//!
+//! Each member advertises the application protocol versions it supports in a
+//! leaf node extension, and the version currently in use is stored in a
+//! group context extension. Both extensions use application-defined extension
+//! types from the private use range, which must also be listed in the
+//! [`Capabilities`](crate::treesync::node::leaf_node::Capabilities) of the
+//! members, see [`AppVersionNegotiation::extension_types()`].
+//!
+//! To upgrade, a member computes the new group context extensions with
+//! [`MlsGroup::app_version_upgrade()`](crate::group::MlsGroup::app_version_upgrade)
+//! and commits them, e.g. with
+//! [`MlsGroup::update_group_context_extensions()`](crate::group::MlsGroup::update_group_context_extensions).
+
+use tls_codec::{Deserialize as _, Serialize as _};
+
+use crate::{
+    extensions::{
+        Extension, ExtensionType, Extensions, RequiredCapabilitiesExtension, UnknownExtension,
+    },
+    group::{errors::AppVersionError, GroupContext, PublicGroup},
+    treesync::LeafNode,
+};
+
+/// Negotiation of the application protocol version of a group. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppVersionNegotiation {
+    group_context_type: u16,
+    leaf_node_type: u16,
+}
+
+impl AppVersionNegotiation {
+    /// Creates a negotiation that stores the active version in the group
+    /// context extension of type `group_context_type` and the supported
+    /// versions in the leaf node extension of type `leaf_node_type`. Both
+    /// types should be taken from the private use range (`0xff00`-`0xffff`).
+    pub const fn new(group_context_type: u16, leaf_node_type: u16) -> Self {
+        Self {
+            group_context_type,
+            leaf_node_type,
+        }
+    }
+
+    /// Returns the extension types that members have to list in the
+    /// capabilities of their leaf node.
+    pub fn extension_types(&self) -> [ExtensionType; 2] {
+        [
+            ExtensionType::Unknown(self.group_context_type),
+            ExtensionType::Unknown(self.leaf_node_type),
+        ]
+    }
+
+    /// Returns the leaf node extension advertising the `supported_versions`.
+    pub fn leaf_node_extension(
+        &self,
+        supported_versions: &[u16],
+    ) -> Result<Extension, AppVersionError> {
+        let extension_data = supported_versions
+            .to_vec()
+            .tls_serialize_detached()
+            .map_err(|_| AppVersionError::Malformed)?;
+        Ok(Extension::Unknown(
+            self.leaf_node_type,
+            UnknownExtension(extension_data),
+        ))
+    }
+
+    /// Returns the group context extension setting the active `version`.
+    pub fn group_context_extension(&self, version: u16) -> Extension {
+        Extension::Unknown(
+            self.group_context_type,
+            UnknownExtension(version.to_be_bytes().to_vec()),
+        )
+    }
+
+    /// Returns the versions supported by the owner of the `leaf_node`, or
+    /// `None` if the leaf node doesn't advertise any.
+    pub fn supported_versions(
+        &self,
+        leaf_node: &LeafNode,
+    ) -> Result<Option<Vec<u16>>, AppVersionError> {
+        leaf_node
+            .extensions()
+            .unknown(self.leaf_node_type)
+            .map(|extension| {
+                Vec::<u16>::tls_deserialize_exact(&extension.0)
+                    .map_err(|_| AppVersionError::Malformed)
+            })
+            .transpose()
+    }
+
+    /// Returns the version that is active in the group with the given
+    /// `group_context`, or `None` if no version was negotiated yet.
+    pub fn active_version(
+        &self,
+        group_context: &GroupContext,
+    ) -> Result<Option<u16>, AppVersionError> {
+        group_context
+            .extensions()
+            .unknown(self.group_context_type)
+            .map(|extension| {
+                <[u8; 2]>::try_from(extension.0.as_slice())
+                    .map(u16::from_be_bytes)
+                    .map_err(|_| AppVersionError::Malformed)
+            })
+            .transpose()
+    }
+
+    /// Returns the versions supported by all members of the `group` in
+    /// ascending order. Members that don't advertise any versions don't
+    /// support any.
+    pub fn common_versions(&self, group: &PublicGroup) -> Result<Vec<u16>, AppVersionError> {
+        let mut common: Option<Vec<u16>> = None;
+        for leaf_node in group.treesync().full_leaves() {
+            let supported = self.supported_versions(leaf_node)?.unwrap_or_default();
+            common = Some(match common {
+                Some(common) => common
+                    .into_iter()
+                    .filter(|version| supported.contains(version))
+                    .collect(),
+                None => supported,
+            });
+        }
+
+        let mut common = common.unwrap_or_default();
+        common.sort_unstable();
+        common.dedup();
+        Ok(common)
+    }
+
+    /// Returns the group context extensions of the `group` with the active
+    /// version set to the highest version supported by all members, or `None`
+    /// if that version is already active or there is no common version.
+    ///
+    /// The returned extensions can be committed with
+    /// [`MlsGroup::update_group_context_extensions()`](crate::group::MlsGroup::update_group_context_extensions)
+    /// or proposed with
+    /// [`MlsGroup::propose_group_context_extensions()`](crate::group::MlsGroup::propose_group_context_extensions).
+    pub fn upgrade_extensions(
+        &self,
+        group: &PublicGroup,
+    ) -> Result<Option<Extensions>, AppVersionError> {
+        let Some(&highest) = self.common_versions(group)?.last() else {
+            return Ok(None);
+        };
+        if let Some(active) = self.active_version(group.group_context())? {
+            if active >= highest {
+                return Ok(None);
+            }
+        }
+
+        let mut extensions = group.group_context().extensions().clone();
+        extensions.add_or_replace(self.group_context_extension(highest));
+
+        // Group context extensions have to be required, so that all members
+        // and new joiners support them.
+        let required_capabilities = extensions
+            .required_capabilities()
+            .cloned()
+            .unwrap_or_default();
+        let mut extension_types = required_capabilities.extension_types().to_vec();
+        for extension_type in self.extension_types() {
+            if !extension_types.contains(&extension_type) {
+                extension_types.push(extension_type);
+            }
+        }
+        extensions.add_or_replace(Extension::RequiredCapabilities(
+            RequiredCapabilitiesExtension::new(
+                &extension_types,
+                required_capabilities.proposal_types(),
+                required_capabilities.credential_types(),
+            ),
+        ));
+
+        Ok(Some(extensions))
+    }
+}
@@ -0,0 +1,202 @@
+//! # Capability reports for prospective members
+//!
+//! This module contains the [`CapabilityReport`], which describes whether a
+//! set of candidate [`KeyPackage`]s could be added to a group. For every
+//! candidate that can't be added, the report lists the capabilities the
+//! candidate (or the group) is missing, so that applications can explain the
+//! failure to the user before attempting a commit.
+
+use openmls_traits::types::{Ciphersuite, VerifiableCiphersuite};
+
+use crate::{
+    binary_tree::LeafNodeIndex, credentials::CredentialType, extensions::ExtensionType,
+    group::PublicGroup, key_packages::KeyPackage, messages::proposals::ProposalType,
+    treesync::node::leaf_node::Capabilities, versions::ProtocolVersion,
+};
+
+/// A reason why a candidate [`KeyPackage`] can't be added to a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingCapability {
+    /// The key package was created for a different ciphersuite than the one
+    /// used by the group.
+    KeyPackageCiphersuite(Ciphersuite),
+    /// The candidate doesn't support the protocol version of the group.
+    Version(ProtocolVersion),
+    /// The candidate doesn't support the ciphersuite of the group.
+    Ciphersuite(Ciphersuite),
+    /// The candidate doesn't support an extension type required by the group.
+    Extension(ExtensionType),
+    /// The candidate doesn't support a proposal type required by the group.
+    Proposal(ProposalType),
+    /// The candidate doesn't support a credential type that is required by
+    /// the group or used by one of its members.
+    Credential(CredentialType),
+    /// The member at leaf index `member` doesn't support the credential type
+    /// of the candidate.
+    CredentialNotSupportedByMember {
+        /// The leaf index of the member.
+        member: LeafNodeIndex,
+        /// The credential type of the candidate.
+        credential_type: CredentialType,
+    },
+}
+
+/// A candidate [`KeyPackage`] that can't be added to the group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleCandidate {
+    index: usize,
+    missing: Vec<MissingCapability>,
+}
+
+impl IncompatibleCandidate {
+    /// Returns the index of the candidate in the list of key packages passed
+    /// to [`PublicGroup::capability_report()`].
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the reasons why the candidate can't be added to the group.
+    pub fn missing(&self) -> &[MissingCapability] {
+        &self.missing
+    }
+}
+
+/// The result of checking a set of candidate [`KeyPackage`]s against the
+/// capabilities of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityReport {
+    intersection: Capabilities,
+    incompatible: Vec<IncompatibleCandidate>,
+}
+
+impl CapabilityReport {
+    /// Checks the `key_packages` against the `group`.
+    pub(crate) fn new(group: &PublicGroup, key_packages: &[KeyPackage]) -> Self {
+        let mut intersection = group
+            .treesync()
+            .full_leaves()
+            .map(|leaf| leaf.capabilities())
+            .fold(None, |acc: Option<Capabilities>, capabilities| match acc {
+                Some(acc) => Some(acc.intersection(capabilities)),
+                None => Some(capabilities.clone()),
+            })
+            .unwrap_or_else(Capabilities::empty);
+        let mut incompatible = Vec::new();
+
+        for (index, key_package) in key_packages.iter().enumerate() {
+            let missing = missing_capabilities(group, key_package);
+            if missing.is_empty() {
+                intersection = intersection.intersection(key_package.leaf_node().capabilities());
+            } else {
+                incompatible.push(IncompatibleCandidate { index, missing });
+            }
+        }
+
+        Self {
+            intersection,
+            incompatible,
+        }
+    }
+
+    /// Returns the capabilities supported by all current members of the group
+    /// and all compatible candidates.
+    pub fn intersection(&self) -> &Capabilities {
+        &self.intersection
+    }
+
+    /// Returns the candidates that can't be added to the group.
+    pub fn incompatible(&self) -> &[IncompatibleCandidate] {
+        &self.incompatible
+    }
+
+    /// Returns `true` if all candidates can be added to the group.
+    pub fn is_compatible(&self) -> bool {
+        self.incompatible.is_empty()
+    }
+
+    /// Returns the reasons why the candidate at `index` can't be added to the
+    /// group, or `None` if it can be added.
+    pub fn missing_capabilities(&self, index: usize) -> Option<&[MissingCapability]> {
+        self.incompatible
+            .iter()
+            .find(|candidate| candidate.index == index)
+            .map(IncompatibleCandidate::missing)
+    }
+}
+
+/// Returns the capabilities that prevent the `key_package` from being added to
+/// the `group`.
+pub(crate) fn missing_capabilities(
+    group: &PublicGroup,
+    key_package: &KeyPackage,
+) -> Vec<MissingCapability> {
+    let ciphersuite = group.ciphersuite();
+    let version = group.version();
+    let required_capabilities = group.required_capabilities();
+    let leaf_node = key_package.leaf_node();
+    let capabilities = leaf_node.capabilities();
+    let credential_type = leaf_node.credential().credential_type();
+    let mut missing = Vec::new();
+
+    if key_package.ciphersuite() != ciphersuite {
+        missing.push(MissingCapability::KeyPackageCiphersuite(
+            key_package.ciphersuite(),
+        ));
+    }
+    if !capabilities.contains_version(version) {
+        missing.push(MissingCapability::Version(version));
+    }
+    if !capabilities.contains_ciphersuite(VerifiableCiphersuite::from(ciphersuite)) {
+        missing.push(MissingCapability::Ciphersuite(ciphersuite));
+    }
+
+    let mut missing_credentials = Vec::new();
+    if let Some(required_capabilities) = required_capabilities {
+        missing.extend(
+            required_capabilities
+                .extension_types()
+                .iter()
+                .filter(|&e| !capabilities.contains_extension(*e))
+                .map(|e| MissingCapability::Extension(*e)),
+        );
+        missing.extend(
+            required_capabilities
+                .proposal_types()
+                .iter()
+                .filter(|&p| !capabilities.contains_proposal(*p))
+                .map(|p| MissingCapability::Proposal(*p)),
+        );
+        missing_credentials.extend(
+            required_capabilities
+                .credential_types()
+                .iter()
+                .filter(|&c| !capabilities.contains_credential(*c))
+                .copied(),
+        );
+    }
+
+    for (member, member_leaf) in group.treesync().full_leaves_with_index() {
+        let member_credential_type = member_leaf.credential().credential_type();
+        if !capabilities.contains_credential(member_credential_type)
+            && !missing_credentials.contains(&member_credential_type)
+        {
+            missing_credentials.push(member_credential_type);
+        }
+        if !member_leaf
+            .capabilities()
+            .contains_credential(credential_type)
+        {
+            missing.push(MissingCapability::CredentialNotSupportedByMember {
+                member,
+                credential_type,
+            });
+        }
+    }
+    missing.extend(
+        missing_credentials
+            .into_iter()
+            .map(MissingCapability::Credential),
+    );
+
+    missing
+}
@@ -0,0 +1,135 @@
+//! # Ciphersuite selection for new groups
+//!
+//! This module contains the [`CiphersuiteSelection`], which chooses the
+//! ciphersuite of a new group based on the [`KeyPackage`]s of its intended
+//! initial members. Members usually publish one key package per ciphersuite
+//! they support, so for every ciphersuite the creator is willing to use, the
+//! selection lists the members that would be excluded, together with the key
+//! packages of the other members that can be passed directly to
+//! [`MlsGroup::new_with_members()`](crate::group::MlsGroup::new_with_members).
+
+use openmls_traits::types::{Ciphersuite, VerifiableCiphersuite};
+
+use crate::key_packages::KeyPackage;
+
+/// A ciphersuite that was considered by a [`CiphersuiteSelection`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CiphersuiteCandidate {
+    ciphersuite: Ciphersuite,
+    key_packages: Vec<KeyPackage>,
+    excluded: Vec<usize>,
+}
+
+impl CiphersuiteCandidate {
+    /// Returns the ciphersuite.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the key packages of the members that support the ciphersuite,
+    /// in the order of the members passed to [`CiphersuiteSelection::new()`].
+    pub fn key_packages(&self) -> &[KeyPackage] {
+        &self.key_packages
+    }
+
+    /// Returns the indices of the members that would be excluded from a
+    /// group using the ciphersuite, because none of their key packages can be
+    /// used with it.
+    pub fn excluded(&self) -> &[usize] {
+        &self.excluded
+    }
+
+    /// Returns `true` if all members support the ciphersuite.
+    pub fn is_supported_by_all(&self) -> bool {
+        self.excluded.is_empty()
+    }
+}
+
+/// The result of matching the key packages of the intended initial members of
+/// a group against the ciphersuites its creator supports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CiphersuiteSelection {
+    candidates: Vec<CiphersuiteCandidate>,
+}
+
+impl CiphersuiteSelection {
+    /// Matches the key packages of the intended initial `members` against the
+    /// ciphersuites in `preference`, which lists the ciphersuites the creator
+    /// supports, most preferred first.
+    ///
+    /// Every entry of `members` contains the key packages of one member. A
+    /// member supports a ciphersuite if one of its key packages was created
+    /// for it. If a member has several such key packages, the first one is
+    /// used.
+    pub fn new(preference: &[Ciphersuite], members: &[impl AsRef<[KeyPackage]>]) -> Self {
+        let mut candidates: Vec<CiphersuiteCandidate> = Vec::with_capacity(preference.len());
+
+        for &ciphersuite in preference {
+            if candidates
+                .iter()
+                .any(|candidate| candidate.ciphersuite == ciphersuite)
+            {
+                continue;
+            }
+            let mut key_packages = Vec::new();
+            let mut excluded = Vec::new();
+            for (index, member) in members.iter().enumerate() {
+                match member
+                    .as_ref()
+                    .iter()
+                    .find(|key_package| supports(key_package, ciphersuite))
+                {
+                    Some(key_package) => key_packages.push(key_package.clone()),
+                    None => excluded.push(index),
+                }
+            }
+            candidates.push(CiphersuiteCandidate {
+                ciphersuite,
+                key_packages,
+                excluded,
+            });
+        }
+
+        Self { candidates }
+    }
+
+    /// Returns all candidates, in the order of preference.
+    pub fn candidates(&self) -> &[CiphersuiteCandidate] {
+        &self.candidates
+    }
+
+    /// Returns the candidate for `ciphersuite`, if it was considered.
+    pub fn candidate(&self, ciphersuite: Ciphersuite) -> Option<&CiphersuiteCandidate> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.ciphersuite == ciphersuite)
+    }
+
+    /// Returns the most preferred candidate that is supported by all members,
+    /// or `None` if there is no such ciphersuite.
+    pub fn best(&self) -> Option<&CiphersuiteCandidate> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.is_supported_by_all())
+    }
+
+    /// Returns the candidate that excludes the fewest members. Of several such
+    /// candidates, the most preferred one is returned. This is the same as
+    /// [`CiphersuiteSelection::best()`] if a ciphersuite is supported by all
+    /// members. Returns `None` only if no ciphersuite was considered.
+    pub fn best_effort(&self) -> Option<&CiphersuiteCandidate> {
+        self.candidates
+            .iter()
+            .min_by_key(|candidate| candidate.excluded.len())
+    }
+}
+
+/// Returns `true` if the `key_package` can be used in a group with the given
+/// `ciphersuite`.
+fn supports(key_package: &KeyPackage, ciphersuite: Ciphersuite) -> bool {
+    key_package.ciphersuite() == ciphersuite
+        && key_package
+            .leaf_node()
+            .capabilities()
+            .contains_ciphersuite(VerifiableCiphersuite::from(ciphersuite))
+}
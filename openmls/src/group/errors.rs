@@ -2,14 +2,16 @@
 //!
 //! This module contains errors that originate at lower levels and are partially re-exported in errors thrown by functions of the `MlsGroup` API.
 
+use std::sync::Arc;
+
 use thiserror::Error;
 
 pub use super::mls_group::errors::*;
-use super::public_group::errors::CreationFromExternalError;
+use super::{public_group::errors::CreationFromExternalError, GroupEpoch, GroupId, StaleLeaf};
 use crate::{
     ciphersuite::signable::SignatureError,
     error::LibraryError,
-    extensions::errors::{ExtensionError, InvalidExtensionError},
+    extensions::errors::{AppExtensionError, ExtensionError, InvalidExtensionError},
     framing::errors::MessageDecryptionError,
     key_packages::errors::{KeyPackageExtensionSupportError, KeyPackageVerifyError},
     messages::{group_info::GroupInfoError, GroupSecretsError},
@@ -87,6 +89,29 @@ pub enum WelcomeError<StorageError> {
     /// This error indicates the leaf node is invalid. See [`LeafNodeValidationError`] for more details.
     #[error(transparent)]
     LeafNodeValidation(#[from] LeafNodeValidationError),
+    /// The sender of the Welcome message was rejected by the application.
+    #[error("The sender of the Welcome message was rejected by the application.")]
+    WelcomeSenderRejected,
+    /// The group ID of the group was rejected by the processing policy.
+    #[error("The group ID of the group was rejected by the processing policy.")]
+    GroupIdRejected,
+    /// The group was rejected by the application's processing policy, see
+    /// [`ProcessingPolicy::validate_group_info()`](crate::group::ProcessingPolicy::validate_group_info()).
+    /// Contains the reason given by the policy.
+    #[error("The group was rejected by the application's processing policy: {0}")]
+    RejectedByPolicy(PolicyRejection),
+    /// The group of the Welcome message was already joined, e.g. because the
+    /// Welcome message was delivered twice. Contains the id of the existing
+    /// group.
+    #[error("The group {0:?} was already joined.")]
+    AlreadyJoined(GroupId),
+    /// See [`TreeSliceError`] for more details.
+    #[error(transparent)]
+    TreeSlice(#[from] TreeSliceError),
+    /// The [`EpochHooks`](crate::group::EpochHooks) failed for the epoch of
+    /// the Welcome message. The group was not stored.
+    #[error("The epoch hooks failed for the joined epoch.")]
+    EpochHookError(EpochHookError),
     /// This error indicates that an error occurred while reading or writing from/to storage.
     #[error("An error occurred when querying storage")]
     StorageError(StorageError),
@@ -123,6 +148,14 @@ pub enum ExternalCommitError<StorageError> {
     /// Credential is missing from external commit.
     #[error("Credential is missing from external commit.")]
     MissingCredential,
+    /// The group ID of the group was rejected by the processing policy.
+    #[error("The group ID of the group was rejected by the processing policy.")]
+    GroupIdRejected,
+    /// The group was rejected by the application's processing policy, see
+    /// [`ProcessingPolicy::validate_group_info()`](crate::group::ProcessingPolicy::validate_group_info()).
+    /// Contains the reason given by the policy.
+    #[error("The group was rejected by the application's processing policy: {0}")]
+    RejectedByPolicy(PolicyRejection),
     /// An erorr occurred when writing group to storage
     #[error("An error occurred when writing group to storage.")]
     StorageError(StorageError),
@@ -149,6 +182,10 @@ pub enum StageCommitError {
     /// Unable to determine commit path.
     #[error("Unable to determine commit path.")]
     RequiredPathNotFound,
+    /// The group requires every commit to contain an update path, but the
+    /// commit didn't contain one.
+    #[error("The group requires every commit to contain an update path, but the commit didn't contain one.")]
+    FullCommitRequired,
     /// The confirmation Tag is missing.
     #[error("The confirmation Tag is missing.")]
     ConfirmationTagMissing,
@@ -202,6 +239,62 @@ pub enum StageCommitError {
     /// See [`LeafNodeValidationError`] for more details.
     #[error(transparent)]
     LeafNodeValidation(#[from] LeafNodeValidationError),
+    /// The commit was rejected by the application's processing policy, see
+    /// [`ProcessingPolicy::validate_staged_commit()`](crate::group::ProcessingPolicy::validate_staged_commit()).
+    /// Contains the reason given by the policy.
+    #[error("The commit was rejected by the application's processing policy: {0}")]
+    RejectedByPolicy(PolicyRejection),
+}
+
+/// The reason why a commit was rejected by
+/// [`ProcessingPolicy::validate_staged_commit()`](crate::group::ProcessingPolicy::validate_staged_commit()),
+/// or a group by
+/// [`ProcessingPolicy::validate_group_info()`](crate::group::ProcessingPolicy::validate_group_info()).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum PolicyRejection {
+    /// The leaf of the committer is stale, see
+    /// [`MandatoryUpdatePolicy`](crate::group::MandatoryUpdatePolicy).
+    #[error("The leaf of the committer wasn't updated since epoch {}.", .0.last_update().as_u64())]
+    StaleCommitter(StaleLeaf),
+    /// The commit or the group info contains a registered application
+    /// extension with a malformed payload, see
+    /// [`AppExtensionRegistry`](crate::extensions::AppExtensionRegistry).
+    #[error(transparent)]
+    AppExtension(#[from] AppExtensionError),
+    /// The commit was rejected by a policy of the application.
+    #[error(transparent)]
+    Application(ApplicationPolicyError),
+}
+
+impl PolicyRejection {
+    /// Creates a [`PolicyRejection::Application`] from an error of the
+    /// application.
+    pub fn application(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Application(ApplicationPolicyError(Arc::new(error)))
+    }
+}
+
+/// An error of a policy of the application, see
+/// [`PolicyRejection::application()`].
+///
+/// The error of the application can be recovered with
+/// [`ApplicationPolicyError::downcast_ref()`]. Two errors are only equal if
+/// they are clones of each other.
+#[derive(Error, Debug, Clone)]
+#[error(transparent)]
+pub struct ApplicationPolicyError(Arc<dyn std::error::Error + Send + Sync>);
+
+impl ApplicationPolicyError {
+    /// Returns the error of the application if it is of type `E`.
+    pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref()
+    }
+}
+
+impl PartialEq for ApplicationPolicyError {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 /// Create commit error
@@ -248,6 +341,14 @@ pub enum CreateCommitError {
     /// See [`TreeSyncAddLeaf`] for more details.
     #[error(transparent)]
     TreeSyncAddLeaf(#[from] TreeSyncAddLeaf),
+    /// The commit requires an update path, but the path was set to be omitted
+    /// with [`UpdatePathInclusion::Never`](crate::group::UpdatePathInclusion::Never).
+    #[error("The commit requires an update path, but the path was set to be omitted.")]
+    UpdatePathRequired,
+    /// The onboarding data of the commit uses an extension type that is
+    /// defined by MLS.
+    #[error(transparent)]
+    AppExtension(#[from] AppExtensionError),
 }
 
 /// Stage commit error
@@ -344,6 +445,16 @@ pub enum ValidationError {
     /// Cannot decrypt own messages because the necessary key has been deleted according to the deletion schedule.
     #[error("Cannot decrypt own messages.")]
     CannotDecryptOwnMessage,
+    /// The message is an application message sent by this client and echoed
+    /// back by the DS. It can't be decrypted, because the key has been
+    /// deleted after encrypting it.
+    #[error("The message is an own application message (epoch {epoch}, generation {generation}).")]
+    OwnMessageEchoed {
+        /// The epoch the message was sent in.
+        epoch: GroupEpoch,
+        /// The generation of the sender ratchet the message was encrypted with.
+        generation: u32,
+    },
     /// See [`ExternalCommitValidationError`] for more details.
     #[error(transparent)]
     ExternalCommitValidation(#[from] ExternalCommitValidationError),
@@ -376,6 +487,11 @@ pub enum ProposalValidationError {
     /// The remove proposal referenced a non-existing member.
     #[error("The remove proposal referenced a non-existing member.")]
     UnknownMemberRemoval,
+    /// The remove proposal removes a protected member and was rejected by the
+    /// application's processing policy, see
+    /// [`ProcessingPolicy::admit_remove()`](crate::group::ProcessingPolicy::admit_remove()).
+    #[error("The remove proposal removes a protected member.")]
+    ProtectedMemberRemoval,
     /// Found an update from a non-member.
     #[error("Found an update from a non-member.")]
     UpdateFromNonMember,
@@ -402,6 +518,18 @@ pub enum ProposalValidationError {
     /// Regular Commits may not contain ExternalInit proposals, but one was found
     #[error("Found ExternalInit proposal in regular commit")]
     ExternalInitProposalInRegularCommit,
+    /// Found an ExternalInit proposal that was not sent inline in an external commit.
+    #[error("Found an ExternalInit proposal that was not sent inline in an external commit.")]
+    StandaloneExternalInitProposal,
+    /// See [`GroupContextExtensionsProposalValidationError`] for more details.
+    #[error(transparent)]
+    GroupContextExtensions(#[from] GroupContextExtensionsProposalValidationError),
+    /// A ReInit proposal was committed together with other proposals.
+    #[error("A ReInit proposal was committed together with other proposals.")]
+    ReInitWithOtherProposals,
+    /// The protocol version of the ReInit proposal is lower than the one of the group.
+    #[error("The protocol version of the ReInit proposal is lower than the one of the group.")]
+    ReInitVersionDowngrade,
 }
 
 /// External Commit validaton error
@@ -433,6 +561,15 @@ pub enum ExternalCommitValidationError {
     /// External commit contains referenced proposal
     #[error("Found a referenced proposal in an External Commit.")]
     ReferencedProposal,
+    /// The external commit was rejected by the application's processing policy.
+    #[error("The external commit was rejected by the application's processing policy.")]
+    RejectedByPolicy,
+    /// The ExternalInit proposal was already used by another external commit
+    /// in this epoch.
+    #[error(
+        "The ExternalInit proposal was already used by another external commit in this epoch."
+    )]
+    ExternalInitReused,
 }
 
 /// Create add proposal error
@@ -515,6 +652,10 @@ pub enum MergeCommitError<StorageError> {
     /// Error writing updated group to storage.
     #[error("Error writing updated group data to storage.")]
     StorageError(StorageError),
+    /// The commit was merged, but the [`EpochHooks`](crate::group::EpochHooks)
+    /// of the group failed for the new epoch.
+    #[error("The epoch hooks failed for the new epoch.")]
+    EpochHookError(EpochHookError),
 }
 
 /// Error validation a GroupContextExtensions proposal.
@@ -550,3 +691,25 @@ pub enum GroupContextExtensionsProposalValidationError {
     )]
     ExtensionNotInRequiredCapabilities,
 }
+
+/// Application protocol version negotiation error
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum AppVersionError {
+    /// An application version extension could not be decoded.
+    #[error("An application version extension could not be decoded.")]
+    Malformed,
+}
+
+/// Error applying a hypothetical change to a [`TreePreview`](crate::group::TreePreview).
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum TreePreviewError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The tree is full, we cannot add any more leaves.
+    #[error("The tree is full, we cannot add any more leaves.")]
+    TreeFull,
+    /// The leaf is blank or outside of the tree.
+    #[error("The leaf is blank or outside of the tree.")]
+    UnknownMember,
+}
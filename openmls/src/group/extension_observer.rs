@@ -0,0 +1,190 @@
+//! # Extension observers
+//!
+//! This module contains [`ExtensionObservers`], which lets applications react
+//! to changes of the extensions they care about when a commit is merged,
+//! instead of diffing the extension lists of the group before and after every
+//! merge. Observers are passed to
+//! [`MlsGroup::merge_staged_commit_with_observers()`] or
+//! [`MlsGroup::merge_pending_commit_with_observers()`].
+
+use std::collections::BTreeMap;
+
+#[cfg(doc)]
+use crate::group::MlsGroup;
+use crate::{
+    binary_tree::LeafNodeIndex,
+    extensions::{Extension, ExtensionType, Extensions},
+    group::{PublicGroup, StagedCommit},
+    treesync::LeafNode,
+};
+
+/// The place of an extension that was changed by a commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionScope {
+    /// The extension is a group context extension.
+    GroupContext,
+    /// The extension is an extension of the leaf node at the given index.
+    Leaf(LeafNodeIndex),
+}
+
+/// A change of a single extension that is handed to the observers registered
+/// for its [`ExtensionType`].
+///
+/// If the extension was added, [`ExtensionChange::old_value()`] is `None`. If
+/// it was removed (including the removal of the leaf that contained it),
+/// [`ExtensionChange::new_value()`] is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionChange {
+    extension_type: ExtensionType,
+    scope: ExtensionScope,
+    old_value: Option<Extension>,
+    new_value: Option<Extension>,
+}
+
+impl ExtensionChange {
+    /// Returns the type of the changed extension.
+    pub fn extension_type(&self) -> ExtensionType {
+        self.extension_type
+    }
+
+    /// Returns where the changed extension is located.
+    pub fn scope(&self) -> ExtensionScope {
+        self.scope
+    }
+
+    /// Returns the value of the extension before the commit.
+    pub fn old_value(&self) -> Option<&Extension> {
+        self.old_value.as_ref()
+    }
+
+    /// Returns the value of the extension after the commit.
+    pub fn new_value(&self) -> Option<&Extension> {
+        self.new_value.as_ref()
+    }
+}
+
+type Observer = Box<dyn FnMut(&ExtensionChange) + Send>;
+
+/// A set of observers, keyed by [`ExtensionType`], that are invoked when a
+/// merged commit changes a group context extension or a leaf node extension of
+/// the given type.
+///
+/// Observers are only invoked after the commit was merged successfully and
+/// receive the old and the new value of the extension. They must be `Send`,
+/// so that the set can be moved to another thread together with the group.
+#[derive(Default)]
+pub struct ExtensionObservers {
+    observers: BTreeMap<ExtensionType, Vec<Observer>>,
+}
+
+impl std::fmt::Debug for ExtensionObservers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtensionObservers")
+            .field("extension_types", &self.observers.keys())
+            .finish()
+    }
+}
+
+impl ExtensionObservers {
+    /// Creates an empty set of observers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an `observer` for extensions of type `extension_type`.
+    /// Multiple observers can be registered for the same type. They are
+    /// invoked in the order they were registered.
+    pub fn register(
+        &mut self,
+        extension_type: ExtensionType,
+        observer: impl FnMut(&ExtensionChange) + Send + 'static,
+    ) -> &mut Self {
+        self.observers
+            .entry(extension_type)
+            .or_default()
+            .push(Box::new(observer));
+        self
+    }
+
+    /// Collects the changes of the observed extensions between the current
+    /// state of the `public_group` and the `staged_commit`.
+    pub(crate) fn collect_changes(
+        &self,
+        public_group: &PublicGroup,
+        staged_commit: &StagedCommit,
+    ) -> Vec<ExtensionChange> {
+        let mut changes = Vec::new();
+        if self.observers.is_empty() {
+            return changes;
+        }
+
+        self.diff_extensions(
+            ExtensionScope::GroupContext,
+            Some(public_group.group_context().extensions()),
+            Some(staged_commit.group_context().extensions()),
+            &mut changes,
+        );
+
+        // Removed leaves that were truncated from the tree don't show up in
+        // the diff, so we add them explicitly.
+        let mut changed_leaves: BTreeMap<LeafNodeIndex, Option<&LeafNode>> =
+            staged_commit.staged_diff().changed_leaves().collect();
+        for remove in staged_commit.remove_proposals() {
+            changed_leaves
+                .entry(remove.remove_proposal().removed())
+                .or_insert(None);
+        }
+
+        for (leaf_index, new_leaf) in changed_leaves {
+            self.diff_extensions(
+                ExtensionScope::Leaf(leaf_index),
+                public_group.leaf(leaf_index).map(|leaf| leaf.extensions()),
+                new_leaf.map(|leaf| leaf.extensions()),
+                &mut changes,
+            );
+        }
+
+        changes
+    }
+
+    /// Invokes the registered observers for each of the `changes`.
+    pub(crate) fn notify(&mut self, changes: &[ExtensionChange]) {
+        for change in changes {
+            if let Some(observers) = self.observers.get_mut(&change.extension_type) {
+                for observer in observers.iter_mut() {
+                    observer(change);
+                }
+            }
+        }
+    }
+
+    fn diff_extensions(
+        &self,
+        scope: ExtensionScope,
+        old: Option<&Extensions>,
+        new: Option<&Extensions>,
+        changes: &mut Vec<ExtensionChange>,
+    ) {
+        let find = |extensions: Option<&Extensions>, extension_type: ExtensionType| {
+            extensions.and_then(|extensions| {
+                extensions
+                    .iter()
+                    .find(|extension| extension.extension_type() == extension_type)
+                    .cloned()
+            })
+        };
+
+        for &extension_type in self.observers.keys() {
+            let old_value = find(old, extension_type);
+            let new_value = find(new, extension_type);
+            if old_value != new_value {
+                changes.push(ExtensionChange {
+                    extension_type,
+                    scope,
+                    old_value,
+                    new_value,
+                });
+            }
+        }
+    }
+}
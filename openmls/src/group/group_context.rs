@@ -9,7 +9,7 @@ use super::*;
 use crate::{
     error::LibraryError,
     framing::{mls_auth_content::AuthenticatedContent, ConfirmedTranscriptHashInput},
-    versions::ProtocolVersion,
+    versions::{ProtocolVersion, VersionBehavior},
 };
 
 /// 8.1 Group Context
@@ -75,7 +75,7 @@ impl GroupContext {
     ) -> Self {
         GroupContext {
             ciphersuite,
-            protocol_version: ProtocolVersion::Mls10,
+            protocol_version: VersionBehavior::current().version,
             group_id,
             epoch: epoch.into(),
             tree_hash: tree_hash.into(),
@@ -0,0 +1,176 @@
+//! # Inactive members
+//!
+//! Devices that are lost or abandoned stay in a group until they are removed.
+//! Besides holding back post-compromise security, they keep the group from
+//! requiring capabilities they don't support. An [`ActivityTracker`] knows the
+//! last epoch in which each member sent a commit, a proposal (including Update
+//! proposals) or an application message, and
+//! [`MlsGroup::propose_remove_inactive_members()`] proposes to remove the
+//! members that were inactive for more than a given number of epochs.
+//!
+//! Groups that collect member statistics know the activity of their members,
+//! and [`MlsGroup::activity_tracker()`] returns a tracker for the current epoch.
+//! Otherwise, the application keeps a tracker, hands it every processed message
+//! with [`ActivityTracker::record()`] and the group after every merge with
+//! [`ActivityTracker::observe()`]. The own client is never inactive.
+
+use openmls_traits::{signatures::Signer, storage::StorageWriter as _};
+use serde::{Deserialize, Serialize};
+
+use super::{errors::ProposeRemoveMemberError, leaf_map::LeafMap, MlsGroup};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::hash_ref::ProposalRef,
+    error::LibraryError,
+    framing::{MlsMessageOut, ProcessedMessage},
+    group::{GroupEpoch, QueuedProposal},
+    storage::OpenMlsProvider,
+};
+
+/// Tracks the last activity of the members of a group. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityTracker {
+    epoch: GroupEpoch,
+    own_leaf_index: Option<LeafNodeIndex>,
+    // The last epoch in which each member was active.
+    members: LeafMap<GroupEpoch>,
+}
+
+impl ActivityTracker {
+    /// Creates a tracker without members.
+    pub fn new() -> Self {
+        Self {
+            epoch: GroupEpoch::from(0),
+            own_leaf_index: None,
+            members: LeafMap::default(),
+        }
+    }
+
+    /// Updates the tracked members to the members of the `group`. New
+    /// members, including members that replaced a removed member at the same
+    /// leaf index, are considered active in the current epoch.
+    pub fn observe(&mut self, group: &MlsGroup) {
+        let epoch = group.epoch();
+        self.members
+            .sync(group, |member| &member.signature_key, || epoch);
+        self.epoch = epoch;
+        self.own_leaf_index = Some(group.own_leaf_index());
+    }
+
+    /// Records the activity of the sender of the `message`, if it is a
+    /// member.
+    pub fn record(&mut self, message: &ProcessedMessage) {
+        let Some(leaf_index) = message.sender_leaf_index() else {
+            return;
+        };
+        if let Some(last_activity) = self.members.get_mut(leaf_index) {
+            *last_activity = (*last_activity).max(message.epoch());
+        }
+    }
+
+    /// Returns the last epoch in which the member at `leaf_index` was active,
+    /// or `None` if there is no such member.
+    pub fn last_activity(&self, leaf_index: LeafNodeIndex) -> Option<GroupEpoch> {
+        self.members.get(leaf_index).copied()
+    }
+
+    /// Sets the last epoch in which the member at `leaf_index` was active, if
+    /// there is such a member.
+    pub(crate) fn set_last_activity(&mut self, leaf_index: LeafNodeIndex, epoch: GroupEpoch) {
+        if let Some(last_activity) = self.members.get_mut(leaf_index) {
+            *last_activity = epoch;
+        }
+    }
+
+    /// Returns the members that weren't active for more than `max_inactivity`
+    /// epochs in the last observed epoch.
+    pub fn inactive_members(&self, max_inactivity: u64) -> Vec<LeafNodeIndex> {
+        self.members
+            .iter()
+            .filter(|(leaf_index, last_activity)| {
+                Some(*leaf_index) != self.own_leaf_index
+                    && self.epoch.as_u64().saturating_sub(last_activity.as_u64()) > max_inactivity
+            })
+            .map(|(leaf_index, _)| leaf_index)
+            .collect()
+    }
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MlsGroup {
+    /// Creates a Remove proposal for each member that wasn't active for more
+    /// than `max_inactivity` epochs according to the `tracker`, see
+    /// [`ActivityTracker::inactive_members()`].
+    ///
+    /// Returns the proposal messages and references in the order of the leaf
+    /// indices of the removed members. Returns an error if there is a pending
+    /// commit. If an error is returned, none of the proposals is queued.
+    #[allow(clippy::type_complexity)]
+    pub fn propose_remove_inactive_members<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        tracker: &ActivityTracker,
+        max_inactivity: u64,
+    ) -> Result<Vec<(MlsMessageOut, ProposalRef)>, ProposeRemoveMemberError<Provider::StorageError>>
+    {
+        self.is_operational()?;
+
+        // Create all proposals before any of them is queued.
+        let proposals = tracker
+            .inactive_members(max_inactivity)
+            .into_iter()
+            .map(|member| {
+                let remove_proposal = self
+                    .create_remove_proposal(self.framing_parameters(), member, signer)
+                    .map_err(|_| ProposeRemoveMemberError::UnknownMember)?;
+                let proposal = QueuedProposal::from_authenticated_content_by_ref(
+                    self.ciphersuite(),
+                    provider.crypto(),
+                    remove_proposal.clone(),
+                )?;
+                Ok((remove_proposal, proposal))
+            })
+            .collect::<Result<Vec<_>, ProposeRemoveMemberError<_>>>()?;
+        let proposals = proposals
+            .into_iter()
+            .map(|(remove_proposal, proposal)| {
+                let mls_message = self.content_to_mls_message(remove_proposal, provider)?;
+                Ok((mls_message, proposal))
+            })
+            .collect::<Result<Vec<_>, LibraryError>>()?;
+
+        // Queue the proposals in the storage and roll back if that fails.
+        let storage = provider.storage();
+        for (queued, (_, proposal)) in proposals.iter().enumerate() {
+            let result =
+                storage.queue_proposal(self.group_id(), &proposal.proposal_reference(), proposal);
+            if let Err(e) = result {
+                for (_, proposal) in &proposals[..queued] {
+                    if let Err(e) =
+                        storage.remove_proposal(self.group_id(), &proposal.proposal_reference())
+                    {
+                        log::error!("Failed to roll back a queued proposal: {e:?}");
+                    }
+                }
+                return Err(ProposeRemoveMemberError::StorageError(e));
+            }
+        }
+
+        self.reset_aad();
+        Ok(proposals
+            .into_iter()
+            .map(|(mls_message, proposal)| {
+                let proposal_ref = proposal.proposal_reference();
+                self.proposal_store_mut().add(proposal);
+                (mls_message, proposal_ref)
+            })
+            .collect())
+    }
+}
@@ -13,12 +13,32 @@ impl MlsGroup {
     /// Returns `CreateMessageError::MlsGroupStateError::PendingProposal` if pending proposals
     /// exist. In that case `.process_pending_proposals()` must be called first
     /// and incoming messages from the DS must be processed afterwards.
+    /// Returns `CreateMessageError::UpdateRequired` if the own leaf is stale
+    /// according to the installed
+    /// [`MandatoryUpdatePolicy`](crate::group::MandatoryUpdatePolicy).
     pub fn create_message<Provider: OpenMlsProvider>(
         &mut self,
         provider: &Provider,
         signer: &impl Signer,
         message: &[u8],
     ) -> Result<MlsMessageOut, CreateMessageError> {
+        self.create_message_with_id(provider, signer, message)
+            .map(|(message, _)| message)
+    }
+
+    /// Creates an application message like [`MlsGroup::create_message()`]
+    /// and returns it together with its [`MessageId`].
+    ///
+    /// Receivers get the same identifier from
+    /// [`ProcessedMessage::message_id()`](crate::framing::ProcessedMessage::message_id),
+    /// so it can be used to match redelivered messages against the ones that
+    /// were sent.
+    pub fn create_message_with_id<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        message: &[u8],
+    ) -> Result<(MlsMessageOut, MessageId), CreateMessageError> {
         if !self.is_active() {
             return Err(CreateMessageError::GroupStateError(
                 MlsGroupStateError::UseAfterEviction,
@@ -29,6 +49,7 @@ impl MlsGroup {
                 MlsGroupStateError::PendingProposal,
             ));
         }
+        self.check_update_policy()?;
 
         let authenticated_content = AuthenticatedContent::new_application(
             self.own_leaf_index(),
@@ -37,15 +58,15 @@ impl MlsGroup {
             self.context(),
             signer,
         )?;
-        let ciphertext = self
-            .encrypt(authenticated_content, provider)
+        let (ciphertext, message_id) = self
+            .encrypt_with_id(authenticated_content, provider)
             // We know the application message is wellformed and we have the key material of the current epoch
             .map_err(|_| LibraryError::custom("Malformed plaintext"))?;
 
         self.reset_aad();
-        Ok(MlsMessageOut::from_private_message(
-            ciphertext,
-            self.version(),
+        Ok((
+            MlsMessageOut::from_private_message(ciphertext, self.version()),
+            message_id,
         ))
     }
 }
@@ -0,0 +1,229 @@
+//! # Attachment keys
+//!
+//! Attachments, like files or images, are usually not sent in application
+//! messages, but stored encrypted on a CDN and only referenced in messages.
+//! This module derives a key for every attachment from the exporter secret of
+//! the current epoch with [`MlsGroup::attachment_key()`]. The key is bound to
+//! the group, the epoch and an attachment ID chosen by the application, so an
+//! encrypted attachment is cryptographically tied to the conversation.
+//!
+//! An [`EncryptedAttachment`] starts with an [`AttachmentHeader`] that
+//! identifies the group, epoch and attachment, and that is authenticated
+//! together with the ciphertext. Its TLS serialization is the blob that is
+//! uploaded.
+//!
+//! The exporter secret is only available in the current epoch. Receivers
+//! therefore derive the key when they process the application message that
+//! references the attachment, and keep the [`AttachmentKey`] if they want to
+//! decrypt the attachment later.
+
+use openmls_traits::{crypto::OpenMlsCrypto, random::OpenMlsRand, types::Ciphersuite};
+use serde::{Deserialize, Serialize};
+use tls_codec::{
+    Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
+    VLBytes,
+};
+
+use super::{
+    errors::{AttachmentDecryptionError, ExportSecretError},
+    *,
+};
+use crate::ciphersuite::{AeadKey, AeadNonce, Secret, NONCE_BYTES};
+
+const ATTACHMENT_KEY_LABEL: &str = "attachment key";
+
+/// The header of an [`EncryptedAttachment`], which identifies the group, the
+/// epoch and the attachment the key was derived for.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TlsSerialize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
+    TlsSize,
+)]
+pub struct AttachmentHeader {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    attachment_id: VLBytes,
+}
+
+impl AttachmentHeader {
+    /// Returns the ID of the group the attachment was sent in.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the attachment key was derived in.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the ID of the attachment.
+    pub fn attachment_id(&self) -> &[u8] {
+        self.attachment_id.as_slice()
+    }
+}
+
+/// An attachment encrypted with an [`AttachmentKey`].
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct EncryptedAttachment {
+    header: AttachmentHeader,
+    nonce: VLBytes,
+    ciphertext: VLBytes,
+}
+
+impl EncryptedAttachment {
+    /// Returns the header of the attachment.
+    pub fn header(&self) -> &AttachmentHeader {
+        &self.header
+    }
+}
+
+// The exporter context of an attachment key.
+#[derive(TlsSerialize, TlsSize)]
+struct AttachmentKeyContext<'a> {
+    group_id: &'a GroupId,
+    epoch: GroupEpoch,
+    attachment_id: &'a VLBytes,
+}
+
+/// The key of a single attachment, derived with
+/// [`MlsGroup::attachment_key()`].
+///
+/// The key can be persisted to decrypt the attachment after the epoch it was
+/// derived in has ended.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentKey {
+    header: AttachmentHeader,
+    secret: Secret,
+}
+
+impl std::fmt::Debug for AttachmentKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AttachmentKey")
+            .field("header", &self.header)
+            .field("secret", &self.secret)
+            .finish()
+    }
+}
+
+impl AttachmentKey {
+    /// Returns the header of the attachments encrypted with this key.
+    pub fn header(&self) -> &AttachmentHeader {
+        &self.header
+    }
+
+    /// Encrypts the `attachment`.
+    pub fn encrypt(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        rand: &impl OpenMlsRand,
+        attachment: &[u8],
+    ) -> Result<EncryptedAttachment, LibraryError> {
+        let aad = self
+            .header
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        let nonce: [u8; NONCE_BYTES] = rand
+            .random_array()
+            .map_err(|_| LibraryError::custom("Not enough randomness"))?;
+        let nonce = AeadNonce::from_slice(&nonce)
+            .ok_or_else(|| LibraryError::custom("Invalid nonce length"))?;
+        let ciphertext = self
+            .aead_key()
+            .aead_seal(crypto, attachment, &aad, &nonce)
+            .map_err(LibraryError::unexpected_crypto_error)?;
+
+        Ok(EncryptedAttachment {
+            header: self.header.clone(),
+            nonce: nonce.as_slice().into(),
+            ciphertext: ciphertext.into(),
+        })
+    }
+
+    /// Decrypts the `encrypted_attachment`.
+    ///
+    /// Returns [`AttachmentDecryptionError::HeaderMismatch`] if the attachment
+    /// was not encrypted with this key.
+    pub fn decrypt(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        encrypted_attachment: &EncryptedAttachment,
+    ) -> Result<Vec<u8>, AttachmentDecryptionError> {
+        if encrypted_attachment.header != self.header {
+            return Err(AttachmentDecryptionError::HeaderMismatch);
+        }
+
+        let aad = self
+            .header
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        let nonce = AeadNonce::from_slice(encrypted_attachment.nonce.as_slice())
+            .ok_or(AttachmentDecryptionError::DecryptionFailed)?;
+        self.aead_key()
+            .aead_open(
+                crypto,
+                encrypted_attachment.ciphertext.as_slice(),
+                &aad,
+                &nonce,
+            )
+            .map_err(|_| AttachmentDecryptionError::DecryptionFailed)
+    }
+
+    fn aead_key(&self) -> AeadKey {
+        AeadKey::from_secret(self.secret.clone(), self.header.ciphersuite)
+    }
+}
+
+impl MlsGroup {
+    /// Derives the [`AttachmentKey`] for the attachment with the given
+    /// `attachment_id` in the current epoch.
+    ///
+    /// The attachment ID is chosen by the sender and has to be unique within
+    /// the epoch, e.g. a random value or a hash of the attachment. It is sent
+    /// to the other members together with the epoch, e.g. in the application
+    /// message that references the attachment.
+    pub fn attachment_key<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        attachment_id: &[u8],
+    ) -> Result<AttachmentKey, ExportSecretError> {
+        let header = AttachmentHeader {
+            group_id: self.group_id().clone(),
+            epoch: self.epoch(),
+            ciphersuite: self.ciphersuite(),
+            attachment_id: attachment_id.into(),
+        };
+        let context = AttachmentKeyContext {
+            group_id: &header.group_id,
+            epoch: header.epoch,
+            attachment_id: &header.attachment_id,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+        let secret = self.export_secret(
+            provider,
+            ATTACHMENT_KEY_LABEL,
+            &context,
+            self.ciphersuite().aead_key_length(),
+        )?;
+
+        Ok(AttachmentKey {
+            header,
+            secret: Secret::from_slice(&secret),
+        })
+    }
+}
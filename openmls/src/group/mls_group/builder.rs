@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use openmls_traits::{signatures::Signer, types::Ciphersuite};
 use tls_codec::Serialize;
 
@@ -6,11 +8,14 @@ use crate::{
     credentials::CredentialWithKey,
     error::LibraryError,
     extensions::{errors::InvalidExtensionError, Extensions},
+    framing::MlsMessageOut,
     group::{
-        public_group::errors::PublicGroupBuildError, GroupId, MlsGroupCreateConfig,
-        MlsGroupCreateConfigBuilder, NewGroupError, PublicGroup, WireFormatPolicy,
+        public_group::errors::PublicGroupBuildError, GroupId, GroupIdStrategy,
+        MlsGroupCreateConfig, MlsGroupCreateConfigBuilder, NewGroupError, NewGroupWithMembersError,
+        PublicGroup, UsedExternalInits, WireFormatPolicy,
     },
-    key_packages::Lifetime,
+    key_packages::{KeyPackage, Lifetime},
+    messages::group_info::GroupInfo,
     prelude::LeafNodeIndex,
     schedule::{
         psk::{load_psks, store::ResumptionPskStore, PskSecret},
@@ -21,11 +26,14 @@ use crate::{
     treesync::{errors::LeafNodeValidationError, node::leaf_node::Capabilities},
 };
 
-use super::{past_secrets::MessageSecretsStore, MlsGroup, MlsGroupState};
+use super::{
+    epoch_hooks::EpochHooks, past_secrets::MessageSecretsStore, stats::MemberStatsCache,
+    timing::LastOperationTiming, MlsGroup, MlsGroupState,
+};
 
 #[derive(Default, Debug)]
 pub struct MlsGroupBuilder {
-    group_id: Option<GroupId>,
+    group_id_strategy: GroupIdStrategy,
     mls_group_create_config_builder: MlsGroupCreateConfigBuilder,
     psk_ids: Vec<PreSharedKeyId>,
 }
@@ -37,7 +45,14 @@ impl MlsGroupBuilder {
 
     /// Sets the group ID of the [`MlsGroup`].
     pub fn with_group_id(mut self, group_id: GroupId) -> Self {
-        self.group_id = Some(group_id);
+        self.group_id_strategy = GroupIdStrategy::Assigned(group_id);
+        self
+    }
+
+    /// Sets the [`GroupIdStrategy`] used to choose the group ID of the
+    /// [`MlsGroup`]. Overrides a group ID set with [`Self::with_group_id()`].
+    pub fn with_group_id_strategy(mut self, group_id_strategy: GroupIdStrategy) -> Self {
+        self.group_id_strategy = group_id_strategy;
         self
     }
 
@@ -51,6 +66,25 @@ impl MlsGroupBuilder {
         self.build_internal(provider, signer, credential_with_key, None)
     }
 
+    /// Build a new group and add the members owning the given `key_packages`
+    /// in a single operation.
+    ///
+    /// See [`MlsGroup::new_with_members()`] for more details.
+    #[allow(clippy::type_complexity)]
+    pub fn build_with_members<Provider: OpenMlsProvider>(
+        self,
+        provider: &Provider,
+        signer: &impl Signer,
+        credential_with_key: CredentialWithKey,
+        key_packages: &[KeyPackage],
+    ) -> Result<
+        (MlsGroup, MlsMessageOut, Option<GroupInfo>),
+        NewGroupWithMembersError<Provider::StorageError>,
+    > {
+        self.build_internal(provider, signer, credential_with_key, None)?
+            .add_initial_members(provider, signer, key_packages)
+    }
+
     /// Build a new group with the given group ID.
     ///
     /// If an [`MlsGroupCreateConfig`] is provided, it will be used to configure the
@@ -63,12 +97,17 @@ impl MlsGroupBuilder {
         credential_with_key: CredentialWithKey,
         mls_group_create_config_option: Option<MlsGroupCreateConfig>,
     ) -> Result<MlsGroup, NewGroupError<Provider::StorageError>> {
-        let mls_group_create_config = mls_group_create_config_option
-            .unwrap_or_else(|| self.mls_group_create_config_builder.build());
-        let group_id = self
-            .group_id
-            .unwrap_or_else(|| GroupId::random(provider.rand()));
+        let mls_group_create_config = match mls_group_create_config_option {
+            Some(mls_group_create_config) => {
+                mls_group_create_config.validate()?;
+                mls_group_create_config
+            }
+            None => self.mls_group_create_config_builder.build()?,
+        };
         let ciphersuite = mls_group_create_config.ciphersuite;
+        let group_id =
+            self.group_id_strategy
+                .group_id(provider.crypto(), provider.rand(), ciphersuite)?;
 
         let (public_group_builder, commit_secret, leaf_keypair) =
             PublicGroup::builder(group_id, ciphersuite, credential_with_key)
@@ -82,6 +121,7 @@ impl MlsGroupBuilder {
                 .map_err(|e| match e {
                     PublicGroupBuildError::LibraryError(e) => NewGroupError::LibraryError(e),
                     PublicGroupBuildError::InvalidExtensions(e) => e.into(),
+                    PublicGroupBuildError::LeafNodeValidation(e) => e.into(),
                 })?;
 
         let serialized_group_context = public_group_builder
@@ -102,8 +142,8 @@ impl MlsGroupBuilder {
         )
         .map_err(LibraryError::unexpected_crypto_error)?;
 
-        // TODO(#1357)
-        let mut resumption_psk_store = ResumptionPskStore::new(32);
+        let mut resumption_psk_store =
+            ResumptionPskStore::new(mls_group_create_config.number_of_resumption_psks());
 
         // Prepare the PskSecret
         let psk_secret = load_psks(provider.storage(), &resumption_psk_store, &self.psk_ids)
@@ -134,10 +174,11 @@ impl MlsGroupBuilder {
             .tag(provider.crypto(), ciphersuite, &[])
             .map_err(LibraryError::unexpected_crypto_error)?;
 
-        let message_secrets_store = MessageSecretsStore::new_with_secret(
+        let mut message_secrets_store = MessageSecretsStore::new_with_secret(
             mls_group_create_config.max_past_epochs(),
             message_secrets,
         );
+        message_secrets_store.set_max_age(mls_group_create_config.max_past_epoch_age());
 
         let public_group = public_group_builder
             .with_confirmation_tag(initial_confirmation_tag)
@@ -151,6 +192,10 @@ impl MlsGroupBuilder {
             mls_group_config: mls_group_create_config.join_config.clone(),
             own_leaf_nodes: vec![],
             aad: vec![],
+            used_external_inits: UsedExternalInits::default(),
+            member_stats: MemberStatsCache::default(),
+            last_operation_timing: LastOperationTiming::default(),
+            epoch_hooks: EpochHooks::default(),
             group_state: MlsGroupState::Operational,
             public_group,
             group_epoch_secrets,
@@ -204,6 +249,16 @@ impl MlsGroupBuilder {
         self
     }
 
+    /// Sets the `max_past_epoch_age` property of the MlsGroup.
+    /// Message secrets of past epochs are deleted once they are older than
+    /// `max_past_epoch_age`.
+    pub fn max_past_epoch_age(mut self, max_past_epoch_age: Duration) -> Self {
+        self.mls_group_create_config_builder = self
+            .mls_group_create_config_builder
+            .max_past_epoch_age(max_past_epoch_age);
+        self
+    }
+
     /// Sets the `number_of_resumption_psks` property of the MlsGroup.
     pub fn number_of_resumption_psks(mut self, number_of_resumption_psks: usize) -> Self {
         self.mls_group_create_config_builder = self
@@ -212,6 +267,38 @@ impl MlsGroupBuilder {
         self
     }
 
+    /// Sets the `removal_grace_period` property of the MlsGroup.
+    pub fn removal_grace_period(mut self, removal_grace_period: bool) -> Self {
+        self.mls_group_create_config_builder = self
+            .mls_group_create_config_builder
+            .removal_grace_period(removal_grace_period);
+        self
+    }
+
+    /// Sets the `collect_member_stats` property of the MlsGroup.
+    pub fn collect_member_stats(mut self, collect_member_stats: bool) -> Self {
+        self.mls_group_create_config_builder = self
+            .mls_group_create_config_builder
+            .collect_member_stats(collect_member_stats);
+        self
+    }
+
+    /// Sets the `force_full_commits` property of the MlsGroup.
+    pub fn force_full_commits(mut self, force_full_commits: bool) -> Self {
+        self.mls_group_create_config_builder = self
+            .mls_group_create_config_builder
+            .force_full_commits(force_full_commits);
+        self
+    }
+
+    /// Sets the `inactive_group_retention` property of the MlsGroup.
+    pub fn inactive_group_retention(mut self, inactive_group_retention: Duration) -> Self {
+        self.mls_group_create_config_builder = self
+            .mls_group_create_config_builder
+            .inactive_group_retention(inactive_group_retention);
+        self
+    }
+
     /// Sets the `use_ratchet_tree_extension` property of the MlsGroup.
     pub fn use_ratchet_tree_extension(mut self, use_ratchet_tree_extension: bool) -> Self {
         self.mls_group_create_config_builder = self
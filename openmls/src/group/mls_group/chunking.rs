@@ -0,0 +1,333 @@
+//! # Chunked application messages
+//!
+//! This module splits large payloads, like files, into multiple application
+//! messages with [`MlsGroup::create_chunked_messages()`] and reassembles them
+//! on the receiving side with a [`ChunkReassembler`].
+//!
+//! Every chunk carries a random transfer ID, its index, the total number of
+//! chunks and a digest of the complete payload. Since application messages
+//! are authenticated, the receiver can check that all chunks of a transfer
+//! were sent by the same member and that the reassembled payload is complete.
+//!
+//! The reassembler bounds the memory used for incomplete transfers, both per
+//! sender and in total. When a limit is reached, the oldest incomplete
+//! transfers are evicted.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    num::NonZeroUsize,
+};
+
+use openmls_traits::{crypto::OpenMlsCrypto, random::OpenMlsRand, signatures::Signer};
+use tls_codec::{
+    DeserializeBytes as _, Serialize as _, TlsDeserialize, TlsDeserializeBytes, TlsSerialize,
+    TlsSize, VLBytes,
+};
+
+use super::{
+    errors::{ChunkReassemblyError, CreateChunkedMessagesError},
+    *,
+};
+
+const TRANSFER_ID_LENGTH: usize = 16;
+
+/// The default maximum number of incomplete transfers per sender of a
+/// [`ChunkReassembler`].
+pub const DEFAULT_MAX_TRANSFERS_PER_SENDER: usize = 4;
+
+// The content of an application message that carries a chunk.
+#[derive(TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize)]
+struct MessageChunk {
+    transfer_id: VLBytes,
+    index: u64,
+    count: u64,
+    payload_digest: VLBytes,
+    data: VLBytes,
+}
+
+impl MlsGroup {
+    /// Splits the `payload` into chunks of at most `chunk_size` bytes and
+    /// creates an application message for each of them. The messages can be
+    /// reassembled by the receivers with a [`ChunkReassembler`], in any order.
+    ///
+    /// The same conditions as for [`MlsGroup::create_message()`] apply. If
+    /// creating a message fails after the messages of previous chunks were
+    /// created, these are returned with
+    /// [`CreateChunkedMessagesError::Incomplete`].
+    pub fn create_chunked_messages<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        payload: &[u8],
+        chunk_size: NonZeroUsize,
+    ) -> Result<Vec<MlsMessageOut>, CreateChunkedMessagesError> {
+        let transfer_id = provider
+            .rand()
+            .random_vec(TRANSFER_ID_LENGTH)
+            .map_err(|_| LibraryError::custom("Not enough randomness."))?;
+        let payload_digest = provider
+            .crypto()
+            .hash(self.ciphersuite().hash_algorithm(), payload)
+            .map_err(LibraryError::unexpected_crypto_error)?;
+
+        // An empty payload is sent as a single empty chunk.
+        let chunks: Vec<&[u8]> = if payload.is_empty() {
+            vec![payload]
+        } else {
+            payload.chunks(chunk_size.get()).collect()
+        };
+        let count = chunks.len() as u64;
+
+        // Serialize all chunks before the first message is created, such that
+        // the remaining failures are the ones of creating the messages.
+        let chunks = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| {
+                MessageChunk {
+                    transfer_id: transfer_id.clone().into(),
+                    index: index as u64,
+                    count,
+                    payload_digest: payload_digest.clone().into(),
+                    data: data.to_vec().into(),
+                }
+                .tls_serialize_detached()
+                .map_err(LibraryError::missing_bound_check)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut messages = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            match self.create_message(provider, signer, &chunk) {
+                Ok(message) => messages.push(message),
+                Err(error) if messages.is_empty() => return Err(error.into()),
+                Err(error) => {
+                    return Err(CreateChunkedMessagesError::Incomplete { messages, error })
+                }
+            }
+        }
+        Ok(messages)
+    }
+}
+
+#[derive(Debug)]
+struct PartialTransfer {
+    // The position of the transfer in the order in which the transfers were
+    // started, used to evict the oldest transfers first.
+    sequence_number: u64,
+    count: u64,
+    payload_digest: Vec<u8>,
+    chunks: BTreeMap<u64, Vec<u8>>,
+    size: usize,
+}
+
+type TransferKey = (LeafNodeIndex, Vec<u8>);
+
+/// Reassembles payloads that were split into multiple application messages
+/// with [`MlsGroup::create_chunked_messages()`].
+///
+/// A reassembler is used for a single group. Incomplete transfers are kept in
+/// memory until all of their chunks were added, until they are discarded, or
+/// until they are evicted to make room for newer transfers. By default, each
+/// sender can have [`DEFAULT_MAX_TRANSFERS_PER_SENDER`] incomplete transfers,
+/// and all incomplete transfers together can buffer as many bytes as that
+/// many transfers of the maximum payload size.
+#[derive(Debug)]
+pub struct ChunkReassembler {
+    ciphersuite: Ciphersuite,
+    max_payload_size: usize,
+    max_transfers_per_sender: NonZeroUsize,
+    max_pending_size: usize,
+    transfers: HashMap<TransferKey, PartialTransfer>,
+    pending_size: usize,
+    next_sequence_number: u64,
+}
+
+impl ChunkReassembler {
+    /// Creates a reassembler for a group with the given `ciphersuite`.
+    /// Transfers with a payload larger than `max_payload_size` bytes are
+    /// rejected.
+    pub fn new(ciphersuite: Ciphersuite, max_payload_size: usize) -> Self {
+        Self {
+            ciphersuite,
+            max_payload_size,
+            max_transfers_per_sender: NonZeroUsize::new(DEFAULT_MAX_TRANSFERS_PER_SENDER)
+                .unwrap_or(NonZeroUsize::MIN),
+            max_pending_size: max_payload_size.saturating_mul(DEFAULT_MAX_TRANSFERS_PER_SENDER),
+            transfers: HashMap::new(),
+            pending_size: 0,
+            next_sequence_number: 0,
+        }
+    }
+
+    /// Sets the maximum number of incomplete transfers per sender. If a
+    /// sender starts another transfer, its oldest incomplete transfer is
+    /// evicted.
+    pub fn with_max_transfers_per_sender(mut self, max_transfers: NonZeroUsize) -> Self {
+        self.max_transfers_per_sender = max_transfers;
+        self
+    }
+
+    /// Sets the maximum number of bytes buffered for all incomplete transfers
+    /// together. If a chunk exceeds it, the oldest incomplete transfers are
+    /// evicted.
+    pub fn with_max_pending_size(mut self, max_pending_size: usize) -> Self {
+        self.max_pending_size = max_pending_size;
+        self
+    }
+
+    /// Adds the content of an application message from the member at
+    /// `sender`, e.g. from [`ProcessedMessage::sender_leaf_index()`].
+    ///
+    /// Returns the reassembled payload once all chunks of its transfer were
+    /// added and `None` otherwise. Adding a chunk a second time has no
+    /// effect.
+    pub fn add(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+        sender: LeafNodeIndex,
+        message: &[u8],
+    ) -> Result<Option<Vec<u8>>, ChunkReassemblyError> {
+        let chunk = MessageChunk::tls_deserialize_exact_bytes(message)
+            .map_err(|_| ChunkReassemblyError::MalformedChunk)?;
+        if chunk.index >= chunk.count {
+            return Err(ChunkReassemblyError::MalformedChunk);
+        }
+
+        let key = (sender, chunk.transfer_id.as_slice().to_vec());
+        if !self.transfers.contains_key(&key) {
+            self.start_transfer(key.clone(), &chunk);
+        }
+        let transfer = self
+            .transfers
+            .get_mut(&key)
+            .ok_or_else(|| LibraryError::custom("Transfer not found."))?;
+        if transfer.count != chunk.count
+            || transfer.payload_digest != chunk.payload_digest.as_slice()
+        {
+            return Err(ChunkReassemblyError::InconsistentChunk);
+        }
+        if let Some(data) = transfer.chunks.get(&chunk.index) {
+            if data.as_slice() != chunk.data.as_slice() {
+                return Err(ChunkReassemblyError::InconsistentChunk);
+            }
+            return Ok(None);
+        }
+
+        let chunk_size = chunk.data.as_slice().len();
+        if transfer.size + chunk_size > self.max_payload_size || chunk_size > self.max_pending_size
+        {
+            self.remove(&key);
+            return Err(ChunkReassemblyError::PayloadTooLarge);
+        }
+        transfer.size += chunk_size;
+        transfer
+            .chunks
+            .insert(chunk.index, chunk.data.as_slice().to_vec());
+        let complete = transfer.chunks.len() as u64 == transfer.count;
+        self.pending_size += chunk_size;
+
+        if !complete {
+            self.evict_until(
+                |reassembler| reassembler.pending_size <= reassembler.max_pending_size,
+                &key,
+            );
+            return Ok(None);
+        }
+
+        let transfer = self
+            .remove(&key)
+            .ok_or_else(|| LibraryError::custom("Transfer not found."))?;
+        let payload = transfer.chunks.into_values().flatten().collect::<Vec<u8>>();
+        let payload_digest = crypto
+            .hash(self.ciphersuite.hash_algorithm(), &payload)
+            .map_err(LibraryError::unexpected_crypto_error)?;
+        if payload_digest != transfer.payload_digest {
+            return Err(ChunkReassemblyError::DigestMismatch);
+        }
+
+        Ok(Some(payload))
+    }
+
+    /// Returns the number of incomplete transfers.
+    pub fn pending_transfers(&self) -> usize {
+        self.transfers.len()
+    }
+
+    /// Returns the number of bytes buffered for incomplete transfers.
+    pub fn pending_size(&self) -> usize {
+        self.pending_size
+    }
+
+    /// Discards all incomplete transfers of the member at `sender`, e.g.
+    /// after it was removed from the group.
+    pub fn discard(&mut self, sender: LeafNodeIndex) {
+        let keys: Vec<TransferKey> = self
+            .transfers
+            .keys()
+            .filter(|(transfer_sender, _)| *transfer_sender == sender)
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    /// Starts the transfer with the given `key`, evicting the oldest transfer
+    /// of the sender if it has too many incomplete transfers.
+    fn start_transfer(&mut self, key: TransferKey, chunk: &MessageChunk) {
+        let sender = key.0;
+        let sender_transfers = |reassembler: &Self| {
+            reassembler
+                .transfers
+                .keys()
+                .filter(|(transfer_sender, _)| *transfer_sender == sender)
+                .count()
+        };
+        while sender_transfers(self) >= self.max_transfers_per_sender.get() {
+            let Some(oldest) = self.oldest(|(transfer_sender, _)| *transfer_sender == sender)
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+
+        self.transfers.insert(
+            key,
+            PartialTransfer {
+                sequence_number: self.next_sequence_number,
+                count: chunk.count,
+                payload_digest: chunk.payload_digest.as_slice().to_vec(),
+                chunks: BTreeMap::new(),
+                size: 0,
+            },
+        );
+        self.next_sequence_number += 1;
+    }
+
+    /// Evicts the oldest transfers other than the one with the key `keep`
+    /// until `done` returns `true`.
+    fn evict_until(&mut self, done: impl Fn(&Self) -> bool, keep: &TransferKey) {
+        while !done(self) {
+            let Some(oldest) = self.oldest(|key| key != keep) else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+
+    /// Returns the key of the oldest transfer that matches the `filter`.
+    fn oldest(&self, filter: impl Fn(&TransferKey) -> bool) -> Option<TransferKey> {
+        self.transfers
+            .iter()
+            .filter(|(key, _)| filter(key))
+            .min_by_key(|(_, transfer)| transfer.sequence_number)
+            .map(|(key, _)| key.clone())
+    }
+
+    fn remove(&mut self, key: &TransferKey) -> Option<PartialTransfer> {
+        let transfer = self.transfers.remove(key)?;
+        self.pending_size -= transfer.size;
+        Some(transfer)
+    }
+}
@@ -1,66 +1,112 @@
 //! This module contains the commit builder types, which can be used to build regular (i.e.
 //! non-external) commits. See the documentation of [`CommitBuilder`] for more information.
 
+use std::collections::{HashMap, HashSet};
+
 use openmls_traits::{
-    crypto::OpenMlsCrypto, random::OpenMlsRand, signatures::Signer, storage::StorageProvider as _,
+    crypto::OpenMlsCrypto, random::OpenMlsRand, signatures::Signer, storage::StorageWriter as _,
 };
-use tls_codec::Serialize as _;
+use tls_codec::{Serialize as _, Size as _};
 
 use crate::{
     binary_tree::LeafNodeIndex,
-    ciphersuite::{signable::Signable as _, Secret},
+    ciphersuite::{
+        hash_ref::{KeyPackageRef, ProposalRef},
+        signable::{Signable as _, SignatureError},
+        Secret,
+    },
+    extensions::{errors::AppExtensionError, ExtensionType},
+    framing::{mls_content::FramedContentBody, MlsMessageBodyOut},
     group::{
         create_commit::CommitType, diff::compute_path::PathComputationResult,
-        CommitBuilderStageError, CreateCommitError, Extension, Extensions, ExternalPubExtension,
-        ProposalQueue, ProposalQueueError, QueuedProposal, RatchetTreeExtension, StagedCommit,
+        CommitBuilderStageError, CommitPolicy, CreateCommitError, Extension, Extensions,
+        ExternalPubExtension, ProposalQueue, ProposalQueueError, ProposalValidationError,
+        QueuedProposal, RatchetTreeExtension, StagedCommit,
     },
     key_packages::KeyPackage,
     messages::{
         group_info::{GroupInfo, GroupInfoTBS},
-        Commit, Welcome,
+        proposals::ProposalType,
+        Commit, ConfirmationTag, Welcome,
     },
     prelude::{LeafNodeParameters, LibraryError},
     schedule::{
-        psk::{load_psks, PskSecret},
+        psk::{load_psks, PskSecret, PskStore},
         JoinerSecret, KeySchedule, PreSharedKeyId,
     },
-    storage::{OpenMlsProvider, StorageProvider},
+    storage::OpenMlsProvider,
     versions::ProtocolVersion,
 };
 
+#[cfg(feature = "operation-timing")]
+use super::timing::{OperationKind, OperationTiming, Recording};
 use super::{
     mls_auth_content::AuthenticatedContent,
+    proposal_check::{filter_reason, ProposalFilterReason},
     staged_commit::{MemberStagedCommitState, StagedCommitState},
+    timing::{measure, TimingPhase},
+    validation_artifact::{CommitValidationArtifact, UnsignedValidationArtifact},
     AddProposal, CreateCommitResult, GroupContextExtensionProposal, MlsGroup, MlsGroupState,
     MlsMessageOut, PendingCommitState, Proposal, RemoveProposal, Sender,
 };
 
+/// Controls whether a commit includes an update path. See
+/// [`CommitBuilder::update_path()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpdatePathInclusion {
+    /// Include an update path only if one is required, i.e. if one of the
+    /// proposals requires it, new leaf node parameters are set or the group
+    /// enforces full commits (see
+    /// [`MlsGroupJoinConfig::force_full_commits()`](crate::group::MlsGroupJoinConfig::force_full_commits)).
+    /// This is the default.
+    #[default]
+    WhenRequired,
+    /// Always include an update path, i.e. force a self-update.
+    Always,
+    /// Never include an update path, to keep the commit small. Building the
+    /// commit fails with [`CreateCommitError::UpdatePathRequired`] if one is
+    /// required.
+    Never,
+}
+
 /// This stage is for populating the builder.
 pub struct Initial {
     own_proposals: Vec<Proposal>,
-    force_self_update: bool,
+    update_path: UpdatePathInclusion,
     leaf_node_parameters: LeafNodeParameters,
 
     /// Whether or not to clear the proposal queue of the group when staging the commit. Needs to
     /// be done when we include the commits that have already been queued.
     consume_proposal_store: bool,
+
+    /// Whether or not to produce a [`CommitValidationArtifact`] for the delivery service.
+    validation_artifact: bool,
+
+    /// Application-defined extensions that are added to the GroupInfo in the Welcome.
+    onboarding_data: Vec<Extension>,
 }
 
 /// This stage is after the PSKs were loaded, ready for validation
 pub struct LoadedPsks {
     own_proposals: Vec<Proposal>,
-    force_self_update: bool,
+    update_path: UpdatePathInclusion,
     leaf_node_parameters: LeafNodeParameters,
 
     /// Whether or not to clear the proposal queue of the group when staging the commit. Needs to
     /// be done when we include the commits that have already been queued.
     consume_proposal_store: bool,
     psks: Vec<(PreSharedKeyId, Secret)>,
+    validation_artifact: bool,
+    onboarding_data: Vec<Extension>,
 }
 
 /// This stage is after we validated the data, ready for staging and exporting the messages
 pub struct Complete {
     result: CreateCommitResult,
+    report: CommitReport,
+    validation_artifact: Option<UnsignedValidationArtifact>,
+    #[cfg(feature = "operation-timing")]
+    timing: OperationTiming,
 }
 
 /// The [`CommitBuilder`] is used to easily and dynamically build commit messages.
@@ -149,9 +195,11 @@ impl<'a> CommitBuilder<'a, Initial> {
             group,
             stage: Initial {
                 consume_proposal_store: true,
-                force_self_update: false,
+                update_path: UpdatePathInclusion::default(),
                 leaf_node_parameters: LeafNodeParameters::default(),
                 own_proposals: vec![],
+                validation_artifact: false,
+                onboarding_data: vec![],
             },
         }
     }
@@ -164,8 +212,50 @@ impl<'a> CommitBuilder<'a, Initial> {
     }
 
     /// Sets whether or not the commit should force a self-update. Defaults to `false`.
+    /// This is a shorthand for setting [`Self::update_path()`] to
+    /// [`UpdatePathInclusion::Always`] or [`UpdatePathInclusion::WhenRequired`].
     pub fn force_self_update(mut self, force_self_update: bool) -> Self {
-        self.stage.force_self_update = force_self_update;
+        self.stage.update_path = if force_self_update {
+            UpdatePathInclusion::Always
+        } else {
+            UpdatePathInclusion::WhenRequired
+        };
+        self
+    }
+
+    /// Sets whether the commit includes an update path. Defaults to
+    /// [`UpdatePathInclusion::WhenRequired`], i.e. the path is omitted
+    /// whenever RFC 9420 permits it.
+    ///
+    /// Receivers reject commits without a path if one is required, so
+    /// [`UpdatePathInclusion::Never`] lets bandwidth-sensitive applications
+    /// make sure that they only send small commits, without risking invalid
+    /// ones.
+    pub fn update_path(mut self, update_path: UpdatePathInclusion) -> Self {
+        self.stage.update_path = update_path;
+        self
+    }
+
+    /// Sets whether a [`CommitValidationArtifact`] is produced together with the commit, which
+    /// lets the delivery service validate the commit without the full public group. Defaults to
+    /// `false`. See [`CommitMessageBundle::sign_validation_artifact()`].
+    pub fn validation_artifact(mut self, validation_artifact: bool) -> Self {
+        self.stage.validation_artifact = validation_artifact;
+        self
+    }
+
+    /// Adds application-defined onboarding data for new members, e.g. a group avatar, an invite
+    /// message or role assignments, as extensions of the GroupInfo in the Welcome. The GroupInfo
+    /// is signed by the committer, so the new members can inspect the authenticated data with
+    /// [`StagedWelcome::onboarding_data()`](crate::group::StagedWelcome::onboarding_data) before
+    /// they join.
+    ///
+    /// The data is only sent if the commit adds members, and only in the GroupInfo of the
+    /// Welcome, not in the one returned with the commit. Building the commit fails with
+    /// [`CreateCommitError::AppExtension`] if an extension type is defined by MLS, and with
+    /// [`CreateCommitError::InvalidExtensionError`] if an extension type is used twice.
+    pub fn onboarding_data(mut self, extensions: impl IntoIterator<Item = Extension>) -> Self {
+        self.stage.onboarding_data.extend(extensions);
         self
     }
 
@@ -218,9 +308,12 @@ impl<'a> CommitBuilder<'a, Initial> {
     }
 
     /// Loads the PSKs for the PskProposals marked for inclusion and moves on to the next phase.
-    pub fn load_psks<Storage: StorageProvider>(
+    ///
+    /// The PSKs are looked up in the given [`PskStore`], which is usually the
+    /// storage of the provider.
+    pub fn load_psks<Store: PskStore>(
         self,
-        storage: &'a Storage,
+        psk_store: &Store,
     ) -> Result<CommitBuilder<'a, LoadedPsks>, CreateCommitError> {
         let psk_ids: Vec<_> = self
             .stage
@@ -239,7 +332,7 @@ impl<'a> CommitBuilder<'a, Initial> {
             .collect();
 
         // Load the PSKs and make the PskIds owned.
-        let psks = load_psks(storage, &self.group.resumption_psk_store, &psk_ids)?
+        let psks = load_psks(psk_store, &self.group.resumption_psk_store, &psk_ids)?
             .into_iter()
             .map(|(psk_id_ref, key)| (psk_id_ref.clone(), key))
             .collect();
@@ -251,9 +344,11 @@ impl<'a> CommitBuilder<'a, Initial> {
                     LoadedPsks {
                         own_proposals: stage.own_proposals,
                         psks,
-                        force_self_update: stage.force_self_update,
+                        update_path: stage.update_path,
                         leaf_node_parameters: stage.leaf_node_parameters,
                         consume_proposal_store: stage.consume_proposal_store,
+                        validation_artifact: stage.validation_artifact,
+                        onboarding_data: stage.onboarding_data,
                     },
                 )
             })
@@ -261,6 +356,16 @@ impl<'a> CommitBuilder<'a, Initial> {
     }
 }
 
+/// A [`CommitPolicy`] that keeps the proposals for which the function returns `true`.
+struct ProposalFilter<F>(F);
+
+impl<F: FnMut(&QueuedProposal) -> bool> CommitPolicy for ProposalFilter<F> {
+    fn select_proposals(&mut self, mut candidates: Vec<QueuedProposal>) -> Vec<QueuedProposal> {
+        candidates.retain(|proposal| (self.0)(proposal));
+        candidates
+    }
+}
+
 impl<'a> CommitBuilder<'a, LoadedPsks> {
     /// Validates the inputs and builds the commit. The last argument `f` is a function that lets
     /// the caller filter the proposals that are considered for inclusion. This provides a way for
@@ -272,11 +377,37 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
         signer: &impl Signer,
         f: impl FnMut(&QueuedProposal) -> bool,
     ) -> Result<CommitBuilder<'a, Complete>, CreateCommitError> {
+        self.build_with_policy(rand, crypto, signer, &mut ProposalFilter(f))
+    }
+
+    /// Validates the inputs and builds the commit, letting the [`CommitPolicy`] select and order
+    /// the proposals that are considered for inclusion. See [`CommitPolicy`] for details.
+    pub fn build_with_policy(
+        self,
+        rand: &impl OpenMlsRand,
+        crypto: &impl OpenMlsCrypto,
+        signer: &impl Signer,
+        policy: &mut impl CommitPolicy,
+    ) -> Result<CommitBuilder<'a, Complete>, CreateCommitError> {
+        #[cfg(feature = "operation-timing")]
+        let recording = Recording::start(OperationKind::CreateCommit);
+
         let ciphersuite = self.group.ciphersuite();
         let sender = Sender::build_member(self.group.own_leaf_index());
         let (cur_stage, builder) = self.take_stage();
         let psks = cur_stage.psks;
 
+        // The onboarding data is defined by the application, so it can't use
+        // the extension types defined by MLS.
+        if let Some(extension_type) = cur_stage
+            .onboarding_data
+            .iter()
+            .map(Extension::extension_type)
+            .find(|extension_type| !matches!(extension_type, ExtensionType::Unknown(_)))
+        {
+            return Err(AppExtensionError::ReservedExtensionType(extension_type.into()).into());
+        }
+
         // put the pending and uniform proposals into a uniform shape,
         // i.e. produce queued proposals from the own proposals
         let own_proposals: Vec<_> = cur_stage
@@ -295,14 +426,76 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
             .filter(|_| cur_stage.consume_proposal_store)
             .cloned();
 
-        // prepare the iterator for the proposal validation and seletion function. That function
+        // prepare the list for the proposal validation and seletion function. That function
         // assumes that "earlier in the list" means "older", so since our own proposals are
         // newest, we have to put them last.
-        let proposal_queue = group_proposal_store_queue.chain(own_proposals).filter(f);
+        let all_proposals: Vec<_> = group_proposal_store_queue.chain(own_proposals).collect();
+
+        // Let the policy select and order the proposals. The policy can only select from the
+        // given proposals, and each proposal is considered only once.
+        let age: HashMap<_, _> = all_proposals
+            .iter()
+            .enumerate()
+            .map(|(age, proposal)| (proposal.proposal_reference(), age))
+            .collect();
+        let mut considered = HashSet::new();
+        let mut candidates: Vec<_> = policy
+            .select_proposals(all_proposals.clone())
+            .into_iter()
+            .filter(|proposal| {
+                let proposal_ref = proposal.proposal_reference();
+                age.contains_key(&proposal_ref) && considered.insert(proposal_ref)
+            })
+            .collect();
+
+        // The most recent Update of a member supersedes the older ones, so Updates must stay in
+        // the order in which they were received, regardless of the order chosen by the policy.
+        let update_slots: Vec<_> = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, proposal)| proposal.proposal().is_type(ProposalType::Update))
+            .map(|(slot, _)| slot)
+            .collect();
+        let mut updates: Vec<_> = update_slots
+            .iter()
+            .map(|&slot| candidates[slot].clone())
+            .collect();
+        updates.sort_by_key(|update| age[&update.proposal_reference()]);
+        for (slot, update) in update_slots.into_iter().zip(updates) {
+            candidates[slot] = update;
+        }
+
+        // Removals of members the policy protects are not committed. Pending proposals of other
+        // members are left out, but the own proposals of the caller are an error.
+        let mut protected_removal = false;
+        candidates.retain(|proposal| {
+            let Proposal::Remove(remove_proposal) = proposal.proposal() else {
+                return true;
+            };
+            let admitted = match builder.group.public_group.leaf(remove_proposal.removed()) {
+                Some(leaf_node) => policy.admit_remove(proposal.sender(), leaf_node.credential()),
+                // Removals of unknown members are rejected by the regular validation.
+                None => true,
+            };
+            protected_removal |= !admitted && proposal.sender() == &sender;
+            admitted
+        });
+        if protected_removal {
+            return Err(ProposalValidationError::ProtectedMemberRemoval.into());
+        }
+
+        let selected_refs: HashSet<_> = candidates
+            .iter()
+            .map(QueuedProposal::proposal_reference)
+            .collect();
+        let rejected: Vec<_> = all_proposals
+            .into_iter()
+            .filter(|proposal| !selected_refs.contains(&proposal.proposal_reference()))
+            .collect();
 
         let (proposal_queue, contains_own_updates) =
             ProposalQueue::filter_proposals_without_inline(
-                proposal_queue,
+                candidates.iter().cloned(),
                 builder.group.own_leaf_index,
             )
             .map_err(|e| match e {
@@ -313,58 +506,15 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
                 }
             })?;
 
-        // Validate the proposals by doing the following checks:
-
-        // ValSem113: All Proposals: The proposal type must be supported by all
-        // members of the group
-        builder
-            .group
-            .public_group
-            .validate_proposal_type_support(&proposal_queue)?;
-        // ValSem101
-        // ValSem102
-        // ValSem103
-        // ValSem104
-        builder
-            .group
-            .public_group
-            .validate_key_uniqueness(&proposal_queue, None)?;
-        // ValSem105
-        builder
-            .group
-            .public_group
-            .validate_add_proposals(&proposal_queue)?;
-        // ValSem106
-        // ValSem109
-        builder
-            .group
-            .public_group
-            .validate_capabilities(&proposal_queue)?;
-        // ValSem107
-        // ValSem108
-        builder
-            .group
-            .public_group
-            .validate_remove_proposals(&proposal_queue)?;
-        builder
-            .group
-            .public_group
-            .validate_pre_shared_key_proposals(&proposal_queue)?;
-        // Validate update proposals for member commits
-        // ValSem110
-        // ValSem111
-        // ValSem112
-        builder
-            .group
-            .public_group
-            .validate_update_proposals(&proposal_queue, builder.group.own_leaf_index())?;
+        // Validate the proposals
+        builder.group.validate_commit_proposals(&proposal_queue)?;
 
-        // ValSem208
-        // ValSem209
-        builder
-            .group
-            .public_group
-            .validate_group_context_extensions_proposal(&proposal_queue)?;
+        let report = CommitReport::new(
+            &proposal_queue,
+            &candidates,
+            &rejected,
+            builder.group.own_leaf_index(),
+        );
 
         let ciphersuite = builder.group.ciphersuite();
         let sender = Sender::build_member(builder.group.own_leaf_index());
@@ -374,32 +524,45 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
         let mut diff = builder.group.public_group.empty_diff();
 
         // Apply proposals to tree
-        let apply_proposals_values =
-            diff.apply_proposals(&proposal_queue, builder.group.own_leaf_index())?;
+        let apply_proposals_values = measure(TimingPhase::TreeDiff, || {
+            diff.apply_proposals(&proposal_queue, builder.group.own_leaf_index())
+        })?;
         if apply_proposals_values.self_removed {
             return Err(CreateCommitError::CannotRemoveSelf);
         }
 
+        // If the group enforces full commits, every commit needs a path.
+        let path_required = apply_proposals_values.path_required
+            || contains_own_updates
+            || !cur_stage.leaf_node_parameters.is_empty()
+            || builder.group.mls_group_config.force_full_commits;
+        let include_path = match cur_stage.update_path {
+            UpdatePathInclusion::WhenRequired => path_required,
+            UpdatePathInclusion::Always => true,
+            UpdatePathInclusion::Never if path_required => {
+                return Err(CreateCommitError::UpdatePathRequired)
+            }
+            UpdatePathInclusion::Never => false,
+        };
+
         let path_computation_result =
             // If path is needed, compute path values
-            if apply_proposals_values.path_required
-                || contains_own_updates
-                || cur_stage.force_self_update
-                || !cur_stage.leaf_node_parameters.is_empty()
-            {
+            if include_path {
                 // Process the path. This includes updating the provisional
                 // group context by updating the epoch and computing the new
                 // tree hash.
-                diff.compute_path(
-                    rand,
-                    crypto,
-                    builder.group.own_leaf_index(),
-                    apply_proposals_values.exclusion_list(),
-                    &CommitType::Member,
-                    &cur_stage.leaf_node_parameters,
-                    signer,
-                    apply_proposals_values.extensions.clone()
-                )?
+                measure(TimingPhase::TreeDiff, || {
+                    diff.compute_path(
+                        rand,
+                        crypto,
+                        builder.group.own_leaf_index(),
+                        apply_proposals_values.exclusion_list(),
+                        &CommitType::Member,
+                        &cur_stage.leaf_node_parameters,
+                        signer,
+                        apply_proposals_values.extensions.clone()
+                    )
+                })?
             } else {
                 // If path is not needed, update the group context and return
                 // empty path processing results
@@ -419,13 +582,15 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
         };
 
         // Build AuthenticatedContent
-        let mut authenticated_content = AuthenticatedContent::commit(
-            builder.group.framing_parameters(),
-            sender,
-            commit,
-            builder.group.public_group.group_context(),
-            signer,
-        )?;
+        let mut authenticated_content = measure(TimingPhase::Signature, || {
+            AuthenticatedContent::commit(
+                builder.group.framing_parameters(),
+                sender,
+                commit,
+                builder.group.public_group.group_context(),
+                signer,
+            )
+        })?;
 
         // Update the confirmed transcript hash using the commit we just created.
         diff.update_confirmed_transcript_hash(crypto, &authenticated_content)?;
@@ -480,16 +645,28 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
 
         diff.update_interim_transcript_hash(ciphersuite, crypto, confirmation_tag.clone())?;
 
+        let validation_artifact = if cur_stage.validation_artifact {
+            Some(UnsignedValidationArtifact::new(
+                crypto,
+                &builder.group.public_group,
+                builder.group.own_leaf_index(),
+                &proposal_queue,
+                diff.group_context().clone(),
+            )?)
+        } else {
+            None
+        };
+
         // If there are invitations, we need to build a welcome
         let needs_welcome = !apply_proposals_values.invitation_list.is_empty();
 
         // We need a GroupInfo if we need to build a Welcome. If the ratchet tree extension
         // should be used, always build a GroupInfo.
-        let needs_group_info =
-            needs_welcome || builder.group.configuration().use_ratchet_tree_extension;
+        let use_ratchet_tree_extension = builder.group.configuration().use_ratchet_tree_extension;
+        let needs_group_info = needs_welcome || use_ratchet_tree_extension;
 
-        let group_info = if !needs_group_info {
-            None
+        let (group_info, welcome_group_info) = if !needs_group_info {
+            (None, None)
         } else {
             // Build ExternalPub extension
             let external_pub = provisional_epoch_secrets
@@ -501,27 +678,46 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
                 Extension::ExternalPub(ExternalPubExtension::new(external_pub.into()));
 
             // Create the ratchet tree extension if necessary
-            let extensions: Extensions = if builder.group.configuration().use_ratchet_tree_extension
-            {
-                Extensions::from_vec(vec![
+            let extensions = if use_ratchet_tree_extension {
+                vec![
                     Extension::RatchetTree(RatchetTreeExtension::new(diff.export_ratchet_tree())),
                     external_pub_extension,
-                ])?
+                ]
             } else {
-                Extensions::single(external_pub_extension)
+                vec![external_pub_extension]
             };
 
-            // Create to-be-signed group info.
-            let group_info_tbs = {
-                GroupInfoTBS::new(
+            // Create and sign the to-be-signed group info.
+            let sign_group_info = |extensions| -> Result<GroupInfo, CreateCommitError> {
+                let group_info_tbs = GroupInfoTBS::new(
                     diff.group_context().clone(),
-                    extensions,
-                    confirmation_tag,
+                    Extensions::from_vec(extensions)?,
+                    confirmation_tag.clone(),
                     builder.group.own_leaf_index(),
-                )
+                );
+                Ok(measure(TimingPhase::Signature, || {
+                    group_info_tbs.sign(signer)
+                })?)
             };
-            // Sign to-be-signed group info.
-            Some(group_info_tbs.sign(signer)?)
+
+            // The onboarding data is only meant for the new members, so it is
+            // only added to the GroupInfo in the Welcome.
+            let group_info = use_ratchet_tree_extension
+                .then(|| sign_group_info(extensions.clone()))
+                .transpose()?;
+            let welcome_group_info = match &group_info {
+                _ if !needs_welcome => None,
+                Some(group_info) if cur_stage.onboarding_data.is_empty() => {
+                    Some(group_info.clone())
+                }
+                _ => Some(sign_group_info(
+                    extensions
+                        .into_iter()
+                        .chain(cur_stage.onboarding_data)
+                        .collect(),
+                )?),
+            };
+            (group_info, welcome_group_info)
         };
 
         let welcome_option = if !needs_welcome {
@@ -534,7 +730,7 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
             let encrypted_group_info = welcome_key
                 .aead_seal(
                     crypto,
-                    group_info
+                    welcome_group_info
                         .as_ref()
                         .ok_or_else(|| LibraryError::custom("GroupInfo was not computed"))?
                         .tls_serialize_detached()
@@ -549,7 +745,7 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
             // `joiner_secret`.
             let encrypted_secrets = diff.encrypt_group_secrets(
                 &joiner_secret,
-                apply_proposals_values.invitation_list,
+                &apply_proposals_values.invitation_list,
                 path_computation_result.plain_path.as_deref(),
                 &apply_proposals_values.presharedkeys,
                 &encrypted_group_info,
@@ -584,15 +780,17 @@ impl<'a> CommitBuilder<'a, LoadedPsks> {
             StagedCommitState::GroupMember(Box::new(staged_commit_state)),
         );
 
-        let use_ratchet_tree_extension = builder.group.configuration().use_ratchet_tree_extension;
-
         Ok(builder.into_stage(Complete {
             result: CreateCommitResult {
                 commit: authenticated_content,
                 welcome_option,
                 staged_commit,
-                group_info: group_info.filter(|_| use_ratchet_tree_extension),
+                group_info,
             },
+            report,
+            validation_artifact,
+            #[cfg(feature = "operation-timing")]
+            timing: recording.finish(),
         }))
     }
 }
@@ -603,6 +801,12 @@ impl CommitBuilder<'_, Complete> {
         self.stage.result
     }
 
+    /// Returns the report of which proposals are included in the commit and
+    /// which were filtered out.
+    pub fn report(&self) -> &CommitReport {
+        &self.stage.report
+    }
+
     /// Stages the commit and returns the protocol messages.
     pub fn stage_commit<Provider: OpenMlsProvider>(
         self,
@@ -610,41 +814,259 @@ impl CommitBuilder<'_, Complete> {
     ) -> Result<CommitMessageBundle, CommitBuilderStageError<Provider::StorageError>> {
         let Self {
             group,
-            stage: Complete {
-                result: create_commit_result,
-            },
+            stage:
+                Complete {
+                    result: create_commit_result,
+                    report,
+                    validation_artifact,
+                    #[cfg(feature = "operation-timing")]
+                        timing: mut build_timing,
+                },
             ..
         } = self;
 
-        // Set the current group state to [`MlsGroupState::PendingCommit`],
-        // storing the current [`StagedCommit`] from the commit results
-        group.group_state = MlsGroupState::PendingCommit(Box::new(PendingCommitState::Member(
-            create_commit_result.staged_commit,
-        )));
+        #[cfg(feature = "operation-timing")]
+        let recording = Recording::start(OperationKind::CreateCommit);
 
-        provider
-            .storage()
-            .write_group_state(group.group_id(), &group.group_state)
-            .map_err(CommitBuilderStageError::KeyStoreError)?;
-
-        group.reset_aad();
+        let confirmation_tag = create_commit_result
+            .staged_commit
+            .confirmation_tag()
+            .clone();
 
         // Convert PublicMessage messages to MLSMessage and encrypt them if required by the
         // configuration.
         //
         // Note that this performs writes to the storage, so we should do that here, rather than
         // when working with the result.
+        let mut size_report = CommitSizeReport::new(
+            &create_commit_result.commit,
+            &report,
+            create_commit_result.welcome_option.as_ref(),
+            create_commit_result.group_info.as_ref(),
+        );
         let mls_message = group.content_to_mls_message(create_commit_result.commit, provider)?;
+        size_report.commit = mls_message.tls_serialized_len();
+        let validation_artifact = validation_artifact
+            .map(|mut validation_artifact| {
+                validation_artifact
+                    .set_commit(provider.crypto(), &mls_message)
+                    .map(|_| validation_artifact)
+            })
+            .transpose()?;
+
+        // We can't decrypt our own private messages, so we remember the
+        // ciphertext to recognize the commit if the DS sends it back to us.
+        let mut staged_commit = create_commit_result.staged_commit;
+        if let MlsMessageBodyOut::PrivateMessage(private_message) = mls_message.body() {
+            let ciphertext_hash = provider
+                .crypto()
+                .hash(
+                    group.ciphersuite().hash_algorithm(),
+                    private_message.ciphertext(),
+                )
+                .map_err(LibraryError::unexpected_crypto_error)?;
+            staged_commit.set_ciphertext_hash(ciphertext_hash);
+        }
+
+        // Set the current group state to [`MlsGroupState::PendingCommit`],
+        // storing the current [`StagedCommit`] from the commit results
+        group.group_state =
+            MlsGroupState::PendingCommit(Box::new(PendingCommitState::Member(staged_commit)));
+
+        measure(TimingPhase::StorageIo, || {
+            provider
+                .storage()
+                .write_group_state(group.group_id(), &group.group_state)
+        })
+        .map_err(CommitBuilderStageError::KeyStoreError)?;
+
+        group.reset_aad();
+
+        #[cfg(feature = "operation-timing")]
+        {
+            build_timing.extend(recording.finish());
+            group.set_last_operation_timing(build_timing);
+        }
 
         Ok(CommitMessageBundle {
             version: group.version(),
             commit: mls_message,
             welcome: create_commit_result.welcome_option,
             group_info: create_commit_result.group_info,
+            report,
+            size_report,
+            confirmation_tag,
+            validation_artifact,
         })
     }
 }
 
+/// Reports which proposals were included in a commit and which were filtered
+/// out, together with the reason. Proposals that fail validation are not
+/// filtered out, but make the creation of the commit fail.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommitReport {
+    included: Vec<ProposalRef>,
+    filtered: Vec<(ProposalRef, ProposalFilterReason)>,
+}
+
+impl CommitReport {
+    fn new(
+        proposal_queue: &ProposalQueue,
+        candidates: &[QueuedProposal],
+        rejected: &[QueuedProposal],
+        own_leaf_index: LeafNodeIndex,
+    ) -> Self {
+        let included = proposal_queue
+            .queued_proposals()
+            .map(QueuedProposal::proposal_reference)
+            .collect();
+        let filtered = candidates
+            .iter()
+            .filter(|candidate| {
+                proposal_queue
+                    .get(&candidate.proposal_reference())
+                    .is_none()
+            })
+            .map(|candidate| {
+                (
+                    candidate.proposal_reference(),
+                    filter_reason(candidate, own_leaf_index),
+                )
+            })
+            .chain(rejected.iter().map(|proposal| {
+                (
+                    proposal.proposal_reference(),
+                    ProposalFilterReason::ApplicationPolicy,
+                )
+            }))
+            .collect::<Vec<_>>();
+
+        for (proposal_ref, reason) in &filtered {
+            log::debug!("Proposal {proposal_ref:?} not included in commit: {reason:?}");
+        }
+
+        Self { included, filtered }
+    }
+
+    /// Returns the references of the proposals that are included in the commit,
+    /// in the order of the commit. This includes proposals that are committed
+    /// by value.
+    pub fn included(&self) -> &[ProposalRef] {
+        &self.included
+    }
+
+    /// Returns the references of the proposals that were not included in the
+    /// commit, together with the reason.
+    pub fn filtered(&self) -> &[(ProposalRef, ProposalFilterReason)] {
+        &self.filtered
+    }
+
+    /// Returns the reason why the proposal with the given reference was not
+    /// included in the commit, or `None` if it wasn't filtered out.
+    pub fn filter_reason(&self, proposal_ref: &ProposalRef) -> Option<ProposalFilterReason> {
+        self.filtered
+            .iter()
+            .find(|(filtered_ref, _)| filtered_ref == proposal_ref)
+            .map(|(_, reason)| *reason)
+    }
+}
+
+/// Reports the sizes of the parts of a commit in bytes, as they are encoded on
+/// the wire. Applications that hit a message size limit of the DS can use it to
+/// decide which proposals to commit separately or whether to send the ratchet
+/// tree out of band.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitSizeReport {
+    commit: usize,
+    update_path: Option<usize>,
+    proposals: Vec<(ProposalRef, usize)>,
+    welcome: Option<usize>,
+    welcome_recipients: Vec<(KeyPackageRef, usize)>,
+    group_info: Option<usize>,
+}
+
+impl CommitSizeReport {
+    fn new(
+        commit: &AuthenticatedContent,
+        report: &CommitReport,
+        welcome: Option<&Welcome>,
+        group_info: Option<&GroupInfo>,
+    ) -> Self {
+        let (update_path, proposals) = match commit.content() {
+            FramedContentBody::Commit(commit) => (
+                commit.path.as_ref().map(|path| path.tls_serialized_len()),
+                report
+                    .included()
+                    .iter()
+                    .cloned()
+                    .zip(
+                        commit
+                            .proposals
+                            .iter()
+                            .map(|proposal| proposal.tls_serialized_len()),
+                    )
+                    .collect(),
+            ),
+            _ => (None, vec![]),
+        };
+        let welcome_recipients = welcome
+            .map(|welcome| {
+                welcome
+                    .secrets()
+                    .iter()
+                    .map(|secrets| (secrets.new_member(), secrets.tls_serialized_len()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            commit: 0,
+            update_path,
+            proposals,
+            welcome: welcome.map(|welcome| welcome.tls_serialized_len()),
+            welcome_recipients,
+            group_info: group_info.map(|group_info| group_info.tls_serialized_len()),
+        }
+    }
+
+    /// Returns the size of the commit message.
+    pub fn commit(&self) -> usize {
+        self.commit
+    }
+
+    /// Returns the size of the update path of the commit, or `None` if the
+    /// commit has no path.
+    pub fn update_path(&self) -> Option<usize> {
+        self.update_path
+    }
+
+    /// Returns the size of each proposal in the commit, in the order of the
+    /// commit. Proposals that are committed by reference only take up the
+    /// size of the reference.
+    pub fn proposals(&self) -> &[(ProposalRef, usize)] {
+        &self.proposals
+    }
+
+    /// Returns the size of the Welcome, or `None` if there is none.
+    pub fn welcome(&self) -> Option<usize> {
+        self.welcome
+    }
+
+    /// Returns the size of the group secrets that are encrypted to each new
+    /// member in the Welcome. The rest of the Welcome, mostly the encrypted
+    /// GroupInfo, is shared by all new members.
+    pub fn welcome_recipients(&self) -> &[(KeyPackageRef, usize)] {
+        &self.welcome_recipients
+    }
+
+    /// Returns the size of the GroupInfo returned with the commit, or `None`
+    /// if there is none.
+    pub fn group_info(&self) -> Option<usize> {
+        self.group_info
+    }
+}
+
 /// Contains the messages that are produced by committing. The messages can be accessed individually
 /// using getters or through the [`IntoIterator`] interface.
 #[derive(Debug, Clone)]
@@ -653,6 +1075,10 @@ pub struct CommitMessageBundle {
     commit: MlsMessageOut,
     welcome: Option<Welcome>,
     group_info: Option<GroupInfo>,
+    report: CommitReport,
+    size_report: CommitSizeReport,
+    confirmation_tag: ConfirmationTag,
+    validation_artifact: Option<UnsignedValidationArtifact>,
 }
 
 #[cfg(test)]
@@ -662,12 +1088,17 @@ impl CommitMessageBundle {
         commit: MlsMessageOut,
         welcome: Option<Welcome>,
         group_info: Option<GroupInfo>,
+        confirmation_tag: ConfirmationTag,
     ) -> Self {
         Self {
             version,
             commit,
             welcome,
             group_info,
+            report: CommitReport::default(),
+            size_report: CommitSizeReport::default(),
+            confirmation_tag,
+            validation_artifact: None,
         }
     }
 }
@@ -701,6 +1132,39 @@ impl CommitMessageBundle {
         self.group_info.as_ref()
     }
 
+    /// Gets the report of which proposals were included in the commit and which
+    /// were filtered out.
+    pub fn report(&self) -> &CommitReport {
+        &self.report
+    }
+
+    /// Gets the sizes of the parts of the commit, the Welcome and the
+    /// GroupInfo, e.g. to find out what to split or omit if the messages
+    /// exceed a size limit of the DS.
+    pub fn size_report(&self) -> &CommitSizeReport {
+        &self.size_report
+    }
+
+    /// Gets the [`ConfirmationTag`] of the commit, which can be compared out of
+    /// band with the one other members get from
+    /// [`StagedCommit::confirmation_tag()`] when processing the commit.
+    pub fn confirmation_tag(&self) -> &ConfirmationTag {
+        &self.confirmation_tag
+    }
+
+    /// Signs the [`CommitValidationArtifact`] for the delivery service with the `signer` of the
+    /// committer, which must hold the signature key of the own leaf. Only [`Some`] if it was
+    /// requested with [`CommitBuilder::validation_artifact()`].
+    pub fn sign_validation_artifact(
+        &self,
+        signer: &impl Signer,
+    ) -> Result<Option<CommitValidationArtifact>, SignatureError> {
+        self.validation_artifact
+            .clone()
+            .map(|validation_artifact| validation_artifact.sign(signer))
+            .transpose()
+    }
+
     /// Gets all three messages, some of which optional. For owned version, see
     /// [`Self::into_contents`].
     pub fn contents(&self) -> (&MlsMessageOut, Option<&Welcome>, Option<&GroupInfo>) {
@@ -0,0 +1,305 @@
+//! This module contains the simulation of a commit. See
+//! [`MlsGroup::simulate_commit()`].
+//!
+//! A simulation applies the proposals that a commit would cover to a copy of
+//! the group state, without deriving secrets, signing anything or changing
+//! the state of the group. It reports the resulting epoch and membership, and
+//! estimates the sizes of the messages the commit would produce, so that
+//! applications can preview the effect and cost of an operation.
+
+use openmls_traits::crypto::OpenMlsCrypto;
+use tls_codec::Size as _;
+
+use super::{
+    proposal_store::{ProposalQueue, QueuedProposal},
+    AddProposal, Member, MlsGroup, Proposal, RemoveProposal, Sender,
+};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    error::LibraryError,
+    group::{CreateCommitError, GroupEpoch, ProposalQueueError},
+    key_packages::KeyPackage,
+    messages::Commit,
+};
+
+// The size of the authentication tag of all AEADs supported by MLS.
+const AEAD_TAG_LENGTH: usize = 16;
+
+/// The proposals a simulated commit covers. See
+/// [`MlsGroup::simulate_commit()`].
+///
+/// Like the [`CommitBuilder`](super::commit_builder::CommitBuilder), the
+/// simulation covers the proposals in the proposal store of the group by
+/// default, in addition to the proposals added here.
+#[derive(Debug, Clone)]
+pub struct CommitSimulationParams {
+    proposals: Vec<Proposal>,
+    consume_proposal_store: bool,
+    force_self_update: bool,
+}
+
+impl Default for CommitSimulationParams {
+    fn default() -> Self {
+        Self {
+            proposals: vec![],
+            consume_proposal_store: true,
+            force_self_update: false,
+        }
+    }
+}
+
+impl CommitSimulationParams {
+    /// Creates new, empty parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether or not the proposals in the proposal store of the group
+    /// should be covered. Defaults to `true`.
+    pub fn consume_proposal_store(mut self, consume_proposal_store: bool) -> Self {
+        self.consume_proposal_store = consume_proposal_store;
+        self
+    }
+
+    /// Sets whether or not the commit would force a self-update. Defaults to
+    /// `false`.
+    pub fn force_self_update(mut self, force_self_update: bool) -> Self {
+        self.force_self_update = force_self_update;
+        self
+    }
+
+    /// Adds a proposal to the proposals to be committed.
+    pub fn add_proposal(mut self, proposal: Proposal) -> Self {
+        self.proposals.push(proposal);
+        self
+    }
+
+    /// Adds an Add proposal for each of the [`KeyPackage`]s.
+    pub fn propose_adds(mut self, key_packages: impl IntoIterator<Item = KeyPackage>) -> Self {
+        self.proposals.extend(
+            key_packages
+                .into_iter()
+                .map(|key_package| Proposal::Add(AddProposal { key_package })),
+        );
+        self
+    }
+
+    /// Adds a Remove proposal for each of the leaf indices.
+    pub fn propose_removals(mut self, removed: impl IntoIterator<Item = LeafNodeIndex>) -> Self {
+        self.proposals.extend(
+            removed
+                .into_iter()
+                .map(|removed| Proposal::Remove(RemoveProposal { removed })),
+        );
+        self
+    }
+}
+
+/// The prospective state of a group after a simulated commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitSimulation {
+    epoch: GroupEpoch,
+    members: Vec<Member>,
+    path_required: bool,
+    joiners: usize,
+    estimated_commit_size: usize,
+    estimated_welcome_size: usize,
+}
+
+impl CommitSimulation {
+    /// Returns the epoch of the group after the commit.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the members of the group after the commit.
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Returns `true` if the commit would contain an update path.
+    pub fn path_required(&self) -> bool {
+        self.path_required
+    }
+
+    /// Returns the number of members the commit would add.
+    pub fn joiners(&self) -> usize {
+        self.joiners
+    }
+
+    /// Returns the number of Welcome messages the commit would produce. A
+    /// single Welcome covers all new members, so this is either 0 or 1.
+    pub fn welcome_count(&self) -> usize {
+        usize::from(self.joiners > 0)
+    }
+
+    /// Returns an estimate of the size in bytes of the serialized `Commit`,
+    /// without the framing of the message that carries it.
+    pub fn estimated_commit_size(&self) -> usize {
+        self.estimated_commit_size
+    }
+
+    /// Returns an estimate of the size in bytes of the serialized `Welcome`,
+    /// or 0 if the commit doesn't add any members.
+    pub fn estimated_welcome_size(&self) -> usize {
+        self.estimated_welcome_size
+    }
+}
+
+impl MlsGroup {
+    /// Simulates a commit covering the proposals in `params` and returns the
+    /// prospective state of the group after the commit.
+    ///
+    /// The proposals are filtered and validated as they would be when
+    /// creating the commit, and an error is returned if creating the commit
+    /// would fail. Unlike creating the commit, the simulation doesn't derive
+    /// any secrets or sign anything, and the group doesn't enter the
+    /// `PendingCommit` state.
+    ///
+    /// The message sizes are estimates. They assume that the committer's new
+    /// leaf node and the new members' secrets are of the same size as the
+    /// current ones, and that no PSKs are used.
+    pub fn simulate_commit(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        params: CommitSimulationParams,
+    ) -> Result<CommitSimulation, CreateCommitError> {
+        let ciphersuite = self.ciphersuite();
+        let own_leaf_index = self.own_leaf_index();
+        let sender = Sender::build_member(own_leaf_index);
+
+        let own_proposals = params
+            .proposals
+            .into_iter()
+            .map(|proposal| {
+                QueuedProposal::from_proposal_and_sender(ciphersuite, crypto, proposal, &sender)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let proposals = self
+            .pending_proposals()
+            .filter(|_| params.consume_proposal_store)
+            .cloned()
+            .chain(own_proposals);
+
+        let (proposal_queue, contains_own_updates) =
+            ProposalQueue::filter_proposals_without_inline(proposals, own_leaf_index).map_err(
+                |e| match e {
+                    ProposalQueueError::LibraryError(e) => e.into(),
+                    ProposalQueueError::ProposalNotFound => CreateCommitError::MissingProposal,
+                    ProposalQueueError::UpdateFromExternalSender => {
+                        CreateCommitError::WrongProposalSenderType
+                    }
+                },
+            )?;
+        self.validate_commit_proposals(&proposal_queue)?;
+
+        let mut diff = self.public_group().empty_diff();
+        let apply_proposals_values = diff.apply_proposals(&proposal_queue, own_leaf_index)?;
+        if apply_proposals_values.self_removed {
+            return Err(CreateCommitError::CannotRemoveSelf);
+        }
+        let path_required = apply_proposals_values.path_required
+            || contains_own_updates
+            || params.force_self_update;
+
+        let hash_length = ciphersuite.hash_length();
+        let own_leaf = self
+            .own_leaf_node()
+            .ok_or_else(|| LibraryError::custom("Own leaf is missing"))?;
+        let kem_output_length = own_leaf.encryption_key().as_slice().len();
+
+        // struct { ProposalOrRef proposals<V>; optional<UpdatePath> path; } Commit;
+        let proposals_length = Commit {
+            proposals: proposal_queue.commit_list(),
+            path: None,
+        }
+        .tls_serialized_len();
+        let path_length = if path_required {
+            let nodes_length = diff
+                .path_recipient_counts(own_leaf_index, &apply_proposals_values.exclusion_list())
+                .into_iter()
+                .map(|recipients| {
+                    let ciphertext_length =
+                        hpke_ciphertext_length(kem_output_length, vl_length(hash_length));
+                    vl_length(kem_output_length) + vl_length(recipients * ciphertext_length)
+                })
+                .sum();
+            own_leaf.tls_serialized_len() + vl_length(hash_length) + vl_length(nodes_length)
+        } else {
+            0
+        };
+        let estimated_commit_size = proposals_length + path_length;
+
+        let joiners = apply_proposals_values.invitation_list.len();
+        let estimated_welcome_size = if joiners == 0 {
+            0
+        } else {
+            // struct {
+            //     opaque joiner_secret<V>;
+            //     optional<PathSecret> path_secret;
+            //     PreSharedKeyID psks<V>;
+            // } GroupSecrets;
+            let group_secrets_length = vl_length(hash_length)
+                + 1
+                + if path_required {
+                    vl_length(hash_length)
+                } else {
+                    0
+                }
+                + vl_length(0);
+            let secrets_length: usize = apply_proposals_values
+                .invitation_list
+                .iter()
+                .map(|(_, add_proposal)| {
+                    let init_key_length =
+                        add_proposal.key_package().hpke_init_key().as_slice().len();
+                    vl_length(hash_length)
+                        + hpke_ciphertext_length(init_key_length, group_secrets_length)
+                })
+                .sum();
+
+            // The GroupInfo contains the group context, the extensions, the
+            // confirmation tag, the signer and the signature.
+            let ratchet_tree_length = if self.configuration().use_ratchet_tree_extension {
+                2 + vl_length(diff.export_ratchet_tree().tls_serialized_len())
+            } else {
+                0
+            };
+            let external_pub_length = 2 + vl_length(vl_length(kem_output_length));
+            let group_info_length = self.context().tls_serialized_len()
+                + vl_length(ratchet_tree_length + external_pub_length)
+                + vl_length(hash_length)
+                + 4
+                + own_leaf.signature().tls_serialized_len();
+
+            2 + vl_length(secrets_length) + vl_length(group_info_length + AEAD_TAG_LENGTH)
+        };
+
+        Ok(CommitSimulation {
+            epoch: GroupEpoch::from(self.epoch().as_u64() + 1),
+            members: diff.members().collect(),
+            path_required,
+            joiners,
+            estimated_commit_size,
+            estimated_welcome_size,
+        })
+    }
+}
+
+// The length of a vector of `length` bytes including its variable-length
+// header (Section 2.1.2 of RFC 9420).
+fn vl_length(length: usize) -> usize {
+    let header_length = if length < 1 << 6 {
+        1
+    } else if length < 1 << 14 {
+        2
+    } else {
+        4
+    };
+    header_length + length
+}
+
+// struct { opaque kem_output<V>; opaque ciphertext<V>; } HPKECiphertext;
+fn hpke_ciphertext_length(kem_output_length: usize, plaintext_length: usize) -> usize {
+    vl_length(kem_output_length) + vl_length(plaintext_length + AEAD_TAG_LENGTH)
+}
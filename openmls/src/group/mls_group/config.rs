@@ -9,7 +9,8 @@
 //!
 //! let group_config = MlsGroupCreateConfig::builder()
 //!     .use_ratchet_tree_extension(true)
-//!     .build();
+//!     .build()
+//!     .expect("invalid group config");
 //! ```
 //!
 //! See [`MlsGroupCreateConfigBuilder`](MlsGroupCreateConfigBuilder#implementations) for
@@ -24,24 +25,38 @@
 //!
 //! let group_config = MlsGroupCreateConfig::builder()
 //!     .wire_format_policy(MIXED_CIPHERTEXT_WIRE_FORMAT_POLICY)
-//!     .build();
+//!     .build()
+//!     .expect("invalid group config");
 //! ```
 
 use super::*;
 use crate::{
+    binary_tree::LeafNodeIndex,
     extensions::errors::InvalidExtensionError,
-    key_packages::Lifetime,
+    group::errors::CreateConfigError,
+    key_packages::{InitKeyRetention, Lifetime},
     tree::sender_ratchet::SenderRatchetConfiguration,
     treesync::{errors::LeafNodeValidationError, node::leaf_node::Capabilities},
+    utils::vector_converter,
 };
+use openmls_traits::types::VerifiableCiphersuite;
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
 
 /// The [`MlsGroupJoinConfig`] contains all configuration parameters that are
 /// relevant to group operation at runtime. It is used to configure the group's
 /// behaviour when joining an existing group. To configure a newly created
 /// group, use [`MlsGroupCreateConfig`].
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+// Options that are missing in a stored config get the values of
+// `MlsGroupJoinConfig::default()`, except for the version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MlsGroupJoinConfig {
+    /// Version of the stored format of this config, see
+    /// [`JOIN_CONFIG_VERSION`]. Configs that were stored before the format was
+    /// versioned have version 0.
+    #[serde(default)]
+    pub(crate) version: u16,
     /// Defines the wire format policy for outgoing and incoming handshake messages.
     /// Application are always encrypted regardless.
     pub(crate) wire_format_policy: WireFormatPolicy,
@@ -50,12 +65,101 @@ pub struct MlsGroupJoinConfig {
     /// Maximum number of past epochs for which application messages
     /// can be decrypted. The default is 0.
     pub(crate) max_past_epochs: usize,
-    /// Number of resumption secrets to keep
+    /// Maximum age of the message secrets of past epochs. Secrets that are
+    /// older are deleted, even if `max_past_epochs` is not reached yet. The
+    /// default is `None`, i.e. secrets are only limited by `max_past_epochs`.
+    pub(crate) max_past_epoch_age: Option<Duration>,
+    /// Number of resumption secrets to keep. The default is
+    /// [`DEFAULT_NUMBER_OF_RESUMPTION_PSKS`].
     pub(crate) number_of_resumption_psks: usize,
+    /// Flag to indicate whether application messages of the final epoch can
+    /// still be processed after this client was removed from the group. The
+    /// default is `false`.
+    pub(crate) removal_grace_period: bool,
+    /// Flag to indicate whether per-member activity statistics are collected
+    /// and stored, see [`MlsGroup::member_stats()`]. The default is `false`.
+    pub(crate) collect_member_stats: bool,
+    /// Flag to indicate whether every commit must contain an update path. If
+    /// set, own commits always include one and received commits without one
+    /// are rejected. The default is `false`.
+    pub(crate) force_full_commits: bool,
+    /// Period after which the secret material of the group should be purged
+    /// once the group became inactive. The default is `None`, i.e. the secret
+    /// material is kept until the group is deleted.
+    pub(crate) inactive_group_retention: Option<Duration>,
+    /// Defines how the key package that was used to join the group is
+    /// handled after the Welcome was processed. The default is
+    /// [`InitKeyRetention::Delete`].
+    pub(crate) init_key_retention: InitKeyRetention,
     /// Flag to indicate the Ratchet Tree Extension should be used
     pub(crate) use_ratchet_tree_extension: bool,
     /// Sender ratchet configuration
     pub(crate) sender_ratchet_configuration: SenderRatchetConfiguration,
+    /// Sender ratchet configurations that override the
+    /// `sender_ratchet_configuration` for the members at specific leaf
+    /// indices.
+    #[serde(with = "vector_converter")]
+    pub(crate) sender_ratchet_overrides: BTreeMap<LeafNodeIndex, SenderRatchetConfiguration>,
+}
+
+/// The default number of resumption PSKs kept by a group.
+pub const DEFAULT_NUMBER_OF_RESUMPTION_PSKS: usize = 32;
+
+/// The current version of the stored format of [`MlsGroupJoinConfig`].
+///
+/// Stored configs of older versions are migrated when the group is loaded,
+/// see [`JoinConfigMigration`].
+pub const JOIN_CONFIG_VERSION: u16 = 1;
+
+/// Migrates [`MlsGroupJoinConfig`]s that were stored with an older version of
+/// the format, see [`MlsGroup::load_with_migration()`].
+///
+/// Options that are missing in a stored config, because they were introduced
+/// after it was stored, are set to their values in
+/// [`MlsGroupJoinConfig::default()`]. The migration is then called with the
+/// version the config was stored with and can override them, e.g. to enable
+/// new options for existing groups.
+///
+/// The trait is implemented for closures:
+///
+/// ```
+/// use openmls::prelude::*;
+///
+/// let migration = |_from_version: u16, config: MlsGroupJoinConfig| {
+///     config.into_builder().collect_member_stats(true).build()
+/// };
+/// ```
+pub trait JoinConfigMigration {
+    /// Returns the migrated `config` that was stored with version
+    /// `from_version`.
+    fn migrate(&self, from_version: u16, config: MlsGroupJoinConfig) -> MlsGroupJoinConfig;
+}
+
+impl<F: Fn(u16, MlsGroupJoinConfig) -> MlsGroupJoinConfig> JoinConfigMigration for F {
+    fn migrate(&self, from_version: u16, config: MlsGroupJoinConfig) -> MlsGroupJoinConfig {
+        self(from_version, config)
+    }
+}
+
+impl Default for MlsGroupJoinConfig {
+    fn default() -> Self {
+        Self {
+            version: JOIN_CONFIG_VERSION,
+            wire_format_policy: WireFormatPolicy::default(),
+            padding_size: 0,
+            max_past_epochs: 0,
+            max_past_epoch_age: None,
+            number_of_resumption_psks: DEFAULT_NUMBER_OF_RESUMPTION_PSKS,
+            removal_grace_period: false,
+            collect_member_stats: false,
+            force_full_commits: false,
+            inactive_group_retention: None,
+            init_key_retention: InitKeyRetention::default(),
+            use_ratchet_tree_extension: false,
+            sender_ratchet_configuration: SenderRatchetConfiguration::default(),
+            sender_ratchet_overrides: BTreeMap::new(),
+        }
+    }
 }
 
 impl MlsGroupJoinConfig {
@@ -64,6 +168,29 @@ impl MlsGroupJoinConfig {
         MlsGroupJoinConfigBuilder::new()
     }
 
+    /// Returns the version of the stored format of this
+    /// [`MlsGroupJoinConfig`], see [`JOIN_CONFIG_VERSION`].
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns a builder that is initialized with the values of this
+    /// [`MlsGroupJoinConfig`].
+    pub fn into_builder(self) -> MlsGroupJoinConfigBuilder {
+        MlsGroupJoinConfigBuilder { join_config: self }
+    }
+
+    /// Migrates this config to [`JOIN_CONFIG_VERSION`] if it was stored with
+    /// an older version. Returns `None` if the config is up to date.
+    pub(crate) fn migrate(&self, migration: &impl JoinConfigMigration) -> Option<Self> {
+        if self.version >= JOIN_CONFIG_VERSION {
+            return None;
+        }
+        let mut config = migration.migrate(self.version, self.clone());
+        config.version = JOIN_CONFIG_VERSION;
+        Some(config)
+    }
+
     /// Returns the wire format policy set in this  [`MlsGroupJoinConfig`].
     pub fn wire_format_policy(&self) -> WireFormatPolicy {
         self.wire_format_policy
@@ -74,10 +201,74 @@ impl MlsGroupJoinConfig {
         self.padding_size
     }
 
+    /// Returns the maximum number of past epochs set in this [`MlsGroupJoinConfig`].
+    pub fn max_past_epochs(&self) -> usize {
+        self.max_past_epochs
+    }
+
+    /// Returns the maximum age of past epoch secrets set in this
+    /// [`MlsGroupJoinConfig`].
+    pub fn max_past_epoch_age(&self) -> Option<Duration> {
+        self.max_past_epoch_age
+    }
+
+    /// Returns the number of resumption PSKs kept by groups using this
+    /// [`MlsGroupJoinConfig`].
+    pub fn number_of_resumption_psks(&self) -> usize {
+        self.number_of_resumption_psks
+    }
+
+    /// Returns whether the removal grace period is enabled in this
+    /// [`MlsGroupJoinConfig`].
+    pub fn removal_grace_period(&self) -> bool {
+        self.removal_grace_period
+    }
+
+    /// Returns whether member statistics are collected in this
+    /// [`MlsGroupJoinConfig`].
+    pub fn collect_member_stats(&self) -> bool {
+        self.collect_member_stats
+    }
+
+    /// Returns whether full commits are enforced in this
+    /// [`MlsGroupJoinConfig`].
+    pub fn force_full_commits(&self) -> bool {
+        self.force_full_commits
+    }
+
+    /// Returns the retention period of the secret material of inactive groups
+    /// set in this [`MlsGroupJoinConfig`].
+    pub fn inactive_group_retention(&self) -> Option<Duration> {
+        self.inactive_group_retention
+    }
+
+    /// Returns the [`InitKeyRetention`] set in this [`MlsGroupJoinConfig`].
+    pub fn init_key_retention(&self) -> InitKeyRetention {
+        self.init_key_retention
+    }
+
     /// Returns the [`SenderRatchetConfiguration`] set in this  [`MlsGroupJoinConfig`].
     pub fn sender_ratchet_configuration(&self) -> &SenderRatchetConfiguration {
         &self.sender_ratchet_configuration
     }
+
+    /// Returns the [`SenderRatchetConfiguration`] that applies to the member
+    /// at `leaf_index`, i.e. its override if one is set and the default
+    /// [`MlsGroupJoinConfig::sender_ratchet_configuration()`] otherwise.
+    pub fn sender_ratchet_configuration_for(
+        &self,
+        leaf_index: LeafNodeIndex,
+    ) -> &SenderRatchetConfiguration {
+        self.sender_ratchet_overrides
+            .get(&leaf_index)
+            .unwrap_or(&self.sender_ratchet_configuration)
+    }
+
+    /// Returns the [`SenderRatchetConfiguration`] overrides for specific
+    /// members, indexed by their leaf index.
+    pub fn sender_ratchet_overrides(&self) -> &BTreeMap<LeafNodeIndex, SenderRatchetConfiguration> {
+        &self.sender_ratchet_overrides
+    }
 }
 
 /// Specifies configuration for the creation of an [`MlsGroup`]. Refer to the
@@ -144,12 +335,61 @@ impl MlsGroupJoinConfigBuilder {
         self
     }
 
+    /// Sets the `max_past_epoch_age` property of the [`MlsGroupJoinConfig`].
+    /// Message secrets of past epochs are deleted once they are older than
+    /// `max_past_epoch_age`.
+    pub fn max_past_epoch_age(mut self, max_past_epoch_age: Duration) -> Self {
+        self.join_config.max_past_epoch_age = Some(max_past_epoch_age);
+        self
+    }
+
     /// Sets the `number_of_resumption_psks` property of the [`MlsGroupJoinConfig`].
     pub fn number_of_resumption_psks(mut self, number_of_resumption_psks: usize) -> Self {
         self.join_config.number_of_resumption_psks = number_of_resumption_psks;
         self
     }
 
+    /// Sets the `removal_grace_period` property of the [`MlsGroupJoinConfig`].
+    /// If enabled, application messages of the final epoch can still be
+    /// processed after this client was removed from the group.
+    pub fn removal_grace_period(mut self, removal_grace_period: bool) -> Self {
+        self.join_config.removal_grace_period = removal_grace_period;
+        self
+    }
+
+    /// Sets the `collect_member_stats` property of the [`MlsGroupJoinConfig`].
+    /// If enabled, per-member activity statistics are collected and stored,
+    /// see [`MlsGroup::member_stats()`].
+    pub fn collect_member_stats(mut self, collect_member_stats: bool) -> Self {
+        self.join_config.collect_member_stats = collect_member_stats;
+        self
+    }
+
+    /// Sets the `force_full_commits` property of the [`MlsGroupJoinConfig`].
+    /// If enabled, every commit created by the group includes an update path,
+    /// even if RFC 9420 permits omitting it, and received commits without an
+    /// update path are rejected.
+    pub fn force_full_commits(mut self, force_full_commits: bool) -> Self {
+        self.join_config.force_full_commits = force_full_commits;
+        self
+    }
+
+    /// Sets the `inactive_group_retention` property of the
+    /// [`MlsGroupJoinConfig`]. The secret material of the group is due for
+    /// purging once the group has been inactive for `inactive_group_retention`.
+    pub fn inactive_group_retention(mut self, inactive_group_retention: Duration) -> Self {
+        self.join_config.inactive_group_retention = Some(inactive_group_retention);
+        self
+    }
+
+    /// Sets the `init_key_retention` property of the [`MlsGroupJoinConfig`].
+    /// It defines how the key package that was used to join the group is
+    /// handled after the Welcome was processed.
+    pub fn init_key_retention(mut self, init_key_retention: InitKeyRetention) -> Self {
+        self.join_config.init_key_retention = init_key_retention;
+        self
+    }
+
     /// Sets the `use_ratchet_tree_extension` property of the [`MlsGroupJoinConfig`].
     pub fn use_ratchet_tree_extension(mut self, use_ratchet_tree_extension: bool) -> Self {
         self.join_config.use_ratchet_tree_extension = use_ratchet_tree_extension;
@@ -165,6 +405,19 @@ impl MlsGroupJoinConfigBuilder {
         self
     }
 
+    /// Overrides the `sender_ratchet_configuration` of the
+    /// [`MlsGroupJoinConfig`] for the member at `leaf_index`.
+    pub fn sender_ratchet_override(
+        mut self,
+        leaf_index: LeafNodeIndex,
+        sender_ratchet_configuration: SenderRatchetConfiguration,
+    ) -> Self {
+        self.join_config
+            .sender_ratchet_overrides
+            .insert(leaf_index, sender_ratchet_configuration);
+        self
+    }
+
     /// Finalizes the builder and returns an [`MlsGroupJoinConfig`].
     pub fn build(self) -> MlsGroupJoinConfig {
         self.join_config
@@ -192,11 +445,36 @@ impl MlsGroupCreateConfig {
         self.join_config.max_past_epochs
     }
 
+    /// Returns the [`MlsGroupCreateConfig`] max past epoch age.
+    pub fn max_past_epoch_age(&self) -> Option<Duration> {
+        self.join_config.max_past_epoch_age
+    }
+
     /// Returns the [`MlsGroupCreateConfig`] number of resumption psks.
     pub fn number_of_resumption_psks(&self) -> usize {
         self.join_config.number_of_resumption_psks
     }
 
+    /// Returns the [`MlsGroupCreateConfig`] removal grace period flag.
+    pub fn removal_grace_period(&self) -> bool {
+        self.join_config.removal_grace_period
+    }
+
+    /// Returns the [`MlsGroupCreateConfig`] member statistics flag.
+    pub fn collect_member_stats(&self) -> bool {
+        self.join_config.collect_member_stats
+    }
+
+    /// Returns the [`MlsGroupCreateConfig`] full commit flag.
+    pub fn force_full_commits(&self) -> bool {
+        self.join_config.force_full_commits
+    }
+
+    /// Returns the [`MlsGroupCreateConfig`] inactive group retention.
+    pub fn inactive_group_retention(&self) -> Option<Duration> {
+        self.join_config.inactive_group_retention
+    }
+
     /// Returns the [`MlsGroupCreateConfig`] boolean flag that indicates whether ratchet_tree_extension should be used.
     pub fn use_ratchet_tree_extension(&self) -> bool {
         self.join_config.use_ratchet_tree_extension
@@ -233,12 +511,82 @@ impl MlsGroupCreateConfig {
             ))
             .ciphersuite(ciphersuite)
             .build()
+            .unwrap()
     }
 
     /// Returns the [`MlsGroupJoinConfig`] of groups created with this create config.
     pub fn join_config(&self) -> &MlsGroupJoinConfig {
         &self.join_config
     }
+
+    /// Checks that the group context extensions, the required capabilities
+    /// and the capabilities of the group creator are consistent. Groups
+    /// created from an inconsistent configuration are rejected by the members
+    /// that join them.
+    ///
+    /// This checks that
+    ///  - the group's ciphersuite is listed in the creator's capabilities,
+    ///  - every non-default group context extension is listed in the
+    ///    creator's capabilities, and
+    ///  - the creator's capabilities support the required capabilities.
+    ///
+    /// [`MlsGroupCreateConfigBuilder::build()`] runs this check.
+    pub fn validate(&self) -> Result<(), CreateConfigError> {
+        if !self
+            .capabilities
+            .contains_ciphersuite(VerifiableCiphersuite::from(self.ciphersuite))
+        {
+            return Err(CreateConfigError::CiphersuiteNotInCapabilities(
+                self.ciphersuite,
+            ));
+        }
+
+        for extension_type in self
+            .group_context_extensions
+            .iter()
+            .map(Extension::extension_type)
+            .filter(|e| !e.is_default())
+        {
+            if !self.capabilities.contains_extension(extension_type) {
+                return Err(CreateConfigError::ExtensionNotInCapabilities(
+                    extension_type,
+                ));
+            }
+        }
+
+        let Some(required_capabilities) = self.group_context_extensions.required_capabilities()
+        else {
+            return Ok(());
+        };
+        if let Some(extension_type) = required_capabilities
+            .extension_types()
+            .iter()
+            .find(|e| !self.capabilities.contains_extension(**e))
+        {
+            return Err(CreateConfigError::RequiredExtensionNotInCapabilities(
+                *extension_type,
+            ));
+        }
+        if let Some(proposal_type) = required_capabilities
+            .proposal_types()
+            .iter()
+            .find(|p| !self.capabilities.contains_proposal(**p))
+        {
+            return Err(CreateConfigError::RequiredProposalNotInCapabilities(
+                *proposal_type,
+            ));
+        }
+        if let Some(credential_type) = required_capabilities
+            .credential_types()
+            .iter()
+            .find(|c| !self.capabilities.contains_credential(**c))
+        {
+            return Err(CreateConfigError::RequiredCredentialNotInCapabilities(
+                *credential_type,
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Builder for an [`MlsGroupCreateConfig`].
@@ -282,12 +630,59 @@ impl MlsGroupCreateConfigBuilder {
         self
     }
 
+    /// Sets the `max_past_epoch_age` property of the MlsGroupCreateConfig.
+    /// Message secrets of past epochs that are older than `max_past_epoch_age`
+    /// are deleted, even if fewer than `max_past_epochs` epochs are stored.
+    ///
+    /// This bounds the time window during which application messages from
+    /// past epochs can be decrypted and thus limits the impact of
+    /// `max_past_epochs` on forward secrecy.
+    pub fn max_past_epoch_age(mut self, max_past_epoch_age: Duration) -> Self {
+        self.config.join_config.max_past_epoch_age = Some(max_past_epoch_age);
+        self
+    }
+
     /// Sets the `number_of_resumption_psks` property of the MlsGroupCreateConfig.
     pub fn number_of_resumption_psks(mut self, number_of_resumption_psks: usize) -> Self {
         self.config.join_config.number_of_resumption_psks = number_of_resumption_psks;
         self
     }
 
+    /// Sets the `removal_grace_period` property of the MlsGroupCreateConfig.
+    /// If enabled, application messages of the final epoch can still be
+    /// processed after this client was removed from the group, see
+    /// [`MlsGroupState::RemovalGracePeriod`].
+    pub fn removal_grace_period(mut self, removal_grace_period: bool) -> Self {
+        self.config.join_config.removal_grace_period = removal_grace_period;
+        self
+    }
+
+    /// Sets the `collect_member_stats` property of the MlsGroupCreateConfig.
+    /// If enabled, per-member activity statistics are collected and stored,
+    /// see [`MlsGroup::member_stats()`].
+    pub fn collect_member_stats(mut self, collect_member_stats: bool) -> Self {
+        self.config.join_config.collect_member_stats = collect_member_stats;
+        self
+    }
+
+    /// Sets the `force_full_commits` property of the MlsGroupCreateConfig.
+    /// If enabled, every commit created by the group includes an update path,
+    /// even if RFC 9420 permits omitting it, and received commits without an
+    /// update path are rejected.
+    pub fn force_full_commits(mut self, force_full_commits: bool) -> Self {
+        self.config.join_config.force_full_commits = force_full_commits;
+        self
+    }
+
+    /// Sets the `inactive_group_retention` property of the
+    /// MlsGroupCreateConfig. The secret material of the group is due for
+    /// purging once the group has been inactive for `inactive_group_retention`,
+    /// see [`MlsGroup::purge_due()`].
+    pub fn inactive_group_retention(mut self, inactive_group_retention: Duration) -> Self {
+        self.config.join_config.inactive_group_retention = Some(inactive_group_retention);
+        self
+    }
+
     /// Sets the `use_ratchet_tree_extension` property of the MlsGroupCreateConfig.
     pub fn use_ratchet_tree_extension(mut self, use_ratchet_tree_extension: bool) -> Self {
         self.config.join_config.use_ratchet_tree_extension = use_ratchet_tree_extension;
@@ -363,9 +758,11 @@ impl MlsGroupCreateConfigBuilder {
         Ok(self)
     }
 
-    /// Finalizes the builder and retursn an `[MlsGroupCreateConfig`].
-    pub fn build(self) -> MlsGroupCreateConfig {
-        self.config
+    /// Finalizes the builder and returns an [`MlsGroupCreateConfig`] after
+    /// checking that it is consistent, see [`MlsGroupCreateConfig::validate()`].
+    pub fn build(self) -> Result<MlsGroupCreateConfig, CreateConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
@@ -394,6 +791,71 @@ impl IncomingWireFormatPolicy {
     }
 }
 
+/// Options that determine which extensions are included in a GroupInfo
+/// exported with [`MlsGroup::export_group_info_with_options()`].
+///
+/// By default, only the external public key is included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupInfoOptions {
+    pub(crate) with_ratchet_tree: bool,
+    pub(crate) with_external_pub: bool,
+    pub(crate) extensions: Vec<Extension>,
+}
+
+impl Default for GroupInfoOptions {
+    fn default() -> Self {
+        Self {
+            with_ratchet_tree: false,
+            with_external_pub: true,
+            extensions: vec![],
+        }
+    }
+}
+
+impl GroupInfoOptions {
+    /// Returns the default [`GroupInfoOptions`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the ratchet tree is embedded in the GroupInfo using a
+    /// [`RatchetTreeExtension`].
+    pub fn with_ratchet_tree(mut self, with_ratchet_tree: bool) -> Self {
+        self.with_ratchet_tree = with_ratchet_tree;
+        self
+    }
+
+    /// Sets whether the external public key is included in the GroupInfo
+    /// using an [`ExternalPubExtension`]. Without it, the GroupInfo can't be
+    /// used to join the group through an external commit.
+    pub fn with_external_pub(mut self, with_external_pub: bool) -> Self {
+        self.with_external_pub = with_external_pub;
+        self
+    }
+
+    /// Sets additional extensions, e.g. application-specific ones, that are
+    /// included in the GroupInfo.
+    pub fn with_extensions(mut self, extensions: Vec<Extension>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Returns whether the ratchet tree is embedded in the GroupInfo.
+    pub fn ratchet_tree(&self) -> bool {
+        self.with_ratchet_tree
+    }
+
+    /// Returns whether the external public key is included in the GroupInfo.
+    pub fn external_pub(&self) -> bool {
+        self.with_external_pub
+    }
+
+    /// Returns the additional extensions that are included in the GroupInfo.
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+}
+
 /// Defines what wire format should be used for outgoing handshake messages.
 /// Note that application messages must always be encrypted.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
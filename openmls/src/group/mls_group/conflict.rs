@@ -0,0 +1,356 @@
+//! Commit conflicts
+//!
+//! This module contains the functions to detect and recover from a commit
+//! race, i.e. the situation in which the pending commit of this client loses
+//! against the commit of another member for the same epoch.
+//!
+//! The recovery workflow is as follows:
+//!
+//! 1. After processing an incoming commit, check whether it conflicts with the
+//!    pending commit using [`MlsGroup::conflicts_with_pending_commit()`].
+//! 2. Drop the pending commit using [`MlsGroup::discard_pending_commit()`],
+//!    which returns the proposals it contained as a [`SupersededCommit`].
+//! 3. Merge the incoming commit.
+//! 4. Re-commit the proposals that are still valid in the new epoch using
+//!    [`MlsGroup::recommit()`].
+//!
+//! Alternatively, [`MlsGroup::merge_staged_commit_and_repropose()`] merges the
+//! incoming commit and sends the own proposals that it didn't cover as new
+//! proposals, to be committed by whoever commits next.
+
+use openmls_traits::signatures::Signer;
+
+use super::{
+    commit_builder::CommitMessageBundle,
+    errors::{ProposalError, RecommitError, ReproposeError},
+    *,
+};
+use crate::{
+    ciphersuite::SignaturePublicKey, messages::proposals::ProposalOrRefType, schedule::Psk,
+    treesync::LeafNodeParameters,
+};
+
+/// The proposals of a pending commit that was discarded because it lost
+/// against the commit of another member.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupersededCommit {
+    epoch: GroupEpoch,
+    proposals: Vec<Proposal>,
+    // The signature keys of the members targeted by the Remove proposals in
+    // the epoch of the discarded commit. A leaf that was freed in the
+    // meantime can be occupied by another member, who must not be removed.
+    removed_signature_keys: Vec<(LeafNodeIndex, SignaturePublicKey)>,
+}
+
+impl SupersededCommit {
+    /// Returns the epoch the discarded commit was created for.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the proposals that were covered by the discarded commit.
+    pub fn proposals(&self) -> &[Proposal] {
+        &self.proposals
+    }
+
+    /// Returns the signature key of the member in the leaf with the given
+    /// index that was targeted by a Remove proposal.
+    fn removed_signature_key(&self, leaf_index: LeafNodeIndex) -> Option<&SignaturePublicKey> {
+        self.removed_signature_keys
+            .iter()
+            .find(|(removed, _)| *removed == leaf_index)
+            .map(|(_, signature_key)| signature_key)
+    }
+}
+
+impl MlsGroup {
+    /// Returns `true` if the group has a pending commit and the given staged
+    /// commit, which was created by processing the commit of another member,
+    /// is for the same epoch. Only one of the two commits can be merged.
+    pub fn conflicts_with_pending_commit(&self, staged_commit: &StagedCommit) -> bool {
+        self.pending_commit().is_some()
+            && staged_commit.group_context().epoch().as_u64() == self.epoch().as_u64() + 1
+    }
+
+    /// Drops the pending commit of this client and returns the proposals it
+    /// covered, so that they can be re-committed in the next epoch using
+    /// [`MlsGroup::recommit()`].
+    ///
+    /// Returns `None` if there is no pending commit created by a member of
+    /// the group. Pending external commits are not discarded.
+    pub fn discard_pending_commit<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<Option<SupersededCommit>, Storage::Error> {
+        let superseded_commit = match &self.group_state {
+            MlsGroupState::PendingCommit(pending_commit_state) => match **pending_commit_state {
+                PendingCommitState::Member(ref staged_commit) => Some(
+                    self.superseded_commit(
+                        staged_commit
+                            .queued_proposals()
+                            .map(|queued_proposal| queued_proposal.proposal().clone())
+                            .collect(),
+                    ),
+                ),
+                PendingCommitState::External(_) => None,
+            },
+            MlsGroupState::Operational
+            | MlsGroupState::Inactive
+            | MlsGroupState::ReInitialized(_)
+            | MlsGroupState::PendingSelfRemoval
+            | MlsGroupState::RemovalGracePeriod => None,
+        };
+
+        self.clear_pending_commit(storage)?;
+
+        Ok(superseded_commit)
+    }
+
+    /// Returns the proposals of the `superseded_commit` that are still valid
+    /// in the current epoch.
+    ///
+    /// The following proposals are dropped:
+    ///  - Add proposals for members that are already in the group
+    ///  - Remove proposals for members that are no longer in the group, also
+    ///    if their leaf is now occupied by another member
+    ///  - Update proposals, as their leaf nodes were created for the old
+    ///    epoch
+    ///  - ExternalInit proposals, which can't be committed by members
+    ///  - GroupContextExtensions proposals that match the current extensions
+    pub fn revalidate_proposals(&self, superseded_commit: &SupersededCommit) -> Vec<Proposal> {
+        superseded_commit
+            .proposals
+            .iter()
+            .filter(|proposal| self.is_valid_in_current_epoch(proposal, superseded_commit))
+            .cloned()
+            .collect()
+    }
+
+    /// Re-commits the proposals of the `superseded_commit` that are still
+    /// valid in the current epoch (see [`MlsGroup::revalidate_proposals()`])
+    /// together with the proposals in the proposal store.
+    ///
+    /// The commit is staged as pending commit, like commits created through
+    /// [`MlsGroup::commit_builder()`]. Returns `None` if none of the proposals are
+    /// valid anymore.
+    pub fn recommit<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        superseded_commit: SupersededCommit,
+    ) -> Result<Option<CommitMessageBundle>, RecommitError<Provider::StorageError>> {
+        self.is_operational()?;
+
+        if superseded_commit.epoch.as_u64() >= self.epoch().as_u64() {
+            return Err(RecommitError::ConflictNotMerged);
+        }
+
+        let proposals = self.revalidate_proposals(&superseded_commit);
+        if proposals.is_empty() {
+            return Ok(None);
+        }
+
+        let bundle = self
+            .commit_builder()
+            .add_proposals(proposals)
+            .load_psks(provider.storage())?
+            .build(provider.rand(), provider.crypto(), signer, |_| true)?
+            .stage_commit(provider)?;
+
+        self.reset_aad();
+
+        Ok(Some(bundle))
+    }
+
+    /// Merges the `staged_commit` of another member like
+    /// [`MlsGroup::merge_staged_commit()`] and re-proposes the own proposals
+    /// that were not covered by it, so that they are not lost when another
+    /// member wins the race to commit.
+    ///
+    /// The own proposals that are considered are the ones in the proposal
+    /// store that were sent by this client, and the proposals that were
+    /// committed by value in the pending commit of this client. Proposals that
+    /// are no longer valid in the new epoch are dropped (see
+    /// [`MlsGroup::revalidate_proposals()`]), except for Update proposals,
+    /// which are re-created with fresh keys.
+    ///
+    /// Returns the messages of the new proposals, which have to be sent to the
+    /// group. If this client was removed by the `staged_commit`, or can't send
+    /// proposals anymore for another reason, the commit is merged and no
+    /// proposals are sent.
+    pub fn merge_staged_commit_and_repropose<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        staged_commit: StagedCommit,
+    ) -> Result<Vec<MlsMessageOut>, ReproposeError<Provider::StorageError>> {
+        let uncovered_proposals = self.uncovered_own_proposals(&staged_commit);
+
+        self.merge_staged_commit(provider, staged_commit)?;
+
+        // Nothing can be proposed if this client was removed by the commit,
+        // or if the group can't be used anymore for another reason, e.g.
+        // because it was re-initialized.
+        let mut messages = Vec::new();
+        if !self.is_active() || self.is_operational().is_err() {
+            return Ok(messages);
+        }
+        for proposal in uncovered_proposals.proposals() {
+            if let Some(message) =
+                self.repropose(provider, signer, proposal.clone(), &uncovered_proposals)?
+            {
+                messages.push(message);
+            }
+        }
+
+        Ok(messages)
+    }
+
+    /// Creates a [`SupersededCommit`] for the `proposals` in the current epoch
+    /// and records the signature keys of the members they remove.
+    fn superseded_commit(&self, proposals: Vec<Proposal>) -> SupersededCommit {
+        let removed_signature_keys = proposals
+            .iter()
+            .filter_map(|proposal| match proposal {
+                Proposal::Remove(remove_proposal) => {
+                    let removed = remove_proposal.removed();
+                    self.public_group
+                        .leaf(removed)
+                        .map(|leaf| (removed, leaf.signature_key().clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        SupersededCommit {
+            epoch: self.epoch(),
+            proposals,
+            removed_signature_keys,
+        }
+    }
+
+    /// Returns `true` if the `proposal` of the `superseded_commit` can still
+    /// be committed in the current epoch.
+    fn is_valid_in_current_epoch(
+        &self,
+        proposal: &Proposal,
+        superseded_commit: &SupersededCommit,
+    ) -> bool {
+        match proposal {
+            Proposal::Add(add_proposal) => {
+                let signature_key = add_proposal.key_package().leaf_node().signature_key();
+                !self
+                    .members()
+                    .any(|member| member.signature_key == signature_key.as_slice())
+            }
+            Proposal::Remove(remove_proposal) => {
+                let removed = remove_proposal.removed();
+                match (
+                    self.public_group.leaf(removed),
+                    superseded_commit.removed_signature_key(removed),
+                ) {
+                    (Some(leaf), Some(signature_key)) => leaf.signature_key() == signature_key,
+                    _ => false,
+                }
+            }
+            Proposal::Update(_) | Proposal::ExternalInit(_) => false,
+            Proposal::GroupContextExtensions(extension_proposal) => {
+                extension_proposal.extensions() != self.extensions()
+            }
+            Proposal::PreSharedKey(_)
+            | Proposal::ReInit(_)
+            | Proposal::AppAck(_)
+            | Proposal::Custom(_) => true,
+        }
+    }
+
+    /// Returns the own proposals that are not covered by the `staged_commit`.
+    fn uncovered_own_proposals(&self, staged_commit: &StagedCommit) -> SupersededCommit {
+        let committed_refs: Vec<ProposalRef> = staged_commit
+            .queued_proposals()
+            .map(|queued_proposal| queued_proposal.proposal_reference())
+            .collect();
+        let committed_proposals: Vec<&Proposal> = staged_commit
+            .queued_proposals()
+            .map(|queued_proposal| queued_proposal.proposal())
+            .collect();
+
+        let own_index = self.own_leaf_index();
+        let queued_proposals = self.proposal_store().proposals().filter(|queued_proposal| {
+            matches!(queued_proposal.sender(), Sender::Member(index) if *index == own_index)
+                && !committed_refs.contains(&queued_proposal.proposal_reference())
+        });
+        let inline_proposals = self
+            .pending_commit()
+            .into_iter()
+            .flat_map(|pending_commit| pending_commit.queued_proposals())
+            .filter(|queued_proposal| {
+                queued_proposal.proposal_or_ref_type() == ProposalOrRefType::Proposal
+            });
+
+        self.superseded_commit(
+            queued_proposals
+                .chain(inline_proposals)
+                .map(|queued_proposal| queued_proposal.proposal())
+                .filter(|proposal| !committed_proposals.contains(proposal))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Proposes the `proposal` of the `superseded_commit` again in the current
+    /// epoch, if it is still valid. Update proposals are re-created with a
+    /// fresh leaf node.
+    fn repropose<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        proposal: Proposal,
+        superseded_commit: &SupersededCommit,
+    ) -> Result<Option<MlsMessageOut>, ProposalError<Provider::StorageError>> {
+        if let Proposal::Update(update_proposal) = &proposal {
+            let leaf_node = update_proposal.leaf_node();
+            let leaf_node_parameters = LeafNodeParameters::builder()
+                .with_capabilities(leaf_node.capabilities().clone())
+                .with_extensions(leaf_node.extensions().clone())
+                .build();
+            let (message, _) = self.propose_self_update(provider, signer, leaf_node_parameters)?;
+            return Ok(Some(message));
+        }
+
+        if !self.is_valid_in_current_epoch(&proposal, superseded_commit) {
+            return Ok(None);
+        }
+
+        let (message, _) = match proposal {
+            Proposal::Add(add_proposal) => {
+                self.propose_add_member(provider, signer, add_proposal.key_package())?
+            }
+            Proposal::Remove(remove_proposal) => {
+                self.propose_remove_member(provider, signer, remove_proposal.removed())?
+            }
+            Proposal::PreSharedKey(psk_proposal) => {
+                let psk_id = psk_proposal.into_psk_id();
+                if !matches!(psk_id.psk(), Psk::External(_)) {
+                    return Ok(None);
+                }
+                self.propose_external_psk(provider, signer, psk_id)?
+            }
+            Proposal::GroupContextExtensions(extension_proposal) => self
+                .propose_group_context_extensions(
+                    provider,
+                    extension_proposal.extensions().clone(),
+                    signer,
+                )?,
+            Proposal::Custom(custom_proposal) => {
+                self.propose_custom_proposal_by_reference(provider, signer, custom_proposal)?
+            }
+            Proposal::Update(_)
+            | Proposal::ReInit(_)
+            | Proposal::ExternalInit(_)
+            | Proposal::AppAck(_) => return Ok(None),
+        };
+
+        Ok(Some(message))
+    }
+}
@@ -340,7 +340,7 @@ impl MlsGroup {
             // `joiner_secret`.
             let encrypted_secrets = diff.encrypt_group_secrets(
                 &joiner_secret,
-                apply_proposals_values.invitation_list,
+                &apply_proposals_values.invitation_list,
                 path_computation_result.plain_path.as_deref(),
                 &apply_proposals_values.presharedkeys,
                 &encrypted_group_info,
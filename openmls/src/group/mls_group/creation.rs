@@ -1,16 +1,22 @@
-use errors::NewGroupError;
-use openmls_traits::{signatures::Signer, storage::StorageProvider as StorageProviderTrait};
+use errors::{NewGroupError, NewGroupWithMembersError};
+use openmls_traits::{signatures::Signer, storage::StorageReader as _};
 
 use super::{builder::MlsGroupBuilder, *};
 use crate::{
+    ciphersuite::Secret,
     credentials::CredentialWithKey,
-    group::errors::{ExternalCommitError, WelcomeError},
+    group::{
+        errors::{ExternalCommitError, WelcomeError},
+        AdmissionToken, ProcessingPolicy,
+    },
+    key_packages::KeyPackage,
     messages::{
         group_info::{GroupInfo, VerifiableGroupInfo},
         Welcome,
     },
     schedule::{
-        psk::{store::ResumptionPskStore, PreSharedKeyId},
+        errors::PskError,
+        psk::{load_psk, store::ResumptionPskStore, PreSharedKeyId, Psk, PskResolver, PskStore},
         EpochSecrets, InitSecret,
     },
     storage::OpenMlsProvider,
@@ -21,9 +27,6 @@ use crate::{
     },
 };
 
-#[cfg(doc)]
-use crate::key_packages::KeyPackage;
-
 impl MlsGroup {
     // === Group creation ===
 
@@ -71,6 +74,70 @@ impl MlsGroup {
             )
     }
 
+    /// Creates a new group (with a random group ID) with the creator and the
+    /// members owning the given `key_packages`.
+    ///
+    /// The group is created and the new members are added in a single
+    /// operation. The returned group is already at epoch 1 and the returned
+    /// [`MlsMessageOut`] contains the [`Welcome`] for the new members. Since
+    /// the creator was the only member before, the commit adding the new
+    /// members doesn't have to be distributed. The returned [`GroupInfo`] is
+    /// [`Some`] if the group has the `use_ratchet_tree_extension` flag set.
+    ///
+    /// If the new members can't be added, the group is removed from the
+    /// storage again.
+    #[allow(clippy::type_complexity)]
+    pub fn new_with_members<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        signer: &impl Signer,
+        mls_group_create_config: &MlsGroupCreateConfig,
+        credential_with_key: CredentialWithKey,
+        key_packages: &[KeyPackage],
+    ) -> Result<
+        (Self, MlsMessageOut, Option<GroupInfo>),
+        NewGroupWithMembersError<Provider::StorageError>,
+    > {
+        MlsGroupBuilder::new()
+            .build_internal(
+                provider,
+                signer,
+                credential_with_key,
+                Some(mls_group_create_config.clone()),
+            )?
+            .add_initial_members(provider, signer, key_packages)
+    }
+
+    /// Adds the members owning the given `key_packages` to a freshly created
+    /// group and merges the commit. If this fails, the group is removed from
+    /// the storage.
+    #[allow(clippy::type_complexity)]
+    pub(super) fn add_initial_members<Provider: OpenMlsProvider>(
+        mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        key_packages: &[KeyPackage],
+    ) -> Result<
+        (Self, MlsMessageOut, Option<GroupInfo>),
+        NewGroupWithMembersError<Provider::StorageError>,
+    > {
+        let result = self
+            .add_members(provider, signer, key_packages)
+            .map_err(NewGroupWithMembersError::from)
+            .and_then(|(_commit, welcome, group_info)| {
+                self.merge_pending_commit(provider)?;
+                Ok((welcome, group_info))
+            });
+
+        match result {
+            Ok((welcome, group_info)) => Ok((self, welcome, group_info)),
+            Err(e) => {
+                self.delete(provider.storage())
+                    .map_err(NewGroupWithMembersError::StorageError)?;
+                Err(e)
+            }
+        }
+    }
+
     /// Join an existing group through an External Commit.
     /// The resulting [`MlsGroup`] instance starts off with a pending
     /// commit (the external commit, which adds this client to the group).
@@ -97,6 +164,41 @@ impl MlsGroup {
         aad: &[u8],
         credential_with_key: CredentialWithKey,
     ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError<Provider::StorageError>>
+    {
+        Self::join_by_external_commit_with_policy(
+            provider,
+            signer,
+            ratchet_tree,
+            verifiable_group_info,
+            mls_group_config,
+            capabilities,
+            extensions,
+            aad,
+            credential_with_key,
+            &(),
+        )
+    }
+
+    /// Like [`MlsGroup::join_by_external_commit()`], but consults the given
+    /// [`ProcessingPolicy`] before joining the group. If
+    /// [`ProcessingPolicy::accept_group_id()`] rejects the group ID,
+    /// [`ExternalCommitError::GroupIdRejected`] is returned, and if
+    /// [`ProcessingPolicy::validate_group_info()`] rejects the group,
+    /// [`ExternalCommitError::RejectedByPolicy`]. In both cases nothing is
+    /// written to storage.
+    #[allow(clippy::too_many_arguments)]
+    pub fn join_by_external_commit_with_policy<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        signer: &impl Signer,
+        ratchet_tree: Option<RatchetTreeIn>,
+        verifiable_group_info: VerifiableGroupInfo,
+        mls_group_config: &MlsGroupJoinConfig,
+        capabilities: Option<Capabilities>,
+        extensions: Option<Extensions>,
+        aad: &[u8],
+        credential_with_key: CredentialWithKey,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError<Provider::StorageError>>
     {
         // Prepare the commit parameters
         let framing_parameters = FramingParameters::new(aad, WireFormat::PublicMessage);
@@ -134,6 +236,13 @@ impl MlsGroup {
         )?;
         let group_context = public_group.group_context();
 
+        if !policy.accept_group_id(group_context.group_id()) {
+            return Err(ExternalCommitError::GroupIdRejected);
+        }
+        policy
+            .validate_group_info(group_context, group_info.extensions())
+            .map_err(ExternalCommitError::RejectedByPolicy)?;
+
         // Obtain external_pub from GroupInfo extensions.
         let external_pub = group_info
             .extensions()
@@ -191,15 +300,22 @@ impl MlsGroup {
             mls_group_config: mls_group_config.clone(),
             own_leaf_nodes: vec![],
             aad: vec![],
+            used_external_inits: UsedExternalInits::default(),
+            member_stats: MemberStatsCache::default(),
+            last_operation_timing: LastOperationTiming::default(),
+            epoch_hooks: EpochHooks::default(),
             group_state: MlsGroupState::Operational,
             public_group,
             group_epoch_secrets,
             own_leaf_index,
             message_secrets_store,
-            resumption_psk_store: ResumptionPskStore::new(32),
+            resumption_psk_store: ResumptionPskStore::new(
+                mls_group_config.number_of_resumption_psks,
+            ),
         };
 
-        mls_group.set_max_past_epochs(mls_group_config.max_past_epochs);
+        mls_group.resize_message_secrets_store(mls_group_config.max_past_epochs);
+        mls_group.set_max_past_epoch_age(mls_group_config.max_past_epoch_age);
 
         // Immediately create the commit to add ourselves to the group.
         let create_commit_result = mls_group
@@ -216,25 +332,85 @@ impl MlsGroup {
 
         let public_message: PublicMessage = create_commit_result.commit.into();
 
+        let version = mls_group.version();
         Ok((
             mls_group,
-            public_message.into(),
+            MlsMessageOut::from_public_message(public_message, version),
             create_commit_result.group_info,
         ))
     }
+
+    /// Join an existing group through an External Commit that carries an
+    /// admission token in its authenticated data.
+    ///
+    /// This is the joiner side of onboarding without Welcome messages: a
+    /// member publishes a GroupInfo using
+    /// [`MlsGroup::export_onboarding_group_info()`], which contains the
+    /// ratchet tree and the external public key, and the members check the
+    /// token when processing the external commit, e.g. using an
+    /// [`AdmissionTokenPolicy`](crate::group::AdmissionTokenPolicy). The
+    /// token is sent as an [`AdmissionToken`] that is bound to the group ID
+    /// and epoch of the GroupInfo. It is readable by the delivery service and
+    /// the members processing the commit, but doesn't become part of the
+    /// group state.
+    ///
+    /// Returns [`ExternalCommitError::MissingRatchetTree`] or
+    /// [`ExternalCommitError::MissingExternalPub`] if the GroupInfo is not
+    /// suitable for onboarding. Otherwise this behaves like
+    /// [`MlsGroup::join_by_external_commit()`].
+    pub fn join_with_admission_token<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        signer: &impl Signer,
+        verifiable_group_info: VerifiableGroupInfo,
+        mls_group_config: &MlsGroupJoinConfig,
+        credential_with_key: CredentialWithKey,
+        admission_token: &[u8],
+    ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError<Provider::StorageError>>
+    {
+        // Check the GroupInfo before doing any work.
+        if verifiable_group_info.extensions().ratchet_tree().is_none() {
+            return Err(ExternalCommitError::MissingRatchetTree);
+        }
+        if verifiable_group_info.extensions().external_pub().is_none() {
+            return Err(ExternalCommitError::MissingExternalPub);
+        }
+
+        let aad = AdmissionToken::new(
+            verifiable_group_info.group_id().clone(),
+            verifiable_group_info.epoch(),
+            admission_token.to_vec(),
+        )
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+
+        Self::join_by_external_commit(
+            provider,
+            signer,
+            None,
+            verifiable_group_info,
+            mls_group_config,
+            None,
+            None,
+            &aad,
+            credential_with_key,
+        )
+    }
 }
 
-impl ProcessedWelcome {
-    /// Creates a new processed [`Welcome`] message , which can be
-    /// inspected before creating a [`StagedWelcome`].
+impl PendingPskWelcome {
+    /// Decrypts the group secrets of the [`Welcome`] message and looks up its
+    /// PSKs in the given [`PskStore`]. PSKs that are not found can be provided
+    /// with [`PendingPskWelcome::provide_psk()`] afterwards.
     ///
-    /// This does not require a ratchet tree yet.
+    /// Nothing is written to the storage until
+    /// [`PendingPskWelcome::into_processed_welcome()`] is called.
     ///
     /// [`Welcome`]: crate::messages::Welcome
     pub fn new_from_welcome<Provider: OpenMlsProvider>(
         provider: &Provider,
         mls_group_config: &MlsGroupJoinConfig,
         welcome: Welcome,
+        psk_store: &impl PskStore,
     ) -> Result<Self, WelcomeError<Provider::StorageError>> {
         let (resumption_psk_store, key_package_bundle) =
             keys_for_welcome(mls_group_config, &welcome, provider)?;
@@ -263,12 +439,101 @@ impl ProcessedWelcome {
             ciphersuite,
             provider.crypto(),
         )?;
+        let psk_secrets = group_secrets
+            .psks
+            .iter()
+            .map(|psk_id| load_psk(psk_store, &resumption_psk_store, psk_id))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            mls_group_config: mls_group_config.clone(),
+            welcome,
+            group_secrets,
+            psk_secrets,
+            resumption_psk_store,
+            key_package_bundle,
+        })
+    }
+
+    /// Returns the ids of the PSKs that are still missing.
+    pub fn missing_psks(&self) -> Vec<PreSharedKeyId> {
+        self.group_secrets
+            .psks
+            .iter()
+            .zip(&self.psk_secrets)
+            .filter(|(_, secret)| secret.is_none())
+            .map(|(psk_id, _)| psk_id.clone())
+            .collect()
+    }
+
+    /// Provides the `secret` of the PSK with the given id.
+    ///
+    /// Returns [`PskError::NotRequired`] if the [`Welcome`] doesn't require
+    /// the PSK.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn provide_psk(&mut self, psk_id: &PreSharedKeyId, secret: &[u8]) -> Result<(), PskError> {
+        let mut required = false;
+        for (_, psk_secret) in self
+            .group_secrets
+            .psks
+            .iter()
+            .zip(self.psk_secrets.iter_mut())
+            .filter(|(id, _)| *id == psk_id)
+        {
+            *psk_secret = Some(Secret::from_slice(secret));
+            required = true;
+        }
+
+        required.then_some(()).ok_or(PskError::NotRequired)
+    }
+
+    /// Resumes the join and returns the [`ProcessedWelcome`].
+    ///
+    /// Returns [`PskError::KeyNotFound`] if a PSK is still missing. Note that
+    /// a wrong PSK secret can't be told apart from a forged [`Welcome`] and
+    /// makes the decryption of the group info fail.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn into_processed_welcome<Provider: OpenMlsProvider>(
+        self,
+        provider: &Provider,
+    ) -> Result<ProcessedWelcome, WelcomeError<Provider::StorageError>> {
+        self.into_processed_welcome_with_policy(provider, &())
+    }
+
+    /// Like [`PendingPskWelcome::into_processed_welcome()`], but consults the
+    /// given [`ProcessingPolicy`] as soon as the group info is decrypted. If
+    /// [`ProcessingPolicy::accept_group_id()`] rejects the group ID,
+    /// [`WelcomeError::GroupIdRejected`] is returned, and if
+    /// [`ProcessingPolicy::validate_group_info()`] rejects the group,
+    /// [`WelcomeError::RejectedByPolicy`]. The key package is left untouched
+    /// in either case.
+    ///
+    /// **NOTE:** The policy is called with the **unverified** group info.
+    pub fn into_processed_welcome_with_policy<Provider: OpenMlsProvider>(
+        self,
+        provider: &Provider,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<ProcessedWelcome, WelcomeError<Provider::StorageError>> {
+        let Self {
+            mls_group_config,
+            welcome,
+            group_secrets,
+            psk_secrets,
+            resumption_psk_store,
+            key_package_bundle,
+        } = self;
+        let ciphersuite = welcome.ciphersuite();
+
         let psk_secret = {
-            let psks = load_psks(
-                provider.storage(),
-                &resumption_psk_store,
-                &group_secrets.psks,
-            )?;
+            let psks = group_secrets
+                .psks
+                .iter()
+                .zip(psk_secrets)
+                .map(|(psk_id, secret)| secret.map(|secret| (psk_id, secret)))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(PskError::KeyNotFound)?;
 
             PskSecret::new(provider.crypto(), ciphersuite, psks)?
         };
@@ -311,8 +576,55 @@ impl ProcessedWelcome {
             return Err(e);
         }
 
-        Ok(Self {
-            mls_group_config: mls_group_config.clone(),
+        if !policy.accept_group_id(verifiable_group_info.group_id()) {
+            return Err(WelcomeError::GroupIdRejected);
+        }
+        policy
+            .validate_group_info(
+                verifiable_group_info.group_context(),
+                verifiable_group_info.extensions(),
+            )
+            .map_err(WelcomeError::RejectedByPolicy)?;
+
+        // Detect a redelivered Welcome before anything is written to the
+        // storage. It is one if this key package already joined the group,
+        // since a last resort key package may join other groups as well. An
+        // inactive group may be joined again.
+        let group_id = verifiable_group_info.group_id();
+        let key_package_ref = key_package_bundle
+            .key_package()
+            .hash_ref(provider.crypto())?;
+        let joined_group_id: Option<GroupId> = provider
+            .storage()
+            .joined_group_id(&key_package_ref)
+            .map_err(WelcomeError::StorageError)?;
+        if joined_group_id.as_ref() == Some(group_id) {
+            let group_state: Option<MlsGroupState> = provider
+                .storage()
+                .group_state(group_id)
+                .map_err(WelcomeError::StorageError)?;
+            if group_state.is_some_and(|state| !matches!(state, MlsGroupState::Inactive)) {
+                return Err(WelcomeError::AlreadyJoined(group_id.clone()));
+            }
+        }
+
+        // The group id is recorded for the key package, such that a
+        // redelivered Welcome is told apart from a Welcome to another group.
+        provider
+            .storage()
+            .write_joined_group_id(&key_package_ref, group_id)
+            .map_err(WelcomeError::StorageError)?;
+        key_package_bundle
+            .clone()
+            .retain_or_delete(
+                provider.storage(),
+                &key_package_ref,
+                mls_group_config.init_key_retention,
+            )
+            .map_err(WelcomeError::StorageError)?;
+
+        Ok(ProcessedWelcome {
+            mls_group_config,
             ciphersuite,
             group_secrets,
             key_schedule,
@@ -321,6 +633,65 @@ impl ProcessedWelcome {
             key_package_bundle,
         })
     }
+}
+
+impl ProcessedWelcome {
+    /// Creates a new processed [`Welcome`] message , which can be
+    /// inspected before creating a [`StagedWelcome`].
+    ///
+    /// This does not require a ratchet tree yet.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn new_from_welcome<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        mls_group_config: &MlsGroupJoinConfig,
+        welcome: Welcome,
+    ) -> Result<Self, WelcomeError<Provider::StorageError>> {
+        Self::new_from_welcome_with_psk_store(
+            provider,
+            mls_group_config,
+            welcome,
+            provider.storage(),
+        )
+    }
+
+    /// Like [`ProcessedWelcome::new_from_welcome()`], but looks up the PSKs
+    /// of the [`Welcome`] in the given [`PskStore`] instead of the storage of
+    /// the provider.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn new_from_welcome_with_psk_store<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        mls_group_config: &MlsGroupJoinConfig,
+        welcome: Welcome,
+        psk_store: &impl PskStore,
+    ) -> Result<Self, WelcomeError<Provider::StorageError>> {
+        PendingPskWelcome::new_from_welcome(provider, mls_group_config, welcome, psk_store)?
+            .into_processed_welcome(provider)
+    }
+
+    /// Like [`ProcessedWelcome::new_from_welcome()`], but calls `resolve` for
+    /// the PSKs of the [`Welcome`] that are not in the storage of the
+    /// provider, so the application can fetch them on demand, e.g. from the
+    /// inviter. Processing only fails with [`PskError::KeyNotFound`] if
+    /// `resolve` declines by returning `None`. See [`PskResolver`].
+    ///
+    /// See [`PendingPskWelcome`] for fetching the PSKs asynchronously.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn new_from_welcome_with_psk_resolver<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        mls_group_config: &MlsGroupJoinConfig,
+        welcome: Welcome,
+        resolve: impl Fn(&Psk) -> Option<Vec<u8>>,
+    ) -> Result<Self, WelcomeError<Provider::StorageError>> {
+        Self::new_from_welcome_with_psk_store(
+            provider,
+            mls_group_config,
+            welcome,
+            &PskResolver::new(provider.storage(), resolve),
+        )
+    }
 
     /// Get a reference to the GroupInfo in this Welcome message.
     ///
@@ -336,8 +707,23 @@ impl ProcessedWelcome {
         &self.group_secrets.psks
     }
 
+    /// Returns `true` if the Welcome message doesn't include the ratchet tree,
+    /// so that it has to be passed to [`ProcessedWelcome::into_staged_welcome()`].
+    ///
+    /// The key package that was used to join may already be deleted, so the
+    /// `ProcessedWelcome` has to be kept until the ratchet tree is fetched.
+    pub fn requires_ratchet_tree(&self) -> bool {
+        self.verifiable_group_info
+            .extensions()
+            .ratchet_tree()
+            .is_none()
+    }
+
     /// Consume the `ProcessedWelcome` and combine it witht he ratchet tree into
     /// a `StagedWelcome`.
+    ///
+    /// Returns [`WelcomeError::MissingRatchetTree`] if no ratchet tree is
+    /// given, but [`ProcessedWelcome::requires_ratchet_tree()`] is `true`.
     pub fn into_staged_welcome<Provider: OpenMlsProvider>(
         mut self,
         provider: &Provider,
@@ -493,8 +879,33 @@ impl StagedWelcome {
         welcome: Welcome,
         ratchet_tree: Option<RatchetTreeIn>,
     ) -> Result<Self, WelcomeError<Provider::StorageError>> {
-        let processed_welcome =
-            ProcessedWelcome::new_from_welcome(provider, mls_group_config, welcome)?;
+        Self::new_from_welcome_with_policy(provider, mls_group_config, welcome, ratchet_tree, &())
+    }
+
+    /// Like [`StagedWelcome::new_from_welcome()`], but consults the given
+    /// [`ProcessingPolicy`] before staging the [`Welcome`]. If
+    /// [`ProcessingPolicy::accept_group_id()`] rejects the group ID,
+    /// [`WelcomeError::GroupIdRejected`] is returned, and if
+    /// [`ProcessingPolicy::validate_group_info()`] rejects the group,
+    /// [`WelcomeError::RejectedByPolicy`]. The policy is consulted before
+    /// the key material is consumed, so a rejected [`Welcome`] doesn't use up
+    /// the key package.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn new_from_welcome_with_policy<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        mls_group_config: &MlsGroupJoinConfig,
+        welcome: Welcome,
+        ratchet_tree: Option<RatchetTreeIn>,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<Self, WelcomeError<Provider::StorageError>> {
+        let processed_welcome = PendingPskWelcome::new_from_welcome(
+            provider,
+            mls_group_config,
+            welcome,
+            provider.storage(),
+        )?
+        .into_processed_welcome_with_policy(provider, policy)?;
 
         processed_welcome.into_staged_welcome(provider, ratchet_tree)
     }
@@ -518,20 +929,81 @@ impl StagedWelcome {
             ))
     }
 
+    /// Returns the [`Credential`] of the group member that authored the
+    /// [`Welcome`] message, i.e. of the signer of the GroupInfo contained in
+    /// the [`Welcome`].
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn welcome_sender_credential(&self) -> Result<&Credential, LibraryError> {
+        self.welcome_sender()
+            .map(|leaf_node| leaf_node.credential())
+    }
+
     /// Get the [`GroupContext`] of this welcome's [`PublicGroup`].
     pub fn group_context(&self) -> &GroupContext {
         self.public_group.group_context()
     }
 
+    /// Returns the onboarding data the welcome sender attached with
+    /// [`CommitBuilder::onboarding_data()`](super::commit_builder::CommitBuilder::onboarding_data),
+    /// i.e. the extensions of the GroupInfo except for the ratchet tree and
+    /// the external public key. The GroupInfo is signed by the welcome sender,
+    /// so the data is authenticated.
+    pub fn onboarding_data(&self) -> impl Iterator<Item = &Extension> {
+        self.verifiable_group_info
+            .extensions()
+            .iter()
+            .filter(|extension| {
+                !matches!(
+                    extension,
+                    Extension::RatchetTree(_) | Extension::ExternalPub(_)
+                )
+            })
+    }
+
     /// Get an iterator over all [`Member`]s of this welcome's [`PublicGroup`].
     pub fn members(&self) -> impl Iterator<Item = Member> + '_ {
         self.public_group.members()
     }
 
+    /// Consumes the [`StagedWelcome`] and returns the respective [`MlsGroup`]
+    /// if the function `validate_sender` accepts the [`LeafNode`] of the group
+    /// member that authored the [`Welcome`] message.
+    ///
+    /// This allows the application to enforce a policy on who may add this
+    /// client to a group, e.g. that the signer of the GroupInfo is the user
+    /// that invited it according to application data. If `validate_sender`
+    /// returns `false`, [`WelcomeError::WelcomeSenderRejected`] is returned and
+    /// no group state is written to storage.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    pub fn into_group_with_sender_validation<Provider: OpenMlsProvider>(
+        self,
+        provider: &Provider,
+        validate_sender: impl FnOnce(&LeafNode) -> bool,
+    ) -> Result<MlsGroup, WelcomeError<Provider::StorageError>> {
+        if !validate_sender(self.welcome_sender()?) {
+            return Err(WelcomeError::WelcomeSenderRejected);
+        }
+
+        self.into_group(provider)
+    }
+
     /// Consumes the [`StagedWelcome`] and returns the respective [`MlsGroup`].
     pub fn into_group<Provider: OpenMlsProvider>(
         self,
         provider: &Provider,
+    ) -> Result<MlsGroup, WelcomeError<Provider::StorageError>> {
+        self.into_group_with_hooks(provider, EpochHooks::default())
+    }
+
+    /// Consumes the [`StagedWelcome`] and returns the respective [`MlsGroup`]
+    /// with the given [`EpochHooks`], which are run for the joined epoch
+    /// before the group is stored.
+    pub fn into_group_with_hooks<Provider: OpenMlsProvider>(
+        self,
+        provider: &Provider,
+        hooks: EpochHooks,
     ) -> Result<MlsGroup, WelcomeError<Provider::StorageError>> {
         // If we got a path secret, derive the path (which also checks if the
         // public keys match) and store the derived keys in the key store.
@@ -547,6 +1019,10 @@ impl StagedWelcome {
             mls_group_config: self.mls_group_config,
             own_leaf_nodes: vec![],
             aad: vec![],
+            used_external_inits: UsedExternalInits::default(),
+            member_stats: MemberStatsCache::default(),
+            last_operation_timing: LastOperationTiming::default(),
+            epoch_hooks: hooks,
             group_state: MlsGroupState::Operational,
             public_group: self.public_group,
             group_epoch_secrets: self.group_epoch_secrets,
@@ -555,10 +1031,15 @@ impl StagedWelcome {
             resumption_psk_store: self.resumption_psk_store,
         };
 
+        mls_group
+            .run_epoch_hooks(provider)
+            .map_err(WelcomeError::EpochHookError)?;
+
         mls_group
             .store_epoch_keypairs(provider.storage(), group_keypairs.as_slice())
             .map_err(WelcomeError::StorageError)?;
-        mls_group.set_max_past_epochs(mls_group.mls_group_config.max_past_epochs);
+        mls_group.resize_message_secrets_store(mls_group.mls_group_config.max_past_epochs);
+        mls_group.set_max_past_epoch_age(mls_group.mls_group_config.max_past_epoch_age);
 
         mls_group
             .store(provider.storage())
@@ -590,13 +1071,12 @@ fn keys_for_welcome<Provider: OpenMlsProvider>(
                 .transpose()
         })
         .ok_or(WelcomeError::NoMatchingKeyPackage)??;
-    if !key_package_bundle.key_package().last_resort() {
-        provider
-            .storage()
-            .delete_key_package(&key_package_bundle.key_package.hash_ref(provider.crypto())?)
-            .map_err(WelcomeError::StorageError)?;
-    } else {
-        log::debug!("Key package has last resort extension, not deleting");
+    // A key package whose grace period is over is not used anymore.
+    if key_package_bundle
+        .retention_reason()
+        .is_some_and(|reason| reason.is_expired())
+    {
+        return Err(WelcomeError::NoMatchingKeyPackage);
     }
     Ok((resumption_psk_store, key_package_bundle))
 }
@@ -0,0 +1,247 @@
+//! # Devices of a user
+//!
+//! MLS only knows leaves, while applications usually think in users with one
+//! or more devices, each of which is a leaf of its own. The application
+//! defines how the user is derived from a leaf's [`Credential`] with a
+//! [`UserIdentityExtractor`], e.g. by parsing a user id out of an X.509
+//! subject or by splitting `user@device` style basic credentials. Closures
+//! implement the trait, and [`BasicCredentialIdentity`] uses the whole identity
+//! of a basic credential.
+//!
+//! With an extractor, [`MlsGroup::members_by_user()`] groups the members by
+//! user, [`MlsGroup::user_devices()`] returns all devices of one user,
+//! [`MlsGroup::remove_user()`] removes all of them in one commit,
+//! [`MlsGroup::remove_members_by_credential()`] does the same for the users
+//! of one or more credentials, e.g. per-device X.509 certificates, and
+//! [`MlsGroup::user_devices_share()`] checks that all of them agree on an
+//! attribute of their credentials.
+
+use std::collections::BTreeMap;
+
+use openmls_traits::signatures::Signer;
+
+use super::{
+    errors::{EmptyInputError, RemoveMembersError},
+    Member, MlsGroup,
+};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    credentials::{BasicCredential, Credential},
+    framing::MlsMessageOut,
+    messages::group_info::GroupInfo,
+    storage::OpenMlsProvider,
+};
+
+/// Derives the identity of the user a leaf belongs to from its
+/// [`Credential`]. See the [module documentation](self) for details.
+pub trait UserIdentityExtractor {
+    /// Returns the identity of the user the `credential` belongs to, or `None`
+    /// if it can't be determined.
+    fn user_identity(&self, credential: &Credential) -> Option<Vec<u8>>;
+}
+
+impl<F: Fn(&Credential) -> Option<Vec<u8>>> UserIdentityExtractor for F {
+    fn user_identity(&self, credential: &Credential) -> Option<Vec<u8>> {
+        self(credential)
+    }
+}
+
+/// A [`UserIdentityExtractor`] that uses the identity of a
+/// [`BasicCredential`] as user identity, i.e. all devices of a user share the
+/// same basic credential. Other credential types have no user identity.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BasicCredentialIdentity;
+
+impl UserIdentityExtractor for BasicCredentialIdentity {
+    fn user_identity(&self, credential: &Credential) -> Option<Vec<u8>> {
+        BasicCredential::try_from(credential.clone())
+            .ok()
+            .map(|credential| credential.identity().to_vec())
+    }
+}
+
+impl MlsGroup {
+    /// Returns the members of the group grouped by the user identity the
+    /// `extractor` derives from their credentials. Members without a user
+    /// identity are skipped.
+    pub fn members_by_user(
+        &self,
+        extractor: &impl UserIdentityExtractor,
+    ) -> BTreeMap<Vec<u8>, Vec<Member>> {
+        let mut users: BTreeMap<Vec<u8>, Vec<Member>> = BTreeMap::new();
+        for member in self.members() {
+            if let Some(user_identity) = extractor.user_identity(&member.credential) {
+                users.entry(user_identity).or_default().push(member);
+            }
+        }
+
+        users
+    }
+
+    /// Returns all members that belong to the user with the given
+    /// `user_identity`, including the own leaf.
+    pub fn user_devices(
+        &self,
+        extractor: &impl UserIdentityExtractor,
+        user_identity: &[u8],
+    ) -> Vec<Member> {
+        self.members()
+            .filter(|member| {
+                extractor.user_identity(&member.credential).as_deref() == Some(user_identity)
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the user with the given `user_identity` has at least
+    /// one device and the `attribute` of all of their credentials is present
+    /// and equal, e.g. the organization of X.509 certificates.
+    pub fn user_devices_share<T: PartialEq>(
+        &self,
+        extractor: &impl UserIdentityExtractor,
+        user_identity: &[u8],
+        attribute: impl Fn(&Credential) -> Option<T>,
+    ) -> bool {
+        let mut attributes = self
+            .user_devices(extractor, user_identity)
+            .into_iter()
+            .map(|member| attribute(&member.credential));
+        match attributes.next() {
+            Some(Some(first)) => attributes.all(|other| other.as_ref() == Some(&first)),
+            _ => false,
+        }
+    }
+
+    /// Removes all devices of the user with the given `user_identity` from
+    /// the group, except for the own leaf. See [`Self::remove_members()`] for
+    /// the returned messages.
+    ///
+    /// Returns an error if there is a pending commit, or
+    /// [`RemoveMembersError::UnknownMember`] if the user has no other device
+    /// in the group.
+    #[allow(clippy::type_complexity)]
+    pub fn remove_user<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        extractor: &impl UserIdentityExtractor,
+        user_identity: &[u8],
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        RemoveMembersError<Provider::StorageError>,
+    > {
+        let (commit, welcome, group_info, _) =
+            self.remove_users(provider, signer, extractor, &[user_identity])?;
+        Ok((commit, welcome, group_info))
+    }
+
+    /// Removes all devices of the users the given `credentials` belong to
+    /// from the group, except for the own leaf. The users are matched by the
+    /// user identity the `extractor` derives from the credentials, so that
+    /// devices with different credentials of the same user are removed as
+    /// well.
+    ///
+    /// See [`Self::remove_members()`] for the returned messages. In addition,
+    /// the credentials that didn't match any leaf are returned.
+    ///
+    /// Returns an error if there is a pending commit, or
+    /// [`RemoveMembersError::UnknownMember`] if none of the `credentials`
+    /// matched a leaf.
+    #[allow(clippy::type_complexity)]
+    pub fn remove_members_by_credential<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        extractor: &impl UserIdentityExtractor,
+        credentials: &[Credential],
+    ) -> Result<
+        (
+            MlsMessageOut,
+            Option<MlsMessageOut>,
+            Option<GroupInfo>,
+            Vec<Credential>,
+        ),
+        RemoveMembersError<Provider::StorageError>,
+    > {
+        if credentials.is_empty() {
+            return Err(RemoveMembersError::EmptyInput(
+                EmptyInputError::RemoveMembers,
+            ));
+        }
+
+        let user_identities: Vec<Option<Vec<u8>>> = credentials
+            .iter()
+            .map(|credential| extractor.user_identity(credential))
+            .collect();
+        let known_user_identities: Vec<&[u8]> = user_identities
+            .iter()
+            .flatten()
+            .map(Vec::as_slice)
+            .collect();
+        let (commit, welcome, group_info, unmatched_user_identities) =
+            self.remove_users(provider, signer, extractor, &known_user_identities)?;
+
+        let unmatched = credentials
+            .iter()
+            .zip(&user_identities)
+            .filter(|(_, user_identity)| match user_identity {
+                Some(user_identity) => {
+                    unmatched_user_identities.contains(&user_identity.as_slice())
+                }
+                None => true,
+            })
+            .map(|(credential, _)| credential.clone())
+            .collect();
+        Ok((commit, welcome, group_info, unmatched))
+    }
+
+    // Removes all devices of the users with the given `user_identities`,
+    // except for the own leaf, in one commit. In addition to the messages of
+    // `remove_members()`, the user identities without any such device are
+    // returned.
+    #[allow(clippy::type_complexity)]
+    fn remove_users<'a, Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        extractor: &impl UserIdentityExtractor,
+        user_identities: &[&'a [u8]],
+    ) -> Result<
+        (
+            MlsMessageOut,
+            Option<MlsMessageOut>,
+            Option<GroupInfo>,
+            Vec<&'a [u8]>,
+        ),
+        RemoveMembersError<Provider::StorageError>,
+    > {
+        self.is_operational()?;
+
+        let own_leaf_index = self.own_leaf_index();
+        let users = self.members_by_user(extractor);
+        let mut members: Vec<LeafNodeIndex> = Vec::new();
+        let mut unmatched = Vec::new();
+        for &user_identity in user_identities {
+            let mut devices = users
+                .get(user_identity)
+                .into_iter()
+                .flatten()
+                .map(|member| member.index)
+                .filter(|index| *index != own_leaf_index)
+                .peekable();
+            if devices.peek().is_none() {
+                unmatched.push(user_identity);
+            }
+            for index in devices {
+                if !members.contains(&index) {
+                    members.push(index);
+                }
+            }
+        }
+        if members.is_empty() {
+            return Err(RemoveMembersError::UnknownMember);
+        }
+
+        let (commit, welcome, group_info) = self.remove_members(provider, signer, &members)?;
+        Ok((commit, welcome, group_info, unmatched))
+    }
+}
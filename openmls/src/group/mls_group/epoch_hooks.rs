@@ -0,0 +1,118 @@
+//! # Epoch hooks
+//!
+//! [`EpochHooks`] are set on an [`MlsGroup`] with
+//! [`MlsGroup::set_epoch_hooks()`] and run whenever the group enters a new
+//! epoch: when a commit is merged with [`MlsGroup::merge_staged_commit()`] or
+//! [`MlsGroup::merge_pending_commit()`], including the merges that are done as
+//! part of other operations, e.g. creating a group with initial members, and
+//! when joining a group with [`StagedWelcome::into_group_with_hooks()`].
+//!
+//! The hooks are not persisted. They have to be set again after loading a
+//! group with [`MlsGroup::load()`]. For the first epoch of a group created
+//! with [`MlsGroup::new()`], they can be run with
+//! [`MlsGroup::run_epoch_hooks()`].
+
+use std::mem;
+
+use super::{errors::EpochHookError, *};
+#[cfg(feature = "compliance-escrow")]
+use crate::group::mls_group::escrow::EscrowHook;
+use crate::group::mls_group::{exported_secrets::ExportHook, update_policy::UpdatePolicyHook};
+
+/// Hooks that are run whenever a group enters a new epoch.
+///
+/// If a hook fails when merging a commit, the commit is merged nonetheless and
+/// [`MergeCommitError::EpochHookError`](crate::group::MergeCommitError::EpochHookError)
+/// is returned. When joining a group, the group isn't stored if a hook fails.
+#[derive(Default)]
+pub struct EpochHooks {
+    pub(super) exports: Option<ExportHook>,
+    pub(super) update_policy: Option<UpdatePolicyHook>,
+    #[cfg(feature = "compliance-escrow")]
+    pub(super) escrow: Option<EscrowHook>,
+}
+
+impl std::fmt::Debug for EpochHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ds = f.debug_struct("EpochHooks");
+        ds.field("exports", &self.exports.is_some());
+        ds.field("update_policy", &self.update_policy());
+        #[cfg(feature = "compliance-escrow")]
+        ds.field("escrow", &self.escrow.is_some());
+        ds.finish()
+    }
+}
+
+// The hooks are ephemeral and contain closures, so cloned groups don't carry
+// them and they don't affect the equality of groups.
+#[cfg(feature = "test-utils")]
+impl Clone for EpochHooks {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl PartialEq for EpochHooks {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl EpochHooks {
+    /// Creates an empty set of hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MlsGroup {
+    /// Sets the [`EpochHooks`] of the group and returns the previous ones.
+    pub fn set_epoch_hooks(&mut self, hooks: EpochHooks) -> EpochHooks {
+        mem::replace(&mut self.epoch_hooks, hooks)
+    }
+
+    /// Returns the [`EpochHooks`] of the group.
+    pub fn epoch_hooks(&self) -> &EpochHooks {
+        &self.epoch_hooks
+    }
+
+    /// Runs the [`EpochHooks`] of the group for the current epoch. Nothing is
+    /// run if the group is inactive.
+    ///
+    /// This is done automatically whenever the group enters a new epoch, so
+    /// it is only needed for the first epoch of a newly created group.
+    pub fn run_epoch_hooks<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+    ) -> Result<(), EpochHookError> {
+        if !self.is_active() {
+            return Ok(());
+        }
+
+        // The hooks are taken out of the group while they run, because they
+        // need access to the state of the group.
+        let mut hooks = mem::take(&mut self.epoch_hooks);
+        let result = self.run_hooks(provider, &mut hooks);
+        self.epoch_hooks = hooks;
+        result
+    }
+
+    fn run_hooks<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        hooks: &mut EpochHooks,
+    ) -> Result<(), EpochHookError> {
+        if let Some(update_policy) = hooks.update_policy.as_mut() {
+            update_policy.run(self);
+        }
+        if let Some(exports) = hooks.exports.as_mut() {
+            exports.run(self, provider)?;
+        }
+        #[cfg(feature = "compliance-escrow")]
+        if let Some(escrow) = hooks.escrow.as_mut() {
+            escrow.run(self, provider.crypto())?;
+        }
+        Ok(())
+    }
+}
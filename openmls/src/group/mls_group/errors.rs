@@ -5,21 +5,27 @@
 
 // These errors are exposed through `crate::group::errors`.
 
+use openmls_traits::types::Ciphersuite;
 use thiserror::Error;
 
 use crate::{
+    ciphersuite::signable::SignatureError,
+    credentials::CredentialType,
     error::LibraryError,
-    extensions::errors::InvalidExtensionError,
+    extensions::{errors::InvalidExtensionError, ExtensionType},
+    framing::MlsMessageOut,
     group::{
         errors::{
             CreateAddProposalError, CreateCommitError, MergeCommitError, StageCommitError,
-            ValidationError,
+            ValidationError, WelcomeError,
         },
-        CommitBuilderStageError, CreateGroupContextExtProposalError,
+        CommitBuilderStageError, CreateGroupContextExtProposalError, GroupEpoch, MissingCapability,
+        ResumableMessage, StaleLeaf,
     },
+    messages::proposals::ProposalType,
     schedule::errors::PskError,
     treesync::{
-        errors::{LeafNodeValidationError, PublicTreeError},
+        errors::{LeafNodeValidationError, PublicTreeError, TreeSliceError},
         node::leaf_node::LeafNodeUpdateError,
     },
 };
@@ -45,6 +51,50 @@ pub enum NewGroupError<StorageError> {
     /// Invalid extensions set in configuration
     #[error("Invalid extensions set in configuration")]
     InvalidExtensions(#[from] InvalidExtensionError),
+    /// The creator's capabilities are inconsistent with its leaf node. See
+    /// [`LeafNodeValidationError`] for more details.
+    #[error(transparent)]
+    LeafNodeValidation(#[from] LeafNodeValidationError),
+    /// See [`CreateConfigError`] for more details.
+    #[error(transparent)]
+    InvalidConfig(#[from] CreateConfigError),
+}
+
+/// Error validating an [`MlsGroupCreateConfig`](crate::group::MlsGroupCreateConfig).
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum CreateConfigError {
+    /// The group's ciphersuite is not listed in the creator's capabilities.
+    #[error("The group's ciphersuite {0:?} is not listed in the creator's capabilities.")]
+    CiphersuiteNotInCapabilities(Ciphersuite),
+    /// A group context extension is not listed in the creator's capabilities.
+    #[error("The group context extension {0:?} is not listed in the creator's capabilities.")]
+    ExtensionNotInCapabilities(ExtensionType),
+    /// A required extension type is not listed in the creator's capabilities.
+    #[error("The required extension type {0:?} is not listed in the creator's capabilities.")]
+    RequiredExtensionNotInCapabilities(ExtensionType),
+    /// A required proposal type is not listed in the creator's capabilities.
+    #[error("The required proposal type {0:?} is not listed in the creator's capabilities.")]
+    RequiredProposalNotInCapabilities(ProposalType),
+    /// A required credential type is not listed in the creator's capabilities.
+    #[error("The required credential type {0:?} is not listed in the creator's capabilities.")]
+    RequiredCredentialNotInCapabilities(CredentialType),
+}
+
+/// Error creating a new group with initial members.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum NewGroupWithMembersError<StorageError> {
+    /// See [`NewGroupError`] for more details.
+    #[error(transparent)]
+    NewGroup(#[from] NewGroupError<StorageError>),
+    /// See [`AddMembersError`] for more details.
+    #[error(transparent)]
+    AddMembers(#[from] AddMembersError<StorageError>),
+    /// See [`MergePendingCommitError`] for more details.
+    #[error(transparent)]
+    MergePendingCommit(#[from] MergePendingCommitError<StorageError>),
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
 }
 
 /// EmptyInput error
@@ -67,6 +117,9 @@ pub enum MlsGroupStateError {
     /// Tried to use a group after being evicted from it.
     #[error("Tried to use a group after being evicted from it.")]
     UseAfterEviction,
+    /// Tried to use a group after it was re-initialized.
+    #[error("Tried to use a group after it was re-initialized.")]
+    UseAfterReInit,
     /// Can't create message because a pending proposal exists.
     #[error("Can't create message because a pending proposal exists.")]
     PendingProposal,
@@ -79,6 +132,9 @@ pub enum MlsGroupStateError {
     /// Requested pending proposal hasn't been found in local pending proposals
     #[error("Requested pending proposal hasn't been found in local pending proposals.")]
     PendingProposalNotFound,
+    /// Can't execute operation because the own client is leaving the group.
+    #[error("Can't execute operation because the own client is leaving the group.")]
+    PendingSelfRemoval,
 }
 
 /// Error merging pending commit
@@ -116,6 +172,51 @@ pub enum ProcessMessageError {
     /// The proposal is invalid for the Sender of type [External](crate::prelude::Sender::External)
     #[error("The proposal is invalid for the Sender of type External")]
     UnsupportedProposalType,
+    /// The external sender is not allowed to send proposals of this type. See
+    /// [`ProcessingPolicy::admit_external_proposal()`](crate::group::ProcessingPolicy::admit_external_proposal()).
+    #[error("The external sender is not allowed to send proposals of this type.")]
+    UnauthorizedExternalProposal,
+    /// The Remove proposal removes a protected member and was rejected by the
+    /// application's processing policy. See
+    /// [`ProcessingPolicy::admit_remove()`](crate::group::ProcessingPolicy::admit_remove()).
+    #[error("The Remove proposal removes a protected member.")]
+    ProtectedMemberRemoval,
+    /// The message is the pending commit of this client, echoed back by the
+    /// DS. The pending commit was accepted and can be merged using
+    /// [`MlsGroup::merge_pending_commit()`](crate::group::MlsGroup::merge_pending_commit()).
+    #[error("The message is the pending commit of this client.")]
+    OwnCommitReceived,
+    /// The message is an application message sent by this client, echoed
+    /// back by the DS. The epoch and the generation can be used to correlate
+    /// it with the sent messages.
+    #[error("The message is an own application message (epoch {epoch}, generation {generation}).")]
+    OwnMessageEchoed {
+        /// The epoch the message was sent in.
+        epoch: GroupEpoch,
+        /// The generation of the sender ratchet the message was encrypted with.
+        generation: u32,
+    },
+    /// The key package of an Add proposal sent by an external sender lacks
+    /// capabilities needed to join the group.
+    #[error("The key package of the external Add proposal is incompatible with the group.")]
+    IncompatibleExternalAdd(Vec<MissingCapability>),
+    /// Accessing storage failed.
+    #[error("Accessing storage failed.")]
+    StorageError,
+}
+
+/// Error of [`MlsGroup::process_message_resumable()`](super::MlsGroup::process_message_resumable())
+/// and [`MlsGroup::resume_message()`](super::MlsGroup::resume_message()).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ResumableProcessMessageError {
+    /// See [`ProcessMessageError`] for more details.
+    #[error(transparent)]
+    ProcessMessageError(#[from] ProcessMessageError),
+    /// Processing was suspended because a resource is missing, see
+    /// [`ResumableMessage::reason()`]. Processing can be resumed with the
+    /// [`ResumableMessage`] once it is available.
+    #[error("Processing was suspended: {:?}", .0.reason())]
+    Suspended(Box<ResumableMessage>),
 }
 
 /// Create message error
@@ -127,6 +228,54 @@ pub enum CreateMessageError {
     /// See [`MlsGroupStateError`] for more details.
     #[error(transparent)]
     GroupStateError(#[from] MlsGroupStateError),
+    /// The own leaf is stale and has to be updated before application
+    /// messages can be sent. See
+    /// [`MandatoryUpdatePolicy`](crate::group::MandatoryUpdatePolicy).
+    #[error("The own leaf wasn't updated since epoch {}.", .0.last_update().as_u64())]
+    UpdateRequired(StaleLeaf),
+}
+
+/// Error creating chunked application messages with
+/// [`MlsGroup::create_chunked_messages()`](crate::group::MlsGroup::create_chunked_messages()).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum CreateChunkedMessagesError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// No message was created. See [`CreateMessageError`] for more details.
+    #[error(transparent)]
+    CreateMessageError(#[from] CreateMessageError),
+    /// Creating a message failed after the messages of the previous chunks
+    /// were created. These messages are returned, because the group already
+    /// used up their ratchet generations.
+    #[error("Only {} chunks could be created: {error}", messages.len())]
+    Incomplete {
+        /// The messages of the chunks that were created.
+        messages: Vec<MlsMessageOut>,
+        /// The error creating the next message.
+        error: CreateMessageError,
+    },
+}
+
+/// Error reassembling chunked application messages.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ChunkReassemblyError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The message is not a valid chunk.
+    #[error("The message is not a valid chunk.")]
+    MalformedChunk,
+    /// The chunk doesn't match the other chunks of its transfer.
+    #[error("The chunk doesn't match the other chunks of its transfer.")]
+    InconsistentChunk,
+    /// The payload of the transfer exceeds the maximum payload size.
+    #[error("The payload of the transfer exceeds the maximum payload size.")]
+    PayloadTooLarge,
+    /// The digest of the reassembled payload doesn't match the digest sent
+    /// with the chunks.
+    #[error("The digest of the reassembled payload doesn't match.")]
+    DigestMismatch,
 }
 
 /// Add members error
@@ -215,6 +364,29 @@ pub enum RemoveMembersError<StorageError> {
     StorageError(StorageError),
 }
 
+/// Swap member error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum SwapMemberError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError),
+    /// See [`CommitBuilderStageError`] for more details.
+    #[error(transparent)]
+    CommitBuilderStageError(#[from] CommitBuilderStageError<StorageError>),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+    /// The member that should be replaced can not be found.
+    #[error("The member that should be replaced can not be found.")]
+    UnknownMember,
+    /// The own leaf can't be replaced with a commit of this client.
+    #[error("The own leaf can't be replaced with a commit of this client.")]
+    CannotSwapSelf,
+}
+
 /// Leave group error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum LeaveGroupError<StorageError> {
@@ -249,6 +421,110 @@ pub enum SelfUpdateError<StorageError> {
     StorageError(StorageError),
 }
 
+/// Error re-initializing a group.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ReInitError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError),
+    /// See [`CommitBuilderStageError`] for more details.
+    #[error(transparent)]
+    CommitBuilderStageError(#[from] CommitBuilderStageError<StorageError>),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+}
+
+/// Purge inactive group error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum PurgeInactiveGroupError<StorageError> {
+    /// The group is still active.
+    #[error("The group is still active.")]
+    GroupActive,
+    /// An error ocurred while writing to storage
+    #[error("An error ocurred while writing to storage")]
+    StorageError(StorageError),
+}
+
+/// Error re-committing the proposals of a superseded commit.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum RecommitError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The superseded commit was created for the current epoch, i.e. the
+    /// conflicting commit has not been merged yet.
+    #[error("The conflicting commit has not been merged yet.")]
+    ConflictNotMerged,
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError),
+    /// See [`CommitBuilderStageError`] for more details.
+    #[error(transparent)]
+    CommitBuilderStageError(#[from] CommitBuilderStageError<StorageError>),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+}
+
+/// Error merging a commit and re-proposing the own proposals it didn't cover.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ReproposeError<StorageError> {
+    /// See [`MergeCommitError`] for more details.
+    #[error(transparent)]
+    MergeCommitError(#[from] MergeCommitError<StorageError>),
+    /// See [`ProposalError`] for more details.
+    #[error(transparent)]
+    ProposalError(#[from] ProposalError<StorageError>),
+}
+
+/// Error creating or joining the successor of a re-initialized group.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ReInitSuccessorError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The group has not been re-initialized.
+    #[error("The group has not been re-initialized.")]
+    NotReInitialized,
+    /// The protocol version of the new group is not supported.
+    #[error("The protocol version of the new group is not supported.")]
+    UnsupportedMlsVersion,
+    /// See [`EmptyInputError`] for more details.
+    #[error(transparent)]
+    EmptyInput(#[from] EmptyInputError),
+    /// See [`NewGroupError`] for more details.
+    #[error(transparent)]
+    NewGroup(#[from] NewGroupError<StorageError>),
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError),
+    /// See [`CommitBuilderStageError`] for more details.
+    #[error(transparent)]
+    CommitBuilderStageError(#[from] CommitBuilderStageError<StorageError>),
+    /// See [`MergePendingCommitError`] for more details.
+    #[error(transparent)]
+    MergePendingCommit(#[from] MergePendingCommitError<StorageError>),
+    /// See [`WelcomeError`] for more details.
+    #[error(transparent)]
+    Welcome(#[from] WelcomeError<StorageError>),
+    /// The Welcome does not contain the resumption PSK of the re-initialized group.
+    #[error("The Welcome does not contain the resumption PSK of the re-initialized group.")]
+    MissingReInitPsk,
+    /// The parameters of the new group don't match the ReInit proposal.
+    #[error("The parameters of the new group don't match the ReInit proposal.")]
+    SuccessorMismatch,
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+}
+
 /// Propose self update error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum ProposeSelfUpdateError<StorageError> {
@@ -299,6 +575,9 @@ pub enum ExportGroupInfoError {
     /// See [`MlsGroupStateError`] for more details.
     #[error(transparent)]
     GroupStateError(#[from] MlsGroupStateError),
+    /// The extensions of the GroupInfo contain duplicate extension types.
+    #[error("Invalid extensions set in the GroupInfo options")]
+    InvalidExtensions(#[from] InvalidExtensionError),
 }
 
 /// Export secret error
@@ -315,6 +594,152 @@ pub enum ExportSecretError {
     GroupStateError(#[from] MlsGroupStateError),
 }
 
+/// Error decrypting a message with a [`LightGroup`](crate::group::LightGroup).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum LightGroupDecryptError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`ValidationError`] for more details.
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
+    /// See [`TreeSliceError`] for more details.
+    #[error(transparent)]
+    TreeSlice(#[from] TreeSliceError),
+    /// A light client can only decrypt application messages.
+    #[error("A light client can only decrypt application messages.")]
+    NotAnApplicationMessage,
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+}
+
+/// Error decrypting an [`EncryptedAttachment`](crate::group::EncryptedAttachment).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum AttachmentDecryptionError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The attachment was encrypted for a different group, epoch or
+    /// attachment ID.
+    #[error("The attachment was encrypted for a different group, epoch or attachment ID.")]
+    HeaderMismatch,
+    /// The attachment could not be decrypted.
+    #[error("The attachment could not be decrypted.")]
+    DecryptionFailed,
+}
+
+/// Error signing application data.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum SignAppDataError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+    /// See [`SignatureError`] for more details.
+    #[error(transparent)]
+    SignatureError(#[from] SignatureError),
+}
+
+/// Error verifying [`SignedAppData`](crate::group::SignedAppData).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum VerifyAppDataError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The data was signed in a different group.
+    #[error("The data was signed in a different group.")]
+    GroupMismatch,
+    /// The signer is not a member of the group.
+    #[error("The signer is not a member of the group.")]
+    UnknownMember,
+    /// The signature is invalid.
+    #[error("The signature is invalid.")]
+    InvalidSignature,
+}
+
+/// Error creating a [`LeafKeyProof`](crate::group::LeafKeyProof).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ProveLeafKeyError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+    /// See [`SignatureError`] for more details.
+    #[error(transparent)]
+    SignatureError(#[from] SignatureError),
+}
+
+/// Error verifying a [`LeafKeyProof`](crate::group::LeafKeyProof).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum VerifyLeafKeyProofError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The proof was created in a different group.
+    #[error("The proof was created in a different group.")]
+    GroupMismatch,
+    /// The proof was created in a different epoch.
+    #[error("The proof was created in a different epoch.")]
+    EpochMismatch,
+    /// The signature key is not the one in the leaf of the prover.
+    #[error("The signature key is not the one in the leaf of the prover.")]
+    KeyMismatch,
+    /// The signature is invalid.
+    #[error("The signature is invalid.")]
+    InvalidSignature,
+}
+
+/// Error creating a [`MembershipToken`](crate::group::MembershipToken).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum CreateMembershipTokenError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`ExportSecretError`] for more details.
+    #[error(transparent)]
+    ExportSecretError(#[from] ExportSecretError),
+    /// See [`SignatureError`] for more details.
+    #[error(transparent)]
+    SignatureError(#[from] SignatureError),
+}
+
+/// Error verifying a [`MembershipToken`](crate::group::MembershipToken).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum VerifyMembershipTokenError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`ExportSecretError`] for more details.
+    #[error(transparent)]
+    ExportSecretError(#[from] ExportSecretError),
+    /// The token was created in a different group.
+    #[error("The token was created in a different group.")]
+    GroupMismatch,
+    /// The token was created in a different epoch.
+    #[error("The token was created in a different epoch.")]
+    EpochMismatch,
+    /// The member is not a member of the group.
+    #[error("The member is not a member of the group.")]
+    UnknownMember,
+    /// The credential is not the one in the leaf of the member.
+    #[error("The credential is not the one in the leaf of the member.")]
+    CredentialMismatch,
+    /// The signature key is not the one in the leaf of the member.
+    #[error("The signature key is not the one in the leaf of the member.")]
+    KeyMismatch,
+    /// The tag of the token is invalid.
+    #[error("The tag of the token is invalid.")]
+    InvalidTag,
+    /// The signature of the token is invalid.
+    #[error("The signature of the token is invalid.")]
+    InvalidSignature,
+}
+
 /// Propose PSK error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum ProposePskError {
@@ -371,3 +796,199 @@ pub enum RemoveProposalError<StorageError> {
     #[error("error writing proposal to storage")]
     Storage(StorageError),
 }
+
+/// Error exporting resumption PSKs.
+#[cfg(feature = "resumption-psk-backup")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ExportResumptionPskError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The resumption PSK of the epoch is not available.
+    #[error("The resumption PSK of epoch {0} is not available.")]
+    UnknownEpoch(GroupEpoch),
+}
+
+/// Error importing a [`ResumptionPskBackup`](crate::group::ResumptionPskBackup).
+#[cfg(feature = "resumption-psk-backup")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ImportResumptionPskError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The backup belongs to a different group or ciphersuite.
+    #[error("The backup belongs to a different group or ciphersuite.")]
+    GroupMismatch,
+    /// The backup could not be decrypted with the given key.
+    #[error("The backup could not be decrypted with the given key.")]
+    DecryptionFailed,
+    /// The decrypted backup is malformed.
+    #[error("The decrypted backup is malformed.")]
+    MalformedBackup,
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+}
+
+/// Error exporting the application message keys of an epoch to an
+/// [`EscrowSink`](crate::group::EscrowSink).
+#[cfg(feature = "compliance-escrow")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum EscrowError<SinkError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The keys of the epoch are no longer available, because messages of the
+    /// epoch were already sent or received, or the group is inactive.
+    #[error("The keys of the epoch are no longer available.")]
+    KeysUnavailable,
+    /// The entry of the log could not be signed.
+    #[error("The entry of the log could not be signed.")]
+    SigningError,
+    /// The sink returned an error.
+    #[error("The sink returned an error.")]
+    SinkError(SinkError),
+}
+
+/// Error running the [`EpochHooks`](crate::group::EpochHooks) of a group.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum EpochHookError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The registered secrets of the epoch could not be exported.
+    #[error(transparent)]
+    ExportSecretError(#[from] ExportSecretError),
+    /// The keys of the epoch could not be escrowed. Errors of the sink are
+    /// logged.
+    #[cfg(feature = "compliance-escrow")]
+    #[error(transparent)]
+    EscrowError(#[from] EscrowError<()>),
+}
+
+/// Error verifying an [`EscrowLog`](crate::group::EscrowLog).
+#[cfg(feature = "compliance-escrow")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum EscrowLogError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The entry at the given position has an unexpected sequence number.
+    #[error("The entry at position {0} has an unexpected sequence number.")]
+    InvalidSequenceNumber(u64),
+    /// The entry at the given position doesn't reference the previous entry.
+    #[error("The entry at position {0} doesn't reference the previous entry.")]
+    BrokenChain(u64),
+    /// The digest of the entry at the given position doesn't match its
+    /// content.
+    #[error("The digest of the entry at position {0} doesn't match its content.")]
+    InvalidDigest(u64),
+    /// The signature of the entry at the given position is invalid.
+    #[error("The signature of the entry at position {0} is invalid.")]
+    InvalidSignature(u64),
+}
+
+/// Error exporting a [`ReplicaDelta`](crate::group::ReplicaDelta).
+#[cfg(feature = "replica-sync")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ExportReplicaDeltaError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+    /// The storage doesn't persist the sequence number of the delta.
+    #[error("The storage doesn't persist the sequence number of the delta.")]
+    SequenceNotPersisted,
+}
+
+/// Error applying a [`ReplicaDelta`](crate::group::ReplicaDelta) to a replica.
+#[cfg(feature = "replica-sync")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ApplyReplicaDeltaError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The delta belongs to a different group.
+    #[error("The delta belongs to a different group.")]
+    GroupIdMismatch,
+    /// The delta doesn't apply to the current epoch of the replica, e.g.
+    /// because it is outdated or a previous delta is missing.
+    #[error("The delta doesn't apply to the current epoch of the replica.")]
+    EpochMismatch,
+    /// The sequence number of the delta is not greater than the one of the
+    /// last delta applied to the replica, e.g. because the delta is replayed.
+    #[error("The delta is not newer than the last delta applied to the replica.")]
+    StaleDelta,
+    /// The delta can't be used to create a new replica, because it only
+    /// contains the message secrets of an epoch.
+    #[error("The delta can't be used to create a new replica.")]
+    NotAnEpochDelta,
+    /// The delta could not be decrypted with the given key.
+    #[error("The delta could not be decrypted with the given key.")]
+    DecryptionFailed,
+    /// The decrypted delta is malformed.
+    #[error("The decrypted delta is malformed.")]
+    MalformedDelta,
+    /// The storage doesn't persist the sequence number of the delta, so
+    /// replayed deltas couldn't be detected.
+    #[error("The storage doesn't persist the sequence number of the delta.")]
+    SequenceNotPersisted,
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+}
+
+/// Error checking the integrity of the group state with
+/// [`MlsGroup::check_integrity()`](crate::group::MlsGroup::check_integrity).
+#[cfg(feature = "integrity-checks")]
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum CheckIntegrityError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The group state violates at least one invariant.
+    #[error("The group state violates at least one invariant.")]
+    Violated(Box<crate::group::IntegrityDiagnostic>),
+}
+
+/// Error validating a commit with a
+/// [`CommitValidationArtifact`](crate::group::CommitValidationArtifact).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ArtifactValidationError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The commit or the artifact belongs to a different group.
+    #[error("The commit or the artifact belongs to a different group.")]
+    WrongGroupId,
+    /// The commit or the artifact doesn't belong to the current epoch.
+    #[error("The commit or the artifact doesn't belong to the current epoch.")]
+    WrongEpoch,
+    /// The message is not a commit.
+    #[error("The message is not a commit.")]
+    NotACommit,
+    /// The sender of the commit is not a member of the group.
+    #[error("The sender of the commit is not a member of the group.")]
+    UnknownSender,
+    /// The artifact is not signed by the sender.
+    #[error("The artifact is not signed by the sender.")]
+    InvalidSignature,
+    /// The artifact was created for a different commit.
+    #[error("The artifact was created for a different commit.")]
+    CommitMismatch,
+    /// A slice in the artifact is not part of the tree of the current epoch.
+    /// See [`TreeSliceError`] for more details.
+    #[error(transparent)]
+    TreeSlice(#[from] TreeSliceError),
+    /// The proposals of the artifact don't match the commit.
+    #[error("The proposals of the artifact don't match the commit.")]
+    ProposalMismatch,
+    /// A proposal of the given type is not admissible in the commit.
+    #[error("A proposal of type {0:?} is not admissible in the commit.")]
+    InadmissibleProposal(ProposalType),
+    /// The commit is invalid, e.g. because its signature doesn't verify.
+    #[error("The commit is invalid: {0}")]
+    InvalidCommit(ValidationError),
+}
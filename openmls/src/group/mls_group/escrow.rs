@@ -0,0 +1,501 @@
+//! # Compliance key escrow
+//!
+//! This module lets regulated deployments export the application message keys
+//! of every epoch of an [`MlsGroup`] to an application-provided
+//! [`EscrowSink`]. Anyone with access to the escrowed keys can decrypt all
+//! application messages of the epoch, so the operator of the sink is
+//! effectively a passive member of the group. The module is only available
+//! with the `compliance-escrow` feature, which is off by default.
+//!
+//! The keys are exported by an escrow hook that is set with
+//! [`EpochHooks::with_escrow()`]. The hook runs whenever the group enters a new
+//! epoch, i.e. before any message of the epoch can be sent or received. The
+//! keys of the current epoch can also be exported with
+//! [`MlsGroup::escrow_epoch_keys()`], as long as no message of the epoch was
+//! sent or received yet.
+//!
+//! Every export is recorded in an [`EscrowLog`]. The entries of the log form a
+//! hash chain and are signed with a signature key of the application, so
+//! removing, reordering, altering or forging entries is detected by
+//! [`EscrowLog::verify()`]. Removing entries from the end of the log can only
+//! be detected by comparing with the entries the sink received.
+
+use openmls_traits::{
+    crypto::OpenMlsCrypto,
+    signatures::{Signer, SignerError},
+    types::{Ciphersuite, SignatureScheme},
+};
+use tls_codec::{
+    SecretVLBytes, Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes,
+    TlsSerialize, TlsSize, VLBytes,
+};
+
+use super::{
+    epoch_hooks::EpochHooks,
+    errors::{EpochHookError, EscrowError, EscrowLogError},
+    *,
+};
+use crate::ciphersuite::{
+    signable::Signable, OpenMlsSignaturePublicKey, Secret, SignContent, Signature,
+};
+
+const ESCROW_LOG_ENTRY_LABEL: &str = "EscrowLogEntry";
+
+/// The application message keys of a single epoch, handed to an
+/// [`EscrowSink`].
+///
+/// The keys are the initial application ratchet secrets of all leaves (see
+/// Section 9.1 of RFC 9420), from which the keys and nonces of all
+/// generations can be derived.
+pub struct EscrowedEpochKeys {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    application_secrets: Vec<(LeafNodeIndex, Secret)>,
+}
+
+impl std::fmt::Debug for EscrowedEpochKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EscrowedEpochKeys")
+            .field("group_id", &self.group_id)
+            .field("epoch", &self.epoch)
+            .field("ciphersuite", &self.ciphersuite)
+            .field("application_secrets", &"***")
+            .finish()
+    }
+}
+
+impl EscrowedEpochKeys {
+    /// Returns the ID of the group the keys belong to.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the keys belong to.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the initial application ratchet secret of the leaf at
+    /// `leaf_index`, or `None` if the index is outside of the tree.
+    pub fn application_secret(&self, leaf_index: LeafNodeIndex) -> Option<&[u8]> {
+        self.application_secrets
+            .iter()
+            .find(|(index, _)| *index == leaf_index)
+            .map(|(_, secret)| secret.as_slice())
+    }
+
+    /// Returns the initial application ratchet secrets of all leaves.
+    pub fn application_secrets(&self) -> impl Iterator<Item = (LeafNodeIndex, &[u8])> {
+        self.application_secrets
+            .iter()
+            .map(|(index, secret)| (*index, secret.as_slice()))
+    }
+
+    /// Computes the digest of the keys that is recorded in the
+    /// [`EscrowLogEntry`] of the export.
+    ///
+    /// The copies of the secrets that are hashed are zeroized on drop.
+    fn digest(&self, crypto: &impl OpenMlsCrypto) -> Result<Vec<u8>, LibraryError> {
+        let secrets: Vec<EscrowedSecret> = self
+            .application_secrets
+            .iter()
+            .map(|(leaf_index, secret)| EscrowedSecret {
+                leaf_index: *leaf_index,
+                secret: secret.as_slice().into(),
+            })
+            .collect();
+        let content: SecretVLBytes = EscrowedEpochKeysTbh {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            secrets,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?
+        .into();
+        crypto
+            .hash(self.ciphersuite.hash_algorithm(), content.as_slice())
+            .map_err(LibraryError::unexpected_crypto_error)
+    }
+}
+
+// The hashed representation of the escrowed keys.
+#[derive(TlsSerialize, TlsSize)]
+struct EscrowedEpochKeysTbh<'a> {
+    group_id: &'a GroupId,
+    epoch: GroupEpoch,
+    secrets: Vec<EscrowedSecret>,
+}
+
+#[derive(Debug, TlsSerialize, TlsSize)]
+struct EscrowedSecret {
+    leaf_index: LeafNodeIndex,
+    secret: SecretVLBytes,
+}
+
+/// A sink provided by the application that receives the application message
+/// keys of every escrowed epoch.
+pub trait EscrowSink {
+    /// The error returned by the sink.
+    type Error: std::fmt::Debug;
+
+    /// Stores the `keys` of an epoch. The `log_entry` is the entry that is
+    /// appended to the [`EscrowLog`] if the sink accepts the keys.
+    fn escrow(
+        &mut self,
+        keys: &EscrowedEpochKeys,
+        log_entry: &EscrowLogEntry,
+    ) -> Result<(), Self::Error>;
+}
+
+/// An entry of the [`EscrowLog`] that records a single export.
+///
+/// The entry contains a digest of the exported keys and the digest of the
+/// previous entry, and is itself identified by the digest over its content.
+/// The content is signed with the signature key of the escrow hook.
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct EscrowLogEntry {
+    sequence_number: u64,
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    keys_digest: VLBytes,
+    previous_digest: VLBytes,
+    digest: VLBytes,
+    signature: Signature,
+}
+
+// The hashed and signed part of an [`EscrowLogEntry`].
+#[derive(TlsSerialize, TlsSize)]
+struct EscrowLogEntryTbh<'a> {
+    sequence_number: u64,
+    group_id: &'a GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    keys_digest: &'a VLBytes,
+    previous_digest: &'a VLBytes,
+}
+
+impl Signable for EscrowLogEntryTbh<'_> {
+    type SignedOutput = Signature;
+
+    fn unsigned_payload(&self) -> Result<Vec<u8>, tls_codec::Error> {
+        self.tls_serialize_detached()
+    }
+
+    fn label(&self) -> &str {
+        ESCROW_LOG_ENTRY_LABEL
+    }
+}
+
+impl EscrowLogEntry {
+    /// Returns the position of the entry in the log.
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    /// Returns the ID of the group whose keys were exported.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch whose keys were exported.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the digest of the exported keys.
+    pub fn keys_digest(&self) -> &[u8] {
+        self.keys_digest.as_slice()
+    }
+
+    /// Returns the digest of the previous entry, which is empty for the
+    /// first entry of the log.
+    pub fn previous_digest(&self) -> &[u8] {
+        self.previous_digest.as_slice()
+    }
+
+    /// Returns the digest of this entry.
+    pub fn digest(&self) -> &[u8] {
+        self.digest.as_slice()
+    }
+
+    /// Returns the signature over the content of this entry.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Returns `true` if the entry records the export of the given `keys`.
+    pub fn matches(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        keys: &EscrowedEpochKeys,
+    ) -> Result<bool, LibraryError> {
+        Ok(self.group_id == keys.group_id
+            && self.epoch == keys.epoch
+            && self.keys_digest.as_slice() == keys.digest(crypto)?)
+    }
+
+    fn tbh(&self) -> EscrowLogEntryTbh<'_> {
+        EscrowLogEntryTbh {
+            sequence_number: self.sequence_number,
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            ciphersuite: self.ciphersuite,
+            keys_digest: &self.keys_digest,
+            previous_digest: &self.previous_digest,
+        }
+    }
+
+    fn compute_digest(&self, crypto: &impl OpenMlsCrypto) -> Result<Vec<u8>, LibraryError> {
+        let content = self
+            .tbh()
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        crypto
+            .hash(self.ciphersuite.hash_algorithm(), &content)
+            .map_err(LibraryError::unexpected_crypto_error)
+    }
+
+    fn verify_signature(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signature_key: &OpenMlsSignaturePublicKey,
+    ) -> Result<bool, LibraryError> {
+        let payload = self
+            .tbh()
+            .unsigned_payload()
+            .map_err(LibraryError::missing_bound_check)?;
+        Ok(signature_key
+            .verify_with_label(
+                crypto,
+                &self.signature,
+                &SignContent::new(ESCROW_LOG_ENTRY_LABEL, payload.into()),
+            )
+            .is_ok())
+    }
+}
+
+/// A tamper-evident log of all exports to an [`EscrowSink`].
+///
+/// The log is kept by the application and can be shared by multiple groups.
+/// It has to be persisted together with the groups, e.g. in its TLS
+/// serialized form.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct EscrowLog {
+    entries: Vec<EscrowLogEntry>,
+}
+
+impl EscrowLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the entries of the log, oldest first.
+    pub fn entries(&self) -> &[EscrowLogEntry] {
+        &self.entries
+    }
+
+    /// Verifies the hash chain of the log and the signatures of its entries
+    /// with the `signature_key` of the escrow hook.
+    ///
+    /// Returns an [`EscrowLogError`] with the sequence number of the first
+    /// entry that was altered, forged, removed or inserted out of order.
+    pub fn verify(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signature_key: &OpenMlsSignaturePublicKey,
+    ) -> Result<(), EscrowLogError> {
+        let mut previous_digest: &[u8] = &[];
+        for (sequence_number, entry) in self.entries.iter().enumerate() {
+            let sequence_number = sequence_number as u64;
+            if entry.sequence_number != sequence_number {
+                return Err(EscrowLogError::InvalidSequenceNumber(sequence_number));
+            }
+            if entry.previous_digest.as_slice() != previous_digest {
+                return Err(EscrowLogError::BrokenChain(sequence_number));
+            }
+            if entry.digest.as_slice() != entry.compute_digest(crypto)? {
+                return Err(EscrowLogError::InvalidDigest(sequence_number));
+            }
+            if !entry.verify_signature(crypto, signature_key)? {
+                return Err(EscrowLogError::InvalidSignature(sequence_number));
+            }
+            previous_digest = entry.digest.as_slice();
+        }
+        Ok(())
+    }
+
+    /// Creates the entry that records the export of `keys`, without
+    /// appending it.
+    fn next_entry<SinkError>(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signer: &impl Signer,
+        keys: &EscrowedEpochKeys,
+    ) -> Result<EscrowLogEntry, EscrowError<SinkError>> {
+        let previous_digest = self
+            .entries
+            .last()
+            .map(|entry| entry.digest.clone())
+            .unwrap_or_else(|| VLBytes::new(Vec::new()));
+        let mut entry = EscrowLogEntry {
+            sequence_number: self.entries.len() as u64,
+            group_id: keys.group_id.clone(),
+            epoch: keys.epoch,
+            ciphersuite: keys.ciphersuite,
+            keys_digest: VLBytes::new(keys.digest(crypto)?),
+            previous_digest,
+            digest: VLBytes::new(Vec::new()),
+            signature: Signature::from(Vec::new()),
+        };
+        entry.digest = VLBytes::new(entry.compute_digest(crypto)?);
+        entry.signature = entry
+            .tbh()
+            .sign(signer)
+            .map_err(|_| EscrowError::SigningError)?;
+        Ok(entry)
+    }
+}
+
+type SinkFn = Box<dyn FnMut(&EscrowedEpochKeys, &EscrowLogEntry) -> Result<(), ()> + Send + Sync>;
+
+/// The escrow hook of the [`EpochHooks`] of a group.
+pub(super) struct EscrowHook {
+    sink: SinkFn,
+    signer: Box<dyn Signer + Send + Sync>,
+    log: EscrowLog,
+}
+
+impl EscrowHook {
+    /// Exports the keys of the current epoch of the `group`.
+    pub(super) fn run(
+        &mut self,
+        group: &MlsGroup,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<(), EpochHookError> {
+        group.escrow_epoch_keys(
+            crypto,
+            &BoxedSigner(self.signer.as_ref()),
+            &mut SinkFnWrapper(&mut self.sink),
+            &mut self.log,
+        )?;
+        Ok(())
+    }
+}
+
+// Adapters that let the boxed signer and sink of the hook be used where
+// `impl Signer` and `impl EscrowSink` are expected.
+struct BoxedSigner<'a>(&'a (dyn Signer + Send + Sync));
+
+impl Signer for BoxedSigner<'_> {
+    fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.0.sign(payload)
+    }
+
+    fn signature_scheme(&self) -> SignatureScheme {
+        self.0.signature_scheme()
+    }
+}
+
+struct SinkFnWrapper<'a>(&'a mut SinkFn);
+
+impl EscrowSink for SinkFnWrapper<'_> {
+    type Error = ();
+
+    fn escrow(
+        &mut self,
+        keys: &EscrowedEpochKeys,
+        log_entry: &EscrowLogEntry,
+    ) -> Result<(), Self::Error> {
+        (self.0)(keys, log_entry)
+    }
+}
+
+impl EpochHooks {
+    /// Adds an escrow hook that exports the application message keys of every
+    /// new epoch to the `sink`, records the exports in the `log` and signs the
+    /// entries of the log with the `signer`.
+    ///
+    /// Errors of the sink are logged and returned as
+    /// [`EscrowError::SinkError`]. The log can be retrieved with
+    /// [`EpochHooks::escrow_log()`] to persist it.
+    pub fn with_escrow<Sink>(
+        mut self,
+        mut sink: Sink,
+        signer: impl Signer + Send + Sync + 'static,
+        log: EscrowLog,
+    ) -> Self
+    where
+        Sink: EscrowSink + Send + Sync + 'static,
+    {
+        self.escrow = Some(EscrowHook {
+            sink: Box::new(move |keys, log_entry| {
+                sink.escrow(keys, log_entry).map_err(|e| {
+                    log::error!("The escrow sink returned an error: {e:?}");
+                })
+            }),
+            signer: Box::new(signer),
+            log,
+        });
+        self
+    }
+
+    /// Returns the [`EscrowLog`] of the escrow hook, if there is one.
+    pub fn escrow_log(&self) -> Option<&EscrowLog> {
+        self.escrow.as_ref().map(|escrow| &escrow.log)
+    }
+}
+
+impl MlsGroup {
+    /// Exports the application message keys of the current epoch to the
+    /// `sink` and records the export in the `log`, signed with the `signer`.
+    ///
+    /// The keys can only be exported before any message of the epoch was sent
+    /// or received. Otherwise, and if the group is no longer active,
+    /// [`EscrowError::KeysUnavailable`] is returned. If the sink returns an
+    /// error, nothing is appended to the log.
+    pub fn escrow_epoch_keys<Sink: EscrowSink>(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signer: &impl Signer,
+        sink: &mut Sink,
+        log: &mut EscrowLog,
+    ) -> Result<EscrowLogEntry, EscrowError<Sink::Error>> {
+        if !self.is_active() {
+            return Err(EscrowError::KeysUnavailable);
+        }
+
+        let ciphersuite = self.ciphersuite();
+        let application_secrets = self
+            .message_secrets()
+            .secret_tree()
+            .application_ratchet_secrets(ciphersuite, crypto)
+            .map_err(|e| match e {
+                SecretTreeError::SecretReuseError => EscrowError::KeysUnavailable,
+                _ => LibraryError::custom("Error deriving application secrets").into(),
+            })?;
+        let keys = EscrowedEpochKeys {
+            group_id: self.group_id().clone(),
+            epoch: self.epoch(),
+            ciphersuite,
+            application_secrets,
+        };
+
+        let entry = log.next_entry(crypto, signer, &keys)?;
+        sink.escrow(&keys, &entry).map_err(EscrowError::SinkError)?;
+        log.entries.push(entry.clone());
+
+        Ok(entry)
+    }
+}
@@ -0,0 +1,181 @@
+//! # Exported secrets on epoch changes
+//!
+//! Applications often derive keys from the exporter secret of the current
+//! epoch, e.g. SFrame keys for media streams, and have to derive them again
+//! whenever the epoch changes. Instead of polling the epoch, the application
+//! registers the exported secrets it depends on in an
+//! [`ExportedSecretRegistry`] and adds it to the [`EpochHooks`] of the group
+//! with [`EpochHooks::with_exported_secrets()`], together with a handler. The
+//! handler receives all registered secrets of every new epoch.
+//!
+//! The secrets of the current epoch, e.g. right after creating a group, are
+//! derived with [`MlsGroup::export_registered_secrets()`].
+
+use std::collections::BTreeMap;
+
+use super::{
+    epoch_hooks::EpochHooks,
+    errors::{EpochHookError, ExportSecretError},
+    *,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Registration {
+    context: Vec<u8>,
+    key_length: usize,
+}
+
+/// The exported secrets an application depends on, identified by their
+/// exporter label.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportedSecretRegistry {
+    registrations: BTreeMap<String, Registration>,
+}
+
+impl ExportedSecretRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the secret exported with the given `label`, `context` and
+    /// `key_length`. A previous registration with the same label is replaced.
+    pub fn register(&mut self, label: impl Into<String>, context: &[u8], key_length: usize) {
+        self.registrations.insert(
+            label.into(),
+            Registration {
+                context: context.to_vec(),
+                key_length,
+            },
+        );
+    }
+
+    /// Removes the registration with the given `label`. Returns `false` if
+    /// there was none.
+    pub fn unregister(&mut self, label: &str) -> bool {
+        self.registrations.remove(label).is_some()
+    }
+
+    /// Returns the labels of all registered secrets.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.registrations.keys().map(String::as_str)
+    }
+
+    /// Returns `true` if no secret is registered.
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+}
+
+/// The registered secrets of an epoch.
+pub struct ExportedSecrets {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    secrets: BTreeMap<String, Vec<u8>>,
+}
+
+impl std::fmt::Debug for ExportedSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportedSecrets")
+            .field("group_id", &self.group_id)
+            .field("epoch", &self.epoch)
+            .field("labels", &self.secrets.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ExportedSecrets {
+    /// Returns the ID of the group the secrets were exported from.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the secrets were exported from.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the secret exported with the given `label`, if it was
+    /// registered.
+    pub fn get(&self, label: &str) -> Option<&[u8]> {
+        self.secrets.get(label).map(Vec::as_slice)
+    }
+
+    /// Returns all exported secrets together with their labels.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.secrets
+            .iter()
+            .map(|(label, secret)| (label.as_str(), secret.as_slice()))
+    }
+}
+
+type Handler = Box<dyn FnMut(&ExportedSecrets) + Send + Sync>;
+
+/// The exported secrets hook of the [`EpochHooks`] of a group.
+pub(super) struct ExportHook {
+    registry: ExportedSecretRegistry,
+    handler: Handler,
+}
+
+impl ExportHook {
+    /// Hands the registered secrets of the current epoch of the `group` to
+    /// the handler.
+    pub(super) fn run<Provider: OpenMlsProvider>(
+        &mut self,
+        group: &MlsGroup,
+        provider: &Provider,
+    ) -> Result<(), EpochHookError> {
+        if self.registry.is_empty() {
+            return Ok(());
+        }
+        let secrets = group.export_registered_secrets(provider, &self.registry)?;
+        (self.handler)(&secrets);
+        Ok(())
+    }
+}
+
+impl EpochHooks {
+    /// Adds a hook that derives the secrets in the `registry` for every new
+    /// epoch and hands them to the `handler`.
+    pub fn with_exported_secrets(
+        mut self,
+        registry: ExportedSecretRegistry,
+        handler: impl FnMut(&ExportedSecrets) + Send + Sync + 'static,
+    ) -> Self {
+        self.exports = Some(ExportHook {
+            registry,
+            handler: Box::new(handler),
+        });
+        self
+    }
+}
+
+impl MlsGroup {
+    /// Derives all secrets in the `registry` from the exporter secret of the
+    /// current epoch.
+    pub fn export_registered_secrets<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        registry: &ExportedSecretRegistry,
+    ) -> Result<ExportedSecrets, ExportSecretError> {
+        let secrets = registry
+            .registrations
+            .iter()
+            .map(|(label, registration)| {
+                let secret = self.export_secret(
+                    provider,
+                    label,
+                    &registration.context,
+                    registration.key_length,
+                )?;
+                Ok((label.clone(), secret))
+            })
+            .collect::<Result<_, ExportSecretError>>()?;
+
+        Ok(ExportedSecrets {
+            group_id: self.group_id().clone(),
+            epoch: self.epoch(),
+            secrets,
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use config::GroupInfoOptions;
 use errors::{ExportGroupInfoError, ExportSecretError};
 use openmls_traits::signatures::Signer;
 
@@ -5,6 +6,7 @@ use crate::{
     ciphersuite::HpkePublicKey,
     schedule::{EpochAuthenticator, ResumptionPskSecret},
     storage::OpenMlsProvider,
+    treesync::{errors::TreeSliceError, TreeSlice},
 };
 
 use super::*;
@@ -49,6 +51,19 @@ impl MlsGroup {
         self.group_epoch_secrets().epoch_authenticator()
     }
 
+    /// ☣️ Returns the secrets of the current epoch that are retained by the
+    /// group.
+    ///
+    /// **WARNING**: This exposes the group's key material and must only be
+    /// used to generate or cross-check test vectors.
+    #[cfg(feature = "insecure-test-vectors")]
+    pub fn insecure_epoch_secrets(
+        &self,
+    ) -> crate::schedule::insecure_test_vectors::InsecureEpochSecrets {
+        self.group_epoch_secrets()
+            .insecure_export(self.message_secrets())
+    }
+
     /// Returns the resumption PSK secret of the current epoch.
     pub fn resumption_psk_secret(&self) -> &ResumptionPskSecret {
         self.group_epoch_secrets().resumption_psk()
@@ -66,36 +81,69 @@ impl MlsGroup {
         provider: &Provider,
         signer: &impl Signer,
         with_ratchet_tree: bool,
+    ) -> Result<MlsMessageOut, ExportGroupInfoError> {
+        self.export_group_info_with_options(
+            provider,
+            signer,
+            &GroupInfoOptions::new().with_ratchet_tree(with_ratchet_tree),
+        )
+    }
+
+    /// Export a group info object that new members can use to join the group
+    /// by external commit without receiving a Welcome message, see
+    /// [`MlsGroup::join_with_admission_token()`]. The group info contains the
+    /// ratchet tree and the external public key. The external key pair is
+    /// rotated with every epoch, so the group info has to be re-exported after
+    /// each epoch change.
+    pub fn export_onboarding_group_info<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        signer: &impl Signer,
+    ) -> Result<MlsMessageOut, ExportGroupInfoError> {
+        self.export_group_info_with_options(
+            provider,
+            signer,
+            &GroupInfoOptions::new()
+                .with_ratchet_tree(true)
+                .with_external_pub(true),
+        )
+    }
+
+    /// Export a group info object for this group, including the extensions
+    /// selected in the given [`GroupInfoOptions`].
+    ///
+    /// Returns [`ExportGroupInfoError::InvalidExtensions`] if the resulting
+    /// extensions contain duplicate extension types.
+    pub fn export_group_info_with_options<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        signer: &impl Signer,
+        options: &GroupInfoOptions,
     ) -> Result<MlsMessageOut, ExportGroupInfoError> {
         let extensions = {
-            let ratchet_tree_extension = || {
-                Extension::RatchetTree(RatchetTreeExtension::new(
+            let mut extensions = Vec::new();
+
+            if options.ratchet_tree() {
+                extensions.push(Extension::RatchetTree(RatchetTreeExtension::new(
                     self.public_group().export_ratchet_tree(),
-                ))
-            };
+                )));
+            }
 
-            let external_pub_extension = || -> Result<Extension, ExportGroupInfoError> {
+            if options.external_pub() {
                 let external_pub = self
                     .group_epoch_secrets()
                     .external_secret()
                     .derive_external_keypair(provider.crypto(), self.ciphersuite())
                     .map_err(LibraryError::unexpected_crypto_error)?
                     .public;
-                Ok(Extension::ExternalPub(ExternalPubExtension::new(
+                extensions.push(Extension::ExternalPub(ExternalPubExtension::new(
                     HpkePublicKey::from(external_pub),
-                )))
-            };
-
-            if with_ratchet_tree {
-                Extensions::from_vec(vec![ratchet_tree_extension(), external_pub_extension()?])
-                    .map_err(|_| {
-                        LibraryError::custom(
-                            "There should not have been duplicate extensions here.",
-                        )
-                    })?
-            } else {
-                Extensions::single(external_pub_extension()?)
+                )));
             }
+
+            extensions.extend(options.extensions().iter().cloned());
+
+            Extensions::from_vec(extensions)?
         };
 
         // Create to-be-signed group info.
@@ -119,4 +167,17 @@ impl MlsGroup {
             .map_err(|_| LibraryError::custom("Signing failed"))?;
         Ok(group_info.into())
     }
+
+    /// Cuts the [`TreeSlice`] of the leaf at `leaf_index` from the current
+    /// tree, e.g. to let a light client join with a Welcome. See
+    /// [`ProcessedWelcome::into_light_group()`].
+    pub fn export_tree_slice(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        leaf_index: LeafNodeIndex,
+    ) -> Result<TreeSlice, TreeSliceError> {
+        self.public_group()
+            .treesync()
+            .tree_slice(crypto, self.ciphersuite(), leaf_index)
+    }
 }
@@ -0,0 +1,311 @@
+//! Inactive groups
+//!
+//! This module contains the retention policy for groups in the
+//! [`MlsGroupState::Inactive`] state. An inactive group can't be used anymore,
+//! but its secret material is kept in the storage until the group is deleted
+//! with [`MlsGroup::delete()`].
+//!
+//! If [`MlsGroupJoinConfig::inactive_group_retention()`] is set, the secret
+//! material is due for purging once the group has been inactive for that
+//! period, see [`MlsGroup::purge_due()`]. [`MlsGroup::purge_secrets()`] deletes
+//! the secret material, but keeps a read-only record of the group in the
+//! storage that can be loaded with [`InactiveGroupRecord::load()`].
+//!
+//! For displaying an inactive group, a [`ClosedGroupView`] exposes its
+//! membership, epochs and group context extensions. In contrast to an
+//! [`MlsGroup`], it doesn't have any functions to send messages or create
+//! proposals or commits.
+
+#[cfg(target_arch = "wasm32")]
+use fluvio_wasm_timer::SystemTime;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::SystemTime;
+
+use super::{errors::PurgeInactiveGroupError, *};
+use crate::storage::StorageReader;
+
+/// A read-only record of an inactive group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InactiveGroupRecord {
+    group_id: GroupId,
+    last_epoch: GroupEpoch,
+    own_leaf_index: LeafNodeIndex,
+    members: Vec<Member>,
+    membership_history: Vec<(GroupEpoch, Vec<Member>)>,
+    has_secrets: bool,
+}
+
+impl InactiveGroupRecord {
+    /// Loads the record of the inactive group with the given `group_id` from
+    /// the `storage`. Returns `None` if there is no such group or if the group
+    /// is not in the [`MlsGroupState::Inactive`] state.
+    ///
+    /// The membership history only contains the epochs for which the message
+    /// secrets are still stored.
+    pub fn load<Storage: StorageReader>(
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<Option<Self>, Storage::Error> {
+        Ok(load_inactive(storage, group_id)?.map(|(record, _, _)| record))
+    }
+
+    /// Returns the group ID of the group.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the last epoch of the group known to this client.
+    pub fn last_epoch(&self) -> GroupEpoch {
+        self.last_epoch
+    }
+
+    /// Returns the leaf index this client had in the group.
+    pub fn own_leaf_index(&self) -> LeafNodeIndex {
+        self.own_leaf_index
+    }
+
+    /// Returns the members of the group in the last epoch.
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Returns the [`Credential`] of the member at the given leaf index in the
+    /// last epoch, or `None` if there is no such member.
+    pub fn member(&self, leaf_index: LeafNodeIndex) -> Option<&Credential> {
+        self.members
+            .iter()
+            .find(|member| member.index == leaf_index)
+            .map(|member| &member.credential)
+    }
+
+    /// Returns the members of the group in the past epochs before the last
+    /// epoch, in ascending order of the epochs.
+    pub fn membership_history(&self) -> &[(GroupEpoch, Vec<Member>)] {
+        &self.membership_history
+    }
+
+    /// Returns whether the secret material of the group is still stored.
+    pub fn has_secrets(&self) -> bool {
+        self.has_secrets
+    }
+}
+
+/// A read-only view of a group in the [`MlsGroupState::Inactive`] state, e.g.
+/// for displaying the history of a group the own client was removed from.
+///
+/// The view can be loaded with [`ClosedGroupView::load()`], also after the
+/// secret material of the group was purged, or created from an
+/// [`MlsGroup`] with [`MlsGroup::closed_view()`].
+#[derive(Debug, Clone)]
+pub struct ClosedGroupView {
+    record: InactiveGroupRecord,
+    group_context: GroupContext,
+    inactive_since: Option<SystemTime>,
+}
+
+impl ClosedGroupView {
+    /// Loads the view of the inactive group with the given `group_id` from
+    /// the `storage`. Returns `None` if there is no such group or if the group
+    /// is not in the [`MlsGroupState::Inactive`] state.
+    pub fn load<Storage: StorageReader>(
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<Option<Self>, Storage::Error> {
+        Ok(
+            load_inactive(storage, group_id)?.map(|(record, group_context, inactive_since)| Self {
+                record,
+                group_context,
+                inactive_since,
+            }),
+        )
+    }
+
+    /// Returns the [`InactiveGroupRecord`] of the group.
+    pub fn record(&self) -> &InactiveGroupRecord {
+        &self.record
+    }
+
+    /// Returns the group ID of the group.
+    pub fn group_id(&self) -> &GroupId {
+        self.group_context.group_id()
+    }
+
+    /// Returns the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.group_context.ciphersuite()
+    }
+
+    /// Returns the last epoch of the group known to this client.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.group_context.epoch()
+    }
+
+    /// Returns the [`GroupContext`] of the last epoch.
+    pub fn group_context(&self) -> &GroupContext {
+        &self.group_context
+    }
+
+    /// Returns the group context extensions of the last epoch, which contain
+    /// the application metadata of the group.
+    pub fn extensions(&self) -> &Extensions {
+        self.group_context.extensions()
+    }
+
+    /// Returns the leaf index this client had in the group.
+    pub fn own_leaf_index(&self) -> LeafNodeIndex {
+        self.record.own_leaf_index()
+    }
+
+    /// Returns the members of the group in the last epoch.
+    pub fn members(&self) -> &[Member] {
+        self.record.members()
+    }
+
+    /// Returns the [`Credential`] of the member at the given leaf index in the
+    /// last epoch, or `None` if there is no such member.
+    pub fn member(&self, leaf_index: LeafNodeIndex) -> Option<&Credential> {
+        self.record.member(leaf_index)
+    }
+
+    /// Returns the members of the group in the past epochs before the last
+    /// epoch for which the message secrets are still stored, in ascending
+    /// order of the epochs.
+    pub fn membership_history(&self) -> &[(GroupEpoch, Vec<Member>)] {
+        self.record.membership_history()
+    }
+
+    /// Returns the time at which the group became inactive, if known.
+    pub fn inactive_since(&self) -> Option<SystemTime> {
+        self.inactive_since
+    }
+}
+
+// The record, the group context and the time at which the group became
+// inactive.
+type InactiveGroupParts = (InactiveGroupRecord, GroupContext, Option<SystemTime>);
+
+/// Loads the [`InactiveGroupParts`] of the inactive group with the given
+/// `group_id`.
+fn load_inactive<Storage: StorageReader>(
+    storage: &Storage,
+    group_id: &GroupId,
+) -> Result<Option<InactiveGroupParts>, Storage::Error> {
+    let group_state: Option<MlsGroupState> = storage.group_state(group_id)?;
+    if !matches!(group_state, Some(MlsGroupState::Inactive)) {
+        return Ok(None);
+    }
+    let own_leaf_index: Option<LeafNodeIndex> = storage.own_leaf_index(group_id)?;
+    let (Some(public_group), Some(own_leaf_index)) = (
+        PublicGroup::load_from_reader(storage, group_id)?,
+        own_leaf_index,
+    ) else {
+        return Ok(None);
+    };
+    let message_secrets_store: Option<MessageSecretsStore> = storage.message_secrets(group_id)?;
+
+    let record = InactiveGroupRecord {
+        group_id: group_id.clone(),
+        last_epoch: public_group.group_context().epoch(),
+        own_leaf_index,
+        members: public_group.members().collect(),
+        membership_history: message_secrets_store
+            .as_ref()
+            .map(MessageSecretsStore::past_members)
+            .unwrap_or_default(),
+        has_secrets: message_secrets_store.is_some(),
+    };
+    let inactive_since = message_secrets_store
+        .as_ref()
+        .and_then(MessageSecretsStore::inactive_since);
+    Ok(Some((
+        record,
+        public_group.group_context().clone(),
+        inactive_since,
+    )))
+}
+
+impl MlsGroup {
+    /// Returns a [`ClosedGroupView`] of the group, or `None` if the group is
+    /// not in the [`MlsGroupState::Inactive`] state.
+    pub fn closed_view(&self) -> Option<ClosedGroupView> {
+        self.inactive_record().map(|record| ClosedGroupView {
+            record,
+            group_context: self.context().clone(),
+            inactive_since: self.inactive_since(),
+        })
+    }
+
+    /// Returns the time at which the group became inactive, or `None` if the
+    /// group is still active.
+    pub fn inactive_since(&self) -> Option<SystemTime> {
+        self.message_secrets_store.inactive_since()
+    }
+
+    /// Returns whether the secret material of the group is due for purging
+    /// according to [`MlsGroupJoinConfig::inactive_group_retention()`].
+    pub fn purge_due(&self) -> bool {
+        let (Some(inactive_since), Some(retention)) = (
+            self.inactive_since(),
+            self.mls_group_config.inactive_group_retention,
+        ) else {
+            return false;
+        };
+        inactive_since + retention <= SystemTime::now()
+    }
+
+    /// Returns the read-only record of the group, or `None` if the group is
+    /// still active.
+    pub fn inactive_record(&self) -> Option<InactiveGroupRecord> {
+        matches!(self.group_state, MlsGroupState::Inactive).then(|| InactiveGroupRecord {
+            group_id: self.group_id().clone(),
+            last_epoch: self.epoch(),
+            own_leaf_index: self.own_leaf_index(),
+            members: self.members().collect(),
+            membership_history: self.message_secrets_store.past_members(),
+            has_secrets: true,
+        })
+    }
+
+    /// Deletes the secret material of the inactive group from the `storage`
+    /// and returns the read-only record of the group, which remains in the
+    /// storage.
+    ///
+    /// This consumes the group, because it can't be loaded with
+    /// [`MlsGroup::load()`] anymore. Use [`InactiveGroupRecord::load()`]
+    /// instead. The membership history is deleted with the secret material, so
+    /// applications that need it have to keep the returned record. Returns
+    /// [`PurgeInactiveGroupError::GroupActive`] if the group is still active,
+    /// in which case nothing is deleted.
+    pub fn purge_secrets<Storage: StorageProvider>(
+        mut self,
+        storage: &Storage,
+    ) -> Result<InactiveGroupRecord, PurgeInactiveGroupError<Storage::Error>> {
+        let mut record = self
+            .inactive_record()
+            .ok_or(PurgeInactiveGroupError::GroupActive)?;
+
+        self.delete_secret_material(storage)
+            .map_err(PurgeInactiveGroupError::StorageError)?;
+        self.proposal_store_mut().empty();
+
+        record.has_secrets = false;
+        Ok(record)
+    }
+
+    fn delete_secret_material<Storage: StorageProvider>(
+        &self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        let group_id = self.group_id();
+        storage.delete_group_epoch_secrets(group_id)?;
+        storage.delete_message_secrets(group_id)?;
+        storage.delete_all_resumption_psk_secrets(group_id)?;
+        storage.delete_own_leaf_nodes(group_id)?;
+        storage.clear_proposal_queue::<GroupId, ProposalRef>(group_id)?;
+        storage.delete_encryption_epoch_key_pairs(
+            group_id,
+            &self.epoch(),
+            self.own_leaf_index().u32(),
+        )
+    }
+}
@@ -0,0 +1,149 @@
+//! # Integrity self-checks
+//!
+//! With the `integrity-checks` feature, [`MlsGroup::merge_staged_commit()`]
+//! checks a set of invariants of the group state after every merge and
+//! panics with a serialized [`IntegrityDiagnostic`] if one of them doesn't
+//! hold. The diagnostic contains the group context and the ratchet tree, so
+//! that bugs that corrupt the group state can be reproduced from it.
+//!
+//! The checks are expensive and meant for debug builds and test
+//! environments. Applications that prefer to report violations themselves
+//! can call [`MlsGroup::check_integrity()`] directly.
+
+use openmls_traits::crypto::OpenMlsCrypto;
+use serde::{Deserialize, Serialize};
+
+use super::{errors::CheckIntegrityError, *};
+use crate::treesync::{ParentHashFinding, RatchetTree};
+
+/// An invariant of the group state that doesn't hold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityViolation {
+    /// The tree hash of the ratchet tree doesn't match the one in the group
+    /// context.
+    TreeHashMismatch {
+        /// The tree hash in the group context.
+        expected: Vec<u8>,
+        /// The tree hash computed from the ratchet tree.
+        actual: Vec<u8>,
+    },
+    /// The own leaf is blank.
+    MissingOwnLeaf,
+    /// The signature of the own leaf is invalid.
+    InvalidOwnLeafSignature,
+    /// A parent node has an invalid parent hash or unmerged leaves.
+    ParentHash(ParentHashFinding),
+}
+
+/// The diagnostic bundle produced when an integrity check fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityDiagnostic {
+    own_leaf_index: LeafNodeIndex,
+    violations: Vec<IntegrityViolation>,
+    group_context: GroupContext,
+    ratchet_tree: RatchetTree,
+}
+
+impl IntegrityDiagnostic {
+    /// Returns the violated invariants.
+    pub fn violations(&self) -> &[IntegrityViolation] {
+        &self.violations
+    }
+
+    /// Returns the group context at the time of the check.
+    pub fn group_context(&self) -> &GroupContext {
+        &self.group_context
+    }
+
+    /// Returns the ratchet tree at the time of the check.
+    pub fn ratchet_tree(&self) -> &RatchetTree {
+        &self.ratchet_tree
+    }
+
+    /// Serializes the diagnostic to JSON.
+    pub fn to_json(&self) -> String {
+        // The diagnostic only contains types that serialize to JSON.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl MlsGroup {
+    /// Checks the invariants of the group state:
+    ///
+    /// - the tree hash of the ratchet tree matches the one in the group
+    ///   context,
+    /// - the own leaf is present and correctly signed, and
+    /// - all parent nodes have valid parent hashes and well-formed unmerged
+    ///   leaves.
+    ///
+    /// Returns a [`CheckIntegrityError::Violated`] error with an
+    /// [`IntegrityDiagnostic`] listing all violated invariants if one of them
+    /// doesn't hold.
+    pub fn check_integrity(&self, crypto: &impl OpenMlsCrypto) -> Result<(), CheckIntegrityError> {
+        let public_group = self.public_group();
+        let ciphersuite = self.ciphersuite();
+        let mut violations = Vec::new();
+
+        let tree_hash = public_group
+            .treesync()
+            .empty_diff()
+            .compute_tree_hashes(crypto, ciphersuite)?;
+        if tree_hash != public_group.group_context().tree_hash() {
+            violations.push(IntegrityViolation::TreeHashMismatch {
+                expected: public_group.group_context().tree_hash().to_vec(),
+                actual: tree_hash,
+            });
+        }
+
+        match self.own_leaf_node() {
+            Some(own_leaf) => {
+                if own_leaf
+                    .verify_signature_at(
+                        crypto,
+                        ciphersuite,
+                        self.group_id(),
+                        self.own_leaf_index(),
+                    )
+                    .is_err()
+                {
+                    violations.push(IntegrityViolation::InvalidOwnLeafSignature);
+                }
+            }
+            None => violations.push(IntegrityViolation::MissingOwnLeaf),
+        }
+
+        violations.extend(
+            public_group
+                .verify_parent_hashes(crypto)?
+                .into_iter()
+                .map(IntegrityViolation::ParentHash),
+        );
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(CheckIntegrityError::Violated(Box::new(
+            IntegrityDiagnostic {
+                own_leaf_index: self.own_leaf_index(),
+                violations,
+                group_context: public_group.group_context().clone(),
+                ratchet_tree: self.export_ratchet_tree(),
+            },
+        )))
+    }
+
+    /// Runs [`MlsGroup::check_integrity()`] and panics with the serialized
+    /// diagnostic if it fails.
+    pub(super) fn assert_integrity(&self, crypto: &impl OpenMlsCrypto) {
+        match self.check_integrity(crypto) {
+            Ok(()) => {}
+            Err(CheckIntegrityError::LibraryError(e)) => {
+                panic!("Integrity check of group state failed: {e}")
+            }
+            Err(CheckIntegrityError::Violated(diagnostic)) => panic!(
+                "Integrity check of group state failed: {}",
+                diagnostic.to_json()
+            ),
+        }
+    }
+}
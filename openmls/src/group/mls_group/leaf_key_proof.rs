@@ -0,0 +1,188 @@
+//! # Proofs of possession for leaf signature keys
+//!
+//! External services, like key-transparency logs or compliance registries,
+//! may require a member to prove that it controls the private key of the
+//! signature key in its leaf before they accept the key.
+//! [`MlsGroup::prove_leaf_key_possession()`] creates a [`LeafKeyProof`] that
+//! signs the signature key, the leaf index and a challenge chosen by the
+//! service, bound to the [`GroupContext`] of the current epoch.
+//!
+//! The service verifies the proof with [`LeafKeyProof::verify()`]. Members of
+//! the group can additionally check with [`MlsGroup::verify_leaf_key_proof()`]
+//! that the key is the one in the leaf of the prover in the current epoch.
+
+use openmls_traits::{crypto::OpenMlsCrypto, signatures::Signer, types::Ciphersuite};
+use tls_codec::{
+    Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
+    VLBytes,
+};
+
+use super::{
+    errors::{ProveLeafKeyError, VerifyLeafKeyProofError},
+    *,
+};
+use crate::ciphersuite::{
+    signable::{Signable, SignedStruct},
+    OpenMlsSignaturePublicKey, SignContent, Signature, SignaturePublicKey,
+};
+
+const LEAF_KEY_PROOF_LABEL: &str = "LeafKeyProof";
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub(crate) struct LeafKeyProofTbs {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    group_context_hash: VLBytes,
+    leaf_index: LeafNodeIndex,
+    signature_key: SignaturePublicKey,
+    challenge: VLBytes,
+}
+
+impl Signable for LeafKeyProofTbs {
+    type SignedOutput = LeafKeyProof;
+
+    fn unsigned_payload(&self) -> Result<Vec<u8>, tls_codec::Error> {
+        self.tls_serialize_detached()
+    }
+
+    fn label(&self) -> &str {
+        LEAF_KEY_PROOF_LABEL
+    }
+}
+
+/// A proof that a member controls the private key of the signature key in its
+/// leaf, created with [`MlsGroup::prove_leaf_key_possession()`].
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct LeafKeyProof {
+    tbs: LeafKeyProofTbs,
+    signature: Signature,
+}
+
+impl SignedStruct<LeafKeyProofTbs> for LeafKeyProof {
+    fn from_payload(tbs: LeafKeyProofTbs, signature: Signature) -> Self {
+        Self { tbs, signature }
+    }
+}
+
+impl LeafKeyProof {
+    /// Returns the ID of the group the proof was created in.
+    pub fn group_id(&self) -> &GroupId {
+        &self.tbs.group_id
+    }
+
+    /// Returns the epoch the proof was created in.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.tbs.epoch
+    }
+
+    /// Returns the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.tbs.ciphersuite
+    }
+
+    /// Returns the hash of the [`GroupContext`] of the epoch the proof was
+    /// created in.
+    pub fn group_context_hash(&self) -> &[u8] {
+        self.tbs.group_context_hash.as_slice()
+    }
+
+    /// Returns the leaf index of the prover.
+    pub fn leaf_index(&self) -> LeafNodeIndex {
+        self.tbs.leaf_index
+    }
+
+    /// Returns the signature key the proof is for.
+    pub fn signature_key(&self) -> &SignaturePublicKey {
+        &self.tbs.signature_key
+    }
+
+    /// Returns the challenge chosen by the verifier.
+    pub fn challenge(&self) -> &[u8] {
+        self.tbs.challenge.as_slice()
+    }
+
+    /// Verifies that the proof was signed with the private key of
+    /// [`LeafKeyProof::signature_key()`].
+    ///
+    /// The caller has to check that the challenge is the one it chose.
+    pub fn verify(&self, crypto: &impl OpenMlsCrypto) -> Result<(), VerifyLeafKeyProofError> {
+        let signature_key = OpenMlsSignaturePublicKey::from_signature_key(
+            self.tbs.signature_key.clone(),
+            self.tbs.ciphersuite.signature_algorithm(),
+        );
+        let payload = self
+            .tbs
+            .unsigned_payload()
+            .map_err(LibraryError::missing_bound_check)?;
+        signature_key
+            .verify_with_label(
+                crypto,
+                &self.signature,
+                &SignContent::new(LEAF_KEY_PROOF_LABEL, payload.into()),
+            )
+            .map_err(|_| VerifyLeafKeyProofError::InvalidSignature)
+    }
+}
+
+impl MlsGroup {
+    /// Creates a [`LeafKeyProof`] for the signature key of the own leaf in
+    /// the current epoch, over the `challenge` chosen by the verifier.
+    ///
+    /// The `signer` must hold the signature key of the own leaf.
+    pub fn prove_leaf_key_possession(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signer: &impl Signer,
+        challenge: &[u8],
+    ) -> Result<LeafKeyProof, ProveLeafKeyError> {
+        if !self.is_active() {
+            return Err(MlsGroupStateError::UseAfterEviction.into());
+        }
+        let own_leaf = self
+            .own_leaf()
+            .ok_or_else(|| LibraryError::custom("The own leaf is missing"))?;
+
+        let tbs = LeafKeyProofTbs {
+            group_id: self.group_id().clone(),
+            epoch: self.epoch(),
+            ciphersuite: self.ciphersuite(),
+            group_context_hash: self.state_summary(crypto)?.group_context_hash().into(),
+            leaf_index: self.own_leaf_index(),
+            signature_key: own_leaf.signature_key().clone(),
+            challenge: challenge.into(),
+        };
+        Ok(tbs.sign(signer)?)
+    }
+
+    /// Verifies the `proof` like [`LeafKeyProof::verify()`] and checks that
+    /// it was created in the current epoch of this group for the signature
+    /// key in the leaf of the prover.
+    pub fn verify_leaf_key_proof(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        proof: &LeafKeyProof,
+    ) -> Result<(), VerifyLeafKeyProofError> {
+        if proof.group_id() != self.group_id() || proof.ciphersuite() != self.ciphersuite() {
+            return Err(VerifyLeafKeyProofError::GroupMismatch);
+        }
+        if proof.epoch() != self.epoch()
+            || proof.group_context_hash() != self.state_summary(crypto)?.group_context_hash()
+        {
+            return Err(VerifyLeafKeyProofError::EpochMismatch);
+        }
+        let leaf = self
+            .public_group()
+            .leaf(proof.leaf_index())
+            .ok_or(VerifyLeafKeyProofError::KeyMismatch)?;
+        if leaf.signature_key() != proof.signature_key() {
+            return Err(VerifyLeafKeyProofError::KeyMismatch);
+        }
+
+        proof.verify(crypto)
+    }
+}
@@ -0,0 +1,79 @@
+//! # Per-member state
+//!
+//! [`LeafMap`] holds a value for each member of a group, keyed by leaf index.
+//! Since leaves are reused when a removed member is replaced, every value is
+//! stored together with a key of the member that occupies the leaf, e.g. its
+//! signature key, and is reset when another member takes over the leaf.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Member, MlsGroup};
+use crate::binary_tree::LeafNodeIndex;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry<T> {
+    key: Vec<u8>,
+    value: T,
+}
+
+/// A value for each member of a group that follows the occupants of the
+/// leaves. See the [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct LeafMap<T> {
+    leaves: BTreeMap<LeafNodeIndex, Entry<T>>,
+}
+
+impl<T> Default for LeafMap<T> {
+    fn default() -> Self {
+        Self {
+            leaves: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> LeafMap<T> {
+    /// Updates the map to the current members of the `group`. The values of
+    /// members that left are dropped. Members that weren't in the map, or
+    /// whose `key` changed, get the value returned by `init`.
+    pub(crate) fn sync(
+        &mut self,
+        group: &MlsGroup,
+        key: impl Fn(&Member) -> &[u8],
+        mut init: impl FnMut() -> T,
+    ) {
+        let mut leaves = BTreeMap::new();
+        for member in group.members() {
+            let key = key(&member);
+            let entry = match self.leaves.remove(&member.index) {
+                Some(entry) if entry.key == key => entry,
+                _ => Entry {
+                    key: key.to_vec(),
+                    value: init(),
+                },
+            };
+            leaves.insert(member.index, entry);
+        }
+        self.leaves = leaves;
+    }
+
+    /// Returns the value of the member at `leaf_index`.
+    pub(crate) fn get(&self, leaf_index: LeafNodeIndex) -> Option<&T> {
+        self.leaves.get(&leaf_index).map(|entry| &entry.value)
+    }
+
+    /// Returns the value of the member at `leaf_index` for modification.
+    pub(crate) fn get_mut(&mut self, leaf_index: LeafNodeIndex) -> Option<&mut T> {
+        self.leaves
+            .get_mut(&leaf_index)
+            .map(|entry| &mut entry.value)
+    }
+
+    /// Returns the values of all members in the order of their leaf indices.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (LeafNodeIndex, &T)> {
+        self.leaves
+            .iter()
+            .map(|(leaf_index, entry)| (*leaf_index, &entry.value))
+    }
+}
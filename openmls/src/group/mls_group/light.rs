@@ -0,0 +1,360 @@
+//! # Light clients
+//!
+//! A light client joins a group from a [`Welcome`](crate::messages::Welcome)
+//! without downloading the full ratchet tree. Instead, it gets two
+//! [`TreeSlice`]s from the delivery service or the sender of the Welcome: its own, to check the path secret
+//! and to learn its direct path, and the one of the signer of the
+//! [`GroupInfo`](crate::messages::group_info::GroupInfo), to verify its
+//! signature. Both slices are verified against the tree hash in the group
+//! context, so a malicious delivery service can't make the light client join
+//! a different tree than the other members.
+//!
+//! A [`LightGroup`] is created with
+//! [`ProcessedWelcome::into_light_group()`] and written to the storage. It
+//! knows the group context and the secrets of the epoch it joined, so it can
+//! export secrets, compare the epoch authenticator and decrypt the
+//! application messages of that epoch with
+//! [`LightGroup::decrypt_application_message()`]. The sender is authenticated
+//! with its [`TreeSlice`], like the signer of the group info, and the sender
+//! ratchets follow the [`MlsGroupJoinConfig`] the light client joined with.
+//!
+//! A light client is a read-only member of a single epoch. It can't send
+//! messages, create proposals or commits, and it doesn't process commits:
+//! Processing a commit requires the full tree to apply the proposals and to
+//! compute the new tree hash, which a light client doesn't have. Instead, it
+//! joins every epoch it is interested in anew with a new Welcome from another
+//! member and new slices. Clients that need to follow the group by themselves
+//! have to join with the full ratchet tree as an [`MlsGroup`](super::MlsGroup).
+
+use openmls_traits::{crypto::OpenMlsCrypto, storage::StorageWriter as _};
+use serde::{Deserialize, Serialize};
+use tls_codec::Serialize as _;
+
+use super::{
+    errors::{ExportSecretError, LightGroupDecryptError},
+    ProcessedWelcome,
+};
+use crate::{
+    binary_tree::{array_representation::TreeSize, LeafNodeIndex},
+    ciphersuite::signable::Verifiable,
+    error::LibraryError,
+    framing::{
+        mls_auth_content_in::AuthenticatedContentIn, mls_content::FramedContentBody,
+        ApplicationMessage, ContentType, PrivateMessageIn,
+    },
+    group::{
+        errors::{ValidationError, WelcomeError},
+        GroupContext, GroupEpoch, GroupId, MlsGroupJoinConfig,
+    },
+    schedule::{message_secrets::MessageSecrets, EpochAuthenticator, GroupEpochSecrets},
+    storage::{OpenMlsProvider, StorageProvider, StorageReader},
+    treesync::{
+        errors::{DerivePathError, PublicTreeError},
+        TreeSlice,
+    },
+};
+
+/// The state of a light client in a group. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LightGroup {
+    mls_group_config: MlsGroupJoinConfig,
+    group_context: GroupContext,
+    own_leaf_index: LeafNodeIndex,
+    tree_slice: TreeSlice,
+    group_epoch_secrets: GroupEpochSecrets,
+    message_secrets: MessageSecrets,
+}
+
+impl LightGroup {
+    /// Loads the state of the light client in the group with the given id
+    /// from the storage. Returns `None` if no light group is stored, which is
+    /// always the case if the storage doesn't implement
+    /// [`StorageWriter::write_light_group()`](openmls_traits::storage::StorageWriter::write_light_group).
+    pub fn load<Storage: StorageReader>(
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<Option<LightGroup>, Storage::Error> {
+        storage.light_group(group_id)
+    }
+
+    /// Deletes the state of the light client from the storage.
+    pub fn delete<Storage: StorageProvider>(
+        &self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        storage.delete_light_group(self.group_id())
+    }
+
+    /// Returns the configuration the light client joined with.
+    pub fn configuration(&self) -> &MlsGroupJoinConfig {
+        &self.mls_group_config
+    }
+
+    /// Returns the group ID.
+    pub fn group_id(&self) -> &GroupId {
+        self.group_context.group_id()
+    }
+
+    /// Returns the epoch the light client joined.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.group_context.epoch()
+    }
+
+    /// Returns the group context of the epoch the light client joined.
+    pub fn group_context(&self) -> &GroupContext {
+        &self.group_context
+    }
+
+    /// Returns the index of the own leaf.
+    pub fn own_leaf_index(&self) -> LeafNodeIndex {
+        self.own_leaf_index
+    }
+
+    /// Returns the verified [`TreeSlice`] of the own leaf.
+    pub fn tree_slice(&self) -> &TreeSlice {
+        &self.tree_slice
+    }
+
+    /// Returns the epoch authenticator of the epoch the light client joined.
+    pub fn epoch_authenticator(&self) -> &EpochAuthenticator {
+        self.group_epoch_secrets.epoch_authenticator()
+    }
+
+    /// Exports a secret from the epoch the light client joined.
+    /// Returns [`ExportSecretError::KeyLengthTooLong`] if the requested
+    /// key length is too long.
+    pub fn export_secret(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        label: &str,
+        context: &[u8],
+        key_length: usize,
+    ) -> Result<Vec<u8>, ExportSecretError> {
+        if key_length > u16::MAX.into() {
+            log::error!("Got a key that is larger than u16::MAX");
+            return Err(ExportSecretError::KeyLengthTooLong);
+        }
+
+        Ok(self
+            .group_epoch_secrets
+            .exporter_secret()
+            .derive_exported_secret(
+                self.group_context.ciphersuite(),
+                crypto,
+                label,
+                context,
+                key_length,
+            )
+            .map_err(LibraryError::unexpected_crypto_error)?)
+    }
+
+    /// Decrypts an application message of the epoch the light client joined.
+    /// The `sender_slice` is the [`TreeSlice`] of the sender's leaf, which is
+    /// used to verify the signature of the message.
+    ///
+    /// The secret tree is advanced past the message, so the updated light
+    /// group is written to the storage.
+    ///
+    /// Returns [`LightGroupDecryptError::NotAnApplicationMessage`] for
+    /// handshake messages and [`ValidationError::UnknownMember`] if the
+    /// `sender_slice` isn't the one of the sender.
+    pub fn decrypt_application_message<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: PrivateMessageIn,
+        sender_slice: &TreeSlice,
+    ) -> Result<ApplicationMessage, LightGroupDecryptError<Provider::StorageError>> {
+        let crypto = provider.crypto();
+        let ciphersuite = self.group_context.ciphersuite();
+
+        if message.group_id() != self.group_id() {
+            return Err(ValidationError::WrongGroupId.into());
+        }
+        if message.epoch() != self.epoch() {
+            return Err(ValidationError::WrongEpoch.into());
+        }
+        if message.content_type() != ContentType::Application {
+            return Err(LightGroupDecryptError::NotAnApplicationMessage);
+        }
+
+        let sender_data = message
+            .sender_data(&self.message_secrets, crypto, ciphersuite)
+            .map_err(ValidationError::from)?;
+        let sender_index = sender_data.leaf_index;
+        if sender_index == self.own_leaf_index {
+            return Err(ValidationError::CannotDecryptOwnMessage.into());
+        }
+
+        // Authenticate the sender with its slice, which is verified against
+        // the tree hash like the slices the light client joined with.
+        if sender_slice.leaf_index() != sender_index {
+            return Err(ValidationError::UnknownMember.into());
+        }
+        sender_slice.verify(crypto, ciphersuite, self.group_context.tree_hash())?;
+        let sender_signature_key = sender_slice
+            .leaf()
+            .ok_or(ValidationError::UnknownMember)?
+            .signature_key()
+            .clone()
+            .into_signature_public_key_enriched(ciphersuite.signature_algorithm());
+
+        let verifiable_content = message
+            .to_verifiable_content(
+                ciphersuite,
+                crypto,
+                &mut self.message_secrets,
+                sender_index,
+                self.mls_group_config
+                    .sender_ratchet_configuration_for(sender_index),
+                sender_data,
+            )
+            .map_err(ValidationError::from)?;
+        let content: AuthenticatedContentIn = verifiable_content
+            .verify(crypto, &sender_signature_key)
+            .map_err(|_| ValidationError::InvalidSignature)?;
+        let content = content.validate(
+            ciphersuite,
+            crypto,
+            None,
+            self.group_context.protocol_version(),
+        )?;
+        let FramedContentBody::Application(bytes) = content.content() else {
+            return Err(LibraryError::custom("Expected an application message").into());
+        };
+
+        provider
+            .storage()
+            .write_light_group(self.group_id(), self)
+            .map_err(LightGroupDecryptError::StorageError)?;
+
+        Ok(ApplicationMessage::new(bytes.as_slice().to_vec()))
+    }
+}
+
+impl ProcessedWelcome {
+    /// Consumes the `ProcessedWelcome` and joins the group as a light client
+    /// with the [`TreeSlice`] of the own leaf and the one of the signer of the
+    /// group info, instead of the full ratchet tree. The [`LightGroup`] is
+    /// written to the storage and can be loaded with [`LightGroup::load()`].
+    /// It only supports reading the epoch it joined, see the
+    /// [module documentation](self) for details.
+    ///
+    /// Returns [`WelcomeError::TreeSlice`] if a slice doesn't match the tree
+    /// hash of the group, and [`WelcomeError::UnknownSender`] if the
+    /// `signer_slice` isn't the one of the signer.
+    pub fn into_light_group<Provider: OpenMlsProvider>(
+        mut self,
+        provider: &Provider,
+        own_slice: TreeSlice,
+        signer_slice: TreeSlice,
+    ) -> Result<LightGroup, WelcomeError<Provider::StorageError>> {
+        let crypto = provider.crypto();
+        let ciphersuite = self.ciphersuite;
+
+        // Verify the group info with the signer's leaf from its slice. The
+        // slice itself is verified against the signed tree hash below.
+        let signer_index = self.verifiable_group_info.signer();
+        if signer_slice.leaf_index() != signer_index {
+            return Err(WelcomeError::UnknownSender);
+        }
+        let group_info = {
+            let signer_signature_key = signer_slice
+                .leaf()
+                .ok_or(WelcomeError::UnknownSender)?
+                .signature_key()
+                .clone()
+                .into_signature_public_key_enriched(ciphersuite.signature_algorithm());
+
+            self.verifiable_group_info
+                .clone()
+                .verify(crypto, &signer_signature_key)
+                .map_err(|_| WelcomeError::InvalidGroupInfoSignature)?
+        };
+        let group_context = group_info.group_context().clone();
+
+        if !group_context.protocol_version().is_supported() {
+            return Err(WelcomeError::UnsupportedMlsVersion);
+        }
+
+        signer_slice.verify(crypto, ciphersuite, group_context.tree_hash())?;
+        own_slice.verify(crypto, ciphersuite, group_context.tree_hash())?;
+
+        // The own leaf must be the one of the key package the Welcome was
+        // encrypted to.
+        let own_leaf_index = own_slice.leaf_index();
+        if own_leaf_index == signer_index {
+            return Err(WelcomeError::PublicTreeError(
+                PublicTreeError::MalformedTree,
+            ));
+        }
+        if own_slice.leaf() != Some(self.key_package_bundle.key_package().leaf_node()) {
+            return Err(WelcomeError::PublicTreeError(
+                PublicTreeError::MissingKeyPackage,
+            ));
+        }
+
+        let (group_epoch_secrets, message_secrets) = {
+            let serialized_group_context = group_context
+                .tls_serialize_detached()
+                .map_err(LibraryError::missing_bound_check)?;
+
+            self.key_schedule
+                .add_context(crypto, &serialized_group_context)
+                .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+
+            let epoch_secrets = self
+                .key_schedule
+                .epoch_secrets(crypto, ciphersuite)
+                .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+
+            epoch_secrets.split_secrets(
+                serialized_group_context,
+                TreeSize::from_leaf_count(own_slice.leaf_count()),
+                own_leaf_index,
+            )
+        };
+
+        // https://validation.openmls.tech/#valn1410
+        let confirmation_tag = message_secrets
+            .confirmation_key()
+            .tag(
+                crypto,
+                ciphersuite,
+                group_context.confirmed_transcript_hash(),
+            )
+            .map_err(LibraryError::unexpected_crypto_error)?;
+        if &confirmation_tag != group_info.confirmation_tag() {
+            log::error!("Confirmation tag mismatch");
+            return Err(WelcomeError::ConfirmationTagMismatch);
+        }
+
+        // A light client doesn't keep the key pairs of its direct path, but
+        // the path secret must still match the public keys in the slice.
+        if let Some(path_secret) = self.group_secrets.path_secret {
+            own_slice
+                .derive_path_secrets(crypto, ciphersuite, path_secret, signer_index)
+                .map_err(|e| match e {
+                    DerivePathError::LibraryError(e) => e.into(),
+                    DerivePathError::PublicKeyMismatch => {
+                        WelcomeError::PublicTreeError(PublicTreeError::PublicKeyMismatch)
+                    }
+                })?;
+        }
+
+        let light_group = LightGroup {
+            mls_group_config: self.mls_group_config,
+            group_context,
+            own_leaf_index,
+            tree_slice: own_slice,
+            group_epoch_secrets,
+            message_secrets,
+        };
+        provider
+            .storage()
+            .write_light_group(light_group.group_id(), &light_group)
+            .map_err(WelcomeError::StorageError)?;
+
+        Ok(light_group)
+    }
+}
@@ -3,11 +3,11 @@
 //! This module contains membership-related operations and exposes [`RemoveOperation`].
 
 use errors::EmptyInputError;
-use openmls_traits::{signatures::Signer, storage::StorageProvider as _};
+use openmls_traits::{signatures::Signer, storage::StorageWriter as _};
 use proposal_store::QueuedRemoveProposal;
 
 use super::{
-    errors::{AddMembersError, LeaveGroupError, RemoveMembersError},
+    errors::{AddMembersError, LeaveGroupError, RemoveMembersError, SwapMemberError},
     *,
 };
 use crate::{
@@ -171,18 +171,82 @@ impl MlsGroup {
         Ok((commit, welcome, group_info))
     }
 
+    /// Replaces the member at `old_leaf` with the member of the
+    /// `new_key_package` in a single commit, e.g. when a user replaces a
+    /// device.
+    ///
+    /// The commit contains a Remove proposal for `old_leaf` and an Add
+    /// proposal for the `new_key_package`. Since removals are applied before
+    /// additions, the new member can't conflict with the keys of the old one
+    /// and usually takes over its leaf.
+    ///
+    /// If successful, it returns a triple of [`MlsMessageOut`]s, where the
+    /// first contains the commit, the second one the [`Welcome`] and the third
+    /// an optional [GroupInfo] that will be [Some] if the group has the
+    /// `use_ratchet_tree_extension` flag set.
+    ///
+    /// Returns an error if there is a pending commit, if `old_leaf` is not a
+    /// member or if it is the own leaf.
+    ///
+    /// [`Welcome`]: crate::messages::Welcome
+    #[allow(clippy::type_complexity)]
+    pub fn swap_member<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        old_leaf: LeafNodeIndex,
+        new_key_package: KeyPackage,
+    ) -> Result<
+        (MlsMessageOut, MlsMessageOut, Option<GroupInfo>),
+        SwapMemberError<Provider::StorageError>,
+    > {
+        self.is_operational()?;
+
+        if old_leaf == self.own_leaf_index() {
+            return Err(SwapMemberError::CannotSwapSelf);
+        }
+        if self.public_group().leaf(old_leaf).is_none() {
+            return Err(SwapMemberError::UnknownMember);
+        }
+
+        let bundle = self
+            .commit_builder()
+            .propose_removals([old_leaf])
+            .propose_adds([new_key_package])
+            .load_psks(provider.storage())?
+            .build(provider.rand(), provider.crypto(), signer, |_| true)?
+            .stage_commit(provider)?;
+
+        let welcome: MlsMessageOut = bundle.to_welcome_msg().ok_or(LibraryError::custom(
+            "No secrets to generate commit message.",
+        ))?;
+        let (commit, _, group_info) = bundle.into_contents();
+
+        self.reset_aad();
+
+        Ok((commit, welcome, group_info))
+    }
+
     /// Leave the group.
     ///
     /// Creates a Remove Proposal that needs to be covered by a Commit from a different member.
     /// The Remove Proposal is returned as a [`MlsMessageOut`].
     ///
+    /// Afterwards, the group is in the [`MlsGroupState::PendingSelfRemoval`]
+    /// state until a commit that removes this client is merged: No more
+    /// proposals or commits can be created, but incoming messages are still
+    /// processed. If a commit doesn't cover the Remove Proposal, this function
+    /// can be called again to re-send it.
+    ///
     /// Returns an error if there is a pending commit.
     pub fn leave_group<Provider: OpenMlsProvider>(
         &mut self,
         provider: &Provider,
         signer: &impl Signer,
     ) -> Result<MlsMessageOut, LeaveGroupError<Provider::StorageError>> {
-        self.is_operational()?;
+        if !self.is_pending_self_removal() {
+            self.is_operational()?;
+        }
 
         let removed = self.own_leaf_index();
         let remove_proposal = self
@@ -207,6 +271,12 @@ impl MlsGroup {
 
         self.proposal_store_mut().add(queued_remove_proposal);
 
+        self.group_state = MlsGroupState::PendingSelfRemoval;
+        provider
+            .storage()
+            .write_group_state(self.group_id(), &self.group_state)
+            .map_err(LeaveGroupError::StorageError)?;
+
         self.reset_aad();
         Ok(self.content_to_mls_message(remove_proposal, provider)?)
     }
@@ -225,6 +295,19 @@ impl MlsGroup {
             .map(|leaf| leaf.credential())
     }
 
+    /// Returns the [`MemberId`] of the member at the given leaf index. Returns
+    /// `None` if the member can not be found in this group.
+    pub fn member_id(&self, leaf_index: LeafNodeIndex) -> Option<MemberId> {
+        self.public_group().member_id(leaf_index)
+    }
+
+    /// Returns the current leaf index of the member with the given
+    /// [`MemberId`]. Returns `None` if the member can not be found in this
+    /// group.
+    pub fn member_index(&self, member_id: &MemberId) -> Option<LeafNodeIndex> {
+        self.public_group().member_index(member_id)
+    }
+
     /// Returns the [`Member`] corresponding to the given
     /// leaf index. Returns `None` if the member can not be found in this group.
     pub fn member_at(&self, leaf_index: LeafNodeIndex) -> Option<Member> {
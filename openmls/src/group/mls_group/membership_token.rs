@@ -0,0 +1,242 @@
+//! # Membership tokens
+//!
+//! A [`MembershipToken`] asserts that the member with a given credential is
+//! member X of group G at epoch N. It can be presented to application
+//! servers, e.g. to authorize access to group resources, without sending an
+//! MLS message.
+//!
+//! The token contains a tag derived from the exporter secret of the epoch and
+//! is signed with the signature key in the leaf of the member by
+//! [`MlsGroup::membership_token()`]. Every member of the group in the same
+//! epoch can verify the token with [`MlsGroup::verify_membership_token()`].
+//! While all members can derive the tag of every other member, only the
+//! member itself can sign the token, so members can't create tokens in the
+//! name of others. Parties outside of the group can check the signature, but
+//! not the tag.
+
+use openmls_traits::signatures::Signer;
+use serde::{Deserialize, Serialize};
+use tls_codec::{
+    Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
+    VLBytes,
+};
+
+use super::{
+    errors::{CreateMembershipTokenError, ExportSecretError, VerifyMembershipTokenError},
+    *,
+};
+use crate::ciphersuite::{
+    signable::{Signable, SignedStruct},
+    OpenMlsSignaturePublicKey, Secret, SignContent, Signature, SignaturePublicKey,
+};
+
+const MEMBERSHIP_TOKEN_LABEL: &str = "membership token";
+const MEMBERSHIP_TOKEN_SIGNATURE_LABEL: &str = "MembershipToken";
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TlsSerialize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
+    TlsSize,
+)]
+pub(crate) struct MembershipTokenTbs {
+    pub(crate) group_id: GroupId,
+    pub(crate) epoch: GroupEpoch,
+    pub(crate) member: LeafNodeIndex,
+    pub(crate) credential: Credential,
+    pub(crate) signature_key: SignaturePublicKey,
+    pub(crate) tag: VLBytes,
+}
+
+impl Signable for MembershipTokenTbs {
+    type SignedOutput = MembershipToken;
+
+    fn unsigned_payload(&self) -> Result<Vec<u8>, tls_codec::Error> {
+        self.tls_serialize_detached()
+    }
+
+    fn label(&self) -> &str {
+        MEMBERSHIP_TOKEN_SIGNATURE_LABEL
+    }
+}
+
+/// A token that binds the credential and the signature key of a member to an
+/// epoch of the group, created with [`MlsGroup::membership_token()`] and
+/// verified with [`MlsGroup::verify_membership_token()`].
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TlsSerialize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
+    TlsSize,
+)]
+pub struct MembershipToken {
+    tbs: MembershipTokenTbs,
+    signature: Signature,
+}
+
+impl SignedStruct<MembershipTokenTbs> for MembershipToken {
+    fn from_payload(tbs: MembershipTokenTbs, signature: Signature) -> Self {
+        Self { tbs, signature }
+    }
+}
+
+// The exporter context of a membership token.
+#[derive(TlsSerialize, TlsSize)]
+struct MembershipTokenContext<'a> {
+    group_id: &'a GroupId,
+    epoch: GroupEpoch,
+    member: LeafNodeIndex,
+    credential: &'a Credential,
+    signature_key: &'a SignaturePublicKey,
+}
+
+impl MembershipToken {
+    /// Returns the ID of the group the token was created in.
+    pub fn group_id(&self) -> &GroupId {
+        &self.tbs.group_id
+    }
+
+    /// Returns the epoch the token was created in.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.tbs.epoch
+    }
+
+    /// Returns the leaf index of the member.
+    pub fn member(&self) -> LeafNodeIndex {
+        self.tbs.member
+    }
+
+    /// Returns the credential of the member. It must only be used after the
+    /// token was verified with [`MlsGroup::verify_membership_token()`].
+    pub fn credential(&self) -> &Credential {
+        &self.tbs.credential
+    }
+
+    /// Returns the signature key of the member the token is signed with.
+    pub fn signature_key(&self) -> &SignaturePublicKey {
+        &self.tbs.signature_key
+    }
+
+    #[cfg(test)]
+    pub(crate) fn tbs(&self) -> &MembershipTokenTbs {
+        &self.tbs
+    }
+}
+
+impl MlsGroup {
+    /// Creates a [`MembershipToken`] for the own credential in the current
+    /// epoch.
+    ///
+    /// The `signer` must hold the signature key of the own leaf.
+    pub fn membership_token<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        signer: &impl Signer,
+    ) -> Result<MembershipToken, CreateMembershipTokenError> {
+        let own_leaf = self
+            .own_leaf()
+            .ok_or_else(|| LibraryError::custom("The own leaf is missing"))?;
+        let credential = own_leaf.credential().clone();
+        let signature_key = own_leaf.signature_key().clone();
+        let member = self.own_leaf_index();
+        let tag = self.membership_token_tag(provider, member, &credential, &signature_key)?;
+
+        let tbs = MembershipTokenTbs {
+            group_id: self.group_id().clone(),
+            epoch: self.epoch(),
+            member,
+            credential,
+            signature_key,
+            tag: tag.into(),
+        };
+        Ok(tbs.sign(signer)?)
+    }
+
+    /// Verifies that the `token` was created in the current epoch of this
+    /// group for the credential and the signature key in the leaf of the
+    /// member, and that it was signed with that signature key.
+    pub fn verify_membership_token<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        token: &MembershipToken,
+    ) -> Result<(), VerifyMembershipTokenError> {
+        if token.group_id() != self.group_id() {
+            return Err(VerifyMembershipTokenError::GroupMismatch);
+        }
+        if token.epoch() != self.epoch() {
+            return Err(VerifyMembershipTokenError::EpochMismatch);
+        }
+        let leaf = self
+            .public_group()
+            .leaf(token.member())
+            .ok_or(VerifyMembershipTokenError::UnknownMember)?;
+        if leaf.credential() != token.credential() {
+            return Err(VerifyMembershipTokenError::CredentialMismatch);
+        }
+        if leaf.signature_key() != token.signature_key() {
+            return Err(VerifyMembershipTokenError::KeyMismatch);
+        }
+
+        let tag = self.membership_token_tag(
+            provider,
+            token.member(),
+            token.credential(),
+            token.signature_key(),
+        )?;
+        if Secret::from_slice(&tag) != Secret::from_slice(token.tbs.tag.as_slice()) {
+            return Err(VerifyMembershipTokenError::InvalidTag);
+        }
+
+        let signature_key = OpenMlsSignaturePublicKey::from_signature_key(
+            token.signature_key().clone(),
+            self.ciphersuite().signature_algorithm(),
+        );
+        let payload = token
+            .tbs
+            .unsigned_payload()
+            .map_err(LibraryError::missing_bound_check)?;
+        signature_key
+            .verify_with_label(
+                provider.crypto(),
+                &token.signature,
+                &SignContent::new(MEMBERSHIP_TOKEN_SIGNATURE_LABEL, payload.into()),
+            )
+            .map_err(|_| VerifyMembershipTokenError::InvalidSignature)
+    }
+
+    fn membership_token_tag<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        member: LeafNodeIndex,
+        credential: &Credential,
+        signature_key: &SignaturePublicKey,
+    ) -> Result<Vec<u8>, ExportSecretError> {
+        let context = MembershipTokenContext {
+            group_id: self.group_id(),
+            epoch: self.epoch(),
+            member,
+            credential,
+            signature_key,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+        self.export_secret(
+            provider,
+            MEMBERSHIP_TOKEN_LABEL,
+            &context,
+            self.ciphersuite().hash_length(),
+        )
+    }
+}
@@ -4,10 +4,14 @@
 //!
 
 use create_commit::CreateCommitParams;
+use epoch_hooks::EpochHooks;
 use past_secrets::MessageSecretsStore;
 use proposal_store::ProposalQueue;
 use serde::{Deserialize, Serialize};
 use staged_commit::{MemberStagedCommitState, StagedCommitState};
+use stats::MemberStatsCache;
+use std::time::Duration;
+use timing::LastOperationTiming;
 use tls_codec::Serialize as _;
 
 #[cfg(test)]
@@ -24,51 +28,87 @@ use crate::{
     error::LibraryError,
     framing::{mls_auth_content::AuthenticatedContent, *},
     group::{
+        AddCheckReport, AppVersionError, AppVersionNegotiation, CapabilityReport,
         CreateCommitError, CreateGroupContextExtProposalError, Extension, ExtensionType,
-        Extensions, ExternalPubExtension, GroupContext, GroupEpoch, GroupId, MlsGroupJoinConfig,
-        MlsGroupStateError, OutgoingWireFormatPolicy, ProposalQueueError, PublicGroup,
-        RatchetTreeExtension, RequiredCapabilitiesExtension, StagedCommit,
+        Extensions, ExternalPubExtension, GroupContext, GroupEpoch, GroupId, GroupStateSummary,
+        MlsGroupJoinConfig, MlsGroupStateError, OutgoingWireFormatPolicy, ProposalQueueError,
+        PublicGroup, RatchetTreeExtension, RequiredCapabilitiesExtension, StagedCommit,
+        TreePreview,
     },
-    key_packages::KeyPackageBundle,
+    key_packages::{KeyPackage, KeyPackageBundle},
     messages::{
         group_info::{GroupInfo, GroupInfoTBS, VerifiableGroupInfo},
         proposals::*,
-        Commit, GroupSecrets, Welcome,
+        Commit, ConfirmationTag, GroupSecrets, Welcome,
     },
     schedule::{
         message_secrets::MessageSecrets,
-        psk::{load_psks, store::ResumptionPskStore, PskSecret},
+        psk::{load_psks, store::ResumptionPskStore, PreSharedKeyId, PskSecret},
         GroupEpochSecrets, JoinerSecret, KeySchedule,
     },
     storage::{OpenMlsProvider, StorageProvider},
+    tree::{
+        secret_tree::SecretType,
+        sender_ratchet::{SenderRatchetConfiguration, SenderRatchetState},
+    },
     treesync::{
         node::{encryption_keys::EncryptionKeyPair, leaf_node::LeafNode},
         RatchetTree,
     },
     versions::ProtocolVersion,
 };
-use openmls_traits::{signatures::Signer, storage::StorageProvider as _, types::Ciphersuite};
+use openmls_traits::{
+    crypto::OpenMlsCrypto, signatures::Signer, storage::StorageWriter as _, types::Ciphersuite,
+};
 
 // Private
 mod application;
 mod builder;
 mod creation;
 mod exporting;
+mod reinit;
 mod updates;
 
 use config::*;
 
 // Crate
+pub(crate) mod activity;
+pub(crate) mod attachment;
+pub(crate) mod chunking;
 pub(crate) mod commit_builder;
+pub(crate) mod commit_simulation;
 pub(crate) mod config;
+pub(crate) mod conflict;
 pub(crate) mod create_commit;
+pub(crate) mod devices;
+pub(crate) mod epoch_hooks;
 pub(crate) mod errors;
+#[cfg(feature = "compliance-escrow")]
+pub(crate) mod escrow;
+pub(crate) mod exported_secrets;
+pub(crate) mod inactive;
+#[cfg(feature = "integrity-checks")]
+pub(crate) mod integrity;
+pub(crate) mod leaf_key_proof;
+pub(crate) mod leaf_map;
+pub(crate) mod light;
 pub(crate) mod membership;
+pub(crate) mod membership_token;
 pub(crate) mod past_secrets;
 pub(crate) mod processing;
 pub(crate) mod proposal;
+pub(crate) mod proposal_check;
 pub(crate) mod proposal_store;
+#[cfg(feature = "resumption-psk-backup")]
+pub(crate) mod psk_backup;
+#[cfg(feature = "replica-sync")]
+pub(crate) mod replica;
+pub(crate) mod signed_app_data;
 pub(crate) mod staged_commit;
+pub(crate) mod stats;
+pub(crate) mod timing;
+pub(crate) mod update_policy;
+pub(crate) mod validation_artifact;
 
 // Tests
 #[cfg(test)]
@@ -110,6 +150,36 @@ impl Member {
             credential,
         }
     }
+
+    /// Returns the [`MemberId`] of this member.
+    pub fn id(&self) -> MemberId {
+        MemberId::from_signature_key(self.signature_key.clone())
+    }
+}
+
+/// A stable identifier of a group member.
+///
+/// While the leaf index of a member does not change as long as the member is
+/// part of the group, the leaf indices of removed members are reused for new
+/// members. A [`MemberId`] is derived from the member's signature key, which is
+/// unique within the group, and can thus be used to attribute messages across
+/// epochs without mistaking a new member for a removed one.
+///
+/// Note that the [`MemberId`] of a member changes if the member changes its
+/// signature key, e.g. as part of an update.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MemberId(Vec<u8>);
+
+impl MemberId {
+    /// Create the [`MemberId`] of the member with the given signature key.
+    pub fn from_signature_key(signature_key: impl Into<Vec<u8>>) -> Self {
+        Self(signature_key.into())
+    }
+
+    /// Returns the bytes of this [`MemberId`].
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
 }
 
 /// Pending Commit state. Differentiates between Commits issued by group members
@@ -153,8 +223,17 @@ impl From<PendingCommitState> for StagedCommit {
 ///   [`MlsGroup::join_by_external_commit()`], see the functions documentation for
 ///   more information). From this `Operational`, the group state can either
 ///   transition to [`MlsGroupState::Inactive`], when it processes a commit that
-///   removes this client from the group, or to [`MlsGroupState::PendingCommit`],
-///   when this client creates a commit.
+///   removes this client from the group, to [`MlsGroupState::PendingCommit`],
+///   when this client creates a commit, or to
+///   [`MlsGroupState::PendingSelfRemoval`], when this client leaves the group.
+///
+/// * [`MlsGroupState::PendingSelfRemoval`]: A group enters this state when the
+///   client calls [`MlsGroup::leave_group()`]. In this state, the client can't
+///   create proposals or commits anymore, but it keeps processing incoming
+///   messages. When it merges a commit that removes it from the group, the
+///   group transitions to [`MlsGroupState::Inactive`]. If a commit doesn't
+///   cover the removal, the group stays in this state and the client can call
+///   [`MlsGroup::leave_group()`] again to re-send its Remove proposal.
 ///
 /// * [`MlsGroupState::Inactive`]: A group can enter this state from any other
 ///   state when it processes a commit that removes this client from the group.
@@ -162,6 +241,21 @@ impl From<PendingCommitState> for StagedCommit {
 ///   wants to re-join the group, it can either be added by a group member or it
 ///   can join via external commit.
 ///
+/// * [`MlsGroupState::RemovalGracePeriod`]: Instead of
+///   [`MlsGroupState::Inactive`], a group enters this state when it merges a
+///   commit that removes this client and
+///   [`MlsGroupJoinConfig::removal_grace_period()`] is enabled. The group keeps
+///   the state of the final epoch before the removal, so that application
+///   messages that were sent in that epoch can still be processed. All other
+///   messages are rejected. The group transitions to
+///   [`MlsGroupState::Inactive`] with [`MlsGroup::end_removal_grace_period()`].
+///
+/// * [`MlsGroupState::ReInitialized`]: A group enters this state when it merges
+///   a commit that contains a ReInit proposal. This is a terminal state in which
+///   no messages can be created anymore. Instead, the group is succeeded by a
+///   new group with the parameters of the ReInit proposal, see
+///   [`MlsGroup::reinit()`] for more information.
+///
 /// * [`MlsGroupState::PendingCommit`]: This state is split into two possible
 ///   sub-states, one for each Commit type:
 ///   [`PendingCommitState::Member`] and [`PendingCommitState::External`]:
@@ -196,6 +290,35 @@ pub enum MlsGroupState {
     Operational,
     /// The group is inactive because the member has been removed.
     Inactive,
+    /// The group was re-initialized with the contained ReInit proposal and is
+    /// succeeded by a new group.
+    ReInitialized(ReInitProposal),
+    /// The own client asked to leave the group and waits for a commit that
+    /// removes it.
+    PendingSelfRemoval,
+    /// The own client was removed from the group, but application messages of
+    /// the final epoch can still be processed.
+    RemovalGracePeriod,
+}
+
+/// The KEM outputs of the ExternalInit proposals of the external commits that
+/// were staged in the current epoch.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UsedExternalInits(Vec<Vec<u8>>);
+
+impl UsedExternalInits {
+    /// Returns `true` if an external commit with the given KEM output was
+    /// staged in the current epoch.
+    pub(crate) fn contains(&self, kem_output: &[u8]) -> bool {
+        self.0.iter().any(|used| used.as_slice() == kem_output)
+    }
+
+    /// Records the KEM output of a staged external commit.
+    pub(crate) fn insert(&mut self, kem_output: &[u8]) {
+        if !self.contains(kem_output) {
+            self.0.push(kem_output.to_vec());
+        }
+    }
 }
 
 /// A `MlsGroup` represents an MLS group with a high-level API. The API exposes
@@ -222,6 +345,18 @@ pub enum MlsGroupState {
 /// An `MlsGroup` has an internal state variable determining if it is active or
 /// inactive, as well as if it has a pending commit. See [`MlsGroupState`] for
 /// more information.
+///
+/// ## Concurrency
+///
+/// An `MlsGroup` owns all of its state and doesn't hold on to the provider
+/// between calls. All IO goes through the provider that is passed to each
+/// method, so the group is `Send` and `Sync` and can be held across await
+/// points and moved between threads. The same holds for the values produced
+/// while processing messages, such as [`StagedCommit`],
+/// [`ProcessedMessage`](crate::prelude::ProcessedMessage), [`StagedWelcome`]
+/// and [`ProcessedWelcome`]. Methods that change the group
+/// take `&mut self`, so concurrent access to a single group has to be
+/// serialized by the application, e.g. with a mutex.
 #[derive(Debug)]
 #[cfg_attr(feature = "test-utils", derive(Clone, PartialEq))]
 pub struct MlsGroup {
@@ -250,6 +385,21 @@ pub struct MlsGroup {
     // is ephemeral and will be reset by every API call that successfully
     // returns an [`MlsMessageOut`].
     aad: Vec<u8>,
+    // The KEM outputs of the ExternalInit proposals of external commits that
+    // were staged in the current epoch. External commits that reuse one of
+    // them are rejected. They are cleared on every epoch change, at which
+    // point the external key pair is rotated as well.
+    used_external_inits: UsedExternalInits,
+    // The member statistics, if they are collected. They are written to the
+    // storage in batches, see [`MlsGroup::flush_member_stats()`].
+    member_stats: MemberStatsCache,
+    // The timing of the last processed message or created commit. This is
+    // ephemeral and only recorded with the `operation-timing` feature.
+    #[cfg_attr(not(feature = "operation-timing"), allow(dead_code))]
+    last_operation_timing: LastOperationTiming,
+    // The hooks that are run whenever the group enters a new epoch. They are
+    // ephemeral and have to be set again after loading the group.
+    epoch_hooks: EpochHooks,
     // A variable that indicates the state of the group. See [`MlsGroupState`]
     // for more information.
     group_state: MlsGroupState,
@@ -270,7 +420,163 @@ impl MlsGroup {
         mls_group_config: &MlsGroupJoinConfig,
     ) -> Result<(), Storage::Error> {
         self.mls_group_config = mls_group_config.clone();
-        storage.write_mls_join_config(self.group_id(), mls_group_config)
+        self.resize_message_secrets_store(mls_group_config.max_past_epochs);
+        self.set_max_past_epoch_age(mls_group_config.max_past_epoch_age);
+        self.resumption_psk_store
+            .resize(mls_group_config.number_of_resumption_psks);
+        storage.write_mls_join_config(self.group_id(), mls_group_config)?;
+        storage.write_message_secrets(self.group_id(), &self.message_secrets_store)?;
+        storage.write_resumption_psk_store(self.group_id(), &self.resumption_psk_store)
+    }
+
+    /// Sets the number of resumption PSKs the group keeps, see
+    /// [`MlsGroupJoinConfig::number_of_resumption_psks()`].
+    ///
+    /// If the group currently keeps more resumption PSKs, the ones of the
+    /// oldest epochs are deleted. A larger number allows resumptions, e.g.
+    /// re-initializations or branches, from older epochs at the cost of more
+    /// storage, see [`MlsGroup::resumption_psk_store_size()`].
+    pub fn set_number_of_resumption_psks<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        number_of_resumption_psks: usize,
+    ) -> Result<(), Storage::Error> {
+        self.mls_group_config.number_of_resumption_psks = number_of_resumption_psks;
+        self.resumption_psk_store.resize(number_of_resumption_psks);
+        storage.write_mls_join_config(self.group_id(), &self.mls_group_config)?;
+        storage.write_resumption_psk_store(self.group_id(), &self.resumption_psk_store)
+    }
+
+    /// Sets the [`WireFormatPolicy`] of the group, see
+    /// [`MlsGroupJoinConfig::wire_format_policy()`]. The policy applies to
+    /// all messages that are created or processed afterwards.
+    pub fn set_wire_format_policy<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        wire_format_policy: WireFormatPolicy,
+    ) -> Result<(), Storage::Error> {
+        self.mls_group_config.wire_format_policy = wire_format_policy;
+        storage.write_mls_join_config(self.group_id(), &self.mls_group_config)
+    }
+
+    /// Sets the size of the padding of private messages created by the group,
+    /// see [`MlsGroupJoinConfig::padding_size()`].
+    pub fn set_padding_size<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        padding_size: usize,
+    ) -> Result<(), Storage::Error> {
+        self.mls_group_config.padding_size = padding_size;
+        storage.write_mls_join_config(self.group_id(), &self.mls_group_config)
+    }
+
+    /// Sets the maximum number of past epochs for which application messages
+    /// can be decrypted, see [`MlsGroupJoinConfig::max_past_epochs()`].
+    ///
+    /// If the group currently keeps the message secrets of more past epochs,
+    /// the ones of the oldest epochs are deleted.
+    pub fn set_max_past_epochs<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        max_past_epochs: usize,
+    ) -> Result<(), Storage::Error> {
+        self.mls_group_config.max_past_epochs = max_past_epochs;
+        self.resize_message_secrets_store(max_past_epochs);
+        storage.write_mls_join_config(self.group_id(), &self.mls_group_config)?;
+        storage.write_message_secrets(self.group_id(), &self.message_secrets_store)
+    }
+
+    /// Sets the default [`SenderRatchetConfiguration`] of the group, e.g. to
+    /// change the out-of-order tolerance. Overrides set with
+    /// [`MlsGroup::set_sender_ratchet_override()`] take precedence.
+    pub fn set_sender_ratchet_configuration<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        sender_ratchet_configuration: SenderRatchetConfiguration,
+    ) -> Result<(), Storage::Error> {
+        self.mls_group_config.sender_ratchet_configuration = sender_ratchet_configuration;
+        storage.write_mls_join_config(self.group_id(), &self.mls_group_config)
+    }
+
+    /// Returns the epochs for which the group keeps a resumption PSK, in
+    /// ascending order.
+    pub fn resumption_psk_epochs(&self) -> Vec<GroupEpoch> {
+        self.resumption_psk_store.epochs()
+    }
+
+    /// Returns the size in bytes of the resumption PSKs kept by the group,
+    /// including their epochs. Use
+    /// [`MlsGroup::set_number_of_resumption_psks()`] to limit it.
+    pub fn resumption_psk_store_size(&self) -> usize {
+        self.resumption_psk_store.size()
+    }
+
+    /// Overrides the [`SenderRatchetConfiguration`] for the member at
+    /// `leaf_index`, e.g. to tolerate more out-of-order messages from a member
+    /// with an unreliable connection, while keeping a strict configuration for
+    /// all other members.
+    ///
+    /// The override applies until it is removed using
+    /// [`MlsGroup::remove_sender_ratchet_override()`] or until the member is
+    /// removed from the group.
+    pub fn set_sender_ratchet_override<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        leaf_index: LeafNodeIndex,
+        sender_ratchet_configuration: SenderRatchetConfiguration,
+    ) -> Result<(), Storage::Error> {
+        self.mls_group_config
+            .sender_ratchet_overrides
+            .insert(leaf_index, sender_ratchet_configuration);
+        storage.write_mls_join_config(self.group_id(), &self.mls_group_config)
+    }
+
+    /// Removes the [`SenderRatchetConfiguration`] override for the member at
+    /// `leaf_index`, such that the default configuration applies again.
+    pub fn remove_sender_ratchet_override<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        leaf_index: LeafNodeIndex,
+    ) -> Result<(), Storage::Error> {
+        if self
+            .mls_group_config
+            .sender_ratchet_overrides
+            .remove(&leaf_index)
+            .is_some()
+        {
+            storage.write_mls_join_config(self.group_id(), &self.mls_group_config)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the message secrets of all past epochs, e.g. once all
+    /// application messages of past epochs have been received.
+    ///
+    /// Keeping the message secrets of past epochs (see
+    /// [`MlsGroupJoinConfig::max_past_epochs()`]) weakens forward secrecy.
+    /// This function allows to delete them as early as possible. Application
+    /// messages of past epochs can't be decrypted afterwards.
+    pub fn delete_past_epoch_secrets<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        self.message_secrets_store.clear_past_epochs();
+        storage.write_message_secrets(self.group_id(), &self.message_secrets_store)
+    }
+
+    /// Deletes the message secrets of past epochs that are older than
+    /// [`MlsGroupJoinConfig::max_past_epoch_age()`].
+    ///
+    /// Expired secrets are never used to decrypt messages, but they are only
+    /// deleted from the storage when the group's epoch changes or when this
+    /// function is called. Applications should call it periodically if the
+    /// group is idle.
+    pub fn delete_expired_past_epoch_secrets<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        self.message_secrets_store.prune_expired();
+        storage.write_message_secrets(self.group_id(), &self.message_secrets_store)
     }
 
     /// Sets the additional authenticated data (AAD) for the next outgoing
@@ -293,10 +599,115 @@ impl MlsGroup {
         self.public_group.ciphersuite()
     }
 
+    /// Computes a [`GroupStateSummary`] of the current state of the group,
+    /// which can be compared with the summary of another client to detect
+    /// whether the two clients' states diverged.
+    pub fn state_summary(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<GroupStateSummary, LibraryError> {
+        self.public_group.state_summary(crypto)
+    }
+
+    /// Checks whether the `key_packages` could be added to the group and
+    /// reports, for each candidate that can't be added, which capabilities it
+    /// is missing. This allows explaining failures before a commit is
+    /// attempted.
+    pub fn capability_report(&self, key_packages: &[KeyPackage]) -> CapabilityReport {
+        self.public_group.capability_report(key_packages)
+    }
+
+    /// Runs the validation an Add commit would perform on each of the
+    /// `key_packages`, i.e. it checks their signatures and lifetimes, their
+    /// capabilities against the group's requirements and whether their
+    /// identities or keys are already used in the group. The group isn't
+    /// modified, so this can be used to vet invitees before creating a
+    /// commit.
+    ///
+    /// Returns one [`AddCheckReport`] per key package, in the same order.
+    pub fn can_add(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        key_packages: &[KeyPackage],
+    ) -> Vec<AddCheckReport> {
+        self.public_group.can_add(crypto, key_packages)
+    }
+
+    /// Returns the application protocol versions supported by all members of
+    /// the group in ascending order. See [`AppVersionNegotiation`].
+    pub fn common_app_versions(
+        &self,
+        negotiation: &AppVersionNegotiation,
+    ) -> Result<Vec<u16>, AppVersionError> {
+        negotiation.common_versions(&self.public_group)
+    }
+
+    /// Returns the group context extensions that upgrade the group to the
+    /// highest application protocol version supported by all members, or
+    /// `None` if no upgrade is possible. See
+    /// [`AppVersionNegotiation::upgrade_extensions()`].
+    pub fn app_version_upgrade(
+        &self,
+        negotiation: &AppVersionNegotiation,
+    ) -> Result<Option<Extensions>, AppVersionError> {
+        negotiation.upgrade_extensions(&self.public_group)
+    }
+
     /// Returns whether the own client is still a member of the group or if it
     /// was already evicted
     pub fn is_active(&self) -> bool {
-        !matches!(self.group_state, MlsGroupState::Inactive)
+        !matches!(
+            self.group_state,
+            MlsGroupState::Inactive | MlsGroupState::RemovalGracePeriod
+        )
+    }
+
+    /// Returns the final epoch of the group if the own client was removed and
+    /// the group is in the [`MlsGroupState::RemovalGracePeriod`].
+    ///
+    /// This is the cut-off for processing messages after the removal:
+    /// application messages of this epoch can still be processed, while all
+    /// messages of later epochs and all handshake messages are rejected with
+    /// [`MlsGroupStateError::UseAfterEviction`].
+    pub fn removal_cutoff(&self) -> Option<GroupEpoch> {
+        matches!(self.group_state, MlsGroupState::RemovalGracePeriod).then(|| self.epoch())
+    }
+
+    /// Ends the [`MlsGroupState::RemovalGracePeriod`] of the group, such that
+    /// no more messages can be processed. Has no effect if the group is not in
+    /// the grace period.
+    pub fn end_removal_grace_period<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        if let MlsGroupState::RemovalGracePeriod = self.group_state {
+            self.group_state = MlsGroupState::Inactive;
+            self.message_secrets_store.mark_inactive();
+            storage.write_group_state(self.group_id(), &self.group_state)?;
+            storage.write_message_secrets(self.group_id(), &self.message_secrets_store)?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether the own client left the group with
+    /// [`MlsGroup::leave_group()`] and waits for a commit that removes it.
+    pub fn is_pending_self_removal(&self) -> bool {
+        matches!(self.group_state, MlsGroupState::PendingSelfRemoval)
+    }
+
+    /// Returns the state of the ratchet used for the application messages of
+    /// the member at `leaf_index` in the current epoch.
+    ///
+    /// For the own leaf index, this is the ratchet used to encrypt outgoing
+    /// messages. Returns `None` if there is no member at `leaf_index` or if no
+    /// application message was sent or received for it in the current epoch.
+    pub fn sender_ratchet_state(&self, leaf_index: LeafNodeIndex) -> Option<SenderRatchetState> {
+        self.message_secrets().secret_tree().sender_ratchet_state(
+            leaf_index,
+            SecretType::ApplicationSecret,
+            self.configuration()
+                .sender_ratchet_configuration_for(leaf_index),
+        )
     }
 
     /// Returns own credential. If the group is inactive, it returns a
@@ -331,6 +742,22 @@ impl MlsGroup {
         self.public_group.group_context().epoch()
     }
 
+    /// Returns the confirmed transcript hash of the current epoch.
+    pub fn confirmed_transcript_hash(&self) -> &[u8] {
+        self.public_group.confirmed_transcript_hash()
+    }
+
+    /// Returns the interim transcript hash of the current epoch.
+    pub fn interim_transcript_hash(&self) -> &[u8] {
+        self.public_group.interim_transcript_hash()
+    }
+
+    /// Returns the confirmation tag of the commit that started the current
+    /// epoch.
+    pub fn confirmation_tag(&self) -> &ConfirmationTag {
+        self.public_group.confirmation_tag()
+    }
+
     /// Returns an `Iterator` over pending proposals.
     pub fn pending_proposals(&self) -> impl Iterator<Item = &QueuedProposal> {
         self.proposal_store().proposals()
@@ -344,8 +771,11 @@ impl MlsGroup {
             MlsGroupState::PendingCommit(ref pending_commit_state) => {
                 Some(pending_commit_state.staged_commit())
             }
-            MlsGroupState::Operational => None,
-            MlsGroupState::Inactive => None,
+            MlsGroupState::Operational
+            | MlsGroupState::Inactive
+            | MlsGroupState::ReInitialized(_)
+            | MlsGroupState::PendingSelfRemoval
+            | MlsGroupState::RemovalGracePeriod => None,
         }
     }
 
@@ -373,7 +803,11 @@ impl MlsGroup {
                     Ok(())
                 }
             }
-            MlsGroupState::Operational | MlsGroupState::Inactive => Ok(()),
+            MlsGroupState::Operational
+            | MlsGroupState::Inactive
+            | MlsGroupState::ReInitialized(_)
+            | MlsGroupState::PendingSelfRemoval
+            | MlsGroupState::RemovalGracePeriod => Ok(()),
         }
     }
 
@@ -415,18 +849,63 @@ impl MlsGroup {
     // === Storage Methods ===
 
     /// Loads the state of the group with given id from persisted state.
-    pub fn load<Storage: crate::storage::StorageProvider>(
+    ///
+    /// This only reads from the `storage`, so a read-only handle is
+    /// sufficient. If the group's configuration was stored with an older
+    /// version of the format, it is migrated with the default values of the
+    /// new options, but the migrated configuration is not persisted. Use
+    /// [`MlsGroup::load_with_migration()`] to choose the values and persist
+    /// them.
+    pub fn load<Storage: crate::storage::StorageReader>(
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<Option<MlsGroup>, Storage::Error> {
+        Self::load_internal(storage, group_id, &|_: u16, config: MlsGroupJoinConfig| {
+            config
+        })
+        .map(|group| group.map(|(group, _)| group))
+    }
+
+    /// Loads the state of the group with given id from persisted state, like
+    /// [`MlsGroup::load()`]. If the group's configuration was stored with an
+    /// older version of the format, it is migrated with the given
+    /// `migration` and the migrated configuration is persisted.
+    pub fn load_with_migration<Storage: StorageProvider>(
         storage: &Storage,
         group_id: &GroupId,
+        migration: &impl JoinConfigMigration,
     ) -> Result<Option<MlsGroup>, Storage::Error> {
-        let public_group = PublicGroup::load(storage, group_id)?;
+        let Some((mut group, migrated)) = Self::load_internal(storage, group_id, migration)? else {
+            return Ok(None);
+        };
+        if migrated {
+            let mls_group_config = group.mls_group_config.clone();
+            group.set_configuration(storage, &mls_group_config)?;
+        }
+        Ok(Some(group))
+    }
+
+    /// Loads the state of the group and migrates its configuration. Also
+    /// returns whether the configuration was migrated.
+    fn load_internal<Storage: crate::storage::StorageReader>(
+        storage: &Storage,
+        group_id: &GroupId,
+        migration: &impl JoinConfigMigration,
+    ) -> Result<Option<(MlsGroup, bool)>, Storage::Error> {
+        let public_group = PublicGroup::load_from_reader(storage, group_id)?;
         let group_epoch_secrets = storage.group_epoch_secrets(group_id)?;
         let own_leaf_index = storage.own_leaf_index(group_id)?;
         let message_secrets_store = storage.message_secrets(group_id)?;
         let resumption_psk_store = storage.resumption_psk_store(group_id)?;
-        let mls_group_config = storage.mls_group_join_config(group_id)?;
+        let stored_config: Option<MlsGroupJoinConfig> = storage.mls_group_join_config(group_id)?;
+        let migrated_config = stored_config
+            .as_ref()
+            .and_then(|config| config.migrate(migration));
+        let migrated = migrated_config.is_some();
+        let mls_group_config = migrated_config.or(stored_config);
         let own_leaf_nodes = storage.own_leaf_nodes(group_id)?;
         let group_state = storage.group_state(group_id)?;
+        let used_external_inits = storage.used_external_inits(group_id)?;
 
         let build = || -> Option<Self> {
             Some(Self {
@@ -438,11 +917,15 @@ impl MlsGroup {
                 mls_group_config: mls_group_config?,
                 own_leaf_nodes,
                 aad: vec![],
+                used_external_inits: used_external_inits.unwrap_or_default(),
+                member_stats: MemberStatsCache::default(),
+                last_operation_timing: LastOperationTiming::default(),
+                epoch_hooks: EpochHooks::default(),
                 group_state: group_state?,
             })
         };
 
-        Ok(build())
+        Ok(build().map(|group| (group, migrated)))
     }
 
     /// Remove the persisted state of this group from storage. Note that
@@ -456,6 +939,10 @@ impl MlsGroup {
         storage.delete_own_leaf_index(self.group_id())?;
         storage.delete_group_epoch_secrets(self.group_id())?;
         storage.delete_message_secrets(self.group_id())?;
+        storage.delete_member_stats(self.group_id())?;
+        self.reset_member_stats();
+        storage.delete_replica_sequence(self.group_id())?;
+        storage.delete_used_external_inits(self.group_id())?;
         storage.delete_all_resumption_psk_secrets(self.group_id())?;
         storage.delete_group_config(self.group_id())?;
         storage.delete_own_leaf_nodes(self.group_id())?;
@@ -480,6 +967,12 @@ impl MlsGroup {
     pub fn export_ratchet_tree(&self) -> RatchetTree {
         self.public_group().export_ratchet_tree()
     }
+
+    /// Returns a [`TreePreview`] to apply hypothetical changes to a copy of
+    /// the ratchet tree. See [`PublicGroup::tree_preview()`].
+    pub fn tree_preview(&self) -> TreePreview<'_> {
+        self.public_group().tree_preview()
+    }
 }
 
 // Crate-public functions
@@ -502,10 +995,16 @@ impl MlsGroup {
     /// Sets the size of the [`MessageSecretsStore`], i.e. the number of past
     /// epochs to keep.
     /// This allows application messages from previous epochs to be decrypted.
-    pub(crate) fn set_max_past_epochs(&mut self, max_past_epochs: usize) {
+    pub(crate) fn resize_message_secrets_store(&mut self, max_past_epochs: usize) {
         self.message_secrets_store.resize(max_past_epochs);
     }
 
+    /// Sets the maximum age of the message secrets of past epochs in the
+    /// [`MessageSecretsStore`].
+    pub(crate) fn set_max_past_epoch_age(&mut self, max_past_epoch_age: Option<Duration>) {
+        self.message_secrets_store.set_max_age(max_past_epoch_age);
+    }
+
     /// Get the message secrets. Either from the secrets store or from the group.
     pub(crate) fn message_secrets_mut(
         &mut self,
@@ -599,8 +1098,19 @@ impl MlsGroup {
         public_message: AuthenticatedContent,
         provider: &Provider,
     ) -> Result<PrivateMessage, MessageEncryptionError<Provider::StorageError>> {
+        self.encrypt_with_id(public_message, provider)
+            .map(|(msg, _)| msg)
+    }
+
+    /// Like [`MlsGroup::encrypt()`], but also returns the [`MessageId`] of
+    /// the encrypted message.
+    pub(crate) fn encrypt_with_id<Provider: OpenMlsProvider>(
+        &mut self,
+        public_message: AuthenticatedContent,
+        provider: &Provider,
+    ) -> Result<(PrivateMessage, MessageId), MessageEncryptionError<Provider::StorageError>> {
         let padding_size = self.configuration().padding_size();
-        let msg = PrivateMessage::try_from_authenticated_content(
+        let (msg, message_id) = PrivateMessage::try_from_authenticated_content(
             provider.crypto(),
             provider.rand(),
             &public_message,
@@ -614,7 +1124,7 @@ impl MlsGroup {
             .write_message_secrets(self.group_id(), &self.message_secrets_store)
             .map_err(MessageEncryptionError::StorageError)?;
 
-        Ok(msg)
+        Ok((msg, message_id))
     }
 
     /// Group framing parameters
@@ -681,7 +1191,7 @@ impl MlsGroup {
     ///
     /// Returns an empty vector if access to the store fails or it can't find
     /// any keys.
-    pub(super) fn read_epoch_keypairs<Storage: StorageProvider>(
+    pub(super) fn read_epoch_keypairs<Storage: crate::storage::StorageReader>(
         &self,
         store: &Storage,
     ) -> Vec<EncryptionKeyPair> {
@@ -746,7 +1256,7 @@ impl MlsGroup {
                         self.message_secrets().serialized_context(),
                     )?;
                 }
-                plaintext.into()
+                MlsMessageOut::from_public_message(plaintext, self.version())
             }
             OutgoingWireFormatPolicy::AlwaysCiphertext => {
                 let ciphertext = self
@@ -764,7 +1274,11 @@ impl MlsGroup {
     fn is_operational(&self) -> Result<(), MlsGroupStateError> {
         match self.group_state {
             MlsGroupState::PendingCommit(_) => Err(MlsGroupStateError::PendingCommit),
-            MlsGroupState::Inactive => Err(MlsGroupStateError::UseAfterEviction),
+            MlsGroupState::Inactive | MlsGroupState::RemovalGracePeriod => {
+                Err(MlsGroupStateError::UseAfterEviction)
+            }
+            MlsGroupState::ReInitialized(_) => Err(MlsGroupStateError::UseAfterReInit),
+            MlsGroupState::PendingSelfRemoval => Err(MlsGroupStateError::PendingSelfRemoval),
             MlsGroupState::Operational => Ok(()),
         }
     }
@@ -854,6 +1368,94 @@ pub struct StagedWelcome {
     path_keypairs: Option<Vec<EncryptionKeyPair>>,
 }
 
+/// A `Welcome` message whose key schedule requires PSKs that are not known
+/// yet.
+///
+/// Use [`PendingPskWelcome::missing_psks()`] to find out which PSKs are
+/// missing, fetch them, e.g. from the inviter, and pass them to
+/// [`PendingPskWelcome::provide_psk()`]. Then resume the join with
+/// [`PendingPskWelcome::into_processed_welcome()`]. Since the application can
+/// fetch the PSKs in any way it likes between these calls, this also works for
+/// asynchronous lookups. For synchronous lookups, see
+/// [`ProcessedWelcome::new_from_welcome_with_psk_resolver()`].
+pub struct PendingPskWelcome {
+    // The group configuration. See [`MlsGroupJoinConfig`] for more information.
+    mls_group_config: MlsGroupJoinConfig,
+    welcome: Welcome,
+    group_secrets: GroupSecrets,
+    // The secrets of the PSKs in `group_secrets`, in the same order.
+    psk_secrets: Vec<Option<crate::ciphersuite::Secret>>,
+    resumption_psk_store: crate::schedule::psk::store::ResumptionPskStore,
+    key_package_bundle: KeyPackageBundle,
+}
+
+/// A message whose processing was suspended by
+/// [`MlsGroup::process_message_resumable()`] because a resource was missing,
+/// see [`SuspendReason`].
+///
+/// It can be serialized, so that it can be stored until the missing resource
+/// is available. Processing is resumed with [`MlsGroup::resume_message()`].
+///
+/// If processing was suspended after the message was decrypted, i.e. because
+/// of missing PSKs, it contains the decrypted content of the message in
+/// plaintext, because the key to decrypt the message was deleted. Serialized
+/// messages have to be protected like the group state in the storage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumableMessage {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    reason: SuspendReason,
+    message: SuspendedMessage,
+}
+
+/// The content of a [`ResumableMessage`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SuspendedMessage {
+    /// The decrypted, but not yet verified content of the message.
+    Decrypted(Box<DecryptedMessage>),
+    /// A message of a later epoch, which can't be decrypted yet.
+    Undecrypted(ProtocolMessage),
+}
+
+/// The reason why processing a [`ResumableMessage`] was suspended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SuspendReason {
+    /// The commit requires PSKs that are not available. Contains their ids.
+    MissingPsks(Vec<PreSharedKeyId>),
+    /// The message was sent in a later epoch, e.g. because a commit that
+    /// precedes it wasn't merged yet.
+    EpochNotReached,
+}
+
+impl ResumableMessage {
+    /// Returns the id of the group the message was sent to.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the message was sent in.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the reason why processing was suspended.
+    pub fn reason(&self) -> &SuspendReason {
+        &self.reason
+    }
+
+    /// Returns the sender of the message, or `None` if the message wasn't
+    /// decrypted yet.
+    pub fn sender(&self) -> Option<&Sender> {
+        match &self.message {
+            SuspendedMessage::Decrypted(decrypted_message) => Some(decrypted_message.sender()),
+            SuspendedMessage::Undecrypted(ProtocolMessage::PublicMessage(public_message)) => {
+                Some(public_message.sender())
+            }
+            SuspendedMessage::Undecrypted(ProtocolMessage::PrivateMessage(_)) => None,
+        }
+    }
+}
+
 /// A `Welcome` message that has been processed but not staged yet.
 ///
 /// This may be used in order to retrieve information from the `Welcome` about
@@ -1,4 +1,9 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, time::Duration};
+
+#[cfg(target_arch = "wasm32")]
+use fluvio_wasm_timer::{SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::schedule::message_secrets::MessageSecrets;
 
@@ -12,20 +17,33 @@ struct EpochTree {
     epoch: u64,
     message_secrets: MessageSecrets,
     leaves: Vec<Member>,
+    // Seconds since the UNIX epoch at which the epoch ended.
+    #[serde(default)]
+    retired_at: u64,
 }
 
 /// Can store message secrets for up to `max_epochs`. The trees are added with [`self::add()`] and can be queried
 /// with [`Self::get_epoch()`].
+///
+/// If `max_age` is set, message secrets that have been retired longer than
+/// `max_age` ago are ignored and dropped from the store the next time it is
+/// modified.
 #[derive(Serialize, Deserialize)]
 #[cfg_attr(any(test, feature = "test-utils"), derive(Clone, PartialEq))]
 #[cfg_attr(feature = "crypto-debug", derive(Debug))]
 pub(crate) struct MessageSecretsStore {
     // Maximum size of the `past_epoch_trees` list.
     pub(crate) max_epochs: usize,
+    // Maximum age of the entries in the `past_epoch_trees` list.
+    #[serde(default)]
+    max_age: Option<Duration>,
     // Past message secrets.
     past_epoch_trees: VecDeque<EpochTree>,
     // The message secrets of the current epoch.
     message_secrets: MessageSecrets,
+    // Seconds since the UNIX epoch at which the group became inactive.
+    #[serde(default)]
+    inactive_since: Option<u64>,
 }
 
 #[cfg(not(feature = "crypto-debug"))]
@@ -33,8 +51,10 @@ impl core::fmt::Debug for MessageSecretsStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MessageSecretsStore")
             .field("max_epochs", &"***")
+            .field("max_age", &self.max_age)
             .field("past_epoch_trees", &"***")
             .field("message_secrets", &"***")
+            .field("inactive_since", &self.inactive_since)
             .finish()
     }
 }
@@ -45,8 +65,10 @@ impl MessageSecretsStore {
     pub(crate) fn new_with_secret(max_epochs: usize, message_secrets: MessageSecrets) -> Self {
         Self {
             max_epochs,
+            max_age: None,
             past_epoch_trees: VecDeque::new(),
             message_secrets,
+            inactive_since: None,
         }
     }
 
@@ -61,6 +83,27 @@ impl MessageSecretsStore {
         }
     }
 
+    /// Set the maximum age of the past message secrets and drop the ones that
+    /// are already expired. If `max_age` is `None`, the secrets don't expire.
+    pub(crate) fn set_max_age(&mut self, max_age: Option<Duration>) {
+        self.max_age = max_age;
+        self.prune_expired();
+    }
+
+    /// Drop the message secrets of past epochs that are older than the maximum
+    /// age.
+    pub(crate) fn prune_expired(&mut self) {
+        let now = now();
+        let max_age = self.max_age;
+        self.past_epoch_trees
+            .retain(|epoch_tree| !is_expired(epoch_tree, max_age, now));
+    }
+
+    /// Drop the message secrets of all past epochs.
+    pub(crate) fn clear_past_epochs(&mut self) {
+        self.past_epoch_trees.clear();
+    }
+
     /// Add a secret tree for a given epoch `group_epoch`.
     /// Note that this does not take the epoch into account and pops out the
     /// oldest element.
@@ -74,6 +117,7 @@ impl MessageSecretsStore {
         if self.max_epochs == 0 {
             return;
         }
+        self.prune_expired();
         if self.past_epoch_trees.len() >= self.max_epochs {
             self.past_epoch_trees.rotate_left(1);
             self.past_epoch_trees.truncate(self.max_epochs - 1);
@@ -82,6 +126,7 @@ impl MessageSecretsStore {
             epoch: group_epoch.into().as_u64(),
             message_secrets,
             leaves,
+            retired_at: now(),
         });
         debug_assert!(
             self.max_epochs >= self.past_epoch_trees.len(),
@@ -98,8 +143,9 @@ impl MessageSecretsStore {
         group_epoch: impl Into<GroupEpoch>,
     ) -> Option<&mut MessageSecrets> {
         let epoch = group_epoch.into().as_u64();
+        let now = now();
         for epoch_tree in self.past_epoch_trees.iter_mut() {
-            if epoch_tree.epoch == epoch {
+            if epoch_tree.epoch == epoch && !is_expired(epoch_tree, self.max_age, now) {
                 return Some(&mut epoch_tree.message_secrets);
             }
         }
@@ -113,8 +159,9 @@ impl MessageSecretsStore {
         group_epoch: impl Into<GroupEpoch>,
     ) -> Option<&MessageSecrets> {
         let epoch = group_epoch.into().as_u64();
+        let now = now();
         for epoch_tree in self.past_epoch_trees.iter() {
-            if epoch_tree.epoch == epoch {
+            if epoch_tree.epoch == epoch && !is_expired(epoch_tree, self.max_age, now) {
                 return Some(&epoch_tree.message_secrets);
             }
         }
@@ -129,8 +176,9 @@ impl MessageSecretsStore {
         group_epoch: impl Into<GroupEpoch>,
     ) -> Option<(&mut MessageSecrets, &[Member])> {
         let epoch = group_epoch.into().as_u64();
+        let now = now();
         for epoch_tree in self.past_epoch_trees.iter_mut() {
-            if epoch_tree.epoch == epoch {
+            if epoch_tree.epoch == epoch && !is_expired(epoch_tree, self.max_age, now) {
                 return Some((&mut epoch_tree.message_secrets, &epoch_tree.leaves));
             }
         }
@@ -171,4 +219,44 @@ impl MessageSecretsStore {
     pub(crate) fn message_secrets(&self) -> &MessageSecrets {
         &self.message_secrets
     }
+
+    /// Records the current time as the time at which the group became
+    /// inactive, unless it was already recorded.
+    pub(crate) fn mark_inactive(&mut self) {
+        self.inactive_since.get_or_insert_with(now);
+    }
+
+    /// Returns the time at which the group became inactive.
+    pub(crate) fn inactive_since(&self) -> Option<SystemTime> {
+        self.inactive_since
+            .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    /// Returns the [`Member`]s of the past epochs that are still stored, in
+    /// ascending order of the epochs.
+    pub(crate) fn past_members(&self) -> Vec<(GroupEpoch, Vec<Member>)> {
+        self.past_epoch_trees
+            .iter()
+            .map(|epoch_tree| (GroupEpoch(epoch_tree.epoch), epoch_tree.leaves.clone()))
+            .collect()
+    }
+}
+
+/// Returns the current time in seconds since the UNIX epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_else(|_| {
+            log::error!("SystemTime before UNIX EPOCH.");
+            0
+        })
+}
+
+/// Returns `true` if the `epoch_tree` was retired longer than `max_age` ago.
+fn is_expired(epoch_tree: &EpochTree, max_age: Option<Duration>, now: u64) -> bool {
+    match max_age {
+        Some(max_age) => now.saturating_sub(epoch_tree.retired_at) >= max_age.as_secs(),
+        None => false,
+    }
 }
@@ -3,17 +3,30 @@
 use std::mem;
 
 use errors::{CommitToPendingProposalsError, MergePendingCommitError};
-use openmls_traits::{crypto::OpenMlsCrypto, signatures::Signer, storage::StorageProvider as _};
+use openmls_traits::{crypto::OpenMlsCrypto, signatures::Signer, storage::StorageWriter as _};
 
 use crate::{
-    framing::mls_content::FramedContentBody,
-    group::{errors::MergeCommitError, StageCommitError, ValidationError},
-    messages::group_info::GroupInfo,
+    framing::{mls_content::FramedContentBody, private_message_in::PrivateMessageIn},
+    group::{
+        errors::MergeCommitError, CommitPolicy, ExtensionObservers, ProcessingPolicy,
+        StageCommitError, ValidationError,
+    },
+    messages::{
+        group_info::GroupInfo,
+        proposals_in::{ProposalIn, ProposalOrRefIn},
+    },
+    schedule::psk::{
+        load_psk, store_resumption_psk, PreSharedKeyId, Psk, PskResolver, PskStore, ResumptionPsk,
+        ResumptionPskUsage,
+    },
     storage::OpenMlsProvider,
-    tree::sender_ratchet::SenderRatchetConfiguration,
 };
 
-use super::{errors::ProcessMessageError, *};
+use super::{
+    errors::{ProcessMessageError, ResumableProcessMessageError},
+    timing::{measure, OperationKind, TimingPhase},
+    *,
+};
 
 impl MlsGroup {
     /// Parses incoming messages from the DS. Checks for syntactic errors and
@@ -30,13 +43,228 @@ impl MlsGroup {
         provider: &Provider,
         message: impl Into<ProtocolMessage>,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
-        // Make sure we are still a member of the group
+        self.process_message_with_policy(provider, message, &())
+    }
+
+    /// Like [`Self::process_message()`], but additionally consults the given
+    /// [`ProcessingPolicy`] during validation. See [`ProcessingPolicy`] for
+    /// the checks that can be customized.
+    pub fn process_message_with_policy<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: impl Into<ProtocolMessage>,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.process_message_with_psk_store(provider, message, policy, provider.storage())
+    }
+
+    /// Like [`Self::process_message_with_policy()`], but calls `resolve` for
+    /// the external PSKs of an incoming commit that are not in the storage of
+    /// the provider, so the application can fetch them on demand. Processing
+    /// only fails with a [`PskError`](crate::schedule::errors::PskError) if
+    /// `resolve` declines by returning `None`. See [`PskResolver`].
+    pub fn process_message_with_psk_resolver<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: impl Into<ProtocolMessage>,
+        policy: &impl ProcessingPolicy,
+        resolve: impl Fn(&Psk) -> Option<Vec<u8>>,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.process_message_with_psk_store(
+            provider,
+            message,
+            policy,
+            &PskResolver::new(provider.storage(), resolve),
+        )
+    }
+
+    /// Like [`Self::process_message_with_policy()`], but looks up the PSKs
+    /// of incoming commits in the given [`PskStore`] instead of the storage
+    /// of the provider.
+    pub fn process_message_with_psk_store<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: impl Into<ProtocolMessage>,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.timed(OperationKind::ProcessMessage, |group| {
+            let decrypted_message = group.decrypt_protocol_message(provider, message.into())?;
+            group.process_decrypted_message(provider, decrypted_message, policy, psk_store)
+        })
+    }
+
+    /// Like [`Self::process_message_with_psk_store()`], but if a resource that
+    /// is needed to process the message is missing, processing is suspended
+    /// instead of failing. The returned
+    /// [`ResumableProcessMessageError::Suspended`] contains a
+    /// [`ResumableMessage`], whose [`SuspendReason`] names the missing
+    /// resource:
+    ///
+    /// - [`SuspendReason::MissingPsks`] if a commit requires PSKs that are
+    ///   not in the `psk_store`. The message is suspended after it was
+    ///   decrypted, because the key to decrypt it is deleted from the secret
+    ///   tree, so that it can't be decrypted a second time.
+    /// - [`SuspendReason::EpochNotReached`] if the message was sent in a later
+    ///   epoch, e.g. because the commit that precedes it wasn't merged yet.
+    ///   The message is suspended without decrypting it.
+    ///
+    /// Once the resource is available, pass the [`ResumableMessage`] to
+    /// [`Self::resume_message()`].
+    ///
+    /// The ratchet tree of a group is always available to its members. A
+    /// [`Welcome`](crate::messages::Welcome) that doesn't include the ratchet
+    /// tree can be kept as a [`ProcessedWelcome`] until the tree is fetched,
+    /// see [`ProcessedWelcome::requires_ratchet_tree()`].
+    pub fn process_message_resumable<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: impl Into<ProtocolMessage>,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
+    ) -> Result<ProcessedMessage, ResumableProcessMessageError> {
+        let message = message.into();
+        if self.is_active()
+            && message.group_id() == self.group_id()
+            && message.epoch() > self.epoch()
+        {
+            return Err(ResumableProcessMessageError::Suspended(Box::new(
+                ResumableMessage {
+                    group_id: self.group_id().clone(),
+                    epoch: message.epoch(),
+                    reason: SuspendReason::EpochNotReached,
+                    message: SuspendedMessage::Undecrypted(message),
+                },
+            )));
+        }
+
+        self.timed(OperationKind::ProcessMessage, |group| {
+            let decrypted_message = group.decrypt_protocol_message(provider, message)?;
+            group.process_resumable(provider, decrypted_message, policy, psk_store)
+        })
+    }
+
+    /// Resumes processing a message that was suspended by
+    /// [`Self::process_message_resumable()`]. A message that was already
+    /// decrypted isn't decrypted again, but its signature is verified again,
+    /// so a modified [`ResumableMessage`] is rejected.
+    ///
+    /// Returns [`ResumableProcessMessageError::Suspended`] again if a resource
+    /// is still missing.
+    ///
+    /// # Errors
+    /// Returns a [`ValidationError::WrongGroupId`] if the message doesn't
+    /// belong to this group, and a [`ValidationError::WrongEpoch`] if a
+    /// decrypted message doesn't belong to the current epoch anymore.
+    pub fn resume_message<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: ResumableMessage,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
+    ) -> Result<ProcessedMessage, ResumableProcessMessageError> {
         if !self.is_active() {
+            return Err(
+                ProcessMessageError::GroupStateError(MlsGroupStateError::UseAfterEviction).into(),
+            );
+        }
+        if message.group_id != *self.group_id() {
+            return Err(ProcessMessageError::from(ValidationError::WrongGroupId).into());
+        }
+
+        match message.message {
+            SuspendedMessage::Decrypted(decrypted_message) => {
+                if message.epoch != self.epoch() {
+                    return Err(ProcessMessageError::from(ValidationError::WrongEpoch).into());
+                }
+                self.timed(OperationKind::ProcessMessage, |group| {
+                    group.process_resumable(provider, *decrypted_message, policy, psk_store)
+                })
+            }
+            SuspendedMessage::Undecrypted(protocol_message) => {
+                self.process_message_resumable(provider, protocol_message, policy, psk_store)
+            }
+        }
+    }
+
+    fn process_resumable<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        decrypted_message: DecryptedMessage,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
+    ) -> Result<ProcessedMessage, ResumableProcessMessageError> {
+        let missing_psks = self.missing_psks(&decrypted_message, psk_store);
+        if !missing_psks.is_empty() {
+            return Err(ResumableProcessMessageError::Suspended(Box::new(
+                ResumableMessage {
+                    group_id: self.group_id().clone(),
+                    epoch: self.epoch(),
+                    reason: SuspendReason::MissingPsks(missing_psks),
+                    message: SuspendedMessage::Decrypted(Box::new(decrypted_message)),
+                },
+            )));
+        }
+
+        Ok(self.process_decrypted_message(provider, decrypted_message, policy, psk_store)?)
+    }
+
+    /// Returns the ids of the PSKs that the commit in `decrypted_message`
+    /// requires and that can't be found, neither in the `psk_store` nor in
+    /// the resumption PSKs of the group.
+    fn missing_psks(
+        &self,
+        decrypted_message: &DecryptedMessage,
+        psk_store: &impl PskStore,
+    ) -> Vec<PreSharedKeyId> {
+        let Some(commit) = decrypted_message.verifiable_content().commit() else {
+            return vec![];
+        };
+
+        commit
+            .proposals()
+            .iter()
+            .filter_map(|proposal_or_ref| match proposal_or_ref {
+                ProposalOrRefIn::Proposal(ProposalIn::PreSharedKey(psk_proposal)) => {
+                    Some(psk_proposal.psk_id().clone())
+                }
+                ProposalOrRefIn::Reference(reference) => self
+                    .proposal_store()
+                    .proposals()
+                    .find(|queued_proposal| queued_proposal.proposal_reference() == *reference)
+                    .and_then(|queued_proposal| match queued_proposal.proposal() {
+                        Proposal::PreSharedKey(psk_proposal) => Some(psk_proposal.psk_id().clone()),
+                        _ => None,
+                    }),
+                ProposalOrRefIn::Proposal(_) => None,
+            })
+            .filter(|psk_id| {
+                !matches!(
+                    load_psk(psk_store, &self.resumption_psk_store, psk_id),
+                    Ok(Some(_))
+                )
+            })
+            .collect()
+    }
+
+    /// Performs the checks that are needed before decrypting the given
+    /// message and decrypts it.
+    fn decrypt_protocol_message<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        message: ProtocolMessage,
+    ) -> Result<DecryptedMessage, ProcessMessageError> {
+        // Make sure we are still a member of the group. During the removal
+        // grace period, application messages of the final epoch are still
+        // processed.
+        let in_grace_period = self.removal_cutoff().is_some_and(|cutoff| {
+            message.epoch() == cutoff && message.content_type() == ContentType::Application
+        });
+        if !self.is_active() && !in_grace_period {
             return Err(ProcessMessageError::GroupStateError(
                 MlsGroupStateError::UseAfterEviction,
             ));
         }
-        let message = message.into();
 
         // Check that handshake messages are compatible with the incoming wire format policy
         if !message.is_external()
@@ -50,18 +278,69 @@ impl MlsGroup {
             return Err(ProcessMessageError::IncompatibleWireFormat);
         }
 
-        // Parse the message
-        let sender_ratchet_configuration =
-            self.configuration().sender_ratchet_configuration().clone();
+        // Check if the DS echoed our own pending commit back to us.
+        if self.is_own_pending_commit(provider.crypto(), &message) {
+            return Err(ProcessMessageError::OwnCommitReceived);
+        }
 
         // Checks the following semantic validation:
         //  - ValSem002
         //  - ValSem003
         //  - ValSem006
         //  - ValSem007 MembershipTag presence
-        let decrypted_message =
-            self.decrypt_message(provider.crypto(), message, &sender_ratchet_configuration)?;
+        match measure(TimingPhase::Decryption, || {
+            self.try_decrypt_message(provider.crypto(), message)
+        }) {
+            Ok(decrypted_message) => Ok(decrypted_message),
+            Err((ValidationError::OwnMessageEchoed { epoch, generation }, _)) => {
+                Err(ProcessMessageError::OwnMessageEchoed { epoch, generation })
+            }
+            Err((e, ciphertext)) => {
+                if let Some(ciphertext) = ciphertext {
+                    self.record_decryption_failure(provider, &ciphertext);
+                }
+                Err(e.into())
+            }
+        }
+    }
 
+    /// Counts a decryption failure for the member named in the sender data of
+    /// the `ciphertext` in the member statistics, if the sender data can be
+    /// decrypted.
+    fn record_decryption_failure<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        ciphertext: &PrivateMessageIn,
+    ) {
+        if !self.configuration().collect_member_stats() {
+            return;
+        }
+        let Ok(message_secrets) = self.message_secrets_for_epoch(ciphertext.epoch()) else {
+            return;
+        };
+        let Ok(sender_data) = measure(TimingPhase::Decryption, || {
+            ciphertext.sender_data(message_secrets, provider.crypto(), self.ciphersuite())
+        }) else {
+            return;
+        };
+        if let Err(e) =
+            self.record_member_stats(provider.storage(), sender_data.leaf_index, |stats| {
+                stats.record_decryption_failure()
+            })
+        {
+            log::error!("Failed to record a decryption failure: {:?}", e);
+        }
+    }
+
+    /// Parses and processes a message that was decrypted with
+    /// [`Self::decrypt_protocol_message()`].
+    fn process_decrypted_message<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        decrypted_message: DecryptedMessage,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
         let unverified_message = self
             .public_group
             .parse_message(decrypted_message, &self.message_secrets_store)
@@ -70,17 +349,53 @@ impl MlsGroup {
         // If this is a commit, we need to load the private key material we need for decryption.
         let (old_epoch_keypairs, leaf_node_keypairs) =
             if let ContentType::Commit = unverified_message.content_type() {
-                self.read_decryption_keypairs(provider, &self.own_leaf_nodes)?
+                measure(TimingPhase::StorageIo, || {
+                    self.read_decryption_keypairs(provider, &self.own_leaf_nodes)
+                })?
             } else {
                 (vec![], vec![])
             };
 
-        self.process_unverified_message(
+        let processed_message = self.process_unverified_message(
             provider,
             unverified_message,
             old_epoch_keypairs,
             leaf_node_keypairs,
-        )
+            policy,
+            psk_store,
+        )?;
+
+        // Remember the external init of a staged external commit, so that it
+        // can't be replayed in this epoch, even after the group was loaded
+        // again.
+        if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+            processed_message.content()
+        {
+            if let Some(kem_output) = staged_commit.external_init_kem_output() {
+                self.used_external_inits.insert(kem_output);
+                provider
+                    .storage()
+                    .write_used_external_inits(self.group_id(), &self.used_external_inits)
+                    .map_err(|e| {
+                        log::error!("Failed to store the used external inits: {:?}", e);
+                        ProcessMessageError::StorageError
+                    })?;
+            }
+        }
+
+        if let Some(leaf_index) = processed_message.sender_leaf_index() {
+            let is_commit = matches!(
+                processed_message.content(),
+                ProcessedMessageContent::StagedCommitMessage(_)
+            );
+            if let Err(e) = self.record_member_stats(provider.storage(), leaf_index, |stats| {
+                stats.record_message(processed_message.epoch(), is_commit)
+            }) {
+                log::error!("Failed to record member statistics: {:?}", e);
+            }
+        }
+
+        Ok(processed_message)
     }
 
     /// Stores a standalone proposal in the internal [ProposalStore]
@@ -114,6 +429,22 @@ impl MlsGroup {
     ) -> Result<
         (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
         CommitToPendingProposalsError<Provider::StorageError>,
+    > {
+        self.commit_to_pending_proposals_with_policy(provider, signer, &mut ())
+    }
+
+    /// Like [`MlsGroup::commit_to_pending_proposals()`], but lets the
+    /// [`CommitPolicy`] select and order the pending proposals that are
+    /// considered for inclusion in the commit.
+    #[allow(clippy::type_complexity)]
+    pub fn commit_to_pending_proposals_with_policy<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        policy: &mut impl CommitPolicy,
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        CommitToPendingProposalsError<Provider::StorageError>,
     > {
         self.is_operational()?;
 
@@ -124,7 +455,7 @@ impl MlsGroup {
             // This forces committing to the proposals in the proposal store:
             .consume_proposal_store(true)
             .load_psks(provider.storage())?
-            .build(provider.rand(), provider.crypto(), signer, |_| true)?
+            .build_with_policy(provider.rand(), provider.crypto(), signer, policy)?
             .stage_commit(provider)?
             .into_contents();
 
@@ -144,8 +475,21 @@ impl MlsGroup {
         staged_commit: StagedCommit,
     ) -> Result<(), MergeCommitError<Provider::StorageError>> {
         // Check if we were removed from the group
-        if staged_commit.self_removed() {
+        if staged_commit.self_removed() && self.mls_group_config.removal_grace_period {
+            // Keep the state of the final epoch, so that its application
+            // messages can still be processed.
+            self.group_state = MlsGroupState::RemovalGracePeriod;
+            self.own_leaf_nodes.clear();
+            let storage = provider.storage();
+            return storage
+                .write_group_state(self.group_id(), &self.group_state)
+                .and_then(|_| storage.delete_own_leaf_nodes(self.group_id()))
+                .map_err(MergeCommitError::StorageError);
+        } else if staged_commit.self_removed() {
             self.group_state = MlsGroupState::Inactive;
+            self.message_secrets_store.mark_inactive();
+        } else if let Some(reinit_proposal) = staged_commit.reinit_proposal() {
+            self.group_state = MlsGroupState::ReInitialized(reinit_proposal.clone());
         }
         provider
             .storage()
@@ -164,6 +508,28 @@ impl MlsGroup {
             .write_resumption_psk_store(self.group_id(), &self.resumption_psk_store)
             .map_err(MergeCommitError::StorageError)?;
 
+        // If the group was re-initialized, keep the resumption psk in the
+        // storage, so that the new group can use it.
+        if let MlsGroupState::ReInitialized(_) = self.group_state {
+            store_resumption_psk(
+                provider.storage(),
+                ResumptionPsk::new(
+                    ResumptionPskUsage::Reinit,
+                    self.group_id().clone(),
+                    self.context().epoch(),
+                ),
+                self.group_epoch_secrets().resumption_psk(),
+            )
+            .map_err(MergeCommitError::StorageError)?;
+        }
+
+        // Forget the external inits of the previous epoch
+        self.used_external_inits = UsedExternalInits::default();
+        provider
+            .storage()
+            .delete_used_external_inits(self.group_id())
+            .map_err(MergeCommitError::StorageError)?;
+
         // Delete own KeyPackageBundles
         self.own_leaf_nodes.clear();
         provider
@@ -175,6 +541,50 @@ impl MlsGroup {
         self.clear_pending_commit(provider.storage())
             .map_err(MergeCommitError::StorageError)?;
 
+        // Track the members that joined in this commit
+        if !matches!(self.group_state, MlsGroupState::Inactive) {
+            self.observe_member_stats(provider.storage())
+                .map_err(MergeCommitError::StorageError)?;
+        }
+
+        #[cfg(feature = "integrity-checks")]
+        if !matches!(self.group_state, MlsGroupState::Inactive) {
+            self.assert_integrity(provider.crypto());
+        }
+
+        self.run_epoch_hooks(provider)
+            .map_err(MergeCommitError::EpochHookError)
+    }
+
+    /// Merge a [StagedCommit] into the group like
+    /// [`MlsGroup::merge_staged_commit()`] and invoke the `observers` for the
+    /// observed extensions that are changed by the commit.
+    pub fn merge_staged_commit_with_observers<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        staged_commit: StagedCommit,
+        observers: &mut ExtensionObservers,
+    ) -> Result<(), MergeCommitError<Provider::StorageError>> {
+        let changes = observers.collect_changes(self.public_group(), &staged_commit);
+        self.merge_staged_commit(provider, staged_commit)?;
+        observers.notify(&changes);
+        Ok(())
+    }
+
+    /// Merges the pending [`StagedCommit`] like
+    /// [`MlsGroup::merge_pending_commit()`] and invoke the `observers` for the
+    /// observed extensions that are changed by the commit.
+    pub fn merge_pending_commit_with_observers<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        observers: &mut ExtensionObservers,
+    ) -> Result<(), MergePendingCommitError<Provider::StorageError>> {
+        let changes = self
+            .pending_commit()
+            .map(|staged_commit| observers.collect_changes(self.public_group(), staged_commit))
+            .unwrap_or_default();
+        self.merge_pending_commit(provider)?;
+        observers.notify(&changes);
         Ok(())
     }
 
@@ -192,8 +602,48 @@ impl MlsGroup {
                 }
                 Ok(())
             }
-            MlsGroupState::Inactive => Err(MlsGroupStateError::UseAfterEviction)?,
-            MlsGroupState::Operational => Ok(()),
+            MlsGroupState::Inactive | MlsGroupState::RemovalGracePeriod => {
+                Err(MlsGroupStateError::UseAfterEviction)?
+            }
+            MlsGroupState::Operational
+            | MlsGroupState::ReInitialized(_)
+            | MlsGroupState::PendingSelfRemoval => Ok(()),
+        }
+    }
+
+    /// Returns `true` if the `message` contains the pending commit of this
+    /// client, i.e. a commit of the current epoch that is either a public
+    /// message sent by us with the confirmation tag of the pending commit, or
+    /// the private message the pending commit was sent in.
+    fn is_own_pending_commit(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        message: &ProtocolMessage,
+    ) -> bool {
+        let Some(pending_commit) = self.pending_commit() else {
+            return false;
+        };
+        if message.content_type() != ContentType::Commit || message.epoch() != self.epoch() {
+            return false;
+        }
+
+        match message {
+            ProtocolMessage::PublicMessage(public_message) => {
+                public_message.sender() == &Sender::Member(self.own_leaf_index())
+                    && public_message.confirmation_tag()
+                        == Some(pending_commit.staged_diff().confirmation_tag())
+            }
+            ProtocolMessage::PrivateMessage(private_message) => {
+                let Some(ciphertext_hash) = pending_commit.ciphertext_hash() else {
+                    return false;
+                };
+                crypto
+                    .hash(
+                        self.ciphersuite().hash_algorithm(),
+                        private_message.ciphertext(),
+                    )
+                    .is_ok_and(|hash| hash == ciphertext_hash)
+            }
         }
     }
 
@@ -254,14 +704,19 @@ impl MlsGroup {
         unverified_message: UnverifiedMessage,
         old_epoch_keypairs: Vec<EncryptionKeyPair>,
         leaf_node_keypairs: Vec<EncryptionKeyPair>,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         // Checks the following semantic validation:
         //  - ValSem010
         //  - ValSem246 (as part of ValSem010)
         //  - https://validation.openmls.tech/#valn1302
         //  - https://validation.openmls.tech/#valn1304
-        let (content, credential) =
-            unverified_message.verify(self.ciphersuite(), provider.crypto(), self.version())?;
+        let generation = unverified_message.generation();
+        let (content, credential) = measure(TimingPhase::Signature, || {
+            unverified_message.verify(self.ciphersuite(), provider.crypto(), self.version())
+        })?;
+        let wire_format = content.wire_format();
 
         match content.sender() {
             Sender::Member(_) | Sender::NewMemberCommit | Sender::NewMemberProposal => {
@@ -275,7 +730,15 @@ impl MlsGroup {
                             application_message.as_slice().to_owned(),
                         ))
                     }
-                    FramedContentBody::Proposal(_) => {
+                    FramedContentBody::Proposal(proposal) => {
+                        if let Proposal::Remove(remove_proposal) = proposal {
+                            if !self
+                                .public_group
+                                .admits_remove(&sender, remove_proposal, policy)
+                            {
+                                return Err(ProcessMessageError::ProtectedMemberRemoval);
+                            }
+                        }
                         let proposal = Box::new(QueuedProposal::from_authenticated_content_by_ref(
                             self.ciphersuite(),
                             provider.crypto(),
@@ -294,6 +757,8 @@ impl MlsGroup {
                             old_epoch_keypairs,
                             leaf_node_keypairs,
                             provider,
+                            policy,
+                            psk_store,
                         )?;
                         ProcessedMessageContent::StagedCommitMessage(Box::new(staged_commit))
                     }
@@ -306,16 +771,32 @@ impl MlsGroup {
                     authenticated_data,
                     content,
                     credential,
+                    wire_format,
+                    generation,
                 ))
             }
-            Sender::External(_) => {
+            Sender::External(sender_index) => {
+                let sender_index = *sender_index;
                 let sender = content.sender().clone();
                 let data = content.authenticated_data().to_owned();
                 match content.content() {
                     FramedContentBody::Application(_) => {
                         Err(ProcessMessageError::UnauthorizedExternalApplicationMessage)
                     }
-                    FramedContentBody::Proposal(Proposal::Remove(_)) => {
+                    FramedContentBody::Proposal(proposal) => {
+                        self.public_group.validate_external_proposal(
+                            sender_index,
+                            proposal,
+                            policy,
+                        )?;
+                        if let Proposal::Remove(remove_proposal) = proposal {
+                            if !self
+                                .public_group
+                                .admits_remove(&sender, remove_proposal, policy)
+                            {
+                                return Err(ProcessMessageError::ProtectedMemberRemoval);
+                            }
+                        }
                         let content = ProcessedMessageContent::ProposalMessage(Box::new(
                             QueuedProposal::from_authenticated_content_by_ref(
                                 self.ciphersuite(),
@@ -330,12 +811,10 @@ impl MlsGroup {
                             data,
                             content,
                             credential,
+                            wire_format,
+                            generation,
                         ))
                     }
-                    // TODO #151/#106
-                    FramedContentBody::Proposal(_) => {
-                        Err(ProcessMessageError::UnsupportedProposalType)
-                    }
                     FramedContentBody::Commit(_) => unimplemented!(),
                 }
             }
@@ -353,16 +832,29 @@ impl MlsGroup {
     ///  - ValSem006
     ///  - ValSem007 MembershipTag presence
     ///  - https://validation.openmls.tech/#valn1202
+    #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn decrypt_message(
         &mut self,
         crypto: &impl OpenMlsCrypto,
         message: ProtocolMessage,
-        sender_ratchet_configuration: &SenderRatchetConfiguration,
     ) -> Result<DecryptedMessage, ValidationError> {
+        self.try_decrypt_message(crypto, message)
+            .map_err(|(e, _)| e)
+    }
+
+    /// Like [`Self::decrypt_message()`], but hands a [`PrivateMessageIn`] that
+    /// can't be decrypted back together with the error.
+    fn try_decrypt_message(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+        message: ProtocolMessage,
+    ) -> Result<DecryptedMessage, (ValidationError, Option<Box<PrivateMessageIn>>)> {
         // Checks the following semantic validation:
         //  - ValSem002
         //  - ValSem003
-        self.public_group.validate_framing(&message)?;
+        self.public_group
+            .validate_framing(&message)
+            .map_err(|e| (e, None))?;
 
         let epoch = message.epoch();
 
@@ -374,11 +866,16 @@ impl MlsGroup {
                 // If the message is older than the current epoch, we need to fetch the correct secret tree first.
                 let message_secrets =
                     self.message_secrets_for_epoch(epoch).map_err(|e| match e {
-                        SecretTreeError::TooDistantInThePast => ValidationError::NoPastEpochData,
-                        _ => LibraryError::custom(
-                            "Unexpected error while retrieving message secrets for epoch.",
-                        )
-                        .into(),
+                        SecretTreeError::TooDistantInThePast => {
+                            (ValidationError::NoPastEpochData, None)
+                        }
+                        _ => (
+                            LibraryError::custom(
+                                "Unexpected error while retrieving message secrets for epoch.",
+                            )
+                            .into(),
+                            None,
+                        ),
                     })?;
                 DecryptedMessage::from_inbound_public_message(
                     *public_message,
@@ -387,15 +884,12 @@ impl MlsGroup {
                     crypto,
                     self.ciphersuite(),
                 )
+                .map_err(|e| (e, None))
             }
             ProtocolMessage::PrivateMessage(ciphertext) => {
                 // If the message is older than the current epoch, we need to fetch the correct secret tree first
-                DecryptedMessage::from_inbound_ciphertext(
-                    ciphertext,
-                    crypto,
-                    self,
-                    sender_ratchet_configuration,
-                )
+                DecryptedMessage::from_inbound_ciphertext(&ciphertext, crypto, self)
+                    .map_err(|e| (e, Some(Box::new(ciphertext))))
             }
         }
     }
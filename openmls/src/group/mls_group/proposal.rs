@@ -1,9 +1,9 @@
-use openmls_traits::{signatures::Signer, storage::StorageProvider as _, types::Ciphersuite};
+use openmls_traits::{signatures::Signer, storage::StorageWriter as _, types::Ciphersuite};
 
 use super::{
     errors::{ProposalError, ProposeAddMemberError, ProposeRemoveMemberError, RemoveProposalError},
     AddProposal, CreateGroupContextExtProposalError, CustomProposal, FramingParameters, MlsGroup,
-    PreSharedKeyProposal, Proposal, QueuedProposal, RemoveProposal, UpdateProposal,
+    PreSharedKeyProposal, Proposal, QueuedProposal, ReInitProposal, RemoveProposal, UpdateProposal,
 };
 use crate::{
     binary_tree::LeafNodeIndex,
@@ -125,6 +125,13 @@ impl MlsGroup {
         ProposalOrRefType::Proposal
     );
 
+    impl_propose_fun!(
+        propose_reinit,
+        ReInitProposal,
+        create_reinit_proposal,
+        ProposalOrRefType::Reference
+    );
+
     impl_propose_fun!(
         propose_custom_proposal_by_value,
         CustomProposal,
@@ -199,13 +206,22 @@ impl MlsGroup {
                 )),
             },
             Propose::ReInit {
-                group_id: _,
-                version: _,
-                ciphersuite: _,
-                extensions: _,
-            } => Err(ProposalError::LibraryError(LibraryError::custom(
-                "Unsupported proposal type ReInit",
-            ))),
+                group_id,
+                version,
+                ciphersuite,
+                extensions,
+            } => match ref_or_value {
+                // A ReInit proposal by value is committed with
+                // [`MlsGroup::reinit()`].
+                ProposalOrRefType::Proposal => Err(ProposalError::LibraryError(
+                    LibraryError::custom("Unsupported proposal type ReInit by value"),
+                )),
+                ProposalOrRefType::Reference => self.propose_reinit(
+                    provider,
+                    signer,
+                    ReInitProposal::new(group_id, version, ciphersuite, extensions),
+                ),
+            },
             Propose::ExternalInit(_) => Err(ProposalError::LibraryError(LibraryError::custom(
                 "Unsupported proposal type ExternalInit",
             ))),
@@ -534,6 +550,22 @@ impl MlsGroup {
         )
     }
 
+    pub(crate) fn create_reinit_proposal(
+        &self,
+        framing_parameters: FramingParameters,
+        reinit_proposal: ReInitProposal,
+        signer: &impl Signer,
+    ) -> Result<AuthenticatedContent, LibraryError> {
+        let proposal = Proposal::ReInit(reinit_proposal);
+        AuthenticatedContent::member_proposal(
+            framing_parameters,
+            self.own_leaf_index(),
+            proposal,
+            self.context(),
+            signer,
+        )
+    }
+
     pub(crate) fn create_custom_proposal(
         &self,
         framing_parameters: FramingParameters,
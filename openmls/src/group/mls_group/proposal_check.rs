@@ -0,0 +1,194 @@
+//! This module contains the dry-run validation of the pending proposals of an
+//! [`MlsGroup`]. See [`MlsGroup::validate_pending_proposals()`].
+
+use std::collections::HashMap;
+
+use super::{
+    proposal_store::{ProposalQueue, QueuedProposal},
+    MlsGroup,
+};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::hash_ref::ProposalRef,
+    framing::Sender,
+    group::{CreateCommitError, ProposalQueueError},
+    messages::proposals::Proposal,
+};
+
+/// The reason why a pending proposal would not be included in a commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalFilterReason {
+    /// The proposal is an Update of the own leaf. The commit replaces it with
+    /// an update path.
+    OwnUpdate,
+    /// Another proposal for the same leaf takes precedence, e.g. a Remove
+    /// over an Update or a more recent Update over an older one.
+    Superseded,
+    /// The proposal is a ReInit proposal by reference, which is not supported.
+    ReInitByReference,
+    /// The proposal was rejected by the policy function passed to
+    /// [`CommitBuilder::build()`](super::commit_builder::CommitBuilder::build).
+    ApplicationPolicy,
+}
+
+/// What would happen to a pending proposal if a commit was created.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingProposalStatus {
+    /// The proposal would be included in the commit.
+    Included,
+    /// The proposal would be left out of the commit.
+    Filtered(ProposalFilterReason),
+    /// The proposal is invalid and creating a commit that covers it would fail
+    /// with the given error. The proposal should be removed using
+    /// [`MlsGroup::remove_pending_proposal()`] before committing.
+    Invalid(CreateCommitError),
+}
+
+/// The result of validating a single pending proposal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingProposalCheck {
+    proposal_ref: ProposalRef,
+    status: PendingProposalStatus,
+}
+
+impl PendingProposalCheck {
+    /// Returns the reference of the proposal.
+    pub fn proposal_ref(&self) -> &ProposalRef {
+        &self.proposal_ref
+    }
+
+    /// Returns what would happen to the proposal if a commit was created.
+    pub fn status(&self) -> &PendingProposalStatus {
+        &self.status
+    }
+}
+
+impl MlsGroup {
+    /// Runs the filtering and validation that creating a commit would apply
+    /// to the pending proposals and returns, for each pending proposal, whether
+    /// it would be included in the commit, filtered out or make the commit
+    /// fail. The results are in the same order as
+    /// [`MlsGroup::pending_proposals()`].
+    ///
+    /// The proposals are validated in the order in which they would appear in
+    /// the commit. If a proposal conflicts with a proposal before it (e.g. two
+    /// Adds with the same signature key), the later one is reported as
+    /// invalid.
+    ///
+    /// Neither the group nor its pending commit are modified.
+    pub fn validate_pending_proposals(
+        &self,
+    ) -> Result<Vec<PendingProposalCheck>, CreateCommitError> {
+        let own_leaf_index = self.own_leaf_index();
+        let (filtered_queue, _contains_own_updates) =
+            ProposalQueue::filter_proposals_without_inline(
+                self.pending_proposals().cloned(),
+                own_leaf_index,
+            )
+            .map_err(|e| match e {
+                ProposalQueueError::LibraryError(e) => e.into(),
+                ProposalQueueError::ProposalNotFound => CreateCommitError::MissingProposal,
+                ProposalQueueError::UpdateFromExternalSender => {
+                    CreateCommitError::WrongProposalSenderType
+                }
+            })?;
+
+        // Validate the proposals one at a time, together with all valid
+        // proposals before them.
+        let mut statuses = HashMap::new();
+        let mut valid_proposals: Vec<QueuedProposal> = Vec::new();
+        for queued_proposal in filtered_queue.queued_proposals() {
+            let proposal_queue: ProposalQueue = valid_proposals
+                .iter()
+                .cloned()
+                .chain(Some(queued_proposal.clone()))
+                .collect();
+
+            let status = match self.validate_commit_proposals(&proposal_queue) {
+                Ok(()) => {
+                    valid_proposals.push(queued_proposal.clone());
+                    PendingProposalStatus::Included
+                }
+                Err(e) => PendingProposalStatus::Invalid(e),
+            };
+            statuses.insert(queued_proposal.proposal_reference(), status);
+        }
+
+        let checks = self
+            .pending_proposals()
+            .map(|queued_proposal| {
+                let proposal_ref = queued_proposal.proposal_reference();
+                let status = statuses.remove(&proposal_ref).unwrap_or_else(|| {
+                    PendingProposalStatus::Filtered(filter_reason(queued_proposal, own_leaf_index))
+                });
+                PendingProposalCheck {
+                    proposal_ref,
+                    status,
+                }
+            })
+            .collect();
+
+        Ok(checks)
+    }
+
+    /// Validates the proposals in the `proposal_queue` the way they are
+    /// validated when creating a commit.
+    pub(crate) fn validate_commit_proposals(
+        &self,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<(), CreateCommitError> {
+        let public_group = &self.public_group;
+
+        // ValSem113: All Proposals: The proposal type must be supported by all
+        // members of the group
+        public_group.validate_proposal_type_support(proposal_queue)?;
+        // ValSem101
+        // ValSem102
+        // ValSem103
+        // ValSem104
+        public_group.validate_key_uniqueness(proposal_queue, None)?;
+        // ValSem105
+        public_group.validate_add_proposals(proposal_queue)?;
+        // ValSem106
+        // ValSem109
+        public_group.validate_capabilities(proposal_queue)?;
+        // ValSem107
+        // ValSem108
+        public_group.validate_remove_proposals(proposal_queue)?;
+        public_group.validate_pre_shared_key_proposals(proposal_queue)?;
+        public_group.validate_reinit_proposals(proposal_queue)?;
+        // Validate update proposals for member commits
+        // ValSem110
+        // ValSem111
+        // ValSem112
+        public_group.validate_update_proposals(proposal_queue, self.own_leaf_index())?;
+
+        // ValSem208
+        // ValSem209
+        public_group.validate_group_context_extensions_proposal(proposal_queue)?;
+
+        if proposal_queue
+            .remove_proposals()
+            .any(|remove| remove.remove_proposal().removed() == self.own_leaf_index())
+        {
+            return Err(CreateCommitError::CannotRemoveSelf);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the reason why the `queued_proposal` was filtered out when creating
+/// a commit.
+pub(super) fn filter_reason(
+    queued_proposal: &QueuedProposal,
+    own_leaf_index: LeafNodeIndex,
+) -> ProposalFilterReason {
+    match (queued_proposal.proposal(), queued_proposal.sender()) {
+        (Proposal::Update(_), Sender::Member(sender)) if *sender == own_leaf_index => {
+            ProposalFilterReason::OwnUpdate
+        }
+        (Proposal::ReInit(_), _) => ProposalFilterReason::ReInitByReference,
+        _ => ProposalFilterReason::Superseded,
+    }
+}
@@ -572,6 +572,7 @@ impl ProposalQueue {
         let mut proposal_pool: HashMap<ProposalRef, QueuedProposal> = HashMap::new();
         let mut contains_own_updates = false;
         let mut contains_external_init = false;
+        let mut contains_reinit = false;
 
         // Parse proposals and build adds and member list
         for queued_proposal in iter {
@@ -614,7 +615,13 @@ impl ProposalQueue {
                     proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
                 }
                 Proposal::ReInit(_) => {
-                    // TODO #751: Only keep one ReInit
+                    // Only use the first ReInit proposal we find, no matter if
+                    // it is committed by value or by reference. Validation
+                    // makes sure that it is the only proposal in the commit.
+                    if !contains_reinit {
+                        valid_proposals.add(queued_proposal.proposal_reference());
+                        contains_reinit = true;
+                    }
                     proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
                 }
                 Proposal::ExternalInit(_) => {
@@ -0,0 +1,262 @@
+//! # Resumption PSK backups
+//!
+//! This module allows exporting resumption PSKs of past epochs of an
+//! [`MlsGroup`] and importing them on another device of the same user, e.g. a
+//! device that joined the group later. This lets the new device take part in
+//! resumption flows, like re-initializations or branches, that reference
+//! epochs from before it joined.
+//!
+//! The exported [`ResumptionPskBackup`] is encrypted under a
+//! [`ResumptionPskBackupKey`] that is shared between the devices out of band.
+//! Resumption PSKs prove membership in the epochs they belong to, so the key
+//! must only be shared with devices of the same user.
+
+use openmls_traits::{
+    crypto::OpenMlsCrypto,
+    random::OpenMlsRand,
+    storage::StorageWriter as _,
+    types::{Ciphersuite, CryptoError},
+};
+use tls_codec::{
+    Deserialize as TlsDeserializeTrait, Serialize as TlsSerializeTrait, TlsDeserialize,
+    TlsDeserializeBytes, TlsSerialize, TlsSize, VLBytes,
+};
+
+use super::{
+    errors::{ExportResumptionPskError, ImportResumptionPskError},
+    *,
+};
+use crate::{
+    ciphersuite::{AeadKey, AeadNonce, Secret, NONCE_BYTES},
+    schedule::ResumptionPskSecret,
+};
+
+const RESUMPTION_PSK_BACKUP_LABEL: &str = "resumption psk backup";
+
+/// A secret shared between the devices of a user that is used to encrypt
+/// [`ResumptionPskBackup`]s.
+///
+/// The key used to encrypt the backup of a group is derived from this secret
+/// and the group ID, so the same secret can be used for multiple groups.
+pub struct ResumptionPskBackupKey {
+    secret: Secret,
+}
+
+impl std::fmt::Debug for ResumptionPskBackupKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResumptionPskBackupKey")
+            .field("secret", &self.secret)
+            .finish()
+    }
+}
+
+impl ResumptionPskBackupKey {
+    /// Creates a [`ResumptionPskBackupKey`] for the given `ciphersuite` from
+    /// the given secret bytes. Returns [`CryptoError::InvalidLength`] if the
+    /// length of `bytes` is not the hash length of the `ciphersuite`.
+    pub fn from_bytes(ciphersuite: Ciphersuite, bytes: &[u8]) -> Result<Self, CryptoError> {
+        if bytes.len() != ciphersuite.hash_length() {
+            return Err(CryptoError::InvalidLength);
+        }
+
+        Ok(Self {
+            secret: Secret::from_slice(bytes),
+        })
+    }
+
+    /// Generates a new random [`ResumptionPskBackupKey`] for the given
+    /// `ciphersuite`.
+    pub fn random(ciphersuite: Ciphersuite, rand: &impl OpenMlsRand) -> Result<Self, CryptoError> {
+        Ok(Self {
+            secret: Secret::random(ciphersuite, rand)?,
+        })
+    }
+
+    fn aead_key(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        group_id: &GroupId,
+    ) -> Result<AeadKey, LibraryError> {
+        let secret = self
+            .secret
+            .kdf_expand_label(
+                crypto,
+                ciphersuite,
+                RESUMPTION_PSK_BACKUP_LABEL,
+                group_id.as_slice(),
+                ciphersuite.aead_key_length(),
+            )
+            .map_err(LibraryError::unexpected_crypto_error)?;
+        Ok(AeadKey::from_secret(secret, ciphersuite))
+    }
+}
+
+/// Encrypted resumption PSKs of a group, exported with
+/// [`MlsGroup::export_resumption_psks()`] and imported with
+/// [`MlsGroup::import_resumption_psks()`].
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct ResumptionPskBackup {
+    group_id: GroupId,
+    ciphersuite: Ciphersuite,
+    epochs: Vec<GroupEpoch>,
+    nonce: VLBytes,
+    ciphertext: VLBytes,
+}
+
+impl ResumptionPskBackup {
+    /// Returns the ID of the group the backup belongs to.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epochs of the resumption PSKs contained in the backup.
+    pub fn epochs(&self) -> &[GroupEpoch] {
+        &self.epochs
+    }
+}
+
+// The unencrypted part of the backup, which is authenticated as AAD.
+#[derive(TlsSerialize, TlsSize)]
+struct ResumptionPskBackupHeader<'a> {
+    group_id: &'a GroupId,
+    ciphersuite: &'a Ciphersuite,
+    epochs: &'a [GroupEpoch],
+}
+
+// The encrypted part of the backup.
+#[derive(Debug, TlsSerialize, TlsDeserialize, TlsSize)]
+struct ResumptionPskEntry {
+    epoch: GroupEpoch,
+    secret: VLBytes,
+}
+
+impl MlsGroup {
+    /// Exports the resumption PSKs of the given `epochs` as a
+    /// [`ResumptionPskBackup`] encrypted under `key`.
+    ///
+    /// Returns [`ExportResumptionPskError::UnknownEpoch`] if the resumption
+    /// PSK of one of the epochs isn't available, e.g. because it was evicted
+    /// from the store. The number of resumption PSKs that are kept is
+    /// configured in the [`MlsGroupJoinConfig`].
+    pub fn export_resumption_psks(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        rand: &impl OpenMlsRand,
+        key: &ResumptionPskBackupKey,
+        epochs: &[GroupEpoch],
+    ) -> Result<ResumptionPskBackup, ExportResumptionPskError> {
+        let entries = epochs
+            .iter()
+            .map(|&epoch| {
+                self.resumption_psk_store
+                    .get(epoch)
+                    .map(|secret| ResumptionPskEntry {
+                        epoch,
+                        secret: secret.as_slice().into(),
+                    })
+                    .ok_or(ExportResumptionPskError::UnknownEpoch(epoch))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let payload = entries
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+
+        let group_id = self.group_id().clone();
+        let ciphersuite = self.ciphersuite();
+        let epochs = epochs.to_vec();
+        let aad = ResumptionPskBackupHeader {
+            group_id: &group_id,
+            ciphersuite: &ciphersuite,
+            epochs: &epochs,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+
+        let nonce: [u8; NONCE_BYTES] = rand
+            .random_array()
+            .map_err(|_| LibraryError::custom("Not enough randomness"))?;
+        let nonce = AeadNonce::from_slice(&nonce)
+            .ok_or_else(|| LibraryError::custom("Invalid nonce length"))?;
+        let ciphertext = key
+            .aead_key(crypto, ciphersuite, &group_id)?
+            .aead_seal(crypto, &payload, &aad, &nonce)
+            .map_err(LibraryError::unexpected_crypto_error)?;
+
+        Ok(ResumptionPskBackup {
+            group_id,
+            ciphersuite,
+            epochs,
+            nonce: nonce.as_slice().into(),
+            ciphertext: ciphertext.into(),
+        })
+    }
+
+    /// Imports the resumption PSKs in the `backup` into the resumption PSK
+    /// store of the group and persists it. Resumption PSKs of epochs that are
+    /// already in the store are skipped.
+    ///
+    /// The size of the store is limited, see
+    /// [`MlsGroup::set_number_of_resumption_psks()`], so importing resumption
+    /// PSKs may evict others. Returns the epochs of the imported resumption
+    /// PSKs.
+    pub fn import_resumption_psks<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        key: &ResumptionPskBackupKey,
+        backup: &ResumptionPskBackup,
+    ) -> Result<Vec<GroupEpoch>, ImportResumptionPskError<Provider::StorageError>> {
+        if &backup.group_id != self.group_id() || backup.ciphersuite != self.ciphersuite() {
+            return Err(ImportResumptionPskError::GroupMismatch);
+        }
+
+        let aad = ResumptionPskBackupHeader {
+            group_id: &backup.group_id,
+            ciphersuite: &backup.ciphersuite,
+            epochs: &backup.epochs,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+        let nonce = AeadNonce::from_slice(backup.nonce.as_slice())
+            .ok_or(ImportResumptionPskError::MalformedBackup)?;
+        let payload = key
+            .aead_key(provider.crypto(), backup.ciphersuite, &backup.group_id)?
+            .aead_open(
+                provider.crypto(),
+                backup.ciphertext.as_slice(),
+                &aad,
+                &nonce,
+            )
+            .map_err(|_| ImportResumptionPskError::DecryptionFailed)?;
+        let entries = Vec::<ResumptionPskEntry>::tls_deserialize_exact(payload)
+            .map_err(|_| ImportResumptionPskError::MalformedBackup)?;
+        if !entries
+            .iter()
+            .map(|entry| entry.epoch)
+            .eq(backup.epochs.iter().copied())
+        {
+            return Err(ImportResumptionPskError::MalformedBackup);
+        }
+
+        let mut imported = Vec::new();
+        for entry in entries {
+            if self.resumption_psk_store.get(entry.epoch).is_some() {
+                continue;
+            }
+            self.resumption_psk_store.add(
+                entry.epoch,
+                ResumptionPskSecret::from_slice(entry.secret.as_slice()),
+            );
+            imported.push(entry.epoch);
+        }
+
+        provider
+            .storage()
+            .write_resumption_psk_store(self.group_id(), &self.resumption_psk_store)
+            .map_err(ImportResumptionPskError::StorageError)?;
+
+        Ok(imported)
+    }
+}
@@ -0,0 +1,250 @@
+//! MLS group re-initialization
+//!
+//! This module contains the functions to re-initialize an [`MlsGroup`] with
+//! new parameters, e.g. to upgrade it to a stronger ciphersuite.
+
+use errors::EmptyInputError;
+use openmls_traits::signatures::Signer;
+
+use super::{
+    builder::MlsGroupBuilder,
+    errors::{ReInitError, ReInitSuccessorError},
+    *,
+};
+use crate::{
+    credentials::CredentialWithKey,
+    group::MlsGroupCreateConfig,
+    key_packages::KeyPackage,
+    schedule::{
+        psk::{Psk, ResumptionPsk, ResumptionPskUsage},
+        PreSharedKeyId,
+    },
+    treesync::RatchetTreeIn,
+};
+
+impl MlsGroup {
+    /// Re-initializes the group with a new group ID, ciphersuite and group
+    /// context extensions, e.g. to upgrade the group to a stronger
+    /// ciphersuite.
+    ///
+    /// This creates a commit that only contains a ReInit proposal. The commit
+    /// has to be distributed to all group members and merged like any other
+    /// commit, after which the group is in the
+    /// [`MlsGroupState::ReInitialized`] state and can't be used to send
+    /// messages anymore. The group is then succeeded by a new group:
+    ///
+    /// 1. One of the members creates the new group using
+    ///    [`MlsGroup::reinit_successor()`], with a KeyPackage for the new
+    ///    ciphersuite of each of the other members, and distributes the
+    ///    resulting [`Welcome`].
+    /// 2. The other members join the new group using
+    ///    [`MlsGroup::join_reinit_successor()`], which verifies that the new
+    ///    group is linked to the old one through its resumption PSK.
+    ///
+    /// Once the new group is established, the old group can be deleted.
+    ///
+    /// Returns the commit and an optional [`GroupInfo`], which will be
+    /// [`Some`] if the group has the `use_ratchet_tree_extension` flag set.
+    pub fn reinit<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        group_id: GroupId,
+        ciphersuite: Ciphersuite,
+        extensions: Extensions,
+    ) -> Result<(MlsMessageOut, Option<GroupInfo>), ReInitError<Provider::StorageError>> {
+        self.is_operational()?;
+
+        let reinit_proposal =
+            ReInitProposal::new(group_id, self.version(), ciphersuite, extensions);
+
+        // A ReInit proposal must be the only proposal in the commit, so we
+        // don't commit to the proposals in the proposal store.
+        let bundle = self
+            .commit_builder()
+            .consume_proposal_store(false)
+            .add_proposal(Proposal::ReInit(reinit_proposal))
+            .load_psks(provider.storage())?
+            .build(provider.rand(), provider.crypto(), signer, |_| true)?
+            .stage_commit(provider)?;
+
+        let (commit, _, group_info) = bundle.into_contents();
+
+        self.reset_aad();
+
+        Ok((commit, group_info))
+    }
+
+    /// Returns the ReInit proposal this group was re-initialized with, or
+    /// `None` if the group has not been re-initialized.
+    pub fn reinit_proposal(&self) -> Option<&ReInitProposal> {
+        match &self.group_state {
+            MlsGroupState::ReInitialized(reinit_proposal) => Some(reinit_proposal),
+            _ => None,
+        }
+    }
+
+    /// Creates the group that succeeds this re-initialized group and adds the
+    /// members owning the given `key_packages` to it.
+    ///
+    /// The new group is configured using `mls_group_create_config`, except for
+    /// its group ID, ciphersuite and group context extensions, which are taken
+    /// from the ReInit proposal. The resumption PSK of the last epoch of this
+    /// group is injected into the key schedule of the new group, which allows
+    /// the new members to verify that the new group succeeds this group.
+    ///
+    /// Returns the new group, which is already at epoch 1, and the
+    /// [`Welcome`] for the new members. Once the new group is created, the
+    /// resumption PSK is deleted from the storage. If the new members can't be
+    /// added, the new group is removed from the storage again.
+    pub fn reinit_successor<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        signer: &impl Signer,
+        mls_group_create_config: &MlsGroupCreateConfig,
+        credential_with_key: CredentialWithKey,
+        key_packages: &[KeyPackage],
+    ) -> Result<(MlsGroup, MlsMessageOut), ReInitSuccessorError<Provider::StorageError>> {
+        let reinit_proposal = self
+            .reinit_proposal()
+            .ok_or(ReInitSuccessorError::NotReInitialized)?;
+        if !reinit_proposal.version().is_supported() {
+            return Err(ReInitSuccessorError::UnsupportedMlsVersion);
+        }
+        if key_packages.is_empty() {
+            return Err(EmptyInputError::AddMembers.into());
+        }
+
+        let mut mls_group_create_config = mls_group_create_config.clone();
+        mls_group_create_config.ciphersuite = reinit_proposal.ciphersuite();
+        mls_group_create_config.group_context_extensions = reinit_proposal.extensions().clone();
+
+        let reinit_psk = PreSharedKeyId::new(
+            reinit_proposal.ciphersuite(),
+            provider.rand(),
+            Psk::Resumption(self.reinit_psk()),
+        )
+        .map_err(LibraryError::unexpected_crypto_error)?;
+
+        let mut successor = MlsGroupBuilder::new()
+            .with_group_id(reinit_proposal.group_id().clone())
+            .build_internal(
+                provider,
+                signer,
+                credential_with_key,
+                Some(mls_group_create_config),
+            )?;
+
+        match successor.add_reinit_members(provider, signer, key_packages, reinit_psk) {
+            Ok(welcome) => {
+                self.delete_reinit_psk(provider.storage())?;
+                Ok((successor, welcome))
+            }
+            Err(e) => {
+                successor
+                    .delete(provider.storage())
+                    .map_err(ReInitSuccessorError::StorageError)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Joins the group that succeeds this re-initialized group using the
+    /// [`Welcome`] created by [`MlsGroup::reinit_successor()`].
+    ///
+    /// Besides the usual validation of the [`Welcome`], this checks that the
+    /// new group uses the resumption PSK of the last epoch of this group and
+    /// that its parameters match the ReInit proposal. The new group uses the
+    /// same [`MlsGroupJoinConfig`] as this group. Once the new group is
+    /// joined, the resumption PSK is deleted from the storage.
+    pub fn join_reinit_successor<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        welcome: Welcome,
+        ratchet_tree: Option<RatchetTreeIn>,
+    ) -> Result<MlsGroup, ReInitSuccessorError<Provider::StorageError>> {
+        let reinit_proposal = self
+            .reinit_proposal()
+            .ok_or(ReInitSuccessorError::NotReInitialized)?;
+
+        let processed_welcome =
+            ProcessedWelcome::new_from_welcome(provider, self.configuration(), welcome)?;
+
+        // The resumption PSK was already used to decrypt the Welcome, so it's
+        // sufficient to check that it's there.
+        let reinit_psk = Psk::Resumption(self.reinit_psk());
+        if !processed_welcome
+            .psks()
+            .iter()
+            .any(|psk_id| psk_id.psk() == &reinit_psk)
+        {
+            return Err(ReInitSuccessorError::MissingReInitPsk);
+        }
+
+        let staged_welcome = processed_welcome.into_staged_welcome(provider, ratchet_tree)?;
+
+        let group_context = staged_welcome.group_context();
+        if group_context.group_id() != reinit_proposal.group_id()
+            || group_context.protocol_version() != reinit_proposal.version()
+            || group_context.ciphersuite() != reinit_proposal.ciphersuite()
+            || group_context.extensions() != reinit_proposal.extensions()
+        {
+            return Err(ReInitSuccessorError::SuccessorMismatch);
+        }
+
+        let successor = staged_welcome.into_group(provider)?;
+        self.delete_reinit_psk(provider.storage())?;
+
+        Ok(successor)
+    }
+
+    /// Returns the resumption PSK that links this group to its successor.
+    fn reinit_psk(&self) -> ResumptionPsk {
+        ResumptionPsk::new(
+            ResumptionPskUsage::Reinit,
+            self.group_id().clone(),
+            self.context().epoch(),
+        )
+    }
+
+    /// Deletes the resumption PSK that links this group to its successor
+    /// from the storage.
+    fn delete_reinit_psk<Storage: StorageProvider>(
+        &self,
+        storage: &Storage,
+    ) -> Result<(), ReInitSuccessorError<Storage::Error>> {
+        storage
+            .delete_psk(&Psk::Resumption(self.reinit_psk()))
+            .map_err(ReInitSuccessorError::StorageError)
+    }
+
+    /// Adds the members of a re-initialized group to its freshly created
+    /// successor and merges the commit. The resumption PSK of the
+    /// re-initialized group is proposed in the same commit, which is
+    /// allowed because it creates the first epoch of the successor.
+    fn add_reinit_members<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        signer: &impl Signer,
+        key_packages: &[KeyPackage],
+        reinit_psk: PreSharedKeyId,
+    ) -> Result<MlsMessageOut, ReInitSuccessorError<Provider::StorageError>> {
+        let bundle = self
+            .commit_builder()
+            .propose_adds(key_packages.iter().cloned())
+            .add_proposal(Proposal::PreSharedKey(PreSharedKeyProposal::new(
+                reinit_psk,
+            )))
+            .load_psks(provider.storage())?
+            .build(provider.rand(), provider.crypto(), signer, |_| true)?
+            .stage_commit(provider)?;
+
+        let welcome = bundle.to_welcome_msg().ok_or(LibraryError::custom(
+            "No secrets to generate commit message.",
+        ))?;
+
+        self.merge_pending_commit(provider)?;
+
+        Ok(welcome)
+    }
+}
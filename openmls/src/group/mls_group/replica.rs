@@ -0,0 +1,499 @@
+//! # Read-only replicas
+//!
+//! This module allows a client (the primary) to keep read-only replicas of an
+//! [`MlsGroup`], e.g. on other devices or services of the same user, in sync
+//! without the replicas having to process the messages of the group
+//! themselves.
+//!
+//! After processing a message or merging a commit, the primary exports the
+//! resulting change of its state as a [`ReplicaDelta`]. The delta is encrypted
+//! under a [`ReplicaSyncKey`] that is shared between the primary and its
+//! replicas out of band. The replicas apply the delta using
+//! [`MlsGroup::apply_replica_delta()`].
+//!
+//! Each delta carries a sequence number that the primary increments with every
+//! export. Replicas only apply deltas that are strictly newer than the last
+//! one they applied, so deltas can't be replayed to roll a replica back. Both
+//! the primary and the replicas persist the last sequence number of a group in
+//! the storage. Storage providers that don't implement the replica sequence
+//! methods can't be used with replicas.
+//!
+//! Replicas must not create or process messages of the group themselves.
+//! Doing so would advance their secret trees independently of the primary and
+//! lead to the reuse of keys.
+
+use openmls_traits::{
+    crypto::OpenMlsCrypto,
+    random::OpenMlsRand,
+    storage::StorageWriter as _,
+    types::{Ciphersuite, CryptoError},
+};
+use serde::{Deserialize, Serialize};
+use tls_codec::{
+    Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
+    VLBytes,
+};
+
+use super::{
+    errors::{ApplyReplicaDeltaError, ExportReplicaDeltaError},
+    *,
+};
+use crate::{
+    ciphersuite::{AeadKey, AeadNonce, Secret, NONCE_BYTES},
+    treesync::TreeSync,
+};
+
+const REPLICA_KEY_LABEL: &str = "replica key";
+
+/// A secret shared between a primary and its replicas that is used to encrypt
+/// [`ReplicaDelta`]s.
+///
+/// The key used to encrypt the deltas of a group is derived from this secret
+/// and the group ID, so the same secret can be used for multiple groups.
+pub struct ReplicaSyncKey {
+    secret: Secret,
+}
+
+impl std::fmt::Debug for ReplicaSyncKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplicaSyncKey")
+            .field("secret", &"***")
+            .finish()
+    }
+}
+
+impl ReplicaSyncKey {
+    /// Creates a [`ReplicaSyncKey`] for the given `ciphersuite` from the given
+    /// secret bytes. Returns [`CryptoError::InvalidLength`] if the length of
+    /// `bytes` is not the hash length of the `ciphersuite`.
+    pub fn from_bytes(ciphersuite: Ciphersuite, bytes: &[u8]) -> Result<Self, CryptoError> {
+        if bytes.len() != ciphersuite.hash_length() {
+            return Err(CryptoError::InvalidLength);
+        }
+
+        Ok(Self {
+            secret: Secret::from_slice(bytes),
+        })
+    }
+
+    /// Generates a new random [`ReplicaSyncKey`] for the given `ciphersuite`.
+    pub fn random(ciphersuite: Ciphersuite, rand: &impl OpenMlsRand) -> Result<Self, CryptoError> {
+        Ok(Self {
+            secret: Secret::random(ciphersuite, rand)?,
+        })
+    }
+
+    fn aead_key(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        group_id: &GroupId,
+    ) -> Result<AeadKey, LibraryError> {
+        let secret = self
+            .secret
+            .kdf_expand_label(
+                crypto,
+                ciphersuite,
+                REPLICA_KEY_LABEL,
+                group_id.as_slice(),
+                ciphersuite.aead_key_length(),
+            )
+            .map_err(LibraryError::unexpected_crypto_error)?;
+        Ok(AeadKey::from_secret(secret, ciphersuite))
+    }
+}
+
+/// The part of the group state contained in a [`ReplicaDelta`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+#[repr(u8)]
+pub enum ReplicaDeltaScope {
+    /// The message secrets of the current epoch, i.e. the state of the
+    /// sender ratchets after processing or creating application messages.
+    /// Replicas can only apply this delta if they are in the same epoch.
+    MessageSecrets = 1,
+    /// The complete state of a new epoch after merging a commit or joining
+    /// the group. Replicas can apply this delta if they are in an earlier
+    /// epoch, and new replicas can be created from it using
+    /// [`MlsGroup::new_replica()`].
+    Epoch = 2,
+}
+
+/// An encrypted change of the state of a group, exported by a primary to be
+/// applied by its read-only replicas.
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct ReplicaDelta {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    scope: ReplicaDeltaScope,
+    sequence: u64,
+    nonce: VLBytes,
+    ciphertext: VLBytes,
+}
+
+impl ReplicaDelta {
+    /// Returns the ID of the group the delta belongs to.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch of the group state contained in the delta.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the part of the group state contained in the delta.
+    pub fn scope(&self) -> ReplicaDeltaScope {
+        self.scope
+    }
+
+    /// Returns the sequence number of the delta.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// The sequence number of the last [`ReplicaDelta`] that was exported by a
+/// primary or applied by a replica.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub(crate) struct ReplicaSequence(u64);
+
+impl ReplicaSequence {
+    fn load<Storage: StorageProvider>(
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<Self, Storage::Error> {
+        Ok(storage.replica_sequence(group_id)?.unwrap_or_default())
+    }
+
+    /// Writes the sequence number to the `storage` and returns whether it was
+    /// persisted.
+    fn store<Storage: StorageProvider>(
+        self,
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<bool, Storage::Error> {
+        storage.write_replica_sequence(group_id, &self)?;
+        Ok(Self::load(storage, group_id)? == self)
+    }
+}
+
+// The unencrypted part of the delta, which is authenticated as AAD.
+#[derive(TlsSerialize, TlsSize)]
+struct ReplicaDeltaHeader<'a> {
+    group_id: &'a GroupId,
+    epoch: &'a GroupEpoch,
+    ciphersuite: &'a Ciphersuite,
+    scope: &'a ReplicaDeltaScope,
+    sequence: &'a u64,
+}
+
+// The encrypted part of an epoch delta. The state of the group can't be
+// cloned, so it is serialized from references and deserialized into the owned
+// `EpochState`.
+#[derive(Serialize)]
+struct EpochStateRef<'a> {
+    treesync: &'a TreeSync,
+    group_context: &'a GroupContext,
+    interim_transcript_hash: &'a [u8],
+    confirmation_tag: &'a ConfirmationTag,
+    group_epoch_secrets: &'a GroupEpochSecrets,
+    message_secrets_store: &'a MessageSecretsStore,
+    resumption_psk_store: &'a ResumptionPskStore,
+    own_leaf_index: &'a LeafNodeIndex,
+    group_state: &'a MlsGroupState,
+}
+
+#[derive(Deserialize)]
+struct EpochState {
+    treesync: TreeSync,
+    group_context: GroupContext,
+    interim_transcript_hash: Vec<u8>,
+    confirmation_tag: ConfirmationTag,
+    group_epoch_secrets: GroupEpochSecrets,
+    message_secrets_store: MessageSecretsStore,
+    resumption_psk_store: ResumptionPskStore,
+    own_leaf_index: LeafNodeIndex,
+    group_state: MlsGroupState,
+}
+
+impl MlsGroup {
+    /// Exports the given `scope` of the current state of the group as a
+    /// [`ReplicaDelta`] encrypted under `key`.
+    ///
+    /// A [`ReplicaDeltaScope::MessageSecrets`] delta should be exported after
+    /// creating or processing application messages, and a
+    /// [`ReplicaDeltaScope::Epoch`] delta after merging a commit. Every export
+    /// increments the persisted sequence number of the group.
+    ///
+    /// Returns [`ExportReplicaDeltaError::SequenceNotPersisted`] if the
+    /// storage doesn't implement
+    /// [`StorageWriter::write_replica_sequence()`](openmls_traits::storage::StorageWriter::write_replica_sequence).
+    pub fn export_replica_delta<Provider: OpenMlsProvider>(
+        &self,
+        provider: &Provider,
+        key: &ReplicaSyncKey,
+        scope: ReplicaDeltaScope,
+    ) -> Result<ReplicaDelta, ExportReplicaDeltaError<Provider::StorageError>> {
+        let storage = provider.storage();
+        let sequence = ReplicaSequence::load(storage, self.group_id())
+            .map_err(ExportReplicaDeltaError::StorageError)?
+            .0
+            .checked_add(1)
+            .ok_or_else(|| LibraryError::custom("Replica sequence number overflow"))?;
+
+        let payload = match scope {
+            ReplicaDeltaScope::MessageSecrets => serde_json::to_vec(&self.message_secrets_store),
+            ReplicaDeltaScope::Epoch => serde_json::to_vec(&EpochStateRef {
+                treesync: self.public_group.treesync(),
+                group_context: self.public_group.group_context(),
+                interim_transcript_hash: self.public_group.interim_transcript_hash(),
+                confirmation_tag: self.public_group.confirmation_tag(),
+                group_epoch_secrets: &self.group_epoch_secrets,
+                message_secrets_store: &self.message_secrets_store,
+                resumption_psk_store: &self.resumption_psk_store,
+                own_leaf_index: &self.own_leaf_index,
+                group_state: &self.group_state,
+            }),
+        }
+        .map_err(|_| LibraryError::custom("Could not serialize the group state"))?;
+
+        let group_id = self.group_id().clone();
+        let epoch = self.epoch();
+        let ciphersuite = self.ciphersuite();
+        let aad = ReplicaDeltaHeader {
+            group_id: &group_id,
+            epoch: &epoch,
+            ciphersuite: &ciphersuite,
+            scope: &scope,
+            sequence: &sequence,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+
+        let nonce: [u8; NONCE_BYTES] = provider
+            .rand()
+            .random_array()
+            .map_err(|_| LibraryError::custom("Not enough randomness"))?;
+        let nonce = AeadNonce::from_slice(&nonce)
+            .ok_or_else(|| LibraryError::custom("Invalid nonce length"))?;
+        let ciphertext = key
+            .aead_key(provider.crypto(), ciphersuite, &group_id)?
+            .aead_seal(provider.crypto(), &payload, &aad, &nonce)
+            .map_err(LibraryError::unexpected_crypto_error)?;
+
+        // Without the persisted sequence number, the next export would reuse
+        // it and the replicas would reject the delta as stale.
+        if !ReplicaSequence(sequence)
+            .store(storage, &group_id)
+            .map_err(ExportReplicaDeltaError::StorageError)?
+        {
+            return Err(ExportReplicaDeltaError::SequenceNotPersisted);
+        }
+
+        Ok(ReplicaDelta {
+            group_id,
+            epoch,
+            ciphersuite,
+            scope,
+            sequence,
+            nonce: nonce.as_slice().into(),
+            ciphertext: ciphertext.into(),
+        })
+    }
+
+    /// Applies a [`ReplicaDelta`] exported by the primary to this replica and
+    /// persists the new state.
+    ///
+    /// The sequence number of the delta must be greater than the one of the
+    /// last delta applied to the replica. A
+    /// [`ReplicaDeltaScope::MessageSecrets`] delta must be of the current
+    /// epoch of the replica, and a [`ReplicaDeltaScope::Epoch`] delta of a
+    /// later epoch. All pending proposals of the replica are discarded when an
+    /// epoch delta is applied.
+    ///
+    /// Returns [`ApplyReplicaDeltaError::SequenceNotPersisted`] if the storage
+    /// doesn't persist the sequence number, since replayed deltas couldn't be
+    /// detected otherwise.
+    pub fn apply_replica_delta<Provider: OpenMlsProvider>(
+        &mut self,
+        provider: &Provider,
+        key: &ReplicaSyncKey,
+        delta: &ReplicaDelta,
+    ) -> Result<(), ApplyReplicaDeltaError<Provider::StorageError>> {
+        if &delta.group_id != self.group_id() {
+            return Err(ApplyReplicaDeltaError::GroupIdMismatch);
+        }
+        let epoch_matches = match delta.scope {
+            ReplicaDeltaScope::MessageSecrets => delta.epoch == self.epoch(),
+            ReplicaDeltaScope::Epoch => delta.epoch > self.epoch(),
+        };
+        if !epoch_matches {
+            return Err(ApplyReplicaDeltaError::EpochMismatch);
+        }
+        let last_sequence = ReplicaSequence::load(provider.storage(), self.group_id())
+            .map_err(ApplyReplicaDeltaError::StorageError)?;
+        if ReplicaSequence(delta.sequence) <= last_sequence {
+            return Err(ApplyReplicaDeltaError::StaleDelta);
+        }
+
+        let payload = delta.decrypt(provider.crypto(), key)?;
+        match delta.scope {
+            ReplicaDeltaScope::MessageSecrets => {
+                let message_secrets_store = serde_json::from_slice(&payload)
+                    .map_err(|_| ApplyReplicaDeltaError::MalformedDelta)?;
+                store_applied_sequence(provider.storage(), self.group_id(), delta)?;
+                self.message_secrets_store = message_secrets_store;
+                provider
+                    .storage()
+                    .write_message_secrets(self.group_id(), &self.message_secrets_store)
+                    .map_err(ApplyReplicaDeltaError::StorageError)?;
+            }
+            ReplicaDeltaScope::Epoch => {
+                let state = delta.decode_epoch_state(&payload)?;
+                let storage = provider.storage();
+                store_applied_sequence(storage, self.group_id(), delta)?;
+                storage
+                    .clear_proposal_queue::<GroupId, ProposalRef>(self.group_id())
+                    .map_err(ApplyReplicaDeltaError::StorageError)?;
+                storage
+                    .delete_own_leaf_nodes(self.group_id())
+                    .map_err(ApplyReplicaDeltaError::StorageError)?;
+
+                self.public_group = PublicGroup::from_parts(
+                    state.treesync,
+                    state.group_context,
+                    state.interim_transcript_hash,
+                    state.confirmation_tag,
+                );
+                self.group_epoch_secrets = state.group_epoch_secrets;
+                self.message_secrets_store = state.message_secrets_store;
+                self.resumption_psk_store = state.resumption_psk_store;
+                self.own_leaf_index = state.own_leaf_index;
+                self.own_leaf_nodes.clear();
+                self.used_external_inits = UsedExternalInits::default();
+                self.group_state = state.group_state;
+
+                self.store(storage)
+                    .map_err(ApplyReplicaDeltaError::StorageError)?;
+                storage
+                    .delete_used_external_inits(self.group_id())
+                    .map_err(ApplyReplicaDeltaError::StorageError)?;
+                // The statistics in memory may be outdated by the other replica.
+                self.reset_member_stats();
+                self.observe_member_stats(storage)
+                    .map_err(ApplyReplicaDeltaError::StorageError)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new read-only replica of a group from a
+    /// [`ReplicaDeltaScope::Epoch`] delta exported by the primary and persists
+    /// it.
+    ///
+    /// The replica is configured using `mls_group_config`.
+    pub fn new_replica<Provider: OpenMlsProvider>(
+        provider: &Provider,
+        key: &ReplicaSyncKey,
+        mls_group_config: &MlsGroupJoinConfig,
+        delta: &ReplicaDelta,
+    ) -> Result<Self, ApplyReplicaDeltaError<Provider::StorageError>> {
+        if delta.scope != ReplicaDeltaScope::Epoch {
+            return Err(ApplyReplicaDeltaError::NotAnEpochDelta);
+        }
+
+        let payload = delta.decrypt(provider.crypto(), key)?;
+        let state = delta.decode_epoch_state(&payload)?;
+        store_applied_sequence(provider.storage(), &delta.group_id, delta)?;
+
+        let group = MlsGroup {
+            mls_group_config: mls_group_config.clone(),
+            public_group: PublicGroup::from_parts(
+                state.treesync,
+                state.group_context,
+                state.interim_transcript_hash,
+                state.confirmation_tag,
+            ),
+            group_epoch_secrets: state.group_epoch_secrets,
+            own_leaf_index: state.own_leaf_index,
+            message_secrets_store: state.message_secrets_store,
+            resumption_psk_store: state.resumption_psk_store,
+            own_leaf_nodes: vec![],
+            aad: vec![],
+            used_external_inits: UsedExternalInits::default(),
+            member_stats: MemberStatsCache::default(),
+            last_operation_timing: LastOperationTiming::default(),
+            epoch_hooks: EpochHooks::default(),
+            group_state: state.group_state,
+        };
+        group
+            .store(provider.storage())
+            .map_err(ApplyReplicaDeltaError::StorageError)?;
+
+        Ok(group)
+    }
+}
+
+/// Persists the sequence number of the applied `delta` before the state of the
+/// replica is changed. Without it, replayed deltas couldn't be detected.
+fn store_applied_sequence<Storage: StorageProvider>(
+    storage: &Storage,
+    group_id: &GroupId,
+    delta: &ReplicaDelta,
+) -> Result<(), ApplyReplicaDeltaError<Storage::Error>> {
+    if !ReplicaSequence(delta.sequence)
+        .store(storage, group_id)
+        .map_err(ApplyReplicaDeltaError::StorageError)?
+    {
+        return Err(ApplyReplicaDeltaError::SequenceNotPersisted);
+    }
+    Ok(())
+}
+
+impl ReplicaDelta {
+    /// Decrypts the payload of the delta and authenticates its header.
+    fn decrypt<StorageError>(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        key: &ReplicaSyncKey,
+    ) -> Result<Vec<u8>, ApplyReplicaDeltaError<StorageError>> {
+        let aad = ReplicaDeltaHeader {
+            group_id: &self.group_id,
+            epoch: &self.epoch,
+            ciphersuite: &self.ciphersuite,
+            scope: &self.scope,
+            sequence: &self.sequence,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+        let nonce = AeadNonce::from_slice(self.nonce.as_slice())
+            .ok_or(ApplyReplicaDeltaError::MalformedDelta)?;
+
+        key.aead_key(crypto, self.ciphersuite, &self.group_id)?
+            .aead_open(crypto, self.ciphertext.as_slice(), &aad, &nonce)
+            .map_err(|_| ApplyReplicaDeltaError::DecryptionFailed)
+    }
+
+    /// Decodes the payload of an epoch delta and checks that it matches the
+    /// header.
+    fn decode_epoch_state<StorageError>(
+        &self,
+        payload: &[u8],
+    ) -> Result<EpochState, ApplyReplicaDeltaError<StorageError>> {
+        let state: EpochState =
+            serde_json::from_slice(payload).map_err(|_| ApplyReplicaDeltaError::MalformedDelta)?;
+        if state.group_context.group_id() != &self.group_id
+            || state.group_context.epoch() != self.epoch
+            || state.group_context.ciphersuite() != self.ciphersuite
+        {
+            return Err(ApplyReplicaDeltaError::MalformedDelta);
+        }
+        Ok(state)
+    }
+}
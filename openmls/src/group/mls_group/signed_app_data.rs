@@ -0,0 +1,146 @@
+//! # Signed application data
+//!
+//! Applications sometimes need to sign data outside of MLS messages with the
+//! signature key of their leaf, e.g. to attest to a profile update or a
+//! receipt. Signing such data with ad-hoc prefixes risks creating signatures
+//! that are valid for MLS structs as well. [`MlsGroup::sign_app_data()`]
+//! instead signs with a dedicated label and binds the signature to the group,
+//! the leaf of the signer and an application-chosen label, and
+//! [`MlsGroup::verify_app_data()`] verifies it against the leaf of the member.
+
+use openmls_traits::{crypto::OpenMlsCrypto, signatures::Signer};
+use tls_codec::{
+    Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
+    VLBytes,
+};
+
+use super::{
+    errors::{SignAppDataError, VerifyAppDataError},
+    *,
+};
+use crate::ciphersuite::{
+    signable::{Signable, SignedStruct},
+    OpenMlsSignaturePublicKey, SignContent, Signature,
+};
+
+const SIGNED_APP_DATA_LABEL: &str = "SignedAppData";
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub(crate) struct SignedAppDataTbs {
+    group_id: GroupId,
+    signer: LeafNodeIndex,
+    label: VLBytes,
+    payload: VLBytes,
+}
+
+impl Signable for SignedAppDataTbs {
+    type SignedOutput = SignedAppData;
+
+    fn unsigned_payload(&self) -> Result<Vec<u8>, tls_codec::Error> {
+        self.tls_serialize_detached()
+    }
+
+    fn label(&self) -> &str {
+        SIGNED_APP_DATA_LABEL
+    }
+}
+
+/// An application-chosen payload signed with the signature key of a member's
+/// leaf. It is created with [`MlsGroup::sign_app_data()`] and verified with
+/// [`MlsGroup::verify_app_data()`].
+#[derive(
+    Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
+)]
+pub struct SignedAppData {
+    tbs: SignedAppDataTbs,
+    signature: Signature,
+}
+
+impl SignedStruct<SignedAppDataTbs> for SignedAppData {
+    fn from_payload(tbs: SignedAppDataTbs, signature: Signature) -> Self {
+        Self { tbs, signature }
+    }
+}
+
+impl SignedAppData {
+    /// Returns the ID of the group the data was signed in.
+    pub fn group_id(&self) -> &GroupId {
+        &self.tbs.group_id
+    }
+
+    /// Returns the leaf index of the signer.
+    pub fn signer(&self) -> LeafNodeIndex {
+        self.tbs.signer
+    }
+
+    /// Returns the application-chosen label of the signature.
+    pub fn label(&self) -> &[u8] {
+        self.tbs.label.as_slice()
+    }
+
+    /// Returns the signed payload. It must only be used after the signature
+    /// was verified with [`MlsGroup::verify_app_data()`].
+    pub fn payload(&self) -> &[u8] {
+        self.tbs.payload.as_slice()
+    }
+}
+
+impl MlsGroup {
+    /// Signs the `payload` with the signature key of the own leaf. The `label`
+    /// is chosen by the application and separates signatures for different
+    /// purposes.
+    ///
+    /// The `signer` must hold the signature key of the own leaf.
+    pub fn sign_app_data(
+        &self,
+        signer: &impl Signer,
+        label: &[u8],
+        payload: &[u8],
+    ) -> Result<SignedAppData, SignAppDataError> {
+        if !self.is_active() {
+            return Err(MlsGroupStateError::UseAfterEviction.into());
+        }
+
+        let tbs = SignedAppDataTbs {
+            group_id: self.group_id().clone(),
+            signer: self.own_leaf_index(),
+            label: label.into(),
+            payload: payload.into(),
+        };
+        Ok(tbs.sign(signer)?)
+    }
+
+    /// Verifies the signature of the `signed_app_data` against the signature
+    /// key of the signer's leaf in the current epoch.
+    pub fn verify_app_data(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signed_app_data: &SignedAppData,
+    ) -> Result<(), VerifyAppDataError> {
+        if signed_app_data.group_id() != self.group_id() {
+            return Err(VerifyAppDataError::GroupMismatch);
+        }
+        let leaf = self
+            .public_group()
+            .leaf(signed_app_data.signer())
+            .ok_or(VerifyAppDataError::UnknownMember)?;
+        let signature_key = OpenMlsSignaturePublicKey::from_signature_key(
+            leaf.signature_key().clone(),
+            self.ciphersuite().signature_algorithm(),
+        );
+
+        let payload = signed_app_data
+            .tbs
+            .unsigned_payload()
+            .map_err(LibraryError::missing_bound_check)?;
+        signature_key
+            .verify_with_label(
+                crypto,
+                &signed_app_data.signature,
+                &SignContent::new(SIGNED_APP_DATA_LABEL, payload.into()),
+            )
+            .map_err(|_| VerifyAppDataError::InvalidSignature)
+    }
+}
@@ -1,7 +1,7 @@
 use core::fmt::Debug;
 use std::mem;
 
-use openmls_traits::storage::StorageProvider;
+use openmls_traits::storage::StorageWriter as _;
 use serde::{Deserialize, Serialize};
 use tls_codec::Serialize as _;
 
@@ -9,19 +9,27 @@ use super::proposal_store::{
     QueuedAddProposal, QueuedPskProposal, QueuedRemoveProposal, QueuedUpdateProposal,
 };
 
+use super::timing::{measure, TimingPhase};
 use super::{
     super::errors::*, load_psks, Credential, Extension, GroupContext, GroupEpochSecrets, GroupId,
     JoinerSecret, KeySchedule, LeafNode, LibraryError, MessageSecrets, MlsGroup, OpenMlsProvider,
-    Proposal, ProposalQueue, PskSecret, QueuedProposal, Sender,
+    Proposal, ProposalQueue, PskSecret, QueuedProposal, ReInitProposal, Sender,
 };
 use crate::{
+    binary_tree::LeafNodeIndex,
     ciphersuite::{hash_ref::ProposalRef, Secret},
     framing::mls_auth_content::AuthenticatedContent,
-    group::public_group::{
-        diff::{apply_proposals::ApplyProposalsValues, StagedPublicGroupDiff},
-        staged_commit::PublicStagedCommitState,
+    group::{
+        public_group::{
+            diff::{apply_proposals::ApplyProposalsValues, StagedPublicGroupDiff},
+            staged_commit::PublicStagedCommitState,
+        },
+        ProcessingPolicy,
+    },
+    messages::ConfirmationTag,
+    schedule::{
+        CommitSecret, EpochAuthenticator, EpochSecrets, InitSecret, PreSharedKeyId, PskStore,
     },
-    schedule::{CommitSecret, EpochAuthenticator, EpochSecrets, InitSecret, PreSharedKeyId},
     treesync::node::encryption_keys::EncryptionKeyPair,
 };
 
@@ -29,6 +37,7 @@ impl MlsGroup {
     fn derive_epoch_secrets(
         &self,
         provider: &impl OpenMlsProvider,
+        psk_store: &impl PskStore,
         apply_proposals_values: ApplyProposalsValues,
         epoch_secrets: &GroupEpochSecrets,
         commit_secret: CommitSecret,
@@ -75,7 +84,7 @@ impl MlsGroup {
         // Fails if PSKs are missing ([valn1205](https://validation.openmls.tech/#valn1205))
         let psk_secret = {
             let psks: Vec<(&PreSharedKeyId, Secret)> = load_psks(
-                provider.storage(),
+                psk_store,
                 &self.resumption_psk_store,
                 &apply_proposals_values.presharedkeys,
             )?;
@@ -140,6 +149,8 @@ impl MlsGroup {
         old_epoch_keypairs: Vec<EncryptionKeyPair>,
         leaf_node_keypairs: Vec<EncryptionKeyPair>,
         provider: &impl OpenMlsProvider,
+        policy: &impl ProcessingPolicy,
+        psk_store: &impl PskStore,
     ) -> Result<StagedCommit, StageCommitError> {
         // Check that the sender is another member of the group
         if let Sender::Member(member) = mls_content.sender() {
@@ -150,28 +161,44 @@ impl MlsGroup {
 
         let ciphersuite = self.ciphersuite();
 
-        let (commit, proposal_queue, sender_index) = self
-            .public_group
-            .validate_commit(mls_content, provider.crypto())?;
+        let (commit, proposal_queue, sender_index) =
+            self.public_group
+                .validate_commit(mls_content, provider.crypto(), policy)?;
+
+        // Reject external commits that reuse the external init of an external
+        // commit that was already staged in this epoch.
+        if proposal_queue.queued_proposals().any(|queued_proposal| {
+            match queued_proposal.proposal() {
+                Proposal::ExternalInit(external_init) => self
+                    .used_external_inits
+                    .contains(external_init.kem_output()),
+                _ => false,
+            }
+        }) {
+            return Err(ExternalCommitValidationError::ExternalInitReused.into());
+        }
 
         // Create the provisional public group state (including the tree and
         // group context) and apply proposals.
         let mut diff = self.public_group.empty_diff();
 
-        let apply_proposals_values =
-            diff.apply_proposals(&proposal_queue, self.own_leaf_index())?;
+        let apply_proposals_values = measure(TimingPhase::TreeDiff, || {
+            diff.apply_proposals(&proposal_queue, self.own_leaf_index())
+        })?;
 
         // Determine if Commit has a path
         let (commit_secret, new_keypairs, new_leaf_keypair_option, update_path_leaf_node) =
             if let Some(path) = commit.path.clone() {
                 // Update the public group
                 // ValSem202: Path must be the right length
-                diff.apply_received_update_path(
-                    provider.crypto(),
-                    ciphersuite,
-                    sender_index,
-                    &path,
-                )?;
+                measure(TimingPhase::TreeDiff, || {
+                    diff.apply_received_update_path(
+                        provider.crypto(),
+                        ciphersuite,
+                        sender_index,
+                        &path,
+                    )
+                })?;
 
                 // Update group context
                 diff.update_group_context(
@@ -187,10 +214,11 @@ impl MlsGroup {
                         staged_diff,
                         commit.path.as_ref().map(|path| path.leaf_node().clone()),
                     );
-                    return Ok(StagedCommit::new(
+                    let staged_commit = StagedCommit::new(
                         proposal_queue,
                         StagedCommitState::PublicState(Box::new(staged_state)),
-                    ));
+                    );
+                    return self.validate_staged_commit_policy(mls_content, staged_commit, policy);
                 }
 
                 let decryption_keypairs: Vec<&EncryptionKeyPair> = old_epoch_keypairs
@@ -200,14 +228,16 @@ impl MlsGroup {
 
                 // ValSem203: Path secrets must decrypt correctly
                 // ValSem204: Public keys from Path must be verified and match the private keys from the direct path
-                let (new_keypairs, commit_secret) = diff.decrypt_path(
-                    provider.crypto(),
-                    &decryption_keypairs,
-                    self.own_leaf_index(),
-                    sender_index,
-                    path.nodes(),
-                    &apply_proposals_values.exclusion_list(),
-                )?;
+                let (new_keypairs, commit_secret) = measure(TimingPhase::PathDecryption, || {
+                    diff.decrypt_path(
+                        provider.crypto(),
+                        &decryption_keypairs,
+                        self.own_leaf_index(),
+                        sender_index,
+                        path.nodes(),
+                        &apply_proposals_values.exclusion_list(),
+                    )
+                })?;
 
                 // Check if one of our update proposals was applied. If so, we
                 // need to store that keypair separately, because after merging
@@ -245,6 +275,9 @@ impl MlsGroup {
                     // ValSem201
                     return Err(StageCommitError::RequiredPathNotFound);
                 }
+                if self.mls_group_config.force_full_commits {
+                    return Err(StageCommitError::FullCommitRequired);
+                }
 
                 // Even if there is no path, we have to update the group context.
                 diff.update_group_context(
@@ -270,6 +303,7 @@ impl MlsGroup {
         let (provisional_group_secrets, provisional_message_secrets) = self
             .derive_epoch_secrets(
                 provider,
+                psk_store,
                 apply_proposals_values,
                 self.group_epoch_secrets(),
                 commit_secret,
@@ -319,7 +353,30 @@ impl MlsGroup {
                 update_path_leaf_node,
             )));
 
-        Ok(StagedCommit::new(proposal_queue, staged_commit_state))
+        let staged_commit = StagedCommit::new(proposal_queue, staged_commit_state);
+
+        self.validate_staged_commit_policy(mls_content, staged_commit, policy)
+    }
+
+    // Check the staged commit against the application's `policy` and the
+    // installed update policy, if any.
+    fn validate_staged_commit_policy(
+        &self,
+        mls_content: &AuthenticatedContent,
+        staged_commit: StagedCommit,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<StagedCommit, StageCommitError> {
+        let staged_commit =
+            self.public_group
+                .validate_staged_commit_policy(mls_content, staged_commit, policy)?;
+        match self.epoch_hooks.update_policy() {
+            Some(update_policy) => self.public_group.validate_staged_commit_policy(
+                mls_content,
+                staged_commit,
+                update_policy,
+            ),
+            None => Ok(staged_commit),
+        }
     }
 
     /// Merges a [StagedCommit] into the group state and optionally return a [`SecretTree`]
@@ -335,6 +392,10 @@ impl MlsGroup {
         // Get all keypairs from the old epoch, so we can later store the ones
         // that are still relevant in the new epoch.
         let old_epoch_keypairs = self.read_epoch_keypairs(provider.storage());
+        let removed_leaves: Vec<LeafNodeIndex> = staged_commit
+            .remove_proposals()
+            .map(|remove| remove.remove_proposal().removed())
+            .collect();
         match staged_commit.state {
             StagedCommitState::PublicState(staged_state) => {
                 self.public_group
@@ -394,6 +455,18 @@ impl MlsGroup {
                     .into());
                 }
 
+                // Sender ratchet overrides apply to members, so the ones of
+                // removed members are dropped.
+                let overrides = &mut self.mls_group_config.sender_ratchet_overrides;
+                let override_count = overrides.len();
+                overrides.retain(|leaf_index, _| !removed_leaves.contains(leaf_index));
+                if overrides.len() != override_count {
+                    provider
+                        .storage()
+                        .write_mls_join_config(self.group_id(), &self.mls_group_config)
+                        .map_err(MergeCommitError::StorageError)?;
+                }
+
                 // Store the updated group state
                 let storage = provider.storage();
                 let group_id = self.group_id();
@@ -446,6 +519,10 @@ pub(crate) enum StagedCommitState {
 pub struct StagedCommit {
     staged_proposal_queue: ProposalQueue,
     state: StagedCommitState,
+    /// The hash of the ciphertext of the private message this commit was sent
+    /// in, if it was created by this client and encrypted.
+    #[serde(default)]
+    ciphertext_hash: Option<Vec<u8>>,
 }
 
 impl StagedCommit {
@@ -455,9 +532,22 @@ impl StagedCommit {
         StagedCommit {
             staged_proposal_queue,
             state,
+            ciphertext_hash: None,
         }
     }
 
+    /// Records the hash of the ciphertext of the private message this commit
+    /// was sent in.
+    pub(crate) fn set_ciphertext_hash(&mut self, ciphertext_hash: Vec<u8>) {
+        self.ciphertext_hash = Some(ciphertext_hash);
+    }
+
+    /// Returns the hash of the ciphertext of the private message this commit
+    /// was sent in, if it was created by this client and encrypted.
+    pub(crate) fn ciphertext_hash(&self) -> Option<&[u8]> {
+        self.ciphertext_hash.as_deref()
+    }
+
     /// Returns the Add proposals that are covered by the Commit message as in iterator over [QueuedAddProposal].
     pub fn add_proposals(&self) -> impl Iterator<Item = QueuedAddProposal> {
         self.staged_proposal_queue.add_proposals()
@@ -483,6 +573,29 @@ impl StagedCommit {
         self.staged_proposal_queue.queued_proposals()
     }
 
+    /// Returns the ReInit proposal that is covered by the Commit message, if
+    /// any. Merging such a commit re-initializes the group, see
+    /// [`MlsGroup::reinit()`].
+    pub fn reinit_proposal(&self) -> Option<&ReInitProposal> {
+        self.staged_proposal_queue
+            .queued_proposals()
+            .find_map(|queued_proposal| match queued_proposal.proposal() {
+                Proposal::ReInit(reinit_proposal) => Some(reinit_proposal),
+                _ => None,
+            })
+    }
+
+    /// Returns the KEM output of the ExternalInit proposal if this is an
+    /// external commit.
+    pub(crate) fn external_init_kem_output(&self) -> Option<&[u8]> {
+        self.staged_proposal_queue
+            .queued_proposals()
+            .find_map(|queued_proposal| match queued_proposal.proposal() {
+                Proposal::ExternalInit(external_init) => Some(external_init.kem_output()),
+                _ => None,
+            })
+    }
+
     /// Returns the leaf node of the (optional) update path.
     pub fn update_path_leaf_node(&self) -> Option<&LeafNode> {
         match self.state {
@@ -556,6 +669,20 @@ impl StagedCommit {
         }
     }
 
+    /// Returns the [`ConfirmationTag`] of the commit. After the commit was
+    /// merged, it is also returned by [`MlsGroup::confirmation_tag()`].
+    pub fn confirmation_tag(&self) -> &ConfirmationTag {
+        self.staged_diff().confirmation_tag()
+    }
+
+    /// Returns the [`StagedPublicGroupDiff`] of the staged commit state.
+    pub(crate) fn staged_diff(&self) -> &StagedPublicGroupDiff {
+        match self.state {
+            StagedCommitState::PublicState(ref ps) => ps.staged_diff(),
+            StagedCommitState::GroupMember(ref gm) => &gm.staged_diff,
+        }
+    }
+
     /// Consume this [`StagedCommit`] and return the internal [`StagedCommitState`].
     pub(crate) fn into_state(self) -> StagedCommitState {
         self.state
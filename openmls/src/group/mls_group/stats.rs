@@ -0,0 +1,270 @@
+//! # Member statistics
+//!
+//! If [`MlsGroupJoinConfig::collect_member_stats()`] is enabled, the group
+//! counts for each member, e.g. to display the health of the devices in a
+//! group:
+//!
+//! - the number of messages received from the member,
+//! - the last epoch in which the member sent a commit,
+//! - the last epoch in which the member sent any message and
+//! - the number of messages that claim to be from the member but couldn't be
+//!   decrypted.
+//!
+//! The statistics are returned by [`MlsGroup::member_stats()`], and
+//! [`MlsGroup::activity_tracker()`] turns them into an [`ActivityTracker`] to
+//! remove inactive members with
+//! [`MlsGroup::propose_remove_inactive_members()`].
+//!
+//! The counters are kept in memory and written to the storage whenever the
+//! group enters a new epoch and after every [`MEMBER_STATS_FLUSH_INTERVAL`]
+//! recorded messages. [`MlsGroup::flush_member_stats()`] writes them right
+//! away, e.g. before the application shuts down. Since the statistics are
+//! auxiliary data, failing to write them doesn't fail the processing of a
+//! message.
+//!
+//! The statistics of a member are reset when another member takes over its
+//! leaf. A message that can't be decrypted can't be authenticated either, so
+//! its failure is counted for the member named in its sender data, if that
+//! can be decrypted. Any member can send such messages in the name of
+//! another, and replayed messages are counted again, so
+//! [`MemberStats::unauthenticated_decryption_failures()`] is only a hint.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    leaf_map::LeafMap,
+    timing::{measure, TimingPhase},
+    MlsGroup,
+};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    group::{ActivityTracker, GroupEpoch},
+    storage::{StorageProvider, StorageReader},
+};
+
+/// The number of recorded messages after which the member statistics are
+/// written to the storage, if the group doesn't enter a new epoch before.
+pub const MEMBER_STATS_FLUSH_INTERVAL: usize = 32;
+
+/// The activity statistics of a member of a group. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberStats {
+    tracked_since: GroupEpoch,
+    messages_received: u64,
+    last_commit: Option<GroupEpoch>,
+    last_activity: Option<GroupEpoch>,
+    unauthenticated_decryption_failures: u64,
+}
+
+impl MemberStats {
+    fn new(tracked_since: GroupEpoch) -> Self {
+        Self {
+            tracked_since,
+            messages_received: 0,
+            last_commit: None,
+            last_activity: None,
+            unauthenticated_decryption_failures: 0,
+        }
+    }
+
+    /// Returns the epoch since which the statistics of the member are
+    /// collected.
+    pub fn tracked_since(&self) -> GroupEpoch {
+        self.tracked_since
+    }
+
+    /// Returns the number of messages received from the member.
+    pub fn messages_received(&self) -> u64 {
+        self.messages_received
+    }
+
+    /// Returns the last epoch in which the member sent a commit.
+    pub fn last_commit(&self) -> Option<GroupEpoch> {
+        self.last_commit
+    }
+
+    /// Returns the last epoch in which the member sent a message.
+    pub fn last_activity(&self) -> Option<GroupEpoch> {
+        self.last_activity
+    }
+
+    /// Returns the number of messages that couldn't be decrypted and whose
+    /// sender data names the member. The sender of such messages isn't
+    /// authenticated, see the [module documentation](self).
+    pub fn unauthenticated_decryption_failures(&self) -> u64 {
+        self.unauthenticated_decryption_failures
+    }
+
+    pub(crate) fn record_message(&mut self, epoch: GroupEpoch, is_commit: bool) {
+        self.messages_received += 1;
+        self.last_activity = self.last_activity.max(Some(epoch));
+        if is_commit {
+            self.last_commit = self.last_commit.max(Some(epoch));
+        }
+    }
+
+    pub(crate) fn record_decryption_failure(&mut self) {
+        self.unauthenticated_decryption_failures += 1;
+    }
+}
+
+/// The statistics of the members of a group as they are kept in the storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct MemberStatsStore {
+    members: LeafMap<MemberStats>,
+}
+
+impl MemberStatsStore {
+    // Updates the entries to the current members of the `group`. New members
+    // are tracked since the current epoch.
+    fn observe(&mut self, group: &MlsGroup) {
+        let epoch = group.epoch();
+        self.members.sync(
+            group,
+            |member| &member.signature_key,
+            || MemberStats::new(epoch),
+        );
+    }
+}
+
+/// The statistics of the members of a group that are kept in memory. They are
+/// loaded from the storage when they are first needed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MemberStatsCache {
+    store: Option<MemberStatsStore>,
+    unflushed: usize,
+}
+
+impl MlsGroup {
+    /// Returns the statistics of the current members of the group. Members
+    /// without recorded statistics are tracked since the current epoch.
+    ///
+    /// The statistics are only collected if
+    /// [`MlsGroupJoinConfig::collect_member_stats()`](crate::group::MlsGroupJoinConfig::collect_member_stats())
+    /// is enabled.
+    pub fn member_stats<Storage: StorageReader>(
+        &self,
+        storage: &Storage,
+    ) -> Result<BTreeMap<LeafNodeIndex, MemberStats>, Storage::Error> {
+        let store = match &self.member_stats.store {
+            Some(store) => store.clone(),
+            None => {
+                let mut store: MemberStatsStore =
+                    storage.member_stats(self.group_id())?.unwrap_or_default();
+                store.observe(self);
+                store
+            }
+        };
+        Ok(store
+            .members
+            .iter()
+            .map(|(leaf_index, stats)| (leaf_index, *stats))
+            .collect())
+    }
+
+    /// Returns an [`ActivityTracker`] for the current epoch that is
+    /// initialized with the last activity from the [`Self::member_stats()`].
+    /// Members without recorded activity are considered active in the epoch
+    /// since which they are tracked.
+    pub fn activity_tracker<Storage: StorageReader>(
+        &self,
+        storage: &Storage,
+    ) -> Result<ActivityTracker, Storage::Error> {
+        let mut tracker = ActivityTracker::new();
+        tracker.observe(self);
+        for (leaf_index, stats) in self.member_stats(storage)? {
+            let last_activity = stats.last_activity.unwrap_or(stats.tracked_since);
+            tracker.set_last_activity(leaf_index, last_activity);
+        }
+        Ok(tracker)
+    }
+
+    /// Writes the statistics that were recorded since they were last written
+    /// to the storage. See the [module documentation](self) for when this
+    /// happens automatically.
+    pub fn flush_member_stats<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        let Some(store) = &self.member_stats.store else {
+            return Ok(());
+        };
+        if self.member_stats.unflushed > 0 {
+            measure(TimingPhase::StorageIo, || {
+                storage.write_member_stats(self.group_id(), store)
+            })?;
+            self.member_stats.unflushed = 0;
+        }
+        Ok(())
+    }
+
+    /// Updates the statistics to the current members of the group and writes
+    /// them to the storage, if the collection of statistics is enabled.
+    pub(crate) fn observe_member_stats<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        if !self.configuration().collect_member_stats() {
+            return Ok(());
+        }
+        let mut store = self.load_member_stats(storage)?;
+        store.observe(self);
+        measure(TimingPhase::StorageIo, || {
+            storage.write_member_stats(self.group_id(), &store)
+        })?;
+        self.member_stats = MemberStatsCache {
+            store: Some(store),
+            unflushed: 0,
+        };
+        Ok(())
+    }
+
+    /// Applies `update` to the statistics of the member at `leaf_index`, if
+    /// the collection of statistics is enabled and there is such a member.
+    pub(crate) fn record_member_stats<Storage: StorageProvider>(
+        &mut self,
+        storage: &Storage,
+        leaf_index: LeafNodeIndex,
+        update: impl FnOnce(&mut MemberStats),
+    ) -> Result<(), Storage::Error> {
+        if !self.configuration().collect_member_stats() {
+            return Ok(());
+        }
+        let mut store = self.load_member_stats(storage)?;
+        if let Some(stats) = store.members.get_mut(leaf_index) {
+            update(stats);
+            self.member_stats.unflushed += 1;
+        }
+        self.member_stats.store = Some(store);
+        if self.member_stats.unflushed >= MEMBER_STATS_FLUSH_INTERVAL {
+            self.flush_member_stats(storage)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets the statistics that are kept in memory, e.g. because they
+    /// were deleted from the storage.
+    pub(crate) fn reset_member_stats(&mut self) {
+        self.member_stats = MemberStatsCache::default();
+    }
+
+    // Takes the statistics out of memory, or loads them from the storage and
+    // updates them to the current members.
+    fn load_member_stats<Storage: StorageReader>(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<MemberStatsStore, Storage::Error> {
+        if let Some(store) = self.member_stats.store.take() {
+            return Ok(store);
+        }
+        let mut store: MemberStatsStore = measure(TimingPhase::StorageIo, || {
+            storage.member_stats(self.group_id())
+        })?
+        .unwrap_or_default();
+        store.observe(self);
+        Ok(store)
+    }
+}
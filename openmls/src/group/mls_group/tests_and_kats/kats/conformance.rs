@@ -0,0 +1,418 @@
+//! Cross-implementation conformance transcripts.
+//!
+//! Other MLS implementations, e.g. mlspp or mls-rs, place transcripts of
+//! their groups in `test_vectors/conformance/`. Every JSON file in that
+//! directory is read by `test_read_transcripts`, which checks that OpenMLS
+//! accepts the KeyPackages, joins the group from the Welcome as a passive
+//! client, processes all proposals and commits to the expected epoch
+//! authenticators and decrypts all application messages. The test fails if
+//! the directory contains no transcript of another implementation.
+//!
+//! The format extends the passive client test vectors, so these can be used
+//! as transcripts as well. `mls-implementations.json` contains one Welcome
+//! and one commit handling vector per ciphersuite from the interop test
+//! vectors.
+//!
+//! `test_write_transcripts` generates the equivalent transcripts of OpenMLS
+//! groups and checks that they can be read back. They are written to a
+//! temporary file, unless `OPENMLS_CONFORMANCE_OUT` is set to the path they
+//! should be exported to for the other implementations to consume.
+
+use log::{info, warn};
+use openmls_traits::crypto::OpenMlsCrypto;
+use serde::{self, Deserialize, Serialize};
+use tls_codec::{Deserialize as TlsDeserialize, Serialize as TlsSerialize};
+
+use super::passive_client::{
+    commit, propose_add, update_inline, ExternalPskTest, PassiveClient, TestEpoch, VecU8,
+};
+use crate::{
+    framing::{MlsMessageBodyIn, MlsMessageIn, MlsMessageOut},
+    group::{
+        IncomingWireFormatPolicy, MlsGroup, MlsGroupCreateConfig, MlsGroupJoinConfig,
+        OutgoingWireFormatPolicy, WireFormatPolicy,
+    },
+    test_utils::*,
+    treesync::RatchetTreeIn,
+    versions::ProtocolVersion,
+};
+
+const TEST_VECTORS_DIR: &str = "test_vectors/conformance";
+const TEST_VECTOR_PATH_WRITE_VAR: &str = "OPENMLS_CONFORMANCE_OUT";
+const IMPLEMENTATION: &str = "openmls";
+
+/// ```json
+/// {
+///   "implementation": /* name of the implementation that produced it */,
+///   "cipher_suite": /* uint16 */,
+///   "external_psks": [ /* optional */ ],
+///
+///   "key_packages": [
+///     /* serialized MLSMessage (KeyPackage) */,
+///   ],
+///
+///   "key_package": /* serialized MLSMessage (KeyPackage) */,
+///   "signature_priv":  /* hex-encoded binary data */,
+///   "encryption_priv": /* hex-encoded binary data */,
+///   "init_priv": /* hex-encoded binary data */,
+///
+///   "welcome":  /* serialized MLSMessage (Welcome) */,
+///   "ratchet_tree": /* optional serialized ratchet tree */,
+///   "initial_epoch_authenticator":  /* hex-encoded binary data */,
+///
+///   "epochs": [
+///     {
+///       "proposals": [
+///         /* serialized MLSMessage (PublicMessage or PrivateMessage) */,
+///       ],
+///       "commit": /* serialized MLSMessage (PublicMessage or PrivateMessage) */,
+///       "epoch_authenticator": /* hex-encoded binary data */,
+///       "application_messages": [
+///         {
+///           "message": /* serialized MLSMessage (PrivateMessage) */,
+///           "plaintext": /* hex-encoded binary data */,
+///         },
+///       ],
+///     },
+///     // ...
+///   ]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConformanceTranscript {
+    #[serde(default)]
+    implementation: String,
+    cipher_suite: u16,
+    #[serde(default)]
+    external_psks: Vec<ExternalPskTest>,
+
+    #[serde(default)]
+    key_packages: Vec<VecU8>,
+
+    #[serde(with = "hex::serde")]
+    key_package: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    signature_priv: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    encryption_priv: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    init_priv: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    welcome: Vec<u8>,
+    ratchet_tree: Option<VecU8>,
+    #[serde(with = "hex::serde")]
+    initial_epoch_authenticator: Vec<u8>,
+    epochs: Vec<ConformanceEpoch>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConformanceEpoch {
+    #[serde(flatten)]
+    epoch: TestEpoch,
+    // Sent in the epoch created by the commit.
+    #[serde(default)]
+    application_messages: Vec<TestApplicationMessage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TestApplicationMessage {
+    #[serde(with = "hex::serde")]
+    message: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    plaintext: Vec<u8>,
+}
+
+#[test]
+fn test_read_transcripts() {
+    let mut files = std::fs::read_dir(TEST_VECTORS_DIR)
+        .expect("Couldn't read the conformance transcripts.")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut external_transcripts = 0;
+    crate::skip_validation::checks::leaf_node_lifetime::handle().with_disabled(|| {
+        for file in &files {
+            let transcripts: Vec<ConformanceTranscript> = read(file.to_str().unwrap());
+
+            info!("# {}", file.display());
+            for (i, transcript) in transcripts.into_iter().enumerate() {
+                info!("## {i:04} START ({})", transcript.implementation);
+                if transcript.implementation != IMPLEMENTATION && run_transcript(transcript) {
+                    external_transcripts += 1;
+                }
+                info!("## {i:04} END");
+            }
+        }
+    });
+    assert!(
+        external_transcripts > 0,
+        "No transcript of another implementation was run."
+    );
+}
+
+/// Runs the `transcript` and returns `false` if it was skipped because its
+/// ciphersuite isn't supported.
+pub fn run_transcript(transcript: ConformanceTranscript) -> bool {
+    let _ = pretty_env_logger::try_init();
+
+    let provider = OpenMlsRustCrypto::default();
+    let cipher_suite = transcript.cipher_suite.try_into().unwrap();
+    if provider.crypto().supports(cipher_suite).is_err() {
+        warn!("Skipping {}", cipher_suite);
+        return false;
+    }
+
+    for key_package in transcript.key_packages {
+        let MlsMessageBodyIn::KeyPackage(key_package) =
+            MlsMessageIn::tls_deserialize_exact(&key_package.0)
+                .unwrap()
+                .extract()
+        else {
+            panic!("Expected a KeyPackage.");
+        };
+        key_package
+            .validate(provider.crypto(), ProtocolVersion::Mls10)
+            .unwrap();
+    }
+
+    let group_config = MlsGroupJoinConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .wire_format_policy(WireFormatPolicy::new(
+            OutgoingWireFormatPolicy::AlwaysPlaintext,
+            IncomingWireFormatPolicy::Mixed,
+        ))
+        .number_of_resumption_psks(16)
+        .build();
+
+    let mut passive_client = PassiveClient::new(group_config, transcript.external_psks);
+
+    passive_client.inject_key_package(
+        transcript.key_package,
+        transcript.signature_priv,
+        transcript.encryption_priv,
+        transcript.init_priv,
+    );
+
+    let ratchet_tree: Option<RatchetTreeIn> = transcript
+        .ratchet_tree
+        .as_ref()
+        .map(|bytes| RatchetTreeIn::tls_deserialize_exact(bytes.0.as_slice()).unwrap());
+
+    passive_client.join_by_welcome(
+        MlsMessageIn::tls_deserialize_exact(&transcript.welcome).unwrap(),
+        ratchet_tree,
+    );
+
+    assert_eq!(
+        transcript.initial_epoch_authenticator,
+        passive_client.epoch_authenticator()
+    );
+
+    for (i, epoch) in transcript.epochs.into_iter().enumerate() {
+        info!("Epoch #{}", i);
+
+        for proposal in epoch.epoch.proposals {
+            let message = MlsMessageIn::tls_deserialize_exact(&proposal.0).unwrap();
+            passive_client.process_message(message);
+        }
+
+        let message = MlsMessageIn::tls_deserialize_exact(&epoch.epoch.commit).unwrap();
+        passive_client.process_message(message);
+
+        assert_eq!(
+            epoch.epoch.epoch_authenticator,
+            passive_client.epoch_authenticator()
+        );
+
+        for application_message in epoch.application_messages {
+            let message =
+                MlsMessageIn::tls_deserialize_exact(&application_message.message).unwrap();
+            assert_eq!(
+                application_message.plaintext,
+                passive_client.process_application_message(message)
+            );
+        }
+    }
+
+    true
+}
+
+#[test]
+fn test_write_transcripts() {
+    crate::skip_validation::checks::leaf_node_lifetime::handle().with_disabled(|| {
+        let mut transcripts = Vec::new();
+
+        for &ciphersuite in OpenMlsRustCrypto::default()
+            .crypto()
+            .supported_ciphersuites()
+            .iter()
+        {
+            transcripts.push(generate_transcript(ciphersuite));
+        }
+
+        // Write to a temporary file, such that running the tests doesn't
+        // modify the committed transcripts.
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path: std::path::PathBuf = match std::env::var(TEST_VECTOR_PATH_WRITE_VAR) {
+            Ok(path) => path.into(),
+            Err(_) => temp_dir.path().join("openmls.json"),
+        };
+        write(path.to_str().unwrap(), &transcripts);
+
+        let transcripts: Vec<ConformanceTranscript> = read(path.to_str().unwrap());
+        for transcript in transcripts {
+            assert!(run_transcript(transcript));
+        }
+    })
+}
+
+pub fn generate_transcript(ciphersuite: Ciphersuite) -> ConformanceTranscript {
+    let group_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+
+    let creator_provider = OpenMlsRustCrypto::default();
+
+    let creator =
+        generate_group_candidate(b"Alice (Creator)", ciphersuite, &creator_provider, true);
+
+    let mut creator_group = MlsGroup::new(
+        &creator_provider,
+        &creator.signature_keypair,
+        &group_config,
+        creator
+            .credential_with_key_and_signer
+            .credential_with_key
+            .clone(),
+    )
+    .unwrap();
+
+    let key_packages = [b"Xavier".as_slice(), b"Yasmin".as_slice()]
+        .into_iter()
+        .map(|identity| {
+            let candidate = generate_group_candidate(
+                identity,
+                ciphersuite,
+                &OpenMlsRustCrypto::default(),
+                false,
+            );
+            VecU8(
+                MlsMessageOut::from(candidate.key_package)
+                    .tls_serialize_detached()
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let passive = generate_group_candidate(
+        b"Bob (Passive Client)",
+        ciphersuite,
+        &OpenMlsRustCrypto::default(),
+        false,
+    );
+
+    let (_, mls_message_welcome, _) = creator_group
+        .add_members(
+            &creator_provider,
+            &creator.signature_keypair,
+            &[passive.key_package.key_package().clone()],
+        )
+        .unwrap();
+
+    creator_group
+        .merge_pending_commit(&creator_provider)
+        .unwrap();
+
+    let initial_epoch_authenticator = creator_group.epoch_authenticator().as_slice().to_vec();
+
+    let epoch1 = ConformanceEpoch {
+        epoch: update_inline(&creator_provider, &creator, &mut creator_group),
+        application_messages: vec![application_message(
+            &creator_provider,
+            &creator,
+            &mut creator_group,
+            b"Hello from epoch 2",
+        )],
+    };
+
+    let epoch2 = {
+        let proposals = vec![propose_add(
+            ciphersuite,
+            &creator_provider,
+            &creator,
+            &mut creator_group,
+            b"Charlie",
+        )];
+
+        let commit = commit(&creator_provider, &creator, &mut creator_group);
+
+        let epoch_authenticator = creator_group.epoch_authenticator().as_slice().to_vec();
+
+        ConformanceEpoch {
+            epoch: TestEpoch {
+                proposals,
+                commit,
+                epoch_authenticator,
+            },
+            application_messages: vec![
+                application_message(
+                    &creator_provider,
+                    &creator,
+                    &mut creator_group,
+                    b"Hello from epoch 3",
+                ),
+                application_message(&creator_provider, &creator, &mut creator_group, b""),
+            ],
+        }
+    };
+
+    let init_priv = passive.key_package.init_private_key().to_vec();
+    let encryption_priv = passive.key_package.encryption_private_key().to_vec();
+
+    ConformanceTranscript {
+        implementation: IMPLEMENTATION.to_owned(),
+        cipher_suite: ciphersuite.into(),
+        external_psks: vec![],
+
+        key_packages,
+
+        key_package: MlsMessageOut::from(passive.key_package)
+            .tls_serialize_detached()
+            .unwrap(),
+
+        signature_priv: passive.signature_keypair.private().to_vec(),
+        encryption_priv,
+        init_priv,
+
+        welcome: mls_message_welcome.tls_serialize_detached().unwrap(),
+        ratchet_tree: None,
+        initial_epoch_authenticator,
+
+        epochs: vec![epoch1, epoch2],
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+fn application_message(
+    provider: &OpenMlsRustCrypto,
+    candidate: &GroupCandidate,
+    group: &mut MlsGroup,
+    plaintext: &[u8],
+) -> TestApplicationMessage {
+    let message = group
+        .create_message(provider, &candidate.signature_keypair, plaintext)
+        .unwrap();
+
+    TestApplicationMessage {
+        message: message.tls_serialize_detached().unwrap(),
+        plaintext: plaintext.to_vec(),
+    }
+}
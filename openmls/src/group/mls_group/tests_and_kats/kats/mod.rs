@@ -1,2 +1,3 @@
+mod conformance;
 mod passive_client;
 mod welcome;
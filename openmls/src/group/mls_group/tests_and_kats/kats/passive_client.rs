@@ -1,5 +1,5 @@
 use log::{debug, info, warn};
-use openmls_traits::{crypto::OpenMlsCrypto, storage::StorageProvider, OpenMlsProvider};
+use openmls_traits::{crypto::OpenMlsCrypto, storage::StorageWriter, OpenMlsProvider};
 use serde::{self, Deserialize, Serialize};
 use tls_codec::{Deserialize as TlsDeserialize, Serialize as TlsSerialize};
 
@@ -75,7 +75,7 @@ pub struct PassiveClientWelcomeTestVector {
 
 // Helper to avoid writing a custom deserializer.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct VecU8(#[serde(with = "hex::serde")] Vec<u8>);
+pub struct VecU8(#[serde(with = "hex::serde")] pub(super) Vec<u8>);
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ExternalPskTest {
@@ -87,15 +87,15 @@ pub struct ExternalPskTest {
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct TestEpoch {
-    proposals: Vec<TestProposal>,
+    pub(super) proposals: Vec<TestProposal>,
     #[serde(with = "hex::serde")]
-    commit: Vec<u8>,
+    pub(super) commit: Vec<u8>,
     #[serde(with = "hex::serde")]
-    epoch_authenticator: Vec<u8>,
+    pub(super) epoch_authenticator: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct TestProposal(#[serde(with = "hex::serde")] Vec<u8>);
+pub struct TestProposal(#[serde(with = "hex::serde")] pub(super) Vec<u8>);
 
 #[test]
 fn test_read_vectors() {
@@ -202,14 +202,14 @@ fn test_write_vectors() {
     })
 }
 
-struct PassiveClient {
+pub(super) struct PassiveClient {
     provider: OpenMlsRustCrypto,
     group_config: MlsGroupJoinConfig,
     group: Option<MlsGroup>,
 }
 
 impl PassiveClient {
-    fn new(group_config: MlsGroupJoinConfig, psks: Vec<ExternalPskTest>) -> Self {
+    pub(super) fn new(group_config: MlsGroupJoinConfig, psks: Vec<ExternalPskTest>) -> Self {
         let provider = OpenMlsRustCrypto::default();
 
         // Load all PSKs into key store.
@@ -228,7 +228,7 @@ impl PassiveClient {
         }
     }
 
-    fn inject_key_package(
+    pub(super) fn inject_key_package(
         &self,
         key_package: Vec<u8>,
         _signature_priv: Vec<u8>,
@@ -250,6 +250,9 @@ impl PassiveClient {
             key_package: key_package.clone(),
             private_init_key: init_priv,
             private_encryption_key: encryption_priv.clone().into(),
+            retained: None,
+            superseded_by: None,
+            supersedes: None,
         };
 
         // Store key package.
@@ -268,7 +271,7 @@ impl PassiveClient {
         key_pair.write(self.provider.storage()).unwrap();
     }
 
-    fn join_by_welcome(
+    pub(super) fn join_by_welcome(
         &mut self,
         mls_message_welcome: MlsMessageIn,
         ratchet_tree: Option<RatchetTreeIn>,
@@ -290,7 +293,7 @@ impl PassiveClient {
         self.group = Some(group);
     }
 
-    fn process_message(&mut self, message: MlsMessageIn) {
+    pub(super) fn process_message(&mut self, message: MlsMessageIn) {
         println!("{:#?}", message);
         let processed_message = self
             .group
@@ -318,7 +321,23 @@ impl PassiveClient {
         }
     }
 
-    fn epoch_authenticator(&self) -> Vec<u8> {
+    pub(super) fn process_application_message(&mut self, message: MlsMessageIn) -> Vec<u8> {
+        let processed_message = self
+            .group
+            .as_mut()
+            .unwrap()
+            .process_message(&self.provider, message.into_protocol_message().unwrap())
+            .unwrap();
+
+        match processed_message.into_content() {
+            ProcessedMessageContent::ApplicationMessage(application_message) => {
+                application_message.into_bytes()
+            }
+            _ => panic!("Expected an application message."),
+        }
+    }
+
+    pub(super) fn epoch_authenticator(&self) -> Vec<u8> {
         self.group
             .as_ref()
             .unwrap()
@@ -332,7 +351,8 @@ pub fn generate_test_vector(ciphersuite: Ciphersuite) -> PassiveClientWelcomeTes
     let group_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
         .use_ratchet_tree_extension(true)
-        .build();
+        .build()
+        .unwrap();
 
     let creator_provider = OpenMlsRustCrypto::default();
 
@@ -507,7 +527,7 @@ pub fn generate_test_vector(ciphersuite: Ciphersuite) -> PassiveClientWelcomeTes
 
 // -------------------------------------------------------------------------------------------------
 
-fn propose_add(
+pub(super) fn propose_add(
     cipher_suite: Ciphersuite,
     provider: &OpenMlsRustCrypto,
     candidate: &GroupCandidate,
@@ -552,7 +572,11 @@ fn propose_remove(
     TestProposal(mls_message_out_proposal.tls_serialize_detached().unwrap())
 }
 
-fn commit(provider: &OpenMlsRustCrypto, creator: &GroupCandidate, group: &mut MlsGroup) -> Vec<u8> {
+pub(super) fn commit(
+    provider: &OpenMlsRustCrypto,
+    creator: &GroupCandidate,
+    group: &mut MlsGroup,
+) -> Vec<u8> {
     let (mls_message_out_commit, _, _) = group
         .commit_to_pending_proposals(provider, &creator.signature_keypair)
         .unwrap();
@@ -561,7 +585,7 @@ fn commit(provider: &OpenMlsRustCrypto, creator: &GroupCandidate, group: &mut Ml
     mls_message_out_commit.tls_serialize_detached().unwrap()
 }
 
-fn update_inline(
+pub(super) fn update_inline(
     provider: &OpenMlsRustCrypto,
     candidate: &GroupCandidate,
     group: &mut MlsGroup,
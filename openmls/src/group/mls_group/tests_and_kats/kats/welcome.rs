@@ -20,7 +20,7 @@
 //!     decrypted GroupContext
 
 use crate::{test_utils::OpenMlsRustCrypto, treesync::node::encryption_keys::EncryptionPrivateKey};
-use openmls_traits::{crypto::OpenMlsCrypto, storage::StorageProvider, OpenMlsProvider};
+use openmls_traits::{crypto::OpenMlsCrypto, storage::StorageWriter, OpenMlsProvider};
 use serde::{self, Deserialize, Serialize};
 use tls_codec::{Deserialize as TlsDeserialize, Serialize as TlsSerialize};
 
@@ -165,6 +165,9 @@ pub fn run_test_vector(test_vector: WelcomeTestVector) -> Result<(), &'static st
         key_package: key_package.clone(),
         private_init_key: init_priv,
         private_encryption_key: EncryptionPrivateKey::from(vec![]),
+        retained: None,
+        superseded_by: None,
+        supersedes: None,
     };
 
     let hash_ref = key_package.hash_ref(provider.crypto()).unwrap();
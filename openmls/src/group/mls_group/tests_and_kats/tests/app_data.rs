@@ -0,0 +1,389 @@
+//! Tests for application data that is bound to the group: chunked messages,
+//! attachment keys, signed application data, leaf key proofs and membership
+//! tokens.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+use tls_codec::{Deserialize, Serialize};
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::signable::Signable,
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{process_and_merge_commit, setup_alice_bob_group},
+        *,
+    },
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn chunked_application_messages() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let payload: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+
+    let messages = alice_group
+        .create_chunked_messages(
+            provider,
+            &alice_signer,
+            &payload,
+            std::num::NonZeroUsize::new(300).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(messages.len(), 4);
+
+    let chunks: Vec<Vec<u8>> = messages
+        .into_iter()
+        .map(|message| {
+            let processed_message = bob_group
+                .process_message(provider, message.into_protocol_message().unwrap())
+                .unwrap();
+            let ProcessedMessageContent::ApplicationMessage(application_message) =
+                processed_message.into_content()
+            else {
+                panic!("expected an application message");
+            };
+            application_message.into_bytes()
+        })
+        .collect();
+
+    // Chunks can be added in any order, and duplicates are ignored.
+    let sender = alice_group.own_leaf_index();
+    let mut reassembler = ChunkReassembler::new(ciphersuite, payload.len());
+    for chunk in chunks.iter().rev().skip(1) {
+        assert_eq!(
+            reassembler.add(provider.crypto(), sender, chunk).unwrap(),
+            None
+        );
+    }
+    assert_eq!(
+        reassembler
+            .add(provider.crypto(), sender, &chunks[1])
+            .unwrap(),
+        None
+    );
+    assert_eq!(reassembler.pending_transfers(), 1);
+    assert_eq!(
+        reassembler
+            .add(provider.crypto(), sender, &chunks[0])
+            .unwrap(),
+        Some(payload.clone())
+    );
+    assert_eq!(reassembler.pending_transfers(), 0);
+
+    // Chunks from a different sender belong to a different transfer.
+    assert_eq!(
+        reassembler
+            .add(provider.crypto(), LeafNodeIndex::new(1), &chunks[0])
+            .unwrap(),
+        None
+    );
+    reassembler.discard(LeafNodeIndex::new(1));
+    assert_eq!(reassembler.pending_transfers(), 0);
+
+    // Transfers that exceed the maximum payload size are rejected.
+    let mut reassembler = ChunkReassembler::new(ciphersuite, 500);
+    reassembler
+        .add(provider.crypto(), sender, &chunks[0])
+        .unwrap();
+    assert_eq!(
+        reassembler.add(provider.crypto(), sender, &chunks[1]),
+        Err(ChunkReassemblyError::PayloadTooLarge)
+    );
+    assert_eq!(
+        reassembler.add(provider.crypto(), sender, b"not a chunk"),
+        Err(ChunkReassemblyError::MalformedChunk)
+    );
+
+    // No chunk is created if the group can't create messages.
+    alice_group
+        .propose_self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+    assert_eq!(
+        alice_group.create_chunked_messages(
+            provider,
+            &alice_signer,
+            &payload,
+            std::num::NonZeroUsize::new(300).unwrap(),
+        ),
+        Err(CreateChunkedMessagesError::CreateMessageError(
+            CreateMessageError::GroupStateError(MlsGroupStateError::PendingProposal)
+        ))
+    );
+}
+
+#[openmls_test]
+fn chunk_reassembler_limits() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let chunk_size = std::num::NonZeroUsize::new(100).unwrap();
+
+    // Returns the first chunk of a new transfer of 200 bytes.
+    let mut first_chunk = || {
+        let message = alice_group
+            .create_chunked_messages(provider, &alice_signer, &[0u8; 200], chunk_size)
+            .unwrap()
+            .remove(0);
+        let processed_message = bob_group
+            .process_message(provider, message.into_protocol_message().unwrap())
+            .unwrap();
+        let ProcessedMessageContent::ApplicationMessage(application_message) =
+            processed_message.into_content()
+        else {
+            panic!("expected an application message");
+        };
+        application_message.into_bytes()
+    };
+    let chunks: Vec<Vec<u8>> = (0..3).map(|_| first_chunk()).collect();
+    let sender = LeafNodeIndex::new(0);
+
+    // The oldest transfer of a sender is evicted when it starts too many.
+    let mut reassembler = ChunkReassembler::new(ciphersuite, 1000)
+        .with_max_transfers_per_sender(std::num::NonZeroUsize::new(2).unwrap());
+    for chunk in &chunks {
+        reassembler.add(provider.crypto(), sender, chunk).unwrap();
+    }
+    assert_eq!(reassembler.pending_transfers(), 2);
+    assert_eq!(reassembler.pending_size(), 200);
+    // Other senders are not affected.
+    reassembler
+        .add(provider.crypto(), LeafNodeIndex::new(1), &chunks[0])
+        .unwrap();
+    assert_eq!(reassembler.pending_transfers(), 3);
+
+    // The oldest transfers are evicted when too many bytes are buffered.
+    let mut reassembler = ChunkReassembler::new(ciphersuite, 1000).with_max_pending_size(250);
+    for (index, chunk) in chunks.iter().enumerate() {
+        reassembler
+            .add(provider.crypto(), LeafNodeIndex::new(index as u32), chunk)
+            .unwrap();
+    }
+    assert_eq!(reassembler.pending_transfers(), 2);
+    assert_eq!(reassembler.pending_size(), 200);
+    reassembler.discard(LeafNodeIndex::new(0));
+    assert_eq!(reassembler.pending_transfers(), 2);
+    reassembler.discard(LeafNodeIndex::new(1));
+    assert_eq!(reassembler.pending_transfers(), 1);
+
+    // A chunk that exceeds the limit on its own is rejected.
+    let mut reassembler = ChunkReassembler::new(ciphersuite, 1000).with_max_pending_size(50);
+    assert_eq!(
+        reassembler.add(provider.crypto(), sender, &chunks[0]),
+        Err(ChunkReassemblyError::PayloadTooLarge)
+    );
+    assert_eq!(reassembler.pending_transfers(), 0);
+    assert_eq!(reassembler.pending_size(), 0);
+}
+
+#[openmls_test]
+fn attachment_keys() {
+    let (mut alice_group, alice_signer, bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let attachment = b"a large file".to_vec();
+
+    let alice_key = alice_group
+        .attachment_key(provider, b"attachment 1")
+        .unwrap();
+    let encrypted = alice_key
+        .encrypt(provider.crypto(), provider.rand(), &attachment)
+        .unwrap();
+    let blob = encrypted.tls_serialize_detached().unwrap();
+
+    // Bob derives the same key from the header in the same epoch.
+    let encrypted = EncryptedAttachment::tls_deserialize_exact(blob).unwrap();
+    assert_eq!(encrypted.header().group_id(), bob_group.group_id());
+    assert_eq!(encrypted.header().epoch(), bob_group.epoch());
+    let bob_key = bob_group
+        .attachment_key(provider, encrypted.header().attachment_id())
+        .unwrap();
+    assert_eq!(bob_key, alice_key);
+    assert_eq!(
+        bob_key.decrypt(provider.crypto(), &encrypted).unwrap(),
+        attachment
+    );
+
+    // The key of a different attachment can't decrypt it.
+    let other_key = bob_group.attachment_key(provider, b"attachment 2").unwrap();
+    assert_eq!(
+        other_key.decrypt(provider.crypto(), &encrypted),
+        Err(AttachmentDecryptionError::HeaderMismatch)
+    );
+
+    // Keys of the same attachment ID are different in the next epoch.
+    alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let next_key = alice_group
+        .attachment_key(provider, b"attachment 1")
+        .unwrap();
+    assert_ne!(next_key, alice_key);
+    assert_eq!(
+        next_key.decrypt(provider.crypto(), &encrypted),
+        Err(AttachmentDecryptionError::HeaderMismatch)
+    );
+}
+
+#[openmls_test]
+fn signed_app_data() {
+    let (alice_group, alice_signer, bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let signed = alice_group
+        .sign_app_data(&alice_signer, b"receipt", b"message 42 was read")
+        .unwrap();
+    assert_eq!(signed.signer(), alice_group.own_leaf_index());
+    assert_eq!(signed.label(), b"receipt");
+
+    let serialized = signed.tls_serialize_detached().unwrap();
+    let signed = SignedAppData::tls_deserialize_exact(&serialized).unwrap();
+    bob_group
+        .verify_app_data(provider.crypto(), &signed)
+        .unwrap();
+    assert_eq!(signed.payload(), b"message 42 was read");
+
+    // A modified signature is rejected.
+    let mut tampered = serialized.clone();
+    *tampered.last_mut().unwrap() ^= 0xff;
+    let tampered = SignedAppData::tls_deserialize_exact(tampered).unwrap();
+    assert_eq!(
+        bob_group.verify_app_data(provider.crypto(), &tampered),
+        Err(VerifyAppDataError::InvalidSignature)
+    );
+
+    // Signing with a key that doesn't belong to the own leaf doesn't produce a
+    // valid signature.
+    let forged = alice_group
+        .sign_app_data(&bob_signer, b"receipt", b"message 42 was read")
+        .unwrap();
+    assert_eq!(
+        bob_group.verify_app_data(provider.crypto(), &forged),
+        Err(VerifyAppDataError::InvalidSignature)
+    );
+}
+
+#[openmls_test]
+fn leaf_key_proof() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let proof = alice_group
+        .prove_leaf_key_possession(provider.crypto(), &alice_signer, b"challenge")
+        .unwrap();
+    assert_eq!(proof.leaf_index(), alice_group.own_leaf_index());
+    assert_eq!(proof.epoch(), alice_group.epoch());
+    assert_eq!(proof.challenge(), b"challenge");
+    assert_eq!(
+        proof.signature_key(),
+        alice_group.own_leaf().unwrap().signature_key()
+    );
+
+    // An external service can verify the proof without the group state.
+    let serialized = proof.tls_serialize_detached().unwrap();
+    let proof = LeafKeyProof::tls_deserialize_exact(&serialized).unwrap();
+    proof.verify(provider.crypto()).unwrap();
+    bob_group
+        .verify_leaf_key_proof(provider.crypto(), &proof)
+        .unwrap();
+
+    // A modified signature is rejected.
+    let mut tampered = serialized.clone();
+    *tampered.last_mut().unwrap() ^= 0xff;
+    let tampered = LeafKeyProof::tls_deserialize_exact(tampered).unwrap();
+    assert_eq!(
+        tampered.verify(provider.crypto()),
+        Err(VerifyLeafKeyProofError::InvalidSignature)
+    );
+
+    // A proof signed with a key that doesn't belong to the own leaf is invalid.
+    let forged = alice_group
+        .prove_leaf_key_possession(provider.crypto(), &bob_signer, b"challenge")
+        .unwrap();
+    assert_eq!(
+        forged.verify(provider.crypto()),
+        Err(VerifyLeafKeyProofError::InvalidSignature)
+    );
+
+    // The proof is bound to the epoch it was created in.
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    assert_eq!(
+        bob_group.verify_leaf_key_proof(provider.crypto(), &proof),
+        Err(VerifyLeafKeyProofError::EpochMismatch)
+    );
+}
+
+#[openmls_test]
+fn membership_token() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let token = alice_group
+        .membership_token(provider, &alice_signer)
+        .unwrap();
+    assert_eq!(token.member(), alice_group.own_leaf_index());
+    assert_eq!(token.epoch(), alice_group.epoch());
+    assert_eq!(
+        token.credential(),
+        alice_group.own_leaf().unwrap().credential()
+    );
+    assert_eq!(
+        token.signature_key(),
+        alice_group.own_leaf().unwrap().signature_key()
+    );
+
+    let serialized = token.tls_serialize_detached().unwrap();
+    let token = MembershipToken::tls_deserialize_exact(&serialized).unwrap();
+    bob_group.verify_membership_token(provider, &token).unwrap();
+
+    // A modified signature is rejected.
+    let mut tampered = serialized.clone();
+    *tampered.last_mut().unwrap() ^= 0xff;
+    let tampered = MembershipToken::tls_deserialize_exact(tampered).unwrap();
+    assert_eq!(
+        bob_group.verify_membership_token(provider, &tampered),
+        Err(VerifyMembershipTokenError::InvalidSignature)
+    );
+
+    // A modified tag is rejected, even if the token is signed by the member.
+    let mut tbs = token.tbs().clone();
+    tbs.tag = vec![0; tbs.tag.as_slice().len()].into();
+    let tampered = tbs.sign(&alice_signer).unwrap();
+    assert_eq!(
+        bob_group.verify_membership_token(provider, &tampered),
+        Err(VerifyMembershipTokenError::InvalidTag)
+    );
+
+    // Bob can derive the tag of Alice's token, but can't sign it with her
+    // key.
+    let forged = token.tbs().clone().sign(&bob_signer).unwrap();
+    assert_eq!(
+        alice_group.verify_membership_token(provider, &forged),
+        Err(VerifyMembershipTokenError::InvalidSignature)
+    );
+    // Neither can he claim her leaf with his own key.
+    let mut tbs = token.tbs().clone();
+    tbs.signature_key = bob_group.own_leaf().unwrap().signature_key().clone();
+    let forged = tbs.sign(&bob_signer).unwrap();
+    assert_eq!(
+        alice_group.verify_membership_token(provider, &forged),
+        Err(VerifyMembershipTokenError::KeyMismatch)
+    );
+
+    // The token is bound to the epoch it was created in.
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    assert_eq!(
+        bob_group.verify_membership_token(provider, &token),
+        Err(VerifyMembershipTokenError::EpochMismatch)
+    );
+}
@@ -0,0 +1,625 @@
+//! Tests for the inspection and configuration of commits before they are
+//! created.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{process_commit, setup_alice_bob_group, setup_client},
+        *,
+    },
+    key_packages::*,
+    messages::proposals::Proposal,
+    treesync::{node::leaf_node::Capabilities, LeafNodeParameters},
+};
+
+#[openmls_test]
+fn capability_report() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (charlie_credential_with_key, _charlie_kpb, charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential_with_key, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let capabilities = Capabilities::new(
+        None,
+        None,
+        Some(&[ExtensionType::Unknown(0xff00)]),
+        None,
+        None,
+    );
+    let required_capabilities = Extension::RequiredCapabilities(
+        RequiredCapabilitiesExtension::new(&[ExtensionType::Unknown(0xff00)], &[], &[]),
+    );
+
+    let alice_group = MlsGroup::builder()
+        .ciphersuite(ciphersuite)
+        .with_capabilities(capabilities.clone())
+        .with_group_context_extensions(Extensions::single(required_capabilities))
+        .expect("error adding group context extension to builder")
+        .build(provider, &alice_signer, alice_credential_with_key)
+        .expect("error creating group using builder");
+
+    // Charlie supports the required extension, Dave doesn't.
+    let charlie_kpb = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities.clone())
+        .build(
+            ciphersuite,
+            provider,
+            &charlie_signer,
+            charlie_credential_with_key,
+        )
+        .unwrap();
+    let key_packages = [
+        charlie_kpb.key_package().clone(),
+        dave_kpb.key_package().clone(),
+    ];
+
+    let report = alice_group.capability_report(&key_packages);
+    assert!(!report.is_compatible());
+    assert!(report.missing_capabilities(0).is_none());
+    assert_eq!(
+        report.missing_capabilities(1),
+        Some(&[MissingCapability::Extension(ExtensionType::Unknown(0xff00))][..])
+    );
+    assert_eq!(report.incompatible().len(), 1);
+    assert_eq!(report.incompatible()[0].index(), 1);
+    assert_eq!(report.intersection(), &capabilities);
+
+    // Without Dave, all candidates are compatible.
+    let report = alice_group.capability_report(&key_packages[..1]);
+    assert!(report.is_compatible());
+}
+
+#[openmls_test]
+fn can_add() {
+    let (alice_group, _alice_signer, _bob_group, bob_signer, bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let bob_kpb = KeyPackage::builder()
+        .build(ciphersuite, provider, &bob_signer, bob_credential_with_key)
+        .unwrap();
+
+    let key_packages = [
+        charlie_kpb.key_package().clone(),
+        bob_kpb.key_package().clone(),
+        charlie_kpb.key_package().clone(),
+    ];
+    let reports = alice_group.can_add(provider.crypto(), &key_packages);
+    assert_eq!(reports.len(), 3);
+
+    // Charlie can be added.
+    assert_eq!(reports[0].index(), 0);
+    assert!(reports[0].is_ok());
+
+    // Bob is already a member.
+    let bob_index = LeafNodeIndex::new(1);
+    assert_eq!(
+        reports[1].failures(),
+        &[
+            AddCheckFailure::DuplicateIdentity(AddCheckConflict::Member(bob_index)),
+            AddCheckFailure::DuplicateSignatureKey(AddCheckConflict::Member(bob_index)),
+        ]
+    );
+
+    // Charlie's key package can't be added twice.
+    let first = AddCheckConflict::Candidate(0);
+    assert_eq!(
+        reports[2].failures(),
+        &[
+            AddCheckFailure::DuplicateIdentity(first),
+            AddCheckFailure::DuplicateSignatureKey(first),
+            AddCheckFailure::DuplicateEncryptionKey(first),
+            AddCheckFailure::DuplicateInitKey(first),
+        ]
+    );
+}
+
+#[openmls_test]
+fn validate_pending_proposals() {
+    let (mut alice_group, alice_signer, _bob_group, bob_signer, bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let bob_kpb = KeyPackage::builder()
+        .build(ciphersuite, provider, &bob_signer, bob_credential_with_key)
+        .unwrap();
+
+    let (_, charlie_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+    // Bob is already a member of the group.
+    let (_, bob_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, bob_kpb.key_package())
+        .unwrap();
+    let (_, update_ref) = alice_group
+        .propose_self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+
+    let checks = alice_group.validate_pending_proposals().unwrap();
+    assert_eq!(checks.len(), 3);
+    assert_eq!(checks[0].proposal_ref(), &charlie_ref);
+    assert_eq!(checks[0].status(), &PendingProposalStatus::Included);
+    assert_eq!(checks[1].proposal_ref(), &bob_ref);
+    assert!(matches!(
+        checks[1].status(),
+        PendingProposalStatus::Invalid(CreateCommitError::ProposalValidationError(_))
+    ));
+    assert_eq!(checks[2].proposal_ref(), &update_ref);
+    assert_eq!(
+        checks[2].status(),
+        &PendingProposalStatus::Filtered(ProposalFilterReason::OwnUpdate)
+    );
+
+    // The group wasn't modified.
+    assert_eq!(alice_group.pending_proposals().count(), 3);
+    assert!(alice_group.pending_commit().is_none());
+
+    // After removing the invalid proposal, the commit can be created.
+    alice_group
+        .remove_pending_proposal(provider.storage(), &bob_ref)
+        .unwrap();
+    alice_group
+        .commit_to_pending_proposals(provider, &alice_signer)
+        .expect("error committing to pending proposals");
+}
+
+#[openmls_test]
+fn commit_report() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential_with_key, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let (_, charlie_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+    let (_, dave_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, dave_kpb.key_package())
+        .unwrap();
+    let (_, update_ref) = alice_group
+        .propose_self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+
+    // The application doesn't want to add Dave.
+    let bundle = alice_group
+        .commit_builder()
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(
+            provider.rand(),
+            provider.crypto(),
+            &alice_signer,
+            |proposal| proposal.proposal_reference() != dave_ref,
+        )
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+
+    let report = bundle.report();
+    assert_eq!(report.included(), &[charlie_ref.clone()]);
+    assert_eq!(
+        report.filtered(),
+        &[
+            (update_ref.clone(), ProposalFilterReason::OwnUpdate),
+            (dave_ref.clone(), ProposalFilterReason::ApplicationPolicy),
+        ]
+    );
+    assert_eq!(report.filter_reason(&charlie_ref), None);
+    assert_eq!(
+        report.filter_reason(&dave_ref),
+        Some(ProposalFilterReason::ApplicationPolicy)
+    );
+}
+
+#[openmls_test]
+fn commit_policy() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential_with_key, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+    alice_group
+        .propose_add_member(provider, &alice_signer, dave_kpb.key_package())
+        .unwrap();
+    alice_group
+        .propose_remove_member(provider, &alice_signer, LeafNodeIndex::new(1))
+        .unwrap();
+
+    // Only add one member per commit.
+    let mut policy = ProposalSelection::new().removes_first().max_adds(1);
+    alice_group
+        .commit_to_pending_proposals_with_policy(provider, &alice_signer, &mut policy)
+        .unwrap();
+
+    let staged_commit = alice_group.pending_commit().unwrap();
+    let added: Vec<_> = staged_commit
+        .add_proposals()
+        .map(|add| add.add_proposal().key_package().clone())
+        .collect();
+    assert_eq!(added, vec![charlie_kpb.key_package().clone()]);
+    assert_eq!(staged_commit.remove_proposals().count(), 1);
+
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.members().count(), 2);
+}
+
+/// A [`CommitPolicy`] that considers the candidates from newest to oldest and
+/// selects each of them twice.
+struct ReversedTwice;
+
+impl CommitPolicy for ReversedTwice {
+    fn select_proposals(&mut self, candidates: Vec<QueuedProposal>) -> Vec<QueuedProposal> {
+        let reversed: Vec<_> = candidates.into_iter().rev().collect();
+        reversed.iter().chain(reversed.iter()).cloned().collect()
+    }
+}
+
+#[openmls_test]
+fn commit_policy_keeps_update_order() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Bob updates his leaf twice.
+    let mut update_leaf_nodes = Vec::new();
+    for _ in 0..2 {
+        let (proposal, _proposal_ref) = bob_group
+            .propose_self_update(provider, &bob_signer, LeafNodeParameters::default())
+            .unwrap();
+        let processed_message = alice_group
+            .process_message(
+                provider,
+                MlsMessageIn::from(proposal)
+                    .into_protocol_message()
+                    .unwrap(),
+            )
+            .unwrap();
+        let ProcessedMessageContent::ProposalMessage(queued_proposal) =
+            processed_message.into_content()
+        else {
+            panic!("Expected a proposal.");
+        };
+        let Proposal::Update(update_proposal) = queued_proposal.proposal() else {
+            panic!("Expected an Update proposal.");
+        };
+        update_leaf_nodes.push(update_proposal.leaf_node().clone());
+        alice_group
+            .store_pending_proposal(provider.storage(), *queued_proposal)
+            .unwrap();
+    }
+    alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+
+    // The policy reverses the order and selects each proposal twice, but each
+    // proposal is only committed once and the newest Update still wins.
+    alice_group
+        .commit_to_pending_proposals_with_policy(provider, &alice_signer, &mut ReversedTwice)
+        .unwrap();
+
+    let staged_commit = alice_group.pending_commit().unwrap();
+    let updates: Vec<_> = staged_commit
+        .update_proposals()
+        .map(|update| update.update_proposal().leaf_node().clone())
+        .collect();
+    assert_eq!(updates, vec![update_leaf_nodes[1].clone()]);
+    assert_eq!(staged_commit.add_proposals().count(), 1);
+}
+
+#[openmls_test]
+fn simulate_commit() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Simulate adding Charlie and removing Bob.
+    let simulation = alice_group
+        .simulate_commit(
+            provider.crypto(),
+            CommitSimulationParams::new()
+                .propose_adds(Some(charlie_kpb.key_package().clone()))
+                .propose_removals(Some(LeafNodeIndex::new(1))),
+        )
+        .unwrap();
+    assert_eq!(
+        simulation.epoch().as_u64(),
+        alice_group.epoch().as_u64() + 1
+    );
+    assert!(simulation.path_required());
+    assert_eq!(simulation.joiners(), 1);
+    assert_eq!(simulation.welcome_count(), 1);
+    assert!(simulation.estimated_commit_size() > 0);
+    assert!(simulation.estimated_welcome_size() > 0);
+
+    // The group is unchanged.
+    assert!(alice_group.pending_commit().is_none());
+    assert_eq!(alice_group.members().count(), 2);
+
+    // The simulation matches the actual commit, except for the committer's
+    // new encryption key.
+    let bundle = alice_group
+        .commit_builder()
+        .propose_adds(Some(charlie_kpb.key_package().clone()))
+        .propose_removals(Some(LeafNodeIndex::new(1)))
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert!(bundle.welcome().is_some());
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.epoch(), simulation.epoch());
+    let identities = |members: &mut dyn Iterator<Item = Member>| {
+        members
+            .map(|member| (member.index, member.credential))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        identities(&mut alice_group.members()),
+        identities(&mut simulation.members().iter().cloned())
+    );
+
+    // Removing oneself fails like it does when committing.
+    let own_index = alice_group.own_leaf_index();
+    assert_eq!(
+        alice_group
+            .simulate_commit(
+                provider.crypto(),
+                CommitSimulationParams::new().propose_removals(Some(own_index)),
+            )
+            .unwrap_err(),
+        CreateCommitError::CannotRemoveSelf
+    );
+}
+
+#[cfg(feature = "operation-timing")]
+#[openmls_test]
+fn operation_timing() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    assert!(alice_group.last_operation_timing().is_none());
+
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    let timing = alice_group.last_operation_timing().unwrap();
+    assert_eq!(timing.operation(), OperationKind::CreateCommit);
+    assert_eq!(
+        timing.phase(TimingPhase::PathDecryption),
+        std::time::Duration::ZERO
+    );
+    let phases: std::time::Duration = timing.phases().map(|(_, duration)| duration).sum();
+    assert_eq!(phases + timing.unattributed(), timing.total());
+
+    bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    let timing = bob_group.last_operation_timing().unwrap();
+    assert_eq!(timing.operation(), OperationKind::ProcessMessage);
+    let phases: std::time::Duration = timing.phases().map(|(_, duration)| duration).sum();
+    assert_eq!(phases + timing.unattributed(), timing.total());
+
+    // Failed processing is timed as well, and the timing isn't stored.
+    alice_group.merge_pending_commit(provider).unwrap();
+    let message = alice_group
+        .create_message(provider, &alice_signer, b"too early")
+        .unwrap();
+    bob_group
+        .process_message(provider, message.into_protocol_message().unwrap())
+        .unwrap_err();
+    assert_eq!(
+        bob_group.last_operation_timing().unwrap().operation(),
+        OperationKind::ProcessMessage
+    );
+    let loaded = MlsGroup::load(provider.storage(), bob_group.group_id())
+        .unwrap()
+        .unwrap();
+    assert!(loaded.last_operation_timing().is_none());
+}
+
+#[openmls_test]
+fn commit_size_report() {
+    use tls_codec::Size as _;
+
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential_with_key, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    // Charlie is committed by reference, Dave by value.
+    let (_, charlie_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+    let bundle = alice_group
+        .commit_builder()
+        .propose_adds(Some(dave_kpb.key_package().clone()))
+        .force_self_update(true)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+
+    let size_report = bundle.size_report();
+    assert_eq!(size_report.commit(), bundle.commit().tls_serialized_len());
+    assert!(size_report.update_path().unwrap() > 0);
+    assert_eq!(size_report.group_info(), None);
+
+    let proposals = size_report.proposals();
+    assert_eq!(
+        proposals
+            .iter()
+            .map(|(proposal_ref, _)| proposal_ref.clone())
+            .collect::<Vec<_>>(),
+        bundle.report().included()
+    );
+    let charlie_size = proposals
+        .iter()
+        .find(|(proposal_ref, _)| *proposal_ref == charlie_ref)
+        .unwrap()
+        .1;
+    let dave_size = proposals
+        .iter()
+        .find(|(proposal_ref, _)| *proposal_ref != charlie_ref)
+        .unwrap()
+        .1;
+    assert!(dave_size > charlie_size);
+
+    let welcome = bundle.welcome().unwrap();
+    assert_eq!(size_report.welcome(), Some(welcome.tls_serialized_len()));
+    let recipients = size_report
+        .welcome_recipients()
+        .iter()
+        .map(|(key_package_ref, _)| key_package_ref.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(recipients.len(), 2);
+    for key_package in [charlie_kpb.key_package(), dave_kpb.key_package()] {
+        assert!(recipients.contains(&key_package.hash_ref(provider.crypto()).unwrap()));
+    }
+}
+
+#[openmls_test]
+fn update_path_inclusion() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Removing a member requires a path, so it can't be omitted.
+    let err = alice_group
+        .commit_builder()
+        .propose_removals(Some(bob_group.own_leaf_index()))
+        .update_path(UpdatePathInclusion::Never)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap_err();
+    assert_eq!(err, CreateCommitError::UpdatePathRequired);
+
+    // Adding a member doesn't, so the commit can be kept small.
+    let bundle = alice_group
+        .commit_builder()
+        .propose_adds(Some(charlie_kpb.key_package().clone()))
+        .update_path(UpdatePathInclusion::Never)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert_eq!(bundle.size_report().update_path(), None);
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // The receiver accepts the commit without a path.
+    let staged_commit = process_commit(provider, &mut bob_group, bundle.into_commit());
+    assert!(staged_commit.update_path_leaf_node().is_none());
+    bob_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+
+    // A path can be included without any proposals.
+    let bundle = alice_group
+        .commit_builder()
+        .update_path(UpdatePathInclusion::Always)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert!(bundle.size_report().update_path().is_some());
+}
+
+#[openmls_test]
+fn force_full_commits() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let full_commit_config = MlsGroupJoinConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .force_full_commits(true)
+        .build();
+    bob_group
+        .set_configuration(provider.storage(), &full_commit_config)
+        .unwrap();
+
+    // Bob rejects a commit without a path, even though the add doesn't
+    // require one.
+    let bundle = alice_group
+        .commit_builder()
+        .propose_adds(Some(charlie_kpb.key_package().clone()))
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert_eq!(bundle.size_report().update_path(), None);
+    let err = bob_group
+        .process_message(
+            provider,
+            bundle.into_commit().into_protocol_message().unwrap(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::FullCommitRequired)
+    );
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+
+    // With the policy, Alice includes a path by default and refuses to omit it.
+    alice_group
+        .set_configuration(provider.storage(), &full_commit_config)
+        .unwrap();
+    let err = alice_group
+        .commit_builder()
+        .propose_adds(Some(charlie_kpb.key_package().clone()))
+        .update_path(UpdatePathInclusion::Never)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap_err();
+    assert_eq!(err, CreateCommitError::UpdatePathRequired);
+
+    let bundle = alice_group
+        .commit_builder()
+        .propose_adds(Some(charlie_kpb.key_package().clone()))
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert!(bundle.size_report().update_path().is_some());
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut bob_group, bundle.into_commit());
+    assert!(staged_commit.update_path_leaf_node().is_some());
+    bob_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+}
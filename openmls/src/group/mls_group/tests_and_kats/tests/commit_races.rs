@@ -0,0 +1,409 @@
+//! Tests for commits that race with commits of other members or with own
+//! messages.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    credentials::test_utils::new_credential,
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{
+            process_and_merge_commit, process_commit, setup_alice_bob_group, setup_client,
+        },
+        *,
+    },
+    messages::proposals::*,
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn commit_race_recovery() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Alice wants to add Charlie, but Bob commits first.
+    alice_group
+        .commit_builder()
+        .propose_adds(Some(charlie_kpb.key_package().clone()))
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    let (bob_commit, _welcome, _group_info) = bob_group
+        .self_update(provider, &bob_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_contents();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut alice_group, bob_commit);
+    assert!(alice_group.conflicts_with_pending_commit(&staged_commit));
+
+    let superseded_commit = alice_group
+        .discard_pending_commit(provider.storage())
+        .unwrap()
+        .expect("No pending commit.");
+    assert!(alice_group.pending_commit().is_none());
+    assert_eq!(superseded_commit.proposals().len(), 1);
+
+    // The proposals can only be re-committed after merging Bob's commit.
+    assert_eq!(
+        alice_group
+            .recommit(provider, &alice_signer, superseded_commit.clone())
+            .unwrap_err(),
+        RecommitError::ConflictNotMerged
+    );
+    alice_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+
+    let bundle = alice_group
+        .recommit(provider, &alice_signer, superseded_commit.clone())
+        .unwrap()
+        .expect("Charlie's Add proposal is still valid.");
+    assert!(bundle.welcome().is_some());
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.members().count(), 3);
+
+    // Charlie is a member now, so there is nothing left to re-commit.
+    assert!(alice_group
+        .revalidate_proposals(&superseded_commit)
+        .is_empty());
+
+    let staged_commit = process_commit(provider, &mut bob_group, bundle.into_commit());
+    assert!(!bob_group.conflicts_with_pending_commit(&staged_commit));
+    bob_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+    assert_eq!(
+        alice_group.state_summary(provider.crypto()).unwrap(),
+        bob_group.state_summary(provider.crypto()).unwrap()
+    );
+}
+
+#[openmls_test]
+fn repropose_after_lost_race() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // Alice proposes to update her leaf, but Bob commits before receiving
+    // the proposal.
+    let (_proposal, proposal_ref) = alice_group
+        .propose_self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+    let old_proposal = alice_group
+        .proposal_store()
+        .proposals()
+        .find(|queued_proposal| queued_proposal.proposal_reference() == proposal_ref)
+        .map(|queued_proposal| queued_proposal.proposal().clone())
+        .unwrap();
+    let (bob_commit, _welcome, _group_info) = bob_group
+        .self_update(provider, &bob_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_contents();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut alice_group, bob_commit);
+    let messages = alice_group
+        .merge_staged_commit_and_repropose(provider, &alice_signer, staged_commit)
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+
+    // The Update proposal was re-created with a fresh leaf node.
+    let new_proposals: Vec<_> = alice_group.proposal_store().proposals().collect();
+    assert_eq!(new_proposals.len(), 1);
+    assert_ne!(new_proposals[0].proposal(), &old_proposal);
+    assert!(matches!(new_proposals[0].proposal(), Proposal::Update(_)));
+
+    // Bob commits the new proposal.
+    let processed_message = bob_group
+        .process_message(
+            provider,
+            messages[0].clone().into_protocol_message().unwrap(),
+        )
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(queued_proposal) =
+        processed_message.into_content()
+    else {
+        panic!("Expected a proposal.");
+    };
+    bob_group
+        .store_pending_proposal(provider.storage(), *queued_proposal)
+        .unwrap();
+    let (commit, _welcome, _group_info) = bob_group
+        .commit_to_pending_proposals(provider, &bob_signer)
+        .unwrap();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut alice_group, commit);
+    let messages = alice_group
+        .merge_staged_commit_and_repropose(provider, &alice_signer, staged_commit)
+        .unwrap();
+    assert!(messages.is_empty());
+    assert_eq!(
+        alice_group.state_summary(provider.crypto()).unwrap(),
+        bob_group.state_summary(provider.crypto()).unwrap()
+    );
+}
+
+// Test receiving an own, encrypted commit from the DS
+#[openmls_test::openmls_test]
+fn own_private_commit_received(
+    ciphersuite: Ciphersuite,
+    provider: &impl crate::storage::OpenMlsProvider,
+) {
+    let (alice_credential_with_key, alice_signature_keys) =
+        new_credential(provider, b"Alice", ciphersuite.signature_algorithm());
+
+    let mut alice_group = MlsGroup::builder()
+        .ciphersuite(ciphersuite)
+        .with_wire_format_policy(PURE_CIPHERTEXT_WIRE_FORMAT_POLICY)
+        .build(provider, &alice_signature_keys, alice_credential_with_key)
+        .expect("Error creating group.");
+
+    let (commit_out, _welcome_option, _group_info_option) = alice_group
+        .self_update(
+            provider,
+            &alice_signature_keys,
+            LeafNodeParameters::default(),
+        )
+        .expect("Could not create commit")
+        .into_contents();
+
+    let error = alice_group
+        .process_message(
+            provider,
+            MlsMessageIn::from(commit_out)
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .expect_err("no error while processing own commit");
+    assert_eq!(error, ProcessMessageError::OwnCommitReceived);
+
+    // Another own commit of the same epoch is not the pending commit.
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .expect("Could not clear pending commit");
+    let (discarded_commit, _welcome_option, _group_info_option) = alice_group
+        .self_update(
+            provider,
+            &alice_signature_keys,
+            LeafNodeParameters::default(),
+        )
+        .expect("Could not create commit")
+        .into_contents();
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .expect("Could not clear pending commit");
+    let (commit_out, _welcome_option, _group_info_option) = alice_group
+        .self_update(
+            provider,
+            &alice_signature_keys,
+            LeafNodeParameters::default(),
+        )
+        .expect("Could not create commit")
+        .into_contents();
+    let error = alice_group
+        .process_message(
+            provider,
+            MlsMessageIn::from(discarded_commit)
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .expect_err("no error while processing own commit");
+    assert_eq!(
+        error,
+        ProcessMessageError::ValidationError(ValidationError::CannotDecryptOwnMessage)
+    );
+    let error = alice_group
+        .process_message(
+            provider,
+            MlsMessageIn::from(commit_out)
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .expect_err("no error while processing own commit");
+    assert_eq!(error, ProcessMessageError::OwnCommitReceived);
+
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("Could not merge pending commit");
+    assert_eq!(alice_group.epoch(), GroupEpoch::from(1));
+}
+
+#[openmls_test]
+fn own_message_echoed() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let messages: Vec<_> = (0..2)
+        .map(|_| {
+            alice_group
+                .create_message(provider, &alice_signer, b"Hello, Bob!")
+                .expect("Could not create message.")
+        })
+        .collect();
+
+    for (generation, message) in messages.into_iter().enumerate() {
+        let error = alice_group
+            .process_message(
+                provider,
+                MlsMessageIn::from(message).into_protocol_message().unwrap(),
+            )
+            .expect_err("no error while processing own message");
+        assert_eq!(
+            error,
+            ProcessMessageError::OwnMessageEchoed {
+                epoch: alice_group.epoch(),
+                generation: generation as u32,
+            }
+        );
+    }
+}
+
+#[openmls_test]
+fn revalidate_remove_after_leaf_reuse() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (dave_credential, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let (commit, _welcome, _group_info) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[charlie_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    let charlie_index = LeafNodeIndex::new(2);
+
+    // Alice wants to remove Charlie, but Bob commits first. He removes Charlie
+    // as well and adds Dave, who takes Charlie's leaf.
+    alice_group
+        .commit_builder()
+        .propose_removals([charlie_index])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    let bob_commit = bob_group
+        .commit_builder()
+        .propose_removals([charlie_index])
+        .propose_adds([dave_kpb.key_package().clone()])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &bob_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap()
+        .into_commit();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut alice_group, bob_commit);
+    let superseded_commit = alice_group
+        .discard_pending_commit(provider.storage())
+        .unwrap()
+        .expect("No pending commit.");
+    alice_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+    assert_eq!(
+        alice_group.member(charlie_index),
+        Some(&dave_credential.credential)
+    );
+
+    // The Remove proposal targeted Charlie, so it must not remove Dave.
+    assert!(alice_group
+        .revalidate_proposals(&superseded_commit)
+        .is_empty());
+    assert!(alice_group
+        .recommit(provider, &alice_signer, superseded_commit)
+        .unwrap()
+        .is_none());
+}
+
+#[openmls_test]
+fn repropose_remove_after_leaf_reuse() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let (commit, _welcome, _group_info) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[charlie_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    let charlie_index = LeafNodeIndex::new(2);
+
+    // Alice proposes to remove Charlie, but Bob commits before receiving the
+    // proposal. He removes Charlie as well and adds Dave, who takes Charlie's
+    // leaf.
+    alice_group
+        .propose_remove_member(provider, &alice_signer, charlie_index)
+        .unwrap();
+    let bob_commit = bob_group
+        .commit_builder()
+        .propose_removals([charlie_index])
+        .propose_adds([dave_kpb.key_package().clone()])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &bob_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap()
+        .into_commit();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    // The Remove proposal targeted Charlie, so it is not proposed again for
+    // Dave.
+    let staged_commit = process_commit(provider, &mut alice_group, bob_commit);
+    let messages = alice_group
+        .merge_staged_commit_and_repropose(provider, &alice_signer, staged_commit)
+        .unwrap();
+    assert!(messages.is_empty());
+    assert_eq!(
+        alice_group.member(charlie_index),
+        Some(&dave_credential.credential)
+    );
+}
+
+#[openmls_test]
+fn repropose_after_own_removal() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // Alice proposes to update her leaf, but Bob removes her.
+    alice_group
+        .propose_self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+    let (bob_commit, _welcome, _group_info) = bob_group
+        .remove_members(provider, &bob_signer, &[alice_group.own_leaf_index()])
+        .unwrap();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    // The commit is merged and nothing is proposed.
+    let staged_commit = process_commit(provider, &mut alice_group, bob_commit);
+    let messages = alice_group
+        .merge_staged_commit_and_repropose(provider, &alice_signer, staged_commit)
+        .unwrap();
+    assert!(messages.is_empty());
+    assert!(!alice_group.is_active());
+}
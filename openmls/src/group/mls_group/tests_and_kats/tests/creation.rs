@@ -0,0 +1,222 @@
+//! Tests for the creation of groups.
+
+use openmls_test::openmls_test;
+use openmls_traits::{storage::StorageReader as _, OpenMlsProvider as _};
+
+use crate::{
+    framing::*,
+    group::{mls_group::tests_and_kats::utils::setup_client, *},
+    key_packages::KeyPackageBundle,
+    messages::Welcome,
+};
+
+#[openmls_test]
+fn group_id_strategies() {
+    use openmls_traits::crypto::OpenMlsCrypto as _;
+
+    struct GroupIdLength(usize);
+
+    impl ProcessingPolicy for GroupIdLength {
+        fn accept_group_id(&self, group_id: &GroupId) -> bool {
+            group_id.as_slice().len() == self.0
+        }
+    }
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (bob_credential_with_key, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    // Random group IDs of a custom length.
+    let group = MlsGroup::builder()
+        .ciphersuite(ciphersuite)
+        .with_group_id_strategy(GroupIdStrategy::Random(32))
+        .build(provider, &alice_signer, alice_credential_with_key.clone())
+        .expect("error creating group");
+    assert_eq!(group.group_id().as_slice().len(), 32);
+
+    // Group IDs derived from application context.
+    let context = b"application context";
+    let mut alice_group = MlsGroup::builder()
+        .ciphersuite(ciphersuite)
+        .with_group_id_strategy(GroupIdStrategy::Derived(context.to_vec()))
+        .build(provider, &alice_signer, alice_credential_with_key)
+        .expect("error creating group");
+    let expected = provider
+        .crypto()
+        .hash(ciphersuite.hash_algorithm(), context)
+        .unwrap();
+    assert_eq!(alice_group.group_id().as_slice(), expected.as_slice());
+
+    // Bob only accepts 16 byte group IDs and rejects the Welcome.
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().expect("expected a welcome");
+    let err = StagedWelcome::new_from_welcome_with_policy(
+        provider,
+        &MlsGroupJoinConfig::default(),
+        welcome,
+        Some(alice_group.export_ratchet_tree().into()),
+        &GroupIdLength(16),
+    )
+    .expect_err("Bob accepted a group ID of the wrong length");
+    assert!(matches!(err, WelcomeError::GroupIdRejected));
+
+    // The rejected Welcome didn't use up Bob's key package.
+    let bob_key_package: Option<KeyPackageBundle> = provider
+        .storage()
+        .key_package(&bob_kpb.key_package().hash_ref(provider.crypto()).unwrap())
+        .unwrap();
+    assert!(bob_key_package.is_some());
+
+    // The same holds for external joins.
+    let verifiable_group_info = alice_group
+        .export_group_info(provider, &alice_signer, true)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let err = MlsGroup::join_by_external_commit_with_policy(
+        provider,
+        &bob_signer,
+        None,
+        verifiable_group_info.clone(),
+        &MlsGroupJoinConfig::default(),
+        None,
+        None,
+        &[],
+        bob_credential_with_key.clone(),
+        &GroupIdLength(16),
+    )
+    .expect_err("Bob accepted a group ID of the wrong length");
+    assert!(matches!(err, ExternalCommitError::GroupIdRejected));
+
+    // A policy accepting the group ID lets Bob join.
+    let (bob_group, _commit, _group_info) = MlsGroup::join_by_external_commit_with_policy(
+        provider,
+        &bob_signer,
+        None,
+        verifiable_group_info,
+        &MlsGroupJoinConfig::default(),
+        None,
+        None,
+        &[],
+        bob_credential_with_key,
+        &GroupIdLength(ciphersuite.hash_length()),
+    )
+    .expect("Bob could not join the group");
+    assert_eq!(bob_group.group_id(), alice_group.group_id());
+}
+
+#[openmls_test]
+fn new_with_members() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    // === Alice creates a group with Bob and Charlie ===
+    let (alice_group, welcome, _group_info) = MlsGroup::new_with_members(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key.clone(),
+        &[
+            bob_kpb.key_package().clone(),
+            charlie_kpb.key_package().clone(),
+        ],
+    )
+    .expect("error creating group with members");
+
+    assert_eq!(alice_group.epoch(), GroupEpoch::from(1));
+    assert_eq!(alice_group.members().count(), 3);
+    assert!(alice_group.pending_commit().is_none());
+
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().expect("expected a welcome");
+    let bob_group = StagedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome,
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .expect("error creating staged join from welcome")
+    .into_group(provider)
+    .expect("error creating group from staged join");
+    assert_eq!(
+        bob_group.epoch_authenticator(),
+        alice_group.epoch_authenticator()
+    );
+
+    // === If the members can't be added, the group isn't stored ===
+    let group_id = GroupId::from_slice(b"Empty group");
+    let err = MlsGroup::builder()
+        .with_group_id(group_id.clone())
+        .ciphersuite(ciphersuite)
+        .build_with_members(provider, &alice_signer, alice_credential_with_key, &[])
+        .expect_err("created a group without members to add");
+    assert!(matches!(err, NewGroupWithMembersError::AddMembers(_)));
+    assert!(MlsGroup::load(provider.storage(), &group_id)
+        .expect("error loading group")
+        .is_none());
+}
+
+#[openmls_test]
+fn ciphersuite_selection() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Nobody published a key package for the other ciphersuite, and Dave
+    // didn't publish any.
+    let other_ciphersuite = if ciphersuite == Ciphersuite::MLS_128_DHKEMP256_AES128GCM_SHA256_P256 {
+        Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519
+    } else {
+        Ciphersuite::MLS_128_DHKEMP256_AES128GCM_SHA256_P256
+    };
+    let members = [
+        vec![bob_kpb.key_package().clone()],
+        vec![],
+        vec![charlie_kpb.key_package().clone()],
+    ];
+    let selection = CiphersuiteSelection::new(&[other_ciphersuite, ciphersuite], &members);
+
+    assert_eq!(selection.candidates().len(), 2);
+    let other = selection.candidate(other_ciphersuite).unwrap();
+    assert_eq!(other.excluded(), &[0, 1, 2]);
+    assert!(other.key_packages().is_empty());
+    assert!(selection.best().is_none());
+
+    // The less preferred ciphersuite excludes only Dave.
+    let candidate = selection.best_effort().unwrap();
+    assert_eq!(candidate.ciphersuite(), ciphersuite);
+    assert_eq!(candidate.excluded(), &[1]);
+
+    // The selection feeds directly into group creation.
+    let (alice_group, _welcome, _group_info) = MlsGroup::new_with_members(
+        provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::test_default(candidate.ciphersuite()),
+        alice_credential_with_key,
+        candidate.key_packages(),
+    )
+    .unwrap();
+    assert_eq!(alice_group.members().count(), 3);
+
+    // Without Dave, all members support the ciphersuite.
+    let selection = CiphersuiteSelection::new(
+        &[other_ciphersuite, ciphersuite],
+        &[&members[0], &members[2]],
+    );
+    assert_eq!(selection.best().unwrap().ciphersuite(), ciphersuite);
+}
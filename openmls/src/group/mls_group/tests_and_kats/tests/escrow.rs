@@ -0,0 +1,191 @@
+//! Tests for the compliance key escrow.
+
+use std::sync::{Arc, Mutex};
+
+use openmls_basic_credential::SignatureKeyPair;
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+use tls_codec::{Deserialize, Serialize};
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::OpenMlsSignaturePublicKey,
+    framing::MlsMessageIn,
+    group::{
+        mls_group::tests_and_kats::utils::{process_commit, setup_alice_bob_group, setup_client},
+        *,
+    },
+    treesync::LeafNodeParameters,
+};
+
+type EscrowedKeys = Vec<(GroupEpoch, Vec<(LeafNodeIndex, Vec<u8>)>)>;
+
+#[derive(Default, Clone)]
+struct TestSink(Arc<Mutex<EscrowedKeys>>);
+
+impl TestSink {
+    fn keys(&self) -> EscrowedKeys {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl EscrowSink for TestSink {
+    type Error = ();
+
+    fn escrow(
+        &mut self,
+        keys: &EscrowedEpochKeys,
+        _log_entry: &EscrowLogEntry,
+    ) -> Result<(), Self::Error> {
+        let secrets = keys
+            .application_secrets()
+            .map(|(leaf_index, secret)| (leaf_index, secret.to_vec()))
+            .collect();
+        self.0.lock().unwrap().push((keys.epoch(), secrets));
+        Ok(())
+    }
+}
+
+fn log_signer(ciphersuite: Ciphersuite) -> (SignatureKeyPair, OpenMlsSignaturePublicKey) {
+    let signer = SignatureKeyPair::new(ciphersuite.signature_algorithm()).unwrap();
+    let public_key = OpenMlsSignaturePublicKey::new(
+        signer.to_public_vec().into(),
+        ciphersuite.signature_algorithm(),
+    )
+    .unwrap();
+    (signer, public_key)
+}
+
+#[openmls_test]
+fn compliance_key_escrow() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (alice_sink, bob_sink) = (TestSink::default(), TestSink::default());
+    let (alice_log_signer, alice_log_key) = log_signer(ciphersuite);
+    let (bob_log_signer, bob_log_key) = log_signer(ciphersuite);
+    alice_group.set_epoch_hooks(EpochHooks::new().with_escrow(
+        alice_sink.clone(),
+        alice_log_signer,
+        EscrowLog::new(),
+    ));
+    bob_group.set_epoch_hooks(EpochHooks::new().with_escrow(
+        bob_sink.clone(),
+        bob_log_signer,
+        EscrowLog::new(),
+    ));
+
+    // The plain merge functions run the escrow hook.
+    let (commit, _, _) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_contents();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut bob_group, commit);
+    bob_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+
+    // Both members escrow the same keys for the new epoch.
+    let alice_keys = alice_sink.keys();
+    assert_eq!(alice_keys.len(), 1);
+    assert_eq!(alice_keys[0].0, alice_group.epoch());
+    assert_eq!(alice_keys[0].1.len(), 2);
+    assert_eq!(alice_keys, bob_sink.keys());
+    let alice_log = alice_group.epoch_hooks().escrow_log().unwrap().clone();
+    let bob_log = bob_group.epoch_hooks().escrow_log().unwrap();
+    assert_eq!(alice_log.entries().len(), 1);
+    assert_eq!(
+        alice_log.entries()[0].keys_digest(),
+        bob_log.entries()[0].keys_digest()
+    );
+    alice_log.verify(provider.crypto(), &alice_log_key).unwrap();
+    bob_log.verify(provider.crypto(), &bob_log_key).unwrap();
+
+    // The entries are signed with the key of the hook.
+    assert_eq!(
+        alice_log.verify(provider.crypto(), &bob_log_key),
+        Err(EscrowLogError::InvalidSignature(0))
+    );
+
+    // Once a message of the epoch was sent, the keys can't be exported
+    // anymore.
+    alice_group
+        .create_message(provider, &alice_signer, b"Hello, Bob!")
+        .unwrap();
+    let (log_signer, _) = log_signer(ciphersuite);
+    let mut log = alice_log.clone();
+    let err = alice_group
+        .escrow_epoch_keys(
+            provider.crypto(),
+            &log_signer,
+            &mut TestSink::default(),
+            &mut log,
+        )
+        .unwrap_err();
+    assert_eq!(err, EscrowError::KeysUnavailable);
+    assert_eq!(log.entries().len(), 1);
+
+    // Altering an entry of the log is detected.
+    let mut serialized = alice_log.tls_serialize_detached().unwrap();
+    // The digest is followed by the signature with a two byte length prefix.
+    let digest_end = serialized.len() - alice_log.entries()[0].signature().as_slice().len() - 3;
+    serialized[digest_end] ^= 0xff;
+    let tampered_log = EscrowLog::tls_deserialize_exact(serialized).unwrap();
+    assert_eq!(
+        tampered_log.verify(provider.crypto(), &alice_log_key),
+        Err(EscrowLogError::InvalidDigest(0))
+    );
+}
+
+#[openmls_test]
+fn compliance_key_escrow_on_join() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let alice_sink = TestSink::default();
+    let (alice_log_signer, _) = log_signer(ciphersuite);
+    alice_group.set_epoch_hooks(EpochHooks::new().with_escrow(
+        alice_sink.clone(),
+        alice_log_signer,
+        EscrowLog::new(),
+    ));
+
+    // Charlie escrows the keys of the epoch he joins before the group is
+    // stored.
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[charlie_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let charlie_sink = TestSink::default();
+    let (charlie_log_signer, charlie_log_key) = log_signer(ciphersuite);
+    let welcome: MlsMessageIn = welcome.into();
+    let charlie_group = StagedWelcome::new_from_welcome(
+        provider,
+        &MlsGroupJoinConfig::default(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap()
+    .into_group_with_hooks(
+        provider,
+        EpochHooks::new().with_escrow(charlie_sink.clone(), charlie_log_signer, EscrowLog::new()),
+    )
+    .unwrap();
+
+    assert_eq!(charlie_sink.keys().len(), 1);
+    assert_eq!(charlie_sink.keys()[0].0, charlie_group.epoch());
+    assert_eq!(alice_sink.keys(), charlie_sink.keys());
+    charlie_group
+        .epoch_hooks()
+        .escrow_log()
+        .unwrap()
+        .verify(provider.crypto(), &charlie_log_key)
+        .unwrap();
+}
@@ -0,0 +1,77 @@
+//! Tests for secrets that are exported on epoch changes.
+
+use std::sync::{Arc, Mutex};
+
+use openmls_test::openmls_test;
+
+use crate::{
+    group::{
+        mls_group::tests_and_kats::utils::{process_commit, setup_alice_bob_group},
+        *,
+    },
+    treesync::LeafNodeParameters,
+};
+
+type MediaKeys = Arc<Mutex<Vec<(GroupEpoch, Vec<u8>)>>>;
+
+fn media_key_hooks(registry: &ExportedSecretRegistry, keys: &MediaKeys) -> EpochHooks {
+    let keys = keys.clone();
+    EpochHooks::new().with_exported_secrets(registry.clone(), move |secrets| {
+        keys.lock()
+            .unwrap()
+            .push((secrets.epoch(), secrets.get("sframe").unwrap().to_vec()));
+    })
+}
+
+#[openmls_test]
+fn exported_secrets_on_epoch_change() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let mut registry = ExportedSecretRegistry::new();
+    registry.register("sframe", b"", 32);
+    assert_eq!(registry.labels().collect::<Vec<_>>(), vec!["sframe"]);
+
+    let alice_keys = MediaKeys::default();
+    let bob_keys = MediaKeys::default();
+    alice_group.set_epoch_hooks(media_key_hooks(&registry, &alice_keys));
+    bob_group.set_epoch_hooks(media_key_hooks(&registry, &bob_keys));
+
+    // The secrets of the current epoch are derived explicitly.
+    let alice_secrets = alice_group
+        .export_registered_secrets(provider, &registry)
+        .unwrap();
+    let bob_secrets = bob_group
+        .export_registered_secrets(provider, &registry)
+        .unwrap();
+    assert_eq!(alice_secrets.epoch(), alice_group.epoch());
+    assert_eq!(alice_secrets.get("sframe"), bob_secrets.get("sframe"));
+
+    // The plain merge functions run the hook.
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let staged_commit = process_commit(provider, &mut bob_group, commit);
+    bob_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+
+    // Both members received the same fresh key for the new epoch.
+    let keys = alice_keys.lock().unwrap().clone();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys, *bob_keys.lock().unwrap());
+    assert_eq!(keys[0].0, alice_group.epoch());
+    assert_ne!(keys[0].1, alice_secrets.get("sframe").unwrap());
+    assert_eq!(
+        keys[0].1,
+        alice_group
+            .export_secret(provider, "sframe", b"", 32)
+            .unwrap()
+    );
+
+    // Merging without a pending commit doesn't call the handler.
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_keys.lock().unwrap().len(), 1);
+}
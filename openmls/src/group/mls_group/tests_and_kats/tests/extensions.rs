@@ -0,0 +1,157 @@
+//! Tests for the handling of extensions and application versions.
+
+use openmls_test::openmls_test;
+
+use crate::{
+    group::{mls_group::tests_and_kats::utils::setup_client, *},
+    key_packages::*,
+    treesync::{node::leaf_node::Capabilities, LeafNodeParameters},
+};
+
+#[openmls_test]
+fn extension_observers() {
+    use std::sync::{Arc, Mutex};
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+
+    const UNKNOWN_EXTENSION_TYPE: u16 = 0xff11;
+    let unknown_extension =
+        |data: Vec<u8>| Extension::Unknown(UNKNOWN_EXTENSION_TYPE, UnknownExtension(data));
+    let required_extension_types = &[ExtensionType::Unknown(UNKNOWN_EXTENSION_TYPE)];
+    let gc_extensions = Extensions::from_vec(vec![
+        unknown_extension(vec![1, 2]),
+        Extension::RequiredCapabilities(RequiredCapabilitiesExtension::new(
+            required_extension_types,
+            &[],
+            &[],
+        )),
+    ])
+    .expect("error creating group context extensions");
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .with_group_context_extensions(gc_extensions.clone())
+        .expect("error adding unknown extension to config")
+        .capabilities(Capabilities::new(
+            None,
+            None,
+            Some(required_extension_types),
+            None,
+            None,
+        ))
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key,
+    )
+    .expect("error creating group");
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let mut observers = ExtensionObservers::new();
+    {
+        let changes = changes.clone();
+        observers.register(
+            ExtensionType::Unknown(UNKNOWN_EXTENSION_TYPE),
+            move |change| changes.lock().unwrap().push(change.clone()),
+        );
+    }
+
+    // A commit that doesn't change the extension doesn't trigger the observer.
+    alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("error creating self update");
+    alice_group
+        .merge_pending_commit_with_observers(provider, &mut observers)
+        .expect("error merging pending commit");
+    assert!(changes.lock().unwrap().is_empty());
+
+    let mut updated_extensions = gc_extensions;
+    updated_extensions.add_or_replace(unknown_extension(vec![3, 4]));
+    alice_group
+        .update_group_context_extensions(provider, updated_extensions, &alice_signer)
+        .expect("error updating group context extensions");
+    alice_group
+        .merge_pending_commit_with_observers(provider, &mut observers)
+        .expect("error merging pending commit");
+
+    let changes = changes.lock().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].scope(), ExtensionScope::GroupContext);
+    assert_eq!(changes[0].old_value(), Some(&unknown_extension(vec![1, 2])));
+    assert_eq!(changes[0].new_value(), Some(&unknown_extension(vec![3, 4])));
+}
+
+#[openmls_test]
+fn app_version_negotiation() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (bob_credential_with_key, _bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    let negotiation = AppVersionNegotiation::new(0xff10, 0xff11);
+    let capabilities =
+        Capabilities::new(None, None, Some(&negotiation.extension_types()), None, None);
+
+    let mut alice_group = MlsGroup::builder()
+        .ciphersuite(ciphersuite)
+        .with_capabilities(capabilities.clone())
+        .with_leaf_node_extensions(Extensions::single(
+            negotiation.leaf_node_extension(&[1, 2, 3]).unwrap(),
+        ))
+        .expect("error adding leaf node extension to builder")
+        .build(provider, &alice_signer, alice_credential_with_key)
+        .expect("error creating group using builder");
+    assert_eq!(
+        alice_group.common_app_versions(&negotiation).unwrap(),
+        vec![1, 2, 3]
+    );
+
+    // Bob only supports versions 1 and 2.
+    let bob_kpb = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities)
+        .leaf_node_extensions(Extensions::single(
+            negotiation.leaf_node_extension(&[2, 1]).unwrap(),
+        ))
+        .build(ciphersuite, provider, &bob_signer, bob_credential_with_key)
+        .unwrap();
+    alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    assert_eq!(
+        alice_group.common_app_versions(&negotiation).unwrap(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        negotiation
+            .active_version(alice_group.export_group_context())
+            .unwrap(),
+        None
+    );
+
+    // Upgrade to the highest common version.
+    let extensions = alice_group
+        .app_version_upgrade(&negotiation)
+        .unwrap()
+        .expect("there should be an upgrade");
+    alice_group
+        .update_group_context_extensions(provider, extensions, &alice_signer)
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    assert_eq!(
+        negotiation
+            .active_version(alice_group.export_group_context())
+            .unwrap(),
+        Some(2)
+    );
+    assert!(alice_group
+        .app_version_upgrade(&negotiation)
+        .unwrap()
+        .is_none());
+}
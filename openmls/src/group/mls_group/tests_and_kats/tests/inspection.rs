@@ -0,0 +1,444 @@
+//! Tests for inspecting the state of a group and the messages it processes.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+use tls_codec::{Deserialize, Serialize};
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{
+            process_and_merge_commit, process_commit, setup_alice_bob_group, setup_client,
+        },
+        *,
+    },
+    key_packages::*,
+    treesync::LeafNodeParameters,
+    versions::ProtocolVersion,
+};
+
+#[openmls_test]
+fn state_summary() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let alice_summary = alice_group.state_summary(provider.crypto()).unwrap();
+    let bob_summary = bob_group.state_summary(provider.crypto()).unwrap();
+    assert_eq!(alice_summary.digest(), bob_summary.digest());
+    assert!(alice_summary.diverging_components(&bob_summary).is_empty());
+
+    // Alice moves to the next epoch before Bob does.
+    let (commit, _welcome, _group_info) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("Could not create self update.")
+        .into_contents();
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let alice_summary = alice_group.state_summary(provider.crypto()).unwrap();
+    // The summary can be sent to the other device.
+    let serialized = alice_summary.tls_serialize_detached().unwrap();
+    let alice_summary = GroupStateSummary::tls_deserialize_exact(serialized).unwrap();
+
+    let bob_summary = bob_group.state_summary(provider.crypto()).unwrap();
+    assert_ne!(alice_summary.digest(), bob_summary.digest());
+    assert_eq!(
+        bob_summary.diverging_components(&alice_summary),
+        vec![
+            GroupStateComponent::Epoch,
+            GroupStateComponent::TreeHash,
+            GroupStateComponent::ConfirmedTranscriptHash,
+            GroupStateComponent::GroupContext,
+        ]
+    );
+
+    process_and_merge_commit(provider, &mut bob_group, commit);
+
+    let bob_summary = bob_group.state_summary(provider.crypto()).unwrap();
+    assert_eq!(alice_summary, bob_summary);
+}
+
+#[openmls_test]
+fn transcript_hashes() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let assert_same_state = |alice_group: &MlsGroup, bob_group: &MlsGroup| {
+        assert_eq!(
+            alice_group.confirmed_transcript_hash(),
+            bob_group.confirmed_transcript_hash()
+        );
+        assert_eq!(
+            alice_group.interim_transcript_hash(),
+            bob_group.interim_transcript_hash()
+        );
+        assert_eq!(alice_group.confirmation_tag(), bob_group.confirmation_tag());
+    };
+    assert_same_state(&alice_group, &bob_group);
+
+    let old_confirmed_transcript_hash = alice_group.confirmed_transcript_hash().to_vec();
+    let old_interim_transcript_hash = alice_group.interim_transcript_hash().to_vec();
+
+    // === Alice updates and commits ===
+    let (commit, _welcome, _group_info) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("Could not create commit.")
+        .into_contents();
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    process_and_merge_commit(provider, &mut bob_group, commit);
+
+    assert_same_state(&alice_group, &bob_group);
+    assert_ne!(
+        alice_group.confirmed_transcript_hash(),
+        old_confirmed_transcript_hash
+    );
+    assert_ne!(
+        alice_group.interim_transcript_hash(),
+        old_interim_transcript_hash
+    );
+    assert_eq!(
+        alice_group.confirmed_transcript_hash(),
+        alice_group
+            .export_group_context()
+            .confirmed_transcript_hash()
+    );
+}
+
+#[openmls_test]
+fn stable_member_ids() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (dave_credential_with_key, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::test_default(ciphersuite),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    // === Alice adds Bob and Charlie ===
+    alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                bob_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .expect("Could not add members to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let bob_index = LeafNodeIndex::new(1);
+    let bob_id = alice_group.member_id(bob_index).unwrap();
+    assert_eq!(bob_id, alice_group.member_at(bob_index).unwrap().id());
+    assert_eq!(alice_group.member_index(&bob_id), Some(bob_index));
+
+    // === Alice removes Bob and adds Dave, who takes Bob's leaf ===
+    alice_group
+        .remove_members(provider, &alice_signer, &[bob_index])
+        .expect("Could not remove member from group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+    assert_eq!(alice_group.member_id(bob_index), None);
+
+    alice_group
+        .add_members(provider, &alice_signer, &[dave_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let dave_id = MemberId::from_signature_key(dave_credential_with_key.signature_key.as_slice());
+    assert_eq!(alice_group.member_index(&dave_id), Some(bob_index));
+    assert_ne!(alice_group.member_id(bob_index), Some(bob_id.clone()));
+    assert_eq!(alice_group.member_index(&bob_id), None);
+}
+
+#[openmls_test]
+fn export_group_info_with_options() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+
+    let alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::test_default(ciphersuite),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    // The default options only include the external public key.
+    let group_info = alice_group
+        .export_group_info_with_options(provider, &alice_signer, &GroupInfoOptions::new())
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    assert!(group_info.extensions().external_pub().is_some());
+    assert!(group_info.extensions().ratchet_tree().is_none());
+
+    // Embed the ratchet tree and an application extension, but no external
+    // public key.
+    let app_extension = Extension::Unknown(0xff00, UnknownExtension(vec![1, 2, 3]));
+    let options = GroupInfoOptions::new()
+        .with_ratchet_tree(true)
+        .with_external_pub(false)
+        .with_extensions(vec![app_extension.clone()]);
+    let group_info = alice_group
+        .export_group_info_with_options(provider, &alice_signer, &options)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    assert!(group_info.extensions().external_pub().is_none());
+    assert!(group_info.extensions().ratchet_tree().is_some());
+    assert_eq!(
+        group_info.extensions().unknown(0xff00),
+        Some(&UnknownExtension(vec![1, 2, 3]))
+    );
+
+    // Duplicate extension types are rejected.
+    let options =
+        GroupInfoOptions::new().with_extensions(vec![app_extension.clone(), app_extension]);
+    let err = alice_group
+        .export_group_info_with_options(provider, &alice_signer, &options)
+        .expect_err("exported a GroupInfo with duplicate extensions");
+    assert!(matches!(err, ExportGroupInfoError::InvalidExtensions(_)));
+}
+
+/// Test that the `Debug` output of a group doesn't contain any secrets.
+#[cfg(not(feature = "crypto-debug"))]
+#[openmls_test]
+fn debug_output_contains_no_secrets() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // Create a pending commit, such that the staged secrets of the next epoch
+    // are part of the group as well.
+    alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("Could not create commit.");
+
+    let epoch_secrets = alice_group.group_epoch_secrets();
+    let message_secrets = alice_group.message_secrets();
+    let secrets: [&[u8]; 8] = [
+        epoch_secrets.init_secret().as_slice(),
+        epoch_secrets.exporter_secret().as_slice(),
+        epoch_secrets.epoch_authenticator().as_slice(),
+        epoch_secrets.external_secret().as_slice(),
+        epoch_secrets.resumption_psk().as_slice(),
+        message_secrets.sender_data_secret().as_slice(),
+        message_secrets.membership_key().as_slice(),
+        message_secrets.confirmation_key().as_slice(),
+    ];
+
+    let debug_output = format!("{alice_group:?} {alice_group:#?}");
+    for secret in secrets {
+        let lower_hex: String = secret.iter().map(|b| format!("{b:02x}")).collect();
+        let upper_hex: String = secret.iter().map(|b| format!("{b:02X}")).collect();
+        let bytes = format!("{secret:?}");
+        assert!(!debug_output.contains(&lower_hex));
+        assert!(!debug_output.contains(&upper_hex));
+        assert!(!debug_output.contains(&bytes[1..bytes.len() - 1]));
+    }
+}
+
+#[openmls_test]
+fn processed_message_metadata() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    for generation in 0..2 {
+        let message = alice_group
+            .create_message(provider, &alice_signer, b"Hello, Bob!")
+            .unwrap();
+        let processed_message = bob_group
+            .process_message(provider, message.into_protocol_message().unwrap())
+            .unwrap();
+        assert_eq!(
+            processed_message.sender_leaf_index(),
+            Some(alice_group.own_leaf_index())
+        );
+        assert_eq!(processed_message.epoch(), alice_group.epoch());
+        assert_eq!(processed_message.wire_format(), WireFormat::PrivateMessage);
+        assert_eq!(processed_message.content_type(), ContentType::Application);
+        assert_eq!(processed_message.generation(), Some(generation));
+    }
+
+    // Public messages don't have a generation.
+    let (commit, _, _) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_contents();
+    let processed_message = bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    assert_eq!(processed_message.wire_format(), WireFormat::PublicMessage);
+    assert_eq!(processed_message.content_type(), ContentType::Commit);
+    assert_eq!(processed_message.generation(), None);
+}
+
+#[openmls_test]
+fn message_id() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let mut message_ids = Vec::new();
+    for _ in 0..2 {
+        let (message, message_id) = alice_group
+            .create_message_with_id(provider, &alice_signer, b"Hello, Bob!")
+            .unwrap();
+        let processed_message = bob_group
+            .process_message(provider, message.into_protocol_message().unwrap())
+            .unwrap();
+
+        // Sender and receiver derive the same identifier.
+        assert_eq!(processed_message.message_id(), Some(message_id.clone()));
+        assert_eq!(message_id.group_id(), alice_group.group_id());
+        assert_eq!(message_id.epoch(), alice_group.epoch());
+        assert_eq!(message_id.sender(), alice_group.own_leaf_index());
+        assert_eq!(message_id.content_type(), ContentType::Application);
+        message_ids.push(message_id);
+    }
+    assert_ne!(message_ids[0], message_ids[1]);
+
+    let serialized = message_ids[0].tls_serialize_detached().unwrap();
+    assert_eq!(
+        MessageId::tls_deserialize_exact(serialized).unwrap(),
+        message_ids[0]
+    );
+
+    // Public messages don't have an identifier.
+    let (commit, _, _) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_contents();
+    let processed_message = bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    assert_eq!(processed_message.message_id(), None);
+}
+
+#[openmls_test]
+fn commit_confirmation_tag() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let bundle = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+    let alice_tag = bundle.confirmation_tag().clone();
+    assert_eq!(
+        alice_group.pending_commit().unwrap().confirmation_tag(),
+        &alice_tag
+    );
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let staged_commit = process_commit(provider, &mut bob_group, bundle.into_commit());
+    assert_eq!(
+        staged_commit.confirmation_tag().as_slice(),
+        alice_tag.as_slice()
+    );
+    bob_group
+        .merge_staged_commit(provider, staged_commit)
+        .unwrap();
+
+    assert_eq!(alice_group.confirmation_tag(), &alice_tag);
+    assert_eq!(bob_group.confirmation_tag(), &alice_tag);
+}
+
+#[openmls_test]
+fn message_body_accessors() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::builder()
+            .ciphersuite(ciphersuite)
+            .use_ratchet_tree_extension(true)
+            .build()
+            .unwrap(),
+        alice_credential_with_key,
+    )
+    .unwrap();
+    let group_id = alice_group.group_id().clone();
+
+    // Messages are inspected as they are received from the wire.
+    let parse = |message: &MlsMessageOut| {
+        MlsMessageIn::tls_deserialize(&mut message.to_bytes().unwrap().as_slice()).unwrap()
+    };
+
+    // A key package is routed by its reference before it is validated.
+    let bob_key_package = bob_kpb.key_package().clone();
+    let bob_key_package_ref = bob_key_package.hash_ref(provider.crypto()).unwrap();
+    let message = parse(&MlsMessageOut::from(bob_key_package.clone()));
+    let body = message.body();
+    assert_eq!(body.wire_format(), WireFormat::KeyPackage);
+    assert!(body.as_welcome().is_none());
+    assert_eq!(body.group_id(), None);
+    let key_package = body.as_key_package().unwrap();
+    assert_eq!(
+        key_package.hash_ref(provider.crypto()).unwrap(),
+        bob_key_package_ref
+    );
+    assert_eq!(key_package.ciphersuite(), ciphersuite);
+    assert_eq!(
+        message.into_key_package_in().unwrap(),
+        KeyPackageIn::from(bob_key_package.clone())
+    );
+
+    let (commit, welcome, group_info) = alice_group
+        .add_members(provider, &alice_signer, &[bob_key_package])
+        .unwrap();
+    let group_info = group_info.unwrap();
+
+    let message = parse(&commit);
+    assert_eq!(message.version(), ProtocolVersion::Mls10);
+    let body = message.body();
+    let public_message = body.as_public_message().unwrap();
+    assert_eq!(public_message.content_type(), ContentType::Commit);
+    assert!(body.as_private_message().is_none());
+    assert_eq!(body.group_id(), Some(&group_id));
+    assert_eq!(body.epoch(), Some(GroupEpoch::from(0)));
+
+    let message = parse(&welcome);
+    let body = message.body();
+    assert_eq!(body.wire_format(), WireFormat::Welcome);
+    assert_eq!(body.epoch(), None);
+    assert_eq!(
+        body.as_welcome().unwrap().recipients().collect::<Vec<_>>(),
+        vec![bob_key_package_ref]
+    );
+
+    alice_group.merge_pending_commit(provider).unwrap();
+    let message = parse(&MlsMessageOut::from(group_info.clone()));
+    let verifiable_group_info = message.body().as_group_info().unwrap();
+    assert_eq!(
+        verifiable_group_info.group_context(),
+        alice_group.export_group_context()
+    );
+    assert_eq!(
+        verifiable_group_info.confirmation_tag(),
+        group_info.confirmation_tag()
+    );
+    assert_eq!(message.body().group_id(), Some(&group_id));
+    assert!(message.into_verifiable_group_info().is_some());
+}
@@ -0,0 +1,30 @@
+//! Tests for the integrity self-checks of the group state.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    group::{
+        mls_group::tests_and_kats::utils::{process_and_merge_commit, setup_alice_bob_group},
+        *,
+    },
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn integrity_checks_after_merge() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // Merging runs the checks and would panic if they failed.
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    process_and_merge_commit(provider, &mut bob_group, commit);
+
+    alice_group.check_integrity(provider.crypto()).unwrap();
+    bob_group.check_integrity(provider.crypto()).unwrap();
+}
@@ -0,0 +1,255 @@
+//! Tests for leaving a group and for the state of inactive groups.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{process_and_merge_commit, setup_alice_bob_group},
+        *,
+    },
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn pending_self_removal() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // Bob leaves the group and can't create commits anymore.
+    let _proposal = bob_group.leave_group(provider, &bob_signer).unwrap();
+    assert!(bob_group.is_pending_self_removal());
+    assert!(matches!(
+        bob_group.self_update(provider, &bob_signer, LeafNodeParameters::default()),
+        Err(SelfUpdateError::GroupStateError(
+            MlsGroupStateError::PendingSelfRemoval
+        ))
+    ));
+
+    let loaded_group = MlsGroup::load(provider.storage(), bob_group.group_id())
+        .expect("Could not load group.")
+        .expect("Group not found.");
+    assert!(loaded_group.is_pending_self_removal());
+
+    // Alice commits without covering Bob's proposal. Bob keeps processing
+    // messages and stays in the pending state.
+    let (commit, _welcome, _group_info) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("Could not create self update.")
+        .into_contents();
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    assert!(bob_group.is_active());
+    assert!(bob_group.is_pending_self_removal());
+
+    // Bob re-sends his proposal, which Alice commits.
+    let proposal = bob_group.leave_group(provider, &bob_signer).unwrap();
+    let processed_message = alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .expect("Could not process message.");
+    let ProcessedMessageContent::ProposalMessage(queued_proposal) =
+        processed_message.into_content()
+    else {
+        panic!("Expected a proposal.");
+    };
+    alice_group
+        .store_pending_proposal(provider.storage(), *queued_proposal)
+        .unwrap();
+    let (commit, _welcome, _group_info) = alice_group
+        .commit_to_pending_proposals(provider, &alice_signer)
+        .expect("Could not commit to proposals.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    assert!(!bob_group.is_active());
+    assert!(!bob_group.is_pending_self_removal());
+}
+
+#[openmls_test]
+fn removal_grace_period() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    bob_group
+        .set_configuration(
+            provider.storage(),
+            &MlsGroupJoinConfig::builder()
+                .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+                .removal_grace_period(true)
+                .inactive_group_retention(std::time::Duration::ZERO)
+                .build(),
+        )
+        .unwrap();
+    let final_epoch = bob_group.epoch();
+
+    // Alice sends two messages and removes Bob before he receives them.
+    let messages: Vec<MlsMessageOut> = [b"first".as_slice(), b"second".as_slice()]
+        .into_iter()
+        .map(|message| {
+            alice_group
+                .create_message(provider, &alice_signer, message)
+                .unwrap()
+        })
+        .collect();
+    let (commit, _welcome, _group_info) = alice_group
+        .remove_members(provider, &alice_signer, &[bob_group.own_leaf_index()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    assert!(!bob_group.is_active());
+    assert_eq!(bob_group.removal_cutoff(), Some(final_epoch));
+
+    // Bob can still read the message of the final epoch ...
+    let processed_message = bob_group
+        .process_message(
+            provider,
+            messages[0].clone().into_protocol_message().unwrap(),
+        )
+        .unwrap();
+    let ProcessedMessageContent::ApplicationMessage(application_message) =
+        processed_message.into_content()
+    else {
+        panic!("Expected an application message.");
+    };
+    assert_eq!(application_message.into_bytes(), b"first");
+
+    // ... but not messages of later epochs.
+    let later_message = alice_group
+        .create_message(provider, &alice_signer, b"third")
+        .unwrap();
+    assert!(matches!(
+        bob_group.process_message(provider, later_message.into_protocol_message().unwrap()),
+        Err(ProcessMessageError::GroupStateError(
+            MlsGroupStateError::UseAfterEviction
+        ))
+    ));
+    assert!(matches!(
+        bob_group.self_update(provider, &bob_signer, LeafNodeParameters::default()),
+        Err(SelfUpdateError::GroupStateError(
+            MlsGroupStateError::UseAfterEviction
+        ))
+    ));
+
+    // The secrets of the group can't be purged during the grace period.
+    assert_eq!(bob_group.inactive_since(), None);
+    assert!(!bob_group.purge_due());
+
+    // After the grace period, no more messages are processed and the secrets
+    // are due for purging.
+    bob_group
+        .end_removal_grace_period(provider.storage())
+        .unwrap();
+    assert_eq!(bob_group.removal_cutoff(), None);
+    assert!(bob_group.inactive_since().is_some());
+    assert!(bob_group.purge_due());
+    let loaded_group = MlsGroup::load(provider.storage(), bob_group.group_id())
+        .expect("Could not load group.")
+        .expect("Group not found.");
+    assert!(loaded_group.purge_due());
+    assert!(matches!(
+        bob_group.process_message(
+            provider,
+            messages[1].clone().into_protocol_message().unwrap()
+        ),
+        Err(ProcessMessageError::GroupStateError(
+            MlsGroupStateError::UseAfterEviction
+        ))
+    ));
+}
+
+#[openmls_test]
+fn inactive_group_purge() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    bob_group
+        .set_configuration(
+            provider.storage(),
+            &MlsGroupJoinConfig::builder()
+                .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+                .inactive_group_retention(std::time::Duration::ZERO)
+                .build(),
+        )
+        .unwrap();
+    let group_id = bob_group.group_id().clone();
+
+    // Active groups can't be purged.
+    assert_eq!(bob_group.inactive_since(), None);
+    assert!(!bob_group.purge_due());
+    assert!(bob_group.inactive_record().is_none());
+    assert_eq!(
+        bob_group.purge_secrets(provider.storage()).unwrap_err(),
+        PurgeInactiveGroupError::GroupActive
+    );
+    let mut bob_group = MlsGroup::load(provider.storage(), &group_id)
+        .unwrap()
+        .unwrap();
+
+    // Alice removes Bob.
+    let (commit, _welcome, _group_info) = alice_group
+        .remove_members(provider, &alice_signer, &[bob_group.own_leaf_index()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+
+    assert!(bob_group.inactive_since().is_some());
+    assert!(bob_group.purge_due());
+    let record = bob_group.inactive_record().unwrap();
+    assert_eq!(record.last_epoch(), bob_group.epoch());
+    assert!(record.has_secrets());
+
+    let purged_record = bob_group.purge_secrets(provider.storage()).unwrap();
+    assert!(!purged_record.has_secrets());
+    assert_eq!(purged_record.members(), record.members());
+
+    // Only the read-only record remains.
+    assert!(MlsGroup::load(provider.storage(), &group_id)
+        .unwrap()
+        .is_none());
+    let loaded_record = InactiveGroupRecord::load(provider.storage(), &group_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded_record.last_epoch(), record.last_epoch());
+    assert_eq!(loaded_record.members(), record.members());
+    assert!(loaded_record.membership_history().is_empty());
+    assert!(!loaded_record.has_secrets());
+}
+
+#[openmls_test]
+fn closed_group_view() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    assert!(bob_group.closed_view().is_none());
+    let group_id = bob_group.group_id().clone();
+    let bob_leaf_index = bob_group.own_leaf_index();
+
+    let (commit, _welcome, _group_info) = alice_group
+        .remove_members(provider, &alice_signer, &[bob_leaf_index])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+
+    let view = bob_group.closed_view().unwrap();
+    assert_eq!(view.group_id(), &group_id);
+    assert_eq!(view.epoch(), alice_group.epoch());
+    assert_eq!(view.own_leaf_index(), bob_leaf_index);
+    assert_eq!(view.extensions(), alice_group.extensions());
+    assert_eq!(view.members().len(), 1);
+    assert_eq!(
+        view.member(alice_group.own_leaf_index()),
+        alice_group.credential().ok()
+    );
+    assert!(view.member(bob_leaf_index).is_none());
+
+    let loaded_view = ClosedGroupView::load(provider.storage(), &group_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded_view.epoch(), view.epoch());
+    assert_eq!(loaded_view.members(), view.members());
+    assert_eq!(loaded_view.inactive_since(), view.inactive_since());
+    assert_eq!(loaded_view.record(), view.record());
+    assert_eq!(view.record(), &bob_group.inactive_record().unwrap());
+}
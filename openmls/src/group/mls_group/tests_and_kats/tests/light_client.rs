@@ -0,0 +1,362 @@
+//! Tests for light clients and for the validation artifacts of commits.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    framing::*,
+    group::{mls_group::tests_and_kats::utils::setup_client, *},
+    messages::proposals::*,
+    tree::{secret_tree::SecretTreeError, sender_ratchet::SenderRatchetConfiguration},
+    treesync::{errors::TreeSliceError, LeafNodeParameters},
+};
+
+#[openmls_test]
+fn light_client_join() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+    let mut alice_group = MlsGroup::new_with_members(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key,
+        &[
+            bob_kpb.key_package().clone(),
+            charlie_kpb.key_package().clone(),
+        ],
+    )
+    .map(|(group, _welcome, _group_info)| group)
+    .unwrap();
+
+    // === Alice adds Dave, who joins as a light client ===
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(provider, &alice_signer, &[dave_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().unwrap();
+
+    let dave_index = LeafNodeIndex::new(3);
+    let dave_slice = alice_group
+        .export_tree_slice(provider.crypto(), dave_index)
+        .unwrap();
+    let alice_slice = alice_group
+        .export_tree_slice(provider.crypto(), alice_group.own_leaf_index())
+        .unwrap();
+    assert_eq!(dave_slice.leaf_count(), 4);
+    assert_eq!(dave_slice.direct_path().len(), 2);
+    assert_eq!(
+        dave_slice.leaf().unwrap().signature_key(),
+        dave_kpb.key_package().leaf_node().signature_key()
+    );
+    assert!(alice_group
+        .export_tree_slice(provider.crypto(), LeafNodeIndex::new(4))
+        .is_err());
+
+    // The slices must be the ones of the new member and of the signer.
+    let charlie_slice = alice_group
+        .export_tree_slice(provider.crypto(), LeafNodeIndex::new(2))
+        .unwrap();
+    let err = ProcessedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.clone(),
+    )
+    .unwrap()
+    .into_light_group(provider, charlie_slice.clone(), alice_slice.clone())
+    .unwrap_err();
+    assert!(matches!(err, WelcomeError::PublicTreeError(_)));
+
+    let err = ProcessedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.clone(),
+    )
+    .unwrap()
+    .into_light_group(provider, dave_slice.clone(), dave_slice.clone())
+    .unwrap_err();
+    assert!(matches!(err, WelcomeError::UnknownSender));
+
+    // Dave doesn't accept messages from the future.
+    let dave_join_config = MlsGroupJoinConfig::builder()
+        .sender_ratchet_configuration(SenderRatchetConfiguration::new(0, 0))
+        .build();
+    let mut light_group = ProcessedWelcome::new_from_welcome(provider, &dave_join_config, welcome)
+        .unwrap()
+        .into_light_group(provider, dave_slice.clone(), alice_slice.clone())
+        .unwrap();
+
+    assert_eq!(light_group.configuration(), &dave_join_config);
+    assert_eq!(light_group.own_leaf_index(), dave_index);
+    assert_eq!(light_group.epoch(), alice_group.epoch());
+    assert_eq!(light_group.tree_slice(), &dave_slice);
+    assert_eq!(
+        light_group.epoch_authenticator(),
+        alice_group.epoch_authenticator()
+    );
+    assert_eq!(
+        light_group
+            .export_secret(provider.crypto(), "label", b"context", 32)
+            .unwrap(),
+        alice_group
+            .export_secret(provider, "label", b"context", 32)
+            .unwrap()
+    );
+
+    // === Alice sends a message, which Dave decrypts with her slice ===
+    let message = alice_group
+        .create_message(provider, &alice_signer, b"Hello, Dave")
+        .unwrap();
+    let MlsMessageBodyIn::PrivateMessage(message) = MlsMessageIn::from(message).extract() else {
+        panic!("Expected a private message");
+    };
+
+    let err = light_group
+        .decrypt_application_message(provider, message.clone(), &charlie_slice)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        LightGroupDecryptError::ValidationError(ValidationError::UnknownMember)
+    ));
+    let decrypted = light_group
+        .decrypt_application_message(provider, message.clone(), &alice_slice)
+        .unwrap();
+    assert_eq!(decrypted.into_bytes(), b"Hello, Dave");
+
+    // The sender ratchet configuration of the join config applies, so Dave
+    // can't skip a message.
+    alice_group
+        .create_message(provider, &alice_signer, b"Skipped")
+        .unwrap();
+    let ahead = alice_group
+        .create_message(provider, &alice_signer, b"Ahead")
+        .unwrap();
+    let MlsMessageBodyIn::PrivateMessage(ahead) = MlsMessageIn::from(ahead).extract() else {
+        panic!("Expected a private message");
+    };
+    assert!(matches!(
+        light_group.decrypt_application_message(provider, ahead, &alice_slice),
+        Err(LightGroupDecryptError::ValidationError(
+            ValidationError::UnableToDecrypt(MessageDecryptionError::SecretTreeError(
+                SecretTreeError::TooDistantInTheFuture
+            ))
+        ))
+    ));
+
+    // The light group is stored with the advanced secret tree, so the message
+    // can't be decrypted again after loading it.
+    let mut loaded = LightGroup::load(provider.storage(), alice_group.group_id())
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded.group_context(), light_group.group_context());
+    assert!(matches!(
+        loaded.decrypt_application_message(provider, message, &alice_slice),
+        Err(LightGroupDecryptError::ValidationError(
+            ValidationError::UnableToDecrypt(_)
+        ))
+    ));
+
+    loaded.delete(provider.storage()).unwrap();
+    assert!(LightGroup::load(provider.storage(), alice_group.group_id())
+        .unwrap()
+        .is_none());
+
+    // The slice is bound to the tree hash of the epoch.
+    dave_slice
+        .verify(
+            provider.crypto(),
+            ciphersuite,
+            alice_group.export_group_context().tree_hash(),
+        )
+        .unwrap();
+    let (_commit, _welcome, _group_info) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_messages();
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(
+        dave_slice.verify(
+            provider.crypto(),
+            ciphersuite,
+            alice_group.export_group_context().tree_hash(),
+        ),
+        Err(TreeSliceError::TreeHashMismatch)
+    );
+}
+
+#[openmls_test]
+fn commit_validation_artifact() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let mut alice_group = MlsGroup::new_with_members(
+        provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::test_default(ciphersuite),
+        alice_credential_with_key,
+        &[
+            bob_kpb.key_package().clone(),
+            charlie_kpb.key_package().clone(),
+        ],
+    )
+    .map(|(group, _welcome, _group_info)| group)
+    .unwrap();
+
+    // The DS only keeps track of the group context of the current epoch.
+    let ds_group_context = alice_group.export_group_context().clone();
+    let into_protocol_message = |message: &MlsMessageOut| {
+        MlsMessageIn::from(message.clone())
+            .try_into_protocol_message()
+            .unwrap()
+    };
+
+    // Artifacts are only produced on request.
+    let bundle = alice_group
+        .commit_builder()
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert!(bundle
+        .sign_validation_artifact(&alice_signer)
+        .unwrap()
+        .is_none());
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+
+    // === Alice removes Charlie ===
+    let charlie_index = LeafNodeIndex::new(2);
+    let remove_bundle = alice_group
+        .commit_builder()
+        .propose_removals([charlie_index])
+        .validation_artifact(true)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    let remove_artifact = remove_bundle
+        .sign_validation_artifact(&alice_signer)
+        .unwrap()
+        .unwrap();
+    let remove_commit = into_protocol_message(remove_bundle.commit());
+    assert_eq!(
+        remove_artifact.sender_slice().leaf_index(),
+        alice_group.own_leaf_index()
+    );
+    assert_eq!(remove_artifact.proposals().len(), 1);
+    let removed = &remove_artifact.proposals()[0];
+    assert_eq!(removed.proposal_type(), ProposalType::Remove);
+    assert_eq!(removed.removed().unwrap().leaf_index(), charlie_index);
+    remove_artifact
+        .validate(provider.crypto(), &ds_group_context, &remove_commit)
+        .unwrap();
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+
+    // === Alice updates instead ===
+    let update_bundle = alice_group
+        .commit_builder()
+        .force_self_update(true)
+        .validation_artifact(true)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    let update_artifact = update_bundle
+        .sign_validation_artifact(&alice_signer)
+        .unwrap()
+        .unwrap();
+    let update_commit = into_protocol_message(update_bundle.commit());
+    assert!(update_artifact.proposals().is_empty());
+
+    // The artifact must match the commit and be signed by the committer.
+    assert_eq!(
+        remove_artifact.validate(provider.crypto(), &ds_group_context, &update_commit),
+        Err(ArtifactValidationError::CommitMismatch)
+    );
+    let forged_artifact = update_bundle
+        .sign_validation_artifact(&bob_signer)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        forged_artifact.validate(provider.crypto(), &ds_group_context, &update_commit),
+        Err(ArtifactValidationError::InvalidSignature)
+    );
+    update_artifact
+        .validate(provider.crypto(), &ds_group_context, &update_commit)
+        .unwrap();
+
+    // The DS moves on to the next epoch with the claimed group context.
+    alice_group.merge_pending_commit(provider).unwrap();
+    let ds_group_context = update_artifact.next_group_context().clone();
+    assert_eq!(&ds_group_context, alice_group.export_group_context());
+    assert_eq!(
+        update_artifact.validate(provider.crypto(), &ds_group_context, &update_commit),
+        Err(ArtifactValidationError::WrongEpoch)
+    );
+
+    // === Alice removes Charlie in a private commit ===
+    alice_group
+        .set_wire_format_policy(provider.storage(), PURE_CIPHERTEXT_WIRE_FORMAT_POLICY)
+        .unwrap();
+    let private_bundle = alice_group
+        .commit_builder()
+        .propose_removals([charlie_index])
+        .validation_artifact(true)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    let private_artifact = private_bundle
+        .sign_validation_artifact(&alice_signer)
+        .unwrap()
+        .unwrap();
+    let private_commit = into_protocol_message(private_bundle.commit());
+    assert!(matches!(private_commit, ProtocolMessage::PrivateMessage(_)));
+    private_artifact
+        .validate(provider.crypto(), &ds_group_context, &private_commit)
+        .unwrap();
+
+    // The private commit is bound to the artifact it was staged with.
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+    let other_bundle = alice_group
+        .commit_builder()
+        .propose_removals([charlie_index])
+        .validation_artifact(true)
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    let other_commit = into_protocol_message(other_bundle.commit());
+    assert_eq!(
+        private_artifact.validate(provider.crypto(), &ds_group_context, &other_commit),
+        Err(ArtifactValidationError::CommitMismatch)
+    );
+}
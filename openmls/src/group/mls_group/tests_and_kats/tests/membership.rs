@@ -0,0 +1,468 @@
+//! Tests for managing the members of a group and their key packages.
+
+use openmls_test::openmls_test;
+use openmls_traits::{storage::StorageReader as _, OpenMlsProvider as _};
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    credentials::{BasicCredential, Credential},
+    framing::*,
+    group::{mls_group::tests_and_kats::utils::setup_client, *},
+    key_packages::*,
+    messages::Welcome,
+};
+
+#[openmls_test]
+fn refresh_key_package_lifetime() {
+    use crate::key_packages::{
+        errors::KeyPackageRefreshError, refresh::purge_superseded_key_packages,
+    };
+
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let (_bob_credential, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+    let old_ref = bob_kpb
+        .key_package()
+        .hash_ref(bob_provider.crypto())
+        .unwrap();
+
+    // Only the owner of the key package can refresh it.
+    assert_eq!(
+        refresh_key_package(
+            bob_provider,
+            &alice_signer,
+            &old_ref,
+            Lifetime::new(60 * 60 * 24 * 7)
+        )
+        .unwrap_err(),
+        KeyPackageRefreshError::SignerMismatch
+    );
+
+    let lifetime = Lifetime::new(60 * 60 * 24 * 7);
+    let refreshed = refresh_key_package(bob_provider, &bob_signer, &old_ref, lifetime).unwrap();
+    let new_ref = refreshed
+        .key_package()
+        .hash_ref(bob_provider.crypto())
+        .unwrap();
+    assert_ne!(new_ref, old_ref);
+    assert_eq!(refreshed.key_package().life_time(), &lifetime);
+    assert_eq!(
+        refreshed.key_package().hpke_init_key(),
+        bob_kpb.key_package().hpke_init_key()
+    );
+    assert_eq!(
+        refreshed.key_package().leaf_node().encryption_key(),
+        bob_kpb.key_package().leaf_node().encryption_key()
+    );
+    assert_eq!(refreshed.supersedes(), Some(&old_ref));
+
+    let old: KeyPackageBundle = bob_provider
+        .storage()
+        .key_package(&old_ref)
+        .unwrap()
+        .unwrap();
+    assert_eq!(old.superseded_by(), Some(&new_ref));
+    assert_eq!(
+        refresh_key_package(bob_provider, &bob_signer, &old_ref, lifetime).unwrap_err(),
+        KeyPackageRefreshError::AlreadySuperseded
+    );
+    // The superseded key package is still valid and kept.
+    assert!(purge_superseded_key_packages(bob_provider.storage())
+        .unwrap()
+        .is_empty());
+
+    // Joining with the refreshed key package deletes the superseded one.
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential,
+    )
+    .unwrap();
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[refreshed.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    let welcome: MlsMessageIn = welcome.into();
+    StagedWelcome::new_from_welcome(
+        bob_provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap()
+    .into_group(bob_provider)
+    .unwrap();
+
+    for hash_ref in [old_ref, new_ref] {
+        let bundle: Option<KeyPackageBundle> =
+            bob_provider.storage().key_package(&hash_ref).unwrap();
+        assert!(bundle.is_none());
+    }
+}
+
+#[openmls_test]
+fn add_many_members_fills_blank_leaves() {
+    let alice_provider = &Provider::default();
+    let members_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let key_packages = |names: std::ops::Range<usize>| -> Vec<KeyPackage> {
+        names
+            .map(|i| {
+                let (_credential, kpb, _signer, _pk) =
+                    setup_client(&format!("Member {i}"), ciphersuite, members_provider);
+                kpb.key_package().clone()
+            })
+            .collect()
+    };
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential,
+    )
+    .unwrap();
+
+    alice_group
+        .add_members(alice_provider, &alice_signer, &key_packages(1..21))
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    alice_group
+        .remove_members(
+            alice_provider,
+            &alice_signer,
+            &[LeafNodeIndex::new(3), LeafNodeIndex::new(7)],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+
+    // The blank leaves are filled first, then the tree is extended.
+    let new_key_packages = key_packages(21..26);
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(alice_provider, &alice_signer, &new_key_packages)
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    let new_indices: Vec<u32> = new_key_packages
+        .iter()
+        .map(|key_package| {
+            alice_group
+                .members()
+                .find(|member| &member.credential == key_package.leaf_node().credential())
+                .unwrap()
+                .index
+                .u32()
+        })
+        .collect();
+    assert_eq!(new_indices, vec![3, 7, 21, 22, 23]);
+
+    // Every new member can join with the Welcome.
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().unwrap();
+    assert_eq!(welcome.secrets().len(), new_key_packages.len());
+    let member_group = StagedWelcome::new_from_welcome(
+        members_provider,
+        mls_group_create_config.join_config(),
+        welcome,
+        None,
+    )
+    .unwrap()
+    .into_group(members_provider)
+    .unwrap();
+    assert_eq!(member_group.members().count(), 24);
+}
+
+#[openmls_test]
+fn remove_members_by_credential() {
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    // Credentials have the form `user@device`.
+    let extractor = |credential: &Credential| -> Option<Vec<u8>> {
+        let identity = BasicCredential::try_from(credential.clone()).ok()?;
+        let user = identity.identity().split(|byte| *byte == b'@').next()?;
+        Some(user.to_vec())
+    };
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("alice@phone", ciphersuite, alice_provider);
+    // Bob has two devices with different credentials.
+    let (bob_credential, bob_phone_kpb, _bob_phone_signer, _bob_phone_pk) =
+        setup_client("bob@phone", ciphersuite, bob_provider);
+    let (_bob_credential, bob_laptop_kpb, _bob_laptop_signer, _bob_laptop_pk) =
+        setup_client("bob@laptop", ciphersuite, bob_provider);
+    let (charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("charlie@phone", ciphersuite, bob_provider);
+    let (dave_credential, _dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("dave@phone", ciphersuite, bob_provider);
+
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::builder()
+            .ciphersuite(ciphersuite)
+            .build()
+            .unwrap(),
+        alice_credential.clone(),
+    )
+    .unwrap();
+    alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[
+                bob_phone_kpb.key_package().clone(),
+                bob_laptop_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+
+    assert_eq!(
+        alice_group
+            .remove_members_by_credential(
+                alice_provider,
+                &alice_signer,
+                &extractor,
+                &[dave_credential.credential.clone()]
+            )
+            .unwrap_err(),
+        RemoveMembersError::UnknownMember
+    );
+    // The own leaf is never removed.
+    assert_eq!(
+        alice_group
+            .remove_members_by_credential(
+                alice_provider,
+                &alice_signer,
+                &extractor,
+                &[alice_credential.credential.clone()]
+            )
+            .unwrap_err(),
+        RemoveMembersError::UnknownMember
+    );
+
+    // The credential of Bob's phone removes Bob's laptop as well.
+    let (_commit, welcome, _group_info, unmatched) = alice_group
+        .remove_members_by_credential(
+            alice_provider,
+            &alice_signer,
+            &extractor,
+            &[
+                bob_credential.credential.clone(),
+                dave_credential.credential.clone(),
+            ],
+        )
+        .unwrap();
+    assert!(welcome.is_none());
+    assert_eq!(unmatched, vec![dave_credential.credential]);
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+
+    let remaining: Vec<Credential> = alice_group.members().map(|m| m.credential).collect();
+    assert_eq!(
+        remaining,
+        vec![alice_credential.credential, charlie_credential.credential]
+    );
+}
+
+#[openmls_test]
+fn swap_member() {
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let (_bob_credential, bob_phone_kpb, _bob_phone_signer, _bob_phone_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+    let (_bob_credential, bob_laptop_kpb, _bob_laptop_signer, _bob_laptop_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential,
+    )
+    .unwrap();
+    alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[bob_phone_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    let bob_phone_index = LeafNodeIndex::new(1);
+
+    assert_eq!(
+        alice_group
+            .swap_member(
+                alice_provider,
+                &alice_signer,
+                alice_group.own_leaf_index(),
+                bob_laptop_kpb.key_package().clone()
+            )
+            .unwrap_err(),
+        SwapMemberError::CannotSwapSelf
+    );
+    assert_eq!(
+        alice_group
+            .swap_member(
+                alice_provider,
+                &alice_signer,
+                LeafNodeIndex::new(5),
+                bob_laptop_kpb.key_package().clone()
+            )
+            .unwrap_err(),
+        SwapMemberError::UnknownMember
+    );
+
+    let (_commit, welcome, _group_info) = alice_group
+        .swap_member(
+            alice_provider,
+            &alice_signer,
+            bob_phone_index,
+            bob_laptop_kpb.key_package().clone(),
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+
+    // The new device took over the leaf of the old one.
+    let bob_leaf = alice_group.public_group().leaf(bob_phone_index).unwrap();
+    assert_eq!(
+        bob_leaf.signature_key(),
+        bob_laptop_kpb.key_package().leaf_node().signature_key()
+    );
+    assert_eq!(alice_group.members().count(), 2);
+
+    let welcome: MlsMessageIn = welcome.into();
+    let bob_laptop_group = StagedWelcome::new_from_welcome(
+        bob_provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap()
+    .into_group(bob_provider)
+    .unwrap();
+    assert_eq!(bob_laptop_group.own_leaf_index(), bob_phone_index);
+}
+
+#[openmls_test]
+fn user_devices() {
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    // Credentials have the form `user@device`.
+    let extractor = |credential: &Credential| -> Option<Vec<u8>> {
+        let identity = BasicCredential::try_from(credential.clone()).ok()?;
+        let user = identity.identity().split(|byte| *byte == b'@').next()?;
+        Some(user.to_vec())
+    };
+    let domain = |credential: &Credential| -> Option<Vec<u8>> {
+        let identity = BasicCredential::try_from(credential.clone()).ok()?;
+        Some(
+            identity
+                .identity()
+                .split(|byte| *byte == b'@')
+                .nth(1)?
+                .to_vec(),
+        )
+    };
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("alice@phone", ciphersuite, alice_provider);
+    let key_packages: Vec<KeyPackage> = ["alice@laptop", "bob@phone", "bob@laptop", "charlie"]
+        .into_iter()
+        .map(|id| {
+            let (_credential, kpb, _signer, _pk) = setup_client(id, ciphersuite, bob_provider);
+            kpb.key_package().clone()
+        })
+        .collect();
+
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::builder()
+            .ciphersuite(ciphersuite)
+            .build()
+            .unwrap(),
+        alice_credential,
+    )
+    .unwrap();
+    alice_group
+        .add_members(alice_provider, &alice_signer, &key_packages)
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+
+    let users = alice_group.members_by_user(&extractor);
+    assert_eq!(
+        users.keys().cloned().collect::<Vec<_>>(),
+        vec![b"alice".to_vec(), b"bob".to_vec(), b"charlie".to_vec()]
+    );
+    let bob_devices: Vec<u32> = alice_group
+        .user_devices(&extractor, b"bob")
+        .iter()
+        .map(|member| member.index.u32())
+        .collect();
+    assert_eq!(bob_devices, vec![2, 3]);
+    assert!(alice_group.user_devices(&extractor, b"dave").is_empty());
+
+    // Basic credentials only match the whole identity.
+    assert_eq!(
+        alice_group
+            .user_devices(&BasicCredentialIdentity, b"bob@phone")
+            .len(),
+        1
+    );
+
+    // Bob's devices don't share the device name, and Charlie has none.
+    assert!(!alice_group.user_devices_share(&extractor, b"bob", domain));
+    assert!(!alice_group.user_devices_share(&extractor, b"charlie", domain));
+    assert!(alice_group.user_devices_share(&extractor, b"charlie", |_: &Credential| Some(())));
+
+    // The own leaf is never removed.
+    alice_group
+        .remove_user(alice_provider, &alice_signer, &extractor, b"alice")
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    assert_eq!(alice_group.user_devices(&extractor, b"alice").len(), 1);
+
+    alice_group
+        .remove_user(alice_provider, &alice_signer, &extractor, b"bob")
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    assert!(alice_group.user_devices(&extractor, b"bob").is_empty());
+    assert_eq!(
+        alice_group
+            .remove_user(alice_provider, &alice_signer, &extractor, b"bob")
+            .unwrap_err(),
+        RemoveMembersError::UnknownMember
+    );
+}
@@ -90,7 +90,8 @@ fn remover() {
     // Define the MlsGroup configuration
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new_with_group_id(
@@ -1022,7 +1023,8 @@ fn key_package_deletion() {
     // Define the MlsGroup configuration
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new_with_group_id(
@@ -1087,7 +1089,8 @@ fn remove_prosposal_by_ref(
     // Define the MlsGroup configuration
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new_with_group_id(
@@ -1171,7 +1174,8 @@ fn max_past_epochs_join_config(
 
     let create_config = MlsGroupCreateConfig::builder()
         .max_past_epochs(max_past_epochs)
-        .build();
+        .build()
+        .unwrap();
 
     let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
         setup_client("Alice", ciphersuite, provider);
@@ -1334,7 +1338,8 @@ fn update_group_context_with_unknown_extension<Provider: OpenMlsProvider + Defau
         .expect("error adding unknown extension to config")
         .capabilities(capabilities.clone())
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new(
@@ -1523,7 +1528,8 @@ fn update_proposal_bob() {
 
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new(
@@ -1629,7 +1635,8 @@ fn update_proposal_alice() {
 
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new(
@@ -1762,7 +1769,8 @@ fn test_update_group_context_with_unknown_extension_using_update_function<
         .expect("error adding unknown extension to config")
         .capabilities(capabilities.clone())
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new(
@@ -2047,10 +2055,12 @@ fn deletion() {
         setup_client("alice", ciphersuite, provider);
 
     // delete the kpb from the provider, as we don't need it
-    <MemoryStorage as openmls_traits::storage::StorageProvider<CURRENT_VERSION>>::
-        delete_key_package(alice_provider.storage(),&alice_kpb.key_package().hash_ref(provider.crypto()).unwrap())
-        .unwrap();
-    <MemoryStorage as openmls_traits::storage::StorageProvider<CURRENT_VERSION>>::
+    <MemoryStorage as openmls_traits::storage::StorageWriter<CURRENT_VERSION>>::delete_key_package(
+        alice_provider.storage(),
+        &alice_kpb.key_package().hash_ref(provider.crypto()).unwrap(),
+    )
+    .unwrap();
+    <MemoryStorage as openmls_traits::storage::StorageWriter<CURRENT_VERSION>>::
         delete_encryption_key_pair(alice_provider.storage(),alice_kpb.key_package().leaf_node().encryption_key()).unwrap();
 
     // alice creates MlsGroup
@@ -2416,7 +2426,16 @@ fn own_commit_processing(
 
     let commit_in = MlsMessageIn::from(commit_out);
 
-    // Alice attempts to process her own commit
+    // Alice receives her own pending commit
+    let error = alice_group
+        .process_message(provider, commit_in.clone().into_protocol_message().unwrap())
+        .expect_err("no error while processing own commit");
+    assert_eq!(error, ProcessMessageError::OwnCommitReceived);
+
+    // Once the pending commit is gone, the commit is rejected
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .expect("Could not clear pending commit");
     let error = alice_group
         .process_message(provider, commit_in.into_protocol_message().unwrap())
         .expect_err("no error while processing own commit");
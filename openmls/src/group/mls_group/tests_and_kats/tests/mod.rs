@@ -1,6 +1,30 @@
 //! Test and Known Answer Test (KAT) modules for the MLS group.
 
+mod app_data;
+mod commit_creation;
+mod commit_races;
+mod creation;
+#[cfg(feature = "compliance-escrow")]
+mod escrow;
+mod exported_secrets;
+mod extensions;
 mod external_init;
+mod inspection;
+#[cfg(feature = "integrity-checks")]
+mod integrity;
+mod leaving;
+mod light_client;
+mod membership;
 mod mls_group;
 mod past_secrets;
+mod policies;
 mod proposals;
+mod psk;
+#[cfg(feature = "resumption-psk-backup")]
+mod psk_backup;
+mod reinit;
+#[cfg(feature = "replica-sync")]
+mod replica;
+mod sender_ratchet;
+mod storage;
+mod welcome;
@@ -0,0 +1,555 @@
+//! Tests for the policies that are applied to incoming commits and for the
+//! tracking of member activity.
+
+use std::sync::{Arc, Mutex};
+
+use openmls_test::openmls_test;
+use openmls_traits::{storage::StorageReader as _, OpenMlsProvider as _};
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::hash_ref::ProposalRef,
+    credentials::Credential,
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{
+            process_and_merge_commit, setup_alice_bob_group, setup_client,
+        },
+        *,
+    },
+    messages::proposals::*,
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn staged_commit_policy() {
+    // A policy that protects the admin of the group from being removed.
+    struct ProtectAdmin(LeafNodeIndex);
+
+    #[derive(Debug, PartialEq, thiserror::Error)]
+    #[error("The admin can't be removed.")]
+    struct AdminRemoval;
+
+    impl ProcessingPolicy for ProtectAdmin {
+        fn validate_staged_commit(
+            &self,
+            commit: &ReceivedCommitInfo,
+        ) -> Result<(), PolicyRejection> {
+            assert!(commit.group().leaf(self.0).is_some());
+            if commit
+                .staged_commit()
+                .remove_proposals()
+                .any(|remove| remove.remove_proposal().removed() == self.0)
+            {
+                return Err(PolicyRejection::application(AdminRemoval));
+            }
+            Ok(())
+        }
+    }
+
+    let (mut alice_group, _alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let admin = alice_group.own_leaf_index();
+
+    // Bob tries to remove Alice, the admin.
+    let (commit, _, _) = bob_group
+        .remove_members(provider, &bob_signer, &[admin])
+        .unwrap();
+    let commit = commit.into_protocol_message().unwrap();
+
+    let err = alice_group
+        .process_message_with_policy(provider, commit.clone(), &ProtectAdmin(admin))
+        .unwrap_err();
+    let ProcessMessageError::InvalidCommit(StageCommitError::RejectedByPolicy(
+        PolicyRejection::Application(error),
+    )) = err
+    else {
+        panic!("expected the commit to be rejected by the policy: {err:?}");
+    };
+    assert_eq!(error.downcast_ref(), Some(&AdminRemoval));
+    assert!(alice_group.is_active());
+
+    // Without the policy, the commit is valid.
+    let processed_message = alice_group.process_message(provider, commit).unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    assert!(staged_commit.self_removed());
+}
+
+#[openmls_test]
+fn protected_members() {
+    let (mut alice_group, _alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let alice = alice_group.own_leaf_index();
+    let alice_credential = alice_group.own_leaf_node().unwrap().credential().clone();
+    let deny_all =
+        ProtectedMembers::new(|_: &Sender, _: &Credential| false).protect(alice_credential.clone());
+    assert!(deny_all.is_protected(&alice_credential));
+
+    // A standalone Remove proposal for Alice is rejected.
+    let (proposal, _) = bob_group
+        .propose_remove_member(provider, &bob_signer, alice)
+        .unwrap();
+    let err = alice_group
+        .process_message_with_policy(
+            provider,
+            proposal.into_protocol_message().unwrap(),
+            &deny_all,
+        )
+        .unwrap_err();
+    assert_eq!(err, ProcessMessageError::ProtectedMemberRemoval);
+
+    // So is a commit that removes Alice.
+    bob_group
+        .clear_pending_proposals(provider.storage())
+        .unwrap();
+    let (commit, _, _) = bob_group
+        .commit_builder()
+        .propose_removals([alice])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &bob_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .map(|bundle| bundle.into_contents())
+        .unwrap();
+    let commit = commit.into_protocol_message().unwrap();
+    let err = alice_group
+        .process_message_with_policy(provider, commit.clone(), &deny_all)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::ProposalValidationError(
+            ProposalValidationError::ProtectedMemberRemoval
+        ))
+    );
+
+    // Unless the policy authorizes the sender.
+    let bob = bob_group.own_leaf_index();
+    let authorize_bob =
+        ProtectedMembers::new(|sender: &Sender, _: &Credential| sender == &Sender::Member(bob))
+            .protect(alice_credential);
+    let processed_message = alice_group
+        .process_message_with_policy(provider, commit, &authorize_bob)
+        .unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    assert!(staged_commit.self_removed());
+}
+
+#[openmls_test]
+fn protected_members_in_own_commits() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (commit, _, _) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[charlie_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+    let charlie = alice_group
+        .members()
+        .find(|member| member.credential == charlie_credential.credential)
+        .unwrap()
+        .index;
+
+    // The protection is combined with another policy.
+    let mut policy = (
+        ProposalSelection::new().removes_first(),
+        ProtectedMembers::new(|_: &Sender, _: &Credential| false)
+            .protect(charlie_credential.credential.clone()),
+    );
+
+    // The combination rejects a Remove proposal for Charlie.
+    let (proposal, _) = bob_group
+        .propose_remove_member(provider, &bob_signer, charlie)
+        .unwrap();
+    let err = alice_group
+        .process_message_with_policy(provider, proposal.into_protocol_message().unwrap(), &policy)
+        .unwrap_err();
+    assert_eq!(err, ProcessMessageError::ProtectedMemberRemoval);
+
+    // When creating a commit, a pending proposal of Bob to remove Charlie is
+    // left out.
+    let (proposal, proposal_ref) = bob_group
+        .propose_remove_member(provider, &bob_signer, charlie)
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(queued_proposal) = alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap()
+        .into_content()
+    else {
+        panic!("expected a proposal");
+    };
+    alice_group
+        .store_pending_proposal(provider.storage(), *queued_proposal)
+        .unwrap();
+    let bundle = alice_group
+        .commit_builder()
+        .load_psks(provider.storage())
+        .unwrap()
+        .build_with_policy(
+            provider.rand(),
+            provider.crypto(),
+            &alice_signer,
+            &mut policy,
+        )
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert!(bundle.report().included().is_empty());
+    assert_eq!(
+        bundle.report().filter_reason(&proposal_ref),
+        Some(ProposalFilterReason::ApplicationPolicy)
+    );
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+
+    // Own proposals to remove Charlie fail the commit.
+    let err = alice_group
+        .commit_builder()
+        .consume_proposal_store(false)
+        .propose_removals([charlie])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build_with_policy(
+            provider.rand(),
+            provider.crypto(),
+            &alice_signer,
+            &mut policy,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        CreateCommitError::ProposalValidationError(ProposalValidationError::ProtectedMemberRemoval)
+    );
+}
+
+#[openmls_test]
+fn mandatory_update_policy() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let bob = bob_group.own_leaf_index();
+    let joined = bob_group.epoch();
+
+    // The policies are brought up to date with every merge, and Bob's handler
+    // is told about the leaves that become stale.
+    let bob_events = Arc::new(Mutex::new(Vec::new()));
+    let events = bob_events.clone();
+    alice_group.set_epoch_hooks(EpochHooks::new().with_update_policy(
+        MandatoryUpdatePolicy::new(1).reject_stale_commits(true),
+        |_| {},
+    ));
+    bob_group.set_epoch_hooks(EpochHooks::new().with_update_policy(
+        MandatoryUpdatePolicy::new(1).block_application_messages(true),
+        move |stale_leaves| events.lock().unwrap().extend_from_slice(stale_leaves),
+    ));
+    alice_group.run_epoch_hooks(provider).unwrap();
+    bob_group.run_epoch_hooks(provider).unwrap();
+    let bob_policy =
+        |bob_group: &MlsGroup| bob_group.epoch_hooks().update_policy().unwrap().clone();
+    assert_eq!(bob_policy(&bob_group).last_update(bob), Some(joined));
+
+    // Alice updates twice, Bob doesn't.
+    for _ in 0..2 {
+        let commit = alice_group
+            .self_update(provider, &alice_signer, LeafNodeParameters::default())
+            .unwrap()
+            .into_commit();
+        alice_group.merge_pending_commit(provider).unwrap();
+        process_and_merge_commit(provider, &mut bob_group, commit);
+    }
+
+    // Bob's leaf is stale now, which was reported once.
+    let stale_leaves = bob_policy(&bob_group).stale_leaves();
+    assert_eq!(stale_leaves.len(), 1);
+    assert_eq!(stale_leaves[0].leaf_index(), bob);
+    assert_eq!(stale_leaves[0].last_update(), joined);
+    assert_eq!(stale_leaves[0].staleness(), 2);
+    assert_eq!(*bob_events.lock().unwrap(), stale_leaves);
+    assert_eq!(
+        alice_group
+            .epoch_hooks()
+            .update_policy()
+            .unwrap()
+            .stale_leaves(),
+        stale_leaves
+    );
+
+    // Bob can't send application messages before updating.
+    let err = bob_group
+        .create_message(provider, &bob_signer, b"hello")
+        .unwrap_err();
+    assert_eq!(err, CreateMessageError::UpdateRequired(stale_leaves[0]));
+
+    // Commits of Bob that don't update the committer's leaf are rejected.
+    let (_charlie_credential, charlie_key_package, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let commit = bob_group
+        .commit_builder()
+        .propose_adds([charlie_key_package.key_package().clone()])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &bob_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap()
+        .into_commit();
+    let err = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::RejectedByPolicy(
+            PolicyRejection::StaleCommitter(_)
+        ))
+    ));
+    bob_group.clear_pending_commit(provider.storage()).unwrap();
+
+    // Once Bob updates, the leaf isn't stale anymore.
+    let commit = bob_group
+        .self_update(provider, &bob_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    bob_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut alice_group, commit);
+    assert!(alice_group
+        .epoch_hooks()
+        .update_policy()
+        .unwrap()
+        .stale_leaves()
+        .is_empty());
+    assert!(bob_policy(&bob_group).stale_leaves().is_empty());
+    bob_group
+        .create_message(provider, &bob_signer, b"hello")
+        .unwrap();
+
+    // The policy survives a round trip through the storage of the application.
+    let serialized = serde_json::to_vec(&bob_policy(&bob_group)).unwrap();
+    let deserialized: MandatoryUpdatePolicy = serde_json::from_slice(&serialized).unwrap();
+    assert_eq!(deserialized, bob_policy(&bob_group));
+}
+
+#[openmls_test]
+fn inactive_members() {
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let alice = alice_group.own_leaf_index();
+    let bob = bob_group.own_leaf_index();
+
+    let mut tracker = ActivityTracker::new();
+    tracker.observe(&alice_group);
+    assert_eq!(tracker.last_activity(bob), Some(alice_group.epoch()));
+
+    // Alice moves the group forward by `epochs` epochs.
+    let advance = |alice_group: &mut MlsGroup,
+                   bob_group: &mut MlsGroup,
+                   tracker: &mut ActivityTracker,
+                   epochs: usize| {
+        for _ in 0..epochs {
+            let commit = alice_group
+                .self_update(provider, &alice_signer, LeafNodeParameters::default())
+                .unwrap()
+                .into_commit();
+            alice_group.merge_pending_commit(provider).unwrap();
+            process_and_merge_commit(provider, &mut bob_group, commit);
+            tracker.observe(alice_group);
+        }
+    };
+
+    advance(&mut alice_group, &mut bob_group, &mut tracker, 2);
+    // The own client is never inactive.
+    assert_eq!(tracker.inactive_members(1), vec![bob]);
+    assert!(tracker.inactive_members(2).is_empty());
+    assert!(!tracker.inactive_members(0).contains(&alice));
+
+    // Bob becomes active again by sending a proposal.
+    let (proposal, _) = bob_group
+        .propose_self_update(provider, &bob_signer, LeafNodeParameters::default())
+        .unwrap();
+    let processed_message = alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    tracker.record(&processed_message);
+    assert_eq!(tracker.last_activity(bob), Some(alice_group.epoch()));
+    assert!(tracker.inactive_members(1).is_empty());
+
+    // After two more epochs without activity, Bob is proposed for removal.
+    advance(&mut alice_group, &mut bob_group, &mut tracker, 2);
+    let proposals = alice_group
+        .propose_remove_inactive_members(provider, &alice_signer, &tracker, 1)
+        .unwrap();
+    assert_eq!(proposals.len(), 1);
+    let processed_message = bob_group
+        .process_message(
+            provider,
+            proposals[0].0.clone().into_protocol_message().unwrap(),
+        )
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(proposal) = processed_message.into_content()
+    else {
+        panic!("expected a proposal");
+    };
+    assert!(matches!(proposal.proposal(), Proposal::Remove(remove) if remove.removed() == bob));
+
+    // The tracker survives a round trip through the storage of the application.
+    let serialized = serde_json::to_vec(&tracker).unwrap();
+    let deserialized: ActivityTracker = serde_json::from_slice(&serialized).unwrap();
+    assert_eq!(deserialized, tracker);
+}
+
+#[openmls_test]
+fn inactive_members_are_proposed_together() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[charlie_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let mut tracker = ActivityTracker::new();
+    tracker.observe(&alice_group);
+    for _ in 0..2 {
+        alice_group
+            .self_update(provider, &alice_signer, LeafNodeParameters::default())
+            .unwrap();
+        alice_group.merge_pending_commit(provider).unwrap();
+    }
+    tracker.observe(&alice_group);
+    let inactive_members = tracker.inactive_members(1);
+    assert_eq!(inactive_members.len(), 2);
+
+    // Charlie is removed, but the tracker still considers Charlie a member.
+    alice_group
+        .remove_members(provider, &alice_signer, &inactive_members[1..])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // Proposing to remove Charlie fails, so Bob's removal isn't queued either.
+    let err = alice_group
+        .propose_remove_inactive_members(provider, &alice_signer, &tracker, 1)
+        .unwrap_err();
+    assert_eq!(err, ProposeRemoveMemberError::UnknownMember);
+    assert_eq!(alice_group.pending_proposals().count(), 0);
+    let queued_proposal_refs: Vec<ProposalRef> = provider
+        .storage()
+        .queued_proposal_refs(alice_group.group_id())
+        .unwrap();
+    assert!(queued_proposal_refs.is_empty());
+}
+
+#[openmls_test]
+fn member_stats() {
+    let (mut alice_group, _alice_signer, mut bob_group, bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let bob = bob_group.own_leaf_index();
+
+    // Without the configuration flag, nothing is recorded.
+    let message = bob_group
+        .create_message(provider, &bob_signer, b"not counted")
+        .unwrap();
+    alice_group
+        .process_message(provider, message.into_protocol_message().unwrap())
+        .unwrap();
+    let stats = alice_group.member_stats(provider.storage()).unwrap();
+    assert_eq!(stats[&bob].messages_received(), 0);
+    assert_eq!(stats[&bob].tracked_since(), alice_group.epoch());
+
+    alice_group
+        .set_configuration(
+            provider.storage(),
+            &MlsGroupJoinConfig::builder()
+                .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+                .collect_member_stats(true)
+                .build(),
+        )
+        .unwrap();
+
+    // An application message is counted as activity.
+    let message = bob_group
+        .create_message(provider, &bob_signer, b"hello")
+        .unwrap();
+    alice_group
+        .process_message(provider, message.into_protocol_message().unwrap())
+        .unwrap();
+    let stats = alice_group.member_stats(provider.storage()).unwrap();
+    assert_eq!(stats[&bob].messages_received(), 1);
+    assert_eq!(stats[&bob].last_activity(), Some(alice_group.epoch()));
+    assert_eq!(stats[&bob].last_commit(), None);
+
+    // Received messages are batched in memory until they are flushed.
+    let group_id = alice_group.group_id().clone();
+    let stored_messages_received = || {
+        let group = MlsGroup::load(provider.storage(), &group_id)
+            .unwrap()
+            .unwrap();
+        group.member_stats(provider.storage()).unwrap()[&bob].messages_received()
+    };
+    assert_eq!(stored_messages_received(), 0);
+    alice_group.flush_member_stats(provider.storage()).unwrap();
+    assert_eq!(stored_messages_received(), 1);
+
+    // A message with a corrupted content is attributed to the sender.
+    let message = bob_group
+        .create_message(provider, &bob_signer, b"corrupted")
+        .unwrap();
+    let Some(ProtocolMessage::PrivateMessage(mut private_message)) =
+        message.into_protocol_message()
+    else {
+        panic!("expected a private message");
+    };
+    let mut ciphertext = private_message.ciphertext().to_vec();
+    *ciphertext.last_mut().unwrap() ^= 0xff;
+    private_message.set_ciphertext(ciphertext);
+    alice_group
+        .process_message(provider, ProtocolMessage::PrivateMessage(private_message))
+        .unwrap_err();
+    let stats = alice_group.member_stats(provider.storage()).unwrap();
+    assert_eq!(stats[&bob].unauthenticated_decryption_failures(), 1);
+    assert_eq!(stats[&bob].messages_received(), 1);
+
+    // A commit is recorded in the epoch it was sent in.
+    let commit_epoch = alice_group.epoch();
+    let commit = bob_group
+        .self_update(provider, &bob_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    bob_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut alice_group, commit);
+    let stats = alice_group.member_stats(provider.storage()).unwrap();
+    assert_eq!(stats[&bob].messages_received(), 2);
+    assert_eq!(stats[&bob].last_commit(), Some(commit_epoch));
+    assert_eq!(stats[&bob].last_activity(), Some(commit_epoch));
+
+    // The statistics power the removal of inactive members.
+    let tracker = alice_group.activity_tracker(provider.storage()).unwrap();
+    assert_eq!(tracker.last_activity(bob), Some(commit_epoch));
+    assert_eq!(tracker.inactive_members(0), vec![bob]);
+    assert!(tracker.inactive_members(1).is_empty());
+
+    // The statistics are deleted together with the group.
+    alice_group.delete(provider.storage()).unwrap();
+    let stored: Option<crate::group::stats::MemberStatsStore> = provider
+        .storage()
+        .member_stats(alice_group.group_id())
+        .unwrap();
+    assert!(stored.is_none());
+}
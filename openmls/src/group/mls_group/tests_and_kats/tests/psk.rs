@@ -0,0 +1,446 @@
+//! Tests for external and resumption PSKs.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    framing::*,
+    group::{mls_group::tests_and_kats::utils::setup_alice_bob_group, *},
+    schedule::{errors::PskError, ExternalPsk, PreSharedKeyId, Psk, PskStore},
+    treesync::LeafNodeParameters,
+};
+
+/// A [`PskStore`] that keeps the external PSKs apart from the storage of the
+/// provider.
+#[derive(Default)]
+struct SeparatePskStore {
+    psks: Vec<(Psk, Vec<u8>)>,
+}
+
+impl PskStore for SeparatePskStore {
+    type Error = std::convert::Infallible;
+
+    fn psk_secret(&self, psk: &Psk) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .psks
+            .iter()
+            .find(|(stored_psk, _)| stored_psk == psk)
+            .map(|(_, secret)| secret.clone()))
+    }
+}
+
+// Test that external PSKs can be served from a PSK store that is separate
+// from the storage of the provider.
+#[openmls_test]
+fn separate_psk_store() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        provider.rand(),
+        Psk::External(ExternalPsk::new(vec![1, 2, 3])),
+    )
+    .unwrap();
+    let secret = Secret::random(ciphersuite, provider.rand()).unwrap();
+    let psk_store = SeparatePskStore {
+        psks: vec![(psk_id.psk().clone(), secret.as_slice().to_vec())],
+    };
+
+    // Alice proposes the PSK and Bob stores the proposal.
+    let (proposal, _) = alice_group
+        .propose_external_psk(provider, &alice_signer, psk_id)
+        .unwrap();
+    let processed_message = bob_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(proposal) = processed_message.into_content()
+    else {
+        panic!("expected a proposal");
+    };
+    bob_group
+        .store_pending_proposal(provider.storage(), *proposal)
+        .unwrap();
+
+    // The PSK is not in the storage of the provider.
+    let result = alice_group.commit_builder().load_psks(provider.storage());
+    assert!(matches!(
+        result,
+        Err(CreateCommitError::PskError(PskError::KeyNotFound))
+    ));
+
+    // Alice commits, loading the PSK from the separate store.
+    let commit = alice_group
+        .commit_builder()
+        .load_psks(&psk_store)
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // Bob can't process the commit without the separate store.
+    let err = bob_group
+        .process_message(provider, commit.clone().into_protocol_message().unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::PskError(PskError::KeyNotFound))
+    );
+
+    let processed_message = bob_group
+        .process_message_with_psk_store(
+            provider,
+            commit.into_protocol_message().unwrap(),
+            &(),
+            &psk_store,
+        )
+        .unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    bob_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+
+    assert_eq!(
+        alice_group.epoch_authenticator(),
+        bob_group.epoch_authenticator()
+    );
+}
+
+#[openmls_test]
+fn resumption_psk_retention() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    assert_eq!(
+        alice_group.configuration().number_of_resumption_psks(),
+        DEFAULT_NUMBER_OF_RESUMPTION_PSKS
+    );
+
+    let self_update = |group: &mut MlsGroup| {
+        group
+            .self_update(provider, &alice_signer, LeafNodeParameters::default())
+            .expect("Could not create self update.");
+        group
+            .merge_pending_commit(provider)
+            .expect("error merging pending commit");
+    };
+
+    for _ in 0..3 {
+        self_update(&mut alice_group);
+    }
+    let epochs = |epochs: &[u64]| {
+        epochs
+            .iter()
+            .copied()
+            .map(GroupEpoch::from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        alice_group.resumption_psk_epochs(),
+        epochs(&[0, 1, 2, 3, 4])
+    );
+    let size = alice_group.resumption_psk_store_size();
+
+    // Reducing the retention prunes the oldest epochs.
+    alice_group
+        .set_number_of_resumption_psks(provider.storage(), 2)
+        .unwrap();
+    assert_eq!(alice_group.resumption_psk_epochs(), epochs(&[3, 4]));
+    assert_eq!(alice_group.resumption_psk_store_size(), size * 2 / 5);
+
+    let loaded_group = MlsGroup::load(provider.storage(), alice_group.group_id())
+        .expect("Could not load group.")
+        .expect("Group not found.");
+    assert_eq!(loaded_group.configuration().number_of_resumption_psks(), 2);
+    assert_eq!(loaded_group.resumption_psk_epochs(), epochs(&[3, 4]));
+
+    // New epochs replace the oldest ones.
+    self_update(&mut alice_group);
+    assert_eq!(alice_group.resumption_psk_epochs(), epochs(&[4, 5]));
+
+    // Increasing the retention keeps the existing resumption PSKs.
+    alice_group
+        .set_number_of_resumption_psks(provider.storage(), 3)
+        .unwrap();
+    self_update(&mut alice_group);
+    assert_eq!(alice_group.resumption_psk_epochs(), epochs(&[4, 5, 6]));
+    self_update(&mut alice_group);
+    assert_eq!(alice_group.resumption_psk_epochs(), epochs(&[5, 6, 7]));
+}
+
+// Test that external PSKs of an incoming commit can be fetched on demand.
+#[openmls_test]
+fn psk_resolver() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        provider.rand(),
+        Psk::External(ExternalPsk::new(vec![1, 2, 3])),
+    )
+    .unwrap();
+    let secret = Secret::random(ciphersuite, provider.rand()).unwrap();
+    let psk_store = SeparatePskStore {
+        psks: vec![(psk_id.psk().clone(), secret.as_slice().to_vec())],
+    };
+
+    // Alice commits to the PSK, which is not in the storage of the provider.
+    let (proposal, _) = alice_group
+        .propose_external_psk(provider, &alice_signer, psk_id.clone())
+        .unwrap();
+    let processed_message = bob_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(proposal) = processed_message.into_content()
+    else {
+        panic!("expected a proposal");
+    };
+    bob_group
+        .store_pending_proposal(provider.storage(), *proposal)
+        .unwrap();
+    let commit = alice_group
+        .commit_builder()
+        .load_psks(&psk_store)
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // If the application declines, processing fails.
+    let err = bob_group
+        .process_message_with_psk_resolver(
+            provider,
+            commit.clone().into_protocol_message().unwrap(),
+            &(),
+            |_| None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::PskError(PskError::KeyNotFound))
+    );
+
+    // Otherwise, the PSK is fetched when it is needed.
+    let requested = std::sync::Mutex::new(Vec::new());
+    let processed_message = bob_group
+        .process_message_with_psk_resolver(
+            provider,
+            commit.into_protocol_message().unwrap(),
+            &(),
+            |psk| {
+                requested.lock().unwrap().push(psk.clone());
+                psk_store.psk_secret(psk).unwrap()
+            },
+        )
+        .unwrap();
+    assert_eq!(*requested.lock().unwrap(), vec![psk_id.psk().clone()]);
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    bob_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+    assert_eq!(
+        alice_group
+            .export_secret(provider, "test", &[], 32)
+            .unwrap(),
+        bob_group.export_secret(provider, "test", &[], 32).unwrap()
+    );
+}
+
+#[openmls_test]
+fn resumable_processing() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    let psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        provider.rand(),
+        Psk::External(ExternalPsk::new(vec![4, 5, 6])),
+    )
+    .unwrap();
+    let secret = Secret::random(ciphersuite, provider.rand()).unwrap();
+    let psk_store = SeparatePskStore {
+        psks: vec![(psk_id.psk().clone(), secret.as_slice().to_vec())],
+    };
+
+    // Alice commits to a PSK that Bob doesn't have yet.
+    let (proposal, _) = alice_group
+        .propose_external_psk(provider, &alice_signer, psk_id.clone())
+        .unwrap();
+    let processed_message = bob_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(proposal) = processed_message.into_content()
+    else {
+        panic!("expected a proposal");
+    };
+    bob_group
+        .store_pending_proposal(provider.storage(), *proposal)
+        .unwrap();
+    let commit = alice_group
+        .commit_builder()
+        .load_psks(&psk_store)
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // Processing is suspended.
+    let err = bob_group
+        .process_message_resumable(
+            provider,
+            commit.clone().into_protocol_message().unwrap(),
+            &(),
+            provider.storage(),
+        )
+        .unwrap_err();
+    let ResumableProcessMessageError::Suspended(resumable) = err else {
+        panic!("expected processing to be suspended");
+    };
+    assert_eq!(resumable.group_id(), bob_group.group_id());
+    assert_eq!(resumable.epoch(), bob_group.epoch());
+    assert_eq!(
+        resumable.reason(),
+        &SuspendReason::MissingPsks(vec![psk_id.clone()])
+    );
+    assert_eq!(
+        resumable.sender(),
+        Some(&Sender::Member(alice_group.own_leaf_index()))
+    );
+
+    // The key to decrypt the commit was deleted, so it can't be processed
+    // again from the wire.
+    assert!(bob_group
+        .process_message_with_psk_store(
+            provider,
+            commit.into_protocol_message().unwrap(),
+            &(),
+            &psk_store,
+        )
+        .is_err());
+
+    // The token survives a round trip through the storage of the application.
+    let serialized = serde_json::to_vec(&resumable).unwrap();
+    let resumable: Box<ResumableMessage> = serde_json::from_slice(&serialized).unwrap();
+
+    // Without the PSK, processing is suspended again.
+    let err = bob_group
+        .resume_message(provider, *resumable.clone(), &(), provider.storage())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ResumableProcessMessageError::Suspended(resumable.clone())
+    );
+
+    // Once the PSK is available, processing is resumed.
+    let processed_message = bob_group
+        .resume_message(provider, *resumable.clone(), &(), &psk_store)
+        .unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    bob_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+    assert_eq!(
+        alice_group
+            .export_secret(provider, "test", &[], 32)
+            .unwrap(),
+        bob_group.export_secret(provider, "test", &[], 32).unwrap()
+    );
+
+    // The token can't be used in a later epoch.
+    let err = bob_group
+        .resume_message(provider, *resumable, &(), &psk_store)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ResumableProcessMessageError::ProcessMessageError(ProcessMessageError::ValidationError(
+            ValidationError::WrongEpoch
+        ))
+    );
+}
+
+#[openmls_test]
+fn resumable_processing_of_later_epoch() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // Alice sends a message in the epoch after her commit, before Bob
+    // processed the commit.
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let message = alice_group
+        .create_message(provider, &alice_signer, b"Hello, Bob!")
+        .unwrap();
+
+    // Processing is suspended without decrypting the message.
+    let err = bob_group
+        .process_message_resumable(
+            provider,
+            message.into_protocol_message().unwrap(),
+            &(),
+            provider.storage(),
+        )
+        .unwrap_err();
+    let ResumableProcessMessageError::Suspended(resumable) = err else {
+        panic!("expected processing to be suspended");
+    };
+    assert_eq!(resumable.epoch(), alice_group.epoch());
+    assert_eq!(resumable.reason(), &SuspendReason::EpochNotReached);
+    assert_eq!(resumable.sender(), None);
+
+    // Until the commit is merged, processing is suspended again.
+    let err = bob_group
+        .resume_message(provider, *resumable.clone(), &(), provider.storage())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ResumableProcessMessageError::Suspended(resumable.clone())
+    );
+
+    let processed_message = bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a commit");
+    };
+    bob_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+
+    let serialized = serde_json::to_vec(&resumable).unwrap();
+    let resumable: ResumableMessage = serde_json::from_slice(&serialized).unwrap();
+    let processed_message = bob_group
+        .resume_message(provider, resumable, &(), provider.storage())
+        .unwrap();
+    let ProcessedMessageContent::ApplicationMessage(application_message) =
+        processed_message.into_content()
+    else {
+        panic!("expected an application message");
+    };
+    assert_eq!(application_message.into_bytes(), b"Hello, Bob!");
+}
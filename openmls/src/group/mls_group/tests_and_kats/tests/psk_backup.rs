@@ -0,0 +1,72 @@
+//! Tests for the backup of resumption PSKs.
+
+use openmls_test::openmls_test;
+use openmls_traits::{types::CryptoError, OpenMlsProvider as _};
+use tls_codec::{Deserialize, Serialize};
+
+use crate::group::{mls_group::tests_and_kats::utils::setup_alice_bob_group, *};
+
+#[openmls_test]
+fn resumption_psk_backup() {
+    let (alice_group, _alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let key = ResumptionPskBackupKey::random(ciphersuite, provider.rand()).unwrap();
+    let epoch_0 = GroupEpoch::from(0);
+
+    // Bob joined in epoch 1 and doesn't have the resumption PSK of epoch 0.
+    let err = bob_group
+        .export_resumption_psks(provider.crypto(), provider.rand(), &key, &[epoch_0])
+        .unwrap_err();
+    assert_eq!(err, ExportResumptionPskError::UnknownEpoch(epoch_0));
+
+    let backup = alice_group
+        .export_resumption_psks(provider.crypto(), provider.rand(), &key, &[epoch_0])
+        .unwrap();
+    assert_eq!(backup.epochs(), &[epoch_0]);
+    let serialized = backup.tls_serialize_detached().unwrap();
+    let backup = ResumptionPskBackup::tls_deserialize_exact(serialized).unwrap();
+
+    // The backup can't be imported with a different key.
+    let wrong_key = ResumptionPskBackupKey::random(ciphersuite, provider.rand()).unwrap();
+    let err = bob_group
+        .import_resumption_psks(provider, &wrong_key, &backup)
+        .unwrap_err();
+    assert_eq!(err, ImportResumptionPskError::DecryptionFailed);
+
+    let imported = bob_group
+        .import_resumption_psks(provider, &key, &backup)
+        .unwrap();
+    assert_eq!(imported, vec![epoch_0]);
+    assert_eq!(
+        bob_group
+            .resumption_psk_store()
+            .get(epoch_0)
+            .unwrap()
+            .as_slice(),
+        alice_group
+            .resumption_psk_store()
+            .get(epoch_0)
+            .unwrap()
+            .as_slice()
+    );
+
+    // Importing the backup again doesn't change anything.
+    let imported = bob_group
+        .import_resumption_psks(provider, &key, &backup)
+        .unwrap();
+    assert!(imported.is_empty());
+}
+
+#[openmls_test]
+fn resumption_psk_backup_key_length() {
+    let bytes = vec![0x42; ciphersuite.hash_length()];
+    assert!(ResumptionPskBackupKey::from_bytes(ciphersuite, &bytes).is_ok());
+    assert_eq!(
+        ResumptionPskBackupKey::from_bytes(ciphersuite, &bytes[1..]).unwrap_err(),
+        CryptoError::InvalidLength
+    );
+    assert_eq!(
+        ResumptionPskBackupKey::from_bytes(ciphersuite, &[]).unwrap_err(),
+        CryptoError::InvalidLength
+    );
+}
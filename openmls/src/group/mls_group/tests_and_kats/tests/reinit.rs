@@ -0,0 +1,177 @@
+//! Tests for re-initializing a group.
+
+use openmls_test::openmls_test;
+use openmls_traits::{
+    crypto::OpenMlsCrypto as _, storage::StorageReader as _, OpenMlsProvider as _,
+};
+
+use crate::{
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{
+            process_commit, setup_alice_bob_group_with_providers, setup_client,
+        },
+        *,
+    },
+    messages::proposals::ReInitProposal,
+    schedule::{
+        psk::{PskBundle, ResumptionPsk, ResumptionPskUsage},
+        Psk,
+    },
+    treesync::LeafNodeParameters,
+};
+
+/// Returns `true` if the resumption PSK that links the re-initialized `group`
+/// to its successor is in the storage.
+fn has_reinit_psk<Provider: OpenMlsProvider>(provider: &Provider, group: &MlsGroup) -> bool {
+    let reinit_psk = Psk::Resumption(ResumptionPsk::new(
+        ResumptionPskUsage::Reinit,
+        group.group_id().clone(),
+        group.epoch(),
+    ));
+    let psk_bundle: Option<PskBundle> = provider.storage().psk(&reinit_psk).unwrap();
+    psk_bundle.is_some()
+}
+
+#[openmls_test]
+fn reinit_ciphersuite_upgrade() {
+    let alice_provider = provider;
+    let bob_provider = &Provider::default();
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer) =
+        setup_alice_bob_group_with_providers(ciphersuite, alice_provider, bob_provider);
+
+    // Upgrade to another ciphersuite if the provider supports one.
+    let new_ciphersuite = alice_provider
+        .crypto()
+        .supported_ciphersuites()
+        .into_iter()
+        .find(|&supported| supported != ciphersuite)
+        .unwrap_or(ciphersuite);
+    let new_group_id = GroupId::from_slice(b"Upgraded Group");
+
+    // === Alice re-initializes the group ===
+    let (commit, _group_info) = alice_group
+        .reinit(
+            alice_provider,
+            &alice_signer,
+            new_group_id.clone(),
+            new_ciphersuite,
+            Extensions::empty(),
+        )
+        .expect("Could not create ReInit commit.");
+    alice_group
+        .merge_pending_commit(alice_provider)
+        .expect("error merging pending commit");
+
+    let staged_commit = process_commit(bob_provider, &mut bob_group, commit);
+    assert_eq!(
+        staged_commit.reinit_proposal().map(|p| p.ciphersuite()),
+        Some(new_ciphersuite)
+    );
+    bob_group
+        .merge_staged_commit(bob_provider, staged_commit)
+        .expect("error merging staged commit");
+
+    assert_eq!(alice_group.reinit_proposal(), bob_group.reinit_proposal());
+    assert!(matches!(
+        alice_group.self_update(alice_provider, &alice_signer, LeafNodeParameters::default()),
+        Err(SelfUpdateError::GroupStateError(
+            MlsGroupStateError::UseAfterReInit
+        ))
+    ));
+    assert!(has_reinit_psk(alice_provider, &alice_group));
+    assert!(has_reinit_psk(bob_provider, &bob_group));
+
+    // === Alice creates the successor group and Bob joins it ===
+    let (alice_credential_with_key, _alice_kpb, alice_new_signer, _alice_pk) =
+        setup_client("Alice", new_ciphersuite, alice_provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_new_signer, _bob_pk) =
+        setup_client("Bob", new_ciphersuite, bob_provider);
+
+    let (new_alice_group, welcome) = alice_group
+        .reinit_successor(
+            alice_provider,
+            &alice_new_signer,
+            &MlsGroupCreateConfig::test_default(new_ciphersuite),
+            alice_credential_with_key,
+            &[bob_kpb.key_package().clone()],
+        )
+        .expect("Could not create successor group.");
+    assert_eq!(new_alice_group.group_id(), &new_group_id);
+    assert_eq!(new_alice_group.ciphersuite(), new_ciphersuite);
+
+    // The resumption PSK is proposed in the commit that creates the successor
+    // and deleted once it is used.
+    assert!(!has_reinit_psk(alice_provider, &alice_group));
+
+    let welcome = welcome.into_welcome().expect("expected a welcome");
+    let new_bob_group = bob_group
+        .join_reinit_successor(
+            bob_provider,
+            welcome,
+            Some(new_alice_group.export_ratchet_tree().into()),
+        )
+        .expect("Could not join successor group.");
+    assert!(!has_reinit_psk(bob_provider, &bob_group));
+
+    assert_eq!(new_bob_group.group_id(), &new_group_id);
+    assert_eq!(new_bob_group.ciphersuite(), new_ciphersuite);
+    assert_eq!(
+        new_alice_group.epoch_authenticator(),
+        new_bob_group.epoch_authenticator()
+    );
+}
+
+#[openmls_test]
+fn reinit_by_reference() {
+    let alice_provider = provider;
+    let bob_provider = &Provider::default();
+    let (mut alice_group, alice_signer, mut bob_group, bob_signer) =
+        setup_alice_bob_group_with_providers(ciphersuite, alice_provider, bob_provider);
+    let new_group_id = GroupId::from_slice(b"Re-initialized Group");
+
+    // === Bob proposes to re-initialize the group ===
+    let (proposal, _proposal_ref) = bob_group
+        .propose_reinit(
+            bob_provider,
+            &bob_signer,
+            ReInitProposal::new(
+                new_group_id.clone(),
+                bob_group.version(),
+                ciphersuite,
+                Extensions::empty(),
+            ),
+        )
+        .expect("Could not create ReInit proposal.");
+
+    let processed_message = alice_group
+        .process_message(alice_provider, proposal.into_protocol_message().unwrap())
+        .expect("Could not process proposal.");
+    let ProcessedMessageContent::ProposalMessage(queued_proposal) =
+        processed_message.into_content()
+    else {
+        panic!("Expected a proposal.");
+    };
+    alice_group
+        .store_pending_proposal(alice_provider.storage(), *queued_proposal)
+        .unwrap();
+
+    // === Alice commits to the ReInit proposal by reference ===
+    let (commit, _welcome, _group_info) = alice_group
+        .commit_to_pending_proposals(alice_provider, &alice_signer)
+        .expect("Could not commit to the ReInit proposal.");
+    alice_group
+        .merge_pending_commit(alice_provider)
+        .expect("error merging pending commit");
+
+    let staged_commit = process_commit(bob_provider, &mut bob_group, commit);
+    bob_group
+        .merge_staged_commit(bob_provider, staged_commit)
+        .expect("error merging staged commit");
+
+    assert_eq!(
+        alice_group.reinit_proposal().map(|p| p.group_id()),
+        Some(&new_group_id)
+    );
+    assert_eq!(alice_group.reinit_proposal(), bob_group.reinit_proposal());
+}
@@ -0,0 +1,157 @@
+//! Tests for syncing the group state to read-only replicas.
+
+use openmls_test::openmls_test;
+use openmls_traits::{types::CryptoError, OpenMlsProvider as _};
+use tls_codec::{Deserialize, Serialize};
+
+use crate::{
+    group::{
+        mls_group::tests_and_kats::utils::{process_and_merge_commit, setup_alice_bob_group},
+        *,
+    },
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn replica_sync() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let replica_provider = &Provider::default();
+    let key = ReplicaSyncKey::random(ciphersuite, provider.rand()).unwrap();
+
+    // Bob sets up a replica from his current state.
+    let delta = bob_group
+        .export_replica_delta(provider, &key, ReplicaDeltaScope::Epoch)
+        .unwrap();
+    let serialized = delta.tls_serialize_detached().unwrap();
+    let delta = ReplicaDelta::tls_deserialize_exact(serialized).unwrap();
+    let mut replica =
+        MlsGroup::new_replica(replica_provider, &key, bob_group.configuration(), &delta).unwrap();
+    assert_eq!(
+        replica.state_summary(provider.crypto()).unwrap(),
+        bob_group.state_summary(provider.crypto()).unwrap()
+    );
+
+    // A delta can't be decrypted with a different key.
+    let wrong_key = ReplicaSyncKey::random(ciphersuite, provider.rand()).unwrap();
+    assert_eq!(
+        MlsGroup::new_replica(
+            replica_provider,
+            &wrong_key,
+            bob_group.configuration(),
+            &delta
+        )
+        .unwrap_err(),
+        ApplyReplicaDeltaError::DecryptionFailed
+    );
+
+    // Bob advances the ratchet of Alice's leaf by processing a message.
+    let message = alice_group
+        .create_message(provider, &alice_signer, b"Hello, Bob!")
+        .unwrap();
+    bob_group
+        .process_message(provider, message.into_protocol_message().unwrap())
+        .unwrap();
+    let delta = bob_group
+        .export_replica_delta(provider, &key, ReplicaDeltaScope::MessageSecrets)
+        .unwrap();
+    replica
+        .apply_replica_delta(replica_provider, &key, &delta)
+        .unwrap();
+    assert_eq!(delta.sequence(), 2);
+
+    // The same delta can't be applied twice.
+    assert_eq!(
+        replica.apply_replica_delta(replica_provider, &key, &delta),
+        Err(ApplyReplicaDeltaError::StaleDelta)
+    );
+    let alice_index = alice_group.own_leaf_index();
+    assert_eq!(
+        replica
+            .sender_ratchet_state(alice_index)
+            .unwrap()
+            .generation(),
+        1
+    );
+
+    // Alice commits and Bob merges the commit.
+    let (commit, _welcome, _group_info) = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_contents();
+    alice_group.merge_pending_commit(provider).unwrap();
+    process_and_merge_commit(provider, &mut bob_group, commit);
+
+    // Deltas that are older than the last applied one are rejected, even if
+    // they would otherwise apply.
+    let stale_delta = bob_group
+        .export_replica_delta(provider, &key, ReplicaDeltaScope::MessageSecrets)
+        .unwrap();
+
+    // The message secrets delta of the old epoch can't be applied anymore.
+    let epoch_delta = bob_group
+        .export_replica_delta(provider, &key, ReplicaDeltaScope::Epoch)
+        .unwrap();
+    replica
+        .apply_replica_delta(replica_provider, &key, &epoch_delta)
+        .unwrap();
+    assert_eq!(
+        replica.apply_replica_delta(replica_provider, &key, &delta),
+        Err(ApplyReplicaDeltaError::EpochMismatch)
+    );
+    assert_eq!(
+        replica.apply_replica_delta(replica_provider, &key, &stale_delta),
+        Err(ApplyReplicaDeltaError::StaleDelta)
+    );
+    assert_eq!(replica.epoch(), bob_group.epoch());
+    assert_eq!(
+        replica.export_secret(provider, "replica", &[], 32).unwrap(),
+        bob_group
+            .export_secret(provider, "replica", &[], 32)
+            .unwrap()
+    );
+
+    let delta = bob_group
+        .export_replica_delta(provider, &key, ReplicaDeltaScope::MessageSecrets)
+        .unwrap();
+    replica
+        .apply_replica_delta(replica_provider, &key, &delta)
+        .unwrap();
+
+    // The state of the replica was persisted.
+    let mut replica = MlsGroup::load(replica_provider.storage(), bob_group.group_id())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        replica.state_summary(provider.crypto()).unwrap(),
+        bob_group.state_summary(provider.crypto()).unwrap()
+    );
+
+    // The sequence number was persisted as well, so the loaded replica still
+    // rejects the last delta.
+    assert_eq!(
+        replica.apply_replica_delta(replica_provider, &key, &delta),
+        Err(ApplyReplicaDeltaError::StaleDelta)
+    );
+}
+
+#[openmls_test]
+fn replica_sync_key_length() {
+    let bytes = vec![0x42; ciphersuite.hash_length()];
+    assert!(ReplicaSyncKey::from_bytes(ciphersuite, &bytes).is_ok());
+    assert_eq!(
+        ReplicaSyncKey::from_bytes(ciphersuite, &bytes[1..]).unwrap_err(),
+        CryptoError::InvalidLength
+    );
+    assert_eq!(
+        ReplicaSyncKey::from_bytes(ciphersuite, &[]).unwrap_err(),
+        CryptoError::InvalidLength
+    );
+}
+
+#[openmls_test]
+fn replica_sync_key_debug() {
+    let key =
+        ReplicaSyncKey::from_bytes(ciphersuite, &vec![0x42; ciphersuite.hash_length()]).unwrap();
+    assert_eq!(format!("{key:?}"), r#"ReplicaSyncKey { secret: "***" }"#);
+}
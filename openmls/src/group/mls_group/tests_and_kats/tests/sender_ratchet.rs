@@ -0,0 +1,133 @@
+//! Tests for the state and configuration of sender ratchets.
+
+use openmls_test::openmls_test;
+use openmls_traits::OpenMlsProvider as _;
+
+use crate::{
+    group::{mls_group::tests_and_kats::utils::setup_alice_bob_group, *},
+    tree::sender_ratchet::SenderRatchetConfiguration,
+};
+
+#[openmls_test]
+fn sender_ratchet_state() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let alice_index = alice_group.own_leaf_index();
+
+    // No application message has been sent yet.
+    assert!(bob_group.sender_ratchet_state(alice_index).is_none());
+
+    let messages: Vec<_> = (0..3)
+        .map(|_| {
+            alice_group
+                .create_message(provider, &alice_signer, b"Hello, Bob!")
+                .expect("Could not create message.")
+        })
+        .collect();
+
+    let alice_state = alice_group
+        .sender_ratchet_state(alice_index)
+        .expect("No sender ratchet state for Alice.");
+    assert_eq!(alice_state.generation(), 3);
+    assert_eq!(alice_state.stored_keys(), 0);
+
+    // Bob only receives the last message and keeps the keys for the skipped
+    // ones.
+    let last_message = messages.last().unwrap().clone();
+    bob_group
+        .process_message(provider, last_message.into_protocol_message().unwrap())
+        .expect("Could not process message.");
+
+    let bob_state = bob_group
+        .sender_ratchet_state(alice_index)
+        .expect("No sender ratchet state for Alice.");
+    assert_eq!(bob_state.generation(), 3);
+    assert_eq!(bob_state.stored_keys(), 2);
+    assert_eq!(
+        bob_state.configuration(),
+        bob_group.configuration().sender_ratchet_configuration()
+    );
+
+    // Receiving a skipped message consumes its key.
+    bob_group
+        .process_message(
+            provider,
+            messages[0].clone().into_protocol_message().unwrap(),
+        )
+        .expect("Could not process message.");
+    let bob_state = bob_group
+        .sender_ratchet_state(alice_index)
+        .expect("No sender ratchet state for Alice.");
+    assert_eq!(bob_state.stored_keys(), 1);
+}
+
+#[openmls_test]
+fn sender_ratchet_override() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+    let alice_index = alice_group.own_leaf_index();
+
+    // Bob only tolerates a single skipped message from Alice.
+    let strict_configuration = SenderRatchetConfiguration::new(0, 1);
+    bob_group
+        .set_sender_ratchet_override(
+            provider.storage(),
+            alice_index,
+            strict_configuration.clone(),
+        )
+        .expect("Could not set override.");
+    assert_eq!(
+        bob_group
+            .configuration()
+            .sender_ratchet_configuration_for(alice_index),
+        &strict_configuration
+    );
+    assert_eq!(
+        bob_group
+            .configuration()
+            .sender_ratchet_configuration_for(bob_group.own_leaf_index()),
+        bob_group.configuration().sender_ratchet_configuration()
+    );
+
+    // The override is persisted with the group.
+    let loaded_group = MlsGroup::load(provider.storage(), bob_group.group_id())
+        .expect("Could not load group.")
+        .expect("Group not found.");
+    assert_eq!(
+        loaded_group
+            .configuration()
+            .sender_ratchet_overrides()
+            .get(&alice_index),
+        Some(&strict_configuration)
+    );
+
+    let messages: Vec<_> = (0..3)
+        .map(|_| {
+            alice_group
+                .create_message(provider, &alice_signer, b"Hello, Bob!")
+                .expect("Could not create message.")
+        })
+        .collect();
+    let last_message = messages.last().unwrap().clone();
+
+    // Two messages were skipped, which exceeds the overridden maximum forward
+    // distance.
+    bob_group
+        .process_message(
+            provider,
+            last_message.clone().into_protocol_message().unwrap(),
+        )
+        .expect_err("Message should have been rejected.");
+
+    // Without the override, the default configuration applies again.
+    bob_group
+        .remove_sender_ratchet_override(provider.storage(), alice_index)
+        .expect("Could not remove override.");
+    assert!(bob_group
+        .configuration()
+        .sender_ratchet_overrides()
+        .is_empty());
+    bob_group
+        .process_message(provider, last_message.into_protocol_message().unwrap())
+        .expect("Could not process message.");
+}
@@ -0,0 +1,261 @@
+//! Tests for the storage of groups and their configuration.
+
+use openmls_test::openmls_test;
+use openmls_traits::{
+    storage::{StorageReader as _, StorageWriter as _},
+    OpenMlsProvider as _,
+};
+
+use crate::{
+    framing::*,
+    group::{
+        mls_group::tests_and_kats::utils::{setup_alice_bob_group, setup_client},
+        *,
+    },
+    key_packages::*,
+    tree::sender_ratchet::SenderRatchetConfiguration,
+    treesync::LeafNodeParameters,
+};
+
+#[openmls_test]
+fn garbage_collection() {
+    use crate::storage::gc::{collect_garbage, find_orphans, group_entities, GroupEntity};
+
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let (bob_credential, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential,
+    )
+    .unwrap();
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[bob_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+
+    let welcome: MlsMessageIn = welcome.into();
+    let bob_group = StagedWelcome::new_from_welcome(
+        bob_provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap()
+    .into_group(bob_provider)
+    .unwrap();
+
+    // Simulate a crash before the used key package was deleted.
+    let used_key_package_ref = bob_kpb
+        .key_package()
+        .hash_ref(bob_provider.crypto())
+        .unwrap();
+    bob_provider
+        .storage()
+        .write_key_package(&used_key_package_ref, &bob_kpb)
+        .unwrap();
+
+    // Simulate a crash while a group was written.
+    let incomplete_group = MlsGroup::new(
+        bob_provider,
+        &bob_signer,
+        &mls_group_create_config,
+        bob_credential.clone(),
+    )
+    .unwrap();
+    let incomplete_group_id = incomplete_group.group_id().clone();
+    bob_provider
+        .storage()
+        .delete_group_state(&incomplete_group_id)
+        .unwrap();
+
+    // A key package that wasn't used yet is kept.
+    let unused_kpb = KeyPackage::builder()
+        .build(ciphersuite, bob_provider, &bob_signer, bob_credential)
+        .unwrap();
+    let unused_key_package_ref = unused_kpb
+        .key_package()
+        .hash_ref(bob_provider.crypto())
+        .unwrap();
+
+    let entities = group_entities(bob_provider.storage(), &incomplete_group_id).unwrap();
+    assert!(entities.contains(&GroupEntity::Tree));
+    assert!(!entities.contains(&GroupEntity::GroupState));
+
+    let orphans = find_orphans(bob_provider.storage()).unwrap();
+    assert_eq!(orphans.groups(), &[incomplete_group_id.clone()]);
+    assert_eq!(orphans.key_packages(), &[used_key_package_ref]);
+    assert!(orphans.encryption_keys().is_empty());
+
+    assert_eq!(collect_garbage(bob_provider.storage()).unwrap(), orphans);
+    assert!(find_orphans(bob_provider.storage()).unwrap().is_empty());
+    assert!(group_entities(bob_provider.storage(), &incomplete_group_id)
+        .unwrap()
+        .is_empty());
+
+    // The complete group and the unused key package are still there.
+    assert!(MlsGroup::load(bob_provider.storage(), bob_group.group_id())
+        .unwrap()
+        .is_some());
+    let unused: Option<KeyPackageBundle> = bob_provider
+        .storage()
+        .key_package(&unused_key_package_ref)
+        .unwrap();
+    assert!(unused.is_some());
+}
+
+#[openmls_test]
+fn runtime_join_config_changes() {
+    let (mut alice_group, alice_signer, mut bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // The wire format policy applies to the next message.
+    alice_group
+        .set_wire_format_policy(provider.storage(), PURE_CIPHERTEXT_WIRE_FORMAT_POLICY)
+        .unwrap();
+    let commit = alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap()
+        .into_commit();
+    assert!(matches!(
+        commit.into_protocol_message(),
+        Some(ProtocolMessage::PrivateMessage(_))
+    ));
+    alice_group
+        .clear_pending_commit(provider.storage())
+        .unwrap();
+
+    // So does the padding size.
+    let ciphertext_len = |message: MlsMessageOut| {
+        let Some(ProtocolMessage::PrivateMessage(private_message)) =
+            message.into_protocol_message()
+        else {
+            panic!("expected a private message");
+        };
+        private_message.ciphertext().len()
+    };
+    let unpadded = alice_group
+        .create_message(provider, &alice_signer, b"hello")
+        .unwrap();
+    alice_group
+        .set_padding_size(provider.storage(), 256)
+        .unwrap();
+    let padded = alice_group
+        .create_message(provider, &alice_signer, b"hello")
+        .unwrap();
+    assert!(ciphertext_len(padded) > ciphertext_len(unpadded));
+
+    // And the out-of-order tolerance when decrypting.
+    bob_group
+        .set_sender_ratchet_configuration(
+            provider.storage(),
+            SenderRatchetConfiguration::builder()
+                .out_of_order_tolerance(0)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let first = alice_group
+        .create_message(provider, &alice_signer, b"first")
+        .unwrap();
+    let second = alice_group
+        .create_message(provider, &alice_signer, b"second")
+        .unwrap();
+    bob_group
+        .process_message(provider, second.into_protocol_message().unwrap())
+        .unwrap();
+    bob_group
+        .process_message(provider, first.into_protocol_message().unwrap())
+        .unwrap_err();
+
+    // All changes are persisted.
+    alice_group
+        .set_max_past_epochs(provider.storage(), 3)
+        .unwrap();
+    let loaded_group = MlsGroup::load(provider.storage(), alice_group.group_id())
+        .expect("Could not load group.")
+        .expect("Group not found.");
+    assert_eq!(loaded_group.configuration(), alice_group.configuration());
+    assert_eq!(
+        loaded_group.configuration().wire_format_policy(),
+        PURE_CIPHERTEXT_WIRE_FORMAT_POLICY
+    );
+    assert_eq!(loaded_group.configuration().padding_size(), 256);
+    assert_eq!(loaded_group.configuration().max_past_epochs(), 3);
+    let loaded_group = MlsGroup::load(provider.storage(), bob_group.group_id())
+        .expect("Could not load group.")
+        .expect("Group not found.");
+    assert_eq!(
+        loaded_group
+            .configuration()
+            .sender_ratchet_configuration()
+            .out_of_order_tolerance(),
+        0
+    );
+}
+
+#[openmls_test]
+fn join_config_migration() {
+    let (alice_group, _alice_signer, _bob_group, _bob_signer, _bob_credential_with_key) =
+        setup_alice_bob_group(ciphersuite, provider);
+    assert_eq!(alice_group.configuration().version(), JOIN_CONFIG_VERSION);
+
+    // Store the config as if it predated the versioning.
+    let mut stored_config = alice_group.configuration().clone();
+    stored_config.version = 0;
+    provider
+        .storage()
+        .write_mls_join_config(alice_group.group_id(), &stored_config)
+        .unwrap();
+
+    // Loading migrates the config, but doesn't persist it.
+    let loaded_group = MlsGroup::load(provider.storage(), alice_group.group_id())
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded_group.configuration(), alice_group.configuration());
+    let reloaded_config: MlsGroupJoinConfig = provider
+        .storage()
+        .mls_group_join_config(alice_group.group_id())
+        .unwrap()
+        .unwrap();
+    assert_eq!(reloaded_config.version(), 0);
+
+    // The application can choose the values of the new options.
+    let migration = |from_version: u16, config: MlsGroupJoinConfig| {
+        assert_eq!(from_version, 0);
+        config.into_builder().collect_member_stats(true).build()
+    };
+    let loaded_group =
+        MlsGroup::load_with_migration(provider.storage(), alice_group.group_id(), &migration)
+            .unwrap()
+            .unwrap();
+    assert!(loaded_group.configuration().collect_member_stats());
+    assert_eq!(loaded_group.configuration().version(), JOIN_CONFIG_VERSION);
+
+    // The migrated config is persisted, so it isn't migrated again.
+    let migration = |_: u16, _: MlsGroupJoinConfig| -> MlsGroupJoinConfig {
+        panic!("the config was already migrated")
+    };
+    let loaded_group =
+        MlsGroup::load_with_migration(provider.storage(), alice_group.group_id(), &migration)
+            .unwrap()
+            .unwrap();
+    assert!(loaded_group.configuration().collect_member_stats());
+}
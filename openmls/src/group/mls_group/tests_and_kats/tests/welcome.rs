@@ -0,0 +1,611 @@
+//! Tests for joining a group from a Welcome message.
+
+use openmls_test::openmls_test;
+use openmls_traits::{storage::StorageReader as _, OpenMlsProvider as _};
+
+use crate::{
+    extensions::{
+        errors::{AppExtensionError, InvalidExtensionError},
+        ApplicationIdExtension,
+    },
+    framing::*,
+    group::{mls_group::tests_and_kats::utils::setup_client, *},
+    key_packages::*,
+    messages::Welcome,
+    schedule::{errors::PskError, ExternalPsk, PreSharedKeyId, Psk},
+    treesync::{node::leaf_node::Capabilities, LeafNodeParameters},
+};
+
+#[openmls_test]
+fn staged_join_sender_validation() {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key.clone(),
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_queued_message, welcome, _group_info) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("couldn't merge commit that adds bob");
+
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().expect("expected a welcome");
+
+    // The Welcome doesn't include the ratchet tree, so Bob keeps the
+    // processed Welcome until the tree is fetched.
+    let processed_welcome = ProcessedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome,
+    )
+    .expect("error processing the welcome");
+    assert!(processed_welcome.requires_ratchet_tree());
+    let staged_bob_group = processed_welcome
+        .into_staged_welcome(provider, Some(alice_group.export_ratchet_tree().into()))
+        .expect("error creating staged mls group");
+
+    assert_eq!(
+        staged_bob_group
+            .welcome_sender_credential()
+            .expect("couldn't determine sender of welcome"),
+        &alice_credential_with_key.credential
+    );
+
+    // Bob only accepts invitations from Charlie.
+    let err = staged_bob_group
+        .into_group_with_sender_validation(provider, |sender| {
+            sender.credential().serialized_content() == b"Charlie"
+        })
+        .expect_err("Bob joined although the welcome sender was rejected");
+
+    assert!(matches!(err, WelcomeError::WelcomeSenderRejected));
+}
+
+#[openmls_test]
+fn split_welcome() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    // === Alice adds Bob and Charlie ===
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                bob_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .expect("Could not add members to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let welcomes = welcome.split_welcome().expect("expected a welcome");
+    assert_eq!(welcomes.len(), 2);
+
+    // Each new member joins with its own welcome.
+    for key_package in [bob_kpb.key_package(), charlie_kpb.key_package()] {
+        let key_package_ref = key_package.hash_ref(provider.crypto()).unwrap();
+        let (_, welcome) = welcomes
+            .iter()
+            .find(|(new_member, _)| new_member == &key_package_ref)
+            .expect("no welcome for new member");
+
+        let welcome: MlsMessageIn = welcome.clone().into();
+        let welcome = welcome.into_welcome().expect("expected a welcome");
+        assert_eq!(welcome.secrets().len(), 1);
+
+        let new_member_group = StagedWelcome::new_from_welcome(
+            provider,
+            mls_group_create_config.join_config(),
+            welcome,
+            Some(alice_group.export_ratchet_tree().into()),
+        )
+        .expect("error creating staged join from welcome")
+        .into_group(provider)
+        .expect("error creating group from staged join");
+
+        assert_eq!(
+            new_member_group.epoch_authenticator(),
+            alice_group.epoch_authenticator()
+        );
+    }
+
+    // Non-welcome messages can't be split.
+    let (proposal, _proposal_ref) = alice_group
+        .propose_self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .unwrap();
+    assert!(proposal.split_welcome().is_none());
+}
+
+#[openmls_test]
+fn init_key_retention() {
+    use crate::key_packages::retention::{
+        purge_expired_key_packages, purge_retained_key_packages, retained_key_packages,
+    };
+
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let (bob_credential, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+    let bob_key_package_ref = bob_kpb
+        .key_package()
+        .hash_ref(bob_provider.crypto())
+        .unwrap();
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    // Alice invites Bob to a new group each time, since his signature key
+    // can only appear once per group.
+    let welcome_for = |key_package: &KeyPackage| -> Welcome {
+        let mut alice_group = MlsGroup::new(
+            alice_provider,
+            &alice_signer,
+            &mls_group_create_config,
+            alice_credential.clone(),
+        )
+        .unwrap();
+        let (_commit, welcome, _group_info) = alice_group
+            .add_members(alice_provider, &alice_signer, &[key_package.clone()])
+            .unwrap();
+        alice_group.merge_pending_commit(alice_provider).unwrap();
+        let welcome: MlsMessageIn = welcome.into();
+        welcome.into_welcome().unwrap()
+    };
+    let join = |join_config: &MlsGroupJoinConfig, welcome: Welcome| {
+        StagedWelcome::new_from_welcome(bob_provider, join_config, welcome, None)
+            .map(|staged_welcome| staged_welcome.into_group(bob_provider).unwrap())
+    };
+
+    // With a grace period, a duplicate Welcome can still be decrypted and is
+    // recognized as such.
+    let join_config = MlsGroupJoinConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .init_key_retention(InitKeyRetention::GracePeriod(
+            std::time::Duration::from_secs(3600),
+        ))
+        .build();
+    let welcome = welcome_for(bob_kpb.key_package());
+    let bob_group = join(&join_config, welcome.clone()).unwrap();
+    let retained = retained_key_packages(bob_provider.storage()).unwrap();
+    assert_eq!(retained.len(), 1);
+    assert_eq!(retained[0].0, bob_key_package_ref);
+    assert!(matches!(retained[0].1, RetentionReason::GracePeriod { .. }));
+    assert_eq!(
+        join(&join_config, welcome).unwrap_err(),
+        WelcomeError::AlreadyJoined(bob_group.group_id().clone())
+    );
+
+    assert!(purge_expired_key_packages(bob_provider.storage())
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        purge_retained_key_packages(bob_provider.storage()).unwrap(),
+        vec![bob_key_package_ref]
+    );
+    assert!(retained_key_packages(bob_provider.storage())
+        .unwrap()
+        .is_empty());
+
+    // Once the grace period is over, the key package isn't used anymore.
+    let bob_kpb = KeyPackage::builder()
+        .build(
+            ciphersuite,
+            bob_provider,
+            &bob_signer,
+            bob_credential.clone(),
+        )
+        .unwrap();
+    let join_config = MlsGroupJoinConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .init_key_retention(InitKeyRetention::GracePeriod(std::time::Duration::ZERO))
+        .build();
+    let welcome = welcome_for(bob_kpb.key_package());
+    join(&join_config, welcome.clone()).unwrap();
+    assert!(matches!(
+        join(&join_config, welcome),
+        Err(WelcomeError::NoMatchingKeyPackage)
+    ));
+    assert_eq!(
+        purge_expired_key_packages(bob_provider.storage()).unwrap(),
+        vec![bob_kpb
+            .key_package()
+            .hash_ref(bob_provider.crypto())
+            .unwrap()]
+    );
+
+    // By default, the key package is deleted right away, so a duplicate
+    // Welcome can't be decrypted anymore.
+    let bob_kpb = KeyPackage::builder()
+        .build(ciphersuite, bob_provider, &bob_signer, bob_credential)
+        .unwrap();
+    let bob_key_package_ref = bob_kpb
+        .key_package()
+        .hash_ref(bob_provider.crypto())
+        .unwrap();
+    let welcome = welcome_for(bob_kpb.key_package());
+    join(mls_group_create_config.join_config(), welcome.clone()).unwrap();
+    let stored: Option<KeyPackageBundle> = bob_provider
+        .storage()
+        .key_package(&bob_key_package_ref)
+        .unwrap();
+    assert!(stored.is_none());
+    assert!(matches!(
+        join(mls_group_create_config.join_config(), welcome),
+        Err(WelcomeError::NoMatchingKeyPackage)
+    ));
+}
+
+#[openmls_test]
+fn duplicate_welcome() {
+    let alice_provider = &Provider::default();
+    let bob_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let (bob_credential, _bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+
+    // Bob's last resort key package is retained after joining, so a
+    // redelivered Welcome can still be decrypted.
+    let bob_kpb = KeyPackage::builder()
+        .leaf_node_capabilities(Capabilities::new(
+            None,
+            None,
+            Some(&[ExtensionType::LastResort]),
+            None,
+            None,
+        ))
+        .mark_as_last_resort()
+        .build(ciphersuite, bob_provider, &bob_signer, bob_credential)
+        .unwrap();
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential.clone(),
+    )
+    .unwrap();
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[bob_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().unwrap();
+
+    let mut bob_group = StagedWelcome::new_from_welcome(
+        bob_provider,
+        mls_group_create_config.join_config(),
+        welcome.clone(),
+        None,
+    )
+    .unwrap()
+    .into_group(bob_provider)
+    .unwrap();
+    bob_group
+        .self_update(bob_provider, &bob_signer, LeafNodeParameters::default())
+        .unwrap();
+    bob_group.merge_pending_commit(bob_provider).unwrap();
+
+    // The redelivered Welcome is rejected and the group is left untouched.
+    assert_eq!(
+        StagedWelcome::new_from_welcome(
+            bob_provider,
+            mls_group_create_config.join_config(),
+            welcome,
+            None,
+        )
+        .unwrap_err(),
+        WelcomeError::AlreadyJoined(bob_group.group_id().clone())
+    );
+    let loaded = MlsGroup::load(bob_provider.storage(), bob_group.group_id())
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded.epoch(), bob_group.epoch());
+
+    // The last resort key package can still be used to join another group.
+    let mut other_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential,
+    )
+    .unwrap();
+    let (_commit, welcome, _group_info) = other_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[bob_kpb.key_package().clone()],
+        )
+        .unwrap();
+    other_group.merge_pending_commit(alice_provider).unwrap();
+    let welcome: MlsMessageIn = welcome.into();
+    let bob_other_group = StagedWelcome::new_from_welcome(
+        bob_provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap()
+    .into_group(bob_provider)
+    .unwrap();
+    assert_eq!(bob_other_group.group_id(), other_group.group_id());
+}
+
+#[openmls_test]
+fn welcome_psk_resolution() {
+    let alice_provider = &Provider::default();
+    let charlie_provider = &Provider::default();
+
+    let (alice_credential, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, alice_provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, charlie_provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential,
+    )
+    .unwrap();
+
+    // Alice adds Charlie in a commit that also injects an external PSK, which
+    // only Alice has.
+    let psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        alice_provider.rand(),
+        Psk::External(ExternalPsk::new(vec![1, 2, 3])),
+    )
+    .unwrap();
+    let secret = vec![0x42; 32];
+    psk_id.store(alice_provider, &secret).unwrap();
+    alice_group
+        .propose_external_psk(alice_provider, &alice_signer, psk_id.clone())
+        .unwrap();
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[charlie_kpb.key_package().clone()],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(alice_provider).unwrap();
+    let welcome: MlsMessageIn = welcome.into();
+    let welcome = welcome.into_welcome().unwrap();
+
+    // Without the PSK, the join fails and the key package is kept.
+    let join_config = MlsGroupJoinConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .init_key_retention(InitKeyRetention::GracePeriod(
+            std::time::Duration::from_secs(3600),
+        ))
+        .build();
+    assert!(matches!(
+        ProcessedWelcome::new_from_welcome(charlie_provider, &join_config, welcome.clone()),
+        Err(WelcomeError::Psk(PskError::KeyNotFound))
+    ));
+
+    // The PSK can be resolved synchronously, and the join fails if the
+    // resolver declines ...
+    assert!(matches!(
+        ProcessedWelcome::new_from_welcome_with_psk_resolver(
+            charlie_provider,
+            &join_config,
+            welcome.clone(),
+            |_| None,
+        ),
+        Err(WelcomeError::Psk(PskError::KeyNotFound))
+    ));
+    ProcessedWelcome::new_from_welcome_with_psk_resolver(
+        charlie_provider,
+        &join_config,
+        welcome.clone(),
+        |psk| {
+            assert_eq!(psk, psk_id.psk());
+            Some(secret.clone())
+        },
+    )
+    .unwrap();
+
+    // ... or fetched in between creating the pending Welcome and resuming the
+    // join.
+    let mut pending = PendingPskWelcome::new_from_welcome(
+        charlie_provider,
+        &join_config,
+        welcome,
+        charlie_provider.storage(),
+    )
+    .unwrap();
+    assert_eq!(pending.missing_psks(), vec![psk_id.clone()]);
+    let other_psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        charlie_provider.rand(),
+        Psk::External(ExternalPsk::new(vec![4, 5, 6])),
+    )
+    .unwrap();
+    assert_eq!(
+        pending.provide_psk(&other_psk_id, &secret),
+        Err(PskError::NotRequired)
+    );
+    pending.provide_psk(&psk_id, &secret).unwrap();
+    assert!(pending.missing_psks().is_empty());
+
+    let charlie_group = pending
+        .into_processed_welcome(charlie_provider)
+        .unwrap()
+        .into_staged_welcome(charlie_provider, None)
+        .unwrap()
+        .into_group(charlie_provider)
+        .unwrap();
+    assert_eq!(charlie_group.epoch(), alice_group.epoch());
+    assert_eq!(
+        charlie_group
+            .export_secret(charlie_provider, "test", &[], 32)
+            .unwrap(),
+        alice_group
+            .export_secret(alice_provider, "test", &[], 32)
+            .unwrap()
+    );
+}
+
+#[openmls_test]
+fn welcome_onboarding_data() {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .ciphersuite(ciphersuite)
+        .use_ratchet_tree_extension(true)
+        .build()
+        .unwrap();
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key,
+    )
+    .unwrap();
+
+    let invite_message = Extension::Unknown(0xf042, UnknownExtension(b"Welcome, Bob!".to_vec()));
+
+    // Extension types can't be used twice.
+    let err = alice_group
+        .commit_builder()
+        .propose_adds([bob_kpb.key_package().clone()])
+        .onboarding_data([invite_message.clone(), invite_message.clone()])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        CreateCommitError::InvalidExtensionError(InvalidExtensionError::Duplicate)
+    );
+
+    // Extension types that are defined by MLS can't be used.
+    let err = alice_group
+        .commit_builder()
+        .propose_adds([bob_kpb.key_package().clone()])
+        .onboarding_data([Extension::ApplicationId(ApplicationIdExtension::new(
+            b"Alice",
+        ))])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        CreateCommitError::AppExtension(AppExtensionError::ReservedExtensionType(0x0001))
+    );
+
+    // === Alice adds Bob with an invite message ===
+    let bundle = alice_group
+        .commit_builder()
+        .propose_adds([bob_kpb.key_package().clone()])
+        .onboarding_data([invite_message.clone()])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // The onboarding data is only in the GroupInfo of the Welcome.
+    assert!(!bundle
+        .group_info()
+        .unwrap()
+        .extensions()
+        .iter()
+        .any(|extension| extension == &invite_message));
+
+    // Bob sees the invite message before he joins.
+    let staged_welcome = StagedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        bundle.welcome().unwrap().clone(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        staged_welcome.onboarding_data().collect::<Vec<_>>(),
+        vec![&invite_message]
+    );
+    assert_eq!(
+        staged_welcome.welcome_sender_index(),
+        alice_group.own_leaf_index()
+    );
+    staged_welcome.into_group(provider).unwrap();
+
+    // Without new members, the onboarding data isn't sent.
+    let bundle = alice_group
+        .commit_builder()
+        .onboarding_data([invite_message])
+        .load_psks(provider.storage())
+        .unwrap()
+        .build(provider.rand(), provider.crypto(), &alice_signer, |_| true)
+        .unwrap()
+        .stage_commit(provider)
+        .unwrap();
+    assert!(bundle.welcome().is_none());
+    let group_info = bundle.group_info().unwrap();
+    assert!(group_info
+        .extensions()
+        .iter()
+        .all(|extension| !matches!(extension, Extension::Unknown(..))));
+}
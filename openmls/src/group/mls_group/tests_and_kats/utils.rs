@@ -3,7 +3,13 @@
 use openmls_basic_credential::SignatureKeyPair;
 use openmls_traits::types::HpkeCiphertext;
 
-use crate::{credentials::*, group::*, key_packages::*, test_utils::*};
+use crate::{
+    credentials::*,
+    framing::{MlsMessageOut, ProcessedMessageContent},
+    group::*,
+    key_packages::*,
+    test_utils::*,
+};
 
 pub(crate) fn setup_alice_group(
     ciphersuite: Ciphersuite,
@@ -160,3 +166,68 @@ pub(crate) fn setup_alice_bob_group<Provider: OpenMlsProvider>(
         bob_credential,
     )
 }
+
+/// Sets up a group of Alice and Bob like [`setup_alice_bob_group()`], but
+/// with a separate provider, and thus a separate storage, for each of them.
+pub(crate) fn setup_alice_bob_group_with_providers<Provider: OpenMlsProvider>(
+    ciphersuite: Ciphersuite,
+    alice_provider: &Provider,
+    bob_provider: &Provider,
+) -> (MlsGroup, SignatureKeyPair, MlsGroup, SignatureKeyPair) {
+    let (mut alice_group, _alice_credential_with_key, alice_signer, _alice_pk) =
+        setup_alice_group(ciphersuite, alice_provider);
+    let (_bob_credential_with_key, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, bob_provider);
+
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(
+            alice_provider,
+            &alice_signer,
+            &[bob_kpb.key_package().clone()],
+        )
+        .expect("Could not add Bob.");
+    alice_group
+        .merge_pending_commit(alice_provider)
+        .expect("error merging pending commit");
+
+    let bob_group = StagedWelcome::new_from_welcome(
+        bob_provider,
+        &MlsGroupJoinConfig::default(),
+        welcome.into_welcome().expect("expected a welcome"),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .and_then(|staged_welcome| staged_welcome.into_group(bob_provider))
+    .expect("error creating group from welcome");
+
+    (alice_group, alice_signer, bob_group, bob_signer)
+}
+
+/// Processes the `commit` in the `group` and returns the staged commit
+/// without merging it.
+pub(crate) fn process_commit<Provider: OpenMlsProvider>(
+    provider: &Provider,
+    group: &mut MlsGroup,
+    commit: MlsMessageOut,
+) -> StagedCommit {
+    let processed_message = group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .expect("Could not process message.");
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("Expected a StagedCommit.");
+    };
+    *staged_commit
+}
+
+/// Processes the `commit` in the `group` and merges it.
+pub(crate) fn process_and_merge_commit<Provider: OpenMlsProvider>(
+    provider: &Provider,
+    group: &mut MlsGroup,
+    commit: MlsMessageOut,
+) {
+    let staged_commit = process_commit(provider, group, commit);
+    group
+        .merge_staged_commit(provider, staged_commit)
+        .expect("error merging staged commit");
+}
@@ -0,0 +1,257 @@
+//! # Operation timing
+//!
+//! With the `operation-timing` feature, [`MlsGroup`] measures how long the
+//! major phases of processing a message and of creating a commit take, so
+//! that integrators can tell whether slowness is caused by the cryptography,
+//! the size of the tree or their storage backend. The breakdown of the last
+//! operation is returned by [`MlsGroup::last_operation_timing()`].
+//!
+//! Processing a message covers [`MlsGroup::process_message()`] and its
+//! variants. Creating a commit covers building the commit with the
+//! [`CommitBuilder`](super::commit_builder::CommitBuilder) and staging it,
+//! which also applies to the convenience functions like
+//! [`MlsGroup::add_members()`]. The timing of processing a message is
+//! recorded whether or not it succeeds, the timing of creating a commit once
+//! the commit is staged.
+//!
+//! Phases that are nested in another measured phase are attributed to the
+//! outer phase only. Time that isn't spent in any of the phases, e.g. for the
+//! key schedule or the validation of proposals, is returned by
+//! [`OperationTiming::unattributed()`].
+
+#[cfg(feature = "operation-timing")]
+use std::{cell::RefCell, time::Duration};
+
+#[cfg(all(feature = "operation-timing", target_arch = "wasm32"))]
+use fluvio_wasm_timer::Instant;
+#[cfg(all(feature = "operation-timing", not(target_arch = "wasm32")))]
+use std::time::Instant;
+
+use super::MlsGroup;
+
+/// An operation of an [`MlsGroup`] whose timing is recorded.
+#[cfg_attr(not(feature = "operation-timing"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OperationKind {
+    /// Processing an incoming message.
+    ProcessMessage,
+    /// Creating a commit.
+    CreateCommit,
+}
+
+/// A phase of an operation whose duration is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TimingPhase {
+    /// Decrypting the message, including the sender data.
+    Decryption,
+    /// Creating and verifying the signatures of messages.
+    Signature,
+    /// Applying the proposals and the update path to the tree.
+    TreeDiff,
+    /// Decrypting the path secrets of a received commit.
+    PathDecryption,
+    /// Reading from and writing to the storage.
+    StorageIo,
+}
+
+#[cfg(feature = "operation-timing")]
+impl TimingPhase {
+    /// All phases in the order of their declaration.
+    pub const ALL: [TimingPhase; 5] = [
+        TimingPhase::Decryption,
+        TimingPhase::Signature,
+        TimingPhase::TreeDiff,
+        TimingPhase::PathDecryption,
+        TimingPhase::StorageIo,
+    ];
+}
+
+/// The breakdown of the time spent in an operation. See the
+/// [module documentation](self) for details.
+#[cfg(feature = "operation-timing")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationTiming {
+    operation: OperationKind,
+    total: Duration,
+    phases: [Duration; 5],
+}
+
+#[cfg(feature = "operation-timing")]
+impl OperationTiming {
+    /// Returns the operation that was timed.
+    pub fn operation(&self) -> OperationKind {
+        self.operation
+    }
+
+    /// Returns the total duration of the operation.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the time spent in the given `phase`.
+    pub fn phase(&self, phase: TimingPhase) -> Duration {
+        self.phases[phase as usize]
+    }
+
+    /// Returns the time spent in each phase.
+    pub fn phases(&self) -> impl Iterator<Item = (TimingPhase, Duration)> + '_ {
+        TimingPhase::ALL
+            .into_iter()
+            .map(|phase| (phase, self.phase(phase)))
+    }
+
+    /// Returns the time that isn't attributed to any of the phases.
+    pub fn unattributed(&self) -> Duration {
+        self.total
+            .saturating_sub(self.phases.iter().sum::<Duration>())
+    }
+
+    // Adds the durations of a later part of the same operation.
+    pub(crate) fn extend(&mut self, other: OperationTiming) {
+        self.total += other.total;
+        for (duration, other) in self.phases.iter_mut().zip(other.phases) {
+            *duration += other;
+        }
+    }
+}
+
+// The durations measured for an operation that is being recorded.
+#[cfg(feature = "operation-timing")]
+#[derive(Default)]
+struct Phases {
+    durations: [Duration; 5],
+    in_phase: bool,
+}
+
+#[cfg(feature = "operation-timing")]
+thread_local! {
+    // The operations that are being recorded on this thread. Phases are
+    // attributed to the innermost one.
+    static RECORDINGS: RefCell<Vec<Phases>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records the phases of an operation until it is finished or dropped.
+#[cfg(feature = "operation-timing")]
+pub(crate) struct Recording {
+    operation: OperationKind,
+    start: Instant,
+}
+
+#[cfg(feature = "operation-timing")]
+impl Recording {
+    pub(crate) fn start(operation: OperationKind) -> Self {
+        RECORDINGS.with(|recordings| recordings.borrow_mut().push(Phases::default()));
+        Self {
+            operation,
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> OperationTiming {
+        let phases = RECORDINGS.with(|recordings| {
+            recordings
+                .borrow()
+                .last()
+                .map(|phases| phases.durations)
+                .unwrap_or_default()
+        });
+        OperationTiming {
+            operation: self.operation,
+            total: self.start.elapsed(),
+            phases,
+        }
+    }
+}
+
+#[cfg(feature = "operation-timing")]
+impl Drop for Recording {
+    fn drop(&mut self) {
+        RECORDINGS.with(|recordings| recordings.borrow_mut().pop());
+    }
+}
+
+/// Runs `f` and attributes its duration to the given `phase` of the operation
+/// that is being recorded, if any.
+#[cfg(feature = "operation-timing")]
+pub(crate) fn measure<T>(phase: TimingPhase, f: impl FnOnce() -> T) -> T {
+    let outermost = RECORDINGS.with(|recordings| {
+        recordings
+            .borrow_mut()
+            .last_mut()
+            .is_some_and(|phases| !std::mem::replace(&mut phases.in_phase, true))
+    });
+    if !outermost {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    RECORDINGS.with(|recordings| {
+        if let Some(phases) = recordings.borrow_mut().last_mut() {
+            phases.durations[phase as usize] += elapsed;
+            phases.in_phase = false;
+        }
+    });
+    result
+}
+
+/// Runs `f`. Without the `operation-timing` feature, nothing is measured.
+#[cfg(not(feature = "operation-timing"))]
+pub(crate) fn measure<T>(_phase: TimingPhase, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/// The timing of the last operation of a group. It is ephemeral, i.e. not
+/// stored, and doesn't affect the equality of groups.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LastOperationTiming {
+    #[cfg(feature = "operation-timing")]
+    timing: Option<OperationTiming>,
+}
+
+impl PartialEq for LastOperationTiming {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl MlsGroup {
+    /// Returns the timing of the last message processed or commit created
+    /// by this group, or `None` if there was none since the group was
+    /// created or loaded.
+    #[cfg(feature = "operation-timing")]
+    pub fn last_operation_timing(&self) -> Option<&OperationTiming> {
+        self.last_operation_timing.timing.as_ref()
+    }
+
+    /// Runs `f` as the given `operation` and keeps its timing.
+    #[cfg(feature = "operation-timing")]
+    pub(crate) fn timed<T>(
+        &mut self,
+        operation: OperationKind,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let recording = Recording::start(operation);
+        let result = f(self);
+        self.last_operation_timing.timing = Some(recording.finish());
+        result
+    }
+
+    /// Runs `f`. Without the `operation-timing` feature, nothing is measured.
+    #[cfg(not(feature = "operation-timing"))]
+    pub(crate) fn timed<T>(
+        &mut self,
+        _operation: OperationKind,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        f(self)
+    }
+
+    /// Keeps the `timing` of an operation that was recorded outside of
+    /// [`Self::timed()`].
+    #[cfg(feature = "operation-timing")]
+    pub(crate) fn set_last_operation_timing(&mut self, timing: OperationTiming) {
+        self.last_operation_timing.timing = Some(timing);
+    }
+}
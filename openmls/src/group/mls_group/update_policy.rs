@@ -0,0 +1,233 @@
+//! # Mandatory updates
+//!
+//! A member only regains post-compromise security for its leaf when it
+//! replaces the encryption key of the leaf, with an Update proposal or a
+//! commit with a path. A [`MandatoryUpdatePolicy`] limits the number of epochs
+//! a leaf may go without such an update. It is installed on a group with
+//! [`EpochHooks::with_update_policy()`], which brings the policy up to date
+//! whenever the group enters a new epoch and reports the leaves that became
+//! stale to a handler, so that the application can warn about them or remove
+//! them. Depending on its settings, the installed policy also
+//!
+//! - makes [`MlsGroup::create_message()`] refuse to encrypt application
+//!   messages while the own leaf is stale, see
+//!   [`MandatoryUpdatePolicy::block_application_messages()`], and
+//! - rejects incoming commits of stale members that don't update the leaf of
+//!   the committer, see [`MandatoryUpdatePolicy::reject_stale_commits()`].
+//!
+//! A new member can't know when the leaves of the group were updated before it
+//! joined, so the policy counts every leaf as updated in the epoch in which it
+//! first saw it. Like all hooks, the policy isn't stored with the group. It
+//! can be serialized from [`EpochHooks::update_policy()`] and installed again
+//! after the group is loaded.
+
+use serde::{Deserialize, Serialize};
+
+use super::{epoch_hooks::EpochHooks, errors::CreateMessageError, leaf_map::LeafMap, MlsGroup};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    framing::Sender,
+    group::{GroupEpoch, PolicyRejection, ProcessingPolicy, ReceivedCommitInfo},
+};
+
+/// A leaf that wasn't updated for more epochs than allowed by a
+/// [`MandatoryUpdatePolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleLeaf {
+    leaf_index: LeafNodeIndex,
+    last_update: GroupEpoch,
+    staleness: u64,
+}
+
+impl StaleLeaf {
+    /// Returns the index of the stale leaf.
+    pub fn leaf_index(&self) -> LeafNodeIndex {
+        self.leaf_index
+    }
+
+    /// Returns the epoch in which the leaf was last updated.
+    pub fn last_update(&self) -> GroupEpoch {
+        self.last_update
+    }
+
+    /// Returns the number of epochs since the last update of the leaf.
+    pub fn staleness(&self) -> u64 {
+        self.staleness
+    }
+}
+
+/// Tracks the last update of each leaf of a group and enforces a maximum
+/// staleness. See the [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MandatoryUpdatePolicy {
+    max_staleness: u64,
+    block_application_messages: bool,
+    reject_stale_commits: bool,
+    epoch: GroupEpoch,
+    // The epoch in which the encryption key of each leaf was first seen.
+    last_updates: LeafMap<GroupEpoch>,
+}
+
+impl MandatoryUpdatePolicy {
+    /// Creates a policy that considers leaves stale that weren't updated for
+    /// more than `max_staleness` epochs. By default, stale leaves are only
+    /// reported to the handler of the hook.
+    pub fn new(max_staleness: u64) -> Self {
+        Self {
+            max_staleness,
+            block_application_messages: false,
+            reject_stale_commits: false,
+            epoch: GroupEpoch::from(0),
+            last_updates: LeafMap::default(),
+        }
+    }
+
+    /// Sets whether [`MlsGroup::create_message()`] refuses to encrypt
+    /// application messages with [`CreateMessageError::UpdateRequired`] while
+    /// the own leaf is stale. The own leaf can be updated with
+    /// [`MlsGroup::self_update()`].
+    pub fn block_application_messages(mut self, block: bool) -> Self {
+        self.block_application_messages = block;
+        self
+    }
+
+    /// Sets whether commits of stale members are rejected with
+    /// [`PolicyRejection::StaleCommitter`], unless they update the leaf of the
+    /// committer. This applies to the commits processed by a group the policy
+    /// is installed on, and to the commits processed with the policy as
+    /// [`ProcessingPolicy`].
+    pub fn reject_stale_commits(mut self, reject: bool) -> Self {
+        self.reject_stale_commits = reject;
+        self
+    }
+
+    /// Returns the maximum number of epochs a leaf may go without an update.
+    pub fn max_staleness(&self) -> u64 {
+        self.max_staleness
+    }
+
+    /// Returns the epoch in which the leaf at `leaf_index` was last updated,
+    /// or `None` if there is no such leaf.
+    pub fn last_update(&self, leaf_index: LeafNodeIndex) -> Option<GroupEpoch> {
+        self.last_updates.get(leaf_index).copied()
+    }
+
+    /// Records the leaves of the `group` whose encryption key changed since
+    /// the last call as updated in the current epoch and returns the leaves
+    /// that are stale.
+    pub(crate) fn observe(&mut self, group: &MlsGroup) -> Vec<StaleLeaf> {
+        let epoch = group.epoch();
+        self.last_updates
+            .sync(group, |member| &member.encryption_key, || epoch);
+        self.epoch = epoch;
+
+        self.stale_leaves()
+    }
+
+    /// Returns the leaves that are stale in the epoch the policy was last
+    /// brought up to date in.
+    pub fn stale_leaves(&self) -> Vec<StaleLeaf> {
+        self.last_updates
+            .iter()
+            .filter_map(|(leaf_index, _)| self.stale_leaf(leaf_index, self.epoch))
+            .collect()
+    }
+
+    // Returns the leaf at `leaf_index` if it is stale in the given `epoch`.
+    fn stale_leaf(&self, leaf_index: LeafNodeIndex, epoch: GroupEpoch) -> Option<StaleLeaf> {
+        let last_update = *self.last_updates.get(leaf_index)?;
+        let staleness = epoch.as_u64().saturating_sub(last_update.as_u64());
+        (staleness > self.max_staleness).then_some(StaleLeaf {
+            leaf_index,
+            last_update,
+            staleness,
+        })
+    }
+}
+
+impl ProcessingPolicy for MandatoryUpdatePolicy {
+    fn validate_staged_commit(&self, commit: &ReceivedCommitInfo) -> Result<(), PolicyRejection> {
+        if !self.reject_stale_commits || commit.staged_commit().update_path_leaf_node().is_some() {
+            return Ok(());
+        }
+        let Sender::Member(leaf_index) = commit.sender() else {
+            return Ok(());
+        };
+        match self.stale_leaf(*leaf_index, commit.group().group_context().epoch()) {
+            Some(stale_leaf) => Err(PolicyRejection::StaleCommitter(stale_leaf)),
+            None => Ok(()),
+        }
+    }
+}
+
+type Handler = Box<dyn FnMut(&[StaleLeaf]) + Send + Sync>;
+
+/// The update policy hook of the [`EpochHooks`] of a group.
+pub(super) struct UpdatePolicyHook {
+    pub(super) policy: MandatoryUpdatePolicy,
+    handler: Handler,
+}
+
+impl UpdatePolicyHook {
+    /// Brings the policy up to date with the current epoch of the `group` and
+    /// hands the leaves that became stale to the handler.
+    pub(super) fn run(&mut self, group: &MlsGroup) {
+        let previously_stale = self.policy.stale_leaves();
+        let newly_stale: Vec<_> = self
+            .policy
+            .observe(group)
+            .into_iter()
+            .filter(|stale_leaf| {
+                !previously_stale.iter().any(|previous| {
+                    previous.leaf_index == stale_leaf.leaf_index
+                        && previous.last_update == stale_leaf.last_update
+                })
+            })
+            .collect();
+        if !newly_stale.is_empty() {
+            (self.handler)(&newly_stale);
+        }
+    }
+}
+
+impl EpochHooks {
+    /// Adds a hook that brings the `policy` up to date for every new epoch and
+    /// hands the leaves that became stale in the epoch to the `handler`. The
+    /// installed policy is also enforced when sending application messages
+    /// and processing commits, see
+    /// [`MandatoryUpdatePolicy::block_application_messages()`] and
+    /// [`MandatoryUpdatePolicy::reject_stale_commits()`].
+    pub fn with_update_policy(
+        mut self,
+        policy: MandatoryUpdatePolicy,
+        handler: impl FnMut(&[StaleLeaf]) + Send + Sync + 'static,
+    ) -> Self {
+        self.update_policy = Some(UpdatePolicyHook {
+            policy,
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Returns the [`MandatoryUpdatePolicy`] of the hook, e.g. to persist it.
+    pub fn update_policy(&self) -> Option<&MandatoryUpdatePolicy> {
+        self.update_policy.as_ref().map(|hook| &hook.policy)
+    }
+}
+
+impl MlsGroup {
+    /// Returns [`CreateMessageError::UpdateRequired`] if the installed
+    /// [`MandatoryUpdatePolicy`] blocks application messages and the own leaf
+    /// is stale.
+    pub(super) fn check_update_policy(&self) -> Result<(), CreateMessageError> {
+        let Some(policy) = self.epoch_hooks.update_policy() else {
+            return Ok(());
+        };
+        if policy.block_application_messages {
+            if let Some(stale_leaf) = policy.stale_leaf(self.own_leaf_index(), self.epoch()) {
+                return Err(CreateMessageError::UpdateRequired(stale_leaf));
+            }
+        }
+        Ok(())
+    }
+}
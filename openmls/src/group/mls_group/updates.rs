@@ -1,6 +1,6 @@
 use commit_builder::CommitMessageBundle;
 use errors::{ProposeSelfUpdateError, SelfUpdateError};
-use openmls_traits::{signatures::Signer, storage::StorageProvider as _};
+use openmls_traits::{signatures::Signer, storage::StorageWriter as _};
 
 use crate::{storage::OpenMlsProvider, treesync::LeafNodeParameters};
 
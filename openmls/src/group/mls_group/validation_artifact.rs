@@ -0,0 +1,441 @@
+//! # Validation artifacts for the delivery service
+//!
+//! A delivery service that polices the traffic of a group usually can't
+//! afford to reconstruct the full public group from its history. Instead, a
+//! committer can attach a [`CommitValidationArtifact`] to its commit with
+//! [`CommitBuilder::validation_artifact()`](super::commit_builder::CommitBuilder::validation_artifact)
+//! and
+//! [`CommitMessageBundle::sign_validation_artifact()`](super::commit_builder::CommitMessageBundle::sign_validation_artifact).
+//! The artifact only contains public data: the [`TreeSlice`] of the committer,
+//! a summary of the committed proposals with the slices of the removed
+//! members, the group context of the next epoch and the hash of the framed
+//! commit. It is signed with the signature key in the committer's leaf.
+//!
+//! The delivery service only needs to keep track of the [`GroupContext`] of
+//! the current epoch. [`CommitValidationArtifact::validate()`] checks the
+//! commit against it, i.e. that the sender is a member of the group, that
+//! the artifact is signed by the sender and was created for this commit, that
+//! the commit is for the current epoch and that the proposals are admissible.
+//! For a public commit, it also checks the signature of the commit and that
+//! every committed proposal matches the artifact in type and target.
+//!
+//! The delivery service trusts the committer for everything it can't see:
+//! the proposals of a private commit and the group context of the next epoch,
+//! [`CommitValidationArtifact::next_group_context()`], which depends on the
+//! full tree. Both are only checked by the other members when they process
+//! the commit. Since the artifact is signed, a committer that lies about them
+//! can be identified.
+
+use std::collections::HashSet;
+
+use openmls_traits::{crypto::OpenMlsCrypto, signatures::Signer, types::Ciphersuite};
+use serde::{Deserialize, Serialize};
+use tls_codec::{Serialize as _, TlsSerialize, TlsSize, VLBytes};
+
+use super::errors::ArtifactValidationError;
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::{
+        hash_ref::ProposalRef,
+        signable::{Signable, SignatureError},
+        SignContent, Signature,
+    },
+    error::LibraryError,
+    framing::{
+        mls_content::FramedContentBody, ContentType, DecryptedMessage, MlsMessageBodyOut,
+        MlsMessageOut, ProtocolMessage, SenderContext, UnverifiedMessage,
+    },
+    group::{GroupContext, ProposalQueue, PublicGroup},
+    messages::proposals::{Proposal, ProposalOrRef, ProposalOrRefType, ProposalType},
+    treesync::{errors::TreeSliceError, TreeSlice},
+};
+
+const COMMIT_VALIDATION_ARTIFACT_LABEL: &str = "CommitValidationArtifact";
+
+/// A summary of a proposal that is committed by a commit. See
+/// [`CommitValidationArtifact::proposals()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactProposal {
+    proposal_type: ProposalType,
+    removed: Option<TreeSlice>,
+    reference: Option<ProposalRef>,
+}
+
+impl ArtifactProposal {
+    /// Returns the type of the proposal.
+    pub fn proposal_type(&self) -> ProposalType {
+        self.proposal_type
+    }
+
+    /// Returns the [`TreeSlice`] of the removed member if this is a remove
+    /// proposal.
+    pub fn removed(&self) -> Option<&TreeSlice> {
+        self.removed.as_ref()
+    }
+
+    /// Returns the reference of the proposal if it is committed by reference.
+    pub fn reference(&self) -> Option<&ProposalRef> {
+        self.reference.as_ref()
+    }
+
+    /// Returns whether the proposal of the commit is the one of the artifact.
+    fn matches(&self, proposal_or_ref: &ProposalOrRef) -> bool {
+        match proposal_or_ref {
+            ProposalOrRef::Proposal(proposal) => {
+                let removed = match proposal {
+                    Proposal::Remove(remove_proposal) => Some(remove_proposal.removed()),
+                    _ => None,
+                };
+                self.reference.is_none()
+                    && self.proposal_type == proposal.proposal_type()
+                    && self.removed.as_ref().map(TreeSlice::leaf_index) == removed
+            }
+            ProposalOrRef::Reference(reference) => self.reference.as_ref() == Some(reference),
+        }
+    }
+}
+
+// The signed part of a [`CommitValidationArtifact`]. The slices are bound by
+// their leaf indices, since they are verified against the tree hash.
+#[derive(Debug, TlsSerialize, TlsSize)]
+struct ArtifactProposalTbs {
+    proposal_type: ProposalType,
+    removed: Option<LeafNodeIndex>,
+    reference: Option<ProposalRef>,
+}
+
+#[derive(TlsSerialize, TlsSize)]
+struct CommitValidationArtifactTbs<'a> {
+    sender: LeafNodeIndex,
+    proposals: Vec<ArtifactProposalTbs>,
+    next_group_context: &'a GroupContext,
+    commit_hash: &'a VLBytes,
+}
+
+impl Signable for CommitValidationArtifactTbs<'_> {
+    type SignedOutput = Signature;
+
+    fn unsigned_payload(&self) -> Result<Vec<u8>, tls_codec::Error> {
+        self.tls_serialize_detached()
+    }
+
+    fn label(&self) -> &str {
+        COMMIT_VALIDATION_ARTIFACT_LABEL
+    }
+}
+
+/// The parts of a [`CommitValidationArtifact`] that are known when the
+/// commit is built. The artifact is completed with the hash of the framed
+/// commit when the commit is staged, and signed on request.
+#[derive(Debug, Clone)]
+pub(crate) struct UnsignedValidationArtifact {
+    sender_slice: TreeSlice,
+    proposals: Vec<ArtifactProposal>,
+    next_group_context: GroupContext,
+    commit_hash: VLBytes,
+}
+
+/// Public data that lets the delivery service validate a commit without the
+/// full public group. See the [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitValidationArtifact {
+    sender_slice: TreeSlice,
+    proposals: Vec<ArtifactProposal>,
+    next_group_context: GroupContext,
+    commit_hash: VLBytes,
+    signature: Signature,
+}
+
+impl UnsignedValidationArtifact {
+    /// Creates the artifact for a commit of the member at `sender` in the
+    /// `public_group`, which commits the proposals in the `proposal_queue`.
+    pub(super) fn new(
+        crypto: &impl OpenMlsCrypto,
+        public_group: &PublicGroup,
+        sender: LeafNodeIndex,
+        proposal_queue: &ProposalQueue,
+        next_group_context: GroupContext,
+    ) -> Result<Self, LibraryError> {
+        // The committer and the removed members have been validated, so they
+        // are in the tree.
+        let tree_slice = |leaf_index| {
+            public_group
+                .treesync()
+                .tree_slice(crypto, public_group.ciphersuite(), leaf_index)
+                .map_err(|e| match e {
+                    TreeSliceError::LibraryError(e) => e,
+                    _ => LibraryError::custom("Could not cut a slice for a member"),
+                })
+        };
+
+        let proposals = proposal_queue
+            .queued_proposals()
+            .map(|queued_proposal| {
+                let removed = match queued_proposal.proposal() {
+                    Proposal::Remove(remove_proposal) => {
+                        Some(tree_slice(remove_proposal.removed())?)
+                    }
+                    _ => None,
+                };
+                let reference = match queued_proposal.proposal_or_ref_type() {
+                    ProposalOrRefType::Reference => Some(queued_proposal.proposal_reference()),
+                    ProposalOrRefType::Proposal => None,
+                };
+                Ok(ArtifactProposal {
+                    proposal_type: queued_proposal.proposal().proposal_type(),
+                    removed,
+                    reference,
+                })
+            })
+            .collect::<Result<_, LibraryError>>()?;
+
+        Ok(Self {
+            sender_slice: tree_slice(sender)?,
+            proposals,
+            next_group_context,
+            commit_hash: VLBytes::new(Vec::new()),
+        })
+    }
+
+    /// Sets the hash of the framed `commit`, once it is encrypted if the
+    /// group uses private handshake messages.
+    pub(super) fn set_commit(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+        commit: &MlsMessageOut,
+    ) -> Result<(), LibraryError> {
+        let serialized_commit = match commit.body() {
+            MlsMessageBodyOut::PublicMessage(public_message) => {
+                public_message.tls_serialize_detached()
+            }
+            MlsMessageBodyOut::PrivateMessage(private_message) => {
+                private_message.tls_serialize_detached()
+            }
+            _ => return Err(LibraryError::custom("A commit must be a protocol message")),
+        }
+        .map_err(LibraryError::missing_bound_check)?;
+        self.commit_hash = commit_hash(
+            crypto,
+            self.next_group_context.ciphersuite(),
+            &serialized_commit,
+        )?
+        .into();
+        Ok(())
+    }
+
+    /// Signs the artifact with the `signer` of the committer.
+    pub(super) fn sign(
+        self,
+        signer: &impl Signer,
+    ) -> Result<CommitValidationArtifact, SignatureError> {
+        let signature = tbs(
+            self.sender_slice.leaf_index(),
+            &self.proposals,
+            &self.next_group_context,
+            &self.commit_hash,
+        )
+        .sign(signer)?;
+        Ok(CommitValidationArtifact {
+            sender_slice: self.sender_slice,
+            proposals: self.proposals,
+            next_group_context: self.next_group_context,
+            commit_hash: self.commit_hash,
+            signature,
+        })
+    }
+}
+
+fn tbs<'a>(
+    sender: LeafNodeIndex,
+    proposals: &[ArtifactProposal],
+    next_group_context: &'a GroupContext,
+    commit_hash: &'a VLBytes,
+) -> CommitValidationArtifactTbs<'a> {
+    CommitValidationArtifactTbs {
+        sender,
+        proposals: proposals
+            .iter()
+            .map(|proposal| ArtifactProposalTbs {
+                proposal_type: proposal.proposal_type,
+                removed: proposal.removed.as_ref().map(TreeSlice::leaf_index),
+                reference: proposal.reference.clone(),
+            })
+            .collect(),
+        next_group_context,
+        commit_hash,
+    }
+}
+
+fn commit_hash(
+    crypto: &impl OpenMlsCrypto,
+    ciphersuite: Ciphersuite,
+    serialized_commit: &[u8],
+) -> Result<Vec<u8>, LibraryError> {
+    crypto
+        .hash(ciphersuite.hash_algorithm(), serialized_commit)
+        .map_err(LibraryError::unexpected_crypto_error)
+}
+
+impl CommitValidationArtifact {
+    /// Returns the [`TreeSlice`] of the committer.
+    pub fn sender_slice(&self) -> &TreeSlice {
+        &self.sender_slice
+    }
+
+    /// Returns the committed proposals, in the order of the commit.
+    pub fn proposals(&self) -> &[ArtifactProposal] {
+        &self.proposals
+    }
+
+    /// Returns the group context of the epoch after the commit, as claimed by
+    /// the committer. It is not checked by
+    /// [`CommitValidationArtifact::validate()`], so a delivery service that
+    /// adopts it trusts the committer. See the
+    /// [module documentation](self) for details.
+    pub fn next_group_context(&self) -> &GroupContext {
+        &self.next_group_context
+    }
+
+    /// Validates the `commit` against the `group_context` of the current
+    /// epoch. See the [module documentation](self) for the checks.
+    ///
+    /// The sender and the proposals of a commit in a private message are
+    /// hidden from the delivery service. A private commit is only bound to
+    /// the artifact, and thereby to the sender, by the signed hash of the
+    /// commit.
+    pub fn validate(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        group_context: &GroupContext,
+        commit: &ProtocolMessage,
+    ) -> Result<(), ArtifactValidationError> {
+        let ciphersuite = group_context.ciphersuite();
+
+        if commit.group_id() != group_context.group_id()
+            || self.next_group_context.group_id() != group_context.group_id()
+        {
+            return Err(ArtifactValidationError::WrongGroupId);
+        }
+        if commit.epoch() != group_context.epoch()
+            || self.next_group_context.epoch().as_u64() != group_context.epoch().as_u64() + 1
+        {
+            return Err(ArtifactValidationError::WrongEpoch);
+        }
+        if commit.content_type() != ContentType::Commit {
+            return Err(ArtifactValidationError::NotACommit);
+        }
+
+        // The sender must be a member of the current epoch and must have
+        // signed the artifact for this commit.
+        self.sender_slice
+            .verify(crypto, ciphersuite, group_context.tree_hash())?;
+        let sender_index = self.sender_slice.leaf_index();
+        let sender_leaf = self
+            .sender_slice
+            .leaf()
+            .ok_or(ArtifactValidationError::UnknownSender)?;
+        let signature_key = sender_leaf
+            .signature_key()
+            .clone()
+            .into_signature_public_key_enriched(ciphersuite.signature_algorithm());
+        let payload = tbs(
+            sender_index,
+            &self.proposals,
+            &self.next_group_context,
+            &self.commit_hash,
+        )
+        .unsigned_payload()
+        .map_err(LibraryError::missing_bound_check)?;
+        signature_key
+            .verify_with_label(
+                crypto,
+                &self.signature,
+                &SignContent::new(COMMIT_VALIDATION_ARTIFACT_LABEL, payload.into()),
+            )
+            .map_err(|_| ArtifactValidationError::InvalidSignature)?;
+
+        let serialized_commit = match commit {
+            ProtocolMessage::PublicMessage(public_message) => {
+                public_message.tls_serialize_detached()
+            }
+            ProtocolMessage::PrivateMessage(private_message) => {
+                private_message.tls_serialize_detached()
+            }
+        }
+        .map_err(LibraryError::missing_bound_check)?;
+        if commit_hash(crypto, ciphersuite, &serialized_commit)? != self.commit_hash.as_slice() {
+            return Err(ArtifactValidationError::CommitMismatch);
+        }
+
+        // The proposals must be admissible in a commit of a member.
+        let mut removed_members = HashSet::new();
+        for proposal in &self.proposals {
+            match (proposal.proposal_type, &proposal.removed) {
+                (ProposalType::Remove, Some(removed)) => {
+                    removed.verify(crypto, ciphersuite, group_context.tree_hash())?;
+                    let removed_index = removed.leaf_index();
+                    if removed.leaf().is_none()
+                        || removed_index == sender_index
+                        || !removed_members.insert(removed_index)
+                    {
+                        return Err(ArtifactValidationError::InadmissibleProposal(
+                            ProposalType::Remove,
+                        ));
+                    }
+                }
+                (ProposalType::Remove, None) | (ProposalType::ExternalInit, _) => {
+                    return Err(ArtifactValidationError::InadmissibleProposal(
+                        proposal.proposal_type,
+                    ))
+                }
+                (_, Some(_)) => return Err(ArtifactValidationError::ProposalMismatch),
+                (_, None) => (),
+            }
+        }
+
+        // For a public commit, check that it is signed by the sender and
+        // commits the proposals of the artifact, in the same order.
+        if let ProtocolMessage::PublicMessage(public_message) = commit {
+            if public_message.sender().as_member() != Some(sender_index) {
+                return Err(ArtifactValidationError::UnknownSender);
+            }
+
+            let decrypted_message = DecryptedMessage::from_inbound_public_message(
+                (**public_message).clone(),
+                None,
+                group_context
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+                crypto,
+                ciphersuite,
+            )
+            .map_err(ArtifactValidationError::InvalidCommit)?;
+            let (content, _credential) = UnverifiedMessage::from_decrypted_message(
+                decrypted_message,
+                sender_leaf.credential().clone(),
+                signature_key,
+                Some(SenderContext::Member((
+                    group_context.group_id().clone(),
+                    sender_index,
+                ))),
+            )
+            .verify(ciphersuite, crypto, group_context.protocol_version())
+            .map_err(ArtifactValidationError::InvalidCommit)?;
+
+            let FramedContentBody::Commit(commit) = content.content() else {
+                return Err(ArtifactValidationError::NotACommit);
+            };
+            if commit.proposals.len() != self.proposals.len()
+                || !commit
+                    .proposals
+                    .iter()
+                    .zip(&self.proposals)
+                    .all(|(proposal_or_ref, proposal)| proposal.matches(proposal_or_ref))
+            {
+                return Err(ArtifactValidationError::ProposalMismatch);
+            }
+        }
+
+        Ok(())
+    }
+}
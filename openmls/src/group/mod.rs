@@ -7,8 +7,8 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use tls_codec::*;
 
-use crate::extensions::*;
-use openmls_traits::random::OpenMlsRand;
+use crate::{error::LibraryError, extensions::*};
+use openmls_traits::{crypto::OpenMlsCrypto, random::OpenMlsRand, types::Ciphersuite};
 
 #[cfg(test)]
 use crate::ciphersuite::*;
@@ -21,17 +21,67 @@ pub(crate) mod mls_group;
 pub(crate) mod public_group;
 
 // Public
+pub use add_check::{AddCheckConflict, AddCheckFailure, AddCheckReport};
+pub use app_version::AppVersionNegotiation;
+pub use capability_report::{CapabilityReport, IncompatibleCandidate, MissingCapability};
+pub use ciphersuite_selection::{CiphersuiteCandidate, CiphersuiteSelection};
 pub use errors::*;
+pub use extension_observer::{ExtensionChange, ExtensionObservers, ExtensionScope};
 pub use group_context::GroupContext;
+pub use mls_group::activity::ActivityTracker;
+pub use mls_group::attachment::{AttachmentHeader, AttachmentKey, EncryptedAttachment};
+pub use mls_group::chunking::{ChunkReassembler, DEFAULT_MAX_TRANSFERS_PER_SENDER};
+pub use mls_group::commit_builder::UpdatePathInclusion;
+pub use mls_group::commit_simulation::{CommitSimulation, CommitSimulationParams};
 pub use mls_group::config::*;
+pub use mls_group::conflict::SupersededCommit;
+pub use mls_group::devices::{BasicCredentialIdentity, UserIdentityExtractor};
+pub use mls_group::epoch_hooks::EpochHooks;
+#[cfg(feature = "compliance-escrow")]
+pub use mls_group::escrow::{EscrowLog, EscrowLogEntry, EscrowSink, EscrowedEpochKeys};
+pub use mls_group::exported_secrets::{ExportedSecretRegistry, ExportedSecrets};
+pub use mls_group::inactive::{ClosedGroupView, InactiveGroupRecord};
+#[cfg(feature = "integrity-checks")]
+pub use mls_group::integrity::{IntegrityDiagnostic, IntegrityViolation};
+pub use mls_group::leaf_key_proof::LeafKeyProof;
+pub use mls_group::light::LightGroup;
 pub use mls_group::membership::*;
+pub use mls_group::membership_token::MembershipToken;
+pub use mls_group::proposal_check::{
+    PendingProposalCheck, PendingProposalStatus, ProposalFilterReason,
+};
 pub use mls_group::proposal_store::*;
+#[cfg(feature = "resumption-psk-backup")]
+pub use mls_group::psk_backup::{ResumptionPskBackup, ResumptionPskBackupKey};
+#[cfg(feature = "replica-sync")]
+pub use mls_group::replica::{ReplicaDelta, ReplicaDeltaScope, ReplicaSyncKey};
+pub use mls_group::signed_app_data::SignedAppData;
 pub use mls_group::staged_commit::StagedCommit;
+pub use mls_group::stats::MemberStats;
+#[cfg(feature = "operation-timing")]
+pub use mls_group::timing::{OperationKind, OperationTiming, TimingPhase};
+pub use mls_group::update_policy::{MandatoryUpdatePolicy, StaleLeaf};
+pub use mls_group::validation_artifact::{ArtifactProposal, CommitValidationArtifact};
 pub use mls_group::{Member, *};
+pub use policy::{
+    AdmissionToken, AdmissionTokenPolicy, CommitPolicy, ExternalCommitInfo,
+    ExternalSenderPermissions, ProcessingPolicy, ProposalSelection, ProtectedMembers,
+    ReceivedCommitInfo,
+};
 pub use public_group::*;
+pub use state_summary::{GroupStateComponent, GroupStateSummary};
+pub use tree_preview::{TreePreview, TreePreviewOutcome};
 
 // Private
+mod add_check;
+mod app_version;
+mod capability_report;
+mod ciphersuite_selection;
+mod extension_observer;
 mod group_context;
+mod policy;
+mod state_summary;
+mod tree_preview;
 
 // Tests
 #[cfg(any(feature = "test-utils", test))]
@@ -87,6 +137,54 @@ impl GroupId {
     }
 }
 
+/// The strategy used to choose the [`GroupId`] of a newly created group.
+///
+/// The default strategy is [`GroupIdStrategy::Random`] with a length of 16
+/// bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupIdStrategy {
+    /// A random group ID of the given length in bytes.
+    Random(usize),
+    /// A group ID derived from application context by hashing it with the
+    /// hash function of the group's ciphersuite. Two groups created with the
+    /// same context and ciphersuite get the same group ID, so the context has
+    /// to be unique for each group.
+    Derived(Vec<u8>),
+    /// A group ID that was chosen by an entity that ensures uniqueness, e.g.
+    /// the Delivery Service.
+    Assigned(GroupId),
+}
+
+impl Default for GroupIdStrategy {
+    fn default() -> Self {
+        Self::Random(16)
+    }
+}
+
+impl GroupIdStrategy {
+    /// Generate a [`GroupId`] for a group with the given ciphersuite according
+    /// to this strategy.
+    pub fn group_id(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        rand: &impl OpenMlsRand,
+        ciphersuite: Ciphersuite,
+    ) -> Result<GroupId, LibraryError> {
+        let value = match self {
+            GroupIdStrategy::Random(length) => rand
+                .random_vec(*length)
+                .map_err(|_| LibraryError::custom("Not enough randomness."))?,
+            GroupIdStrategy::Derived(context) => crypto
+                .hash(ciphersuite.hash_algorithm(), context)
+                .map_err(LibraryError::unexpected_crypto_error)?,
+            GroupIdStrategy::Assigned(group_id) => return Ok(group_id.clone()),
+        };
+        Ok(GroupId {
+            value: value.into(),
+        })
+    }
+}
+
 /// Group epoch. Internally this is stored as a `u64`.
 /// The group epoch is incremented with every valid Commit that is merged into the group state.
 #[derive(
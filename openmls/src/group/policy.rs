@@ -0,0 +1,588 @@
+//! # Processing policies
+//!
+//! This module contains the [`ProcessingPolicy`] trait, which lets applications
+//! take part in the validation of incoming messages. A policy is passed to
+//! [`MlsGroup::process_message_with_policy()`] or
+//! [`PublicGroup::process_message_with_policy()`] and is consulted in addition
+//! to the checks required by the MLS specification.
+//!
+//! It also contains the [`CommitPolicy`] trait, which lets applications select
+//! the proposals that are included in the commits they create.
+//!
+//! Policies are combined by putting them in a tuple of up to four policies,
+//! e.g. `(ProtectedMembers::new(authorize), ExternalSenderPermissions::new())`.
+//! A combination admits a message if all of its policies admit it.
+
+use tls_codec::{Deserialize as _, TlsDeserialize, TlsSerialize, TlsSize, VLBytes};
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::SignaturePublicKey,
+    credentials::Credential,
+    extensions::{Extensions, ExternalSender},
+    framing::Sender,
+    group::{
+        GroupContext, GroupEpoch, GroupId, PolicyRejection, PublicGroup, QueuedProposal,
+        StagedCommit,
+    },
+    messages::proposals::ProposalType,
+    treesync::node::leaf_node::LeafNode,
+};
+#[cfg(doc)]
+use crate::{
+    extensions::ExternalSendersExtension,
+    group::{
+        ExternalCommitError, ExternalCommitValidationError, MlsGroup, ProcessMessageError,
+        ProposalFilterReason, ProposalValidationError, StageCommitError, StagedWelcome,
+        WelcomeError,
+    },
+};
+
+/// A policy that is consulted while processing incoming messages.
+///
+/// All methods have permissive default implementations, such that implementors
+/// only need to override the checks they care about. The unit type `()`
+/// implements this trait with the default behaviour and is the policy used by
+/// [`MlsGroup::process_message()`] and [`PublicGroup::process_message()`].
+pub trait ProcessingPolicy {
+    /// Called when an external commit, i.e. a commit with sender
+    /// [`Sender::NewMemberCommit`], is staged. Returning `false` rejects the
+    /// commit with [`ExternalCommitValidationError::RejectedByPolicy`].
+    ///
+    /// Defaults to admitting all external commits.
+    fn admit_external_commit(&self, _external_commit: &ExternalCommitInfo) -> bool {
+        true
+    }
+
+    /// Called with the [`GroupId`] of a group this client is about to join,
+    /// either through a Welcome message
+    /// ([`StagedWelcome::new_from_welcome_with_policy()`]) or through an
+    /// external commit ([`MlsGroup::join_by_external_commit_with_policy()`]).
+    /// Returning `false` aborts the join with
+    /// [`WelcomeError::GroupIdRejected`] or
+    /// [`ExternalCommitError::GroupIdRejected`] respectively.
+    ///
+    /// This allows applications to only accept group IDs of a certain format,
+    /// e.g. ones assigned by their Delivery Service. Defaults to accepting
+    /// all group IDs.
+    fn accept_group_id(&self, _group_id: &GroupId) -> bool {
+        true
+    }
+
+    /// Called with the group context and the extensions of the group info of
+    /// a group this client is about to join, after
+    /// [`ProcessingPolicy::accept_group_id()`]. Returning an error aborts the
+    /// join with [`WelcomeError::RejectedByPolicy`] or
+    /// [`ExternalCommitError::RejectedByPolicy`] respectively, which contain
+    /// the returned reason.
+    ///
+    /// This allows applications to check the state of a group, e.g. its
+    /// extensions, before joining it. Defaults to accepting all groups.
+    fn validate_group_info(
+        &self,
+        _group_context: &GroupContext,
+        _extensions: &Extensions,
+    ) -> Result<(), PolicyRejection> {
+        Ok(())
+    }
+
+    /// Called when a proposal of an external sender, i.e. a sender of type
+    /// [`Sender::External`], is processed. Returning `false` rejects the
+    /// proposal with [`ProcessMessageError::UnauthorizedExternalProposal`].
+    ///
+    /// This allows restricting each of the external senders in the group's
+    /// [`ExternalSendersExtension`] to the proposal types that match its role.
+    /// Update and ExternalInit proposals can't be sent by external senders and
+    /// are always rejected. Defaults to only admitting Remove proposals. See
+    /// [`ExternalSenderPermissions`] for a configurable policy.
+    fn admit_external_proposal(
+        &self,
+        _external_sender: &ExternalSender,
+        proposal_type: ProposalType,
+    ) -> bool {
+        proposal_type == ProposalType::Remove
+    }
+
+    /// Called with the `sender` of a Remove proposal and the credential of
+    /// the member it removes, both when the proposal is received on its own
+    /// and when it is covered by a commit. Returning `false` rejects the
+    /// proposal with [`ProcessMessageError::ProtectedMemberRemoval`] or the
+    /// commit with [`ProposalValidationError::ProtectedMemberRemoval`]
+    /// respectively.
+    ///
+    /// The sender of a Remove proposal in an external commit that re-joins the
+    /// group is [`Sender::NewMemberCommit`]. Defaults to admitting all
+    /// removals. See [`ProtectedMembers`] for a configurable policy.
+    fn admit_remove(&self, _sender: &Sender, _removed: &Credential) -> bool {
+        true
+    }
+
+    /// Called when an incoming commit was staged and passed all checks
+    /// required by the MLS specification, right before the [`StagedCommit`]
+    /// is returned. Returning an error rejects the commit with
+    /// [`StageCommitError::RejectedByPolicy`], which contains the returned
+    /// reason. Applications return their own errors with
+    /// [`PolicyRejection::application()`].
+    ///
+    /// This allows applications to enforce their own semantics on the
+    /// changes of a commit, e.g. that the last admin of a group can't be
+    /// removed. Defaults to admitting all commits.
+    fn validate_staged_commit(&self, _commit: &ReceivedCommitInfo) -> Result<(), PolicyRejection> {
+        Ok(())
+    }
+}
+
+impl ProcessingPolicy for () {}
+
+/// A [`ProcessingPolicy`] that restricts each external sender, identified by
+/// its signature key, to a set of proposal types.
+///
+/// External senders without an entry are only allowed to send Remove
+/// proposals, like with the default policy.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalSenderPermissions {
+    permissions: Vec<(SignaturePublicKey, Vec<ProposalType>)>,
+}
+
+impl ExternalSenderPermissions {
+    /// Creates a policy without any entries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the external sender with the given `signature_key` to send
+    /// proposals of the given `proposal_types`. Replaces a previous entry for
+    /// the same sender.
+    pub fn allow(
+        mut self,
+        signature_key: SignaturePublicKey,
+        proposal_types: impl IntoIterator<Item = ProposalType>,
+    ) -> Self {
+        self.permissions
+            .retain(|(existing_key, _)| existing_key != &signature_key);
+        self.permissions
+            .push((signature_key, proposal_types.into_iter().collect()));
+        self
+    }
+
+    /// Returns the proposal types the external sender with the given
+    /// `signature_key` is allowed to send, or `None` if there is no entry for
+    /// it.
+    pub fn allowed_proposal_types(
+        &self,
+        signature_key: &SignaturePublicKey,
+    ) -> Option<&[ProposalType]> {
+        self.permissions
+            .iter()
+            .find(|(existing_key, _)| existing_key == signature_key)
+            .map(|(_, proposal_types)| proposal_types.as_slice())
+    }
+}
+
+impl ProcessingPolicy for ExternalSenderPermissions {
+    fn admit_external_proposal(
+        &self,
+        external_sender: &ExternalSender,
+        proposal_type: ProposalType,
+    ) -> bool {
+        match self.allowed_proposal_types(external_sender.signature_key()) {
+            Some(proposal_types) => proposal_types.contains(&proposal_type),
+            None => proposal_type == ProposalType::Remove,
+        }
+    }
+}
+
+/// An admission token that a joiner presents in the authenticated data of its
+/// external commit, see [`AdmissionTokenPolicy`].
+///
+/// The token is bound to the group and epoch the joiner joins, so that it
+/// can't be replayed to join another group or a later epoch. The
+/// authenticated data is signed by the joiner, but it is not encrypted: the
+/// delivery service and the members that process the external commit can read
+/// the token. It doesn't become part of the group state, so members that join
+/// later and holders of a GroupInfo can't read it. Applications should still
+/// issue tokens for a single use or bind them to the credential of the joiner.
+#[derive(Debug, Clone, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsSize)]
+pub struct AdmissionToken {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    token: VLBytes,
+}
+
+impl AdmissionToken {
+    /// Creates an admission token for the given group and epoch.
+    pub fn new(group_id: GroupId, epoch: GroupEpoch, token: Vec<u8>) -> Self {
+        Self {
+            group_id,
+            epoch,
+            token: token.into(),
+        }
+    }
+
+    /// Returns the ID of the group the token is bound to.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the token is bound to.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the token issued by the application.
+    pub fn token(&self) -> &[u8] {
+        self.token.as_slice()
+    }
+}
+
+/// A [`ProcessingPolicy`] that only admits external commits that carry a
+/// valid [`AdmissionToken`] in their authenticated data.
+///
+/// This supports onboarding new members without Welcome messages: a member
+/// publishes a GroupInfo (see [`MlsGroup::export_onboarding_group_info()`]),
+/// and the application hands out admission tokens to the clients that may
+/// join. Joiners present their token with
+/// [`MlsGroup::join_with_admission_token()`]. External commits without an
+/// admission token for the current group and epoch are rejected. Otherwise,
+/// the function passed to [`AdmissionTokenPolicy::new()`] is called with the
+/// token and the credential of the joiner and decides whether the token is
+/// valid, e.g. by checking a signature or looking it up in a list of issued
+/// tokens.
+pub struct AdmissionTokenPolicy<F> {
+    verify_token: F,
+}
+
+impl<F: Fn(&[u8], &Credential) -> bool> AdmissionTokenPolicy<F> {
+    /// Creates a policy that admits external commits for which
+    /// `verify_token` returns `true`.
+    pub fn new(verify_token: F) -> Self {
+        Self { verify_token }
+    }
+}
+
+impl<F: Fn(&[u8], &Credential) -> bool> ProcessingPolicy for AdmissionTokenPolicy<F> {
+    fn admit_external_commit(&self, external_commit: &ExternalCommitInfo) -> bool {
+        let Ok(admission_token) =
+            AdmissionToken::tls_deserialize_exact(external_commit.authenticated_data())
+        else {
+            return false;
+        };
+        let group_context = external_commit.group_context();
+
+        admission_token.group_id() == group_context.group_id()
+            && admission_token.epoch() == group_context.epoch()
+            && (self.verify_token)(admission_token.token(), external_commit.credential())
+    }
+}
+
+/// A [`ProcessingPolicy`] that protects members, identified by their
+/// credential, from being removed, e.g. bots that provide a service to the
+/// group.
+///
+/// Remove proposals for a protected member are rejected unless the function
+/// passed to [`ProtectedMembers::new()`] authorizes them. It is called with the
+/// sender of the proposal and the credential of the protected member.
+///
+/// The policy is also a [`CommitPolicy`], such that the same rules apply to
+/// the commits the application creates. Other policies can be added by
+/// combining them in a tuple, e.g. `(protected_members, permissions)`.
+pub struct ProtectedMembers<F> {
+    protected: Vec<Credential>,
+    authorize: F,
+}
+
+impl<F: Fn(&Sender, &Credential) -> bool> ProtectedMembers<F> {
+    /// Creates a policy without protected members that uses `authorize` to
+    /// decide whether a protected member may be removed.
+    pub fn new(authorize: F) -> Self {
+        Self {
+            protected: Vec::new(),
+            authorize,
+        }
+    }
+
+    /// Protects the member with the given `credential` from being removed.
+    pub fn protect(mut self, credential: Credential) -> Self {
+        if !self.is_protected(&credential) {
+            self.protected.push(credential);
+        }
+        self
+    }
+
+    /// Returns `true` if the member with the given `credential` is protected.
+    pub fn is_protected(&self, credential: &Credential) -> bool {
+        self.protected.contains(credential)
+    }
+}
+
+impl<F: Fn(&Sender, &Credential) -> bool> ProcessingPolicy for ProtectedMembers<F> {
+    fn admit_remove(&self, sender: &Sender, removed: &Credential) -> bool {
+        !self.is_protected(removed) || (self.authorize)(sender, removed)
+    }
+}
+
+impl<F: Fn(&Sender, &Credential) -> bool> CommitPolicy for ProtectedMembers<F> {
+    fn admit_remove(&self, sender: &Sender, removed: &Credential) -> bool {
+        ProcessingPolicy::admit_remove(self, sender, removed)
+    }
+}
+
+/// Information about an external commit that is handed to
+/// [`ProcessingPolicy::admit_external_commit()`].
+#[derive(Debug, Clone, Copy)]
+pub struct ExternalCommitInfo<'a> {
+    pub(crate) leaf_node: &'a LeafNode,
+    pub(crate) authenticated_data: &'a [u8],
+    pub(crate) removed_leaf: Option<(LeafNodeIndex, &'a LeafNode)>,
+    pub(crate) group_context: &'a GroupContext,
+}
+
+impl<'a> ExternalCommitInfo<'a> {
+    /// Returns the credential of the joiner, as contained in the leaf node of
+    /// the commit's update path.
+    pub fn credential(&self) -> &'a Credential {
+        self.leaf_node.credential()
+    }
+
+    /// Returns the leaf node of the joiner, as contained in the commit's
+    /// update path.
+    pub fn leaf_node(&self) -> &'a LeafNode {
+        self.leaf_node
+    }
+
+    /// Returns the authenticated data of the external commit. Note that the
+    /// authenticated data of an external commit is not encrypted.
+    pub fn authenticated_data(&self) -> &'a [u8] {
+        self.authenticated_data
+    }
+
+    /// Returns the index and leaf node of the member that is removed by the
+    /// external commit, if any. This is the case when a member re-joins the
+    /// group ("resync").
+    pub fn removed_leaf(&self) -> Option<(LeafNodeIndex, &'a LeafNode)> {
+        self.removed_leaf
+    }
+
+    /// Returns the [`GroupContext`] that was published in the GroupInfo the
+    /// joiner used to create the external commit.
+    ///
+    /// Since an external commit has to be for the current epoch, this is the
+    /// group context of the current epoch of the processing group.
+    pub fn group_context(&self) -> &'a GroupContext {
+        self.group_context
+    }
+}
+
+/// Information about an incoming commit that is handed to
+/// [`ProcessingPolicy::validate_staged_commit()`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReceivedCommitInfo<'a> {
+    pub(crate) sender: &'a Sender,
+    pub(crate) authenticated_data: &'a [u8],
+    pub(crate) group: &'a PublicGroup,
+    pub(crate) staged_commit: &'a StagedCommit,
+}
+
+impl<'a> ReceivedCommitInfo<'a> {
+    /// Returns the sender of the commit.
+    pub fn sender(&self) -> &'a Sender {
+        self.sender
+    }
+
+    /// Returns the authenticated data of the commit.
+    pub fn authenticated_data(&self) -> &'a [u8] {
+        self.authenticated_data
+    }
+
+    /// Returns the public state of the group in the current epoch, i.e.
+    /// before the commit is merged. This can be used to look up the members
+    /// that are affected by the commit.
+    pub fn group(&self) -> &'a PublicGroup {
+        self.group
+    }
+
+    /// Returns the staged commit with the full set of changes, including the
+    /// committed proposals and the group context of the next epoch.
+    pub fn staged_commit(&self) -> &'a StagedCommit {
+        self.staged_commit
+    }
+}
+
+/// A policy that selects the proposals that are considered for inclusion when
+/// creating a commit. A policy is passed to
+/// [`MlsGroup::commit_to_pending_proposals_with_policy()`] or to
+/// `CommitBuilder::build_with_policy()`.
+///
+/// The selected proposals are still filtered and validated as required by the
+/// MLS specification, e.g. only the most recent Update for a leaf is included.
+/// Proposals that are not selected are reported with
+/// [`ProposalFilterReason::ApplicationPolicy`]. Note that pending proposals
+/// that are not committed are discarded when the commit is merged, like all
+/// proposals of the epoch.
+pub trait CommitPolicy {
+    /// Called with the candidate proposals, i.e. the pending proposals followed
+    /// by the proposals passed to the commit builder, from oldest to newest.
+    /// Returns the proposals to consider, in the order in which they should be
+    /// considered. Proposals that are not in `candidates` are ignored, and
+    /// proposals that are returned more than once are considered once. Update
+    /// proposals are always considered from oldest to newest, such that the
+    /// most recent Update of a member supersedes the older ones.
+    ///
+    /// Defaults to considering all candidates in their original order.
+    fn select_proposals(&mut self, candidates: Vec<QueuedProposal>) -> Vec<QueuedProposal> {
+        candidates
+    }
+
+    /// Called with the `sender` and the credential of the removed member for
+    /// each selected Remove proposal, like
+    /// [`ProcessingPolicy::admit_remove()`] for incoming commits. Returning
+    /// `false` leaves out a pending proposal of another member, which is
+    /// reported with [`ProposalFilterReason::ApplicationPolicy`], and fails
+    /// the commit with [`ProposalValidationError::ProtectedMemberRemoval`] if
+    /// the proposal is the committer's own.
+    ///
+    /// Defaults to admitting all removals.
+    fn admit_remove(&self, _sender: &Sender, _removed: &Credential) -> bool {
+        true
+    }
+}
+
+impl CommitPolicy for () {}
+
+// Policies are combined by putting them in a tuple. A combination admits what
+// all of its policies admit. Proposals are selected by each policy in turn.
+macro_rules! impl_policy_tuple {
+    ($($policy:ident),+) => {
+        impl<$($policy: ProcessingPolicy),+> ProcessingPolicy for ($($policy,)+) {
+            fn admit_external_commit(&self, external_commit: &ExternalCommitInfo) -> bool {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $($policy.admit_external_commit(external_commit))&&+
+            }
+
+            fn accept_group_id(&self, group_id: &GroupId) -> bool {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $($policy.accept_group_id(group_id))&&+
+            }
+
+            fn validate_group_info(
+                &self,
+                group_context: &GroupContext,
+                extensions: &Extensions,
+            ) -> Result<(), PolicyRejection> {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $($policy.validate_group_info(group_context, extensions)?;)+
+                Ok(())
+            }
+
+            fn admit_external_proposal(
+                &self,
+                external_sender: &ExternalSender,
+                proposal_type: ProposalType,
+            ) -> bool {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $($policy.admit_external_proposal(external_sender, proposal_type))&&+
+            }
+
+            fn admit_remove(&self, sender: &Sender, removed: &Credential) -> bool {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $($policy.admit_remove(sender, removed))&&+
+            }
+
+            fn validate_staged_commit(
+                &self,
+                commit: &ReceivedCommitInfo,
+            ) -> Result<(), PolicyRejection> {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $($policy.validate_staged_commit(commit)?;)+
+                Ok(())
+            }
+        }
+
+        impl<$($policy: CommitPolicy),+> CommitPolicy for ($($policy,)+) {
+            fn select_proposals(
+                &mut self,
+                candidates: Vec<QueuedProposal>,
+            ) -> Vec<QueuedProposal> {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $(let candidates = $policy.select_proposals(candidates);)+
+                candidates
+            }
+
+            fn admit_remove(&self, sender: &Sender, removed: &Credential) -> bool {
+                #[allow(non_snake_case)]
+                let ($($policy,)+) = self;
+                $(CommitPolicy::admit_remove($policy, sender, removed))&&+
+            }
+        }
+    };
+}
+
+impl_policy_tuple!(A, B);
+impl_policy_tuple!(A, B, C);
+impl_policy_tuple!(A, B, C, D);
+
+/// A configurable [`CommitPolicy`] for common selection strategies.
+#[derive(Debug, Clone, Default)]
+pub struct ProposalSelection {
+    removes_first: bool,
+    max_adds: Option<usize>,
+    excluded_senders: Vec<Sender>,
+}
+
+impl ProposalSelection {
+    /// Creates a policy that selects all proposals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Considers Remove proposals before all other proposals.
+    pub fn removes_first(mut self) -> Self {
+        self.removes_first = true;
+        self
+    }
+
+    /// Selects at most `max_adds` Add proposals. The oldest ones are selected.
+    pub fn max_adds(mut self, max_adds: usize) -> Self {
+        self.max_adds = Some(max_adds);
+        self
+    }
+
+    /// Doesn't select any proposals of the given `sender`.
+    pub fn exclude_sender(mut self, sender: Sender) -> Self {
+        self.excluded_senders.push(sender);
+        self
+    }
+}
+
+impl CommitPolicy for ProposalSelection {
+    fn select_proposals(&mut self, candidates: Vec<QueuedProposal>) -> Vec<QueuedProposal> {
+        let mut adds = 0;
+        let mut selected: Vec<QueuedProposal> = candidates
+            .into_iter()
+            .filter(|proposal| !self.excluded_senders.contains(proposal.sender()))
+            .filter(|proposal| {
+                if proposal.proposal().is_type(ProposalType::Add) {
+                    adds += 1;
+                    match self.max_adds {
+                        Some(max_adds) => adds <= max_adds,
+                        None => true,
+                    }
+                } else {
+                    true
+                }
+            })
+            .collect();
+        if self.removes_first {
+            // The sort is stable, so the order within both groups is kept.
+            selected.sort_by_key(|proposal| !proposal.proposal().is_type(ProposalType::Remove));
+        }
+        selected
+    }
+}
@@ -95,6 +95,11 @@ impl TempBuilderPG1 {
             required_proposals,
             required_credentials,
         ));
+        capabilities.validate_for_leaf_node(
+            self.ciphersuite,
+            self.credential_with_key.credential.credential_type(),
+            &self.leaf_node_extensions,
+        )?;
         let (treesync, commit_secret, leaf_keypair) = TreeSync::new(
             provider,
             signer,
@@ -15,7 +15,7 @@ use crate::{
     error::LibraryError,
     extensions::Extensions,
     framing::{mls_auth_content::AuthenticatedContent, public_message::InterimTranscriptHashInput},
-    group::GroupContext,
+    group::{GroupContext, Member},
     messages::{proposals::AddProposal, ConfirmationTag, EncryptedGroupSecrets},
     schedule::{psk::PreSharedKeyId, CommitSecret, JoinerSecret},
     treesync::{
@@ -81,7 +81,7 @@ impl<'a> PublicGroupDiff<'a> {
     pub(crate) fn encrypt_group_secrets(
         &self,
         joiner_secret: &JoinerSecret,
-        invited_members: Vec<(LeafNodeIndex, AddProposal)>,
+        invited_members: &[(LeafNodeIndex, &AddProposal)],
         plain_path_option: Option<&[PlainUpdatePathNode]>,
         presharedkeys: &[PreSharedKeyId],
         encrypted_group_info: &[u8],
@@ -104,6 +104,29 @@ impl<'a> PublicGroupDiff<'a> {
         self.diff.tree_size()
     }
 
+    /// See [`TreeSyncDiff::path_recipient_counts()`].
+    pub(crate) fn path_recipient_counts(
+        &self,
+        leaf_index: LeafNodeIndex,
+        exclusion_list: &HashSet<&LeafNodeIndex>,
+    ) -> Vec<usize> {
+        self.diff.path_recipient_counts(leaf_index, exclusion_list)
+    }
+
+    /// Returns the members of the group resulting from merging this diff.
+    pub(crate) fn members(&self) -> impl Iterator<Item = Member> + '_ {
+        self.diff
+            .full_leaves_with_index()
+            .map(|(index, leaf_node)| {
+                Member::new(
+                    index,
+                    leaf_node.encryption_key().as_slice().to_vec(),
+                    leaf_node.signature_key().as_slice().to_vec(),
+                    leaf_node.credential().clone(),
+                )
+            })
+    }
+
     /// Returns a vector of all nodes in the tree resulting from merging this
     /// diff.
     pub(crate) fn export_ratchet_tree(&self) -> RatchetTree {
@@ -251,4 +274,17 @@ impl StagedPublicGroupDiff {
     pub(crate) fn group_context(&self) -> &GroupContext {
         &self.group_context
     }
+
+    /// Get the [`ConfirmationTag`] of the staged commit.
+    pub(crate) fn confirmation_tag(&self) -> &ConfirmationTag {
+        &self.confirmation_tag
+    }
+
+    /// Returns an iterator over the leaves that are changed by this diff,
+    /// where `None` indicates a blanked leaf.
+    pub(crate) fn changed_leaves(
+        &self,
+    ) -> impl Iterator<Item = (LeafNodeIndex, Option<&LeafNode>)> {
+        self.staged_diff.changed_leaves()
+    }
 }
@@ -13,16 +13,16 @@ use super::*;
 
 /// This struct contain the return values of the `apply_proposals()` function
 #[derive(Debug)]
-pub(crate) struct ApplyProposalsValues {
+pub(crate) struct ApplyProposalsValues<'a> {
     pub(crate) path_required: bool,
     pub(crate) self_removed: bool,
-    pub(crate) invitation_list: Vec<(LeafNodeIndex, AddProposal)>,
+    pub(crate) invitation_list: Vec<(LeafNodeIndex, &'a AddProposal)>,
     pub(crate) presharedkeys: Vec<PreSharedKeyId>,
     pub(crate) external_init_proposal_option: Option<ExternalInitProposal>,
     pub(crate) extensions: Option<Extensions>,
 }
 
-impl ApplyProposalsValues {
+impl ApplyProposalsValues<'_> {
     /// This function creates a `HashSet` of node indexes of the new nodes that
     /// were added to the tree. The `HashSet` will be querried by the
     /// `resolve()` function to filter out those nodes from the resolution.
@@ -50,11 +50,11 @@ impl ApplyProposalsValues {
 ///
 /// Returns an error if the proposals have not been validated before.
 impl PublicGroupDiff<'_> {
-    pub(crate) fn apply_proposals(
+    pub(crate) fn apply_proposals<'a>(
         &mut self,
-        proposal_queue: &ProposalQueue,
+        proposal_queue: &'a ProposalQueue,
         own_leaf_index: impl Into<Option<LeafNodeIndex>>,
-    ) -> Result<ApplyProposalsValues, LibraryError> {
+    ) -> Result<ApplyProposalsValues<'a>, LibraryError> {
         log::debug!("Applying proposal");
         let mut self_removed = false;
 
@@ -107,7 +107,7 @@ impl PublicGroupDiff<'_> {
         }
 
         // Process adds
-        let add_proposals = proposal_queue
+        let add_proposals: Vec<&AddProposal> = proposal_queue
             .filtered_by_type(ProposalType::Add)
             .filter_map(|queued_proposal| {
                 if let Proposal::Add(add_proposal) = queued_proposal.proposal() {
@@ -115,20 +115,21 @@ impl PublicGroupDiff<'_> {
                 } else {
                     None
                 }
-            });
+            })
+            .collect();
 
-        // Extract KeyPackages from proposals
-        let mut invitation_list = Vec::new();
-        for add_proposal in add_proposals {
-            // XXX: There are too many clones here.
-            let leaf_node = add_proposal.key_package.leaf_node();
-            let leaf_index = self
-                .diff
-                .add_leaf(leaf_node.clone())
-                // TODO #810
-                .map_err(|_| LibraryError::custom("Tree full: cannot add more members"))?;
-            invitation_list.push((leaf_index, add_proposal.clone()))
-        }
+        // Add all new leaves at once, so that the tree is only searched for
+        // free leaves once.
+        let leaf_indices = self
+            .diff
+            .add_leaves(
+                add_proposals
+                    .iter()
+                    .map(|add_proposal| add_proposal.key_package.leaf_node()),
+            )
+            // TODO #810
+            .map_err(|_| LibraryError::custom("Tree full: cannot add more members"))?;
+        let invitation_list = leaf_indices.into_iter().zip(add_proposals).collect();
 
         // Process PSK proposals
         let presharedkeys: Vec<PreSharedKeyId> = proposal_queue
@@ -21,34 +21,39 @@ use self::{
     errors::CreationFromExternalError,
 };
 use super::{
+    add_check::check_adds,
     proposal_store::{ProposalStore, QueuedProposal},
-    GroupContext, GroupId, Member, StagedCommit,
+    tree_preview::TreePreview,
+    AddCheckReport, CapabilityReport, GroupContext, GroupId, GroupStateSummary, Member, MemberId,
+    StagedCommit,
 };
 #[cfg(test)]
 use crate::treesync::{node::parent_node::PlainUpdatePathNode, treekem::UpdatePathNode};
 use crate::{
     binary_tree::{
-        array_representation::{direct_path, TreeSize},
-        LeafNodeIndex,
+        array_representation::{direct_path, TreeSize, MIN_TREE_SIZE},
+        LeafNodeIndex, ParentNodeIndex, TreeNodeIndex,
     },
     ciphersuite::{hash_ref::ProposalRef, signable::Verifiable},
     error::LibraryError,
     extensions::RequiredCapabilitiesExtension,
     framing::InterimTranscriptHashInput,
+    key_packages::KeyPackage,
     messages::{
         group_info::{GroupInfo, VerifiableGroupInfo},
         proposals::{Proposal, ProposalOrRefType, ProposalType},
         ConfirmationTag, PathSecret,
     },
     schedule::CommitSecret,
-    storage::PublicStorageProvider,
+    storage::{PublicStorageProvider, StorageReader},
     treesync::{
         errors::{DerivePathError, TreeSyncFromNodesError},
         node::{
             encryption_keys::{EncryptionKey, EncryptionKeyPair},
             leaf_node::LeafNode,
+            parent_node::ParentNode,
         },
-        RatchetTree, RatchetTreeIn, TreeSync,
+        ParentHashFinding, RatchetTree, RatchetTreeIn, TreeSync,
     },
     versions::ProtocolVersion,
 };
@@ -108,6 +113,24 @@ impl PublicGroup {
         })
     }
 
+    /// Create a [`PublicGroup`] from the state exported by another client, e.g.
+    /// by the primary device of a read-only replica.
+    #[cfg(feature = "replica-sync")]
+    pub(crate) fn from_parts(
+        treesync: TreeSync,
+        group_context: GroupContext,
+        interim_transcript_hash: Vec<u8>,
+        confirmation_tag: ConfirmationTag,
+    ) -> Self {
+        PublicGroup {
+            treesync,
+            proposal_store: ProposalStore::new(),
+            group_context,
+            interim_transcript_hash,
+            confirmation_tag,
+        }
+    }
+
     /// Create a [`PublicGroup`] instance to start tracking an existing MLS group.
     ///
     /// This function performs basic validation checks and returns an error if
@@ -230,7 +253,7 @@ impl PublicGroup {
             return Err(CreationFromExternalError::TreeHashMismatch);
         }
 
-        if group_info.group_context().protocol_version() != ProtocolVersion::Mls10 {
+        if !group_info.group_context().protocol_version().is_supported() {
             return Err(CreationFromExternalError::UnsupportedMlsVersion);
         }
 
@@ -371,6 +394,22 @@ impl PublicGroup {
         self.treesync().full_leave_members()
     }
 
+    /// Returns the [`MemberId`] of the member at the given leaf index. Returns
+    /// `None` if the leaf is blank or outside of the tree.
+    pub fn member_id(&self, leaf_index: LeafNodeIndex) -> Option<MemberId> {
+        self.leaf(leaf_index)
+            .map(|leaf_node| MemberId::from_signature_key(leaf_node.signature_key().as_slice()))
+    }
+
+    /// Returns the current leaf index of the member with the given
+    /// [`MemberId`]. Returns `None` if there is no such member in the group.
+    pub fn member_index(&self, member_id: &MemberId) -> Option<LeafNodeIndex> {
+        self.treesync()
+            .full_leaves_with_index()
+            .find(|(_, leaf_node)| leaf_node.signature_key().as_slice() == member_id.as_slice())
+            .map(|(index, _)| index)
+    }
+
     /// Export the nodes of the public tree.
     pub fn export_ratchet_tree(&self) -> RatchetTree {
         self.treesync().export_ratchet_tree()
@@ -429,13 +468,60 @@ impl PublicGroup {
         &self.group_context
     }
 
+    /// Computes a [`GroupStateSummary`] of the current state of the group.
+    pub fn state_summary(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<GroupStateSummary, LibraryError> {
+        GroupStateSummary::new(crypto, self.ciphersuite(), &self.group_context)
+    }
+
+    /// Checks whether the `key_packages` could be added to the group.
+    ///
+    /// The returned [`CapabilityReport`] contains the capabilities shared by
+    /// all members and all compatible candidates, as well as the capabilities
+    /// that each incompatible candidate is missing.
+    pub fn capability_report(&self, key_packages: &[KeyPackage]) -> CapabilityReport {
+        CapabilityReport::new(self, key_packages)
+    }
+
+    /// Runs the checks an Add commit would perform on each of the
+    /// `key_packages` without modifying the group. See [`MlsGroup::can_add()`].
+    pub fn can_add(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        key_packages: &[KeyPackage],
+    ) -> Vec<AddCheckReport> {
+        check_adds(crypto, self, key_packages)
+    }
+
+    /// Re-runs the complete parent hash validation, including the checks on
+    /// the position of unmerged leaves, over the current tree.
+    ///
+    /// Unlike the validation performed when a tree is imported, this doesn't
+    /// stop at the first problem but returns a [`ParentHashFinding`] for every
+    /// problematic parent node. An empty list means the tree is valid.
+    pub fn verify_parent_hashes(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<Vec<ParentHashFinding>, LibraryError> {
+        self.treesync()
+            .audit_parent_hashes(crypto, self.ciphersuite())
+    }
+
+    /// Returns a [`TreePreview`] to apply hypothetical changes to a copy of
+    /// the ratchet tree.
+    pub fn tree_preview(&self) -> TreePreview<'_> {
+        TreePreview::new(self)
+    }
+
     /// Get the required capabilities.
     pub fn required_capabilities(&self) -> Option<&RequiredCapabilitiesExtension> {
         self.group_context.required_capabilities()
     }
 
     /// Get treesync.
-    fn treesync(&self) -> &TreeSync {
+    pub(crate) fn treesync(&self) -> &TreeSync {
         &self.treesync
     }
 
@@ -450,12 +536,87 @@ impl PublicGroup {
         self.treesync().leaf(leaf_index)
     }
 
+    /// Return a reference to the parent node at the given `ParentNodeIndex` or
+    /// `None` if the node is blank or outside of the tree.
+    pub fn parent(&self, parent_index: ParentNodeIndex) -> Option<&ParentNode> {
+        self.treesync().parent(parent_index)
+    }
+
+    /// Returns `true` if the node at the given `TreeNodeIndex` is blank. Nodes
+    /// outside of the tree are considered blank.
+    pub fn is_blank(&self, node_index: TreeNodeIndex) -> bool {
+        match node_index {
+            TreeNodeIndex::Leaf(leaf_index) => self.leaf(leaf_index).is_none(),
+            TreeNodeIndex::Parent(parent_index) => self.parent(parent_index).is_none(),
+        }
+    }
+
+    /// Returns the direct path of the leaf at the given `LeafNodeIndex`,
+    /// beginning with the leaf's parent and ending with the root. Returns `None`
+    /// if the leaf is outside of the tree.
+    pub fn direct_path(&self, leaf_index: LeafNodeIndex) -> Option<Vec<ParentNodeIndex>> {
+        self.treesync()
+            .is_leaf_in_tree(leaf_index)
+            .then(|| self.treesync().empty_diff().direct_path(leaf_index))
+    }
+
+    /// Returns the direct path of the leaf at the given `LeafNodeIndex`
+    /// without the nodes whose copath child has an empty resolution. This is
+    /// the path for which a member sends path secrets when committing. Returns
+    /// `None` if the leaf is outside of the tree.
+    pub fn filtered_direct_path(&self, leaf_index: LeafNodeIndex) -> Option<Vec<ParentNodeIndex>> {
+        self.treesync().is_leaf_in_tree(leaf_index).then(|| {
+            self.treesync()
+                .empty_diff()
+                .filtered_direct_path(leaf_index)
+        })
+    }
+
+    /// Returns the copath of the leaf at the given `LeafNodeIndex`, beginning
+    /// with the leaf's sibling. Returns `None` if the leaf is outside of the
+    /// tree.
+    pub fn copath(&self, leaf_index: LeafNodeIndex) -> Option<Vec<TreeNodeIndex>> {
+        if !self.treesync().is_leaf_in_tree(leaf_index) {
+            return None;
+        }
+        // If the leaf is the only node in the tree, there's no copath.
+        if self.tree_size().leaf_count() == MIN_TREE_SIZE {
+            return Some(vec![]);
+        }
+        Some(self.treesync().empty_diff().copath(leaf_index))
+    }
+
+    /// Returns the resolutions of the nodes in the copath of the leaf at the
+    /// given `LeafNodeIndex`, beginning with the resolution of the leaf's
+    /// sibling. The resolution of a copath node is the set of nodes whose
+    /// owners can decrypt a path secret encrypted to that copath node. Returns
+    /// `None` if the leaf is outside of the tree.
+    pub fn copath_resolutions(&self, leaf_index: LeafNodeIndex) -> Option<Vec<Vec<TreeNodeIndex>>> {
+        if !self.treesync().is_leaf_in_tree(leaf_index) {
+            return None;
+        }
+        let resolutions = self
+            .treesync()
+            .empty_diff()
+            .copath_resolutions(leaf_index)
+            .into_iter()
+            .map(|resolution| resolution.into_iter().map(|(index, _)| index).collect())
+            .collect();
+        Some(resolutions)
+    }
+
     /// Returns the tree size
     pub(crate) fn tree_size(&self) -> TreeSize {
         self.treesync().tree_size()
     }
 
-    fn interim_transcript_hash(&self) -> &[u8] {
+    /// Get the confirmed transcript hash of the current epoch.
+    pub fn confirmed_transcript_hash(&self) -> &[u8] {
+        self.group_context.confirmed_transcript_hash()
+    }
+
+    /// Get the interim transcript hash of the current epoch.
+    pub fn interim_transcript_hash(&self) -> &[u8] {
         &self.interim_transcript_hash
     }
 
@@ -502,29 +663,49 @@ impl PublicGroup {
         storage: &Storage,
         group_id: &GroupId,
     ) -> Result<Option<Self>, Storage::Error> {
-        let treesync = storage.tree(group_id)?;
-        let proposals: Vec<(ProposalRef, QueuedProposal)> = storage.queued_proposals(group_id)?;
-        let group_context = storage.group_context(group_id)?;
-        let interim_transcript_hash: Option<InterimTranscriptHash> =
-            storage.interim_transcript_hash(group_id)?;
-        let confirmation_tag = storage.confirmation_tag(group_id)?;
-        let mut proposal_store = ProposalStore::new();
+        Ok(Self::from_stored(
+            storage.tree(group_id)?,
+            storage.queued_proposals(group_id)?,
+            storage.group_context(group_id)?,
+            storage.interim_transcript_hash(group_id)?,
+            storage.confirmation_tag(group_id)?,
+        ))
+    }
 
+    /// Loads the [`PublicGroup`] corresponding to a [`GroupId`] from a
+    /// read-only handle to the storage of a member.
+    pub(crate) fn load_from_reader<Storage: StorageReader>(
+        storage: &Storage,
+        group_id: &GroupId,
+    ) -> Result<Option<Self>, Storage::Error> {
+        Ok(Self::from_stored(
+            storage.tree(group_id)?,
+            storage.queued_proposals(group_id)?,
+            storage.group_context(group_id)?,
+            storage.interim_transcript_hash(group_id)?,
+            storage.confirmation_tag(group_id)?,
+        ))
+    }
+
+    fn from_stored(
+        treesync: Option<TreeSync>,
+        proposals: Vec<(ProposalRef, QueuedProposal)>,
+        group_context: Option<GroupContext>,
+        interim_transcript_hash: Option<InterimTranscriptHash>,
+        confirmation_tag: Option<ConfirmationTag>,
+    ) -> Option<Self> {
+        let mut proposal_store = ProposalStore::new();
         for (_ref, proposal) in proposals {
             proposal_store.add(proposal);
         }
 
-        let build = || -> Option<Self> {
-            Some(Self {
-                treesync: treesync?,
-                proposal_store,
-                group_context: group_context?,
-                interim_transcript_hash: interim_transcript_hash?.0,
-                confirmation_tag: confirmation_tag?,
-            })
-        };
-
-        Ok(build())
+        Some(Self {
+            treesync: treesync?,
+            proposal_store,
+            group_context: group_context?,
+            interim_transcript_hash: interim_transcript_hash?.0,
+            confirmation_tag: confirmation_tag?,
+        })
     }
 
     /// Returns a reference to the [`ProposalStore`].
@@ -8,15 +8,17 @@ use crate::{
     ciphersuite::OpenMlsSignaturePublicKey,
     credentials::CredentialWithKey,
     error::LibraryError,
+    extensions::SenderExtensionIndex,
     framing::{
         mls_content::FramedContentBody, ApplicationMessage, DecryptedMessage, ProcessedMessage,
         ProcessedMessageContent, ProtocolMessage, Sender, SenderContext, UnverifiedMessage,
     },
     group::{
-        errors::ValidationError, mls_group::errors::ProcessMessageError,
-        past_secrets::MessageSecretsStore, proposal_store::QueuedProposal,
+        capability_report::missing_capabilities, errors::ValidationError,
+        mls_group::errors::ProcessMessageError, past_secrets::MessageSecretsStore,
+        proposal_store::QueuedProposal, ProcessingPolicy,
     },
-    messages::proposals::Proposal,
+    messages::proposals::{Proposal, ProposalType},
 };
 
 use super::PublicGroup;
@@ -149,6 +151,17 @@ impl PublicGroup {
         &self,
         crypto: &impl OpenMlsCrypto,
         message: impl Into<ProtocolMessage>,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.process_message_with_policy(crypto, message, &())
+    }
+
+    /// Like [`Self::process_message()`], but additionally consults the given
+    /// [`ProcessingPolicy`] during validation.
+    pub fn process_message_with_policy(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        message: impl Into<ProtocolMessage>,
+        policy: &impl ProcessingPolicy,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         let protocol_message = message.into();
         // Checks the following semantic validation:
@@ -176,7 +189,7 @@ impl PublicGroup {
         let unverified_message = self
             .parse_message(decrypted_message, None)
             .map_err(ProcessMessageError::from)?;
-        self.process_unverified_message(crypto, unverified_message)
+        self.process_unverified_message(crypto, unverified_message, policy)
     }
 }
 
@@ -211,13 +224,16 @@ impl PublicGroup {
         &self,
         crypto: &impl OpenMlsCrypto,
         unverified_message: UnverifiedMessage,
+        policy: &impl ProcessingPolicy,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         // Checks the following semantic validation:
         //  - ValSem010
         //  - ValSem246 (as part of ValSem010)
         //  - https://validation.openmls.tech/#valn1203
+        let generation = unverified_message.generation();
         let (content, credential) =
             unverified_message.verify(self.ciphersuite(), crypto, self.version())?;
+        let wire_format = content.wire_format();
 
         match content.sender() {
             Sender::Member(_) | Sender::NewMemberCommit | Sender::NewMemberProposal => {
@@ -230,7 +246,12 @@ impl PublicGroup {
                             application_message.as_slice().to_owned(),
                         ))
                     }
-                    FramedContentBody::Proposal(_) => {
+                    FramedContentBody::Proposal(proposal) => {
+                        if let Proposal::Remove(remove_proposal) = proposal {
+                            if !self.admits_remove(&sender, remove_proposal, policy) {
+                                return Err(ProcessMessageError::ProtectedMemberRemoval);
+                            }
+                        }
                         let proposal = Box::new(QueuedProposal::from_authenticated_content_by_ref(
                             self.ciphersuite(),
                             crypto,
@@ -243,7 +264,7 @@ impl PublicGroup {
                         }
                     }
                     FramedContentBody::Commit(_) => {
-                        let staged_commit = self.stage_commit(&content, crypto)?;
+                        let staged_commit = self.stage_commit(&content, crypto, policy)?;
                         ProcessedMessageContent::StagedCommitMessage(Box::new(staged_commit))
                     }
                 };
@@ -255,16 +276,25 @@ impl PublicGroup {
                     authenticated_data,
                     content,
                     credential,
+                    wire_format,
+                    generation,
                 ))
             }
-            Sender::External(_) => {
+            Sender::External(sender_index) => {
+                let sender_index = *sender_index;
                 let sender = content.sender().clone();
                 let data = content.authenticated_data().to_owned();
                 match content.content() {
                     FramedContentBody::Application(_) => {
                         Err(ProcessMessageError::UnauthorizedExternalApplicationMessage)
                     }
-                    FramedContentBody::Proposal(Proposal::Remove(_)) => {
+                    FramedContentBody::Proposal(proposal) => {
+                        self.validate_external_proposal(sender_index, proposal, policy)?;
+                        if let Proposal::Remove(remove_proposal) = proposal {
+                            if !self.admits_remove(&sender, remove_proposal, policy) {
+                                return Err(ProcessMessageError::ProtectedMemberRemoval);
+                            }
+                        }
                         let content = ProcessedMessageContent::ProposalMessage(Box::new(
                             QueuedProposal::from_authenticated_content_by_ref(
                                 self.ciphersuite(),
@@ -279,15 +309,50 @@ impl PublicGroup {
                             data,
                             content,
                             credential,
+                            wire_format,
+                            generation,
                         ))
                     }
-                    // TODO #151/#106
-                    FramedContentBody::Proposal(_) => {
-                        Err(ProcessMessageError::UnsupportedProposalType)
-                    }
                     FramedContentBody::Commit(_) => unimplemented!(),
                 }
             }
         }
     }
+
+    /// Checks that the external sender at `sender_index` may send the
+    /// `proposal` and, for Add proposals, that the key package is compatible
+    /// with the group.
+    pub(crate) fn validate_external_proposal(
+        &self,
+        sender_index: SenderExtensionIndex,
+        proposal: &Proposal,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<(), ProcessMessageError> {
+        let proposal_type = proposal.proposal_type();
+        if matches!(
+            proposal_type,
+            ProposalType::Update | ProposalType::ExternalInit
+        ) {
+            return Err(ProcessMessageError::UnsupportedProposalType);
+        }
+
+        let external_sender = self
+            .group_context()
+            .extensions()
+            .external_senders()
+            .and_then(|external_senders| external_senders.get(sender_index.index()))
+            .ok_or(ValidationError::UnauthorizedExternalSender)?;
+        if !policy.admit_external_proposal(external_sender, proposal_type) {
+            return Err(ProcessMessageError::UnauthorizedExternalProposal);
+        }
+
+        if let Proposal::Add(add_proposal) = proposal {
+            let missing = missing_capabilities(self, add_proposal.key_package());
+            if !missing.is_empty() {
+                return Err(ProcessMessageError::IncompatibleExternalAdd(missing));
+            }
+        }
+
+        Ok(())
+    }
 }
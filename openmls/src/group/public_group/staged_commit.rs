@@ -2,7 +2,8 @@ use super::{super::errors::*, *};
 use crate::{
     framing::{mls_auth_content::AuthenticatedContent, mls_content::FramedContentBody, Sender},
     group::{
-        mls_group::staged_commit::StagedCommitState, proposal_store::ProposalQueue, StagedCommit,
+        mls_group::staged_commit::StagedCommitState, proposal_store::ProposalQueue,
+        ExternalCommitInfo, ProcessingPolicy, ReceivedCommitInfo, StagedCommit,
     },
     messages::{proposals::ProposalOrRef, Commit},
 };
@@ -43,6 +44,7 @@ impl PublicGroup {
         &self,
         mls_content: &'a AuthenticatedContent,
         crypto: &impl OpenMlsCrypto,
+        policy: &impl ProcessingPolicy,
     ) -> Result<(&'a Commit, ProposalQueue, LeafNodeIndex), StageCommitError> {
         let ciphersuite = self.ciphersuite();
 
@@ -134,6 +136,8 @@ impl PublicGroup {
         // ValSem107
         // ValSem108
         self.validate_remove_proposals(&proposal_queue)?;
+        // Let the application protect members from being removed.
+        self.validate_remove_policy(&proposal_queue, policy)?;
         // ValSem113: All Proposals: The proposal type must be supported by all
         // members of the group
         self.validate_proposal_type_support(&proposal_queue)?;
@@ -144,6 +148,7 @@ impl PublicGroup {
         // ValSem402
         // ValSem403
         self.validate_pre_shared_key_proposals(&proposal_queue)?;
+        self.validate_reinit_proposals(&proposal_queue)?;
 
         match sender {
             Sender::Member(leaf_index) => {
@@ -167,6 +172,14 @@ impl PublicGroup {
                 // ValSem241: External Commit, inline Proposals: There MUST be at most one ExternalInit proposal.
                 // ValSem242: External Commit must only cover inline proposal in allowlist (ExternalInit, Remove, PreSharedKey)
                 self.validate_external_commit(&proposal_queue)?;
+
+                // Let the application decide whether to admit the joiner.
+                self.validate_external_commit_policy(
+                    commit,
+                    mls_content.authenticated_data(),
+                    &proposal_queue,
+                    policy,
+                )?;
             }
         }
 
@@ -191,6 +204,59 @@ impl PublicGroup {
         Ok((commit, proposal_queue, sender_index))
     }
 
+    // Check the external commit against the application's admission policy.
+    fn validate_external_commit_policy(
+        &self,
+        commit: &Commit,
+        authenticated_data: &[u8],
+        proposal_queue: &ProposalQueue,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<(), ExternalCommitValidationError> {
+        let leaf_node = commit
+            .path
+            .as_ref()
+            .ok_or(ExternalCommitValidationError::NoPath)?
+            .leaf_node();
+        let removed_leaf = proposal_queue
+            .remove_proposals()
+            .find_map(|remove_proposal| {
+                let removed = remove_proposal.remove_proposal().removed();
+                self.leaf(removed).map(|leaf_node| (removed, leaf_node))
+            });
+        let external_commit = ExternalCommitInfo {
+            leaf_node,
+            authenticated_data,
+            removed_leaf,
+            group_context: self.group_context(),
+        };
+
+        if !policy.admit_external_commit(&external_commit) {
+            return Err(ExternalCommitValidationError::RejectedByPolicy);
+        }
+
+        Ok(())
+    }
+
+    // Check the staged commit against the application's semantic validation.
+    pub(crate) fn validate_staged_commit_policy(
+        &self,
+        mls_content: &AuthenticatedContent,
+        staged_commit: StagedCommit,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<StagedCommit, StageCommitError> {
+        let commit = ReceivedCommitInfo {
+            sender: mls_content.sender(),
+            authenticated_data: mls_content.authenticated_data(),
+            group: self,
+            staged_commit: &staged_commit,
+        };
+        policy
+            .validate_staged_commit(&commit)
+            .map_err(StageCommitError::RejectedByPolicy)?;
+
+        Ok(staged_commit)
+    }
+
     // Check that no external init proposal occurs. Needed only for regular commits.
     // [valn0310](https://validation.openmls.tech/#valn0310)
     fn validate_no_external_init_proposals(
@@ -249,8 +315,10 @@ impl PublicGroup {
         &self,
         mls_content: &AuthenticatedContent,
         crypto: &impl OpenMlsCrypto,
+        policy: &impl ProcessingPolicy,
     ) -> Result<StagedCommit, StageCommitError> {
-        let (commit, proposal_queue, sender_index) = self.validate_commit(mls_content, crypto)?;
+        let (commit, proposal_queue, sender_index) =
+            self.validate_commit(mls_content, crypto, policy)?;
 
         let staged_diff = self.stage_diff(mls_content, &proposal_queue, sender_index, crypto)?;
         let staged_state = PublicStagedCommitState {
@@ -259,8 +327,9 @@ impl PublicGroup {
         };
 
         let staged_commit_state = StagedCommitState::PublicState(Box::new(staged_state));
+        let staged_commit = StagedCommit::new(proposal_queue, staged_commit_state);
 
-        Ok(StagedCommit::new(proposal_queue, staged_commit_state))
+        self.validate_staged_commit_policy(mls_content, staged_commit, policy)
     }
 
     fn stage_diff(
@@ -1,16 +1,21 @@
 use openmls_traits::prelude::*;
 
 use crate::{
-    binary_tree::LeafNodeIndex,
+    binary_tree::{LeafNodeIndex, ParentNodeIndex, TreeNodeIndex},
     framing::{
         public_message_in::PublicMessageIn, MlsMessageIn, MlsMessageOut, ProcessedMessage,
         ProcessedMessageContent, ProtocolMessage, Sender,
     },
     group::{
-        mls_group::tests_and_kats::utils::setup_client, proposal_store::ProposalStore, GroupId,
-        MlsGroup, MlsGroupCreateConfig, StagedCommit, PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
+        errors::ProposalValidationError,
+        mls_group::tests_and_kats::utils::setup_client,
+        proposal_store::{ProposalStore, QueuedProposal},
+        GroupId, MlsGroup, MlsGroupCreateConfig, StagedCommit, TreePreviewError,
+        PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
     },
+    key_packages::KeyPackageBundle,
     messages::proposals::Proposal,
+    treesync::LeafNodeParameters,
 };
 
 use super::{super::mls_group::StagedWelcome, PublicGroup};
@@ -31,7 +36,8 @@ fn public_group<Provider: OpenMlsProvider>(ciphersuite: Ciphersuite, provider: &
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new_with_group_id(
@@ -291,6 +297,282 @@ fn public_group<Provider: OpenMlsProvider>(ciphersuite: Ciphersuite, provider: &
     );
 }
 
+#[openmls_test::openmls_test]
+fn validate_proposal<Provider: OpenMlsProvider>(ciphersuite: Ciphersuite, provider: &Provider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (bob_credential_with_key, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let verifiable_group_info = alice_group
+        .export_group_info(provider, &alice_signer, false)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (mut public_group, _extensions) = PublicGroup::from_external(
+        provider.crypto(),
+        provider.storage(),
+        alice_group.export_ratchet_tree().into(),
+        verifiable_group_info,
+        ProposalStore::new(),
+    )
+    .unwrap();
+
+    // === Alice proposes to add Bob ===
+    let (message, _proposal_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, bob_kpb.key_package())
+        .unwrap();
+    let queued_proposal = extract_proposal(
+        public_group
+            .process_message(provider.crypto(), into_public_message(message))
+            .unwrap(),
+    );
+
+    public_group.validate_proposal(&queued_proposal).unwrap();
+    public_group
+        .add_proposal(provider.storage(), queued_proposal)
+        .unwrap();
+
+    // === Alice proposes to add Bob again with another key package ===
+    let bob_kpb_2 =
+        KeyPackageBundle::generate(provider, &bob_signer, ciphersuite, bob_credential_with_key);
+    let (message, _proposal_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, bob_kpb_2.key_package())
+        .unwrap();
+    let queued_proposal = extract_proposal(
+        public_group
+            .process_message(provider.crypto(), into_public_message(message))
+            .unwrap(),
+    );
+
+    // The signature key is already used by the first proposal.
+    assert_eq!(
+        public_group.validate_proposal(&queued_proposal),
+        Err(ProposalValidationError::DuplicateSignatureKey)
+    );
+
+    // === The conflicting proposal ends up in the store anyway ===
+    public_group
+        .add_proposal(provider.storage(), queued_proposal)
+        .unwrap();
+
+    // It doesn't make an unrelated proposal fail.
+    let (message, _proposal_ref) = alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+    let queued_proposal = extract_proposal(
+        public_group
+            .process_message(provider.crypto(), into_public_message(message))
+            .unwrap(),
+    );
+    public_group.validate_proposal(&queued_proposal).unwrap();
+}
+
+#[openmls_test::openmls_test]
+fn path_queries<Provider: OpenMlsProvider>(ciphersuite: Ciphersuite, provider: &Provider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    // With a single member, there are neither a direct path nor a copath.
+    let public_group = alice_group.public_group();
+    assert_eq!(
+        public_group.direct_path(LeafNodeIndex::new(0)),
+        Some(vec![])
+    );
+    assert_eq!(public_group.copath(LeafNodeIndex::new(0)), Some(vec![]));
+    assert_eq!(
+        public_group.copath_resolutions(LeafNodeIndex::new(0)),
+        Some(vec![])
+    );
+
+    // === Alice adds Bob and Charlie ===
+    alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                bob_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .expect("Could not add members to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    // The tree now has four leaves, the last one being blank.
+    let public_group = alice_group.public_group();
+    let charlie_index = LeafNodeIndex::new(2);
+
+    assert_eq!(
+        public_group.direct_path(charlie_index),
+        Some(vec![ParentNodeIndex::new(2), ParentNodeIndex::new(1)])
+    );
+    assert_eq!(
+        public_group.copath(charlie_index),
+        Some(vec![
+            TreeNodeIndex::Leaf(LeafNodeIndex::new(3)),
+            TreeNodeIndex::Parent(ParentNodeIndex::new(0)),
+        ])
+    );
+
+    // The blank sibling leaf has an empty resolution, so the parent node above
+    // it is skipped in the filtered direct path.
+    let copath_resolutions = public_group.copath_resolutions(charlie_index).unwrap();
+    assert_eq!(copath_resolutions.len(), 2);
+    assert!(copath_resolutions[0].is_empty());
+    assert!(!copath_resolutions[1].is_empty());
+    assert_eq!(
+        public_group.filtered_direct_path(charlie_index),
+        Some(vec![ParentNodeIndex::new(1)])
+    );
+
+    // Leaves outside of the tree have no paths.
+    assert_eq!(public_group.direct_path(LeafNodeIndex::new(4)), None);
+    assert_eq!(public_group.copath(LeafNodeIndex::new(4)), None);
+    assert_eq!(public_group.copath_resolutions(LeafNodeIndex::new(4)), None);
+    assert_eq!(
+        public_group.filtered_direct_path(LeafNodeIndex::new(4)),
+        None
+    );
+}
+
+#[openmls_test::openmls_test]
+fn node_introspection<Provider: OpenMlsProvider>(ciphersuite: Ciphersuite, provider: &Provider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    // === Alice adds Bob and Charlie without a path ===
+    alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                bob_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .expect("Could not add members to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let public_group = alice_group.public_group();
+    for index in 0..3 {
+        assert!(public_group.is_blank(TreeNodeIndex::Parent(ParentNodeIndex::new(index))));
+    }
+    assert!(!public_group.is_blank(TreeNodeIndex::Leaf(LeafNodeIndex::new(2))));
+    assert!(public_group.is_blank(TreeNodeIndex::Leaf(LeafNodeIndex::new(3))));
+
+    // === Alice updates, which fills her direct path ===
+    alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("Could not create commit.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let public_group = alice_group.public_group();
+    for index in [0, 1] {
+        let parent = public_group
+            .parent(ParentNodeIndex::new(index))
+            .expect("parent on Alice's direct path should not be blank");
+        assert!(parent.unmerged_leaves().is_empty());
+    }
+    assert!(public_group.is_blank(TreeNodeIndex::Parent(ParentNodeIndex::new(2))));
+
+    // === Alice adds Dave without a path ===
+    alice_group
+        .add_members(provider, &alice_signer, &[dave_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    // Dave is an unmerged leaf of the non-blank root, but not of the node
+    // above Alice and Bob.
+    let public_group = alice_group.public_group();
+    assert!(public_group
+        .parent(ParentNodeIndex::new(0))
+        .unwrap()
+        .unmerged_leaves()
+        .is_empty());
+    assert_eq!(
+        public_group
+            .parent(ParentNodeIndex::new(1))
+            .unwrap()
+            .unmerged_leaves(),
+        &[LeafNodeIndex::new(3)]
+    );
+    assert!(public_group.is_blank(TreeNodeIndex::Parent(ParentNodeIndex::new(2))));
+
+    // The full audit of the tree, including the unmerged leaves, finds
+    // nothing.
+    assert!(public_group
+        .verify_parent_hashes(provider.crypto())
+        .unwrap()
+        .is_empty());
+
+    // Nodes outside of the tree are blank.
+    assert!(public_group.parent(ParentNodeIndex::new(3)).is_none());
+    assert!(public_group.is_blank(TreeNodeIndex::Leaf(LeafNodeIndex::new(4))));
+}
+
 // A helper function
 fn into_public_message(message: MlsMessageOut) -> PublicMessageIn {
     match message.into_protocol_message().unwrap() {
@@ -309,3 +591,79 @@ fn extract_staged_commit(ppm: ProcessedMessage) -> StagedCommit {
         ProcessedMessageContent::StagedCommitMessage(staged_content) => *staged_content,
     }
 }
+
+fn extract_proposal(ppm: ProcessedMessage) -> QueuedProposal {
+    match ppm.into_content() {
+        ProcessedMessageContent::ProposalMessage(queued_proposal) => *queued_proposal,
+        _ => panic!("Unexpected message type."),
+    }
+}
+
+#[openmls_test::openmls_test]
+fn tree_preview<Provider: OpenMlsProvider>(ciphersuite: Ciphersuite, provider: &Provider) {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &MlsGroupCreateConfig::test_default(ciphersuite),
+        GroupId::from_slice(b"Test Group"),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+    // Fill the direct path of Alice.
+    alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("Could not create commit.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    // Without changes, the preview matches the current tree.
+    let preview = alice_group.tree_preview();
+    let outcome = preview.outcome(provider.crypto()).unwrap();
+    assert_eq!(outcome.tree_hash(), alice_group.tree_hash());
+    assert_eq!(outcome.leaf_count(), 2);
+    assert!(outcome.changed_leaves().is_empty());
+    assert!(outcome.changed_parents().is_empty());
+
+    // Adding Charlie extends the tree. His direct path consists of new, blank
+    // nodes.
+    let mut preview = alice_group.tree_preview();
+    let charlie_index = preview.add(charlie_kpb.key_package()).unwrap();
+    assert_eq!(charlie_index, LeafNodeIndex::new(2));
+    let outcome = preview.outcome(provider.crypto()).unwrap();
+    assert_ne!(outcome.tree_hash(), alice_group.tree_hash());
+    assert_eq!(outcome.leaf_count(), 4);
+    assert_eq!(outcome.changed_leaves(), &[charlie_index]);
+    assert!(outcome.changed_parents().is_empty());
+
+    // Removing Bob blanks his leaf and the root, and trims the tree.
+    let mut preview = alice_group.tree_preview();
+    preview.remove(LeafNodeIndex::new(1)).unwrap();
+    let outcome = preview.outcome(provider.crypto()).unwrap();
+    assert_eq!(outcome.leaf_count(), 1);
+    assert_eq!(outcome.changed_leaves(), &[LeafNodeIndex::new(1)]);
+    assert_eq!(outcome.changed_parents(), &[ParentNodeIndex::new(0)]);
+
+    // Blank leaves can't be removed.
+    assert_eq!(
+        preview.remove(LeafNodeIndex::new(1)),
+        Err(TreePreviewError::UnknownMember)
+    );
+
+    // The group itself is unchanged.
+    assert_eq!(alice_group.public_group().tree_size().leaf_count(), 2);
+}
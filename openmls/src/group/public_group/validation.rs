@@ -7,7 +7,7 @@ use openmls_traits::types::VerifiableCiphersuite;
 
 use super::PublicGroup;
 use crate::extensions::RequiredCapabilitiesExtension;
-use crate::group::proposal_store::ProposalQueue;
+use crate::group::proposal_store::{ProposalQueue, QueuedProposal};
 use crate::group::GroupContextExtensionsProposalValidationError;
 use crate::prelude::LibraryError;
 use crate::treesync::{errors::LeafNodeValidationError, LeafNode};
@@ -20,13 +20,13 @@ use crate::{
     group::{
         errors::{ExternalCommitValidationError, ProposalValidationError, ValidationError},
         past_secrets::MessageSecretsStore,
-        Member,
+        Member, ProcessingPolicy,
     },
     messages::{
-        proposals::{Proposal, ProposalOrRefType, ProposalType},
+        proposals::{Proposal, ProposalOrRefType, ProposalType, RemoveProposal},
         Commit,
     },
-    schedule::errors::PskError,
+    schedule::{errors::PskError, psk::ResumptionPskUsage},
 };
 
 use crate::treesync::errors::LifetimeError;
@@ -125,6 +125,92 @@ impl PublicGroup {
 
     // === Proposals ===
 
+    /// Validates a standalone proposal against the current state of the group.
+    ///
+    /// This allows a Delivery Service (or any other party tracking the public
+    /// group state) to check whether a proposal obtained from
+    /// [`Self::process_message()`] would be acceptable in a commit for the
+    /// current epoch before fanning it out to the group members. The proposal
+    /// is validated on its own and then together with each proposal in the
+    /// proposal store of this group, such that conflicts with previously
+    /// queued proposals are detected as well. An invalid or conflicting
+    /// proposal in the store doesn't affect the validation of other proposals.
+    ///
+    /// This function does not verify the signature or the membership tag of
+    /// the message that contained the proposal. This is done when processing
+    /// the message.
+    ///
+    /// Checks the following semantic validation:
+    ///  - ValSem101
+    ///  - ValSem102
+    ///  - ValSem103
+    ///  - ValSem104
+    ///  - ValSem105
+    ///  - ValSem106
+    ///  - ValSem107
+    ///  - ValSem108
+    ///  - ValSem109
+    ///  - ValSem112
+    ///  - ValSem113
+    ///  - ValSem208
+    ///  - ValSem209
+    ///  - ValSem401
+    ///  - ValSem402
+    ///  - ValSem403
+    pub fn validate_proposal(
+        &self,
+        proposal: &QueuedProposal,
+    ) -> Result<(), ProposalValidationError> {
+        // ExternalInit proposals can only be sent inline in an external commit.
+        if proposal.proposal().proposal_type() == ProposalType::ExternalInit {
+            return Err(ProposalValidationError::StandaloneExternalInitProposal);
+        }
+
+        self.validate_standalone_proposals(std::iter::once(proposal.clone()).collect())?;
+
+        // Check for conflicts with each stored proposal separately. Stored
+        // proposals that are invalid on their own are skipped, so that they
+        // don't make every later proposal fail.
+        for stored_proposal in self.proposal_store().proposals() {
+            if self
+                .validate_standalone_proposals(std::iter::once(stored_proposal.clone()).collect())
+                .is_err()
+            {
+                continue;
+            }
+            self.validate_standalone_proposals(
+                [stored_proposal.clone(), proposal.clone()]
+                    .into_iter()
+                    .collect(),
+            )?;
+        }
+
+        // ValSem112
+        if let Proposal::Update(update_proposal) = proposal.proposal() {
+            if !matches!(proposal.sender(), Sender::Member(_)) {
+                return Err(ProposalValidationError::UpdateFromNonMember);
+            }
+            self.validate_leaf_node(update_proposal.leaf_node())?;
+        }
+
+        Ok(())
+    }
+
+    // Runs the checks of `validate_proposal()` that apply to a set of
+    // proposals.
+    fn validate_standalone_proposals(
+        &self,
+        proposal_queue: ProposalQueue,
+    ) -> Result<(), ProposalValidationError> {
+        self.validate_key_uniqueness(&proposal_queue, None)?;
+        self.validate_add_proposals(&proposal_queue)?;
+        self.validate_capabilities(&proposal_queue)?;
+        self.validate_remove_proposals(&proposal_queue)?;
+        self.validate_proposal_type_support(&proposal_queue)?;
+        self.validate_group_context_extensions_proposal(&proposal_queue)?;
+        self.validate_pre_shared_key_proposals(&proposal_queue)
+    }
+
     /// Validate that all group members support the types of all proposals.
     /// Implements check [valn0311](https://validation.openmls.tech/#valn0311)
     pub(crate) fn validate_proposal_type_support(
@@ -422,6 +508,43 @@ impl PublicGroup {
         Ok(())
     }
 
+    /// Checks that the application's [`ProcessingPolicy`] admits the removal
+    /// of the members that are removed by the proposals in the
+    /// `proposal_queue`, see [`ProcessingPolicy::admit_remove()`].
+    pub(crate) fn validate_remove_policy(
+        &self,
+        proposal_queue: &ProposalQueue,
+        policy: &impl ProcessingPolicy,
+    ) -> Result<(), ProposalValidationError> {
+        for remove_proposal in proposal_queue.remove_proposals() {
+            if !self.admits_remove(
+                remove_proposal.sender(),
+                remove_proposal.remove_proposal(),
+                policy,
+            ) {
+                return Err(ProposalValidationError::ProtectedMemberRemoval);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the application's [`ProcessingPolicy`] admits the
+    /// removal of the member removed by the `remove_proposal` of the `sender`.
+    pub(crate) fn admits_remove(
+        &self,
+        sender: &Sender,
+        remove_proposal: &RemoveProposal,
+        policy: &impl ProcessingPolicy,
+    ) -> bool {
+        match self.treesync().leaf(remove_proposal.removed()) {
+            Some(leaf_node) => policy.admit_remove(sender, leaf_node.credential()),
+            // Removals of unknown members are rejected by the regular
+            // validation.
+            None => true,
+        }
+    }
+
     /// Validate Update proposals. This function implements the following checks:
     ///  - ValSem111: Update Proposal: The sender of a full Commit must not include own update proposals
     ///  - ValSem112: Update Proposal: The sender of a standalone update proposal must be of type member
@@ -470,12 +593,26 @@ impl PublicGroup {
         //              Let's do this check here until we haven't made sure.
         let mut visited_psk_ids = BTreeSet::new();
 
+        // Resumption PSKs with usage reinit or branch link a new group to
+        // another group. They are only allowed in the commit that creates the
+        // first epoch of the new group, which is only processed by the
+        // committer itself and is otherwise only visible through the Welcome.
+        let allowed_usages: &[ResumptionPskUsage] = if self.group_context().epoch().as_u64() == 0 {
+            &[
+                ResumptionPskUsage::Application,
+                ResumptionPskUsage::Reinit,
+                ResumptionPskUsage::Branch,
+            ]
+        } else {
+            &[ResumptionPskUsage::Application]
+        };
+
         for proposal in proposal_queue.psk_proposals() {
             let psk_id = proposal.psk_proposal().clone().into_psk_id();
 
             // ValSem401
             // ValSem402
-            let psk_id = psk_id.validate_in_proposal(self.ciphersuite())?;
+            let psk_id = psk_id.validate_in_proposal(self.ciphersuite(), allowed_usages)?;
 
             // ValSem403 (2/2)
             if !visited_psk_ids.contains(&psk_id) {
@@ -488,6 +625,29 @@ impl PublicGroup {
         Ok(())
     }
 
+    /// Validate ReInit proposals. This function implements the following checks:
+    ///  - A ReInit proposal must be the only proposal in the Commit.
+    ///  - The protocol version of a ReInit proposal must not be lower than the
+    ///    one of the group.
+    pub(crate) fn validate_reinit_proposals(
+        &self,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<(), ProposalValidationError> {
+        for queued_proposal in proposal_queue.filtered_by_type(ProposalType::Reinit) {
+            let Proposal::ReInit(reinit_proposal) = queued_proposal.proposal() else {
+                continue;
+            };
+            if proposal_queue.queued_proposals().count() > 1 {
+                return Err(ProposalValidationError::ReInitWithOtherProposals);
+            }
+            if reinit_proposal.version() < self.version() {
+                return Err(ProposalValidationError::ReInitVersionDowngrade);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Validate constraints on an external commit. This function implements the following checks:
     ///  - ValSem240: External Commit, inline Proposals: There MUST be at least one ExternalInit proposal.
     ///  - ValSem241: External Commit, inline Proposals: There MUST be at most one ExternalInit proposal.
@@ -0,0 +1,160 @@
+//! # Group state summary
+//!
+//! This module contains the [`GroupStateSummary`], a compact digest of the
+//! state of a group that two clients (e.g. two devices of the same user) can
+//! compare to detect whether their views of the group diverged.
+
+use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use serde::{Deserialize, Serialize};
+use tls_codec::{
+    Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
+    VLBytes,
+};
+
+use crate::{
+    error::LibraryError,
+    group::{GroupContext, GroupEpoch},
+};
+
+/// A summary of the state of a group in a given epoch.
+///
+/// The summary contains the components that make up the group state agreed
+/// upon by all members, as well as a single digest over all of them. Clients
+/// can exchange the [`GroupStateSummary::digest()`] to quickly check whether
+/// they are in the same state. If the digests don't match, the full summaries
+/// can be exchanged (the summary can be serialized) and compared using
+/// [`GroupStateSummary::diverging_components()`] to find out which part of the
+/// state differs.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TlsSerialize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
+    TlsSize,
+)]
+pub struct GroupStateSummary {
+    epoch: GroupEpoch,
+    tree_hash: VLBytes,
+    confirmed_transcript_hash: VLBytes,
+    group_context_hash: VLBytes,
+    digest: VLBytes,
+}
+
+/// A component of the [`GroupStateSummary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupStateComponent {
+    /// The epoch of the group.
+    Epoch,
+    /// The hash of the ratchet tree, i.e. the group's membership and the
+    /// members' leaf nodes and keys.
+    TreeHash,
+    /// The confirmed transcript hash, i.e. the history of commits.
+    ConfirmedTranscriptHash,
+    /// The remaining fields of the group context, e.g. the group ID, the
+    /// ciphersuite or the group context extensions.
+    GroupContext,
+}
+
+// The components over which the digest is computed.
+#[derive(TlsSerialize, TlsSize)]
+struct DigestInput<'a> {
+    epoch: &'a GroupEpoch,
+    tree_hash: &'a VLBytes,
+    confirmed_transcript_hash: &'a VLBytes,
+    group_context_hash: &'a VLBytes,
+}
+
+impl GroupStateSummary {
+    /// Computes the summary of the state described by the `group_context`.
+    pub(crate) fn new(
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        group_context: &GroupContext,
+    ) -> Result<Self, LibraryError> {
+        let serialized_group_context = group_context
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        let group_context_hash: VLBytes = crypto
+            .hash(ciphersuite.hash_algorithm(), &serialized_group_context)
+            .map_err(LibraryError::unexpected_crypto_error)?
+            .into();
+
+        let epoch = group_context.epoch();
+        let tree_hash: VLBytes = group_context.tree_hash().into();
+        let confirmed_transcript_hash: VLBytes = group_context.confirmed_transcript_hash().into();
+
+        let digest_input = DigestInput {
+            epoch: &epoch,
+            tree_hash: &tree_hash,
+            confirmed_transcript_hash: &confirmed_transcript_hash,
+            group_context_hash: &group_context_hash,
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+        let digest = crypto
+            .hash(ciphersuite.hash_algorithm(), &digest_input)
+            .map_err(LibraryError::unexpected_crypto_error)?
+            .into();
+
+        Ok(Self {
+            epoch,
+            tree_hash,
+            confirmed_transcript_hash,
+            group_context_hash,
+            digest,
+        })
+    }
+
+    /// Returns the digest over all components of the summary.
+    pub fn digest(&self) -> &[u8] {
+        self.digest.as_slice()
+    }
+
+    /// Returns the epoch of the group.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the tree hash of the group.
+    pub fn tree_hash(&self) -> &[u8] {
+        self.tree_hash.as_slice()
+    }
+
+    /// Returns the confirmed transcript hash of the group.
+    pub fn confirmed_transcript_hash(&self) -> &[u8] {
+        self.confirmed_transcript_hash.as_slice()
+    }
+
+    /// Returns the hash of the group's serialized [`GroupContext`].
+    pub fn group_context_hash(&self) -> &[u8] {
+        self.group_context_hash.as_slice()
+    }
+
+    /// Returns the components in which this summary differs from `other`. The
+    /// result is empty if the summaries match.
+    ///
+    /// Note that the group context covers the other components. If only
+    /// [`GroupStateComponent::GroupContext`] is returned, the groups only
+    /// differ in the remaining fields of the group context.
+    pub fn diverging_components(&self, other: &Self) -> Vec<GroupStateComponent> {
+        let mut components = Vec::new();
+        if self.epoch != other.epoch {
+            components.push(GroupStateComponent::Epoch);
+        }
+        if self.tree_hash != other.tree_hash {
+            components.push(GroupStateComponent::TreeHash);
+        }
+        if self.confirmed_transcript_hash != other.confirmed_transcript_hash {
+            components.push(GroupStateComponent::ConfirmedTranscriptHash);
+        }
+        if self.group_context_hash != other.group_context_hash {
+            components.push(GroupStateComponent::GroupContext);
+        }
+        components
+    }
+}
@@ -55,7 +55,8 @@ fn test_add_member_with_aad(
         let mls_group_create_config = MlsGroupCreateConfig::builder()
             .ciphersuite(ciphersuite)
             .wire_format_policy(wire_format_policy)
-            .build();
+            .build()
+            .unwrap();
 
         // === Alice creates a group ===
 
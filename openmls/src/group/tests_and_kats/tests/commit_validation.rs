@@ -65,7 +65,8 @@ fn validation_test_setup(
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .wire_format_policy(wire_format_policy)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new_with_group_id(
@@ -3,13 +3,18 @@ use openmls_test::openmls_test;
 
 use crate::{
     binary_tree::LeafNodeIndex,
+    extensions::{
+        Extension, ExtensionType, Extensions, ExternalSender, RequiredCapabilitiesExtension,
+        SenderExtensionIndex,
+    },
     framing::*,
     group::*,
+    key_packages::KeyPackage,
     messages::{
         external_proposals::*,
         proposals::{AddProposal, Proposal, ProposalType},
     },
-    treesync::LeafNodeParameters,
+    treesync::{node::leaf_node::Capabilities, LeafNodeParameters},
 };
 
 use openmls_traits::{types::Ciphersuite, OpenMlsProvider as _};
@@ -38,7 +43,8 @@ fn new_test_group(
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .wire_format_policy(wire_format_policy)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     (
         MlsGroup::new_with_group_id(
@@ -360,3 +366,129 @@ fn new_member_proposal_sender_should_be_reserved_for_join_proposals<Provider: Op
         .clear_pending_proposals(provider.storage())
         .unwrap();
 }
+
+#[openmls_test]
+fn external_sender_add_proposal() {
+    let alice_credential =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+    let ds_credential_with_key = generate_credential_with_key(
+        "delivery-service".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+
+    let capabilities = Capabilities::new(
+        None,
+        None,
+        Some(&[ExtensionType::Unknown(0xff00)]),
+        None,
+        None,
+    );
+    let group_context_extensions = Extensions::from_vec(vec![
+        Extension::ExternalSenders(vec![ExternalSender::new(
+            ds_credential_with_key
+                .credential_with_key
+                .signature_key
+                .clone(),
+            ds_credential_with_key
+                .credential_with_key
+                .credential
+                .clone(),
+        )]),
+        Extension::RequiredCapabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::Unknown(0xff00)],
+            &[],
+            &[],
+        )),
+    ])
+    .unwrap();
+
+    let mut alice_group = MlsGroup::builder()
+        .ciphersuite(ciphersuite)
+        .with_wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .with_capabilities(capabilities.clone())
+        .with_group_context_extensions(group_context_extensions)
+        .expect("error adding group context extension to builder")
+        .build(
+            provider,
+            &alice_credential.signer,
+            alice_credential.credential_with_key,
+        )
+        .expect("error creating group using builder");
+
+    // Charlie supports the required extension, Dave doesn't.
+    let charlie_credential = generate_credential_with_key(
+        "Charlie".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+    let charlie_kpb = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities)
+        .build(
+            ciphersuite,
+            provider,
+            &charlie_credential.signer,
+            charlie_credential.credential_with_key,
+        )
+        .unwrap();
+    let dave_credential =
+        generate_credential_with_key("Dave".into(), ciphersuite.signature_algorithm(), provider);
+    let dave_kpb =
+        generate_key_package(ciphersuite, Extensions::empty(), provider, dave_credential);
+
+    // The delivery service proposes to add Dave, which is rejected.
+    let dave_proposal: MlsMessageIn =
+        ExternalProposal::add_builder(dave_kpb.key_package().clone(), SenderExtensionIndex::new(0))
+            .group_context(alice_group.export_group_context())
+            .build(&ds_credential_with_key.signer)
+            .unwrap()
+            .into();
+    let error = alice_group
+        .process_message(provider, dave_proposal.try_into_protocol_message().unwrap())
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ProcessMessageError::IncompatibleExternalAdd(vec![MissingCapability::Extension(
+            ExtensionType::Unknown(0xff00)
+        )])
+    );
+
+    // The delivery service proposes to add Charlie.
+    let charlie_proposal: MlsMessageIn = ExternalProposal::add_builder(
+        charlie_kpb.key_package().clone(),
+        SenderExtensionIndex::new(0),
+    )
+    .group_context(alice_group.export_group_context())
+    .build(&ds_credential_with_key.signer)
+    .unwrap()
+    .into();
+    let processed_message = alice_group
+        .process_message(
+            provider,
+            charlie_proposal.try_into_protocol_message().unwrap(),
+        )
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(proposal) = processed_message.into_content()
+    else {
+        panic!("Not a proposal");
+    };
+    assert_eq!(
+        proposal.sender(),
+        &Sender::External(SenderExtensionIndex::new(0))
+    );
+    assert!(matches!(
+        proposal.proposal(),
+        Proposal::Add(AddProposal { key_package }) if key_package == charlie_kpb.key_package()
+    ));
+
+    // Alice commits the proposal.
+    alice_group
+        .store_pending_proposal(provider.storage(), *proposal)
+        .unwrap();
+    let (_commit, welcome, _group_info) = alice_group
+        .commit_to_pending_proposals(provider, &alice_credential.signer)
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert!(welcome.is_some());
+    assert_eq!(alice_group.members().count(), 2);
+}
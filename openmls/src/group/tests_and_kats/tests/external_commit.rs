@@ -1,10 +1,13 @@
 //! This module contains tests for external commit messages
+use openmls_traits::OpenMlsProvider as _;
 use tls_codec::{Deserialize, Serialize};
 
 use crate::{
     framing::{MlsMessageIn, Sender},
     group::{
-        tests_and_kats::utils::generate_credential_with_key, MlsGroup, MlsGroupCreateConfig,
+        tests_and_kats::utils::generate_credential_with_key, AdmissionToken, AdmissionTokenPolicy,
+        ExternalCommitError, ExternalCommitInfo, ExternalCommitValidationError, GroupEpoch,
+        MlsGroup, MlsGroupCreateConfig, ProcessMessageError, ProcessingPolicy, StageCommitError,
         PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
     },
     prelude::ProcessedMessageContent,
@@ -30,7 +33,8 @@ fn test_external_commit() {
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // Alice creates a group
     let mut alice_group = MlsGroup::new(
@@ -261,3 +265,301 @@ fn test_external_commit() {
         charlie_group.export_ratchet_tree()
     );
 }
+
+/// Only admits external commits that re-add an existing member.
+struct RejoinOnlyPolicy;
+
+impl ProcessingPolicy for RejoinOnlyPolicy {
+    fn admit_external_commit(&self, external_commit: &ExternalCommitInfo) -> bool {
+        external_commit
+            .removed_leaf()
+            .map(|(_, leaf_node)| leaf_node.credential() == external_commit.credential())
+            .unwrap_or(false)
+    }
+}
+
+// External commits can be rejected by an application-defined admission policy.
+#[openmls_test::openmls_test]
+fn test_external_commit_admission_policy() {
+    let alice_credential =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+    let bob_credential =
+        generate_credential_with_key("Bob".into(), ciphersuite.signature_algorithm(), provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_credential.signer,
+        &mls_group_create_config,
+        alice_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    // Bob, who was never a member, joins externally.
+    let verifiable_group_info = alice_group
+        .export_group_info(provider, &alice_credential.signer, false)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+
+    let (_bob_group, public_message_commit, _) = MlsGroup::join_by_external_commit(
+        provider,
+        &bob_credential.signer,
+        Some(alice_group.export_ratchet_tree().into()),
+        verifiable_group_info,
+        alice_group.configuration(),
+        None,
+        None,
+        &[],
+        bob_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    let bob_commit = MlsMessageIn::from(public_message_commit)
+        .into_plaintext()
+        .unwrap();
+
+    // The policy only admits rejoins, so Bob's commit is rejected.
+    let err = alice_group
+        .process_message_with_policy(provider, bob_commit.clone(), &RejoinOnlyPolicy)
+        .expect_err("the policy should reject the external commit");
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::ExternalCommitValidation(
+            ExternalCommitValidationError::RejectedByPolicy
+        ))
+    );
+
+    // Without the policy, the commit is accepted.
+    let processed_message = alice_group.process_message(provider, bob_commit).unwrap();
+    assert!(matches!(
+        processed_message.into_content(),
+        ProcessedMessageContent::StagedCommitMessage(_)
+    ));
+}
+
+// New members can be onboarded with a published GroupInfo and an admission
+// token instead of a Welcome message.
+#[openmls_test::openmls_test]
+fn test_external_commit_admission_token() {
+    let alice_credential =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+    let bob_credential =
+        generate_credential_with_key("Bob".into(), ciphersuite.signature_algorithm(), provider);
+    let charlie_credential = generate_credential_with_key(
+        "Charlie".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_credential.signer,
+        &mls_group_create_config,
+        alice_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    let policy = AdmissionTokenPolicy::new(|token, _| token == b"invite-42");
+
+    // A GroupInfo without the ratchet tree can't be used for onboarding.
+    let verifiable_group_info = alice_group
+        .export_group_info(provider, &alice_credential.signer, false)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let err = MlsGroup::join_with_admission_token(
+        provider,
+        &bob_credential.signer,
+        verifiable_group_info,
+        alice_group.configuration(),
+        bob_credential.credential_with_key.clone(),
+        b"invite-42",
+    )
+    .expect_err("the group info doesn't contain the ratchet tree");
+    assert!(matches!(err, ExternalCommitError::MissingRatchetTree));
+
+    // Bob joins with a valid token.
+    let verifiable_group_info = alice_group
+        .export_onboarding_group_info(provider, &alice_credential.signer)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (bob_group, bob_commit, _) = MlsGroup::join_with_admission_token(
+        provider,
+        &bob_credential.signer,
+        verifiable_group_info.clone(),
+        alice_group.configuration(),
+        bob_credential.credential_with_key.clone(),
+        b"invite-42",
+    )
+    .unwrap();
+
+    // Charlie uses a wrong token on the same GroupInfo.
+    let (_charlie_group, charlie_commit, _) = MlsGroup::join_with_admission_token(
+        provider,
+        &charlie_credential.signer,
+        verifiable_group_info.clone(),
+        alice_group.configuration(),
+        charlie_credential.credential_with_key.clone(),
+        b"invite-43",
+    )
+    .unwrap();
+
+    // Charlie presents the valid token, but not bound to the group and epoch.
+    let (_charlie_group, charlie_unbound_commit, _) = MlsGroup::join_by_external_commit(
+        provider,
+        &charlie_credential.signer,
+        None,
+        verifiable_group_info.clone(),
+        alice_group.configuration(),
+        None,
+        None,
+        b"invite-42",
+        charlie_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    // Charlie presents the valid token, but bound to another epoch.
+    let replayed_token = AdmissionToken::new(
+        alice_group.group_id().clone(),
+        GroupEpoch::from(alice_group.epoch().as_u64() + 1),
+        b"invite-42".to_vec(),
+    );
+    let (_charlie_group, charlie_replayed_commit, _) = MlsGroup::join_by_external_commit(
+        provider,
+        &charlie_credential.signer,
+        None,
+        verifiable_group_info.clone(),
+        alice_group.configuration(),
+        None,
+        None,
+        &replayed_token.tls_serialize_detached().unwrap(),
+        charlie_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    for charlie_commit in [
+        charlie_commit,
+        charlie_unbound_commit,
+        charlie_replayed_commit,
+    ] {
+        let charlie_commit = MlsMessageIn::from(charlie_commit).into_plaintext().unwrap();
+        let err = alice_group
+            .process_message_with_policy(provider, charlie_commit, &policy)
+            .expect_err("the policy should reject the commit");
+        assert_eq!(
+            err,
+            ProcessMessageError::InvalidCommit(StageCommitError::ExternalCommitValidation(
+                ExternalCommitValidationError::RejectedByPolicy
+            ))
+        );
+    }
+
+    let bob_commit = MlsMessageIn::from(bob_commit).into_plaintext().unwrap();
+    let processed_message = alice_group
+        .process_message_with_policy(provider, bob_commit, &policy)
+        .unwrap();
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("expected a staged commit");
+    };
+    alice_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+
+    assert_eq!(
+        alice_group.export_ratchet_tree(),
+        bob_group.export_ratchet_tree()
+    );
+
+    // The token doesn't become part of the ratchet tree.
+    assert!(bob_group.own_leaf_node().unwrap().extensions().is_empty());
+}
+
+// The same external commit can't be staged twice in an epoch.
+#[openmls_test::openmls_test]
+fn test_external_commit_replay() {
+    let alice_credential =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+    let bob_credential =
+        generate_credential_with_key("Bob".into(), ciphersuite.signature_algorithm(), provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_credential.signer,
+        &mls_group_create_config,
+        alice_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    let verifiable_group_info = alice_group
+        .export_onboarding_group_info(provider, &alice_credential.signer)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (_bob_group, bob_commit, _) = MlsGroup::join_by_external_commit(
+        provider,
+        &bob_credential.signer,
+        None,
+        verifiable_group_info,
+        alice_group.configuration(),
+        None,
+        None,
+        &[],
+        bob_credential.credential_with_key.clone(),
+    )
+    .unwrap();
+    let bob_commit = MlsMessageIn::from(bob_commit).into_plaintext().unwrap();
+
+    let processed_message = alice_group
+        .process_message(provider, bob_commit.clone())
+        .unwrap();
+    assert!(matches!(
+        processed_message.into_content(),
+        ProcessedMessageContent::StagedCommitMessage(_)
+    ));
+
+    // Replaying the commit in the same epoch fails.
+    let err = alice_group
+        .process_message(provider, bob_commit.clone())
+        .expect_err("the external init was already used");
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::ExternalCommitValidation(
+            ExternalCommitValidationError::ExternalInitReused
+        ))
+    );
+
+    // Also after the group was loaded from storage.
+    let mut alice_group = MlsGroup::load(provider.storage(), alice_group.group_id())
+        .unwrap()
+        .unwrap();
+    let err = alice_group
+        .process_message(provider, bob_commit)
+        .expect_err("the external init was already used");
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::ExternalCommitValidation(
+            ExternalCommitValidationError::ExternalInitReused
+        ))
+    );
+}
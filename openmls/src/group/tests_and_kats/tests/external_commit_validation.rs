@@ -643,7 +643,8 @@ mod utils {
         let mls_group_create_config = MlsGroupCreateConfig::builder()
             .wire_format_policy(wire_format_policy)
             .ciphersuite(ciphersuite)
-            .build();
+            .build()
+            .unwrap();
 
         // Alice creates a group
         let alice_group = MlsGroup::new(
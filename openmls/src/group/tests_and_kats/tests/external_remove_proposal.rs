@@ -1,6 +1,15 @@
 use openmls_test::openmls_test;
 
-use crate::{credentials::BasicCredential, framing::*, group::*, messages::external_proposals::*};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    credentials::BasicCredential,
+    framing::*,
+    group::*,
+    messages::{
+        external_proposals::*,
+        proposals::{Proposal, ProposalType},
+    },
+};
 
 use openmls_traits::{types::Ciphersuite, OpenMlsProvider as _};
 
@@ -28,7 +37,8 @@ fn new_test_group(
             external_senders,
         )))
         .unwrap()
-        .build();
+        .build()
+        .unwrap();
 
     (
         MlsGroup::new_with_group_id(
@@ -367,3 +377,180 @@ fn external_remove_proposal_should_fail_when_no_external_senders() {
         ProcessMessageError::ValidationError(ValidationError::UnauthorizedExternalSender)
     ));
 }
+
+#[openmls_test]
+fn external_remove_proposal_should_respect_sender_permissions() {
+    // Two external senders with different roles. Only the first one may
+    // remove members.
+    let ds_credential_with_key = generate_credential_with_key(
+        "delivery-service".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+    let directory_credential_with_key = generate_credential_with_key(
+        "directory".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+
+    let (mut alice_group, _alice_credential) = validation_test_setup(
+        PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
+        ciphersuite,
+        provider,
+        vec![
+            ExternalSender::new(
+                ds_credential_with_key
+                    .credential_with_key
+                    .signature_key
+                    .clone(),
+                ds_credential_with_key
+                    .credential_with_key
+                    .credential
+                    .clone(),
+            ),
+            ExternalSender::new(
+                directory_credential_with_key
+                    .credential_with_key
+                    .signature_key
+                    .clone(),
+                directory_credential_with_key
+                    .credential_with_key
+                    .credential
+                    .clone(),
+            ),
+        ],
+    );
+    let permissions = ExternalSenderPermissions::new()
+        .allow(
+            ds_credential_with_key
+                .credential_with_key
+                .signature_key
+                .clone(),
+            [ProposalType::Remove, ProposalType::Add],
+        )
+        .allow(
+            directory_credential_with_key
+                .credential_with_key
+                .signature_key
+                .clone(),
+            [ProposalType::Add],
+        );
+
+    let bob_index = alice_group
+        .members()
+        .find(|member| member.credential.serialized_content() == b"Bob")
+        .map(|member| member.index)
+        .unwrap();
+
+    // The directory is not allowed to remove Bob
+    let directory_remove_proposal: MlsMessageIn = ExternalProposal::new_remove::<Provider>(
+        bob_index,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &directory_credential_with_key.signer,
+        SenderExtensionIndex::new(1),
+    )
+    .unwrap()
+    .into();
+    let error = alice_group
+        .process_message_with_policy(
+            provider,
+            directory_remove_proposal
+                .try_into_protocol_message()
+                .unwrap(),
+            &permissions,
+        )
+        .unwrap_err();
+    assert_eq!(error, ProcessMessageError::UnauthorizedExternalProposal);
+
+    // The delivery service is
+    let ds_remove_proposal: MlsMessageIn = ExternalProposal::new_remove::<Provider>(
+        bob_index,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap()
+    .into();
+    let processed_message = alice_group
+        .process_message_with_policy(
+            provider,
+            ds_remove_proposal.try_into_protocol_message().unwrap(),
+            &permissions,
+        )
+        .unwrap();
+    assert!(matches!(
+        processed_message.into_content(),
+        ProcessedMessageContent::ProposalMessage(_)
+    ));
+}
+
+#[openmls_test]
+fn external_remove_proposal_builder() {
+    let ds_credential_with_key = generate_credential_with_key(
+        "delivery-service".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+
+    let (mut alice_group, _alice_credential) = validation_test_setup(
+        PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
+        ciphersuite,
+        provider,
+        vec![ExternalSender::new(
+            ds_credential_with_key
+                .credential_with_key
+                .signature_key
+                .clone(),
+            ds_credential_with_key
+                .credential_with_key
+                .credential
+                .clone(),
+        )],
+    );
+    let group_context = alice_group.export_group_context().clone();
+    let bob_index = LeafNodeIndex::new(1);
+
+    // The builder needs to know the group
+    let error = ExternalProposal::remove_builder(bob_index, SenderExtensionIndex::new(0))
+        .build(&ds_credential_with_key.signer)
+        .unwrap_err();
+    assert_eq!(error, ExternalProposalBuildError::MissingGroupInfo);
+
+    // There is only one external sender
+    let error = ExternalProposal::remove_builder(bob_index, SenderExtensionIndex::new(1))
+        .group_context(&group_context)
+        .build(&ds_credential_with_key.signer)
+        .unwrap_err();
+    assert_eq!(error, ExternalProposalBuildError::UnknownExternalSender);
+
+    let ds_remove_proposal: MlsMessageIn =
+        ExternalProposal::remove_builder(bob_index, SenderExtensionIndex::new(0))
+            .group_context(&group_context)
+            .aad(b"reason: spam".to_vec())
+            .build(&ds_credential_with_key.signer)
+            .unwrap()
+            .into();
+
+    let processed_message = alice_group
+        .process_message(
+            provider,
+            ds_remove_proposal.try_into_protocol_message().unwrap(),
+        )
+        .unwrap();
+    assert_eq!(processed_message.aad(), b"reason: spam");
+    assert_eq!(
+        processed_message.sender(),
+        &Sender::External(SenderExtensionIndex::new(0))
+    );
+    let ProcessedMessageContent::ProposalMessage(remove_proposal) =
+        processed_message.into_content()
+    else {
+        panic!("Not a remove proposal");
+    };
+    let Proposal::Remove(remove_proposal) = remove_proposal.proposal() else {
+        panic!("Not a remove proposal");
+    };
+    assert_eq!(remove_proposal.removed(), bob_index);
+}
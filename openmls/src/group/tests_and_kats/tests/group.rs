@@ -143,7 +143,8 @@ fn wrong_group_create_config() {
             UnknownExtension(b"testdata".to_vec()),
         )))
         .expect("leaf node extension is in leaf node capabilities, should have succeeded")
-        .build();
+        .build()
+        .unwrap();
 }
 
 /// This test simulates various group operations like Add, Update, Remove in a
@@ -46,7 +46,8 @@ fn test_past_secrets_in_group<Provider: crate::storage::OpenMlsProvider>(
         let mls_group_create_config = MlsGroupCreateConfig::builder()
             .max_past_epochs(max_epochs / 2)
             .ciphersuite(ciphersuite)
-            .build();
+            .build()
+            .unwrap();
 
         // === Alice creates a group ===
         let mut alice_group = MlsGroup::new_with_group_id(
@@ -171,3 +172,163 @@ fn test_past_secrets_in_group<Provider: crate::storage::OpenMlsProvider>(
         }
     }
 }
+
+#[openmls_test::openmls_test]
+fn delete_past_epoch_secrets<Provider: crate::storage::OpenMlsProvider>(
+    ciphersuite: Ciphersuite,
+    provider: &Provider,
+) {
+    let alice_provider = &mut Provider::default();
+    let bob_provider = &mut Provider::default();
+
+    let alice_credential_with_keys = generate_credential_with_key(
+        b"Alice".to_vec(),
+        ciphersuite.signature_algorithm(),
+        alice_provider,
+    );
+    let bob_credential_with_keys = generate_credential_with_key(
+        b"Bob".to_vec(),
+        ciphersuite.signature_algorithm(),
+        bob_provider,
+    );
+    let bob_key_package = generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        bob_provider,
+        bob_credential_with_keys,
+    );
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .max_past_epochs(5)
+        .ciphersuite(ciphersuite)
+        .build()
+        .unwrap();
+
+    let mut alice_group = MlsGroup::new(
+        alice_provider,
+        &alice_credential_with_keys.signer,
+        &mls_group_create_config,
+        alice_credential_with_keys.credential_with_key.clone(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_message, welcome, _group_info) = alice_group
+        .add_members(
+            alice_provider,
+            &alice_credential_with_keys.signer,
+            &[bob_key_package.key_package().clone()],
+        )
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit(alice_provider)
+        .expect("error merging pending commit");
+
+    let welcome: MlsMessageIn = welcome.into();
+    let mut bob_group = StagedWelcome::new_from_welcome(
+        bob_provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().expect("expected a welcome"),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .expect("Error creating staged join from Welcome")
+    .into_group(bob_provider)
+    .expect("Error creating group from staged join");
+
+    // Alice sends an application message in each epoch before moving on to
+    // the next one.
+    let mut application_messages = Vec::new();
+    for _ in 0..2 {
+        let application_message = alice_group
+            .create_message(
+                alice_provider,
+                &alice_credential_with_keys.signer,
+                &[1, 2, 3],
+            )
+            .expect("An unexpected error occurred.");
+        application_messages.push(application_message.into_protocol_message().unwrap());
+
+        let (commit, _welcome, _group_info) = alice_group
+            .self_update(
+                alice_provider,
+                &alice_credential_with_keys.signer,
+                LeafNodeParameters::default(),
+            )
+            .expect("An unexpected error occurred.")
+            .into_contents();
+        alice_group
+            .merge_pending_commit(alice_provider)
+            .expect("error merging pending commit");
+
+        let processed_message = bob_group
+            .process_message(bob_provider, commit.into_protocol_message().unwrap())
+            .expect("An unexpected error occurred.");
+        let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+            processed_message.into_content()
+        else {
+            unreachable!("Expected a StagedCommit.");
+        };
+        bob_group
+            .merge_staged_commit(bob_provider, *staged_commit)
+            .expect("Error merging commit.");
+    }
+
+    // Once the maximum age is exceeded, the secrets of past epochs can't be
+    // used anymore.
+    let join_config = MlsGroupJoinConfig::builder()
+        .max_past_epochs(5)
+        .max_past_epoch_age(std::time::Duration::ZERO)
+        .build();
+    bob_group
+        .set_configuration(bob_provider.storage(), &join_config)
+        .expect("error setting the configuration");
+    bob_group
+        .process_message(bob_provider, application_messages[0].clone())
+        .expect_err("Expired secrets were used to decrypt a message.");
+
+    // Secrets of past epochs can be deleted explicitly.
+    let application_message = alice_group
+        .create_message(
+            alice_provider,
+            &alice_credential_with_keys.signer,
+            &[4, 5, 6],
+        )
+        .expect("An unexpected error occurred.");
+    let (commit, _welcome, _group_info) = alice_group
+        .self_update(
+            alice_provider,
+            &alice_credential_with_keys.signer,
+            LeafNodeParameters::default(),
+        )
+        .expect("An unexpected error occurred.")
+        .into_contents();
+    alice_group
+        .merge_pending_commit(alice_provider)
+        .expect("error merging pending commit");
+
+    bob_group
+        .set_configuration(
+            bob_provider.storage(),
+            mls_group_create_config.join_config(),
+        )
+        .expect("error setting the configuration");
+    let processed_message = bob_group
+        .process_message(bob_provider, commit.into_protocol_message().unwrap())
+        .expect("An unexpected error occurred.");
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        unreachable!("Expected a StagedCommit.");
+    };
+    bob_group
+        .merge_staged_commit(bob_provider, *staged_commit)
+        .expect("Error merging commit.");
+
+    bob_group
+        .delete_past_epoch_secrets(bob_provider.storage())
+        .expect("error deleting past epoch secrets");
+    bob_group
+        .process_message(
+            bob_provider,
+            application_message.into_protocol_message().unwrap(),
+        )
+        .expect_err("Deleted secrets were used to decrypt a message.");
+}
@@ -71,7 +71,8 @@ fn create_group_with_members<Provider: OpenMlsProvider>(
         &alice_credential_with_key_and_signer.signer,
         &MlsGroupCreateConfig::builder()
             .ciphersuite(ciphersuite)
-            .build(),
+            .build()
+            .unwrap(),
         GroupId::from_slice(b"Alice's Friends"),
         alice_credential_with_key_and_signer
             .credential_with_key
@@ -117,7 +118,8 @@ fn new_test_group(
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .wire_format_policy(wire_format_policy)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     (
         MlsGroup::new_with_group_id(
@@ -648,7 +650,8 @@ fn test_valsem101b() {
             &alice_credential_with_key.signer,
             &MlsGroupCreateConfig::builder()
                 .ciphersuite(ciphersuite)
-                .build(),
+                .build()
+                .unwrap(),
             GroupId::from_slice(b"Alice's Friends"),
             alice_credential_with_key.credential_with_key.clone(),
         )
@@ -48,7 +48,8 @@ fn remove_blank() {
     // Define the MlsGroup configuration
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     // === Alice creates a group ===
     let mut alice_group = MlsGroup::new_with_group_id(
@@ -167,7 +168,8 @@ fn test_remove_operation_variants() {
         // Define the MlsGroup configuration
         let mls_group_create_config = MlsGroupCreateConfig::builder()
             .ciphersuite(ciphersuite)
-            .build();
+            .build()
+            .unwrap();
 
         // === Alice creates a group ===
         let mut alice_group = MlsGroup::new_with_group_id(
@@ -25,7 +25,8 @@ fn create_group(
         .wire_format_policy(wire_format_policy)
         .use_ratchet_tree_extension(true)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     (
         MlsGroup::new_with_group_id(
@@ -0,0 +1,138 @@
+//! # Tree previews
+//!
+//! A [`TreePreview`] applies hypothetical adds, removes and updates to a copy
+//! of the ratchet tree of a group, without creating proposals or commits. The
+//! resulting [`TreePreviewOutcome`] describes what the tree would look like,
+//! so that tooling can answer "what if" questions, e.g. which nodes would be
+//! blanked by a set of removes.
+//!
+//! Note that the preview doesn't validate the changes the way proposal
+//! validation would, and that it doesn't model the update path of a commit.
+
+use openmls_traits::crypto::OpenMlsCrypto;
+
+use super::{errors::TreePreviewError, PublicGroup};
+use crate::{
+    binary_tree::{LeafNodeIndex, ParentNodeIndex},
+    error::LibraryError,
+    key_packages::KeyPackage,
+    treesync::{diff::TreeSyncDiff, errors::TreeSyncAddLeaf, LeafNode},
+};
+
+/// A set of hypothetical changes to the ratchet tree of a group. See the
+/// [module documentation](self) for details.
+pub struct TreePreview<'a> {
+    group: &'a PublicGroup,
+    diff: TreeSyncDiff<'a>,
+}
+
+impl<'a> TreePreview<'a> {
+    pub(super) fn new(group: &'a PublicGroup) -> Self {
+        Self {
+            group,
+            diff: group.treesync().empty_diff(),
+        }
+    }
+
+    /// Adds the leaf node of the `key_package` to the leftmost free leaf, like
+    /// an Add proposal would, and returns the index of the new leaf.
+    pub fn add(&mut self, key_package: &KeyPackage) -> Result<LeafNodeIndex, TreePreviewError> {
+        self.diff
+            .add_leaf(key_package.leaf_node().clone())
+            .map_err(|e| match e {
+                TreeSyncAddLeaf::LibraryError(e) => e.into(),
+                TreeSyncAddLeaf::TreeFull => TreePreviewError::TreeFull,
+            })
+    }
+
+    /// Blanks the leaf at `leaf_index` and its direct path, like a Remove
+    /// proposal would.
+    pub fn remove(&mut self, leaf_index: LeafNodeIndex) -> Result<(), TreePreviewError> {
+        if self.diff.leaf(leaf_index).is_none() {
+            return Err(TreePreviewError::UnknownMember);
+        }
+        self.diff.blank_leaf(leaf_index);
+        Ok(())
+    }
+
+    /// Replaces the leaf at `leaf_index` with `leaf_node` and blanks its
+    /// direct path, like an Update proposal would.
+    pub fn update(
+        &mut self,
+        leaf_index: LeafNodeIndex,
+        leaf_node: LeafNode,
+    ) -> Result<(), TreePreviewError> {
+        if self.diff.leaf(leaf_index).is_none() {
+            return Err(TreePreviewError::UnknownMember);
+        }
+        self.diff.update_leaf(leaf_node, leaf_index);
+        Ok(())
+    }
+
+    /// Computes what the tree would look like after the changes applied so
+    /// far.
+    pub fn outcome(&self, crypto: &impl OpenMlsCrypto) -> Result<TreePreviewOutcome, LibraryError> {
+        let treesync = self.group.treesync();
+        let tree_hash = self
+            .diff
+            .compute_tree_hashes(crypto, self.group.ciphersuite())?;
+        let leaf_count = self.diff.leaf_count();
+
+        let changed_leaves = (0..leaf_count.max(treesync.tree_size().leaf_count()))
+            .map(LeafNodeIndex::new)
+            .filter(|&index| self.diff.leaf(index) != treesync.leaf(index))
+            .collect();
+
+        let parent_count = self
+            .diff
+            .tree_size()
+            .parent_count()
+            .max(treesync.tree_size().parent_count());
+        let changed_parents = (0..parent_count)
+            .map(ParentNodeIndex::new)
+            .filter(|&index| self.diff.parent(index) != treesync.parent(index))
+            .collect();
+
+        Ok(TreePreviewOutcome {
+            tree_hash,
+            leaf_count,
+            changed_leaves,
+            changed_parents,
+        })
+    }
+}
+
+/// The tree resulting from the changes in a [`TreePreview`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreePreviewOutcome {
+    tree_hash: Vec<u8>,
+    leaf_count: u32,
+    changed_leaves: Vec<LeafNodeIndex>,
+    changed_parents: Vec<ParentNodeIndex>,
+}
+
+impl TreePreviewOutcome {
+    /// Returns the tree hash of the resulting tree.
+    pub fn tree_hash(&self) -> &[u8] {
+        &self.tree_hash
+    }
+
+    /// Returns the number of leaves of the resulting tree, including blank
+    /// leaves.
+    pub fn leaf_count(&self) -> u32 {
+        self.leaf_count
+    }
+
+    /// Returns the indices of the leaves that were added, removed or changed,
+    /// including leaves that were trimmed from the tree.
+    pub fn changed_leaves(&self) -> &[LeafNodeIndex] {
+        &self.changed_leaves
+    }
+
+    /// Returns the indices of the parent nodes that were blanked or whose
+    /// unmerged leaves changed, including nodes that were trimmed from the
+    /// tree.
+    pub fn changed_parents(&self) -> &[ParentNodeIndex] {
+        &self.changed_parents
+    }
+}
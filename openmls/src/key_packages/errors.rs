@@ -4,7 +4,10 @@
 
 use thiserror::Error;
 
-use crate::{ciphersuite::signable::SignatureError, error::LibraryError};
+use crate::{
+    ciphersuite::signable::SignatureError, error::LibraryError,
+    treesync::errors::LeafNodeValidationError,
+};
 
 /// KeyPackage verify error
 #[derive(Error, Debug, PartialEq, Clone)]
@@ -61,4 +64,38 @@ pub enum KeyPackageNewError {
     /// See [`SignatureError`] for more details.
     #[error(transparent)]
     SignatureError(#[from] SignatureError),
+    /// The leaf node capabilities are inconsistent with the leaf node. See
+    /// [`LeafNodeValidationError`] for more details.
+    #[error(transparent)]
+    LeafNodeValidation(#[from] LeafNodeValidationError),
+}
+
+/// KeyPackage refresh error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum KeyPackageRefreshError<StorageError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// Error accessing the storage.
+    #[error("Error accessing the storage.")]
+    StorageError(StorageError),
+    /// No key package with the given hash reference is stored.
+    #[error("No key package with the given hash reference is stored.")]
+    KeyPackageNotFound,
+    /// The key package was already refreshed. Refresh the newest key package
+    /// instead.
+    #[error("The key package was already refreshed.")]
+    AlreadySuperseded,
+    /// The key package was already used to join a group.
+    #[error("The key package was already used to join a group.")]
+    AlreadyUsed,
+    /// The new lifetime is not valid.
+    #[error("The new lifetime is not valid.")]
+    InvalidLifetime,
+    /// The signer doesn't match the signature key of the key package.
+    #[error("The signer doesn't match the signature key of the key package.")]
+    SignerMismatch,
+    /// See [`SignatureError`] for more details.
+    #[error(transparent)]
+    SignatureError(#[from] SignatureError),
 }
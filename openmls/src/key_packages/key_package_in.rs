@@ -2,8 +2,13 @@
 //! of KeyPackages.
 
 use crate::{
-    ciphersuite::{signable::*, *},
+    ciphersuite::{
+        hash_ref::{make_key_package_ref, KeyPackageRef},
+        signable::*,
+        *,
+    },
     credentials::*,
+    error::LibraryError,
     extensions::Extensions,
     treesync::node::leaf_node::{LeafNodeIn, VerifiableLeafNode},
     versions::ProtocolVersion,
@@ -122,6 +127,44 @@ impl KeyPackageIn {
         }
     }
 
+    /// Returns the (unverified) protocol version of the key package.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.payload.protocol_version
+    }
+
+    /// Returns the (unverified) ciphersuite of the key package.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.payload.ciphersuite
+    }
+
+    /// Returns the (unverified) init key of the key package.
+    pub fn init_key(&self) -> &InitKey {
+        &self.payload.init_key
+    }
+
+    /// Returns the (unverified) leaf node of the key package.
+    pub fn leaf_node(&self) -> &LeafNodeIn {
+        &self.payload.leaf_node
+    }
+
+    /// Returns the (unverified) extensions of the key package.
+    pub fn extensions(&self) -> &Extensions {
+        &self.payload.extensions
+    }
+
+    /// Computes the [`KeyPackageRef`] of the (unverified) key package, e.g. to
+    /// route it before it is validated.
+    pub fn hash_ref(&self, crypto: &impl OpenMlsCrypto) -> Result<KeyPackageRef, LibraryError> {
+        make_key_package_ref(
+            &self
+                .tls_serialize_detached()
+                .map_err(LibraryError::missing_bound_check)?,
+            self.payload.ciphersuite,
+            crypto,
+        )
+        .map_err(LibraryError::unexpected_crypto_error)
+    }
+
     /// Verify that this key package is valid:
     /// * verify that the signature on this key package is valid
     /// * verify that the signature on the leaf node is valid
@@ -110,7 +110,7 @@ use crate::{
     versions::ProtocolVersion,
 };
 use openmls_traits::{
-    crypto::OpenMlsCrypto, signatures::Signer, storage::StorageProvider, types::Ciphersuite,
+    crypto::OpenMlsCrypto, signatures::Signer, storage::StorageWriter, types::Ciphersuite,
 };
 use serde::{Deserialize, Serialize};
 use tls_codec::{
@@ -123,6 +123,8 @@ use errors::*;
 // Public
 pub mod errors;
 pub mod key_package_in;
+pub mod refresh;
+pub mod retention;
 
 mod lifetime;
 
@@ -133,6 +135,8 @@ pub(crate) mod tests;
 // Public types
 pub use key_package_in::KeyPackageIn;
 pub use lifetime::Lifetime;
+pub use refresh::refresh_key_package;
+pub use retention::{InitKeyRetention, RetentionReason};
 
 /// The unsigned payload of a key package.
 /// Any modification must happen on this unsigned struct. Use `sign` to get a
@@ -271,6 +275,14 @@ impl KeyPackage {
             return Err(KeyPackageNewError::CiphersuiteSignatureSchemeMismatch);
         }
 
+        // Make sure peers won't reject the leaf node because of inconsistent
+        // capabilities.
+        leaf_node_capabilities.validate_for_leaf_node(
+            ciphersuite,
+            credential_with_key.credential.credential_type(),
+            &leaf_node_extensions,
+        )?;
+
         // Create a new HPKE key pair
         let ikm = Secret::random(ciphersuite, provider.rand())
             .map_err(LibraryError::unexpected_crypto_error)?;
@@ -417,6 +429,26 @@ impl KeyPackage {
     pub(crate) fn protocol_version(&self) -> ProtocolVersion {
         self.payload.protocol_version
     }
+
+    /// Returns a copy of this key package with the given `lifetime`, signed
+    /// with `signer`. All other values, including the init and encryption
+    /// keys, are kept.
+    pub(crate) fn with_lifetime(
+        &self,
+        lifetime: Lifetime,
+        signer: &impl Signer,
+    ) -> Result<Self, SignatureError> {
+        let leaf_node = self.payload.leaf_node.with_lifetime(lifetime, signer)?;
+        let key_package_tbs = KeyPackageTbs {
+            protocol_version: self.payload.protocol_version,
+            ciphersuite: self.payload.ciphersuite,
+            init_key: self.payload.init_key.clone(),
+            leaf_node,
+            extensions: self.payload.extensions.clone(),
+        };
+
+        key_package_tbs.sign(signer)
+    }
 }
 
 /// Builder that helps creating (and configuring) a [`KeyPackage`].
@@ -535,6 +567,9 @@ impl KeyPackageBuilder {
             key_package,
             private_init_key: init_private_key,
             private_encryption_key: encryption_keypair.private_key().clone(),
+            retained: None,
+            superseded_by: None,
+            supersedes: None,
         };
         provider
             .storage()
@@ -555,6 +590,15 @@ pub struct KeyPackageBundle {
     pub(crate) key_package: KeyPackage,
     pub(crate) private_init_key: HpkePrivateKey,
     pub(crate) private_encryption_key: EncryptionPrivateKey,
+    /// Set once the bundle was used to join a group and is retained anyway.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) retained: Option<RetentionReason>,
+    /// Set once the key package was refreshed, see [`refresh_key_package()`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) superseded_by: Option<KeyPackageRef>,
+    /// Set if the key package is a refreshed copy of another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) supersedes: Option<KeyPackageRef>,
 }
 
 // Public `KeyPackageBundle` functions.
@@ -590,6 +634,9 @@ impl KeyPackageBundle {
             key_package,
             private_init_key,
             private_encryption_key,
+            retained: None,
+            superseded_by: None,
+            supersedes: None,
         }
     }
 
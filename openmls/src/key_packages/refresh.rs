@@ -0,0 +1,152 @@
+//! # Key package refresh
+//!
+//! A stored [`KeyPackage`](super::KeyPackage) can be re-issued with a new [`Lifetime`] using
+//! [`refresh_key_package()`], e.g. to extend the validity of a published pool
+//! of key packages without generating new HPKE keys. The refreshed key package
+//! keeps the init key, the encryption key and all other values and is signed
+//! again.
+//!
+//! The refreshed key package has a new hash reference. The superseded one is
+//! kept in the storage, so that Welcomes for it can still be processed, and
+//! is marked with [`KeyPackageBundle::superseded_by()`]. Since all copies of a
+//! key package share their private keys, the other copies are deleted once
+//! one of them was used to join a group, unless it is a last resort key
+//! package. Superseded copies whose lifetime is over are deleted with
+//! [`purge_superseded_key_packages()`].
+
+use openmls_traits::{
+    signatures::Signer,
+    storage::{StorageReader as _, StorageWriter as _},
+};
+
+use super::{errors::KeyPackageRefreshError, KeyPackageBundle, KeyPackageIn, Lifetime};
+use crate::{
+    ciphersuite::hash_ref::KeyPackageRef,
+    error::LibraryError,
+    key_packages::errors::KeyPackageVerifyError,
+    storage::{OpenMlsProvider, StorageProvider, StorageReader},
+};
+
+impl KeyPackageBundle {
+    /// Returns the hash reference of the refreshed copy of this key package,
+    /// or `None` if it wasn't refreshed.
+    pub fn superseded_by(&self) -> Option<&KeyPackageRef> {
+        self.superseded_by.as_ref()
+    }
+
+    /// Returns the hash reference of the key package this one is a refreshed
+    /// copy of, or `None` if it isn't a copy.
+    pub fn supersedes(&self) -> Option<&KeyPackageRef> {
+        self.supersedes.as_ref()
+    }
+
+    /// Returns the hash references of the other stored copies of this key
+    /// package, which share its private keys.
+    pub(crate) fn copies<Storage: StorageReader>(
+        &self,
+        storage: &Storage,
+    ) -> Result<Vec<KeyPackageRef>, Storage::Error> {
+        let mut copies = Vec::new();
+        for (mut next, forward) in [
+            (self.supersedes.clone(), false),
+            (self.superseded_by.clone(), true),
+        ] {
+            while let Some(hash_ref) = next {
+                let bundle: Option<KeyPackageBundle> = storage.key_package(&hash_ref)?;
+                copies.push(hash_ref);
+                next = bundle.and_then(|bundle| {
+                    if forward {
+                        bundle.superseded_by
+                    } else {
+                        bundle.supersedes
+                    }
+                });
+            }
+        }
+
+        Ok(copies)
+    }
+}
+
+/// Re-issues the stored key package with the hash reference `hash_ref` with
+/// the given `lifetime`, keeping its keys. The refreshed key package is
+/// stored and returned, and the old one is marked as superseded. See the
+/// [module documentation](self) for details.
+///
+/// The `signer` must be the one the key package was created with.
+pub fn refresh_key_package<Provider: OpenMlsProvider>(
+    provider: &Provider,
+    signer: &impl Signer,
+    hash_ref: &KeyPackageRef,
+    lifetime: Lifetime,
+) -> Result<KeyPackageBundle, KeyPackageRefreshError<Provider::StorageError>> {
+    let storage = provider.storage();
+    let mut bundle: KeyPackageBundle = storage
+        .key_package(hash_ref)
+        .map_err(KeyPackageRefreshError::StorageError)?
+        .ok_or(KeyPackageRefreshError::KeyPackageNotFound)?;
+    if bundle.superseded_by.is_some() {
+        return Err(KeyPackageRefreshError::AlreadySuperseded);
+    }
+    if bundle.retained.is_some() {
+        return Err(KeyPackageRefreshError::AlreadyUsed);
+    }
+    if !lifetime.is_valid() {
+        return Err(KeyPackageRefreshError::InvalidLifetime);
+    }
+    let ciphersuite = bundle.key_package.ciphersuite();
+    if signer.signature_scheme() != ciphersuite.signature_algorithm() {
+        return Err(KeyPackageRefreshError::SignerMismatch);
+    }
+
+    // Make sure that the signer matches the signature key in the leaf node,
+    // so that peers don't reject the refreshed key package.
+    let key_package = KeyPackageIn::from(bundle.key_package.with_lifetime(lifetime, signer)?)
+        .validate(provider.crypto(), bundle.key_package.protocol_version())
+        .map_err(|e| match e {
+            KeyPackageVerifyError::InvalidSignature
+            | KeyPackageVerifyError::InvalidLeafNodeSignature => {
+                KeyPackageRefreshError::SignerMismatch
+            }
+            _ => LibraryError::custom("refreshed key package is invalid").into(),
+        })?;
+    let new_hash_ref = key_package.hash_ref(provider.crypto())?;
+
+    let refreshed = KeyPackageBundle {
+        key_package,
+        private_init_key: bundle.private_init_key.clone(),
+        private_encryption_key: bundle.private_encryption_key.clone(),
+        retained: None,
+        superseded_by: None,
+        supersedes: Some(hash_ref.clone()),
+    };
+    storage
+        .write_key_package(&new_hash_ref, &refreshed)
+        .map_err(KeyPackageRefreshError::StorageError)?;
+    bundle.superseded_by = Some(new_hash_ref);
+    storage
+        .write_key_package(hash_ref, &bundle)
+        .map_err(KeyPackageRefreshError::StorageError)?;
+
+    Ok(refreshed)
+}
+
+/// Deletes the superseded key packages whose lifetime is over from the
+/// `storage` and returns their hash references.
+pub fn purge_superseded_key_packages<Storage: StorageProvider>(
+    storage: &Storage,
+) -> Result<Vec<KeyPackageRef>, Storage::Error> {
+    let mut purged = Vec::new();
+    for hash_ref in storage.key_package_refs::<KeyPackageRef>()? {
+        let bundle: Option<KeyPackageBundle> = storage.key_package(&hash_ref)?;
+        let Some(bundle) = bundle else {
+            continue;
+        };
+        if bundle.superseded_by.is_some() && !bundle.key_package.life_time().is_valid() {
+            storage.delete_key_package(&hash_ref)?;
+            purged.push(hash_ref);
+        }
+    }
+
+    Ok(purged)
+}
@@ -0,0 +1,185 @@
+//! # Init key retention
+//!
+//! After a [`Welcome`](crate::messages::Welcome) was processed, the
+//! [`KeyPackageBundle`] that was used to join, including the private init and
+//! encryption keys, is handled according to the [`InitKeyRetention`] of the
+//! [`MlsGroupJoinConfig`](crate::group::MlsGroupJoinConfig):
+//!
+//! - [`InitKeyRetention::Delete`] (the default) deletes it immediately.
+//! - [`InitKeyRetention::GracePeriod`] keeps it for the given period, so that
+//!   duplicate Welcomes can still be decrypted.
+//!
+//! The id of the joined group is stored for the reference of the key package.
+//! A duplicate Welcome that can still be decrypted with a retained key package
+//! is reported as
+//! [`WelcomeError::AlreadyJoined`](crate::group::WelcomeError::AlreadyJoined)
+//! if the key package joined the same group, which is still stored, provided
+//! the storage implements
+//! [`StorageWriter::write_joined_group_id()`](openmls_traits::storage::StorageWriter::write_joined_group_id).
+//! Once the key package is deleted, a duplicate Welcome fails with
+//! [`WelcomeError::NoMatchingKeyPackage`](crate::group::WelcomeError::NoMatchingKeyPackage).
+//!
+//! Key packages with the last resort extension are meant to be used for more
+//! than one join and are always retained, independently of the
+//! [`InitKeyRetention`].
+//!
+//! Retained key packages are marked with a [`RetentionReason`] in the storage
+//! and can be listed with [`retained_key_packages()`]. Key packages whose grace
+//! period is over are not used to join anymore and are deleted with
+//! [`purge_expired_key_packages()`], and [`purge_retained_key_packages()`]
+//! deletes all of them.
+//!
+//! Refreshed copies of a key package (see
+//! [`refresh_key_package()`](super::refresh_key_package)) are deleted when
+//! one of them is used to join a group, since they share the private keys.
+
+use std::time::Duration;
+
+#[cfg(target_arch = "wasm32")]
+use fluvio_wasm_timer::{SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::KeyPackageBundle;
+use crate::{
+    ciphersuite::hash_ref::KeyPackageRef,
+    storage::{StorageProvider, StorageReader},
+};
+
+/// Defines how a [`KeyPackageBundle`] is handled after it was used to join a
+/// group. See the [module documentation](self) for details.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InitKeyRetention {
+    /// Delete the key package right after the Welcome was processed.
+    #[default]
+    Delete,
+    /// Keep the key package for the given period after the Welcome was
+    /// processed.
+    GracePeriod(Duration),
+}
+
+/// The reason why a [`KeyPackageBundle`] is still stored after it was used to
+/// join a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionReason {
+    /// The key package is retained until the given time, see
+    /// [`InitKeyRetention::GracePeriod`].
+    GracePeriod {
+        /// The time at which the grace period is over, in seconds since the
+        /// UNIX epoch.
+        until: u64,
+    },
+    /// The key package has the last resort extension.
+    LastResort,
+}
+
+impl RetentionReason {
+    /// Returns `true` if the grace period of the key package is over.
+    pub fn is_expired(&self) -> bool {
+        match self {
+            RetentionReason::GracePeriod { until } => *until <= now(),
+            RetentionReason::LastResort => false,
+        }
+    }
+}
+
+impl KeyPackageBundle {
+    /// Returns why this bundle is still stored after it was used to join a
+    /// group, or `None` if it wasn't used yet.
+    pub fn retention_reason(&self) -> Option<RetentionReason> {
+        self.retained
+    }
+
+    /// Deletes this bundle from the `storage`, or marks it as retained, after
+    /// it was used to join a group.
+    pub(crate) fn retain_or_delete<Storage: StorageProvider>(
+        mut self,
+        storage: &Storage,
+        hash_ref: &KeyPackageRef,
+        retention: InitKeyRetention,
+    ) -> Result<(), Storage::Error> {
+        if self.retained.is_some() {
+            // The bundle was used before. Keep the original reason, so that a
+            // duplicate Welcome doesn't extend the grace period.
+            return Ok(());
+        }
+        self.retained = match (self.key_package.last_resort(), retention) {
+            (true, _) => Some(RetentionReason::LastResort),
+            (false, InitKeyRetention::GracePeriod(period)) => Some(RetentionReason::GracePeriod {
+                until: now().saturating_add(period.as_secs()),
+            }),
+            (false, InitKeyRetention::Delete) => None,
+        };
+        // Refreshed copies share the private keys and must not be used to
+        // join another group, unless the key package is a last resort one.
+        if !self.key_package.last_resort() {
+            for copy in self.copies(storage)? {
+                storage.delete_key_package(&copy)?;
+            }
+        }
+        match self.retained {
+            Some(_) => storage.write_key_package(hash_ref, &self),
+            None => storage.delete_key_package(hash_ref),
+        }
+    }
+}
+
+/// Returns the current time in seconds since the UNIX epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_else(|_| {
+            log::error!("SystemTime before UNIX EPOCH.");
+            0
+        })
+}
+
+/// Returns the hash references of the key packages that are still stored
+/// after they were used to join a group, together with the reason.
+pub fn retained_key_packages<Storage: StorageReader>(
+    storage: &Storage,
+) -> Result<Vec<(KeyPackageRef, RetentionReason)>, Storage::Error> {
+    let mut retained = Vec::new();
+    for hash_ref in storage.key_package_refs::<KeyPackageRef>()? {
+        let bundle: Option<KeyPackageBundle> = storage.key_package(&hash_ref)?;
+        if let Some(reason) = bundle.and_then(|bundle| bundle.retained) {
+            retained.push((hash_ref, reason));
+        }
+    }
+
+    Ok(retained)
+}
+
+/// Deletes the retained key packages whose grace period is over from the
+/// `storage` and returns their hash references.
+pub fn purge_expired_key_packages<Storage: StorageProvider>(
+    storage: &Storage,
+) -> Result<Vec<KeyPackageRef>, Storage::Error> {
+    purge(storage, RetentionReason::is_expired)
+}
+
+/// Deletes all retained key packages from the `storage`, including last resort
+/// key packages that were used before, and returns their hash references.
+pub fn purge_retained_key_packages<Storage: StorageProvider>(
+    storage: &Storage,
+) -> Result<Vec<KeyPackageRef>, Storage::Error> {
+    purge(storage, |_| true)
+}
+
+fn purge<Storage: StorageProvider>(
+    storage: &Storage,
+    filter: impl Fn(&RetentionReason) -> bool,
+) -> Result<Vec<KeyPackageRef>, Storage::Error> {
+    let mut purged = Vec::new();
+    for (hash_ref, reason) in retained_key_packages(storage)? {
+        if filter(&reason) {
+            storage.delete_key_package(&hash_ref)?;
+            purged.push(hash_ref);
+        }
+    }
+
+    Ok(purged)
+}
@@ -176,6 +176,7 @@ pub mod group;
 pub mod key_packages;
 pub mod messages;
 pub mod schedule;
+pub mod tree_math;
 pub mod treesync;
 pub mod versions;
 
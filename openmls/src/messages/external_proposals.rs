@@ -2,21 +2,23 @@
 //!
 //! Contains the types and methods to build external proposal to add/remove a client from a MLS group
 //!
-//! `Add` (from external sender), `Remove` & `ReInit are not yet implemented`
+//! External `ReInit` proposals are not yet implemented
 
 use crate::{
     binary_tree::LeafNodeIndex,
-    extensions::SenderExtensionIndex,
+    error::LibraryError,
+    extensions::{ExternalSendersExtension, SenderExtensionIndex},
     framing::{mls_auth_content::AuthenticatedContent, MlsMessageOut, PublicMessage},
     group::{
-        errors::ProposeRemoveMemberError, mls_group::errors::ProposeAddMemberError, GroupEpoch,
-        GroupId,
+        errors::ProposeRemoveMemberError, mls_group::errors::ProposeAddMemberError, GroupContext,
+        GroupEpoch, GroupId,
     },
     key_packages::KeyPackage,
     messages::{AddProposal, Proposal},
     storage::{OpenMlsProvider, StorageProvider},
 };
-use openmls_traits::signatures::Signer;
+use openmls_traits::{signatures::Signer, types::SignatureScheme};
+use thiserror::Error;
 
 use super::proposals::RemoveProposal;
 
@@ -83,9 +85,174 @@ impl ExternalProposal {
             epoch,
             signer,
             sender_index,
+            &[],
         )
         .map(PublicMessage::from)
         .map(MlsMessageOut::from)
         .map_err(ProposeRemoveMemberError::from)
     }
+
+    /// Creates an external Add proposal. For delivery services requesting to add a client.
+    /// This proposal will have to be committed later by a group member.
+    ///
+    /// # Arguments
+    /// * `key_package` - of the client to add
+    /// * `group_id` - unique group identifier of the group to join
+    /// * `epoch` - group's epoch
+    /// * `signer` - of the sender to sign the message
+    /// * `sender` - index of the sender of the proposal (in the [crate::extensions::ExternalSendersExtension] array
+    ///   from the Group Context)
+    pub fn new_add<Provider: OpenMlsProvider>(
+        key_package: KeyPackage,
+        group_id: GroupId,
+        epoch: GroupEpoch,
+        signer: &impl Signer,
+        sender_index: SenderExtensionIndex,
+    ) -> Result<MlsMessageOut, ProposeAddMemberError<Provider::StorageError>> {
+        AuthenticatedContent::new_external_proposal(
+            Proposal::Add(AddProposal { key_package }),
+            group_id,
+            epoch,
+            signer,
+            sender_index,
+            &[],
+        )
+        .map(PublicMessage::from)
+        .map(MlsMessageOut::from)
+        .map_err(ProposeAddMemberError::from)
+    }
+}
+
+/// Builder for external proposals, see [`ExternalProposal::add_builder()`] and
+/// [`ExternalProposal::remove_builder()`].
+///
+/// The builder doesn't need an [`MlsGroup`](crate::group::MlsGroup). When it
+/// is given the [`GroupContext`] of the group, it also checks that the sender
+/// is one of the external senders of the group and that the signer uses the
+/// signature scheme of the group.
+#[derive(Debug, Clone)]
+pub struct ExternalProposalBuilder {
+    proposal: Proposal,
+    sender_index: SenderExtensionIndex,
+    group_id: Option<GroupId>,
+    epoch: Option<GroupEpoch>,
+    external_senders: Option<(ExternalSendersExtension, SignatureScheme)>,
+    aad: Vec<u8>,
+}
+
+/// External proposal build error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ExternalProposalBuildError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// Neither a group context nor a group id and epoch were provided.
+    #[error("Neither a group context nor a group id and epoch were provided.")]
+    MissingGroupInfo,
+    /// The sender index doesn't point to an external sender of the group.
+    #[error("The sender index doesn't point to an external sender of the group.")]
+    UnknownExternalSender,
+    /// The signer doesn't use the signature scheme of the group.
+    #[error("The signer doesn't use the signature scheme of the group.")]
+    SignatureSchemeMismatch,
+}
+
+impl ExternalProposal {
+    /// Returns a builder for an external Add proposal that adds the client
+    /// with the given `key_package` and is sent by the external sender at
+    /// `sender_index` in the [crate::extensions::ExternalSendersExtension] of
+    /// the group.
+    pub fn add_builder(
+        key_package: KeyPackage,
+        sender_index: SenderExtensionIndex,
+    ) -> ExternalProposalBuilder {
+        ExternalProposalBuilder::new(Proposal::Add(AddProposal { key_package }), sender_index)
+    }
+
+    /// Returns a builder for an external Remove proposal that removes the
+    /// member at leaf index `removed` and is sent by the external sender at
+    /// `sender_index` in the [crate::extensions::ExternalSendersExtension] of
+    /// the group.
+    pub fn remove_builder(
+        removed: LeafNodeIndex,
+        sender_index: SenderExtensionIndex,
+    ) -> ExternalProposalBuilder {
+        ExternalProposalBuilder::new(Proposal::Remove(RemoveProposal { removed }), sender_index)
+    }
+}
+
+impl ExternalProposalBuilder {
+    fn new(proposal: Proposal, sender_index: SenderExtensionIndex) -> Self {
+        Self {
+            proposal,
+            sender_index,
+            group_id: None,
+            epoch: None,
+            external_senders: None,
+            aad: vec![],
+        }
+    }
+
+    /// Sets the group id and epoch from the `group_context` and validates the
+    /// sender against the external senders of the group when building.
+    pub fn group_context(mut self, group_context: &GroupContext) -> Self {
+        self.group_id = Some(group_context.group_id().clone());
+        self.epoch = Some(group_context.epoch());
+        self.external_senders = Some((
+            group_context
+                .extensions()
+                .external_senders()
+                .cloned()
+                .unwrap_or_default(),
+            group_context.ciphersuite().signature_algorithm(),
+        ));
+        self
+    }
+
+    /// Sets the id of the group.
+    pub fn group_id(mut self, group_id: GroupId) -> Self {
+        self.group_id = Some(group_id);
+        self
+    }
+
+    /// Sets the epoch the proposal is sent in. This overrides the epoch of a
+    /// group context set before.
+    pub fn epoch(mut self, epoch: GroupEpoch) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Sets the additional authenticated data of the proposal.
+    pub fn aad(mut self, aad: Vec<u8>) -> Self {
+        self.aad = aad;
+        self
+    }
+
+    /// Signs the proposal with the `signer` of the external sender and
+    /// returns it as an [`MlsMessageOut`] containing a [`PublicMessage`].
+    pub fn build(self, signer: &impl Signer) -> Result<MlsMessageOut, ExternalProposalBuildError> {
+        let (Some(group_id), Some(epoch)) = (self.group_id, self.epoch) else {
+            return Err(ExternalProposalBuildError::MissingGroupInfo);
+        };
+
+        if let Some((external_senders, signature_scheme)) = &self.external_senders {
+            if external_senders.get(self.sender_index.index()).is_none() {
+                return Err(ExternalProposalBuildError::UnknownExternalSender);
+            }
+            if signer.signature_scheme() != *signature_scheme {
+                return Err(ExternalProposalBuildError::SignatureSchemeMismatch);
+            }
+        }
+
+        let authenticated_content = AuthenticatedContent::new_external_proposal(
+            self.proposal,
+            group_id,
+            epoch,
+            signer,
+            self.sender_index,
+            &self.aad,
+        )?;
+
+        Ok(PublicMessage::from(authenticated_content).into())
+    }
 }
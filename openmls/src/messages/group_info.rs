@@ -96,7 +96,7 @@ impl VerifiableGroupInfo {
     /// Get (unverified) signer of the verifiable group info.
     ///
     /// Note: This method should only be used when necessary to verify the group info signature.
-    pub(crate) fn signer(&self) -> LeafNodeIndex {
+    pub fn signer(&self) -> LeafNodeIndex {
         self.payload.signer
     }
 
@@ -122,6 +122,22 @@ impl VerifiableGroupInfo {
     pub fn epoch(&self) -> GroupEpoch {
         self.payload.group_context.epoch()
     }
+
+    /// Get (unverified) group context of the verifiable group info.
+    ///
+    /// Note: This method should only be used when necessary to verify the group
+    /// info signature.
+    pub fn group_context(&self) -> &GroupContext {
+        &self.payload.group_context
+    }
+
+    /// Get (unverified) confirmation tag of the verifiable group info.
+    ///
+    /// Note: This method should only be used when necessary to verify the group
+    /// info signature.
+    pub fn confirmation_tag(&self) -> &ConfirmationTag {
+        &self.payload.confirmation_tag
+    }
 }
 
 #[cfg(test)]
@@ -8,6 +8,8 @@ use openmls_traits::{
     crypto::OpenMlsCrypto,
     types::{Ciphersuite, HpkeCiphertext, HpkeKeyPair},
 };
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tls_codec::{Deserialize as TlsDeserializeTrait, Serialize as TlsSerializeTrait, *};
@@ -92,7 +94,7 @@ impl Welcome {
     }
 
     /// Returns a reference to the ciphersuite in this Welcome message.
-    pub(crate) fn ciphersuite(&self) -> Ciphersuite {
+    pub fn ciphersuite(&self) -> Ciphersuite {
         self.cipher_suite
     }
 
@@ -101,6 +103,33 @@ impl Welcome {
         self.secrets.as_slice()
     }
 
+    /// Returns the [`KeyPackageRef`]s of the key packages the Welcome message
+    /// is encrypted to, e.g. to route it to the new members.
+    pub fn recipients(&self) -> impl Iterator<Item = KeyPackageRef> + '_ {
+        self.secrets.iter().map(EncryptedGroupSecrets::new_member)
+    }
+
+    /// Splits this Welcome message into one Welcome message per new member.
+    ///
+    /// Each of the resulting Welcome messages only contains the
+    /// [`EncryptedGroupSecrets`] of a single new member and is returned along
+    /// with the [`KeyPackageRef`] of that member. This allows delivering the
+    /// invitations individually instead of sending the combined Welcome to all
+    /// new members.
+    pub fn split(&self) -> Vec<(KeyPackageRef, Welcome)> {
+        self.secrets
+            .iter()
+            .map(|secrets| {
+                let welcome = Welcome {
+                    cipher_suite: self.cipher_suite,
+                    secrets: vec![secrets.clone()],
+                    encrypted_group_info: self.encrypted_group_info.clone(),
+                };
+                (secrets.new_member(), welcome)
+            })
+            .collect()
+    }
+
     /// Returns a reference to the encrypted group info.
     pub(crate) fn encrypted_group_info(&self) -> &[u8] {
         self.encrypted_group_info.as_slice()
@@ -201,6 +230,11 @@ pub(crate) struct CommitIn {
 }
 
 impl CommitIn {
+    /// Returns the proposals of the commit.
+    pub(crate) fn proposals(&self) -> &[ProposalOrRefIn] {
+        &self.proposals
+    }
+
     pub(crate) fn unverified_credential(&self) -> Option<CredentialWithKey> {
         self.path.as_ref().map(|p| {
             let credential = p.leaf_node().credential().clone();
@@ -220,9 +254,15 @@ impl CommitIn {
         sender_context: SenderContext,
         protocol_version: ProtocolVersion,
     ) -> Result<Commit, ValidationError> {
-        let proposals = self
-            .proposals
-            .into_iter()
+        // Validate the proposals, including the signatures of the key packages
+        // in add proposals, in parallel.
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let proposals = self.proposals.into_par_iter();
+        #[cfg(target_arch = "wasm32")]
+        let proposals = self.proposals.into_iter();
+
+        let proposals = proposals
             .map(|p| p.validate(crypto, ciphersuite, protocol_version))
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -301,6 +341,16 @@ impl From<Commit> for CommitIn {
 )]
 pub struct ConfirmationTag(pub(crate) Mac);
 
+impl ConfirmationTag {
+    /// Returns the confirmation tag as a slice.
+    ///
+    /// Two members that applied the same commit have the same confirmation
+    /// tag, so it can be compared out of band to confirm that.
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.mac_value.as_slice()
+    }
+}
+
 /// PathSecret
 ///
 /// > 11.2.2. Welcoming New Members
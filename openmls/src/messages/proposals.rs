@@ -24,6 +24,7 @@ use crate::{
     key_packages::*,
     prelude::LeafNode,
     schedule::psk::*,
+    treesync::node::leaf_node::is_grease,
     versions::ProtocolVersion,
 };
 
@@ -96,6 +97,12 @@ impl ProposalType {
             ProposalType::AppAck | ProposalType::Custom(_) => false,
         }
     }
+
+    /// Returns true if this is a GREASE proposal type, which has no meaning
+    /// and must be ignored.
+    pub fn is_grease(self) -> bool {
+        is_grease(self.into())
+    }
 }
 
 impl Size for ProposalType {
@@ -351,6 +358,11 @@ pub struct PreSharedKeyProposal {
 }
 
 impl PreSharedKeyProposal {
+    /// Returns the [`PreSharedKeyId`] of this proposal.
+    pub(crate) fn psk_id(&self) -> &PreSharedKeyId {
+        &self.psk
+    }
+
     /// Returns the [`PreSharedKeyId`] and consume this proposal.
     pub(crate) fn into_psk_id(self) -> PreSharedKeyId {
         self.psk
@@ -398,6 +410,43 @@ pub struct ReInitProposal {
     pub(crate) extensions: Extensions,
 }
 
+impl ReInitProposal {
+    /// Create a new ReInit proposal for a new group with the given parameters.
+    pub fn new(
+        group_id: GroupId,
+        version: ProtocolVersion,
+        ciphersuite: Ciphersuite,
+        extensions: Extensions,
+    ) -> Self {
+        Self {
+            group_id,
+            version,
+            ciphersuite,
+            extensions,
+        }
+    }
+
+    /// Returns the group ID of the new group.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the protocol version of the new group.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Returns the ciphersuite of the new group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the group context extensions of the new group.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+}
+
 /// ExternalInit Proposal.
 ///
 /// An ExternalInit proposal is used by new members that want to join a group by using an external
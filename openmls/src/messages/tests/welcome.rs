@@ -44,7 +44,8 @@ fn test_welcome_context_mismatch(
     let group_id = GroupId::random(provider.rand());
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_signature_key) =
         setup_client("Alice", ciphersuite, provider);
@@ -305,7 +306,8 @@ fn test_welcome_processing() {
     let group_id = GroupId::random(provider.rand());
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_signature_key) =
         setup_client("Alice", ciphersuite, provider);
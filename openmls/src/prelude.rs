@@ -34,18 +34,23 @@ pub use crate::framing::{
 pub use crate::key_packages::{errors::*, *};
 
 // Tree
-pub use crate::tree::sender_ratchet::SenderRatchetConfiguration;
+pub use crate::tree::sender_ratchet::{
+    SenderRatchetConfiguration, SenderRatchetConfigurationBuilder, SenderRatchetConfigurationError,
+    SenderRatchetState,
+};
 
 // Binary tree
-pub use crate::binary_tree::LeafNodeIndex;
+pub use crate::binary_tree::{LeafNodeIndex, ParentNodeIndex, TreeNodeIndex};
 
 // TreeSync
 pub use crate::treesync::{
     errors::{ApplyUpdatePathError, PublicTreeError},
-    node::leaf_node::{Capabilities, CapabilitiesBuilder, LeafNode, LeafNodeParameters},
+    node::leaf_node::{
+        Capabilities, CapabilitiesBuilder, LeafNode, LeafNodeIn, LeafNodeParameters,
+    },
     node::parent_node::ParentNode,
-    node::Node,
-    RatchetTreeIn,
+    node::{Node, NodeIn},
+    ParentHashFinding, ParentHashIssue, RatchetTreeIn,
 };
 
 // PSKs
@@ -20,6 +20,10 @@ pub enum PskError {
     /// The PSK could not be found in the store.
     #[error("The PSK could not be found in the store.")]
     KeyNotFound,
+    /// The PSK is not required, e.g. by the Welcome message it was provided
+    /// for.
+    #[error("The PSK is not required.")]
+    NotRequired,
     /// Failed to write PSK into storage.
     #[error("Failed to write PSK storage.")]
     Storage,
@@ -47,6 +51,9 @@ pub enum PskError {
         /// Got nonce length.
         got: usize,
     },
+    /// The PSK ID is empty.
+    #[error("The PSK ID is empty.")]
+    EmptyPskId,
     /// Duplicate PSK ID.
     #[error("Duplicate PSK ID. First detected duplicate is `{first:?}`.")]
     Duplicate {
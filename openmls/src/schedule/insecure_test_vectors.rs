@@ -0,0 +1,138 @@
+//! # ☣️ Export of epoch secrets for test vector generation
+//!
+//! **WARNING**: This module is only available with the
+//! `insecure-test-vectors` feature. It exposes secrets of the key schedule
+//! that must never leave an MLS client in production. It is meant for
+//! conformance tooling and analyses that generate test vectors or cross-check
+//! them against other implementations.
+
+use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+
+use super::{
+    message_secrets::MessageSecrets, psk::PskSecret, CommitSecret, GroupEpochSecrets, InitSecret,
+    JoinerSecret, KeySchedule,
+};
+use crate::{ciphersuite::Secret, error::LibraryError};
+
+/// The secrets of a single epoch of the key schedule.
+///
+/// Secrets that are not available are `None`. This is the case for the
+/// `joiner_secret`, `welcome_secret`, `epoch_secret` and `encryption_secret`
+/// of a group's current epoch, which OpenMLS discards as soon as the secrets
+/// derived from them are computed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsecureEpochSecrets {
+    /// The joiner secret.
+    pub joiner_secret: Option<Vec<u8>>,
+    /// The welcome secret.
+    pub welcome_secret: Option<Vec<u8>>,
+    /// The epoch secret.
+    pub epoch_secret: Option<Vec<u8>>,
+    /// The encryption secret, i.e. the root of the secret tree.
+    pub encryption_secret: Option<Vec<u8>>,
+    /// The init secret for the next epoch.
+    pub init_secret: Vec<u8>,
+    /// The sender data secret.
+    pub sender_data_secret: Vec<u8>,
+    /// The exporter secret.
+    pub exporter_secret: Vec<u8>,
+    /// The epoch authenticator.
+    pub epoch_authenticator: Vec<u8>,
+    /// The external secret.
+    pub external_secret: Vec<u8>,
+    /// The confirmation key.
+    pub confirmation_key: Vec<u8>,
+    /// The membership key.
+    pub membership_key: Vec<u8>,
+    /// The resumption PSK.
+    pub resumption_psk: Vec<u8>,
+}
+
+/// Runs one epoch of the key schedule on the given inputs and returns all
+/// secrets of the epoch.
+///
+/// The inputs correspond to the ones of the key schedule test vectors of the
+/// MLS test vector suite: the `init_secret` of the previous epoch, the
+/// `commit_secret` and `psk_secret` of the commit, and the TLS-serialized
+/// `GroupContext` of the new epoch.
+pub fn derive_epoch_secrets(
+    crypto: &impl OpenMlsCrypto,
+    ciphersuite: Ciphersuite,
+    init_secret: &[u8],
+    commit_secret: &[u8],
+    psk_secret: &[u8],
+    serialized_group_context: &[u8],
+) -> Result<InsecureEpochSecrets, LibraryError> {
+    let init_secret = InitSecret::from(Secret::from_slice(init_secret));
+    let commit_secret = CommitSecret {
+        secret: Secret::from_slice(commit_secret),
+    };
+    let psk_secret = PskSecret::from(Secret::from_slice(psk_secret));
+
+    let joiner_secret = JoinerSecret::new(
+        crypto,
+        ciphersuite,
+        commit_secret,
+        &init_secret,
+        serialized_group_context,
+    )
+    .map_err(LibraryError::unexpected_crypto_error)?;
+
+    let mut key_schedule = KeySchedule::init(ciphersuite, crypto, &joiner_secret, psk_secret)?;
+    let welcome_secret = key_schedule
+        .welcome(crypto, ciphersuite)
+        .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+    key_schedule
+        .add_context(crypto, serialized_group_context)
+        .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+    let epoch_secret = key_schedule
+        .epoch_secret
+        .as_ref()
+        .map(|epoch_secret| epoch_secret.secret.as_slice().to_vec());
+    let epoch_secrets = key_schedule
+        .epoch_secrets(crypto, ciphersuite)
+        .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+
+    Ok(InsecureEpochSecrets {
+        joiner_secret: Some(joiner_secret.secret.as_slice().to_vec()),
+        welcome_secret: Some(welcome_secret.secret.as_slice().to_vec()),
+        epoch_secret,
+        encryption_secret: Some(epoch_secrets.encryption_secret.secret.as_slice().to_vec()),
+        init_secret: epoch_secrets.init_secret.secret.as_slice().to_vec(),
+        sender_data_secret: epoch_secrets.sender_data_secret.secret.as_slice().to_vec(),
+        exporter_secret: epoch_secrets.exporter_secret.secret.as_slice().to_vec(),
+        epoch_authenticator: epoch_secrets.epoch_authenticator.as_slice().to_vec(),
+        external_secret: epoch_secrets.external_secret.secret.as_slice().to_vec(),
+        confirmation_key: epoch_secrets.confirmation_key.secret.as_slice().to_vec(),
+        membership_key: epoch_secrets.membership_key.secret.as_slice().to_vec(),
+        resumption_psk: epoch_secrets.resumption_psk.as_slice().to_vec(),
+    })
+}
+
+impl GroupEpochSecrets {
+    /// Export the secrets of the current epoch that are retained by a group.
+    pub(crate) fn insecure_export(&self, message_secrets: &MessageSecrets) -> InsecureEpochSecrets {
+        InsecureEpochSecrets {
+            joiner_secret: None,
+            welcome_secret: None,
+            epoch_secret: None,
+            encryption_secret: None,
+            init_secret: self.init_secret.secret.as_slice().to_vec(),
+            sender_data_secret: message_secrets
+                .sender_data_secret()
+                .secret
+                .as_slice()
+                .to_vec(),
+            exporter_secret: self.exporter_secret.secret.as_slice().to_vec(),
+            epoch_authenticator: self.epoch_authenticator.as_slice().to_vec(),
+            external_secret: self.external_secret.secret.as_slice().to_vec(),
+            confirmation_key: message_secrets
+                .confirmation_key()
+                .secret
+                .as_slice()
+                .to_vec(),
+            membership_key: message_secrets.membership_key().secret.as_slice().to_vec(),
+            resumption_psk: self.resumption_psk.as_slice().to_vec(),
+        }
+    }
+}
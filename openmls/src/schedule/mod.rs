@@ -138,6 +138,8 @@ use crate::{
 
 // Public
 pub mod errors;
+#[cfg(feature = "insecure-test-vectors")]
+pub mod insecure_test_vectors;
 pub mod psk;
 
 // Crate
@@ -154,7 +156,7 @@ use psk::PskSecret;
 pub mod tests_and_kats;
 
 // Public types
-pub use psk::{ExternalPsk, PreSharedKeyId, Psk};
+pub use psk::{ExternalPsk, PreSharedKeyId, Psk, PskResolver, PskStore};
 
 /// A group secret that can be used among members to prove that a member was
 /// part of a group in a given epoch.
@@ -181,6 +183,14 @@ impl ResumptionPskSecret {
     pub fn as_slice(&self) -> &[u8] {
         self.secret.as_slice()
     }
+
+    /// Creates a resumption PSK from an exported secret.
+    #[cfg(feature = "resumption-psk-backup")]
+    pub(crate) fn from_slice(bytes: &[u8]) -> Self {
+        Self {
+            secret: Secret::from_slice(bytes),
+        }
+    }
 }
 
 /// A secret that can be used among members to make sure everyone has the same
@@ -266,10 +276,10 @@ impl From<Secret> for InitSecret {
 /// the `init_secret` when creating or processing a commit with an external init
 /// proposal. TODO: #628.
 fn hpke_info_from_version(version: ProtocolVersion) -> &'static str {
-    match version {
-        ProtocolVersion::Mls10 => "MLS 1.0 external init secret",
-        _ => "<OpenMLS reserved; Don't use this.>",
-    }
+    version
+        .behavior()
+        .map(|behavior| behavior.external_init_info)
+        .unwrap_or("<OpenMLS reserved; Don't use this.>")
 }
 
 impl InitSecret {
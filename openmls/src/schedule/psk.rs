@@ -2,15 +2,18 @@
 
 use std::borrow::Borrow;
 
-use openmls_traits::{random::OpenMlsRand, storage::StorageProvider as StorageProviderTrait};
+use openmls_traits::{
+    random::OpenMlsRand,
+    storage::{StorageReader as StorageReaderTrait, StorageWriter as _, CURRENT_VERSION},
+};
 use serde::{Deserialize, Serialize};
-use tls_codec::{Serialize as TlsSerializeTrait, VLBytes};
+use tls_codec::{Deserialize as TlsDeserializeTrait, Serialize as TlsSerializeTrait, VLBytes};
 
 use super::*;
 use crate::{
     group::{GroupEpoch, GroupId},
     schedule::psk::store::ResumptionPskStore,
-    storage::{OpenMlsProvider, StorageProvider},
+    storage::{OpenMlsProvider, StorageProvider, StorageReader},
 };
 
 /// Resumption PSK usage.
@@ -87,6 +90,37 @@ impl ExternalPsk {
     pub fn psk_id(&self) -> &[u8] {
         self.psk_id.as_slice()
     }
+
+    /// Create a new `ExternalPsk` whose PSK ID is scoped to an application.
+    ///
+    /// The PSK ID is the TLS serialization of the `scope` and the `id`, both
+    /// as variable-length byte vectors, which prevents collisions between the
+    /// PSK IDs of different applications that share a PSK store.
+    pub fn scoped(scope: &[u8], id: &[u8]) -> Result<Self, LibraryError> {
+        let psk_id = ScopedPskId {
+            scope: scope.into(),
+            id: id.into(),
+        }
+        .tls_serialize_detached()
+        .map_err(LibraryError::missing_bound_check)?;
+
+        Ok(Self::new(psk_id))
+    }
+
+    /// Return the scope and the ID of a PSK ID created with
+    /// [`ExternalPsk::scoped()`], or `None` if the PSK ID is not scoped.
+    pub fn scope_and_id(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let scoped_psk_id = ScopedPskId::tls_deserialize_exact(self.psk_id.as_slice()).ok()?;
+
+        Some((scoped_psk_id.scope.into(), scoped_psk_id.id.into()))
+    }
+}
+
+// The structure of an application-scoped PSK ID.
+#[derive(TlsDeserialize, TlsSerialize, TlsSize)]
+struct ScopedPskId {
+    scope: VLBytes,
+    id: VLBytes,
 }
 
 /// Contains the secret part of the PSK as well as the
@@ -239,6 +273,19 @@ impl PreSharedKeyId {
         Ok(Self { psk, psk_nonce })
     }
 
+    /// Construct an external `PreSharedKeyID` with a random nonce of the
+    /// length required by the `ciphersuite`.
+    ///
+    /// A new nonce must be used every time a PSK is injected into the key
+    /// schedule, so a new `PreSharedKeyId` should be created each time.
+    pub fn new_external(
+        ciphersuite: Ciphersuite,
+        rand: &impl OpenMlsRand,
+        external_psk: ExternalPsk,
+    ) -> Result<Self, CryptoError> {
+        Self::new(ciphersuite, rand, Psk::External(external_psk))
+    }
+
     /// Construct an external `PreSharedKeyID`.
     pub fn external(psk_id: Vec<u8>, psk_nonce: Vec<u8>) -> Self {
         let psk = Psk::External(ExternalPsk::new(psk_id));
@@ -298,13 +345,54 @@ impl PreSharedKeyId {
 
     // ----- Validation ----------------------------------------------------------------------------
 
-    pub(crate) fn validate_in_proposal(self, ciphersuite: Ciphersuite) -> Result<Self, PskError> {
+    /// Validates an external `PreSharedKeyId`, e.g. one received from another
+    /// client, for use in a group with the given `ciphersuite`.
+    ///
+    /// Returns an error if the PSK is not an external PSK, if its PSK ID is
+    /// empty, or if its nonce doesn't have the length required by the
+    /// `ciphersuite`.
+    pub fn validate_external(&self, ciphersuite: Ciphersuite) -> Result<(), PskError> {
+        let Psk::External(external_psk) = self.psk() else {
+            return Err(PskError::TypeMismatch {
+                allowed: vec![PskType::External],
+                got: PskType::Resumption,
+            });
+        };
+        if external_psk.psk_id().is_empty() {
+            return Err(PskError::EmptyPskId);
+        }
+
+        self.validate_nonce_length(ciphersuite)
+    }
+
+    /// Checks that the nonce has the length required by the `ciphersuite`.
+    fn validate_nonce_length(&self, ciphersuite: Ciphersuite) -> Result<(), PskError> {
+        let expected_nonce_length = ciphersuite.hash_length();
+        let got_nonce_length = self.psk_nonce().len();
+
+        if expected_nonce_length != got_nonce_length {
+            return Err(PskError::NonceLengthMismatch {
+                expected: expected_nonce_length,
+                got: got_nonce_length,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Validates the PSK ID of a PreSharedKey proposal, where resumption PSKs
+    /// must have one of the `allowed_usages`.
+    pub(crate) fn validate_in_proposal(
+        self,
+        ciphersuite: Ciphersuite,
+        allowed_usages: &[ResumptionPskUsage],
+    ) -> Result<Self, PskError> {
         // ValSem402
         match self.psk() {
             Psk::Resumption(resumption_psk) => {
-                if resumption_psk.usage != ResumptionPskUsage::Application {
+                if !allowed_usages.contains(&resumption_psk.usage) {
                     return Err(PskError::UsageMismatch {
-                        allowed: vec![ResumptionPskUsage::Application],
+                        allowed: allowed_usages.to_vec(),
                         got: resumption_psk.usage,
                     });
                 }
@@ -313,17 +401,7 @@ impl PreSharedKeyId {
         };
 
         // ValSem401
-        {
-            let expected_nonce_length = ciphersuite.hash_length();
-            let got_nonce_length = self.psk_nonce().len();
-
-            if expected_nonce_length != got_nonce_length {
-                return Err(PskError::NonceLengthMismatch {
-                    expected: expected_nonce_length,
-                    got: got_nonce_length,
-                });
-            }
-        }
+        self.validate_nonce_length(ciphersuite)?;
 
         Ok(self)
     }
@@ -448,40 +526,131 @@ impl From<Secret> for PskSecret {
     }
 }
 
-pub(crate) fn load_psks<'p, Storage: StorageProvider>(
-    storage: &Storage,
+/// A source of pre-shared keys.
+///
+/// External PSKs, as well as resumption PSKs used for re-initialization or
+/// branching, are looked up through this trait when they are injected into
+/// the key schedule. It is implemented for every [`StorageReader`], which
+/// serves the PSKs written with [`PreSharedKeyId::store()`].
+///
+/// Applications that keep their PSKs elsewhere, e.g. in a secure enclave or
+/// a remote secrets manager, can implement this trait separately and pass it
+/// to the `load_psks` step of
+/// [`MlsGroup::commit_builder()`](crate::group::MlsGroup::commit_builder), to
+/// [`MlsGroup::process_message_with_psk_store()`](crate::group::MlsGroup::process_message_with_psk_store)
+/// or to [`ProcessedWelcome::new_from_welcome_with_psk_store()`](crate::group::ProcessedWelcome::new_from_welcome_with_psk_store),
+/// while the rest of the group state stays in the local storage.
+pub trait PskStore {
+    /// The error returned when looking up a PSK fails.
+    type Error: core::fmt::Debug + std::error::Error;
+
+    /// Returns the secret of the PSK with the given id, or `None` if the PSK
+    /// is unknown.
+    fn psk_secret(&self, psk: &Psk) -> Result<Option<Vec<u8>>, Self::Error>;
+}
+
+impl<Storage: StorageReader> PskStore for Storage {
+    type Error = <Storage as StorageReaderTrait<CURRENT_VERSION>>::Error;
+
+    fn psk_secret(&self, psk: &Psk) -> Result<Option<Vec<u8>>, Self::Error> {
+        let psk_bundle: Option<PskBundle> = self.psk(psk)?;
+
+        Ok(psk_bundle.map(|psk_bundle| psk_bundle.secret.as_slice().to_vec()))
+    }
+}
+
+/// A [`PskStore`] that looks up PSKs in another [`PskStore`] first and asks
+/// the application for the ones that are missing there.
+///
+/// The `resolve` callback is called with each PSK that isn't in the `store`,
+/// e.g. an external PSK referenced by an incoming commit, and can fetch its
+/// secret on demand. If it declines by returning `None`, the lookup fails as
+/// if the PSK was unknown. Fetched secrets are not written to the `store`;
+/// applications that want to keep them can use [`PreSharedKeyId::store()`].
+///
+/// See [`MlsGroup::process_message_with_psk_resolver()`](crate::group::MlsGroup::process_message_with_psk_resolver).
+pub struct PskResolver<'a, Store, Resolve> {
+    store: &'a Store,
+    resolve: Resolve,
+}
+
+impl<'a, Store: PskStore, Resolve: Fn(&Psk) -> Option<Vec<u8>>> PskResolver<'a, Store, Resolve> {
+    /// Creates a new [`PskResolver`] that falls back to `resolve` for the PSKs
+    /// that are not in `store`.
+    pub fn new(store: &'a Store, resolve: Resolve) -> Self {
+        Self { store, resolve }
+    }
+}
+
+impl<Store: PskStore, Resolve: Fn(&Psk) -> Option<Vec<u8>>> PskStore
+    for PskResolver<'_, Store, Resolve>
+{
+    type Error = Store::Error;
+
+    fn psk_secret(&self, psk: &Psk) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.store.psk_secret(psk)? {
+            Some(secret) => Ok(Some(secret)),
+            None => Ok((self.resolve)(psk)),
+        }
+    }
+}
+
+pub(crate) fn load_psks<'p, Store: PskStore>(
+    psk_store: &Store,
     resumption_psk_store: &ResumptionPskStore,
     psk_ids: &'p [PreSharedKeyId],
 ) -> Result<Vec<(&'p PreSharedKeyId, Secret)>, PskError> {
     let mut psk_bundles = Vec::new();
 
     for psk_id in psk_ids.iter() {
-        log_crypto!(trace, "PSK store {:?}", resumption_psk_store);
-
-        match &psk_id.psk {
-            Psk::Resumption(resumption) => {
-                if let Some(psk_bundle) = resumption_psk_store.get(resumption.psk_epoch()) {
-                    psk_bundles.push((psk_id, psk_bundle.secret.clone()));
-                } else {
-                    return Err(PskError::KeyNotFound);
-                }
-            }
-            Psk::External(_) => {
-                let psk_bundle: Option<PskBundle> = storage
-                    .psk(psk_id.psk())
-                    .map_err(|_| PskError::KeyNotFound)?;
-                if let Some(psk_bundle) = psk_bundle {
-                    psk_bundles.push((psk_id, psk_bundle.secret));
-                } else {
-                    return Err(PskError::KeyNotFound);
-                }
-            }
-        }
+        let secret =
+            load_psk(psk_store, resumption_psk_store, psk_id)?.ok_or(PskError::KeyNotFound)?;
+        psk_bundles.push((psk_id, secret));
     }
 
     Ok(psk_bundles)
 }
 
+/// Returns the secret of the PSK with the given id, or `None` if it can't be
+/// found.
+pub(crate) fn load_psk<Store: PskStore>(
+    psk_store: &Store,
+    resumption_psk_store: &ResumptionPskStore,
+    psk_id: &PreSharedKeyId,
+) -> Result<Option<Secret>, PskError> {
+    log_crypto!(trace, "PSK store {:?}", resumption_psk_store);
+
+    match &psk_id.psk {
+        Psk::Resumption(resumption) if resumption.usage() == ResumptionPskUsage::Application => {
+            Ok(resumption_psk_store
+                .get(resumption.psk_epoch())
+                .map(|psk_bundle| psk_bundle.secret.clone()))
+        }
+        // External PSKs and resumption PSKs used for re-initialization or
+        // branching stem from outside the group and are thus kept in the
+        // PSK store.
+        Psk::Resumption(_) | Psk::External(_) => Ok(psk_store
+            .psk_secret(psk_id.psk())
+            .map_err(|_| PskError::KeyNotFound)?
+            .map(|secret| Secret::from_slice(&secret))),
+    }
+}
+
+/// Writes the secret of a resumption PSK to the storage, such that it can be
+/// used in a group other than the one it stems from, e.g. to re-initialize the
+/// group.
+pub(crate) fn store_resumption_psk<Storage: StorageProvider>(
+    storage: &Storage,
+    resumption_psk: ResumptionPsk,
+    secret: &ResumptionPskSecret,
+) -> Result<(), Storage::Error> {
+    let psk_bundle = PskBundle {
+        secret: secret.secret.clone(),
+    };
+
+    storage.write_psk(&Psk::Resumption(resumption_psk), &psk_bundle)
+}
+
 /// This module contains a store that can hold a rollover list of resumption PSKs.
 pub mod store {
     use serde::{Deserialize, Serialize};
@@ -519,8 +688,10 @@ pub mod store {
                 self.resumption_psk.push(item);
                 self.cursor += 1;
             } else {
-                self.cursor += 1;
-                self.cursor %= self.resumption_psk.len();
+                // Right after the store filled up, the cursor points past the
+                // last entry and the oldest entry is at the start.
+                let last = self.cursor.min(self.resumption_psk.len() - 1);
+                self.cursor = (last + 1) % self.resumption_psk.len();
                 self.resumption_psk[self.cursor] = item;
             }
         }
@@ -533,6 +704,41 @@ pub mod store {
                 .find(|&(e, _s)| e == &epoch)
                 .map(|(_e, s)| s)
         }
+
+        /// Changes the maximum number of entries in the store to
+        /// `max_number_of_secrets`. If the store holds more entries, the ones
+        /// of the oldest epochs are dropped.
+        pub(crate) fn resize(&mut self, max_number_of_secrets: usize) {
+            // Order the entries by epoch so that the store is filled up
+            // before the rollover starts again.
+            self.resumption_psk.sort_by_key(|(epoch, _)| *epoch);
+            let number_of_dropped = self
+                .resumption_psk
+                .len()
+                .saturating_sub(max_number_of_secrets);
+            self.resumption_psk.drain(..number_of_dropped);
+            self.max_number_of_secrets = max_number_of_secrets;
+            self.cursor = self.resumption_psk.len();
+        }
+
+        /// Returns the epochs of the entries in the store in ascending order.
+        pub(crate) fn epochs(&self) -> Vec<GroupEpoch> {
+            let mut epochs: Vec<GroupEpoch> = self
+                .resumption_psk
+                .iter()
+                .map(|(epoch, _)| *epoch)
+                .collect();
+            epochs.sort();
+            epochs
+        }
+
+        /// Returns the number of bytes of secret material held by the store.
+        pub(crate) fn size(&self) -> usize {
+            self.resumption_psk
+                .iter()
+                .map(|(_, secret)| std::mem::size_of::<u64>() + secret.as_slice().len())
+                .sum()
+        }
     }
 
     #[cfg(test)]
@@ -274,6 +274,78 @@ fn read_test_vectors_key_schedule() {
     }
 }
 
+#[cfg(feature = "insecure-test-vectors")]
+#[openmls_test::openmls_test]
+fn read_test_vectors_key_schedule_insecure_export() {
+    use crate::schedule::insecure_test_vectors::derive_epoch_secrets;
+
+    let tests: Vec<KeyScheduleTestVector> =
+        read_json!("../../../../test_vectors/key-schedule.json");
+
+    for test_vector in tests {
+        let ciphersuite =
+            Ciphersuite::try_from(test_vector.cipher_suite).expect("Invalid ciphersuite");
+        if !provider
+            .crypto()
+            .supported_ciphersuites()
+            .contains(&ciphersuite)
+        {
+            continue;
+        }
+
+        let mut init_secret = hex_to_bytes(&test_vector.initial_init_secret);
+        for epoch in test_vector.epochs {
+            let secrets = derive_epoch_secrets(
+                provider.crypto(),
+                ciphersuite,
+                &init_secret,
+                &hex_to_bytes(&epoch.commit_secret),
+                &hex_to_bytes(&epoch.psk_secret),
+                &hex_to_bytes(&epoch.group_context),
+            )
+            .expect("Error deriving the epoch secrets.");
+
+            assert_eq!(
+                secrets.joiner_secret,
+                Some(hex_to_bytes(&epoch.joiner_secret))
+            );
+            assert_eq!(
+                secrets.welcome_secret,
+                Some(hex_to_bytes(&epoch.welcome_secret))
+            );
+            assert_eq!(
+                secrets.encryption_secret,
+                Some(hex_to_bytes(&epoch.encryption_secret))
+            );
+            assert_eq!(
+                secrets.sender_data_secret,
+                hex_to_bytes(&epoch.sender_data_secret)
+            );
+            assert_eq!(
+                secrets.exporter_secret,
+                hex_to_bytes(&epoch.exporter_secret)
+            );
+            assert_eq!(
+                secrets.epoch_authenticator,
+                hex_to_bytes(&epoch.epoch_authenticator)
+            );
+            assert_eq!(
+                secrets.external_secret,
+                hex_to_bytes(&epoch.external_secret)
+            );
+            assert_eq!(
+                secrets.confirmation_key,
+                hex_to_bytes(&epoch.confirmation_key)
+            );
+            assert_eq!(secrets.membership_key, hex_to_bytes(&epoch.membership_key));
+            assert_eq!(secrets.resumption_psk, hex_to_bytes(&epoch.resumption_psk));
+            assert_eq!(secrets.init_secret, hex_to_bytes(&epoch.init_secret));
+
+            init_secret = secrets.init_secret;
+        }
+    }
+}
+
 #[cfg(any(feature = "test-utils", test))]
 pub fn run_test_vector(
     test_vector: KeyScheduleTestVector,
@@ -4,7 +4,11 @@ use openmls_traits::{random::OpenMlsRand, OpenMlsProvider};
 
 use crate::{
     ciphersuite::Secret,
-    schedule::psk::{store::ResumptionPskStore, PskSecret, *},
+    group::{GroupEpoch, GroupId},
+    schedule::{
+        errors::PskError,
+        psk::{store::ResumptionPskStore, PskSecret, *},
+    },
 };
 
 #[openmls_test::openmls_test]
@@ -39,3 +43,47 @@ fn test_psks() {
         PskSecret::new(provider.crypto(), ciphersuite, psks).unwrap()
     };
 }
+
+#[openmls_test::openmls_test]
+fn external_psk_helpers() {
+    let external_psk = ExternalPsk::scoped(b"my app", b"psk 1").unwrap();
+    assert_eq!(
+        external_psk.scope_and_id(),
+        Some((b"my app".to_vec(), b"psk 1".to_vec()))
+    );
+    assert_eq!(ExternalPsk::new(b"psk 1".to_vec()).scope_and_id(), None);
+
+    // Every `PreSharedKeyId` gets a fresh nonce of the right length.
+    let psk_id =
+        PreSharedKeyId::new_external(ciphersuite, provider.rand(), external_psk.clone()).unwrap();
+    let other_psk_id =
+        PreSharedKeyId::new_external(ciphersuite, provider.rand(), external_psk).unwrap();
+    assert_eq!(psk_id.psk_nonce().len(), ciphersuite.hash_length());
+    assert_ne!(psk_id.psk_nonce(), other_psk_id.psk_nonce());
+    psk_id.validate_external(ciphersuite).unwrap();
+
+    // Invalid PSK IDs are rejected.
+    let short_nonce = PreSharedKeyId::external(b"psk 1".to_vec(), vec![0; 4]);
+    assert_eq!(
+        short_nonce.validate_external(ciphersuite),
+        Err(PskError::NonceLengthMismatch {
+            expected: ciphersuite.hash_length(),
+            got: 4,
+        })
+    );
+    let empty_id = PreSharedKeyId::external(vec![], vec![0; ciphersuite.hash_length()]);
+    assert_eq!(
+        empty_id.validate_external(ciphersuite),
+        Err(PskError::EmptyPskId)
+    );
+    let resumption = PreSharedKeyId::resumption(
+        ResumptionPskUsage::Application,
+        GroupId::from_slice(b"group"),
+        GroupEpoch::from(1),
+        vec![0; ciphersuite.hash_length()],
+    );
+    assert!(matches!(
+        resumption.validate_external(ciphersuite),
+        Err(PskError::TypeMismatch { .. })
+    ));
+}
@@ -19,7 +19,10 @@ use crate::{
     treesync::{LeafNode, TreeSync},
 };
 use crate::{
-    group::{past_secrets::MessageSecretsStore, GroupEpoch},
+    group::{
+        mls_group::UsedExternalInits, past_secrets::MessageSecretsStore, stats::MemberStatsStore,
+        GroupEpoch, LightGroup,
+    },
     prelude::KeyPackageBundle,
     schedule::{
         psk::{store::ResumptionPskStore, PskBundle},
@@ -28,6 +31,7 @@ use crate::{
     treesync::{node::encryption_keys::EncryptionKeyPair, EncryptionKey},
 };
 
+pub mod gc;
 #[cfg(test)]
 pub mod kat_storage_stability;
 
@@ -35,6 +39,11 @@ pub mod kat_storage_stability;
 /// Throughout the code, this one should be used instead of `openmls_traits::storage::StorageProvider`.
 pub trait StorageProvider: openmls_traits::storage::StorageProvider<CURRENT_VERSION> {}
 
+/// A convenience trait for the read half of the current version of the storage.
+/// Operations that only read the state, like loading a group, accept this one instead of
+/// [`StorageProvider`], so that read-only handles can be passed to them.
+pub trait StorageReader: openmls_traits::storage::StorageReader<CURRENT_VERSION> {}
+
 /// A convenience trait for the current version of the public storage.
 /// Throughout the code, this one should be used instead of `openmls_traits::public_storage::PublicStorageProvider`.
 pub trait PublicStorageProvider:
@@ -50,6 +59,8 @@ pub trait PublicStorageProvider:
 
 impl<P: openmls_traits::storage::StorageProvider<CURRENT_VERSION>> StorageProvider for P {}
 
+impl<P: openmls_traits::storage::StorageReader<CURRENT_VERSION>> StorageReader for P {}
+
 impl<P: openmls_traits::public_storage::PublicStorageProvider<CURRENT_VERSION>>
     PublicStorageProvider for P
 {
@@ -87,6 +98,7 @@ impl Entity<CURRENT_VERSION> for TreeSync {}
 impl traits::TreeSync<CURRENT_VERSION> for TreeSync {}
 
 impl Key<CURRENT_VERSION> for GroupId {}
+impl Entity<CURRENT_VERSION> for GroupId {}
 impl traits::GroupId<CURRENT_VERSION> for GroupId {}
 
 impl Key<CURRENT_VERSION> for ProposalRef {}
@@ -107,6 +119,7 @@ impl Entity<CURRENT_VERSION> for KeyPackageBundle {}
 impl traits::KeyPackage<CURRENT_VERSION> for KeyPackageBundle {}
 
 impl Key<CURRENT_VERSION> for EncryptionKey {}
+impl Entity<CURRENT_VERSION> for EncryptionKey {}
 impl traits::EncryptionKey<CURRENT_VERSION> for EncryptionKey {}
 
 impl Entity<CURRENT_VERSION> for EncryptionKeyPair {}
@@ -121,6 +134,20 @@ impl traits::GroupEpochSecrets<CURRENT_VERSION> for GroupEpochSecrets {}
 impl Entity<CURRENT_VERSION> for MessageSecretsStore {}
 impl traits::MessageSecrets<CURRENT_VERSION> for MessageSecretsStore {}
 
+impl Entity<CURRENT_VERSION> for MemberStatsStore {}
+impl traits::MemberStats<CURRENT_VERSION> for MemberStatsStore {}
+
+impl Entity<CURRENT_VERSION> for UsedExternalInits {}
+impl traits::UsedExternalInits<CURRENT_VERSION> for UsedExternalInits {}
+
+#[cfg(feature = "replica-sync")]
+impl Entity<CURRENT_VERSION> for crate::group::replica::ReplicaSequence {}
+#[cfg(feature = "replica-sync")]
+impl traits::ReplicaSequence<CURRENT_VERSION> for crate::group::replica::ReplicaSequence {}
+
+impl Entity<CURRENT_VERSION> for LightGroup {}
+impl traits::LightGroup<CURRENT_VERSION> for LightGroup {}
+
 impl Entity<CURRENT_VERSION> for ResumptionPskStore {}
 impl traits::ResumptionPskStore<CURRENT_VERSION> for ResumptionPskStore {}
 
@@ -154,7 +181,7 @@ mod test {
 
     use openmls_rust_crypto::{MemoryStorage, OpenMlsRustCrypto};
     use openmls_traits::{
-        storage::{traits as type_traits, StorageProvider, V_TEST},
+        storage::{traits as type_traits, StorageReader, StorageWriter, V_TEST},
         types::{Ciphersuite, HpkePrivateKey},
         OpenMlsProvider,
     };
@@ -213,7 +240,7 @@ mod test {
 
         // first, read the old data
         let read_key_package_bundle: crate::prelude::KeyPackageBundle =
-            <MemoryStorage as StorageProvider<CURRENT_VERSION>>::key_package(
+            <MemoryStorage as StorageReader<CURRENT_VERSION>>::key_package(
                 provider.storage(),
                 &key_package_ref,
             )
@@ -229,7 +256,7 @@ mod test {
         };
 
         // insert the data in the new format
-        <MemoryStorage as StorageProvider<V_TEST>>::write_key_package(
+        <MemoryStorage as StorageWriter<V_TEST>>::write_key_package(
             &new_storage_provider,
             &key_package_ref,
             &new_key_package_bundle,
@@ -238,7 +265,7 @@ mod test {
 
         // read the new value from storage
         let read_new_key_package_bundle: NewKeyPackageBundle =
-            <MemoryStorage as StorageProvider<V_TEST>>::key_package(
+            <MemoryStorage as StorageReader<V_TEST>>::key_package(
                 &new_storage_provider,
                 &key_package_ref,
             )
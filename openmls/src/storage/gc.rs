@@ -0,0 +1,296 @@
+//! # Garbage collection
+//!
+//! Long-running clients can accumulate state in the storage that isn't
+//! reachable anymore, e.g. because the process crashed while a group was
+//! created, joined or deleted, or before a key package that was used to join
+//! a group was deleted. [`find_orphans()`] enumerates the storage and reports
+//! such state, and [`collect_garbage()`] deletes it.
+//!
+//! The following state is considered orphaned:
+//!
+//! - groups whose state is incomplete, i.e. that can neither be loaded as an
+//!   [`MlsGroup`] nor as an [`InactiveGroupRecord`],
+//! - key packages that have expired, key packages whose retention grace
+//!   period is over (see [`retention`](crate::key_packages::retention)), and
+//!   key packages that are not retained but whose leaf node is the own leaf of
+//!   a group, i.e. that were used to join the group, and
+//! - encryption key pairs of leaf nodes that are neither the own leaf of a
+//!   group nor a leaf node of a pending own update proposal.
+//!
+//! The epoch encryption key pairs of an incomplete group are deleted together
+//! with the group if its group context and own leaf index are still stored.
+//!
+//! Note that encryption key pairs of leaf nodes that the application created
+//! for later use, e.g. with [`LeafNode::new()`](crate::treesync::LeafNode),
+//! are reported as orphaned as well until the leaf node is used.
+//!
+//! ## Concurrency
+//!
+//! The storage doesn't record whether an operation is in progress, so state
+//! that is being written looks the same as state that was left behind by a
+//! crash: a group that is being created or joined is incomplete until the
+//! operation finished, and the encryption key pair of a self-update proposal
+//! is stored before the proposal is. Garbage collection must therefore not
+//! run concurrently with any operation that writes to the same storage,
+//! otherwise it reports and deletes the state of these operations. Run it
+//! e.g. on startup, before any group is used.
+
+use super::{StorageProvider, StorageReader};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::hash_ref::{KeyPackageRef, ProposalRef},
+    group::{
+        mls_group::UsedExternalInits, past_secrets::MessageSecretsStore, stats::MemberStatsStore,
+        GroupContext, GroupId, InactiveGroupRecord, InterimTranscriptHash, MlsGroup,
+        MlsGroupJoinConfig, MlsGroupState, PublicGroup,
+    },
+    key_packages::KeyPackageBundle,
+    messages::ConfirmationTag,
+    schedule::{psk::store::ResumptionPskStore, GroupEpochSecrets},
+    treesync::{EncryptionKey, LeafNode, TreeSync},
+};
+
+/// A kind of state that is stored for a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GroupEntity {
+    /// The [`MlsGroupJoinConfig`] of the group.
+    JoinConfig,
+    /// The own leaf nodes of pending update proposals.
+    OwnLeafNodes,
+    /// The queue of pending proposals.
+    ProposalQueue,
+    /// The ratchet tree.
+    Tree,
+    /// The interim transcript hash.
+    InterimTranscriptHash,
+    /// The group context.
+    GroupContext,
+    /// The confirmation tag.
+    ConfirmationTag,
+    /// The [`MlsGroupState`].
+    GroupState,
+    /// The message secrets of the current and past epochs.
+    MessageSecrets,
+    /// The statistics of the members.
+    MemberStats,
+    /// The external inits of the external commits staged in the current epoch.
+    UsedExternalInits,
+    /// The sequence number of the last replica delta.
+    #[cfg(feature = "replica-sync")]
+    ReplicaSequence,
+    /// The resumption PSKs.
+    ResumptionPskStore,
+    /// The own leaf index.
+    OwnLeafIndex,
+    /// The epoch secrets.
+    EpochSecrets,
+}
+
+/// Returns the kinds of state that are stored for the group with the given
+/// `group_id`, or an empty vector if nothing is stored.
+///
+/// Note that this reads all values of the group from the storage.
+pub fn group_entities<Storage: StorageReader>(
+    storage: &Storage,
+    group_id: &GroupId,
+) -> Result<Vec<GroupEntity>, Storage::Error> {
+    let join_config: Option<MlsGroupJoinConfig> = storage.mls_group_join_config(group_id)?;
+    let own_leaf_nodes: Vec<LeafNode> = storage.own_leaf_nodes(group_id)?;
+    let proposal_refs: Vec<ProposalRef> = storage.queued_proposal_refs(group_id)?;
+    let tree: Option<TreeSync> = storage.tree(group_id)?;
+    let interim_transcript_hash: Option<InterimTranscriptHash> =
+        storage.interim_transcript_hash(group_id)?;
+    let group_context: Option<GroupContext> = storage.group_context(group_id)?;
+    let confirmation_tag: Option<ConfirmationTag> = storage.confirmation_tag(group_id)?;
+    let group_state: Option<MlsGroupState> = storage.group_state(group_id)?;
+    let message_secrets: Option<MessageSecretsStore> = storage.message_secrets(group_id)?;
+    let member_stats: Option<MemberStatsStore> = storage.member_stats(group_id)?;
+    let used_external_inits: Option<UsedExternalInits> = storage.used_external_inits(group_id)?;
+    #[cfg(feature = "replica-sync")]
+    let replica_sequence: Option<crate::group::replica::ReplicaSequence> =
+        storage.replica_sequence(group_id)?;
+    let resumption_psk_store: Option<ResumptionPskStore> =
+        storage.resumption_psk_store(group_id)?;
+    let own_leaf_index: Option<LeafNodeIndex> = storage.own_leaf_index(group_id)?;
+    let epoch_secrets: Option<GroupEpochSecrets> = storage.group_epoch_secrets(group_id)?;
+
+    #[allow(unused_mut)]
+    let mut entities = vec![
+        (join_config.is_some(), GroupEntity::JoinConfig),
+        (!own_leaf_nodes.is_empty(), GroupEntity::OwnLeafNodes),
+        (!proposal_refs.is_empty(), GroupEntity::ProposalQueue),
+        (tree.is_some(), GroupEntity::Tree),
+        (
+            interim_transcript_hash.is_some(),
+            GroupEntity::InterimTranscriptHash,
+        ),
+        (group_context.is_some(), GroupEntity::GroupContext),
+        (confirmation_tag.is_some(), GroupEntity::ConfirmationTag),
+        (group_state.is_some(), GroupEntity::GroupState),
+        (message_secrets.is_some(), GroupEntity::MessageSecrets),
+        (member_stats.is_some(), GroupEntity::MemberStats),
+        (
+            used_external_inits.is_some(),
+            GroupEntity::UsedExternalInits,
+        ),
+        (
+            resumption_psk_store.is_some(),
+            GroupEntity::ResumptionPskStore,
+        ),
+        (own_leaf_index.is_some(), GroupEntity::OwnLeafIndex),
+        (epoch_secrets.is_some(), GroupEntity::EpochSecrets),
+    ];
+    #[cfg(feature = "replica-sync")]
+    entities.push((replica_sequence.is_some(), GroupEntity::ReplicaSequence));
+
+    Ok(entities
+        .into_iter()
+        .filter_map(|(stored, entity)| stored.then_some(entity))
+        .collect())
+}
+
+/// The orphaned state found by [`find_orphans()`]. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Orphans {
+    groups: Vec<GroupId>,
+    key_packages: Vec<KeyPackageRef>,
+    encryption_keys: Vec<EncryptionKey>,
+}
+
+impl Orphans {
+    /// Returns the ids of the groups whose state is incomplete.
+    pub fn groups(&self) -> &[GroupId] {
+        &self.groups
+    }
+
+    /// Returns the hash references of the orphaned key packages.
+    pub fn key_packages(&self) -> &[KeyPackageRef] {
+        &self.key_packages
+    }
+
+    /// Returns the public keys of the orphaned encryption key pairs.
+    pub fn encryption_keys(&self) -> &[EncryptionKey] {
+        &self.encryption_keys
+    }
+
+    /// Returns `true` if no orphaned state was found.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.key_packages.is_empty() && self.encryption_keys.is_empty()
+    }
+
+    /// Deletes the orphaned state from the `storage`.
+    ///
+    /// No operation may write to the storage between finding and deleting
+    /// the orphans, see [Concurrency](self#concurrency).
+    pub fn delete<Storage: StorageProvider>(
+        &self,
+        storage: &Storage,
+    ) -> Result<(), Storage::Error> {
+        for group_id in &self.groups {
+            // The epoch key pairs can only be found if the group context and
+            // the own leaf index are still stored.
+            let group_context: Option<GroupContext> = storage.group_context(group_id)?;
+            let own_leaf_index: Option<LeafNodeIndex> = storage.own_leaf_index(group_id)?;
+            if let (Some(group_context), Some(own_leaf_index)) = (group_context, own_leaf_index) {
+                storage.delete_encryption_epoch_key_pairs(
+                    group_id,
+                    &group_context.epoch(),
+                    own_leaf_index.u32(),
+                )?;
+            }
+            PublicGroup::delete(storage, group_id)?;
+            storage.delete_own_leaf_index(group_id)?;
+            storage.delete_group_epoch_secrets(group_id)?;
+            storage.delete_message_secrets(group_id)?;
+            storage.delete_member_stats(group_id)?;
+            storage.delete_replica_sequence(group_id)?;
+            storage.delete_used_external_inits(group_id)?;
+            storage.delete_all_resumption_psk_secrets(group_id)?;
+            storage.delete_group_config(group_id)?;
+            storage.delete_own_leaf_nodes(group_id)?;
+            storage.delete_group_state(group_id)?;
+            storage.clear_proposal_queue::<GroupId, ProposalRef>(group_id)?;
+        }
+        for key_package_ref in &self.key_packages {
+            storage.delete_key_package(key_package_ref)?;
+        }
+        for encryption_key in &self.encryption_keys {
+            storage.delete_encryption_key_pair(encryption_key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Enumerates the `storage` and returns the orphaned state. See the
+/// [module documentation](self) for details.
+///
+/// This only reads from the `storage`, so a read-only handle is sufficient.
+/// No operation may write to the storage concurrently, see
+/// [Concurrency](self#concurrency).
+pub fn find_orphans<Storage: StorageReader>(storage: &Storage) -> Result<Orphans, Storage::Error> {
+    let mut orphans = Orphans::default();
+
+    // The own leaf nodes of all groups, including the ones of pending update
+    // proposals.
+    let mut own_leaf_nodes: Vec<LeafNode> = Vec::new();
+    for group_id in storage.group_ids::<GroupId>()? {
+        match MlsGroup::load(storage, &group_id)? {
+            Some(group) => {
+                own_leaf_nodes.extend(group.own_leaf_node().cloned());
+                own_leaf_nodes.extend(storage.own_leaf_nodes::<_, LeafNode>(&group_id)?);
+            }
+            None => {
+                if InactiveGroupRecord::load(storage, &group_id)?.is_none() {
+                    orphans.groups.push(group_id);
+                }
+            }
+        }
+    }
+    let is_own_leaf = |encryption_key: &EncryptionKey| {
+        own_leaf_nodes
+            .iter()
+            .any(|leaf_node| leaf_node.encryption_key() == encryption_key)
+    };
+
+    for key_package_ref in storage.key_package_refs::<KeyPackageRef>()? {
+        let Some(key_package_bundle): Option<KeyPackageBundle> =
+            storage.key_package(&key_package_ref)?
+        else {
+            continue;
+        };
+        let key_package = key_package_bundle.key_package();
+        let leaked = match key_package_bundle.retention_reason() {
+            Some(reason) => reason.is_expired(),
+            None => {
+                !key_package.last_resort() && is_own_leaf(key_package.leaf_node().encryption_key())
+            }
+        };
+        if leaked || !key_package.life_time().is_valid() {
+            orphans.key_packages.push(key_package_ref);
+        }
+    }
+
+    for encryption_key in storage.encryption_keys::<EncryptionKey>()? {
+        if !is_own_leaf(&encryption_key) {
+            orphans.encryption_keys.push(encryption_key);
+        }
+    }
+
+    Ok(orphans)
+}
+
+/// Deletes the orphaned state from the `storage` and returns what was
+/// deleted. See the [module documentation](self) for details.
+///
+/// No operation may write to the storage concurrently, see
+/// [Concurrency](self#concurrency).
+pub fn collect_garbage<Storage: StorageProvider>(
+    storage: &Storage,
+) -> Result<Orphans, Storage::Error> {
+    let orphans = find_orphans(storage)?;
+    orphans.delete(storage)?;
+
+    Ok(orphans)
+}
@@ -10,7 +10,10 @@ use std::{
 
 use openmls_basic_credential::SignatureKeyPair;
 pub use openmls_traits::{
-    storage::StorageProvider as StorageProviderTrait,
+    storage::{
+        StorageProvider as StorageProviderTrait, StorageReader as StorageReaderTrait,
+        StorageWriter as StorageWriterTrait,
+    },
     types::{Ciphersuite, HpkeKeyPair},
     OpenMlsProvider,
 };
@@ -175,6 +175,77 @@ impl SecretTree {
         }
     }
 
+    /// Derive the initial application ratchet secrets of all leaves from the
+    /// encryption secret in the root node, without modifying the tree.
+    ///
+    /// Returns a [`SecretTreeError::SecretReuseError`] if the root node was
+    /// already consumed, i.e. if secrets of the epoch were used before.
+    #[cfg(feature = "compliance-escrow")]
+    pub(crate) fn application_ratchet_secrets(
+        &self,
+        ciphersuite: Ciphersuite,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<Vec<(LeafNodeIndex, Secret)>, SecretTreeError> {
+        let root_index = root(self.size);
+        let root_secret = match self.get_node(root_index)? {
+            Some(node) => node.secret.clone(),
+            None => return Err(SecretTreeError::SecretReuseError),
+        };
+
+        let hash_len = ciphersuite.hash_length();
+        let mut leaf_secrets = Vec::with_capacity(self.size.leaf_count() as usize);
+        let mut nodes = vec![(root_index, root_secret)];
+        while let Some((index, node_secret)) = nodes.pop() {
+            match index {
+                TreeNodeIndex::Leaf(leaf_index) => {
+                    let application_ratchet_secret = node_secret.kdf_expand_label(
+                        crypto,
+                        ciphersuite,
+                        "application",
+                        b"",
+                        hash_len,
+                    )?;
+                    leaf_secrets.push((leaf_index, application_ratchet_secret));
+                }
+                TreeNodeIndex::Parent(parent_index) => {
+                    let left_secret = node_secret.kdf_expand_label(
+                        crypto,
+                        ciphersuite,
+                        "tree",
+                        b"left",
+                        hash_len,
+                    )?;
+                    let right_secret = node_secret.kdf_expand_label(
+                        crypto,
+                        ciphersuite,
+                        "tree",
+                        b"right",
+                        hash_len,
+                    )?;
+                    nodes.push((right(parent_index), right_secret));
+                    nodes.push((left(parent_index), left_secret));
+                }
+            }
+        }
+
+        Ok(leaf_secrets)
+    }
+
+    /// Get the current state of a specific SenderRatchet. Returns `None` if
+    /// the index is out of bounds or the SenderRatchet hasn't been initialized
+    /// yet.
+    pub(crate) fn sender_ratchet_state(
+        &self,
+        index: LeafNodeIndex,
+        secret_type: SecretType,
+        configuration: &SenderRatchetConfiguration,
+    ) -> Option<SenderRatchetState> {
+        self.ratchet_opt(index, secret_type)
+            .ok()
+            .flatten()
+            .map(|sender_ratchet| sender_ratchet.state(configuration))
+    }
+
     /// Initializes a specific SenderRatchet pair for a given index by
     /// calculating and deleting the appropriate values in the SecretTree
     fn initialize_sender_ratchets(
@@ -7,6 +7,7 @@
 
 use openmls_traits::crypto::OpenMlsCrypto;
 use std::collections::VecDeque;
+use thiserror::Error;
 
 use openmls_traits::types::Ciphersuite;
 
@@ -29,6 +30,11 @@ pub(crate) type Generation = u32;
 /// - maximum_forward_distance:
 ///   This parameter defines how many incoming messages can be skipped. This is useful if the DS
 ///   drops application messages. The default value is 1000.
+///
+/// Instead of choosing the values by hand, a preset such as
+/// [`SenderRatchetConfiguration::strict_forward_secrecy()`] can be used, or
+/// the configuration can be built and validated using
+/// [`SenderRatchetConfiguration::builder()`].
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SenderRatchetConfiguration {
     out_of_order_tolerance: Generation,
@@ -36,6 +42,16 @@ pub struct SenderRatchetConfiguration {
 }
 
 impl SenderRatchetConfiguration {
+    /// The largest `out_of_order_tolerance` accepted by the
+    /// [`SenderRatchetConfigurationBuilder`]. Every tolerated generation may
+    /// cause a key to be stored per sender.
+    pub const MAX_OUT_OF_ORDER_TOLERANCE: Generation = 10_000;
+
+    /// The largest `maximum_forward_distance` accepted by the
+    /// [`SenderRatchetConfigurationBuilder`]. Every skipped generation costs a
+    /// key derivation when decrypting a message.
+    pub const MAX_MAXIMUM_FORWARD_DISTANCE: Generation = 100_000;
+
     /// Create a new configuration
     pub fn new(out_of_order_tolerance: Generation, maximum_forward_distance: Generation) -> Self {
         Self {
@@ -43,6 +59,41 @@ impl SenderRatchetConfiguration {
             maximum_forward_distance,
         }
     }
+
+    /// Returns a builder for [`SenderRatchetConfiguration`] that validates
+    /// the values when building.
+    pub fn builder() -> SenderRatchetConfigurationBuilder {
+        SenderRatchetConfigurationBuilder::new()
+    }
+
+    /// A configuration for a DS that delivers application messages in order.
+    ///
+    /// No keys of past generations are kept, such that the key of a message is
+    /// deleted as soon as the message is decrypted. Messages that arrive out
+    /// of order can't be decrypted. Up to 1000 dropped messages are tolerated.
+    pub fn strict_forward_secrecy() -> Self {
+        Self::new(0, 1000)
+    }
+
+    /// A configuration for clients on unreliable networks, e.g. mobile
+    /// clients that frequently reconnect.
+    ///
+    /// Messages that arrive up to 20 generations late can be decrypted, and
+    /// up to 5000 dropped messages are tolerated.
+    pub fn mobile_lossy_network() -> Self {
+        Self::new(20, 5000)
+    }
+
+    /// A configuration for server-side members, e.g. bridges to other
+    /// messaging systems, that receive many messages from concurrent senders.
+    ///
+    /// Messages that arrive up to 100 generations late can be decrypted, and
+    /// up to 10000 dropped messages are tolerated. This keeps more keys
+    /// around than the other presets and weakens forward secrecy accordingly.
+    pub fn server_bridge() -> Self {
+        Self::new(100, 10_000)
+    }
+
     /// Get a reference to the sender ratchet configuration's out of order tolerance.
     pub fn out_of_order_tolerance(&self) -> Generation {
         self.out_of_order_tolerance
@@ -60,6 +111,93 @@ impl Default for SenderRatchetConfiguration {
     }
 }
 
+/// Builder for [`SenderRatchetConfiguration`]. Starts from the default
+/// configuration.
+#[derive(Clone, Debug, Default)]
+pub struct SenderRatchetConfigurationBuilder {
+    configuration: SenderRatchetConfiguration,
+}
+
+impl SenderRatchetConfigurationBuilder {
+    /// Returns a new [`SenderRatchetConfigurationBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `out_of_order_tolerance` of the [`SenderRatchetConfiguration`].
+    pub fn out_of_order_tolerance(mut self, out_of_order_tolerance: Generation) -> Self {
+        self.configuration.out_of_order_tolerance = out_of_order_tolerance;
+        self
+    }
+
+    /// Sets the `maximum_forward_distance` of the
+    /// [`SenderRatchetConfiguration`].
+    pub fn maximum_forward_distance(mut self, maximum_forward_distance: Generation) -> Self {
+        self.configuration.maximum_forward_distance = maximum_forward_distance;
+        self
+    }
+
+    /// Validates the values and returns the [`SenderRatchetConfiguration`].
+    pub fn build(self) -> Result<SenderRatchetConfiguration, SenderRatchetConfigurationError> {
+        let configuration = self.configuration;
+        if configuration.out_of_order_tolerance
+            > SenderRatchetConfiguration::MAX_OUT_OF_ORDER_TOLERANCE
+        {
+            return Err(SenderRatchetConfigurationError::OutOfOrderToleranceTooLarge);
+        }
+        if configuration.maximum_forward_distance
+            > SenderRatchetConfiguration::MAX_MAXIMUM_FORWARD_DISTANCE
+        {
+            return Err(SenderRatchetConfigurationError::MaximumForwardDistanceTooLarge);
+        }
+        Ok(configuration)
+    }
+}
+
+/// Sender ratchet configuration error
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum SenderRatchetConfigurationError {
+    /// The out of order tolerance is larger than
+    /// [`SenderRatchetConfiguration::MAX_OUT_OF_ORDER_TOLERANCE`].
+    #[error("The out of order tolerance is too large.")]
+    OutOfOrderToleranceTooLarge,
+    /// The maximum forward distance is larger than
+    /// [`SenderRatchetConfiguration::MAX_MAXIMUM_FORWARD_DISTANCE`].
+    #[error("The maximum forward distance is too large.")]
+    MaximumForwardDistanceTooLarge,
+}
+
+/// A snapshot of the state of a [`SenderRatchet`] that can be used for
+/// diagnostics, e.g. to tune the [`SenderRatchetConfiguration`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SenderRatchetState {
+    generation: Generation,
+    stored_keys: usize,
+    configuration: SenderRatchetConfiguration,
+}
+
+impl SenderRatchetState {
+    /// Returns the generation of the next message that is expected from the
+    /// sender, i.e. the number of messages that were received from the sender
+    /// or skipped in the current epoch.
+    pub fn generation(&self) -> Generation {
+        self.generation
+    }
+
+    /// Returns the number of keys of skipped generations that are stored to
+    /// decrypt out-of-order messages. At most
+    /// [`SenderRatchetConfiguration::out_of_order_tolerance()`] keys are
+    /// stored.
+    pub fn stored_keys(&self) -> usize {
+        self.stored_keys
+    }
+
+    /// Returns the [`SenderRatchetConfiguration`] that applies to the ratchet.
+    pub fn configuration(&self) -> &SenderRatchetConfiguration {
+        &self.configuration
+    }
+}
+
 /// The key material derived from a [`RatchetSecret`] meant for use with a
 /// nonce-based symmetric encryption scheme.
 pub(crate) type RatchetKeyMaterial = (AeadKey, AeadNonce);
@@ -79,13 +217,26 @@ pub(crate) enum SenderRatchet {
 }
 
 impl SenderRatchet {
-    #[cfg(test)]
     pub(crate) fn generation(&self) -> Generation {
         match self {
             SenderRatchet::EncryptionRatchet(enc_ratchet) => enc_ratchet.generation(),
             SenderRatchet::DecryptionRatchet(dec_ratchet) => dec_ratchet.generation(),
         }
     }
+
+    /// Returns a snapshot of the state of this ratchet.
+    pub(crate) fn state(&self, configuration: &SenderRatchetConfiguration) -> SenderRatchetState {
+        let stored_keys = match self {
+            // Encryption ratchets never keep past secrets around.
+            SenderRatchet::EncryptionRatchet(_) => 0,
+            SenderRatchet::DecryptionRatchet(dec_ratchet) => dec_ratchet.stored_keys(),
+        };
+        SenderRatchetState {
+            generation: self.generation(),
+            stored_keys,
+            configuration: configuration.clone(),
+        }
+    }
 }
 
 /// The core of both types of [`SenderRatchet`]. It contains the current head of
@@ -202,6 +353,15 @@ impl DecryptionRatchet {
         self.ratchet_head.generation()
     }
 
+    /// Get the number of past secrets that are still available for
+    /// decryption.
+    pub(crate) fn stored_keys(&self) -> usize {
+        self.past_secrets
+            .iter()
+            .filter(|secret| secret.is_some())
+            .count()
+    }
+
     #[cfg(test)]
     pub(crate) fn ratchet_secret_mut(&mut self) -> &mut RatchetSecret {
         &mut self.ratchet_head
@@ -71,7 +71,7 @@ use crate::{
     group::*,
     schedule::{EncryptionSecret, SenderDataSecret},
     test_utils::*,
-    tree::{secret_tree::SecretTree, sender_ratchet::SenderRatchetConfiguration},
+    tree::secret_tree::SecretTree,
     versions::ProtocolVersion,
 };
 
@@ -346,15 +346,11 @@ pub fn run_test_vector(
             commit: CommitIn,
             commit_pub: MlsMessageIn,
         ) {
-            // Group stuff we need for openmls
-            let sender_ratchet_config = SenderRatchetConfiguration::new(10, 10);
-
             // check that the commit in commit_pub == commit
             let decrypted_message = group
                 .decrypt_message(
                     provider.crypto(),
                     commit_pub.into_protocol_message().unwrap(),
-                    &sender_ratchet_config,
                 )
                 .unwrap();
 
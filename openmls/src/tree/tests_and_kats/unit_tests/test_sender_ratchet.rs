@@ -143,3 +143,48 @@ fn sender_ratchet_generation_overflow() {
         .expect_err("no error exceeding generation u32::MAX");
     assert_eq!(err, SecretTreeError::RatchetTooLong)
 }
+
+#[test]
+fn sender_ratchet_configuration_builder() {
+    // The builder starts from the default configuration.
+    assert_eq!(
+        SenderRatchetConfiguration::builder().build(),
+        Ok(SenderRatchetConfiguration::default())
+    );
+
+    let configuration = SenderRatchetConfiguration::builder()
+        .out_of_order_tolerance(20)
+        .maximum_forward_distance(5000)
+        .build()
+        .expect("Error building configuration.");
+    assert_eq!(
+        configuration,
+        SenderRatchetConfiguration::mobile_lossy_network()
+    );
+
+    assert_eq!(
+        SenderRatchetConfiguration::builder()
+            .out_of_order_tolerance(SenderRatchetConfiguration::MAX_OUT_OF_ORDER_TOLERANCE + 1)
+            .build(),
+        Err(SenderRatchetConfigurationError::OutOfOrderToleranceTooLarge)
+    );
+    assert_eq!(
+        SenderRatchetConfiguration::builder()
+            .maximum_forward_distance(u32::MAX)
+            .build(),
+        Err(SenderRatchetConfigurationError::MaximumForwardDistanceTooLarge)
+    );
+
+    // All presets pass the validation.
+    for preset in [
+        SenderRatchetConfiguration::strict_forward_secrecy(),
+        SenderRatchetConfiguration::mobile_lossy_network(),
+        SenderRatchetConfiguration::server_bridge(),
+    ] {
+        let built = SenderRatchetConfiguration::builder()
+            .out_of_order_tolerance(preset.out_of_order_tolerance())
+            .maximum_forward_distance(preset.maximum_forward_distance())
+            .build();
+        assert_eq!(built, Ok(preset));
+    }
+}
@@ -0,0 +1,149 @@
+//! # Tree math
+//!
+//! This module exposes the array-based tree math that OpenMLS uses for its
+//! ratchet trees (see Appendix C of the MLS specification), so that Delivery
+//! Services and tooling can reason about OpenMLS trees without re-implementing
+//! it.
+//!
+//! Nodes are addressed with [`LeafNodeIndex`], [`ParentNodeIndex`] and
+//! [`TreeNodeIndex`]. Functions that depend on the shape of the tree take the
+//! number of leaves of the tree. Like OpenMLS' ratchet trees, the tree is
+//! always considered to be full, i.e. the leaf count is rounded up to the next
+//! power of two. Leaf counts of `0` are treated as `1`.
+
+use crate::binary_tree::array_representation::{self, TreeSize, MAX_TREE_SIZE};
+pub use crate::binary_tree::{LeafNodeIndex, ParentNodeIndex, TreeNodeIndex};
+
+/// Returns the smallest full tree that contains `leaf_count` leaves.
+fn tree_size(leaf_count: u32) -> TreeSize {
+    let leaf_count = leaf_count.clamp(1, MAX_TREE_SIZE / 2 + 1);
+    TreeSize::new(2 * leaf_count - 1)
+}
+
+/// Returns `true` if the given node is part of a tree with `leaf_count` leaves.
+pub fn is_in_tree(node_index: impl Into<TreeNodeIndex>, leaf_count: u32) -> bool {
+    array_representation::is_node_in_tree(node_index.into(), tree_size(leaf_count))
+}
+
+/// Returns the level of the given node in the tree. Leaves are at level 0.
+pub fn level(node_index: impl Into<TreeNodeIndex>) -> usize {
+    array_representation::level(node_index.into().u32())
+}
+
+/// Returns the root of a tree with `leaf_count` leaves.
+pub fn root(leaf_count: u32) -> TreeNodeIndex {
+    array_representation::root(tree_size(leaf_count))
+}
+
+/// Returns the left child of the given parent node.
+pub fn left(parent_index: ParentNodeIndex) -> TreeNodeIndex {
+    array_representation::left(parent_index)
+}
+
+/// Returns the right child of the given parent node.
+pub fn right(parent_index: ParentNodeIndex) -> TreeNodeIndex {
+    array_representation::right(parent_index)
+}
+
+/// Returns the parent of the given node in a tree with `leaf_count` leaves, or
+/// `None` if the node is the root or is outside of the tree.
+pub fn parent(node_index: impl Into<TreeNodeIndex>, leaf_count: u32) -> Option<ParentNodeIndex> {
+    let node_index = node_index.into();
+    (is_in_tree(node_index, leaf_count) && node_index != root(leaf_count))
+        .then(|| array_representation::parent(node_index))
+}
+
+/// Returns the sibling of the given node in a tree with `leaf_count` leaves, or
+/// `None` if the node is the root or is outside of the tree.
+pub fn sibling(node_index: impl Into<TreeNodeIndex>, leaf_count: u32) -> Option<TreeNodeIndex> {
+    let node_index = node_index.into();
+    (is_in_tree(node_index, leaf_count) && node_index != root(leaf_count))
+        .then(|| array_representation::sibling(node_index))
+}
+
+/// Returns the direct path of the given leaf in a tree with `leaf_count`
+/// leaves, beginning with the leaf's parent and ending with the root. Returns
+/// `None` if the leaf is outside of the tree.
+pub fn direct_path(leaf_index: LeafNodeIndex, leaf_count: u32) -> Option<Vec<ParentNodeIndex>> {
+    is_in_tree(leaf_index, leaf_count)
+        .then(|| array_representation::direct_path(leaf_index, tree_size(leaf_count)))
+}
+
+/// Returns the copath of the given leaf in a tree with `leaf_count` leaves,
+/// beginning with the leaf's sibling. Returns `None` if the leaf is outside of
+/// the tree.
+pub fn copath(leaf_index: LeafNodeIndex, leaf_count: u32) -> Option<Vec<TreeNodeIndex>> {
+    if !is_in_tree(leaf_index, leaf_count) {
+        return None;
+    }
+    // If the leaf is the root, there's no copath.
+    if TreeNodeIndex::from(leaf_index) == root(leaf_count) {
+        return Some(vec![]);
+    }
+    Some(array_representation::copath(
+        leaf_index,
+        tree_size(leaf_count),
+    ))
+}
+
+/// Returns the lowest common ancestor of two leaves, i.e. the node where their
+/// direct paths intersect, or `None` if the two leaves are the same.
+pub fn lowest_common_ancestor(
+    leaf_index_1: LeafNodeIndex,
+    leaf_index_2: LeafNodeIndex,
+) -> Option<ParentNodeIndex> {
+    (leaf_index_1 != leaf_index_2)
+        .then(|| array_representation::lowest_common_ancestor(leaf_index_1, leaf_index_2))
+}
+
+#[test]
+fn tree_math() {
+    let leaf = LeafNodeIndex::new;
+    let parent_node = ParentNodeIndex::new;
+
+    // A tree with three leaves is a full tree with four leaves.
+    assert_eq!(root(1), TreeNodeIndex::Leaf(leaf(0)));
+    assert_eq!(root(3), TreeNodeIndex::Parent(parent_node(1)));
+    assert!(is_in_tree(leaf(3), 3));
+    assert!(!is_in_tree(leaf(4), 3));
+
+    assert_eq!(level(leaf(2)), 0);
+    assert_eq!(level(parent_node(1)), 2);
+    assert_eq!(left(parent_node(1)), TreeNodeIndex::Parent(parent_node(0)));
+    assert_eq!(right(parent_node(2)), TreeNodeIndex::Leaf(leaf(3)));
+
+    assert_eq!(parent(leaf(2), 4), Some(parent_node(2)));
+    assert_eq!(parent(parent_node(1), 4), None);
+    assert_eq!(parent(leaf(0), 1), None);
+    assert_eq!(sibling(leaf(2), 4), Some(TreeNodeIndex::Leaf(leaf(3))));
+    assert_eq!(
+        sibling(parent_node(0), 4),
+        Some(TreeNodeIndex::Parent(parent_node(2)))
+    );
+    assert_eq!(sibling(leaf(4), 4), None);
+
+    assert_eq!(
+        direct_path(leaf(2), 4),
+        Some(vec![parent_node(2), parent_node(1)])
+    );
+    assert_eq!(direct_path(leaf(0), 1), Some(vec![]));
+    assert_eq!(direct_path(leaf(4), 4), None);
+    assert_eq!(
+        copath(leaf(2), 4),
+        Some(vec![
+            TreeNodeIndex::Leaf(leaf(3)),
+            TreeNodeIndex::Parent(parent_node(0))
+        ])
+    );
+    assert_eq!(copath(leaf(0), 1), Some(vec![]));
+
+    assert_eq!(
+        lowest_common_ancestor(leaf(0), leaf(1)),
+        Some(parent_node(0))
+    );
+    assert_eq!(
+        lowest_common_ancestor(leaf(1), leaf(2)),
+        Some(parent_node(1))
+    );
+    assert_eq!(lowest_common_ancestor(leaf(3), leaf(3)), None);
+}
@@ -0,0 +1,58 @@
+//! # Tree audits
+//!
+//! When a tree is imported, e.g. from a `Welcome` or for an external join,
+//! OpenMLS verifies the parent hashes of all parent nodes and rejects the
+//! tree on the first mismatch. [`ParentHashFinding`]s are the result of
+//! re-running that validation over the whole tree on demand, together with
+//! the checks on the position of unmerged leaves, so that every problematic
+//! node is reported rather than just the first one.
+
+use serde::{Deserialize, Serialize};
+
+use crate::binary_tree::{LeafNodeIndex, ParentNodeIndex};
+
+/// A problem with a non-blank parent node found when auditing a tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParentHashFinding {
+    node_index: ParentNodeIndex,
+    issue: ParentHashIssue,
+}
+
+impl ParentHashFinding {
+    pub(super) fn new(node_index: ParentNodeIndex, issue: ParentHashIssue) -> Self {
+        Self { node_index, issue }
+    }
+
+    /// Returns the index of the parent node the finding is about.
+    pub fn node_index(&self) -> ParentNodeIndex {
+        self.node_index
+    }
+
+    /// Returns the problem that was found.
+    pub fn issue(&self) -> &ParentHashIssue {
+        &self.issue
+    }
+}
+
+/// The problem described by a [`ParentHashFinding`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParentHashIssue {
+    /// No node in the resolution of either child carries the parent hash of
+    /// the node.
+    NoMatchingDescendant,
+    /// Nodes in the resolutions of both children carry the parent hash of the
+    /// node.
+    AmbiguousDescendants,
+    /// The unmerged leaf is not a descendant of the node.
+    UnmergedLeafNotDescendant(LeafNodeIndex),
+    /// The unmerged leaf is blank.
+    UnmergedLeafBlank(LeafNodeIndex),
+    /// The unmerged leaf is missing from the unmerged leaves of a non-blank
+    /// parent node between the leaf and the node.
+    UnmergedLeafMissingBelow {
+        /// The unmerged leaf.
+        leaf_index: LeafNodeIndex,
+        /// The parent node that doesn't list the leaf as unmerged.
+        intermediate: ParentNodeIndex,
+    },
+}
@@ -27,6 +27,7 @@ use serde::{Deserialize, Serialize};
 
 use super::node::leaf_node::UpdateLeafNodeParams;
 use super::{
+    audit::{ParentHashFinding, ParentHashIssue},
     errors::*,
     node::{
         encryption_keys::{EncryptionKey, EncryptionKeyPair, EncryptionPrivateKey},
@@ -76,6 +77,16 @@ impl StagedTreeSyncDiff {
     ) {
         (self.diff, self.new_tree_hash)
     }
+
+    /// Returns an iterator over the leaves that are changed by this diff,
+    /// where `None` indicates a blanked leaf.
+    pub(crate) fn changed_leaves(
+        &self,
+    ) -> impl Iterator<Item = (LeafNodeIndex, Option<&LeafNode>)> {
+        self.diff
+            .leaves()
+            .map(|(index, leaf)| (index, leaf.node().as_ref()))
+    }
 }
 
 /// A [`TreeSyncDiff`] serves as a way to perform changes on an otherwise
@@ -97,6 +108,18 @@ impl<'a> From<&'a TreeSync> for TreeSyncDiff<'a> {
 }
 
 impl TreeSyncDiff<'_> {
+    /// Direct path of the leaf with the given index, from the leaf's parent
+    /// to the root.
+    pub(crate) fn direct_path(&self, leaf_index: LeafNodeIndex) -> Vec<ParentNodeIndex> {
+        self.diff.direct_path(leaf_index)
+    }
+
+    /// Copath of the leaf with the given index, beginning with the leaf's
+    /// sibling.
+    pub(crate) fn copath(&self, leaf_index: LeafNodeIndex) -> Vec<TreeNodeIndex> {
+        self.diff.copath(leaf_index)
+    }
+
     /// Filtered direct path, skips the nodes whose copath resolution is empty.
     pub(crate) fn filtered_direct_path(&self, leaf_index: LeafNodeIndex) -> Vec<ParentNodeIndex> {
         // Full direct path
@@ -228,6 +251,51 @@ impl TreeSyncDiff<'_> {
     ) -> Result<LeafNodeIndex, TreeSyncAddLeaf> {
         // Find a free leaf and fill it with the new key package.
         let leaf_index = self.free_leaf_index();
+        self.add_leaf_at(leaf_index, leaf_node)?;
+        Ok(leaf_index)
+    }
+
+    /// Adds the given leaves like [`Self::add_leaf()`], but only searches the
+    /// tree for free leaves once, so that adding many leaves doesn't scan the
+    /// tree for each of them.
+    ///
+    /// Returns the LeafNodeIndex of each new leaf, in the given order.
+    pub(crate) fn add_leaves<'a>(
+        &mut self,
+        leaf_nodes: impl ExactSizeIterator<Item = &'a LeafNode>,
+    ) -> Result<Vec<LeafNodeIndex>, TreeSyncAddLeaf> {
+        // The left-most blank leaves are filled first, then the tree is
+        // extended to the right.
+        let new_leaves = leaf_nodes.len();
+        let mut leaf_count = 0;
+        let mut leaf_indices = Vec::with_capacity(new_leaves);
+        for (leaf_index, leaf_id) in self.diff.leaves() {
+            if leaf_indices.len() == new_leaves {
+                break;
+            }
+            if leaf_id.node().is_none() {
+                leaf_indices.push(leaf_index);
+            }
+            leaf_count += 1;
+        }
+        while leaf_indices.len() < new_leaves {
+            leaf_indices.push(LeafNodeIndex::new(leaf_count));
+            leaf_count += 1;
+        }
+
+        for (&leaf_index, leaf_node) in leaf_indices.iter().zip(leaf_nodes) {
+            self.add_leaf_at(leaf_index, leaf_node.clone())?;
+        }
+        Ok(leaf_indices)
+    }
+
+    /// Puts the given leaf at the free `leaf_index`, extending the tree if
+    /// necessary, and adds it to the `unmerged_leaves` of its direct path.
+    fn add_leaf_at(
+        &mut self,
+        leaf_index: LeafNodeIndex,
+        leaf_node: LeafNode,
+    ) -> Result<(), TreeSyncAddLeaf> {
         // If the free leaf index is within the tree, put the new leaf there,
         // otherwise extend the tree first.
         while leaf_index.u32() >= self.diff.size().leaf_count() {
@@ -246,7 +314,7 @@ impl TreeSyncDiff<'_> {
                 parent_node.add_unmerged_leaf(leaf_index);
             }
         }
-        Ok(leaf_index)
+        Ok(())
     }
 
     /// Remove a group member by blanking the target leaf and its direct path.
@@ -632,60 +700,10 @@ impl TreeSyncDiff<'_> {
         // carries the parent hash it its parent hash field.
         for (parent_index, tree_sync_parent_node) in self.diff.parents() {
             if let Some(parent_node) = tree_sync_parent_node.node() {
-                // We consider both children of the parent node. One of them
-                // takes the role of the descendant, whose resolution carries
-                // the parent hash. The other one is the descendants sibling,
-                // whose original tree hash is used to compute the parent hash.
-                let left_child = self.diff.left_child(parent_index);
-                let right_child = self.diff.right_child(parent_index);
-
-                // We exclude the unmerged leaves from the parent node for the
-                // following computations. Those leaves were obviously added
-                // after the parent node was populated during a commit and must
-                // therefore be removed to recreate the tree state at the time
-                // of the commit.
-                let exclusion_list = HashSet::from_iter(parent_node.unmerged_leaves().iter());
-
-                // Compute the original tree hash (oth) for the left and right child.
-                let oth_left =
-                    self.compute_tree_hash(crypto, ciphersuite, left_child, &exclusion_list)?;
-
-                let oth_right =
-                    self.compute_tree_hash(crypto, ciphersuite, right_child, &exclusion_list)?;
-
-                // Compute the parent hash for both child roles.
-                let parent_hash_left =
-                    parent_node.compute_parent_hash(crypto, ciphersuite, &oth_right)?;
-
-                let parent_hash_right =
-                    parent_node.compute_parent_hash(crypto, ciphersuite, &oth_left)?;
-
-                // Compute the resolution for both children.
-                let left_resolution = self.resolution(left_child, &exclusion_list);
-
-                let right_resolution = self.resolution(right_child, &exclusion_list);
-
-                // Find parent hash in the left resolution.
-                let left_descendant = left_resolution.iter().find(|(_, node)| match node {
-                    NodeReference::Leaf(leaf) => leaf
-                        .parent_hash()
-                        .map(|parent_hash| parent_hash == parent_hash_left)
-                        .unwrap_or(false),
-                    NodeReference::Parent(parent) => parent.parent_hash() == parent_hash_left,
-                });
-
-                // Find parent hash in the right resolution.
-                let right_descendant = right_resolution.iter().find(|(_, node)| match node {
-                    NodeReference::Leaf(leaf) => leaf
-                        .parent_hash()
-                        .map(|parent_hash| parent_hash == parent_hash_right)
-                        .unwrap_or(false),
-                    NodeReference::Parent(parent) => parent.parent_hash() == parent_hash_right,
-                });
-
-                // If one of the parent hashes is in the resolution of the
-                // other child, the parent hash is valid.
-                if left_descendant.is_none() ^ right_descendant.is_some() {
+                if self
+                    .parent_hash_issue(crypto, ciphersuite, parent_index, parent_node)?
+                    .is_some()
+                {
                     return Err(TreeSyncParentHashError::InvalidParentHash);
                 }
             }
@@ -693,6 +711,141 @@ impl TreeSyncDiff<'_> {
         Ok(())
     }
 
+    /// Checks the parent hash of the given parent node and returns the issue
+    /// if it is invalid.
+    fn parent_hash_issue(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        parent_index: ParentNodeIndex,
+        parent_node: &ParentNode,
+    ) -> Result<Option<ParentHashIssue>, LibraryError> {
+        // We consider both children of the parent node. One of them
+        // takes the role of the descendant, whose resolution carries
+        // the parent hash. The other one is the descendants sibling,
+        // whose original tree hash is used to compute the parent hash.
+        let left_child = self.diff.left_child(parent_index);
+        let right_child = self.diff.right_child(parent_index);
+
+        // We exclude the unmerged leaves from the parent node for the
+        // following computations. Those leaves were obviously added
+        // after the parent node was populated during a commit and must
+        // therefore be removed to recreate the tree state at the time
+        // of the commit.
+        let exclusion_list = HashSet::from_iter(parent_node.unmerged_leaves().iter());
+
+        // Compute the original tree hash (oth) for the left and right child.
+        let oth_left = self.compute_tree_hash(crypto, ciphersuite, left_child, &exclusion_list)?;
+
+        let oth_right =
+            self.compute_tree_hash(crypto, ciphersuite, right_child, &exclusion_list)?;
+
+        // Compute the parent hash for both child roles.
+        let parent_hash_left = parent_node.compute_parent_hash(crypto, ciphersuite, &oth_right)?;
+
+        let parent_hash_right = parent_node.compute_parent_hash(crypto, ciphersuite, &oth_left)?;
+
+        // Compute the resolution for both children.
+        let left_resolution = self.resolution(left_child, &exclusion_list);
+
+        let right_resolution = self.resolution(right_child, &exclusion_list);
+
+        // Find parent hash in the left resolution.
+        let left_descendant = left_resolution.iter().find(|(_, node)| match node {
+            NodeReference::Leaf(leaf) => leaf
+                .parent_hash()
+                .map(|parent_hash| parent_hash == parent_hash_left)
+                .unwrap_or(false),
+            NodeReference::Parent(parent) => parent.parent_hash() == parent_hash_left,
+        });
+
+        // Find parent hash in the right resolution.
+        let right_descendant = right_resolution.iter().find(|(_, node)| match node {
+            NodeReference::Leaf(leaf) => leaf
+                .parent_hash()
+                .map(|parent_hash| parent_hash == parent_hash_right)
+                .unwrap_or(false),
+            NodeReference::Parent(parent) => parent.parent_hash() == parent_hash_right,
+        });
+
+        // If one of the parent hashes is in the resolution of the other
+        // child, the parent hash is valid.
+        Ok(match (left_descendant, right_descendant) {
+            (None, None) => Some(ParentHashIssue::NoMatchingDescendant),
+            (Some(_), Some(_)) => Some(ParentHashIssue::AmbiguousDescendants),
+            _ => None,
+        })
+    }
+
+    /// Checks that the unmerged leaves of the given parent node are
+    /// non-blank descendants of the node and that they are listed as
+    /// unmerged in all non-blank parent nodes between them and the node.
+    fn unmerged_leaves_issues(
+        &self,
+        parent_index: ParentNodeIndex,
+        parent_node: &ParentNode,
+    ) -> Vec<ParentHashIssue> {
+        let mut issues = Vec::new();
+        for &leaf_index in parent_node.unmerged_leaves() {
+            if leaf_index.u32() >= self.leaf_count() {
+                issues.push(ParentHashIssue::UnmergedLeafNotDescendant(leaf_index));
+                continue;
+            }
+            let direct_path = self.direct_path(leaf_index);
+            let Some(position) = direct_path.iter().position(|index| *index == parent_index) else {
+                issues.push(ParentHashIssue::UnmergedLeafNotDescendant(leaf_index));
+                continue;
+            };
+            if self.diff.leaf(leaf_index).node().is_none() {
+                issues.push(ParentHashIssue::UnmergedLeafBlank(leaf_index));
+            }
+            for &intermediate in &direct_path[..position] {
+                if let Some(node) = self.diff.parent(intermediate).node() {
+                    if !node.unmerged_leaves().contains(&leaf_index) {
+                        issues.push(ParentHashIssue::UnmergedLeafMissingBelow {
+                            leaf_index,
+                            intermediate,
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// Re-runs the parent hash and unmerged leaf validation over all parent
+    /// nodes in the tree and returns a finding for every problem, instead of
+    /// stopping at the first one.
+    pub(super) fn audit_parent_hashes(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+    ) -> Result<Vec<ParentHashFinding>, LibraryError> {
+        let mut findings = Vec::new();
+        for (parent_index, tree_sync_parent_node) in self.diff.parents() {
+            if let Some(parent_node) = tree_sync_parent_node.node() {
+                // Check the unmerged leaves first, since the parent hash
+                // computation assumes that they are well-formed.
+                let unmerged_leaves_issues = self.unmerged_leaves_issues(parent_index, parent_node);
+                let well_formed = unmerged_leaves_issues.is_empty();
+                findings.extend(
+                    unmerged_leaves_issues
+                        .into_iter()
+                        .map(|issue| ParentHashFinding::new(parent_index, issue)),
+                );
+                if !well_formed {
+                    continue;
+                }
+                if let Some(issue) =
+                    self.parent_hash_issue(crypto, ciphersuite, parent_index, parent_node)?
+                {
+                    findings.push(ParentHashFinding::new(parent_index, issue));
+                }
+            }
+        }
+        Ok(findings)
+    }
+
     /// This turns the diff into a staged diff. In the process, the diff
     /// computes and sets the new tree hash.
     pub(crate) fn into_staged_diff(
@@ -750,6 +903,45 @@ impl TreeSyncDiff<'_> {
         self.diff.leaf(index).node().as_ref()
     }
 
+    /// Returns the number of nodes that each path secret of an update path
+    /// sent by the leaf with the given index would be encrypted to, skipping
+    /// the nodes whose copath resolution is empty. The leaves in
+    /// `exclusion_list` are not counted.
+    pub(crate) fn path_recipient_counts(
+        &self,
+        leaf_index: LeafNodeIndex,
+        exclusion_list: &HashSet<&LeafNodeIndex>,
+    ) -> Vec<usize> {
+        self.filtered_copath_resolutions(leaf_index, exclusion_list)
+            .iter()
+            .map(Vec::len)
+            .collect()
+    }
+
+    /// Returns an iterator over the (non-blank) [`LeafNode`]s in the tree
+    /// resulting from merging this diff, together with their indices.
+    pub(crate) fn full_leaves_with_index(
+        &self,
+    ) -> impl Iterator<Item = (LeafNodeIndex, &LeafNode)> {
+        self.diff
+            .leaves()
+            .filter_map(|(index, leaf)| leaf.node().as_ref().map(|leaf| (index, leaf)))
+    }
+
+    /// Return a reference to the parent node with the given index, or `None`
+    /// if the node is blank or outside of the tree.
+    #[cfg(test)]
+    pub(crate) fn parent_mut(&mut self, index: ParentNodeIndex) -> Option<&mut ParentNode> {
+        self.diff.parent_mut(index).node_mut().as_mut()
+    }
+
+    pub(crate) fn parent(&self, index: ParentNodeIndex) -> Option<&ParentNode> {
+        if index.u32() >= self.diff.size().parent_count() {
+            return None;
+        }
+        self.diff.parent(index).node().as_ref()
+    }
+
     /// Compute and set the tree hash of all nodes in the tree.
     pub(crate) fn compute_tree_hashes(
         &self,
@@ -115,6 +115,23 @@ pub(crate) enum TreeSyncError {
     UnsupportedProposal,
 }
 
+/// Tree slice error
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum TreeSliceError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The leaf is outside of the tree.
+    #[error("The leaf is outside of the tree.")]
+    LeafOutsideTree,
+    /// The paths of the slice don't match the size of the tree.
+    #[error("The paths of the slice don't match the size of the tree.")]
+    MalformedSlice,
+    /// The tree hash of the slice doesn't match the expected one.
+    #[error("The tree hash of the slice doesn't match the expected one.")]
+    TreeHashMismatch,
+}
+
 /// Derive path error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub(crate) enum DerivePathError {
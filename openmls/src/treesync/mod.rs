@@ -32,12 +32,9 @@ use tls_codec::{TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize};
 
 use self::{
     diff::{StagedTreeSyncDiff, TreeSyncDiff},
-    node::{
-        leaf_node::{
-            Capabilities, LeafNodeSource, NewLeafNodeParams, TreeInfoTbs, TreePosition,
-            VerifiableLeafNode,
-        },
-        NodeIn,
+    node::leaf_node::{
+        Capabilities, LeafNodeSource, NewLeafNodeParams, TreeInfoTbs, TreePosition,
+        VerifiableLeafNode,
     },
     treesync_node::{TreeSyncLeafNode, TreeSyncNode, TreeSyncParentNode},
 };
@@ -61,12 +58,14 @@ use crate::{
 };
 
 // Private
+mod audit;
 mod hashes;
 use errors::*;
 
 // Crate
 pub(crate) mod diff;
 pub(crate) mod node;
+pub(crate) mod slice;
 pub(crate) mod treekem;
 pub(crate) mod treesync_node;
 
@@ -79,11 +78,15 @@ pub use node::encryption_keys::test_utils;
 pub use node::encryption_keys::EncryptionKey;
 
 // Public re-exports
+pub use audit::{ParentHashFinding, ParentHashIssue};
 pub use node::{
-    leaf_node::{LeafNode, LeafNodeParameters, LeafNodeParametersBuilder, LeafNodeUpdateError},
+    leaf_node::{
+        LeafNode, LeafNodeIn, LeafNodeParameters, LeafNodeParametersBuilder, LeafNodeUpdateError,
+    },
     parent_node::ParentNode,
-    Node,
+    Node, NodeIn,
 };
+pub use slice::{CopathNode, TreeSlice};
 
 // Tests
 #[cfg(any(feature = "test-utils", test))]
@@ -236,6 +239,13 @@ impl RatchetTreeIn {
         RatchetTree::try_from_nodes(ciphersuite, crypto, self.0, group_id)
     }
 
+    /// Returns the (unverified) nodes of the ratchet tree in array
+    /// representation, i.e. leaves at even and parents at odd indices. Blank
+    /// nodes are `None`.
+    pub fn nodes(&self) -> &[Option<NodeIn>] {
+        &self.0
+    }
+
     fn from_ratchet_tree(ratchet_tree: RatchetTree) -> Self {
         let nodes = ratchet_tree
             .0
@@ -537,6 +547,16 @@ impl TreeSync {
         diff.verify_parent_hashes(crypto, ciphersuite)
     }
 
+    /// Re-runs the parent hash and unmerged leaf validation over all parent
+    /// nodes in the tree and returns all findings.
+    pub(crate) fn audit_parent_hashes(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+    ) -> Result<Vec<ParentHashFinding>, LibraryError> {
+        self.empty_diff().audit_parent_hashes(crypto, ciphersuite)
+    }
+
     /// Returns the tree size
     pub(crate) fn tree_size(&self) -> TreeSize {
         self.tree.tree_size()
@@ -549,6 +569,16 @@ impl TreeSync {
             .filter_map(|(_, tsn)| tsn.node().as_ref())
     }
 
+    /// Returns an iterator over the (non-blank) [`LeafNode`]s in the tree
+    /// together with their indices.
+    pub(crate) fn full_leaves_with_index(
+        &self,
+    ) -> impl Iterator<Item = (LeafNodeIndex, &LeafNode)> {
+        self.tree
+            .leaves()
+            .filter_map(|(index, tsn)| tsn.node().as_ref().map(|ln| (index, ln)))
+    }
+
     /// Returns an iterator over the (non-blank) [`ParentNode`]s in the tree.
     pub(crate) fn full_parents(&self) -> impl Iterator<Item = (ParentNodeIndex, &ParentNode)> {
         self.tree
@@ -38,6 +38,8 @@ pub enum Node {
     ParentNode(ParentNode),
 }
 
+/// A [`Node`] as it was received, before its signature and its position in
+/// the tree have been verified.
 #[derive(
     Debug,
     PartialEq,
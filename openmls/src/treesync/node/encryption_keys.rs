@@ -3,7 +3,7 @@ use std::fmt::Debug;
 use openmls_traits::{
     crypto::OpenMlsCrypto,
     random::OpenMlsRand,
-    storage::{StorageProvider as StorageProviderTrait, CURRENT_VERSION},
+    storage::{StorageReader as _, StorageWriter as StorageWriterTrait, CURRENT_VERSION},
     types::{Ciphersuite, HpkeCiphertext, HpkeKeyPair},
 };
 use serde::{Deserialize, Serialize};
@@ -41,7 +41,7 @@ impl EncryptionKey {
     }
 
     /// Return the internal [`HpkePublicKey`] as slice.
-    pub(crate) fn as_slice(&self) -> &[u8] {
+    pub fn as_slice(&self) -> &[u8] {
         self.key.as_slice()
     }
 
@@ -86,7 +86,7 @@ impl Debug for EncryptionPrivateKey {
         #[cfg(feature = "crypto-debug")]
         ds.field("key", &self.key);
         #[cfg(not(feature = "crypto-debug"))]
-        ds.field("key", &"***");
+        ds.field("len", &self.key.len());
 
         ds.finish()
     }
@@ -190,7 +190,7 @@ impl EncryptionKeyPair {
     /// This must only be used for encryption key pairs that are generated for
     /// update leaf nodes. All other encryption key pairs are stored as part
     /// of the key package or the epoch encryption key pairs.
-    pub(crate) fn delete<Storage: StorageProviderTrait<CURRENT_VERSION>>(
+    pub(crate) fn delete<Storage: StorageWriterTrait<CURRENT_VERSION>>(
         &self,
         store: &Storage,
     ) -> Result<(), Storage::Error> {
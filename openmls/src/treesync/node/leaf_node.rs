@@ -256,6 +256,29 @@ impl LeafNode {
             .map_err(|_| LibraryError::custom("Signing failed"))
     }
 
+    /// Returns a copy of this key package leaf node with the given
+    /// `lifetime`, signed with `signer`. All other values, including the
+    /// encryption key, are kept.
+    pub(crate) fn with_lifetime(
+        &self,
+        lifetime: Lifetime,
+        signer: &impl Signer,
+    ) -> Result<Self, crate::ciphersuite::signable::SignatureError> {
+        let leaf_node_tbs = LeafNodeTbs::new(
+            self.payload.encryption_key.clone(),
+            CredentialWithKey {
+                credential: self.payload.credential.clone(),
+                signature_key: self.payload.signature_key.clone(),
+            },
+            self.payload.capabilities.clone(),
+            LeafNodeSource::KeyPackage(lifetime),
+            self.payload.extensions.clone(),
+            TreeInfoTbs::KeyPackage,
+        );
+
+        leaf_node_tbs.sign(signer)
+    }
+
     /// New [`LeafNode`] with a parent hash.
     #[allow(clippy::too_many_arguments)]
     pub(in crate::treesync) fn new_with_parent_hash(
@@ -364,6 +387,14 @@ impl LeafNode {
             leaf_node_tbs.payload.capabilities = capabilities;
         }
 
+        // Make sure peers won't reject the leaf node because of inconsistent
+        // capabilities.
+        leaf_node_tbs.payload.capabilities.validate_for_leaf_node(
+            ciphersuite,
+            leaf_node_tbs.payload.credential.credential_type(),
+            &leaf_node_tbs.payload.extensions,
+        )?;
+
         // Create a new encryption key pair
         let encryption_key_pair =
             EncryptionKeyPair::random(provider.rand(), provider.crypto(), ciphersuite)?;
@@ -509,6 +540,36 @@ impl LeafNode {
 
         Ok(())
     }
+
+    /// Verifies the signature of this leaf node, assuming it is at
+    /// `leaf_index` in the tree of the group with the given `group_id`.
+    #[cfg(feature = "integrity-checks")]
+    pub(crate) fn verify_signature_at(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        group_id: &GroupId,
+        leaf_index: LeafNodeIndex,
+    ) -> Result<(), crate::ciphersuite::signable::SignatureError> {
+        let tree_position = TreePosition::new(group_id.clone(), leaf_index);
+        let verifiable_leaf_node = LeafNodeIn::from(self.clone()).into_verifiable_leaf_node();
+        let signature_key = verifiable_leaf_node
+            .signature_key()
+            .clone()
+            .into_signature_public_key_enriched(ciphersuite.signature_algorithm());
+        match verifiable_leaf_node {
+            VerifiableLeafNode::KeyPackage(leaf_node) => leaf_node.verify(crypto, &signature_key),
+            VerifiableLeafNode::Update(mut leaf_node) => {
+                leaf_node.add_tree_position(tree_position);
+                leaf_node.verify(crypto, &signature_key)
+            }
+            VerifiableLeafNode::Commit(mut leaf_node) => {
+                leaf_node.add_tree_position(tree_position);
+                leaf_node.verify(crypto, &signature_key)
+            }
+        }
+        .map(|_| ())
+    }
 }
 
 /// The payload of a [`LeafNode`]
@@ -698,6 +759,8 @@ impl TreePosition {
 
 const LEAF_NODE_SIGNATURE_LABEL: &str = "LeafNodeTBS";
 
+/// A [`LeafNode`] as it was received, before its signature has been
+/// verified.
 #[derive(
     Debug,
     Clone,
@@ -758,6 +821,16 @@ impl LeafNodeIn {
     pub fn credential(&self) -> &Credential {
         &self.payload.credential
     }
+
+    /// Returns the `capabilities` of this leaf node.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.payload.capabilities
+    }
+
+    /// Returns the `extensions` of this leaf node.
+    pub fn extensions(&self) -> &Extensions {
+        &self.payload.extensions
+    }
 }
 
 impl From<LeafNode> for LeafNodeIn {
@@ -1005,4 +1078,8 @@ pub enum LeafNodeUpdateError<StorageError> {
     /// Signature error.
     #[error(transparent)]
     Signature(#[from] crate::ciphersuite::signable::SignatureError),
+
+    /// The capabilities are inconsistent with the leaf node.
+    #[error(transparent)]
+    LeafNodeValidation(#[from] LeafNodeValidationError),
 }
@@ -1,4 +1,7 @@
-use openmls_traits::types::{Ciphersuite, VerifiableCiphersuite};
+use openmls_traits::{
+    random::OpenMlsRand,
+    types::{Ciphersuite, VerifiableCiphersuite},
+};
 use serde::{Deserialize, Serialize};
 use tls_codec::{TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize};
 
@@ -12,6 +15,19 @@ use crate::{
     versions::ProtocolVersion,
 };
 
+/// The GREASE values of Section 13.5 of RFC 9420. They are reserved in the
+/// registries of ciphersuites, extension types, proposal types and credential
+/// types, and are advertised to make sure peers tolerate unknown values.
+pub const GREASE_VALUES: [u16; 15] = [
+    0x0A0A, 0x1A1A, 0x2A2A, 0x3A3A, 0x4A4A, 0x5A5A, 0x6A6A, 0x7A7A, 0x8A8A, 0x9A9A, 0xAAAA, 0xBABA,
+    0xCACA, 0xDADA, 0xEAEA,
+];
+
+/// Returns `true` if the `value` is one of the [`GREASE_VALUES`].
+pub fn is_grease(value: u16) -> bool {
+    GREASE_VALUES.contains(&value)
+}
+
 /// Capabilities of [`LeafNode`]s.
 ///
 /// ```text
@@ -125,6 +141,22 @@ impl Capabilities {
         &self.credentials
     }
 
+    /// Returns the [`Capabilities`] that are contained in both `self` and
+    /// `other`. The order of the entries in `self` is preserved.
+    pub fn intersection(&self, other: &Capabilities) -> Capabilities {
+        fn intersect<T: PartialEq + Clone>(a: &[T], b: &[T]) -> Vec<T> {
+            a.iter().filter(|x| b.contains(x)).cloned().collect()
+        }
+
+        Self {
+            versions: intersect(&self.versions, &other.versions),
+            ciphersuites: intersect(&self.ciphersuites, &other.ciphersuites),
+            extensions: intersect(&self.extensions, &other.extensions),
+            proposals: intersect(&self.proposals, &other.proposals),
+            credentials: intersect(&self.credentials, &other.credentials),
+        }
+    }
+
     // ---------------------------------------------------------------------------------------------
 
     /// Check if these [`Capabilities`] support all the capabilities required by
@@ -173,12 +205,59 @@ impl Capabilities {
         Ok(())
     }
 
+    /// Check that these [`Capabilities`] are consistent with the leaf node
+    /// they are used in, i.e. that they list the group's `ciphersuite`, the
+    /// `credential_type` of the leaf node's credential and the types of the
+    /// `leaf_node_extensions`.
+    ///
+    /// Peers reject leaf nodes with inconsistent capabilities, so this should
+    /// be checked before creating a [`KeyPackage`](crate::key_packages::KeyPackage)
+    /// or a [`LeafNode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LeafNodeValidationError`] naming the first inconsistency.
+    pub fn validate_for_leaf_node(
+        &self,
+        ciphersuite: Ciphersuite,
+        credential_type: CredentialType,
+        leaf_node_extensions: &Extensions,
+    ) -> Result<(), LeafNodeValidationError> {
+        if !self.contains_ciphersuite(VerifiableCiphersuite::from(ciphersuite)) {
+            log::error!(
+                "Capabilities don't contain the ciphersuite {ciphersuite:?}\n
+                Supported ciphersuites: {:?}",
+                self.ciphersuites()
+            );
+            return Err(LeafNodeValidationError::CiphersuiteNotInCapabilities);
+        }
+        if !self.contains_credential(credential_type) {
+            log::error!(
+                "Capabilities don't contain the credential type {credential_type:?}\n
+                Supported credential types: {:?}",
+                self.credentials()
+            );
+            return Err(LeafNodeValidationError::CredentialNotInCapabilities);
+        }
+        if !self.contains_extensions(leaf_node_extensions) {
+            log::error!(
+                "Capabilities don't contain all leaf node extensions\n
+                Supported extensions: {:?}\n
+                Used extensions: {:?}",
+                self.extensions(),
+                leaf_node_extensions
+            );
+            return Err(LeafNodeValidationError::ExtensionsNotInCapabilities);
+        }
+        Ok(())
+    }
+
     /// Check if these [`Capabilities`] contain all the extensions.
     pub(crate) fn contains_extensions(&self, extension: &Extensions) -> bool {
         extension
             .iter()
             .map(Extension::extension_type)
-            .all(|e| e.is_default() || self.extensions().contains(&e))
+            .all(|e| self.contains_extension(e))
     }
 
     /// Check if these [`Capabilities`] contains the credential.
@@ -251,10 +330,51 @@ impl CapabilitiesBuilder {
         })
     }
 
+    /// Adds a random GREASE value to the ciphersuites, extensions, proposals
+    /// and credentials of the [`Capabilities`], as recommended in Section 13.5
+    /// of RFC 9420.
+    ///
+    /// If the `rand` provider fails, the first GREASE value is used instead.
+    pub fn with_grease(self, rand: &impl OpenMlsRand) -> Self {
+        let indices: [u8; 4] = rand.random_array().unwrap_or_default();
+        let [ciphersuite, extension, proposal, credential] =
+            indices.map(|index| GREASE_VALUES[index as usize % GREASE_VALUES.len()]);
+
+        let mut capabilities = self.0;
+        push_if_missing(
+            &mut capabilities.ciphersuites,
+            VerifiableCiphersuite::new(ciphersuite),
+        );
+        push_if_missing(
+            &mut capabilities.extensions,
+            ExtensionType::Unknown(extension),
+        );
+        push_if_missing(&mut capabilities.proposals, ProposalType::Custom(proposal));
+        push_if_missing(
+            &mut capabilities.credentials,
+            CredentialType::Other(credential),
+        );
+        Self(capabilities)
+    }
+
     /// Builds the [`Capabilities`].
     pub fn build(self) -> Capabilities {
         self.0
     }
+
+    /// Builds the [`Capabilities`] and checks that they are consistent with
+    /// the leaf node they will be used in. See
+    /// [`Capabilities::validate_for_leaf_node()`] for details.
+    pub fn build_for_leaf_node(
+        self,
+        ciphersuite: Ciphersuite,
+        credential_type: CredentialType,
+        leaf_node_extensions: &Extensions,
+    ) -> Result<Capabilities, LeafNodeValidationError> {
+        self.0
+            .validate_for_leaf_node(ciphersuite, credential_type, leaf_node_extensions)?;
+        Ok(self.0)
+    }
 }
 
 #[cfg(test)]
@@ -285,8 +405,14 @@ impl Default for Capabilities {
     }
 }
 
+fn push_if_missing<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
 pub(super) fn default_versions() -> Vec<ProtocolVersion> {
-    vec![ProtocolVersion::Mls10]
+    ProtocolVersion::SUPPORTED.to_vec()
 }
 
 pub(super) fn default_ciphersuites() -> Vec<Ciphersuite> {
@@ -305,12 +431,20 @@ pub(super) fn default_credentials() -> Vec<CredentialType> {
 
 #[cfg(test)]
 mod tests {
-    use openmls_traits::types::{Ciphersuite, VerifiableCiphersuite};
+    use openmls_rust_crypto::OpenMlsRustCrypto;
+    use openmls_traits::{
+        types::{Ciphersuite, VerifiableCiphersuite},
+        OpenMlsProvider,
+    };
     use tls_codec::{Deserialize, Serialize};
 
-    use super::Capabilities;
+    use super::{is_grease, Capabilities, GREASE_VALUES};
     use crate::{
-        credentials::CredentialType, messages::proposals::ProposalType, prelude::ExtensionType,
+        credentials::CredentialType,
+        extensions::{Extension, Extensions, RequiredCapabilitiesExtension, UnknownExtension},
+        messages::proposals::ProposalType,
+        prelude::ExtensionType,
+        treesync::errors::LeafNodeValidationError,
         versions::ProtocolVersion,
     };
 
@@ -361,4 +495,93 @@ mod tests {
 
         assert_eq!(expected, got);
     }
+
+    #[test]
+    fn capabilities_are_validated_for_leaf_node() {
+        let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+        let leaf_node_extensions = Extensions::single(Extension::Unknown(
+            0xff00,
+            UnknownExtension(vec![0x00, 0x01, 0x02]),
+        ));
+
+        let capabilities = Capabilities::builder()
+            .extensions(vec![ExtensionType::Unknown(0xff00)])
+            .build_for_leaf_node(ciphersuite, CredentialType::Basic, &leaf_node_extensions)
+            .expect("Capabilities should be consistent.");
+        assert_eq!(capabilities.extensions(), &[ExtensionType::Unknown(0xff00)]);
+
+        // The ciphersuite is missing.
+        let err = Capabilities::builder()
+            .ciphersuites(vec![Ciphersuite::MLS_128_DHKEMP256_AES128GCM_SHA256_P256])
+            .extensions(vec![ExtensionType::Unknown(0xff00)])
+            .build_for_leaf_node(ciphersuite, CredentialType::Basic, &leaf_node_extensions)
+            .unwrap_err();
+        assert_eq!(err, LeafNodeValidationError::CiphersuiteNotInCapabilities);
+
+        // The credential type is missing.
+        let err = Capabilities::builder()
+            .extensions(vec![ExtensionType::Unknown(0xff00)])
+            .build_for_leaf_node(ciphersuite, CredentialType::X509, &leaf_node_extensions)
+            .unwrap_err();
+        assert_eq!(err, LeafNodeValidationError::CredentialNotInCapabilities);
+
+        // The extension type is missing.
+        let err = Capabilities::builder()
+            .build_for_leaf_node(ciphersuite, CredentialType::Basic, &leaf_node_extensions)
+            .unwrap_err();
+        assert_eq!(err, LeafNodeValidationError::ExtensionsNotInCapabilities);
+    }
+
+    #[test]
+    fn grease_values_are_injected_and_tolerated() {
+        let provider = OpenMlsRustCrypto::default();
+        let capabilities = Capabilities::builder().with_grease(provider.rand()).build();
+
+        assert!(capabilities
+            .extensions()
+            .iter()
+            .any(|extension_type| extension_type.is_grease()));
+        assert!(capabilities
+            .proposals()
+            .iter()
+            .any(|proposal_type| proposal_type.is_grease()));
+        assert!(capabilities
+            .credentials()
+            .iter()
+            .any(|credential_type| credential_type.is_grease()));
+        assert_eq!(
+            capabilities.ciphersuites().len(),
+            Capabilities::default().ciphersuites().len() + 1
+        );
+
+        // The injected values survive a round trip.
+        let serialized = capabilities.tls_serialize_detached().unwrap();
+        assert_eq!(
+            Capabilities::tls_deserialize_exact(serialized).unwrap(),
+            capabilities
+        );
+
+        // GREASE values are treated like any other unknown value: they are
+        // only supported if they are advertised.
+        let required_capabilities = RequiredCapabilitiesExtension::new(
+            &[ExtensionType::Unknown(GREASE_VALUES[0])],
+            &[ProposalType::Custom(GREASE_VALUES[1])],
+            &[CredentialType::Other(GREASE_VALUES[2])],
+        );
+        assert_eq!(
+            Capabilities::default().supports_required_capabilities(&required_capabilities),
+            Err(LeafNodeValidationError::UnsupportedExtensions)
+        );
+        Capabilities::builder()
+            .extensions(vec![ExtensionType::Unknown(GREASE_VALUES[0])])
+            .proposals(vec![ProposalType::Custom(GREASE_VALUES[1])])
+            .credentials(vec![CredentialType::Other(GREASE_VALUES[2])])
+            .build()
+            .supports_required_capabilities(&required_capabilities)
+            .unwrap();
+
+        assert!(GREASE_VALUES.iter().all(|value| is_grease(*value)));
+        assert!(!is_grease(0x0001));
+        assert!(!is_grease(0xFAFA));
+    }
 }
@@ -178,13 +178,15 @@ impl ParentNode {
         self.encryption_key.key()
     }
 
-    /// Return a reference to the `public_key` of this node.
-    pub(crate) fn encryption_key(&self) -> &EncryptionKey {
+    /// Return a reference to the `encryption_key` of this node.
+    pub fn encryption_key(&self) -> &EncryptionKey {
         &self.encryption_key
     }
 
-    /// Get the list of unmerged leaves.
-    pub(crate) fn unmerged_leaves(&self) -> &[LeafNodeIndex] {
+    /// Get the list of unmerged leaves, i.e. the leaves below this node that
+    /// were added after this node was last set and thus don't know its private
+    /// key.
+    pub fn unmerged_leaves(&self) -> &[LeafNodeIndex] {
         self.unmerged_leaves.list()
     }
 
@@ -219,7 +221,7 @@ impl ParentNode {
     }
 
     /// Get the parent hash value of this node.
-    pub(crate) fn parent_hash(&self) -> &[u8] {
+    pub fn parent_hash(&self) -> &[u8] {
         self.parent_hash.as_slice()
     }
 }
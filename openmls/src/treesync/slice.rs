@@ -0,0 +1,226 @@
+//! # Tree slices
+//!
+//! A [`TreeSlice`] is the part of a ratchet tree a light client needs instead
+//! of the full tree: its own leaf, the nodes on its direct path and, for every
+//! node on its copath, the tree hash and the encryption keys of its
+//! resolution. Its size is logarithmic in the size of the group.
+//!
+//! A slice is bound to the group by its tree hash: [`TreeSlice::verify()`]
+//! recomputes the tree hash of the full tree from the slice and compares it to
+//! the one in the group context. Unlike a full ratchet tree, the leaves and
+//! parent hashes outside the direct path can't be validated by the holder of
+//! the slice. See [`LightGroup`](crate::group::LightGroup) for joining a group
+//! with slices.
+
+use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use serde::{Deserialize, Serialize};
+use tls_codec::VLByteSlice;
+
+use super::{
+    errors::{DerivePathError, TreeSliceError},
+    hashes::TreeHashInput,
+    node::{encryption_keys::EncryptionKeyPair, NodeReference},
+    EncryptionKey, LeafNode, ParentNode, TreeSync,
+};
+use crate::{binary_tree::LeafNodeIndex, error::LibraryError, messages::PathSecret};
+
+/// A node on the copath of a [`TreeSlice`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopathNode {
+    tree_hash: Vec<u8>,
+    resolution: Vec<EncryptionKey>,
+}
+
+impl CopathNode {
+    /// Returns the tree hash of the subtree rooted at this node.
+    pub fn tree_hash(&self) -> &[u8] {
+        &self.tree_hash
+    }
+
+    /// Returns the encryption keys of the resolution of this node.
+    pub fn resolution(&self) -> &[EncryptionKey] {
+        &self.resolution
+    }
+}
+
+/// The part of a ratchet tree a light client needs. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeSlice {
+    leaf_count: u32,
+    leaf_index: LeafNodeIndex,
+    leaf: Option<LeafNode>,
+    direct_path: Vec<Option<ParentNode>>,
+    copath: Vec<CopathNode>,
+}
+
+impl TreeSlice {
+    /// Returns the number of leaves of the full tree.
+    pub fn leaf_count(&self) -> u32 {
+        self.leaf_count
+    }
+
+    /// Returns the index of the leaf this slice was cut for.
+    pub fn leaf_index(&self) -> LeafNodeIndex {
+        self.leaf_index
+    }
+
+    /// Returns the leaf this slice was cut for, or `None` if it is blank.
+    pub fn leaf(&self) -> Option<&LeafNode> {
+        self.leaf.as_ref()
+    }
+
+    /// Returns the nodes on the direct path of the leaf, from the leaf's
+    /// parent to the root. Blank nodes are `None`.
+    pub fn direct_path(&self) -> &[Option<ParentNode>] {
+        &self.direct_path
+    }
+
+    /// Returns the nodes on the copath of the leaf, beginning with the leaf's
+    /// sibling.
+    pub fn copath(&self) -> &[CopathNode] {
+        &self.copath
+    }
+
+    /// Recomputes the tree hash of the full tree from this slice.
+    ///
+    /// Returns [`TreeSliceError::MalformedSlice`] if the paths don't match
+    /// the size of the tree.
+    pub fn tree_hash(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+    ) -> Result<Vec<u8>, TreeSliceError> {
+        // Trees always have a power of two leaves, and both paths have one
+        // node per level.
+        if !self.leaf_count.is_power_of_two()
+            || self.leaf_index.u32() >= self.leaf_count
+            || self.direct_path.len() != self.leaf_count.trailing_zeros() as usize
+            || self.copath.len() != self.direct_path.len()
+        {
+            return Err(TreeSliceError::MalformedSlice);
+        }
+
+        let mut hash = TreeHashInput::new_leaf(&self.leaf_index, self.leaf.as_ref())
+            .hash(crypto, ciphersuite)?;
+        for (level, (parent, sibling)) in self.direct_path.iter().zip(&self.copath).enumerate() {
+            // The bit of the leaf index at this level tells whether the
+            // subtree of the leaf is the left or the right child.
+            let (left_hash, right_hash) = if (self.leaf_index.u32() >> level) & 1 == 0 {
+                (hash.as_slice(), sibling.tree_hash.as_slice())
+            } else {
+                (sibling.tree_hash.as_slice(), hash.as_slice())
+            };
+            hash = TreeHashInput::new_parent(
+                parent.as_ref(),
+                VLByteSlice(left_hash),
+                VLByteSlice(right_hash),
+            )
+            .hash(crypto, ciphersuite)?;
+        }
+
+        Ok(hash)
+    }
+
+    /// Verifies that this slice was cut from the tree with the given
+    /// `tree_hash`, e.g. the one in the group context.
+    pub fn verify(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        tree_hash: &[u8],
+    ) -> Result<(), TreeSliceError> {
+        if self.tree_hash(crypto, ciphersuite)? != tree_hash {
+            return Err(TreeSliceError::TreeHashMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Derives the key pairs of the direct path from the `path_secret` of a
+    /// commit of the leaf at `sender_index`, like
+    /// [`TreeSync::derive_path_secrets()`] does for the full tree.
+    pub(crate) fn derive_path_secrets(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        mut path_secret: PathSecret,
+        sender_index: LeafNodeIndex,
+    ) -> Result<Vec<EncryptionKeyPair>, DerivePathError> {
+        // The common path starts at the lowest common ancestor of both leaves.
+        let common_level = (self.leaf_index.u32() ^ sender_index.u32())
+            .checked_ilog2()
+            .ok_or_else(|| LibraryError::custom("The sender is the own leaf"))?
+            as usize;
+
+        let mut keypairs = Vec::new();
+        for parent_node in self.direct_path.iter().skip(common_level).flatten() {
+            // Skip the nodes for which we are an unmerged leaf.
+            if parent_node.unmerged_leaves().contains(&self.leaf_index) {
+                continue;
+            }
+            let keypair = path_secret.derive_key_pair(crypto, ciphersuite)?;
+            if parent_node.encryption_key() != keypair.public_key() {
+                return Err(DerivePathError::PublicKeyMismatch);
+            }
+            keypairs.push(keypair);
+            path_secret = path_secret.derive_path_secret(crypto, ciphersuite)?;
+        }
+
+        Ok(keypairs)
+    }
+}
+
+impl TreeSync {
+    /// Cuts the [`TreeSlice`] for the leaf at `leaf_index` from this tree.
+    pub(crate) fn tree_slice(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        leaf_index: LeafNodeIndex,
+    ) -> Result<TreeSlice, TreeSliceError> {
+        if !self.is_leaf_in_tree(leaf_index) {
+            return Err(TreeSliceError::LeafOutsideTree);
+        }
+
+        let diff = self.empty_diff();
+        let direct_path = diff
+            .direct_path(leaf_index)
+            .into_iter()
+            .map(|parent_index| diff.parent(parent_index).cloned())
+            .collect();
+        // A tree with a single leaf has no copath.
+        let copath_indices = if diff.leaf_count() == 1 {
+            vec![]
+        } else {
+            diff.copath(leaf_index)
+        };
+        let copath = copath_indices
+            .into_iter()
+            .map(|node_index| {
+                let tree_hash =
+                    diff.compute_tree_hash(crypto, ciphersuite, node_index, &Default::default())?;
+                let resolution = diff
+                    .resolution(node_index, &Default::default())
+                    .into_iter()
+                    .map(|(_, node)| match node {
+                        NodeReference::Leaf(leaf) => leaf.encryption_key().clone(),
+                        NodeReference::Parent(parent) => parent.encryption_key().clone(),
+                    })
+                    .collect();
+                Ok(CopathNode {
+                    tree_hash,
+                    resolution,
+                })
+            })
+            .collect::<Result<Vec<_>, LibraryError>>()?;
+
+        Ok(TreeSlice {
+            leaf_count: diff.leaf_count(),
+            leaf_index,
+            leaf: self.leaf(leaf_index).cloned(),
+            direct_path,
+            copath,
+        })
+    }
+}
@@ -18,7 +18,8 @@ fn that_commit_secret_is_derived_from_end_of_update_path_not_root() {
     let mls_group_create_config = MlsGroupCreateConfig::builder()
         .ciphersuite(ciphersuite)
         .use_ratchet_tree_extension(true)
-        .build();
+        .build()
+        .unwrap();
 
     struct Member<Provider: OpenMlsProvider> {
         id: Vec<u8>,
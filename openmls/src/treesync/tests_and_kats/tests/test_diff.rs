@@ -1,9 +1,11 @@
 use openmls_traits::prelude::*;
 
 use crate::{
+    binary_tree::ParentNodeIndex,
     credentials::test_utils::new_credential,
+    group::mls_group::tests_and_kats::utils::setup_alice_bob_group,
     key_packages::KeyPackageBundle,
-    treesync::{node::Node, RatchetTree, TreeSync},
+    treesync::{node::Node, LeafNodeParameters, ParentHashIssue, RatchetTree, TreeSync},
 };
 
 // Verifies that when we add a leaf to a tree with blank leaf nodes, the leaf will be added at the leftmost free leaf index
@@ -48,3 +50,40 @@ fn test_free_leaf_computation() {
 
     assert_eq!(free_leaf_index.u32(), 2u32);
 }
+
+// Verifies that auditing a tree with a corrupted parent node reports the node.
+#[openmls_test::openmls_test]
+fn test_audit_corrupted_parent_hash() {
+    let (mut alice_group, alice_signer, _bob_group, _bob_signer, _bob_credential) =
+        setup_alice_bob_group(ciphersuite, provider);
+
+    // The update path of Alice's commit populates the root.
+    alice_group
+        .self_update(provider, &alice_signer, LeafNodeParameters::default())
+        .expect("error creating commit");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging commit");
+    let tree = alice_group.public_group().treesync();
+    let root = ParentNodeIndex::new(0);
+    assert!(tree
+        .audit_parent_hashes(provider.crypto(), ciphersuite)
+        .expect("error auditing tree")
+        .is_empty());
+
+    // Changing the root invalidates the parent hash in Alice's leaf.
+    let mut diff = tree.empty_diff();
+    diff.parent_mut(root)
+        .expect("the root is blank")
+        .set_parent_hash(vec![0xff; ciphersuite.hash_length()]);
+    assert!(diff
+        .verify_parent_hashes(provider.crypto(), ciphersuite)
+        .is_err());
+
+    let findings = diff
+        .audit_parent_hashes(provider.crypto(), ciphersuite)
+        .expect("error auditing tree");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].node_index(), root);
+    assert_eq!(findings[0].issue(), &ParentHashIssue::NoMatchingDescendant);
+}
@@ -172,59 +172,69 @@ impl TreeSyncDiff<'_> {
     pub(crate) fn encrypt_group_secrets(
         &self,
         joiner_secret: &JoinerSecret,
-        invited_members: Vec<(LeafNodeIndex, AddProposal)>,
+        invited_members: &[(LeafNodeIndex, &AddProposal)],
         plain_path_option: Option<&[PlainUpdatePathNode]>,
         presharedkeys: &[PreSharedKeyId],
         encrypted_group_info: &[u8],
         crypto: &impl OpenMlsCrypto,
         encryptor_leaf_index: LeafNodeIndex,
     ) -> Result<Vec<EncryptedGroupSecrets>, LibraryError> {
-        let mut encrypted_group_secrets_vec = vec![];
-        for (leaf_index, add_proposal) in invited_members {
-            let key_package = add_proposal.key_package;
-
-            let direct_path_position = self
-                .subtree_root_position(encryptor_leaf_index, leaf_index)
-                // This can only fail if the nodes are outside the tree or identical
-                .map_err(|_| LibraryError::custom("Unexpected error in subtree_root_position"))?;
-
-            // If a plain path was given, there have to be secrets for every new member.
-            let path_secret_option = if let Some(plain_path) = plain_path_option {
-                Some(
-                    plain_path
-                        .get(direct_path_position)
-                        .map(|pupn| pupn.path_secret())
-                        // This only fails if the supplied plain path is invalid
-                        .ok_or_else(|| LibraryError::custom("Invalid plain path"))?,
-                )
-            } else {
-                None
-            };
-
-            // Create the GroupSecrets object for the respective member.
-            let group_secrets_bytes =
-                GroupSecrets::new_encoded(joiner_secret, path_secret_option, presharedkeys)
-                    .map_err(LibraryError::missing_bound_check)?;
-            let ciphertext = hpke::encrypt_with_label(
-                key_package.hpke_init_key().as_slice(),
-                "Welcome",
-                encrypted_group_info,
-                &group_secrets_bytes,
-                key_package.ciphersuite(),
-                crypto,
-            )
-            .map_err(|_| {
-                LibraryError::custom(
-                    "Error while encrypting group secrets. \
-                     This could have really only been a missing bounds check in \
-                     the serialization",
+        // Derive and encrypt the group secrets of each new member in a single
+        // pass, so that only the ciphertexts are kept.
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let invited_members = invited_members.par_iter();
+        #[cfg(target_arch = "wasm32")]
+        let invited_members = invited_members.iter();
+
+        invited_members
+            .map(|&(leaf_index, add_proposal)| {
+                let key_package = &add_proposal.key_package;
+                let direct_path_position = self
+                    .subtree_root_position(encryptor_leaf_index, leaf_index)
+                    // This can only fail if the nodes are outside the tree or identical
+                    .map_err(|_| {
+                        LibraryError::custom("Unexpected error in subtree_root_position")
+                    })?;
+
+                // If a plain path was given, there have to be secrets for every new member.
+                let path_secret_option = if let Some(plain_path) = plain_path_option {
+                    Some(
+                        plain_path
+                            .get(direct_path_position)
+                            .map(|pupn| pupn.path_secret())
+                            // This only fails if the supplied plain path is invalid
+                            .ok_or_else(|| LibraryError::custom("Invalid plain path"))?,
+                    )
+                } else {
+                    None
+                };
+
+                // Create the GroupSecrets object for the respective member.
+                let group_secrets_bytes =
+                    GroupSecrets::new_encoded(joiner_secret, path_secret_option, presharedkeys)
+                        .map_err(LibraryError::missing_bound_check)?;
+                let ciphertext = hpke::encrypt_with_label(
+                    key_package.hpke_init_key().as_slice(),
+                    "Welcome",
+                    encrypted_group_info,
+                    &group_secrets_bytes,
+                    key_package.ciphersuite(),
+                    crypto,
                 )
-            })?;
-            let encrypted_group_secrets =
-                EncryptedGroupSecrets::new(key_package.hash_ref(crypto)?, ciphertext);
-            encrypted_group_secrets_vec.push(encrypted_group_secrets);
-        }
-        Ok(encrypted_group_secrets_vec)
+                .map_err(|_| {
+                    LibraryError::custom(
+                        "Error while encrypting group secrets. \
+                         This could have really only been a missing bounds check in \
+                         the serialization",
+                    )
+                })?;
+                Ok(EncryptedGroupSecrets::new(
+                    key_package.hash_ref(crypto)?,
+                    ciphertext,
+                ))
+            })
+            .collect::<Result<Vec<EncryptedGroupSecrets>, LibraryError>>()
     }
 }
 
@@ -1,6 +1,11 @@
 //! # MLS versions
 //!
 //! Only MLS 1.0 is currently supported.
+//!
+//! Behavior that differs between protocol versions is collected in a
+//! [`VersionBehavior`] table per version, which is looked up with
+//! [`ProtocolVersion::behavior()`]. Supporting a new version means adding its
+//! table instead of changing every place that checks the version.
 
 use serde::{Deserialize, Serialize};
 use std::{fmt, io::Read};
@@ -92,6 +97,57 @@ impl fmt::Display for ProtocolVersion {
     }
 }
 
+impl ProtocolVersion {
+    /// The protocol versions supported by OpenMLS, in order of preference.
+    pub const SUPPORTED: &'static [ProtocolVersion] = &[ProtocolVersion::Mls10];
+
+    /// Returns `true` if OpenMLS supports this protocol version.
+    pub fn is_supported(&self) -> bool {
+        self.behavior().is_some()
+    }
+
+    /// Returns the version-specific behavior of this protocol version, or
+    /// `None` if the version is not supported.
+    pub(crate) fn behavior(&self) -> Option<&'static VersionBehavior> {
+        match self {
+            ProtocolVersion::Mls10 => Some(&MLS10_BEHAVIOR),
+            ProtocolVersion::Other(_) => None,
+        }
+    }
+}
+
+/// The behavior of OpenMLS that depends on the [`ProtocolVersion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct VersionBehavior {
+    /// The version this behavior belongs to.
+    pub(crate) version: ProtocolVersion,
+    /// The prefix of the labels used for key derivation, signatures, HPKE
+    /// encryption and hash references.
+    pub(crate) label_prefix: &'static str,
+    /// The HPKE info used to derive the `init_secret` of an external commit.
+    pub(crate) external_init_info: &'static str,
+}
+
+const MLS10_BEHAVIOR: VersionBehavior = VersionBehavior {
+    version: ProtocolVersion::Mls10,
+    label_prefix: "MLS 1.0 ",
+    external_init_info: "MLS 1.0 external init secret",
+};
+
+impl VersionBehavior {
+    /// Returns the behavior of the version OpenMLS uses for new groups and
+    /// for the primitives that aren't bound to a group, i.e. the default
+    /// [`ProtocolVersion`].
+    pub(crate) fn current() -> &'static Self {
+        &MLS10_BEHAVIOR
+    }
+
+    /// Returns the `label` with the label prefix of the version.
+    pub(crate) fn label(&self, label: &str) -> String {
+        format!("{}{label}", self.label_prefix)
+    }
+}
+
 /// Version Error
 #[derive(Error, Debug, PartialEq, Eq, Clone)]
 pub enum VersionError {
@@ -99,3 +155,24 @@ pub enum VersionError {
     #[error("Unsupported MLS version.")]
     UnsupportedMlsVersion,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_behavior() {
+        let current = VersionBehavior::current();
+        assert_eq!(current.version, ProtocolVersion::default());
+        assert_eq!(ProtocolVersion::SUPPORTED[0].behavior(), Some(current));
+        for version in ProtocolVersion::SUPPORTED {
+            assert_eq!(version.behavior().unwrap().version, *version);
+        }
+        assert_eq!(ProtocolVersion::Other(2).behavior(), None);
+
+        assert_eq!(
+            current.label("KeyPackage Reference"),
+            "MLS 1.0 KeyPackage Reference"
+        );
+    }
+}
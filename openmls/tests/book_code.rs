@@ -135,7 +135,8 @@ fn book_operations() {
         )))
         .expect("failed to configure leaf extensions")
         .use_ratchet_tree_extension(true)
-        .build();
+        .build()
+        .expect("invalid group config");
     // ANCHOR_END: mls_group_create_config_example
 
     // ANCHOR: alice_create_group
@@ -1155,11 +1156,12 @@ fn book_operations() {
         unreachable!("Expected a QueuedProposal.");
     }
 
-    // Should fail because you cannot remove yourself from a group
+    // Should fail because Bob is leaving the group
+    assert!(bob_group.is_pending_self_removal());
     assert!(matches!(
         bob_group.commit_to_pending_proposals(provider, &bob_signature_keys),
-        Err(CommitToPendingProposalsError::CreateCommitError(
-            CreateCommitError::CannotRemoveSelf
+        Err(CommitToPendingProposalsError::GroupStateError(
+            MlsGroupStateError::PendingSelfRemoval
         ))
     ));
 
@@ -1632,7 +1634,8 @@ fn commit_builder() {
         )))
         .expect("failed to configure leaf extensions")
         .use_ratchet_tree_extension(true)
-        .build();
+        .build()
+        .unwrap();
 
     let mut alice_group = MlsGroup::new(
         provider,
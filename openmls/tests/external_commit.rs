@@ -15,7 +15,8 @@ fn create_alice_group(
     let group_config = MlsGroupCreateConfig::builder()
         .use_ratchet_tree_extension(use_ratchet_tree_extension)
         .ciphersuite(ciphersuite)
-        .build();
+        .build()
+        .unwrap();
 
     let (credential_with_key, signature_keys) =
         new_credential(provider, b"Alice", ciphersuite.signature_algorithm());
@@ -73,7 +73,8 @@ fn mls_group_operations() {
         let mls_group_create_config = MlsGroupCreateConfig::builder()
             .wire_format_policy(*wire_format_policy)
             .ciphersuite(ciphersuite)
-            .build();
+            .build()
+            .unwrap();
 
         // === Alice creates a group ===
         let mut alice_group = MlsGroup::new_with_group_id(
@@ -841,11 +842,12 @@ fn mls_group_operations() {
             unreachable!("Expected a QueuedProposal.");
         }
 
-        // Should fail because you cannot remove yourself from a group
+        // Should fail because Bob is leaving the group
+        assert!(bob_group.is_pending_self_removal());
         assert!(matches!(
             bob_group.commit_to_pending_proposals(bob_provider, &bob_signer),
-            Err(CommitToPendingProposalsError::CreateCommitError(
-                CreateCommitError::CannotRemoveSelf
+            Err(CommitToPendingProposalsError::GroupStateError(
+                MlsGroupStateError::PendingSelfRemoval
             ))
         ));
 
@@ -1020,7 +1022,8 @@ fn addition_order() {
         let mls_group_config = MlsGroupCreateConfig::builder()
             .wire_format_policy(*wire_format_policy)
             .ciphersuite(ciphersuite)
-            .build();
+            .build()
+            .unwrap();
 
         // === Alice creates a group ===
         let mut alice_group = MlsGroup::new_with_group_id(
@@ -1157,7 +1160,8 @@ fn mls_group_ratchet_tree_extension(
             .wire_format_policy(*wire_format_policy)
             .use_ratchet_tree_extension(true)
             .ciphersuite(ciphersuite)
-            .build();
+            .build()
+            .unwrap();
 
         // === Alice creates a group ===
         let mut alice_group = MlsGroup::new_with_group_id(
@@ -78,7 +78,8 @@ fn opaque_extension() {
             None, // Defaults to all basic extension types
             Some(&[CredentialType::Basic]),
         ))
-        .build();
+        .build()
+        .unwrap();
 
     let mut alice_group = MlsGroup::new(
         provider,
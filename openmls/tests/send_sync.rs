@@ -0,0 +1,65 @@
+//! Compile-time checks of the concurrency contract of the public API.
+//!
+//! The group state and the values returned while processing messages own all
+//! of their data and don't perform any IO themselves, so they can be moved
+//! between threads and held across await points. If one of these checks
+//! stops compiling, the change that caused it breaks that contract.
+use openmls::prelude::*;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn group_state_is_send_and_sync() {
+    assert_send::<MlsGroup>();
+    assert_sync::<MlsGroup>();
+    assert_send::<PublicGroup>();
+    assert_sync::<PublicGroup>();
+    assert_send::<StagedCommit>();
+    assert_sync::<StagedCommit>();
+    assert_send::<PendingPskWelcome>();
+    assert_sync::<PendingPskWelcome>();
+    assert_send::<ProcessedWelcome>();
+    assert_sync::<ProcessedWelcome>();
+    assert_send::<ResumableMessage>();
+    assert_sync::<ResumableMessage>();
+    assert_send::<StagedWelcome>();
+    assert_sync::<StagedWelcome>();
+}
+
+#[test]
+fn messages_are_send_and_sync() {
+    assert_send::<MlsMessageIn>();
+    assert_sync::<MlsMessageIn>();
+    assert_send::<MlsMessageOut>();
+    assert_sync::<MlsMessageOut>();
+    assert_send::<Welcome>();
+    assert_sync::<Welcome>();
+    assert_send::<ProtocolMessage>();
+    assert_sync::<ProtocolMessage>();
+    assert_send::<ProcessedMessage>();
+    assert_sync::<ProcessedMessage>();
+    assert_send::<KeyPackage>();
+    assert_sync::<KeyPackage>();
+    assert_send::<KeyPackageBundle>();
+    assert_sync::<KeyPackageBundle>();
+}
+
+#[test]
+fn extension_observers_are_send() {
+    assert_send::<ExtensionObservers>();
+}
+
+// A future that holds a group and a staged commit across an await point must
+// be `Send`, so that it can be spawned on a multi-threaded executor.
+#[allow(dead_code)]
+fn hold_across_await(
+    mut group: MlsGroup,
+    staged_commit: StagedCommit,
+    observers: ExtensionObservers,
+) -> impl std::future::Future<Output = ()> + Send {
+    async move {
+        std::future::ready(()).await;
+        let _ = (&mut group, staged_commit, observers);
+    }
+}
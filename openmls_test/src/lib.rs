@@ -29,11 +29,13 @@ pub fn openmls_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
             #[test]
             fn #name() {
                 use openmls_rust_crypto::OpenMlsRustCrypto;
-                use openmls_traits::{types::Ciphersuite, crypto::OpenMlsCrypto, storage::StorageProvider as StorageProviderTrait};
+                use openmls_traits::{types::Ciphersuite, crypto::OpenMlsCrypto, storage::StorageReader as StorageReaderTrait};
+                #[allow(unused_imports)]
+                use openmls_traits::storage::StorageWriter as _;
 
                 type Provider = OpenMlsRustCrypto;
                 type StorageProvider = <Provider as openmls_traits::OpenMlsProvider>::StorageProvider;
-                type StorageError = <StorageProvider as StorageProviderTrait<{openmls_traits::storage::CURRENT_VERSION}>>::Error;
+                type StorageError = <StorageProvider as StorageReaderTrait<{openmls_traits::storage::CURRENT_VERSION}>>::Error;
 
                 let _ = pretty_env_logger::try_init();
 
@@ -72,7 +74,7 @@ pub fn openmls_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
                     type Provider = OpenMlsLibcrux;
                     type StorageProvider = <Provider as openmls_traits::OpenMlsProvider>::StorageProvider;
-                    type StorageError = <StorageProvider as openmls_traits::storage::StorageProvider<{openmls_traits::storage::CURRENT_VERSION}>>::Error;
+                    type StorageError = <StorageProvider as openmls_traits::storage::StorageReader<{openmls_traits::storage::CURRENT_VERSION}>>::Error;
 
                     let _ = pretty_env_logger::try_init();
 
@@ -2,7 +2,7 @@
 //! Applications that only want to use the `PublicGroup` only need to implement
 //! the `PublicStorageProvider` trait, and not the `StorageProvider` trait.
 
-use crate::storage::StorageProvider;
+use crate::storage::{StorageReader, StorageWriter};
 
 pub trait PublicStorageProvider<const VERSION: u16> {
     /// An opaque error returned by all methods on this trait.
@@ -157,9 +157,9 @@ pub trait PublicStorageProvider<const VERSION: u16> {
 
 impl<T, const VERSION: u16> PublicStorageProvider<VERSION> for T
 where
-    T: StorageProvider<VERSION>,
+    T: StorageReader<VERSION> + StorageWriter<VERSION>,
 {
-    type PublicError = <T as StorageProvider<VERSION>>::Error;
+    type PublicError = <T as StorageReader<VERSION>>::Error;
 
     fn write_tree<
         GroupId: crate::storage::traits::GroupId<VERSION>,
@@ -169,7 +169,7 @@ where
         group_id: &GroupId,
         tree: &TreeSync,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::write_tree(self, group_id, tree)
+        <Self as StorageWriter<VERSION>>::write_tree(self, group_id, tree)
     }
 
     fn write_interim_transcript_hash<
@@ -180,7 +180,7 @@ where
         group_id: &GroupId,
         interim_transcript_hash: &InterimTranscriptHash,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::write_interim_transcript_hash(
+        <Self as StorageWriter<VERSION>>::write_interim_transcript_hash(
             self,
             group_id,
             interim_transcript_hash,
@@ -195,7 +195,7 @@ where
         group_id: &GroupId,
         group_context: &GroupContext,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::write_context(self, group_id, group_context)
+        <Self as StorageWriter<VERSION>>::write_context(self, group_id, group_context)
     }
 
     fn write_confirmation_tag<
@@ -206,7 +206,7 @@ where
         group_id: &GroupId,
         confirmation_tag: &ConfirmationTag,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::write_confirmation_tag(self, group_id, confirmation_tag)
+        <Self as StorageWriter<VERSION>>::write_confirmation_tag(self, group_id, confirmation_tag)
     }
 
     fn queue_proposal<
@@ -219,7 +219,7 @@ where
         proposal_ref: &ProposalRef,
         proposal: &QueuedProposal,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::queue_proposal(self, group_id, proposal_ref, proposal)
+        <Self as StorageWriter<VERSION>>::queue_proposal(self, group_id, proposal_ref, proposal)
     }
 
     fn queued_proposals<
@@ -230,7 +230,7 @@ where
         &self,
         group_id: &GroupId,
     ) -> Result<Vec<(ProposalRef, QueuedProposal)>, Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::queued_proposals(self, group_id)
+        <Self as StorageReader<VERSION>>::queued_proposals(self, group_id)
     }
 
     fn tree<
@@ -240,7 +240,7 @@ where
         &self,
         group_id: &GroupId,
     ) -> Result<Option<TreeSync>, Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::tree(self, group_id)
+        <Self as StorageReader<VERSION>>::tree(self, group_id)
     }
 
     fn group_context<
@@ -250,7 +250,7 @@ where
         &self,
         group_id: &GroupId,
     ) -> Result<Option<GroupContext>, Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::group_context(self, group_id)
+        <Self as StorageReader<VERSION>>::group_context(self, group_id)
     }
 
     fn interim_transcript_hash<
@@ -260,7 +260,7 @@ where
         &self,
         group_id: &GroupId,
     ) -> Result<Option<InterimTranscriptHash>, Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::interim_transcript_hash(self, group_id)
+        <Self as StorageReader<VERSION>>::interim_transcript_hash(self, group_id)
     }
 
     fn confirmation_tag<
@@ -270,35 +270,35 @@ where
         &self,
         group_id: &GroupId,
     ) -> Result<Option<ConfirmationTag>, Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::confirmation_tag(self, group_id)
+        <Self as StorageReader<VERSION>>::confirmation_tag(self, group_id)
     }
 
     fn delete_tree<GroupId: crate::storage::traits::GroupId<VERSION>>(
         &self,
         group_id: &GroupId,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::delete_tree(self, group_id)
+        <Self as StorageWriter<VERSION>>::delete_tree(self, group_id)
     }
 
     fn delete_confirmation_tag<GroupId: crate::storage::traits::GroupId<VERSION>>(
         &self,
         group_id: &GroupId,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::delete_confirmation_tag(self, group_id)
+        <Self as StorageWriter<VERSION>>::delete_confirmation_tag(self, group_id)
     }
 
     fn delete_context<GroupId: crate::storage::traits::GroupId<VERSION>>(
         &self,
         group_id: &GroupId,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::delete_context(self, group_id)
+        <Self as StorageWriter<VERSION>>::delete_context(self, group_id)
     }
 
     fn delete_interim_transcript_hash<GroupId: crate::storage::traits::GroupId<VERSION>>(
         &self,
         group_id: &GroupId,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::delete_interim_transcript_hash(self, group_id)
+        <Self as StorageWriter<VERSION>>::delete_interim_transcript_hash(self, group_id)
     }
 
     fn remove_proposal<
@@ -309,7 +309,7 @@ where
         group_id: &GroupId,
         proposal_ref: &ProposalRef,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::remove_proposal(self, group_id, proposal_ref)
+        <Self as StorageWriter<VERSION>>::remove_proposal(self, group_id, proposal_ref)
     }
 
     fn clear_proposal_queue<
@@ -319,7 +319,7 @@ where
         &self,
         group_id: &GroupId,
     ) -> Result<(), Self::PublicError> {
-        <Self as StorageProvider<VERSION>>::clear_proposal_queue::<GroupId, ProposalRef>(
+        <Self as StorageWriter<VERSION>>::clear_proposal_queue::<GroupId, ProposalRef>(
             self, group_id,
         )
     }
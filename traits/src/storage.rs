@@ -1,6 +1,7 @@
 //! This module describes the storage provider and type traits.
 //! The concept is that the type traits are implemented by OpenMLS, and the storage provider
-//! implements the [`StorageProvider`] trait. The trait mostly defines getters and setters, but
+//! implements the [`StorageReader`] and [`StorageWriter`] traits, which together make up the
+//! [`StorageProvider`] trait. The traits mostly define getters and setters, but
 //! also a few methods that append to lists (which behave similar to setters).
 
 use serde::{de::DeserializeOwned, Serialize};
@@ -15,6 +16,11 @@ pub const V_TEST: u16 = u16::MAX;
 
 /// StorageProvider describes the storage backing OpenMLS and persists the state of OpenMLS groups.
 ///
+/// It combines a [`StorageReader`], which provides the getters, and a [`StorageWriter`], which
+/// provides the setters and deleters, and is implemented for every type that implements both.
+/// Operations that only read the state, like loading a group, accept a [`StorageReader`], so
+/// that they can be used with read-only handles, e.g. to a read replica of the storage.
+///
 /// The getters for individual values usually return a `Result<Option<T>, E>`, where `Err(_)`
 /// indicates that some sort of IO or internal error occurred, and `Ok(None)` indicates that no
 /// error occurred, but no value exists.
@@ -26,8 +32,20 @@ pub const V_TEST: u16 = u16::MAX;
 /// loading a group.
 ///
 /// More details can be taken from the comments on the respective method.
-pub trait StorageProvider<const VERSION: u16> {
-    /// An opaque error returned by all methods on this trait.
+pub trait StorageProvider<const VERSION: u16>:
+    StorageReader<VERSION> + StorageWriter<VERSION>
+{
+}
+
+impl<const VERSION: u16, T: StorageReader<VERSION> + StorageWriter<VERSION>>
+    StorageProvider<VERSION> for T
+{
+}
+
+/// The read half of the [`StorageProvider`]. It contains the getters and defines the error type
+/// shared with the [`StorageWriter`].
+pub trait StorageReader<const VERSION: u16> {
+    /// An opaque error returned by all methods on this trait and the [`StorageWriter`].
     type Error: core::fmt::Debug + std::error::Error;
 
     /// Get the version of this provider.
@@ -36,166 +54,204 @@ pub trait StorageProvider<const VERSION: u16> {
     }
 
     //
-    //    ---   setters/writers/enqueuers for group state  ---
+    //    ---   getters for group state  ---
     //
 
-    /// Writes the MlsGroupJoinConfig for the group with given id to storage
-    fn write_mls_join_config<
+    /// Returns the MlsGroupJoinConfig for the group with given id
+    fn mls_group_join_config<
         GroupId: traits::GroupId<VERSION>,
         MlsGroupJoinConfig: traits::MlsGroupJoinConfig<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        config: &MlsGroupJoinConfig,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error>;
 
-    /// Adds an own leaf node for the group with given id to storage
-    fn append_own_leaf_node<
+    // ANCHOR: own_leaf_nodes
+    /// Returns the own leaf nodes for the group with given id
+    fn own_leaf_nodes<GroupId: traits::GroupId<VERSION>, LeafNode: traits::LeafNode<VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Vec<LeafNode>, Self::Error>;
+    // ANCHOR_END: own_leaf_nodes
+
+    /// Returns references of all queued proposals for the group with group id `group_id`, or an empty vector of none are stored.
+    fn queued_proposal_refs<
         GroupId: traits::GroupId<VERSION>,
-        LeafNode: traits::LeafNode<VERSION>,
+        ProposalRef: traits::ProposalRef<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        leaf_node: &LeafNode,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Vec<ProposalRef>, Self::Error>;
 
-    /// Enqueue a proposal.
-    ///
-    /// A good way to implement this could be to add a proposal to a proposal store, indexed by the
-    /// proposal reference, and adding the reference to a per-group proposal queue list.
-    fn queue_proposal<
+    /// Returns all queued proposals for the group with group id `group_id`, or an empty vector of none are stored.
+    fn queued_proposals<
         GroupId: traits::GroupId<VERSION>,
         ProposalRef: traits::ProposalRef<VERSION>,
         QueuedProposal: traits::QueuedProposal<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        proposal_ref: &ProposalRef,
-        proposal: &QueuedProposal,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Vec<(ProposalRef, QueuedProposal)>, Self::Error>;
 
-    /// Write the TreeSync tree.
-    fn write_tree<GroupId: traits::GroupId<VERSION>, TreeSync: traits::TreeSync<VERSION>>(
+    /// Returns the TreeSync tree for the group with group id `group_id`.
+    fn tree<GroupId: traits::GroupId<VERSION>, TreeSync: traits::TreeSync<VERSION>>(
         &self,
         group_id: &GroupId,
-        tree: &TreeSync,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<TreeSync>, Self::Error>;
 
-    /// Write the interim transcript hash.
-    fn write_interim_transcript_hash<
+    /// Returns the group context for the group with group id `group_id`.
+    fn group_context<
         GroupId: traits::GroupId<VERSION>,
-        InterimTranscriptHash: traits::InterimTranscriptHash<VERSION>,
+        GroupContext: traits::GroupContext<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        interim_transcript_hash: &InterimTranscriptHash,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<GroupContext>, Self::Error>;
 
-    /// Write the group context.
-    fn write_context<
+    /// Returns the interim transcript hash for the group with group id `group_id`.
+    fn interim_transcript_hash<
         GroupId: traits::GroupId<VERSION>,
-        GroupContext: traits::GroupContext<VERSION>,
+        InterimTranscriptHash: traits::InterimTranscriptHash<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        group_context: &GroupContext,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<InterimTranscriptHash>, Self::Error>;
 
-    /// Write the confirmation tag.
-    fn write_confirmation_tag<
+    /// Returns the confirmation tag for the group with group id `group_id`.
+    fn confirmation_tag<
         GroupId: traits::GroupId<VERSION>,
         ConfirmationTag: traits::ConfirmationTag<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        confirmation_tag: &ConfirmationTag,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<ConfirmationTag>, Self::Error>;
 
-    /// Writes the MlsGroupState for group with given id.
-    fn write_group_state<
-        GroupState: traits::GroupState<VERSION>,
+    /// Returns the group state for the group with group id `group_id`.
+    fn group_state<GroupState: traits::GroupState<VERSION>, GroupId: traits::GroupId<VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<GroupState>, Self::Error>;
+
+    /// Returns the per-member statistics for the group with the given id.
+    ///
+    /// The default implementation returns `None`, i.e. no statistics are persisted.
+    fn member_stats<
+        GroupId: traits::GroupId<VERSION>,
+        MemberStats: traits::MemberStats<VERSION>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<MemberStats>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Returns the KEM outputs of the external commits that were staged in the
+    /// current epoch of the group with the given id.
+    fn used_external_inits<
         GroupId: traits::GroupId<VERSION>,
+        UsedExternalInits: traits::UsedExternalInits<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        group_state: &GroupState,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<UsedExternalInits>, Self::Error>;
 
-    /// Writes the MessageSecretsStore for the group with the given id.
-    fn write_message_secrets<
+    /// Returns the sequence number of the last replica delta that was
+    /// exported or applied for the group with the given id.
+    ///
+    /// The default implementation returns `None`, i.e. no sequence numbers
+    /// are persisted and replica deltas can't be exported.
+    fn replica_sequence<
+        GroupId: traits::GroupId<VERSION>,
+        ReplicaSequence: traits::ReplicaSequence<VERSION>,
+    >(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<ReplicaSequence>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Returns the state of the light client in the group with the given id.
+    ///
+    /// The default implementation returns `None`, i.e. light clients aren't
+    /// persisted.
+    fn light_group<GroupId: traits::GroupId<VERSION>, LightGroup: traits::LightGroup<VERSION>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<Option<LightGroup>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Returns the MessageSecretsStore for the group with the given id.
+    fn message_secrets<
         GroupId: traits::GroupId<VERSION>,
         MessageSecrets: traits::MessageSecrets<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        message_secrets: &MessageSecrets,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<MessageSecrets>, Self::Error>;
 
-    /// Writes the ResumptionPskStore for the group with the given id.
-    fn write_resumption_psk_store<
+    /// Returns the ResumptionPskStore for the group with the given id.
+    ///
+    /// Returning `None` here is considered an error because the store is needed
+    /// by OpenMLS when loading a group.
+    fn resumption_psk_store<
         GroupId: traits::GroupId<VERSION>,
         ResumptionPskStore: traits::ResumptionPskStore<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        resumption_psk_store: &ResumptionPskStore,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<ResumptionPskStore>, Self::Error>;
 
-    /// Writes the own leaf index inside the group for the group with the given id.
-    fn write_own_leaf_index<
+    /// Returns the own leaf index inside the group for the group with the given id.
+    fn own_leaf_index<
         GroupId: traits::GroupId<VERSION>,
         LeafNodeIndex: traits::LeafNodeIndex<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        own_leaf_index: &LeafNodeIndex,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<LeafNodeIndex>, Self::Error>;
 
-    /// Writes the GroupEpochSecrets for the group with the given id.
-    fn write_group_epoch_secrets<
+    /// Returns the GroupEpochSecrets for the group with the given id.
+    fn group_epoch_secrets<
         GroupId: traits::GroupId<VERSION>,
         GroupEpochSecrets: traits::GroupEpochSecrets<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-        group_epoch_secrets: &GroupEpochSecrets,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<GroupEpochSecrets>, Self::Error>;
 
     //
-    //    ---   setters/writers/enqueuers for crypto objects  ---
+    //    ---   getter for crypto objects  ---
     //
 
-    /// Store a signature key.
+    /// Get a signature key based on the public key.
     ///
     /// The signature key pair is not known to OpenMLS. This may be used by the
     /// application
-    fn write_signature_key_pair<
+    fn signature_key_pair<
         SignaturePublicKey: traits::SignaturePublicKey<VERSION>,
         SignatureKeyPair: traits::SignatureKeyPair<VERSION>,
     >(
         &self,
         public_key: &SignaturePublicKey,
-        signature_key_pair: &SignatureKeyPair,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<SignatureKeyPair>, Self::Error>;
 
-    /// Store an HPKE encryption key pair.
-    /// This includes the private and public key
+    /// Get an HPKE encryption key pair based on the public key.
     ///
     /// This is only be used for encryption key pairs that are generated for
     /// update leaf nodes. All other encryption key pairs are stored as part
     /// of the key package or the epoch encryption key pairs.
-    fn write_encryption_key_pair<
-        EncryptionKey: traits::EncryptionKey<VERSION>,
+    fn encryption_key_pair<
         HpkeKeyPair: traits::HpkeKeyPair<VERSION>,
+        EncryptionKey: traits::EncryptionKey<VERSION>,
     >(
         &self,
         public_key: &EncryptionKey,
-        key_pair: &HpkeKeyPair,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<HpkeKeyPair>, Self::Error>;
 
-    /// Store a list of HPKE encryption key pairs for a given epoch.
+    /// Get a list of HPKE encryption key pairs for a given epoch.
     /// This includes the private and public keys.
-    fn write_encryption_epoch_key_pairs<
+    fn encryption_epoch_key_pairs<
         GroupId: traits::GroupId<VERSION>,
         EpochKey: traits::EpochKey<VERSION>,
         HpkeKeyPair: traits::HpkeKeyPair<VERSION>,
@@ -204,190 +260,298 @@ pub trait StorageProvider<const VERSION: u16> {
         group_id: &GroupId,
         epoch: &EpochKey,
         leaf_index: u32,
-        key_pairs: &[HpkeKeyPair],
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Vec<HpkeKeyPair>, Self::Error>;
 
-    /// Store key packages.
-    ///
-    /// Store a key package. This includes the private init key.
-    /// The encryption key is stored separately with `write_encryption_key_pair`.
-    ///
-    /// Note that it is recommended to store a list of the hash references as well
-    /// in order to iterate over key packages. OpenMLS does not have a reference
-    /// for them.
-    // ANCHOR: write_key_package
-    fn write_key_package<
-        HashReference: traits::HashReference<VERSION>,
+    /// Get a key package based on its hash reference.
+    fn key_package<
+        KeyPackageRef: traits::HashReference<VERSION>,
         KeyPackage: traits::KeyPackage<VERSION>,
     >(
         &self,
-        hash_ref: &HashReference,
-        key_package: &KeyPackage,
-    ) -> Result<(), Self::Error>;
-    // ANCHOR_END: write_key_package
+        hash_ref: &KeyPackageRef,
+    ) -> Result<Option<KeyPackage>, Self::Error>;
 
-    /// Store a PSK.
+    /// Returns the id of the group that was joined with the key package with
+    /// the given hash reference.
     ///
-    /// This stores PSKs based on the PSK id.
-    ///
-    /// PSKs are only read by OpenMLS. The application is responsible for managing
-    /// and storing PSKs.
-    fn write_psk<PskId: traits::PskId<VERSION>, PskBundle: traits::PskBundle<VERSION>>(
+    /// The default implementation returns `None`. A redelivered Welcome is then
+    /// not detected.
+    fn joined_group_id<
+        KeyPackageRef: traits::HashReference<VERSION>,
+        GroupId: traits::GroupId<VERSION> + Entity<VERSION>,
+    >(
+        &self,
+        _hash_ref: &KeyPackageRef,
+    ) -> Result<Option<GroupId>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Get a PSK based on the PSK identifier.
+    fn psk<PskBundle: traits::PskBundle<VERSION>, PskId: traits::PskId<VERSION>>(
         &self,
         psk_id: &PskId,
-        psk: &PskBundle,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Option<PskBundle>, Self::Error>;
 
     //
-    //    ---   getters for group state  ---
+    //    ---   enumeration of stored entities  ---
     //
 
-    /// Returns the MlsGroupJoinConfig for the group with given id
-    fn mls_group_join_config<
+    // The enumeration is only used to find orphaned state. The default implementations return
+    // empty lists, so that nothing is found.
+
+    /// Returns the ids of all groups for which any group state is stored.
+    ///
+    /// This includes groups whose state is incomplete, e.g. because the process crashed while
+    /// the group was written or deleted. The order of the ids is unspecified.
+    ///
+    /// The default implementation returns an empty list.
+    fn group_ids<GroupId: traits::GroupId<VERSION> + Entity<VERSION>>(
+        &self,
+    ) -> Result<Vec<GroupId>, Self::Error> {
+        Ok(vec![])
+    }
+
+    /// Returns the hash references of all stored key packages. The order of the references is
+    /// unspecified.
+    ///
+    /// The default implementation returns an empty list.
+    fn key_package_refs<KeyPackageRef: traits::HashReference<VERSION> + Entity<VERSION>>(
+        &self,
+    ) -> Result<Vec<KeyPackageRef>, Self::Error> {
+        Ok(vec![])
+    }
+
+    /// Returns the public keys of all HPKE encryption key pairs that were written with
+    /// [`StorageWriter::write_encryption_key_pair()`]. The order of the keys is unspecified.
+    ///
+    /// The default implementation returns an empty list. Providers that implement this method
+    /// must implement [`StorageReader::group_ids()`] as well, since the key pairs of the groups
+    /// that aren't listed are considered orphaned.
+    fn encryption_keys<EncryptionKey: traits::EncryptionKey<VERSION> + Entity<VERSION>>(
+        &self,
+    ) -> Result<Vec<EncryptionKey>, Self::Error> {
+        Ok(vec![])
+    }
+}
+
+/// The write half of the [`StorageProvider`]. It contains the setters and deleters, i.e. all
+/// methods that mutate the stored state.
+pub trait StorageWriter<const VERSION: u16>: StorageReader<VERSION> {
+    //
+    //    ---   setters/writers/enqueuers for group state  ---
+    //
+
+    /// Writes the MlsGroupJoinConfig for the group with given id to storage
+    fn write_mls_join_config<
         GroupId: traits::GroupId<VERSION>,
         MlsGroupJoinConfig: traits::MlsGroupJoinConfig<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error>;
-
-    // ANCHOR: own_leaf_nodes
-    /// Returns the own leaf nodes for the group with given id
-    fn own_leaf_nodes<GroupId: traits::GroupId<VERSION>, LeafNode: traits::LeafNode<VERSION>>(
-        &self,
-        group_id: &GroupId,
-    ) -> Result<Vec<LeafNode>, Self::Error>;
-    // ANCHOR_END: own_leaf_nodes
+        config: &MlsGroupJoinConfig,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns references of all queued proposals for the group with group id `group_id`, or an empty vector of none are stored.
-    fn queued_proposal_refs<
+    /// Adds an own leaf node for the group with given id to storage
+    fn append_own_leaf_node<
         GroupId: traits::GroupId<VERSION>,
-        ProposalRef: traits::ProposalRef<VERSION>,
+        LeafNode: traits::LeafNode<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Vec<ProposalRef>, Self::Error>;
+        leaf_node: &LeafNode,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns all queued proposals for the group with group id `group_id`, or an empty vector of none are stored.
-    fn queued_proposals<
+    /// Enqueue a proposal.
+    ///
+    /// A good way to implement this could be to add a proposal to a proposal store, indexed by the
+    /// proposal reference, and adding the reference to a per-group proposal queue list.
+    fn queue_proposal<
         GroupId: traits::GroupId<VERSION>,
         ProposalRef: traits::ProposalRef<VERSION>,
         QueuedProposal: traits::QueuedProposal<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Vec<(ProposalRef, QueuedProposal)>, Self::Error>;
+        proposal_ref: &ProposalRef,
+        proposal: &QueuedProposal,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the TreeSync tree for the group with group id `group_id`.
-    fn tree<GroupId: traits::GroupId<VERSION>, TreeSync: traits::TreeSync<VERSION>>(
+    /// Write the TreeSync tree.
+    fn write_tree<GroupId: traits::GroupId<VERSION>, TreeSync: traits::TreeSync<VERSION>>(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<TreeSync>, Self::Error>;
+        tree: &TreeSync,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the group context for the group with group id `group_id`.
-    fn group_context<
+    /// Write the interim transcript hash.
+    fn write_interim_transcript_hash<
         GroupId: traits::GroupId<VERSION>,
-        GroupContext: traits::GroupContext<VERSION>,
+        InterimTranscriptHash: traits::InterimTranscriptHash<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<GroupContext>, Self::Error>;
+        interim_transcript_hash: &InterimTranscriptHash,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the interim transcript hash for the group with group id `group_id`.
-    fn interim_transcript_hash<
+    /// Write the group context.
+    fn write_context<
         GroupId: traits::GroupId<VERSION>,
-        InterimTranscriptHash: traits::InterimTranscriptHash<VERSION>,
+        GroupContext: traits::GroupContext<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<InterimTranscriptHash>, Self::Error>;
+        group_context: &GroupContext,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the confirmation tag for the group with group id `group_id`.
-    fn confirmation_tag<
+    /// Write the confirmation tag.
+    fn write_confirmation_tag<
         GroupId: traits::GroupId<VERSION>,
         ConfirmationTag: traits::ConfirmationTag<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<ConfirmationTag>, Self::Error>;
+        confirmation_tag: &ConfirmationTag,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the group state for the group with group id `group_id`.
-    fn group_state<GroupState: traits::GroupState<VERSION>, GroupId: traits::GroupId<VERSION>>(
+    /// Writes the MlsGroupState for group with given id.
+    fn write_group_state<
+        GroupState: traits::GroupState<VERSION>,
+        GroupId: traits::GroupId<VERSION>,
+    >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<GroupState>, Self::Error>;
+        group_state: &GroupState,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the MessageSecretsStore for the group with the given id.
-    fn message_secrets<
+    /// Writes the per-member statistics for the group with the given id.
+    ///
+    /// The default implementation doesn't persist the statistics.
+    fn write_member_stats<
         GroupId: traits::GroupId<VERSION>,
-        MessageSecrets: traits::MessageSecrets<VERSION>,
+        MemberStats: traits::MemberStats<VERSION>,
+    >(
+        &self,
+        _group_id: &GroupId,
+        _member_stats: &MemberStats,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Writes the KEM outputs of the external commits that were staged in the
+    /// current epoch of the group with the given id.
+    fn write_used_external_inits<
+        GroupId: traits::GroupId<VERSION>,
+        UsedExternalInits: traits::UsedExternalInits<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<MessageSecrets>, Self::Error>;
+        used_external_inits: &UsedExternalInits,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the ResumptionPskStore for the group with the given id.
+    /// Writes the sequence number of the last replica delta that was exported
+    /// or applied for the group with the given id.
     ///
-    /// Returning `None` here is considered an error because the store is needed
-    /// by OpenMLS when loading a group.
-    fn resumption_psk_store<
+    /// The default implementation doesn't persist the sequence number.
+    fn write_replica_sequence<
+        GroupId: traits::GroupId<VERSION>,
+        ReplicaSequence: traits::ReplicaSequence<VERSION>,
+    >(
+        &self,
+        _group_id: &GroupId,
+        _replica_sequence: &ReplicaSequence,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Writes the state of the light client in the group with the given id.
+    ///
+    /// The default implementation doesn't persist the state.
+    fn write_light_group<
+        GroupId: traits::GroupId<VERSION>,
+        LightGroup: traits::LightGroup<VERSION>,
+    >(
+        &self,
+        _group_id: &GroupId,
+        _light_group: &LightGroup,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Writes the MessageSecretsStore for the group with the given id.
+    fn write_message_secrets<
+        GroupId: traits::GroupId<VERSION>,
+        MessageSecrets: traits::MessageSecrets<VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        message_secrets: &MessageSecrets,
+    ) -> Result<(), Self::Error>;
+
+    /// Writes the ResumptionPskStore for the group with the given id.
+    fn write_resumption_psk_store<
         GroupId: traits::GroupId<VERSION>,
         ResumptionPskStore: traits::ResumptionPskStore<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<ResumptionPskStore>, Self::Error>;
+        resumption_psk_store: &ResumptionPskStore,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the own leaf index inside the group for the group with the given id.
-    fn own_leaf_index<
+    /// Writes the own leaf index inside the group for the group with the given id.
+    fn write_own_leaf_index<
         GroupId: traits::GroupId<VERSION>,
         LeafNodeIndex: traits::LeafNodeIndex<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<LeafNodeIndex>, Self::Error>;
+        own_leaf_index: &LeafNodeIndex,
+    ) -> Result<(), Self::Error>;
 
-    /// Returns the GroupEpochSecrets for the group with the given id.
-    fn group_epoch_secrets<
+    /// Writes the GroupEpochSecrets for the group with the given id.
+    fn write_group_epoch_secrets<
         GroupId: traits::GroupId<VERSION>,
         GroupEpochSecrets: traits::GroupEpochSecrets<VERSION>,
     >(
         &self,
         group_id: &GroupId,
-    ) -> Result<Option<GroupEpochSecrets>, Self::Error>;
+        group_epoch_secrets: &GroupEpochSecrets,
+    ) -> Result<(), Self::Error>;
 
     //
-    //    ---   getter for crypto objects  ---
+    //    ---   setters/writers/enqueuers for crypto objects  ---
     //
 
-    /// Get a signature key based on the public key.
+    /// Store a signature key.
     ///
     /// The signature key pair is not known to OpenMLS. This may be used by the
     /// application
-    fn signature_key_pair<
+    fn write_signature_key_pair<
         SignaturePublicKey: traits::SignaturePublicKey<VERSION>,
         SignatureKeyPair: traits::SignatureKeyPair<VERSION>,
     >(
         &self,
         public_key: &SignaturePublicKey,
-    ) -> Result<Option<SignatureKeyPair>, Self::Error>;
+        signature_key_pair: &SignatureKeyPair,
+    ) -> Result<(), Self::Error>;
 
-    /// Get an HPKE encryption key pair based on the public key.
+    /// Store an HPKE encryption key pair.
+    /// This includes the private and public key
     ///
     /// This is only be used for encryption key pairs that are generated for
     /// update leaf nodes. All other encryption key pairs are stored as part
     /// of the key package or the epoch encryption key pairs.
-    fn encryption_key_pair<
-        HpkeKeyPair: traits::HpkeKeyPair<VERSION>,
+    fn write_encryption_key_pair<
         EncryptionKey: traits::EncryptionKey<VERSION>,
+        HpkeKeyPair: traits::HpkeKeyPair<VERSION>,
     >(
         &self,
         public_key: &EncryptionKey,
-    ) -> Result<Option<HpkeKeyPair>, Self::Error>;
+        key_pair: &HpkeKeyPair,
+    ) -> Result<(), Self::Error>;
 
-    /// Get a list of HPKE encryption key pairs for a given epoch.
+    /// Store a list of HPKE encryption key pairs for a given epoch.
     /// This includes the private and public keys.
-    fn encryption_epoch_key_pairs<
+    fn write_encryption_epoch_key_pairs<
         GroupId: traits::GroupId<VERSION>,
         EpochKey: traits::EpochKey<VERSION>,
         HpkeKeyPair: traits::HpkeKeyPair<VERSION>,
@@ -396,22 +560,54 @@ pub trait StorageProvider<const VERSION: u16> {
         group_id: &GroupId,
         epoch: &EpochKey,
         leaf_index: u32,
-    ) -> Result<Vec<HpkeKeyPair>, Self::Error>;
+        key_pairs: &[HpkeKeyPair],
+    ) -> Result<(), Self::Error>;
 
-    /// Get a key package based on its hash reference.
-    fn key_package<
-        KeyPackageRef: traits::HashReference<VERSION>,
+    /// Store key packages.
+    ///
+    /// Store a key package. This includes the private init key.
+    /// The encryption key is stored separately with `write_encryption_key_pair`.
+    ///
+    /// Note that it is recommended to store a list of the hash references as well
+    /// in order to iterate over key packages. OpenMLS does not have a reference
+    /// for them.
+    // ANCHOR: write_key_package
+    fn write_key_package<
+        HashReference: traits::HashReference<VERSION>,
         KeyPackage: traits::KeyPackage<VERSION>,
     >(
         &self,
-        hash_ref: &KeyPackageRef,
-    ) -> Result<Option<KeyPackage>, Self::Error>;
+        hash_ref: &HashReference,
+        key_package: &KeyPackage,
+    ) -> Result<(), Self::Error>;
+    // ANCHOR_END: write_key_package
 
-    /// Get a PSK based on the PSK identifier.
-    fn psk<PskBundle: traits::PskBundle<VERSION>, PskId: traits::PskId<VERSION>>(
+    /// Writes the id of the group that was joined with the key package with
+    /// the given hash reference.
+    ///
+    /// The default implementation doesn't persist the group id.
+    fn write_joined_group_id<
+        KeyPackageRef: traits::HashReference<VERSION>,
+        GroupId: traits::GroupId<VERSION> + Entity<VERSION>,
+    >(
+        &self,
+        _hash_ref: &KeyPackageRef,
+        _group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Store a PSK.
+    ///
+    /// This stores PSKs based on the PSK id.
+    ///
+    /// PSKs are only read by OpenMLS. The application is responsible for managing
+    /// and storing PSKs.
+    fn write_psk<PskId: traits::PskId<VERSION>, PskBundle: traits::PskBundle<VERSION>>(
         &self,
         psk_id: &PskId,
-    ) -> Result<Option<PskBundle>, Self::Error>;
+        psk: &PskBundle,
+    ) -> Result<(), Self::Error>;
 
     //
     //     ---    deleters for group state    ---
@@ -457,6 +653,46 @@ pub trait StorageProvider<const VERSION: u16> {
         group_id: &GroupId,
     ) -> Result<(), Self::Error>;
 
+    /// Deletes the per-member statistics for the group with given id.
+    ///
+    /// The default implementation does nothing, matching
+    /// [`StorageWriter::write_member_stats()`].
+    fn delete_member_stats<GroupId: traits::GroupId<VERSION>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Deletes the KEM outputs of the external commits that were staged for
+    /// the group with given id.
+    fn delete_used_external_inits<GroupId: traits::GroupId<VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error>;
+
+    /// Deletes the replica sequence number for the group with given id.
+    ///
+    /// The default implementation does nothing, matching
+    /// [`StorageWriter::write_replica_sequence()`].
+    fn delete_replica_sequence<GroupId: traits::GroupId<VERSION>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Deletes the state of the light client in the group with given id.
+    ///
+    /// The default implementation does nothing, matching
+    /// [`StorageWriter::write_light_group()`].
+    fn delete_light_group<GroupId: traits::GroupId<VERSION>>(
+        &self,
+        _group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     /// Deletes the group context for the group with given id
     fn delete_context<GroupId: traits::GroupId<VERSION>>(
         &self,
@@ -546,6 +782,18 @@ pub trait StorageProvider<const VERSION: u16> {
         hash_ref: &KeyPackageRef,
     ) -> Result<(), Self::Error>;
 
+    /// Deletes the id of the group that was joined with the key package with
+    /// the given hash reference.
+    ///
+    /// The default implementation does nothing, matching
+    /// [`StorageWriter::write_joined_group_id()`].
+    fn delete_joined_group_id<KeyPackageRef: traits::HashReference<VERSION>>(
+        &self,
+        _hash_ref: &KeyPackageRef,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     /// Delete a PSK based on an identifier.
     fn delete_psk<PskKey: traits::PskId<VERSION>>(
         &self,
@@ -605,6 +853,10 @@ pub mod traits {
     pub trait KeyPackage<const VERSION: u16>: Entity<VERSION> {}
     pub trait MlsGroupJoinConfig<const VERSION: u16>: Entity<VERSION> {}
     pub trait LeafNode<const VERSION: u16>: Entity<VERSION> {}
+    pub trait MemberStats<const VERSION: u16>: Entity<VERSION> {}
+    pub trait ReplicaSequence<const VERSION: u16>: Entity<VERSION> {}
+    pub trait LightGroup<const VERSION: u16>: Entity<VERSION> {}
+    pub trait UsedExternalInits<const VERSION: u16>: Entity<VERSION> {}
 
     // traits for types that implement both
     pub trait ProposalRef<const VERSION: u16>: Entity<VERSION> + Key<VERSION> {}
@@ -16,6 +16,8 @@ pub mod prelude {
     pub use super::random::OpenMlsRand as _;
     pub use super::signatures::Signer as _;
     pub use super::storage::StorageProvider as _;
+    pub use super::storage::StorageReader as _;
+    pub use super::storage::StorageWriter as _;
     pub use super::types as openmls_types;
     pub use super::OpenMlsProvider as _;
 }
@@ -241,8 +241,10 @@ pub struct HpkeCiphertext {
 }
 
 /// A simple type for HPKE private keys.
+///
+/// Note: This has a hand-written `Debug` implementation that only prints the
+///       length of the key.
 #[derive(
-    Debug,
     Clone,
     serde::Serialize,
     serde::Deserialize,
@@ -255,6 +257,14 @@ pub struct HpkeCiphertext {
 #[serde(transparent)]
 pub struct HpkePrivateKey(SecretVLBytes);
 
+impl std::fmt::Debug for HpkePrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HpkePrivateKey")
+            .field("len", &self.0.as_slice().len())
+            .finish()
+    }
+}
+
 impl From<Vec<u8>> for HpkePrivateKey {
     fn from(bytes: Vec<u8>) -> Self {
         Self(bytes.into())
@@ -283,9 +293,20 @@ pub struct HpkeKeyPair {
 }
 
 pub type KemOutput = Vec<u8>;
-#[derive(Clone, Debug)]
+
+/// Note: This has a hand-written `Debug` implementation that only prints the
+///       length of the secret.
+#[derive(Clone)]
 pub struct ExporterSecret(SecretVLBytes);
 
+impl std::fmt::Debug for ExporterSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExporterSecret")
+            .field("len", &self.0.as_slice().len())
+            .finish()
+    }
+}
+
 impl Deref for ExporterSecret {
     type Target = [u8];
 